@@ -82,3 +82,45 @@ fn unit_native_function_env() -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(feature = "sys")]
+#[test]
+fn current_vmctx_is_only_set_for_instances() -> Result<(), String> {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use wasmer::sys::vm::{current_vmctx, track_current_vmctx};
+
+    track_current_vmctx();
+    let mut store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "probe" (func $probe))
+            (func (export "run") call $probe))"#,
+    )
+    .map_err(|e| format!("{e:?}"))?;
+
+    let seen = Arc::new(AtomicBool::new(false));
+    let probe = Function::new_typed(&mut store, {
+        let seen = seen.clone();
+        move || seen.store(current_vmctx().is_some(), Ordering::SeqCst)
+    });
+    let imports = imports! { "env" => { "probe" => probe.clone() } };
+    let instance = Instance::new(&mut store, &module, &imports).map_err(|e| format!("{e:?}"))?;
+
+    let run = instance
+        .exports
+        .get_function("run")
+        .map_err(|e| format!("{e:?}"))?;
+    run.call(&mut store, &[]).map_err(|e| format!("{e:?}"))?;
+    assert!(seen.load(Ordering::SeqCst));
+
+    // A host function that is called from the host runs no instance
+    probe.call(&mut store, &[]).map_err(|e| format!("{e:?}"))?;
+    assert!(!seen.load(Ordering::SeqCst));
+    assert!(current_vmctx().is_none());
+
+    Ok(())
+}
@@ -188,12 +188,35 @@ pub struct RuntimeOptions {
     #[clap(long)]
     enable_verifier: bool,
 
+    /// Register the compiled code with GDB and LLDB through the JIT
+    /// interface, translating the DWARF of the module when it has any.
+    ///
+    /// Only the functions and the source lines are translated, so the
+    /// debuggers can name the frames and break on lines but can't print
+    /// the variables of the program.
+    ///
+    /// Available for cranelift, LLVM and singlepass, when wasmer is built
+    /// with the `gdb-jit` feature.
+    #[clap(long)]
+    debug_info: bool,
+
+    /// Have the compilers record where the locals of each function live,
+    /// for a debugger or a coredump that reads them from a stopped thread.
+    #[clap(skip)]
+    record_locals: bool,
+
     /// Enable a profiler.
     ///
     /// Available for cranelift, LLVM and singlepass.
     #[clap(long, value_enum)]
     profiler: Option<Profiler>,
 
+    /// Optimization level of the generated code.
+    ///
+    /// Available for cranelift and LLVM.
+    #[clap(long, value_enum)]
+    opt_level: Option<OptLevel>,
+
     /// LLVM debug directory, where IR and object files will be written to.
     ///
     /// Only available for the LLVM compiler.
@@ -220,6 +243,17 @@ pub enum Profiler {
     Perfmap,
 }
 
+/// The optimization levels of the compilers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OptLevel {
+    /// Minimizes the compilation time
+    None,
+    /// Generates the fastest code
+    Speed,
+    /// Generates fast and small code
+    SpeedAndSize,
+}
+
 impl FromStr for Profiler {
     type Err = anyhow::Error;
 
@@ -232,6 +266,23 @@ impl FromStr for Profiler {
 }
 
 impl RuntimeOptions {
+    /// Returns these options with another optimization level
+    pub fn with_opt_level(&self, opt_level: Option<OptLevel>) -> Self {
+        Self {
+            opt_level,
+            ..self.clone()
+        }
+    }
+
+    /// Returns these options with the compilers recording where the locals
+    /// of each function live
+    pub fn with_recorded_locals(&self) -> Self {
+        Self {
+            record_locals: true,
+            ..self.clone()
+        }
+    }
+
     pub fn get_available_backends(&self) -> Result<Vec<BackendType>> {
         // If a specific backend is explicitly requested, use it
         #[cfg(feature = "cranelift")]
@@ -437,12 +488,23 @@ impl RuntimeOptions {
             .into())
     }
 
+    /// `--debug-info` only does something when the compiled code can be
+    /// registered with a debugger
+    fn check_debug_info(&self) -> Result<()> {
+        #[cfg(not(feature = "gdb-jit"))]
+        if self.debug_info {
+            bail!("wasmer was built without gdb-jit support, --debug-info is not available");
+        }
+        Ok(())
+    }
+
     #[allow(unused_variables)]
     #[cfg(feature = "compiler")]
     pub(crate) fn get_sys_compiler_config(
         &self,
         rt: &BackendType,
     ) -> Result<Box<dyn CompilerConfig>> {
+        self.check_debug_info()?;
         let compiler_config: Box<dyn CompilerConfig> = match rt {
             BackendType::Headless => bail!("The headless engine can't be chosen"),
             #[cfg(feature = "singlepass")]
@@ -451,6 +513,9 @@ impl RuntimeOptions {
                 if self.enable_verifier {
                     config.enable_verifier();
                 }
+                if self.debug_info || self.record_locals {
+                    config.enable_debug_info();
+                }
                 if let Some(p) = &self.profiler {
                     match p {
                         Profiler::Perfmap => config.enable_perfmap(),
@@ -465,6 +530,9 @@ impl RuntimeOptions {
                 if self.enable_verifier {
                     config.enable_verifier();
                 }
+                if self.debug_info || self.record_locals {
+                    config.enable_debug_info();
+                }
                 if let Some(p) = &self.profiler {
                     match p {
                         Profiler::Perfmap => config.enable_perfmap(),
@@ -597,6 +665,9 @@ impl RuntimeOptions {
                 if self.enable_verifier {
                     config.enable_verifier();
                 }
+                if self.debug_info || self.record_locals {
+                    config.enable_debug_info();
+                }
                 if let Some(p) = &self.profiler {
                     match p {
                         Profiler::Perfmap => config.enable_perfmap(),
@@ -673,6 +744,7 @@ impl BackendType {
         features: &Features,
         runtime_opts: &RuntimeOptions,
     ) -> Result<Engine> {
+        runtime_opts.check_debug_info()?;
         match self {
             #[cfg(feature = "singlepass")]
             Self::Singlepass => {
@@ -680,6 +752,9 @@ impl BackendType {
                 if runtime_opts.enable_verifier {
                     config.enable_verifier();
                 }
+                if runtime_opts.debug_info || runtime_opts.record_locals {
+                    config.enable_debug_info();
+                }
                 if let Some(p) = &runtime_opts.profiler {
                     match p {
                         Profiler::Perfmap => config.enable_perfmap(),
@@ -695,9 +770,20 @@ impl BackendType {
             #[cfg(feature = "cranelift")]
             Self::Cranelift => {
                 let mut config = wasmer_compiler_cranelift::Cranelift::new();
+                if let Some(opt_level) = runtime_opts.opt_level {
+                    use wasmer_compiler_cranelift::CraneliftOptLevel;
+                    config.opt_level(match opt_level {
+                        OptLevel::None => CraneliftOptLevel::None,
+                        OptLevel::Speed => CraneliftOptLevel::Speed,
+                        OptLevel::SpeedAndSize => CraneliftOptLevel::SpeedAndSize,
+                    });
+                }
                 if runtime_opts.enable_verifier {
                     config.enable_verifier();
                 }
+                if runtime_opts.debug_info || runtime_opts.record_locals {
+                    config.enable_debug_info();
+                }
                 if let Some(p) = &runtime_opts.profiler {
                     match p {
                         Profiler::Perfmap => config.enable_perfmap(),
@@ -822,12 +908,23 @@ impl BackendType {
                     }
                 }
 
+                if let Some(opt_level) = runtime_opts.opt_level {
+                    use wasmer_compiler_llvm::LLVMOptLevel;
+                    config.opt_level(match opt_level {
+                        OptLevel::None => LLVMOptLevel::None,
+                        OptLevel::Speed => LLVMOptLevel::Aggressive,
+                        OptLevel::SpeedAndSize => LLVMOptLevel::Default,
+                    });
+                }
                 if let Some(ref llvm_debug_dir) = runtime_opts.llvm_debug_dir {
                     config.callbacks(Some(Arc::new(Callbacks::new(llvm_debug_dir.clone())?)));
                 }
                 if runtime_opts.enable_verifier {
                     config.enable_verifier();
                 }
+                if runtime_opts.debug_info || runtime_opts.record_locals {
+                    config.enable_debug_info();
+                }
 
                 if runtime_opts.enable_pass_params_opt {
                     config.enable_pass_params_opt();
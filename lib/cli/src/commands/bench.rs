@@ -0,0 +1,404 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::backend::{BackendType, OptLevel, RuntimeOptions};
+use anyhow::{bail, Context, Result};
+use bytesize::ByteSize;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use wasmer::*;
+use wasmer_types::target::Target;
+use wasmer_wasix::{virtual_fs::NullFile, WasiEnv, WasiError, WasiFunctionEnv};
+
+#[derive(Debug, Parser)]
+/// The options for the `wasmer bench` subcommand
+pub struct Bench {
+    /// The WebAssembly module to benchmark
+    #[clap(name = "FILE")]
+    path: PathBuf,
+
+    /// Function to call after each instantiation, WASI modules run
+    /// `_start` by default
+    #[clap(long, short = 'i')]
+    invoke: Option<String>,
+
+    /// Number of times that each step is measured
+    #[clap(long, short = 'n', default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    iterations: u64,
+
+    /// Optimization levels to compare [default: all of them]
+    ///
+    /// Only cranelift and LLVM have optimization levels.
+    #[clap(long, value_enum, value_delimiter = ',')]
+    opt_levels: Vec<OptLevel>,
+
+    /// Only measure the compilation and the instantiation
+    #[clap(long)]
+    no_run: bool,
+
+    /// Print the results as JSON
+    #[clap(long)]
+    json: bool,
+
+    #[clap(flatten)]
+    rt: RuntimeOptions,
+
+    /// Arguments of the invoked function, or of the WASI program. The
+    /// output of the program is discarded.
+    args: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    module: String,
+    size: usize,
+    iterations: u64,
+    /// The function that was run, if any
+    function: Option<String>,
+    results: Vec<BenchResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct BenchResult {
+    backend: String,
+    /// The deterministic id of the engine
+    engine: String,
+    opt_level: Option<String>,
+    compile: Stats,
+    artifact_size: Option<usize>,
+    instantiate: Stats,
+    run: Option<Stats>,
+}
+
+/// Statistics of the measurements of a step, in milliseconds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Stats {
+    mean: f64,
+    median: f64,
+    min: f64,
+    max: f64,
+    stddev: f64,
+}
+
+impl Stats {
+    fn new(samples: &[Duration]) -> Self {
+        let mut samples: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1e3).collect();
+        samples.sort_by(f64::total_cmp);
+        let count = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / count;
+        let middle = samples.len() / 2;
+        let median = if samples.len() % 2 == 0 {
+            (samples[middle - 1] + samples[middle]) / 2.0
+        } else {
+            samples[middle]
+        };
+        // The sample standard deviation, zero for a single sample
+        let variance =
+            samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (count - 1.0).max(1.0);
+        Self {
+            mean,
+            median,
+            min: samples[0],
+            max: samples[samples.len() - 1],
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+/// An instance of the module, with the WASI environment that it was
+/// instantiated with
+enum Instantiated {
+    Wasi(Instance, WasiFunctionEnv),
+    Plain(Instance),
+}
+
+impl Bench {
+    /// Runs logic for the `bench` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context(format!("failed to benchmark `{}`", self.path.display()))
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        // WASI needs an async runtime for its tasks
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let _guard = runtime.enter();
+
+        let wasm = std::fs::read(&self.path)?;
+        let wasm = wat2wasm(&wasm)?.into_owned();
+        let target = Target::default();
+        let features = self.rt.detect_features_from_wasm(&wasm).unwrap_or_default();
+        let backends = RuntimeOptions::filter_backends_by_features(
+            self.rt.get_available_backends()?,
+            &features,
+            &target,
+        );
+        if backends.is_empty() {
+            bail!("No backends support the required features for the Wasm module");
+        }
+
+        let mut function = None;
+        let mut results = Vec::new();
+        for backend in backends {
+            let opt_levels = match backend {
+                BackendType::Cranelift | BackendType::LLVM if self.opt_levels.is_empty() => {
+                    OptLevel::value_variants()
+                        .iter()
+                        .copied()
+                        .map(Some)
+                        .collect()
+                }
+                BackendType::Cranelift | BackendType::LLVM => {
+                    self.opt_levels.iter().copied().map(Some).collect()
+                }
+                _ => vec![None],
+            };
+            for opt_level in opt_levels {
+                let opt_level_name = opt_level
+                    .map(|level| level.to_possible_value().unwrap().get_name().to_string());
+                if !self.json {
+                    eprintln!(
+                        "Benchmarking {backend}{}...",
+                        opt_level_name
+                            .as_ref()
+                            .map(|name| format!(" ({name})"))
+                            .unwrap_or_default()
+                    );
+                }
+                let engine =
+                    backend.get_engine(&target, &features, &self.rt.with_opt_level(opt_level))?;
+                let (result, ran) = self
+                    .bench_engine(&engine, &wasm)
+                    .with_context(|| format!("failed to benchmark the {backend} backend"))?;
+                function = ran;
+                results.push(BenchResult {
+                    backend: backend.to_string(),
+                    engine: engine.deterministic_id().to_string(),
+                    opt_level: opt_level_name,
+                    ..result
+                });
+            }
+        }
+
+        let report = Report {
+            module: self.path.display().to_string(),
+            size: wasm.len(),
+            iterations: self.iterations,
+            function,
+            results,
+        };
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        Ok(())
+    }
+
+    /// Measures each step with an engine, and returns the function that
+    /// was run
+    fn bench_engine(&self, engine: &Engine, wasm: &[u8]) -> Result<(BenchResult, Option<String>)> {
+        let mut compile_times = Vec::new();
+        let mut module = None;
+        for _ in 0..self.iterations {
+            let start = Instant::now();
+            let compiled = Module::new(engine, wasm)?;
+            compile_times.push(start.elapsed());
+            module = Some(compiled);
+        }
+        let module = module.expect("there is at least one iteration");
+        // Not every backend produces an artifact
+        let artifact_size = module.serialize().ok().map(|artifact| artifact.len());
+
+        let is_wasi = wasmer_wasix::is_wasi_module(&module);
+        let function = match &self.invoke {
+            _ if self.no_run => None,
+            Some(function) => Some(function.clone()),
+            None if module.exports().functions().any(|f| f.name() == "_start") => {
+                Some("_start".to_string())
+            }
+            None => None,
+        };
+
+        let mut instantiate_times = Vec::new();
+        let mut run_times = Vec::new();
+        for _ in 0..self.iterations {
+            let mut store = Store::new(engine.clone());
+            let start = Instant::now();
+            let instantiated = self.instantiate(&mut store, &module, is_wasi)?;
+            instantiate_times.push(start.elapsed());
+
+            if let Some(function) = &function {
+                let start = Instant::now();
+                self.run(&mut store, &instantiated, function)?;
+                run_times.push(start.elapsed());
+            }
+            if let Instantiated::Wasi(_, env) = &instantiated {
+                env.on_exit(&mut store, None);
+            }
+        }
+
+        let result = BenchResult {
+            backend: String::new(),
+            engine: String::new(),
+            opt_level: None,
+            compile: Stats::new(&compile_times),
+            artifact_size,
+            instantiate: Stats::new(&instantiate_times),
+            run: function.as_ref().map(|_| Stats::new(&run_times)),
+        };
+        Ok((result, function))
+    }
+
+    fn instantiate(
+        &self,
+        store: &mut Store,
+        module: &Module,
+        is_wasi: bool,
+    ) -> Result<Instantiated> {
+        if !is_wasi {
+            return Ok(Instantiated::Plain(Instance::new(
+                store,
+                module,
+                &imports! {},
+            )?));
+        }
+
+        let program = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut builder = WasiEnv::builder(program)
+            .stdout(Box::<NullFile>::default())
+            .stderr(Box::<NullFile>::default());
+        if self.invoke.is_none() {
+            builder = builder.args(&self.args);
+        }
+        let (instance, env) = builder.instantiate(module.clone(), store)?;
+        Ok(Instantiated::Wasi(instance, env))
+    }
+
+    fn run(&self, store: &mut Store, instantiated: &Instantiated, function: &str) -> Result<()> {
+        let (instance, env) = match instantiated {
+            Instantiated::Wasi(instance, env) => (instance, Some(env)),
+            Instantiated::Plain(instance) => (instance, None),
+        };
+        let func = instance.exports.get_function(function)?;
+        let args = match &self.invoke {
+            Some(_) => {
+                let params = func.ty(store).params().to_vec();
+                if params.len() != self.args.len() {
+                    bail!(
+                        "Function expected {} arguments, but received {}",
+                        params.len(),
+                        self.args.len()
+                    );
+                }
+                self.args
+                    .iter()
+                    .zip(params)
+                    .map(|(arg, ty)| {
+                        super::run::parse_value(arg, ty)
+                            .with_context(|| format!("Unable to convert {arg:?} to {ty:?}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            }
+            None => Vec::new(),
+        };
+
+        if let Some(env) = env {
+            env.data(store).thread.set_status_running();
+        }
+        match func.call(store, &args) {
+            Ok(_) => Ok(()),
+            Err(error) => match error.downcast_ref::<WasiError>() {
+                Some(WasiError::Exit(code)) if code.is_success() => Ok(()),
+                Some(WasiError::Exit(code)) => bail!("`{function}` exited with code {code}"),
+                _ => Err(error).with_context(|| format!("`{function}` failed")),
+            },
+        }
+    }
+}
+
+fn format_ms(ms: f64) -> String {
+    if ms >= 1000.0 {
+        format!("{:.2} s", ms / 1000.0)
+    } else if ms >= 1.0 {
+        format!("{ms:.2} ms")
+    } else {
+        format!("{:.1} µs", ms * 1000.0)
+    }
+}
+
+fn format_stats(stats: &Stats) -> String {
+    format!("{} ± {}", format_ms(stats.mean), format_ms(stats.stddev))
+}
+
+fn print_report(report: &Report) {
+    println!(
+        "{} ({}), {} iterations{}",
+        report.module,
+        ByteSize(report.size as _),
+        report.iterations,
+        report
+            .function
+            .as_ref()
+            .map(|function| format!(", running `{function}`"))
+            .unwrap_or_default()
+    );
+    println!();
+    println!(
+        "{:<12} {:<16} {:<22} {:<10} {:<22} run",
+        "backend", "opt level", "compile", "artifact", "instantiate"
+    );
+    for result in &report.results {
+        println!(
+            "{:<12} {:<16} {:<22} {:<10} {:<22} {}",
+            result.backend,
+            result.opt_level.as_deref().unwrap_or("default"),
+            format_stats(&result.compile),
+            result
+                .artifact_size
+                .map(|size| ByteSize(size as _).to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            format_stats(&result.instantiate),
+            result
+                .run
+                .as_ref()
+                .map(format_stats)
+                .unwrap_or_else(|| "-".to_string()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_of_the_samples() {
+        let samples = [4, 1, 3, 2].map(Duration::from_millis);
+        let stats = Stats::new(&samples);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 4.0);
+        assert!((stats.stddev - 1.290_994).abs() < 1e-6);
+
+        let single = Stats::new(&[Duration::from_micros(1500)]);
+        assert_eq!(single.median, 1.5);
+        assert_eq!(single.stddev, 0.0);
+    }
+
+    #[test]
+    fn durations_are_formatted_in_a_readable_unit() {
+        assert_eq!(format_ms(0.0125), "12.5 µs");
+        assert_eq!(format_ms(12.345), "12.35 ms");
+        assert_eq!(format_ms(2500.0), "2.50 s");
+    }
+}
@@ -1,7 +1,13 @@
 use crate::config::WasmerEnv;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use bytesize::ByteSize;
 use clap::Parser;
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use wasmer_package::utils::from_disk;
+use wasmer_wasix::runtime::{package_loader::BlobStore, resolver::WebcHash};
 
 #[derive(Debug, Parser)]
 /// The options for the `wasmer cache` subcommand
@@ -25,6 +31,12 @@ impl Cache {
             Cmd::Dir => {
                 println!("{}", self.env.cache_dir().display());
             }
+            Cmd::Dedup => {
+                dedup(cache_dir)?;
+            }
+            Cmd::Gc => {
+                gc(cache_dir)?;
+            }
         }
 
         Ok(())
@@ -37,6 +49,12 @@ enum Cmd {
     Clean,
     /// Display the location of the cache
     Dir,
+    /// Move every cached package into the deduplicated file store and report
+    /// how much space is being shared
+    Dedup,
+    /// Remove cached packages that are already in the deduplicated file
+    /// store, and files from the store that are no longer used by any package
+    Gc,
 }
 
 fn clean(cache_dir: &Path) -> Result<()> {
@@ -48,3 +66,119 @@ fn clean(cache_dir: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Find the hash of every package in the checkout cache, along with the path
+/// to its `*.webc` file.
+fn cached_packages(cache_dir: &Path) -> Result<Vec<(WebcHash, PathBuf)>> {
+    let checkouts = cache_dir.join("checkouts");
+    let entries = match fs::read_dir(&checkouts) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Unable to read \"{}\"", checkouts.display()))
+        }
+    };
+
+    let mut packages = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let hash = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_suffix(".bin"))
+            .and_then(|hash| WebcHash::parse_hex(hash).ok());
+        if let Some(hash) = hash {
+            packages.push((hash, entry.path()));
+        }
+    }
+
+    packages.sort();
+    Ok(packages)
+}
+
+/// Delete a package from the checkout cache, returning its size.
+fn remove_checkout(path: &Path) -> Result<u64> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Unable to read \"{}\"", path.display()))?
+        .len();
+    fs::remove_file(path).with_context(|| format!("Unable to delete \"{}\"", path.display()))?;
+    Ok(size)
+}
+
+fn dedup(cache_dir: &Path) -> Result<()> {
+    let store = BlobStore::new(cache_dir.join("blobs"));
+    let mut checkout_bytes = 0;
+
+    for (hash, path) in cached_packages(cache_dir)? {
+        if !store.contains(&hash) {
+            let container = from_disk(&path)
+                .with_context(|| format!("Unable to load \"{}\"", path.display()))?;
+            let report = store.ingest(&hash, &container)?;
+            // Make sure the package can be loaded before deleting the original
+            store
+                .container(&hash)?
+                .context("The package disappeared from the blob store")?;
+
+            println!(
+                "{}: {} of {} files ({}) were already stored",
+                hash.as_hex(),
+                report.deduplicated_files(),
+                report.files,
+                ByteSize(report.deduplicated_bytes()),
+            );
+        }
+
+        checkout_bytes += remove_checkout(&path)?;
+    }
+
+    if checkout_bytes > 0 {
+        println!(
+            "Removed {} of packages that are now in the blob store",
+            ByteSize(checkout_bytes)
+        );
+    }
+
+    let stats = store.stats()?;
+    println!(
+        "{} packages containing {} files ({}) are stored in {} blobs ({}), saving {}",
+        stats.packages,
+        stats.files,
+        ByteSize(stats.logical_bytes),
+        stats.blobs,
+        ByteSize(stats.stored_bytes),
+        ByteSize(stats.saved_bytes()),
+    );
+    if stats.blobs > stats.referenced_blobs {
+        println!(
+            "{} blobs are no longer used and can be removed with \"wasmer cache gc\"",
+            stats.blobs - stats.referenced_blobs,
+        );
+    }
+
+    Ok(())
+}
+
+fn gc(cache_dir: &Path) -> Result<()> {
+    let store = BlobStore::new(cache_dir.join("blobs"));
+
+    let mut removed_checkouts = 0;
+    let mut checkout_bytes = 0;
+    for (hash, path) in cached_packages(cache_dir)? {
+        if store.contains(&hash) {
+            checkout_bytes += remove_checkout(&path)?;
+            removed_checkouts += 1;
+        }
+    }
+
+    let report = store.gc(|_| true)?;
+
+    println!(
+        "Removed {} cached packages, {} stored packages and {} blobs, freeing {}",
+        removed_checkouts,
+        report.removed_packages,
+        report.removed_blobs,
+        ByteSize(checkout_bytes + report.freed_bytes),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,494 @@
+use std::{collections::HashMap, io::Write, path::PathBuf};
+
+use anyhow::Context;
+use bytesize::ByteSize;
+use clap::Parser;
+use gimli::{EndianSlice, LittleEndian};
+use wasmer::wasmparser::{
+    CoreDumpInstance, CoreDumpStackFrame, CoreDumpValue, DataKind, ExternalKind, KnownCustom, Name,
+    Operator, Payload, ValType,
+};
+
+use crate::commands::CliCommand;
+
+const WASM_PAGE_SIZE: u64 = 0x10000;
+
+/// Prints the threads, frames, locals, globals and memories of a coredump
+#[derive(Debug, Parser)]
+pub struct CmdCoredumpInspect {
+    /// Path to the coredump
+    #[clap(index = 1)]
+    coredump_path: PathBuf,
+
+    /// The module that was running, whose name section and DWARF name the
+    /// functions and locate them in the sources [default: the program
+    /// recorded in the coredump, if it exists]
+    #[clap(long)]
+    module: Option<PathBuf>,
+}
+
+impl CliCommand for CmdCoredumpInspect {
+    type Output = ();
+
+    fn run(self) -> Result<(), anyhow::Error> {
+        let bytes = std::fs::read(&self.coredump_path)
+            .with_context(|| format!("Unable to read \"{}\"", self.coredump_path.display()))?;
+        let coredump = Coredump::parse(&bytes).context("Unable to parse the coredump")?;
+
+        let module = self
+            .module
+            .clone()
+            .or_else(|| Some(PathBuf::from(&coredump.executable)).filter(|path| path.is_file()));
+        let symbols = module
+            .map(|path| -> anyhow::Result<Symbols> {
+                let wasm = std::fs::read(&path)
+                    .with_context(|| format!("Unable to read \"{}\"", path.display()))?;
+                let wasm = wasmer::wat2wasm(&wasm)?;
+                Symbols::parse(&wasm)
+                    .with_context(|| format!("Unable to parse \"{}\"", path.display()))
+            })
+            .transpose()?;
+
+        print(&coredump, symbols.as_ref(), &mut std::io::stdout().lock())?;
+        Ok(())
+    }
+}
+
+/// The contents of a coredump
+#[derive(Debug, Default)]
+struct Coredump {
+    executable: String,
+    modules: Vec<String>,
+    instances: Vec<CoreDumpInstance>,
+    threads: Vec<(String, Vec<CoreDumpStackFrame>)>,
+    globals: Vec<(ValType, Option<String>)>,
+    /// Size of each memory, and the number of bytes of its data segments
+    memories: Vec<(u64, u64)>,
+}
+
+impl Coredump {
+    fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut coredump = Coredump::default();
+        let mut is_coredump = false;
+        for payload in wasmer::wasmparser::Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::CustomSection(section) => match section.as_known() {
+                    KnownCustom::CoreDump(core) => {
+                        is_coredump = true;
+                        coredump.executable = core.name.to_string();
+                    }
+                    KnownCustom::CoreDumpModules(modules) => {
+                        coredump.modules = modules.modules.iter().map(|m| m.to_string()).collect();
+                    }
+                    KnownCustom::CoreDumpInstances(instances) => {
+                        coredump.instances = instances.instances;
+                    }
+                    KnownCustom::CoreDumpStack(stack) => {
+                        coredump
+                            .threads
+                            .push((stack.name.to_string(), stack.frames));
+                    }
+                    _ => {}
+                },
+                Payload::MemorySection(memories) => {
+                    for memory in memories {
+                        coredump
+                            .memories
+                            .push((memory?.initial * WASM_PAGE_SIZE, 0));
+                    }
+                }
+                Payload::GlobalSection(globals) => {
+                    for global in globals {
+                        let global = global?;
+                        let value = global
+                            .init_expr
+                            .get_operators_reader()
+                            .read()
+                            .ok()
+                            .and_then(|operator| format_const(&operator));
+                        coredump.globals.push((global.ty.content_type, value));
+                    }
+                }
+                Payload::DataSection(data) => {
+                    for segment in data {
+                        let segment = segment?;
+                        if let DataKind::Active { memory_index, .. } = segment.kind {
+                            if let Some(memory) = coredump.memories.get_mut(memory_index as usize) {
+                                memory.1 += segment.data.len() as u64;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !is_coredump {
+            anyhow::bail!("the file has no \"core\" section, it isn't a coredump");
+        }
+        Ok(coredump)
+    }
+}
+
+fn format_const(operator: &Operator) -> Option<String> {
+    let value = match operator {
+        Operator::I32Const { value } => format!("{value}"),
+        Operator::I64Const { value } => format!("{value}"),
+        Operator::F32Const { value } => format!("{}", f32::from_bits(value.bits())),
+        Operator::F64Const { value } => format!("{}", f64::from_bits(value.bits())),
+        Operator::V128Const { value } => format!("0x{:032x}", value.i128()),
+        Operator::RefNull { .. } => "null".to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn format_value(value: &CoreDumpValue) -> String {
+    match value {
+        CoreDumpValue::Missing => "<optimized out>".to_string(),
+        CoreDumpValue::I32(value) => format!("i32 {value}"),
+        CoreDumpValue::I64(value) => format!("i64 {value}"),
+        CoreDumpValue::F32(value) => format!("f32 {value}"),
+        CoreDumpValue::F64(value) => format!("f64 {value}"),
+    }
+}
+
+/// What the module that was running tells about its functions
+#[derive(Debug, Default)]
+struct Symbols {
+    imported_functions: u32,
+    /// Offset in the module of each function body
+    bodies: Vec<usize>,
+    function_names: HashMap<u32, String>,
+    local_names: HashMap<u32, HashMap<u32, String>>,
+    global_names: HashMap<u32, String>,
+    lines: Vec<LineRow>,
+}
+
+/// A row of the DWARF line programs
+#[derive(Debug, Clone)]
+struct LineRow {
+    /// Offset in the module
+    offset: usize,
+    end_sequence: bool,
+    path: String,
+    line: u64,
+}
+
+impl Symbols {
+    fn parse(wasm: &[u8]) -> anyhow::Result<Self> {
+        let mut symbols = Symbols::default();
+        let mut sections = HashMap::new();
+        let mut code_start = 0;
+        let mut export_names = HashMap::new();
+        for payload in wasmer::wasmparser::Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        if let wasmer::wasmparser::TypeRef::Func(_) = import?.ty {
+                            symbols.imported_functions += 1;
+                        }
+                    }
+                }
+                Payload::ExportSection(exports) => {
+                    for export in exports {
+                        let export = export?;
+                        if export.kind == ExternalKind::Func {
+                            export_names.insert(export.index, export.name.to_string());
+                        }
+                    }
+                }
+                Payload::CodeSectionStart { range, .. } => code_start = range.start,
+                Payload::CodeSectionEntry(body) => symbols.bodies.push(body.range().start),
+                Payload::CustomSection(section) => match section.as_known() {
+                    KnownCustom::Name(names) => {
+                        // The name section is only informative, its
+                        // malformed parts are ignored
+                        for name in names.into_iter().flatten() {
+                            match name {
+                                Name::Function(functions) => {
+                                    for naming in functions.into_iter().flatten() {
+                                        symbols
+                                            .function_names
+                                            .insert(naming.index, naming.name.to_string());
+                                    }
+                                }
+                                Name::Local(functions) => {
+                                    for function in functions.into_iter().flatten() {
+                                        let locals = function
+                                            .names
+                                            .into_iter()
+                                            .flatten()
+                                            .map(|naming| (naming.index, naming.name.to_string()))
+                                            .collect();
+                                        symbols.local_names.insert(function.index, locals);
+                                    }
+                                }
+                                Name::Global(globals) => {
+                                    for naming in globals.into_iter().flatten() {
+                                        symbols
+                                            .global_names
+                                            .insert(naming.index, naming.name.to_string());
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ if section.name().starts_with(".debug_") => {
+                        sections.insert(section.name(), section.data());
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        // Exported functions are named after their export otherwise
+        for (index, name) in export_names {
+            symbols.function_names.entry(index).or_insert(name);
+        }
+        // Without usable DWARF the frames are still named
+        if let Err(error) = symbols.read_line_programs(&sections, code_start) {
+            tracing::warn!(%error, "Unable to read the DWARF line programs");
+        }
+        symbols
+            .lines
+            .sort_by_key(|row| (row.offset, !row.end_sequence));
+        Ok(symbols)
+    }
+
+    /// Reads the rows of the line programs, whose addresses are offsets in
+    /// the code section
+    fn read_line_programs(
+        &mut self,
+        sections: &HashMap<&str, &[u8]>,
+        code_start: usize,
+    ) -> gimli::Result<()> {
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = sections.get(id.name()).copied().unwrap_or_default();
+            Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+        })?;
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            let mut rows = program.rows();
+            // Sequences of functions that the linker dropped are moved to
+            // address 0, or to a tombstone address
+            let mut dropped = None;
+            while let Some((header, row)) = rows.next_row()? {
+                let sequence_dropped = *dropped
+                    .get_or_insert(row.address() == 0 || row.address() >= u32::MAX as u64 - 1);
+                if sequence_dropped {
+                    if row.end_sequence() {
+                        dropped = None;
+                    }
+                    continue;
+                }
+                if row.end_sequence() {
+                    dropped = None;
+                    self.lines.push(LineRow {
+                        offset: code_start + row.address() as usize,
+                        end_sequence: true,
+                        path: String::new(),
+                        line: 0,
+                    });
+                    continue;
+                }
+                let (Some(file), Some(line)) = (row.file(header), row.line()) else {
+                    continue;
+                };
+                // Each component replaces the previous ones when it's absolute
+                let mut path = PathBuf::new();
+                if let Some(comp_dir) = unit.comp_dir {
+                    path.push(&*comp_dir.to_string_lossy());
+                }
+                if let Some(directory) = file.directory(header) {
+                    path.push(&*dwarf.attr_string(&unit, directory)?.to_string_lossy());
+                }
+                path.push(
+                    &*dwarf
+                        .attr_string(&unit, file.path_name())?
+                        .to_string_lossy(),
+                );
+                self.lines.push(LineRow {
+                    offset: code_start + row.address() as usize,
+                    end_sequence: false,
+                    path: path.to_string_lossy().into_owned(),
+                    line: line.get(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Offset in the module of an instruction of a frame
+    fn module_offset(&self, frame: &CoreDumpStackFrame) -> Option<usize> {
+        let defined = frame.funcidx.checked_sub(self.imported_functions)?;
+        let body = self.bodies.get(defined as usize)?;
+        Some(body + frame.codeoffset as usize)
+    }
+
+    /// The source line of the instruction at an offset in the module
+    fn source_line(&self, offset: usize) -> Option<(&str, u64)> {
+        let index = self
+            .lines
+            .partition_point(|row| row.offset <= offset)
+            .checked_sub(1)?;
+        let row = &self.lines[index];
+        (!row.end_sequence).then_some((row.path.as_str(), row.line))
+    }
+}
+
+fn print(
+    coredump: &Coredump,
+    symbols: Option<&Symbols>,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "Coredump of {}", coredump.executable)?;
+    if symbols.is_none() {
+        writeln!(
+            out,
+            "The functions aren't named, pass the module that was running with --module"
+        )?;
+    }
+
+    for (name, frames) in &coredump.threads {
+        writeln!(out)?;
+        writeln!(out, "Thread {name}:")?;
+        for (depth, frame) in frames.iter().enumerate() {
+            let function = symbols
+                .and_then(|symbols| symbols.function_names.get(&frame.funcidx))
+                .map(|name| format!("{name} (func {})", frame.funcidx))
+                .unwrap_or_else(|| format!("func {}", frame.funcidx));
+            write!(out, "  #{depth:<3} {function}")?;
+            match symbols.and_then(|symbols| Some((symbols, symbols.module_offset(frame)?))) {
+                Some((symbols, offset)) => {
+                    write!(out, " at {offset:#x}")?;
+                    if let Some((path, line)) = symbols.source_line(offset) {
+                        write!(out, " in {path}:{line}")?;
+                    }
+                }
+                None => write!(out, " at +{:#x}", frame.codeoffset)?,
+            }
+            if coredump.instances.len() > 1 {
+                write!(out, ", instance {}", frame.instanceidx)?;
+            }
+            writeln!(out)?;
+
+            let local_names = symbols.and_then(|symbols| symbols.local_names.get(&frame.funcidx));
+            for (index, value) in frame.locals.iter().enumerate() {
+                let name = local_names
+                    .and_then(|names| names.get(&(index as u32)))
+                    .map(|name| format!(" ({name})"))
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "         local {index}{name} = {}",
+                    format_value(value)
+                )?;
+            }
+        }
+        if frames.iter().all(|frame| frame.locals.is_empty()) {
+            writeln!(out, "  The values of the locals were not captured")?;
+        }
+    }
+
+    for (index, instance) in coredump.instances.iter().enumerate() {
+        writeln!(out)?;
+        let module = coredump
+            .modules
+            .get(instance.module_index as usize)
+            .map(String::as_str)
+            .unwrap_or("?");
+        writeln!(out, "Instance {index} of {module}:")?;
+        for (index, global) in instance.globals.iter().enumerate() {
+            let name = symbols
+                .and_then(|symbols| symbols.global_names.get(&(index as u32)))
+                .map(|name| format!(" ({name})"))
+                .unwrap_or_default();
+            match coredump.globals.get(*global as usize) {
+                Some((ty, value)) => writeln!(
+                    out,
+                    "  global {index}{name}: {ty} = {}",
+                    value.as_deref().unwrap_or("?")
+                )?,
+                None => writeln!(out, "  global {index}{name}: missing")?,
+            }
+        }
+        for (index, memory) in instance.memories.iter().enumerate() {
+            match coredump.memories.get(*memory as usize) {
+                Some((size, data)) => writeln!(
+                    out,
+                    "  memory {index}: {}, {} of data",
+                    ByteSize(*size),
+                    ByteSize(*data)
+                )?,
+                None => writeln!(out, "  memory {index}: missing")?,
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = r#"
+        (module
+          (import "env" "log" (func $log (param i32)))
+          (global $counter (mut i32) (i32.const 0))
+          (func $inner (param $value i32)
+            local.get $value
+            call $log)
+          (func (export "outer")
+            i32.const 1
+            call $inner))
+    "#;
+
+    #[test]
+    fn frames_are_located_in_the_module() {
+        let wasm = wasmer::wat2wasm(MODULE.as_bytes()).unwrap();
+        let symbols = Symbols::parse(&wasm).unwrap();
+        assert_eq!(symbols.imported_functions, 1);
+        assert_eq!(symbols.bodies.len(), 2);
+        assert_eq!(symbols.function_names[&2], "outer");
+        assert_eq!(symbols.local_names[&1][&0], "value");
+        assert_eq!(symbols.global_names[&0], "counter");
+
+        let frame = CoreDumpStackFrame {
+            instanceidx: 0,
+            funcidx: 2,
+            codeoffset: 3,
+            locals: Vec::new(),
+            stack: Vec::new(),
+        };
+        assert_eq!(symbols.module_offset(&frame), Some(symbols.bodies[1] + 3));
+        let imported = CoreDumpStackFrame {
+            funcidx: 0,
+            ..frame
+        };
+        assert_eq!(symbols.module_offset(&imported), None);
+    }
+
+    #[test]
+    fn rows_map_offsets_to_lines() {
+        let row = |offset, end_sequence, line| LineRow {
+            offset,
+            end_sequence,
+            path: "main.rs".to_string(),
+            line,
+        };
+        let symbols = Symbols {
+            lines: vec![row(10, false, 1), row(14, false, 2), row(20, true, 0)],
+            ..Default::default()
+        };
+        assert_eq!(symbols.source_line(9), None);
+        assert_eq!(symbols.source_line(10), Some(("main.rs", 1)));
+        assert_eq!(symbols.source_line(19), Some(("main.rs", 2)));
+        assert_eq!(symbols.source_line(20), None);
+    }
+}
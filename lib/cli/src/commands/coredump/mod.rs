@@ -0,0 +1,22 @@
+use crate::commands::CliCommand;
+
+mod inspect;
+
+pub use inspect::*;
+
+/// Work with WebAssembly coredumps.
+#[derive(clap::Subcommand, Debug)]
+pub enum CmdCoredump {
+    /// Prints the threads, frames, globals and memories of a coredump
+    Inspect(CmdCoredumpInspect),
+}
+
+impl CliCommand for CmdCoredump {
+    type Output = ();
+
+    fn run(self) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Inspect(cmd) => cmd.run(),
+        }
+    }
+}
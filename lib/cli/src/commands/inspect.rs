@@ -1,25 +1,143 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
-use crate::backend::RuntimeOptions;
+use crate::backend::{BackendType, RuntimeOptions};
 use anyhow::{Context, Result};
 use bytesize::ByteSize;
 use clap::Parser;
-use wasmer::*;
-use wasmer_types::target::Target;
+use serde::Serialize;
+use wasmer::{
+    wasmparser::{KnownCustom, Name, Payload, TypeRef, Validator, WasmFeatures},
+    *,
+};
+use wasmer_types::{target::Target, Features};
+
+/// Number of the largest functions listed by default
+const DEFAULT_TOP_FUNCTIONS: usize = 10;
+
+/// The proposals that `wasmer inspect` looks for, along with the
+/// proposals that depend on them and must be disabled with them
+const PROPOSALS: &[(&str, WasmFeatures)] = &[
+    ("threads", WasmFeatures::THREADS),
+    ("simd", WasmFeatures::SIMD.union(WasmFeatures::RELAXED_SIMD)),
+    ("relaxed_simd", WasmFeatures::RELAXED_SIMD),
+    (
+        "exceptions",
+        WasmFeatures::EXCEPTIONS.union(WasmFeatures::LEGACY_EXCEPTIONS),
+    ),
+    ("memory64", WasmFeatures::MEMORY64),
+    ("multi_memory", WasmFeatures::MULTI_MEMORY),
+    ("multi_value", WasmFeatures::MULTI_VALUE),
+    ("bulk_memory", WasmFeatures::BULK_MEMORY),
+    (
+        "reference_types",
+        WasmFeatures::REFERENCE_TYPES
+            .union(WasmFeatures::FUNCTION_REFERENCES)
+            .union(WasmFeatures::GC),
+    ),
+    ("tail_call", WasmFeatures::TAIL_CALL),
+    ("extended_const", WasmFeatures::EXTENDED_CONST),
+    ("sign_extension", WasmFeatures::SIGN_EXTENSION),
+    (
+        "saturating_float_to_int",
+        WasmFeatures::SATURATING_FLOAT_TO_INT,
+    ),
+    (
+        "function_references",
+        WasmFeatures::FUNCTION_REFERENCES.union(WasmFeatures::GC),
+    ),
+    ("gc", WasmFeatures::GC),
+];
 
 #[derive(Debug, Parser)]
-/// The options for the `wasmer validate` subcommand
+/// The options for the `wasmer inspect` subcommand
 pub struct Inspect {
-    /// File to validate as WebAssembly
+    /// File to inspect as WebAssembly
     #[clap(name = "FILE")]
     path: PathBuf,
 
+    /// Print the report as JSON
+    #[clap(long)]
+    json: bool,
+
+    /// Number of the largest functions to list [default: 10, or all of
+    /// them with `--json`]
+    #[clap(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// Also compile the module with every backend that supports it and
+    /// report the size of the artifacts, which can take a while
+    #[clap(long)]
+    compiled_sizes: bool,
+
     #[clap(flatten)]
     rt: RuntimeOptions,
 }
 
+#[derive(Debug, Serialize)]
+struct Report {
+    backend: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: usize,
+    imports: Vec<Import>,
+    exports: Vec<Export>,
+    custom_sections: Vec<CustomSection>,
+    features: Vec<&'static str>,
+    supported_backends: Vec<String>,
+    wasi_versions: Vec<&'static str>,
+    code_size: usize,
+    function_count: usize,
+    /// The largest functions first
+    functions: Vec<FunctionSize>,
+    /// Only measured with `--compiled-sizes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled_sizes: Option<Vec<CompiledSize>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Import {
+    module: String,
+    name: String,
+    kind: &'static str,
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Export {
+    name: String,
+    kind: &'static str,
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CustomSection {
+    name: String,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionSize {
+    index: u32,
+    name: Option<String>,
+    size: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct CompiledSize {
+    backend: String,
+    size: Option<usize>,
+    error: Option<String>,
+}
+
+/// The sections of a module that are reported on
+#[derive(Debug, Default)]
+struct Sections {
+    custom_sections: Vec<CustomSection>,
+    functions: Vec<FunctionSize>,
+}
+
 impl Inspect {
-    /// Runs logic for the `validate` subcommand
+    /// Runs logic for the `inspect` subcommand
     pub fn execute(&self) -> Result<()> {
         self.inner_execute()
             .context(format!("failed to inspect `{}`", self.path.display()))
@@ -27,54 +145,335 @@ impl Inspect {
 
     fn inner_execute(&self) -> Result<()> {
         let module_contents = std::fs::read(&self.path)?;
-        let engine = self
-            .rt
-            .get_engine_for_module(&module_contents, &Target::default())?;
-
         let iswasm = is_wasm(&module_contents);
-        let module_len = module_contents.len();
-        let module = Module::new(&engine, module_contents)?;
-        println!(
-            "Backend used to parse the module: {}",
-            engine.deterministic_id()
-        );
+        let wasm = wat2wasm(&module_contents)?;
+
+        let target = Target::default();
+        let engine = self.rt.get_engine_for_module(&wasm, &target)?;
+        let module = Module::new(&engine, &wasm)?;
 
-        println!("Type: {}", if !iswasm { "wat" } else { "wasm" });
-        println!("Size: {}", ByteSize(module_len as _));
-        println!("Imports:");
-        println!("  Functions:");
-        for f in module.imports().functions() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        let features = used_features(&wasm)?;
+        let sections = read_sections(&wasm)?;
+        let top = self.top.unwrap_or(if self.json {
+            usize::MAX
+        } else {
+            DEFAULT_TOP_FUNCTIONS
+        });
+        let report = Report {
+            backend: engine.deterministic_id().to_string(),
+            kind: if iswasm { "wasm" } else { "wat" },
+            size: module_contents.len(),
+            imports: module
+                .imports()
+                .map(|import| {
+                    let (kind, ty) = describe(import.ty());
+                    Import {
+                        module: import.module().to_string(),
+                        name: import.name().to_string(),
+                        kind,
+                        ty,
+                    }
+                })
+                .collect(),
+            exports: module
+                .exports()
+                .map(|export| {
+                    let (kind, ty) = describe(export.ty());
+                    Export {
+                        name: export.name().to_string(),
+                        kind,
+                        ty,
+                    }
+                })
+                .collect(),
+            supported_backends: BackendType::enabled()
+                .into_iter()
+                .filter(|backend| backend.supports_features(&to_features(&features), &target))
+                .map(|backend| backend.to_string())
+                .collect(),
+            features,
+            wasi_versions: wasmer_wasix::get_wasi_versions(&module, false)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|version| version.get_namespace_str())
+                .collect(),
+            code_size: sections
+                .functions
+                .iter()
+                .map(|function| function.size)
+                .sum(),
+            function_count: sections.functions.len(),
+            functions: sections.functions.into_iter().take(top).collect(),
+            custom_sections: sections.custom_sections,
+            compiled_sizes: if self.compiled_sizes {
+                Some(self.measure_compiled_sizes(&wasm, &target)?)
+            } else {
+                None
+            },
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
         }
-        println!("  Memories:");
-        for f in module.imports().memories() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        Ok(())
+    }
+
+    /// Compiles the module with every backend that is selected and
+    /// supports it, and measures the serialized artifact
+    fn measure_compiled_sizes(&self, wasm: &[u8], target: &Target) -> Result<Vec<CompiledSize>> {
+        let required_features = self.rt.detect_features_from_wasm(wasm).unwrap_or_default();
+        let backends = RuntimeOptions::filter_backends_by_features(
+            self.rt.get_available_backends()?,
+            &required_features,
+            target,
+        );
+        Ok(backends
+            .into_iter()
+            .map(|backend| {
+                let size = backend
+                    .get_engine(target, &required_features, &self.rt)
+                    .and_then(|engine| Ok(Module::new(&engine, wasm)?))
+                    .and_then(|module| Ok(module.serialize()?.len()));
+                CompiledSize {
+                    backend: backend.to_string(),
+                    size: size.as_ref().ok().copied(),
+                    error: size.err().map(|error| error.to_string()),
+                }
+            })
+            .collect())
+    }
+}
+
+fn describe(ty: &ExternType) -> (&'static str, String) {
+    match ty {
+        ExternType::Function(ty) => ("function", ty.to_string()),
+        ExternType::Memory(ty) => ("memory", ty.to_string()),
+        ExternType::Table(ty) => ("table", ty.to_string()),
+        ExternType::Global(ty) => ("global", ty.to_string()),
+        ExternType::Tag(ty) => ("tag", ty.to_string()),
+    }
+}
+
+/// Finds the proposals that the module uses, as the ones that it doesn't
+/// validate without
+fn used_features(wasm: &[u8]) -> Result<Vec<&'static str>> {
+    Validator::new_with_features(WasmFeatures::all()).validate_all(wasm)?;
+    Ok(PROPOSALS
+        .iter()
+        .filter(|(_, proposal)| {
+            Validator::new_with_features(WasmFeatures::all().difference(*proposal))
+                .validate_all(wasm)
+                .is_err()
+        })
+        .map(|(name, _)| *name)
+        .collect())
+}
+
+/// The `Features` that the backends need to support for the proposals
+fn to_features(used: &[&str]) -> Features {
+    let mut features = Features::none();
+    for name in used {
+        match *name {
+            "threads" => features.threads = true,
+            "simd" => features.simd = true,
+            "relaxed_simd" => features.relaxed_simd = true,
+            "exceptions" => features.exceptions = true,
+            "memory64" => features.memory64 = true,
+            "multi_memory" => features.multi_memory = true,
+            "multi_value" => features.multi_value = true,
+            "bulk_memory" => features.bulk_memory = true,
+            "reference_types" => features.reference_types = true,
+            "tail_call" => features.tail_call = true,
+            "extended_const" => features.extended_const = true,
+            _ => {}
         }
-        println!("  Tables:");
-        for f in module.imports().tables() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+    }
+    features
+}
+
+fn read_sections(wasm: &[u8]) -> Result<Sections> {
+    let mut sections = Sections::default();
+    let mut names = HashMap::new();
+    let mut imported_functions = 0;
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    if matches!(import?.ty, TypeRef::Func(_)) {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => sections.functions.push(FunctionSize {
+                index: imported_functions + sections.functions.len() as u32,
+                name: None,
+                size: body.range().len(),
+            }),
+            Payload::CustomSection(section) => {
+                sections.custom_sections.push(CustomSection {
+                    name: section.name().to_string(),
+                    size: section.data().len(),
+                });
+                if let KnownCustom::Name(reader) = section.as_known() {
+                    // A malformed name section only loses the names
+                    for name in reader.into_iter().flatten() {
+                        if let Name::Function(functions) = name {
+                            for naming in functions.into_iter().flatten() {
+                                names.insert(naming.index, naming.name.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
         }
-        println!("  Globals:");
-        for f in module.imports().globals() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+    }
+
+    for function in &mut sections.functions {
+        function.name = names.remove(&function.index);
+    }
+    sections
+        .functions
+        .sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+    Ok(sections)
+}
+
+fn print_report(report: &Report) {
+    println!("Backend used to parse the module: {}", report.backend);
+    println!("Type: {}", report.kind);
+    println!("Size: {}", ByteSize(report.size as _));
+
+    let kinds = [
+        ("Functions", "function"),
+        ("Memories", "memory"),
+        ("Tables", "table"),
+        ("Globals", "global"),
+        ("Tags", "tag"),
+    ];
+    println!("Imports:");
+    for (title, kind) in kinds {
+        let mut imports = report.imports.iter().filter(|i| i.kind == kind).peekable();
+        // Tags are rare, only list them when there are some
+        if kind == "tag" && imports.peek().is_none() {
+            continue;
         }
-        println!("Exports:");
-        println!("  Functions:");
-        for f in module.exports().functions() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("  {title}:");
+        for import in imports {
+            println!(
+                "    \"{}\".\"{}\": {}",
+                import.module, import.name, import.ty
+            );
         }
-        println!("  Memories:");
-        for f in module.exports().memories() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+    println!("Exports:");
+    for (title, kind) in kinds {
+        let mut exports = report.exports.iter().filter(|e| e.kind == kind).peekable();
+        if kind == "tag" && exports.peek().is_none() {
+            continue;
         }
-        println!("  Tables:");
-        for f in module.exports().tables() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("  {title}:");
+        for export in exports {
+            println!("    \"{}\": {}", export.name, export.ty);
         }
-        println!("  Globals:");
-        for f in module.exports().globals() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+    }
+
+    println!("Custom sections:");
+    for section in &report.custom_sections {
+        println!("  {}: {}", section.name, ByteSize(section.size as _));
+    }
+    let or_none = |items: Vec<String>| match items.is_empty() {
+        true => "none".to_string(),
+        false => items.join(", "),
+    };
+    println!(
+        "Features: {}",
+        or_none(report.features.iter().map(|f| f.to_string()).collect())
+    );
+    println!(
+        "Supported by: {}",
+        or_none(report.supported_backends.clone())
+    );
+    println!(
+        "WASI: {}",
+        or_none(report.wasi_versions.iter().map(|v| v.to_string()).collect())
+    );
+    println!(
+        "Code: {} in {} functions",
+        ByteSize(report.code_size as _),
+        report.function_count
+    );
+    println!("Largest functions:");
+    for function in &report.functions {
+        let name = function.name.as_deref().unwrap_or("<unnamed>");
+        println!(
+            "  {:>10}  #{} {}",
+            ByteSize(function.size as _).to_string(),
+            function.index,
+            name
+        );
+    }
+    let Some(compiled_sizes) = &report.compiled_sizes else {
+        return;
+    };
+    println!("Compiled size:");
+    for compiled in compiled_sizes {
+        match (compiled.size, &compiled.error) {
+            (Some(size), _) => println!("  {}: {}", compiled.backend, ByteSize(size as _)),
+            (None, error) => println!(
+                "  {}: unavailable ({})",
+                compiled.backend,
+                error.as_deref().unwrap_or_default()
+            ),
         }
-        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features_are_the_proposals_the_module_needs() {
+        let wasm = wat2wasm(
+            br#"(module
+                (memory 1 1 shared)
+                (func (param v128) (result i64)
+                    local.get 0
+                    i64x2.extract_lane 0
+                    i64.extend32_s))"#,
+        )
+        .unwrap();
+        assert_eq!(
+            used_features(&wasm).unwrap(),
+            ["threads", "simd", "sign_extension"]
+        );
+        assert!(used_features(&wat2wasm(b"(module)").unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn functions_are_sorted_by_size_and_named() {
+        let wasm = wat2wasm(
+            br#"(module
+                (import "env" "f" (func))
+                (func $small)
+                (func $large (result i32) i32.const 1 i32.const 2 i32.add)
+                (@custom "extra" "abc"))"#,
+        )
+        .unwrap();
+        let sections = read_sections(&wasm).unwrap();
+        let functions: Vec<_> = sections
+            .functions
+            .iter()
+            .map(|f| (f.index, f.name.as_deref()))
+            .collect();
+        assert_eq!(functions, [(2, Some("large")), (1, Some("small"))]);
+        assert!(sections
+            .custom_sections
+            .iter()
+            .any(|section| section.name == "extra" && section.size == 3));
     }
 }
@@ -289,7 +289,31 @@ impl WritableJournal for MutexState {
                 if let Some(file) = state.lookup.get(&fd) {
                     handle.block_on(async {
                         let mut file = file.lock().await;
-                        file.set_len(offset + len)
+                        if offset + len > file.size() {
+                            file.set_len(offset + len)?;
+                        }
+                        Ok::<_, FsError>(())
+                    })?;
+                }
+            }
+            JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            } => {
+                let handle = state.handle.clone();
+                if let Some(file) = state.lookup.get(&fd) {
+                    handle.block_on(async {
+                        let mut file = file.lock().await;
+                        if flags.contains(wasi::Fallocflags::PUNCH_HOLE) {
+                            file.punch_hole(offset, len)?;
+                        } else if !flags.contains(wasi::Fallocflags::KEEP_SIZE)
+                            && offset + len > file.size()
+                        {
+                            file.set_len(offset + len)?;
+                        }
+                        Ok::<_, FsError>(())
                     })?;
                 }
             }
@@ -2,6 +2,7 @@
 mod add;
 mod app;
 mod auth;
+mod bench;
 #[cfg(target_os = "linux")]
 mod binfmt;
 mod cache;
@@ -10,6 +11,7 @@ mod compile;
 mod config;
 mod connect;
 mod container;
+mod coredump;
 #[cfg(any(feature = "static-artifact-create", feature = "wasmer-artifact-create"))]
 mod create_exe;
 #[cfg(feature = "static-artifact-create")]
@@ -49,8 +51,8 @@ pub use {create_obj::*, gen_c_header::*};
 #[cfg(feature = "journal")]
 pub use self::journal::*;
 pub use self::{
-    add::*, auth::*, cache::*, config::*, container::*, init::*, inspect::*, package::*,
-    publish::*, run::Run, self_update::*, validate::*,
+    add::*, auth::*, bench::*, cache::*, config::*, container::*, coredump::*, init::*, inspect::*,
+    package::*, publish::*, run::Run, self_update::*, validate::*,
 };
 use crate::error::PrettyError;
 
@@ -162,7 +164,11 @@ impl WasmerCmd {
             output,
         } = self;
 
-        output.initialize_logging();
+        let strace = match &cmd {
+            Some(Cmd::Run(run)) => run.strace_layer()?,
+            _ => None,
+        };
+        output.initialize_logging(strace);
 
         if version {
             return print_version(output.is_verbose());
@@ -183,6 +189,8 @@ impl WasmerCmd {
             Some(Cmd::CreateObj(create_obj)) => create_obj.execute(),
             Some(Cmd::Config(config)) => config.run(),
             Some(Cmd::Inspect(inspect)) => inspect.execute(),
+            Some(Cmd::Bench(bench)) => bench.execute(),
+            Some(Cmd::Coredump(coredump)) => coredump.run(),
             Some(Cmd::Init(init)) => init.run(),
             Some(Cmd::Login(login)) => login.run(),
             Some(Cmd::Auth(auth)) => auth.run(),
@@ -269,7 +277,10 @@ impl WasmerCmd {
                         // because it's not shown as part of the main argument
                         // parser's help, but that's fine.
                         let output = crate::logging::Output::default();
-                        output.initialize_logging();
+                        let strace = run
+                            .strace_layer()
+                            .unwrap_or_else(|e| PrettyError::report::<()>(Err(e)));
+                        output.initialize_logging(strace);
                         run.execute(output);
                     }
                 }
@@ -386,6 +397,14 @@ enum Cmd {
     /// Inspect a WebAssembly file
     Inspect(Inspect),
 
+    /// Compare the compilation, instantiation and run times of a
+    /// WebAssembly file with each backend
+    Bench(Bench),
+
+    /// Inspect the WebAssembly coredumps written by `wasmer run`
+    #[clap(subcommand)]
+    Coredump(CmdCoredump),
+
     /// Initializes a new wasmer.toml file
     #[clap(name = "init")]
     Init(Init),
@@ -0,0 +1,560 @@
+//! Stopping the threads of the process to dump them.
+//!
+//! A dump is requested from a signal handler or from a thread that is about
+//! to be terminated, so the request only records the stack of the calling
+//! thread and wakes up a dumper thread through a pipe. The dumper sends a
+//! pause signal to every other thread of the process: the handler records
+//! the stack of threads that run WebAssembly code into a slot that was
+//! allocated beforehand and waits until the dump is written. The dumper
+//! then maps the return addresses to WebAssembly functions, reads the
+//! locals that the compiler recorded the location of from the stopped
+//! frames, and copies the memories and globals of the instances while
+//! nothing can change them.
+
+use std::{
+    cell::UnsafeCell,
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use wasmer_compiler::FRAME_INFO;
+use wasmer_types::Type;
+use wasmer_vm::VMContext;
+use wasmer_wasix::os::task::thread::{current_thread_ids, set_fatal_signal_hook};
+
+use super::{
+    super::unwind::{self, Registers},
+    Config, Coredump, Frame, Global, Instance, Local, Thread,
+};
+
+/// Deepest stack that is recorded, the outermost frames are dropped
+const MAX_DEPTH: usize = 256;
+/// Threads that can be recorded in one dump
+const MAX_THREADS: usize = 256;
+/// How long the dumper waits for the threads to stop, threads that block
+/// the pause signal never do
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+/// The signals that wasmer turns into traps
+const TRAP_SIGNALS: [libc::c_int; 4] = [libc::SIGSEGV, libc::SIGBUS, libc::SIGILL, libc::SIGFPE];
+/// Requests a dump without stopping the program
+const DUMP_SIGNAL: libc::c_int = libc::SIGUSR1;
+
+/// What a dump was requested for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Request {
+    /// A trap or a signal that terminates the program
+    Fatal = 0,
+    /// A `SIGUSR1`
+    OnDemand = 1,
+}
+
+#[derive(Clone, Copy)]
+struct RawStack {
+    /// WASIX process and thread ids
+    thread: Option<(u32, u32)>,
+    vmctx: usize,
+    /// Registers of the frame that the signal interrupted, if the stack
+    /// was recorded from a signal handler
+    registers: Option<Registers>,
+    depth: usize,
+    /// The innermost first
+    frames: [unwind::Frame; MAX_DEPTH],
+}
+
+struct Slots(Box<[UnsafeCell<RawStack>]>);
+
+// Safety: each slot is written by the thread that took it while the dumper
+// waits, and read by the dumper once the thread acknowledged
+unsafe impl Sync for Slots {}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+static SLOTS: OnceLock<Slots> = OnceLock::new();
+static PREVIOUS_HANDLERS: [OnceLock<libc::sigaction>; TRAP_SIGNALS.len()] =
+    [const { OnceLock::new() }; TRAP_SIGNALS.len()];
+/// Write end of the pipe that wakes up the dumper
+static REQUESTS: AtomicI32 = AtomicI32::new(-1);
+/// Set while the threads are stopped for a dump
+static DUMPING: AtomicBool = AtomicBool::new(false);
+/// Incremented once a dump is written, which resumes the stopped threads
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Host thread that requested the dump, which isn't paused
+static REQUESTER: AtomicI32 = AtomicI32::new(0);
+/// Slots that were taken for the current dump
+static TAKEN: AtomicUsize = AtomicUsize::new(0);
+/// Threads that handled the pause signal
+static STOPPED: AtomicUsize = AtomicUsize::new(0);
+/// Set once a trap or a fatal signal was dumped, the process is dumped at
+/// most once for them
+static FATAL_DUMPED: AtomicBool = AtomicBool::new(false);
+
+fn pause_signal() -> libc::c_int {
+    libc::SIGRTMIN()
+}
+
+fn gettid() -> i32 {
+    // Safety: `gettid` has no side effect
+    unsafe { libc::syscall(libc::SYS_gettid) as i32 }
+}
+
+pub(super) fn install(config: Config) -> anyhow::Result<()> {
+    if CONFIG.set(config).is_err() {
+        anyhow::bail!("coredumps are already set up");
+    }
+    let config = CONFIG.get().unwrap();
+    // The stacks are recorded with the instance that each thread runs
+    wasmer_vm::track_current_vmctx();
+    let slots = (0..MAX_THREADS)
+        .map(|_| {
+            UnsafeCell::new(RawStack {
+                thread: None,
+                vmctx: 0,
+                registers: None,
+                depth: 0,
+                frames: [unwind::Frame::default(); MAX_DEPTH],
+            })
+        })
+        .collect();
+    let _ = SLOTS.set(Slots(slots));
+
+    let mut fds = [0; 2];
+    // Safety: `fds` has room for both ends of the pipe
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        anyhow::bail!(
+            "unable to create the coredump pipe: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    REQUESTS.store(fds[1], Ordering::SeqCst);
+    std::thread::Builder::new()
+        .name("coredump".to_string())
+        .spawn(move || dumper(fds[0]))?;
+
+    // Safety: the handlers only touch memory that was set up above, and
+    // pass the traps on to the handlers of wasmer
+    unsafe {
+        install_handler(pause_signal(), on_pause as usize, libc::SA_RESTART)?;
+        if config.on_signal.is_some() {
+            install_handler(DUMP_SIGNAL, on_dump_signal as usize, libc::SA_RESTART)?;
+        }
+        if config.on_trap.is_some() {
+            // Our handlers run first and chain to the ones of wasmer
+            wasmer_vm::init_traps();
+            for (signum, previous) in TRAP_SIGNALS.iter().zip(&PREVIOUS_HANDLERS) {
+                let handler = install_handler(
+                    *signum,
+                    on_trap as usize,
+                    libc::SA_NODEFER | libc::SA_ONSTACK,
+                )?;
+                let _ = previous.set(handler);
+            }
+        }
+    }
+    if config.on_trap.is_some() {
+        set_fatal_signal_hook(|_| request(Request::Fatal, None));
+    }
+    Ok(())
+}
+
+pub(super) fn has_dumped() -> bool {
+    FATAL_DUMPED.load(Ordering::SeqCst)
+}
+
+/// Installs a `SA_SIGINFO` handler and returns the previous one
+unsafe fn install_handler(
+    signum: libc::c_int,
+    handler: usize,
+    flags: libc::c_int,
+) -> anyhow::Result<libc::sigaction> {
+    let mut action: libc::sigaction = std::mem::zeroed();
+    action.sa_flags = libc::SA_SIGINFO | flags;
+    action.sa_sigaction = handler;
+    libc::sigemptyset(&mut action.sa_mask);
+    let mut previous: libc::sigaction = std::mem::zeroed();
+    if libc::sigaction(signum, &action, &mut previous) != 0 {
+        anyhow::bail!(
+            "unable to install the handler of signal {signum}: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(previous)
+}
+
+/// Asks the dumper for a dump, fatal requests wait until it's written
+fn request(kind: Request, registers: Option<Registers>) {
+    if kind == Request::Fatal && FATAL_DUMPED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    // One dump at a time, a fatal request waits for the current one while
+    // an on-demand request is dropped
+    while DUMPING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        if kind == Request::OnDemand {
+            return;
+        }
+        // Safety: `sched_yield` has no side effect
+        unsafe { libc::sched_yield() };
+    }
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    STOPPED.store(0, Ordering::SeqCst);
+    TAKEN.store(0, Ordering::SeqCst);
+    // The requesting thread isn't paused, it records its own stack so that
+    // the registers of its frames are those of the signal it handles
+    REQUESTER.store(gettid(), Ordering::SeqCst);
+    let recorded = record(registers);
+    let byte = kind as u8;
+    // Safety: a one byte write to a pipe is atomic and async-signal-safe
+    let written = unsafe {
+        libc::write(
+            REQUESTS.load(Ordering::SeqCst),
+            &byte as *const u8 as *const libc::c_void,
+            1,
+        )
+    };
+    if written != 1 {
+        if kind == Request::Fatal {
+            FATAL_DUMPED.store(false, Ordering::SeqCst);
+        }
+        DUMPING.store(false, Ordering::SeqCst);
+        return;
+    }
+    // Like paused threads, a thread that doesn't run WebAssembly code
+    // doesn't wait for an on-demand dump, which the dumper thread itself
+    // may receive the signal for
+    if kind == Request::Fatal || recorded {
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            // Safety: `sched_yield` has no side effect
+            unsafe { libc::sched_yield() };
+        }
+    }
+}
+
+/// Records the stack of the calling thread in a free slot, if it runs
+/// WebAssembly code
+fn record(registers: Option<Registers>) -> bool {
+    let Some(vmctx) = wasmer_vm::current_vmctx() else {
+        return false;
+    };
+    let index = TAKEN.fetch_add(1, Ordering::SeqCst);
+    let Some(slot) = SLOTS.get().and_then(|slots| slots.0.get(index)) else {
+        return false;
+    };
+    // Safety: nobody else writes this slot, and the dumper reads it once
+    // the thread acknowledged
+    let stack = unsafe { &mut *slot.get() };
+    stack.thread = current_thread_ids().map(|(pid, tid)| (pid.raw(), tid.raw()));
+    stack.vmctx = vmctx.as_ptr() as usize;
+    stack.registers = registers;
+    stack.depth = 0;
+    // Safety: only this thread is unwound, and the closure doesn't allocate
+    unsafe {
+        unwind::trace(|frame| {
+            stack.frames[stack.depth] = *frame;
+            stack.depth += 1;
+            stack.depth < MAX_DEPTH
+        });
+    }
+    true
+}
+
+extern "C" fn on_pause(
+    _signum: libc::c_int,
+    _siginfo: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    if !DUMPING.load(Ordering::SeqCst) {
+        return;
+    }
+    let generation = GENERATION.load(Ordering::SeqCst);
+    // Safety: the kernel passes the context of the interrupted thread
+    let recorded = record(Some(unsafe { Registers::from_context(context) }));
+    STOPPED.fetch_add(1, Ordering::SeqCst);
+    // Threads that don't run WebAssembly code can't change the instances
+    if recorded {
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            // Safety: `sched_yield` has no side effect
+            unsafe { libc::sched_yield() };
+        }
+    }
+}
+
+extern "C" fn on_dump_signal(
+    _signum: libc::c_int,
+    _siginfo: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    // Safety: the kernel passes the context of the interrupted thread
+    request(
+        Request::OnDemand,
+        Some(unsafe { Registers::from_context(context) }),
+    );
+}
+
+extern "C" fn on_trap(
+    signum: libc::c_int,
+    siginfo: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    let pc = trap_pc(context);
+    // The lock is held for writing while modules are registered, which
+    // doesn't happen in the code of a module
+    let in_module = FRAME_INFO
+        .try_read()
+        .map(|frame_info| frame_info.lookup_frame_info(pc).is_some())
+        .unwrap_or(false);
+    if in_module && wasmer_vm::current_vmctx().is_some() {
+        // Safety: the kernel passes the context of the interrupted thread
+        request(
+            Request::Fatal,
+            Some(unsafe { Registers::from_context(context) }),
+        );
+    }
+    chain(signum, siginfo, context);
+}
+
+fn trap_pc(context: *mut libc::c_void) -> usize {
+    let context = context as *const libc::ucontext_t;
+    // Safety: the kernel passes the context of the interrupted thread
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        return (*context).uc_mcontext.gregs[libc::REG_RIP as usize] as usize;
+        #[cfg(target_arch = "aarch64")]
+        return (*context).uc_mcontext.pc as usize;
+        #[allow(unreachable_code)]
+        0
+    }
+}
+
+/// Passes a trap on to the handler that was installed before
+fn chain(signum: libc::c_int, siginfo: *mut libc::siginfo_t, context: *mut libc::c_void) {
+    let Some(previous) = TRAP_SIGNALS
+        .iter()
+        .position(|trap_signal| *trap_signal == signum)
+        .and_then(|index| PREVIOUS_HANDLERS[index].get())
+    else {
+        return;
+    };
+    // Safety: the previous handler expects to be called like this
+    unsafe {
+        if previous.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                std::mem::transmute(previous.sa_sigaction);
+            handler(signum, siginfo, context);
+        } else if previous.sa_sigaction == libc::SIG_DFL {
+            // The faulting instruction runs again and crashes
+            libc::sigaction(signum, previous, std::ptr::null_mut());
+        } else if previous.sa_sigaction != libc::SIG_IGN {
+            let handler: extern "C" fn(libc::c_int) = std::mem::transmute(previous.sa_sigaction);
+            handler(signum);
+        }
+    }
+}
+
+fn dumper(requests: libc::c_int) {
+    let config = CONFIG.get().unwrap();
+    loop {
+        let mut byte = 0u8;
+        // Safety: reads one byte into `byte`
+        let read = unsafe { libc::read(requests, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+        if read != 1 {
+            if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return;
+        }
+        let path = if byte == Request::Fatal as u8 {
+            &config.on_trap
+        } else {
+            &config.on_signal
+        };
+
+        stop_threads();
+        let result = path
+            .as_ref()
+            .map(|path| (path, collect(&config.executable)))
+            .map(|(path, coredump)| (path, coredump.write(path)));
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        DUMPING.store(false, Ordering::SeqCst);
+
+        match result {
+            Some((path, Ok(()))) => eprintln!("Core dumped at {}", path.display()),
+            Some((_, Err(error))) => eprintln!("{error:?}"),
+            None => {}
+        }
+    }
+}
+
+/// Sends the pause signal to the other threads and waits until they
+/// handled it
+fn stop_threads() {
+    let own = gettid();
+    let requester = REQUESTER.load(Ordering::SeqCst);
+    // Safety: `getpid` has no side effect
+    let pid = unsafe { libc::getpid() };
+    let Ok(tasks) = std::fs::read_dir("/proc/self/task") else {
+        return;
+    };
+    let mut signaled = 0;
+    for tid in tasks
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter(|tid| *tid != own && *tid != requester)
+    {
+        // Safety: signals a thread of this process, whose handler is set
+        if unsafe { libc::syscall(libc::SYS_tgkill, pid, tid, pause_signal()) } == 0 {
+            signaled += 1;
+        }
+    }
+    let start = Instant::now();
+    while STOPPED.load(Ordering::SeqCst) < signaled && start.elapsed() < STOP_TIMEOUT {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Builds the coredump from the recorded stacks, while their threads are
+/// stopped
+fn collect(executable: &str) -> Coredump {
+    let mut coredump = Coredump {
+        executable: executable.to_string(),
+        ..Default::default()
+    };
+    let slots = SLOTS.get().unwrap();
+    let taken = TAKEN.load(Ordering::SeqCst).min(MAX_THREADS);
+    let frame_info = FRAME_INFO.read().unwrap();
+
+    let mut modules = HashMap::new();
+    let mut instances = HashMap::new();
+    let mut memories = HashMap::new();
+    for slot in &slots.0[..taken] {
+        // Safety: the thread that recorded the slot is stopped
+        let stack = unsafe { &*slot.get() };
+        let vmctx = stack.vmctx as *const VMContext;
+
+        let instance = *instances.entry(stack.vmctx).or_insert_with(|| {
+            // Safety: the instance can't be dropped while its thread is
+            // stopped, and nothing else runs its code
+            let instance = unsafe {
+                snapshot(
+                    &*vmctx,
+                    executable,
+                    &mut coredump,
+                    &mut modules,
+                    &mut memories,
+                )
+            };
+            coredump.instances.push(instance);
+            coredump.instances.len() as u32 - 1
+        });
+
+        let frames = stack.frames[..stack.depth]
+            .iter()
+            .filter_map(|frame| {
+                // Return addresses point after the call, and the
+                // instruction before is the call
+                let pc = if frame.interrupted {
+                    frame.ip
+                } else {
+                    frame.ip.wrapping_sub(1)
+                };
+                let info = frame_info.lookup_frame_info(pc)?;
+                let locals = frame_info
+                    .lookup_locals(frame.ip)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(ty, location)| Local {
+                        ty,
+                        // Safety: the thread is stopped, its frames stay
+                        // on its stack
+                        bits: location
+                            .and_then(|location| unsafe {
+                                frame.read(
+                                    location,
+                                    unwind::value_size(ty),
+                                    stack.registers.as_ref(),
+                                )
+                            })
+                            .map(|bytes| {
+                                let mut bits = [0; 16];
+                                bits[..bytes.len()].copy_from_slice(&bytes);
+                                u128::from_le_bytes(bits)
+                            }),
+                    })
+                    .collect();
+                Some(Frame {
+                    instance,
+                    func_index: info.func_index(),
+                    code_offset: info.func_offset() as u32,
+                    locals,
+                })
+            })
+            .collect::<Vec<_>>();
+        if frames.is_empty() {
+            continue;
+        }
+        let name = match stack.thread {
+            Some((pid, tid)) => format!("pid {pid} tid {tid}"),
+            None => "main".to_string(),
+        };
+        coredump.threads.push(Thread { name, frames });
+    }
+    coredump
+}
+
+/// Copies the memories and the globals of an instance into the coredump
+unsafe fn snapshot(
+    vmctx: &VMContext,
+    executable: &str,
+    coredump: &mut Coredump,
+    modules: &mut HashMap<usize, u32>,
+    memories: &mut HashMap<usize, u32>,
+) -> Instance {
+    let info = vmctx.module_info();
+    let module = *modules.entry(info as *const _ as usize).or_insert_with(|| {
+        let name = info.name.clone().unwrap_or_else(|| executable.to_string());
+        coredump.modules.push(name);
+        coredump.modules.len() as u32 - 1
+    });
+
+    let mut instance = Instance {
+        module,
+        ..Default::default()
+    };
+    for index in info.memories.keys() {
+        let definition = vmctx.memory_definition(index);
+        // Shared memories are imported by the instance of each thread
+        let memory = *memories.entry(definition.base as usize).or_insert_with(|| {
+            let bytes = std::slice::from_raw_parts(definition.base, definition.current_length);
+            coredump.memories.push(bytes.to_vec());
+            coredump.memories.len() as u32 - 1
+        });
+        instance.memories.push(memory);
+    }
+    for (index, ty) in info.globals.iter() {
+        let size = match ty.ty {
+            Type::I32 | Type::F32 => 4,
+            Type::I64 | Type::F64 => 8,
+            Type::V128 => 16,
+            Type::ExternRef | Type::FuncRef | Type::ExceptionRef => 0,
+        };
+        let definition = vmctx.global_definition(index);
+        let mut bytes = [0; 16];
+        bytes[..size].copy_from_slice(std::slice::from_raw_parts(
+            definition.as_ptr() as *const u8,
+            size,
+        ));
+        coredump.globals.push(Global {
+            ty: ty.ty,
+            mutable: ty.mutability.is_mutable(),
+            bits: u128::from_le_bytes(bytes),
+        });
+        instance.globals.push(coredump.globals.len() as u32 - 1);
+    }
+    instance
+}
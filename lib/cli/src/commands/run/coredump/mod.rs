@@ -0,0 +1,417 @@
+//! Coredumps of `wasmer run`, in the format of the [tool conventions].
+//!
+//! A coredump is a WebAssembly module whose memories and globals hold the
+//! state of the instances when the dump was taken, and whose custom
+//! sections describe the call stack of each thread. On Linux, the threads
+//! are stopped while the dump is written so that it includes all of them
+//! with the values of their locals, see [`capture`]. Elsewhere only the
+//! frames of a trap are saved.
+//!
+//! [tool conventions]: https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+
+#[cfg(all(feature = "sys", target_os = "linux"))]
+mod capture;
+
+#[cfg(not(all(feature = "sys", target_os = "linux")))]
+mod capture {
+    use super::*;
+
+    pub(super) fn install(config: Config) -> anyhow::Result<()> {
+        if config.on_signal.is_some() {
+            anyhow::bail!(
+                "--coredump-on-signal is only supported on Linux with the native engines"
+            );
+        }
+        Ok(())
+    }
+
+    pub(super) fn has_dumped() -> bool {
+        false
+    }
+}
+
+use std::{ops::Range, path::PathBuf};
+
+use anyhow::Context;
+use wasm_encoder::{
+    ConstExpr, CoreDumpInstancesSection, CoreDumpModulesSection, CoreDumpSection,
+    CoreDumpStackSection, CoreDumpValue, DataSection, GlobalSection, MemorySection, RefType,
+    ValType,
+};
+use wasmer::{RuntimeError, Type};
+
+const WASM_PAGE_SIZE: usize = 0x10000;
+/// Granularity of the data segments, runs of zeroed bytes this large are
+/// left out of the dump
+const CHUNK_SIZE: usize = 0x1000;
+
+/// Where and when coredumps are written
+#[derive(Debug, Clone)]
+pub(super) struct Config {
+    /// Name of the program, stored in the dump
+    pub executable: String,
+    /// Written when a trap happens or a WASIX process is terminated by a
+    /// signal that dumps core
+    pub on_trap: Option<PathBuf>,
+    /// Written each time the process receives `SIGUSR1`
+    pub on_signal: Option<PathBuf>,
+}
+
+/// Sets up the coredumps of the whole process
+///
+/// Without it, or where it isn't supported, [`save_trace`] still writes
+/// the frames of traps.
+pub(super) fn install(config: Config) -> anyhow::Result<()> {
+    capture::install(config)
+}
+
+/// Writes a coredump with the frames of the trap that made the program
+/// fail, unless the whole process was already dumped when it happened
+pub(super) fn save_trace(
+    error: &anyhow::Error,
+    executable: &str,
+    path: &std::path::Path,
+) -> anyhow::Result<()> {
+    if capture::has_dumped() {
+        return Ok(());
+    }
+    let Some(error) = error.downcast_ref::<RuntimeError>() else {
+        tracing::warn!("no runtime error found to generate coredump with");
+        return Ok(());
+    };
+
+    let frames = error
+        .trace()
+        .iter()
+        .map(|frame| Frame {
+            instance: 0,
+            func_index: frame.func_index(),
+            code_offset: frame.func_offset() as u32,
+            locals: Vec::new(),
+        })
+        .collect();
+    let coredump = Coredump {
+        executable: executable.to_string(),
+        modules: vec![executable.to_string()],
+        instances: vec![Instance::default()],
+        memories: Vec::new(),
+        globals: Vec::new(),
+        threads: vec![Thread {
+            name: "main".to_string(),
+            frames,
+        }],
+    };
+    coredump.write(path)
+}
+
+/// The state of a process, as written in a coredump
+#[derive(Debug, Default)]
+struct Coredump {
+    executable: String,
+    modules: Vec<String>,
+    instances: Vec<Instance>,
+    /// Contents of the memories of all the instances, shared memories are
+    /// included once
+    memories: Vec<Vec<u8>>,
+    globals: Vec<Global>,
+    threads: Vec<Thread>,
+}
+
+#[derive(Debug, Default)]
+struct Instance {
+    /// Index in [`Coredump::modules`]
+    module: u32,
+    /// Indices in [`Coredump::memories`]
+    memories: Vec<u32>,
+    /// Indices in [`Coredump::globals`]
+    globals: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Global {
+    ty: Type,
+    mutable: bool,
+    /// Little-endian bits of the value, references are saved as null
+    bits: u128,
+}
+
+#[derive(Debug)]
+struct Thread {
+    name: String,
+    /// The innermost frame first
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    /// Index in [`Coredump::instances`]
+    instance: u32,
+    func_index: u32,
+    /// Offset of the instruction from the start of the function body
+    code_offset: u32,
+    /// The parameters then the declared locals, empty when the compiler
+    /// didn't record where they live
+    locals: Vec<Local>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Local {
+    ty: Type,
+    /// Little-endian bits of the value, unknown when it isn't kept
+    /// anywhere at that point of the function
+    bits: Option<u128>,
+}
+
+impl Coredump {
+    fn write(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.encode())
+            .with_context(|| format!("Unable to save the coredump to \"{}\"", path.display()))
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut module = wasm_encoder::Module::new();
+        module.section(&CoreDumpSection::new(&self.executable));
+
+        let mut memories = MemorySection::new();
+        let mut data = DataSection::new();
+        for (index, memory) in self.memories.iter().enumerate() {
+            memories.memory(wasm_encoder::MemoryType {
+                minimum: memory.len().div_ceil(WASM_PAGE_SIZE) as u64,
+                maximum: None,
+                memory64: false,
+                shared: false,
+                page_size_log2: None,
+            });
+            for run in data_runs(memory) {
+                let offset = ConstExpr::i32_const(run.start as i32);
+                data.active(index as u32, &offset, memory[run].iter().copied());
+            }
+        }
+        let mut globals = GlobalSection::new();
+        for global in &self.globals {
+            let (val_type, init) = global.encode();
+            let ty = wasm_encoder::GlobalType {
+                val_type,
+                mutable: global.mutable,
+                shared: false,
+            };
+            globals.global(ty, &init);
+        }
+        // The known sections must be in order
+        if !memories.is_empty() {
+            module.section(&memories);
+        }
+        if !globals.is_empty() {
+            module.section(&globals);
+        }
+        if !data.is_empty() {
+            module.section(&data);
+        }
+
+        let mut modules = CoreDumpModulesSection::new();
+        for name in &self.modules {
+            modules.module(name);
+        }
+        module.section(&modules);
+
+        let mut instances = CoreDumpInstancesSection::new();
+        for instance in &self.instances {
+            instances.instance(
+                instance.module,
+                instance.memories.iter().copied(),
+                instance.globals.iter().copied(),
+            );
+        }
+        module.section(&instances);
+
+        for thread in &self.threads {
+            let mut stack = CoreDumpStackSection::new(&thread.name);
+            for frame in &thread.frames {
+                // The operand stack isn't known once the code is compiled
+                stack.frame(
+                    frame.instance,
+                    frame.func_index,
+                    frame.code_offset,
+                    frame.locals.iter().map(Local::encode),
+                    [],
+                );
+            }
+            module.section(&stack);
+        }
+
+        module.finish()
+    }
+}
+
+impl Global {
+    fn encode(&self) -> (ValType, ConstExpr) {
+        match self.ty {
+            Type::I32 => (ValType::I32, ConstExpr::i32_const(self.bits as i32)),
+            Type::I64 => (ValType::I64, ConstExpr::i64_const(self.bits as i64)),
+            Type::F32 => (
+                ValType::F32,
+                ConstExpr::f32_const(f32::from_bits(self.bits as u32)),
+            ),
+            Type::F64 => (
+                ValType::F64,
+                ConstExpr::f64_const(f64::from_bits(self.bits as u64)),
+            ),
+            Type::V128 => (ValType::V128, ConstExpr::v128_const(self.bits as i128)),
+            Type::ExternRef => null(RefType::EXTERNREF),
+            Type::FuncRef => null(RefType::FUNCREF),
+            Type::ExceptionRef => null(RefType::EXNREF),
+        }
+    }
+}
+
+impl Local {
+    /// The format has no vectors nor references, they are saved as missing
+    fn encode(&self) -> CoreDumpValue {
+        match (self.ty, self.bits) {
+            (Type::I32, Some(bits)) => CoreDumpValue::I32(bits as i32),
+            (Type::I64, Some(bits)) => CoreDumpValue::I64(bits as i64),
+            (Type::F32, Some(bits)) => CoreDumpValue::F32(f32::from_bits(bits as u32)),
+            (Type::F64, Some(bits)) => CoreDumpValue::F64(f64::from_bits(bits as u64)),
+            _ => CoreDumpValue::Missing,
+        }
+    }
+}
+
+fn null(ty: RefType) -> (ValType, ConstExpr) {
+    (ValType::Ref(ty), ConstExpr::ref_null(ty.heap_type))
+}
+
+/// The ranges of a memory that aren't zeroed, in chunks of [`CHUNK_SIZE`]
+fn data_runs(memory: &[u8]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (index, chunk) in memory.chunks(CHUNK_SIZE).enumerate() {
+        if chunk.iter().all(|byte| *byte == 0) {
+            continue;
+        }
+        let start = index * CHUNK_SIZE;
+        let end = start + chunk.len();
+        match runs.last_mut() {
+            Some(run) if run.end == start => run.end = end,
+            _ => runs.push(start..end),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmer::wasmparser::{CoreDumpValue, KnownCustom, Parser, Payload};
+
+    use super::*;
+
+    #[test]
+    fn only_the_chunks_with_data_are_saved() {
+        let mut memory = vec![0; CHUNK_SIZE * 5 + 10];
+        memory[1] = 1;
+        memory[CHUNK_SIZE * 2 + 3] = 1;
+        memory[CHUNK_SIZE * 3] = 1;
+        memory[CHUNK_SIZE * 5 + 9] = 1;
+        assert_eq!(
+            data_runs(&memory),
+            [
+                0..CHUNK_SIZE,
+                CHUNK_SIZE * 2..CHUNK_SIZE * 4,
+                CHUNK_SIZE * 5..CHUNK_SIZE * 5 + 10
+            ]
+        );
+        assert!(data_runs(&[0; CHUNK_SIZE]).is_empty());
+    }
+
+    #[test]
+    fn the_coredump_is_a_valid_module() {
+        let coredump = Coredump {
+            executable: "test.wasm".to_string(),
+            modules: vec!["test".to_string()],
+            instances: vec![Instance {
+                module: 0,
+                memories: vec![0],
+                globals: vec![0, 1],
+            }],
+            memories: vec![vec![1; WASM_PAGE_SIZE]],
+            globals: vec![
+                Global {
+                    ty: Type::I32,
+                    mutable: true,
+                    bits: 42,
+                },
+                Global {
+                    ty: Type::FuncRef,
+                    mutable: false,
+                    bits: 0,
+                },
+            ],
+            threads: vec![Thread {
+                name: "main".to_string(),
+                frames: vec![
+                    Frame {
+                        instance: 0,
+                        func_index: 3,
+                        code_offset: 12,
+                        locals: vec![
+                            Local {
+                                ty: Type::I32,
+                                bits: Some(-7i32 as u32 as u128),
+                            },
+                            Local {
+                                ty: Type::F64,
+                                bits: Some(1.5f64.to_bits() as u128),
+                            },
+                            Local {
+                                ty: Type::I64,
+                                bits: None,
+                            },
+                            Local {
+                                ty: Type::V128,
+                                bits: Some(1),
+                            },
+                        ],
+                    },
+                    Frame {
+                        instance: 0,
+                        func_index: 1,
+                        code_offset: 4,
+                        locals: Vec::new(),
+                    },
+                ],
+            }],
+        };
+        let wasm = coredump.encode();
+        wasmer::wasmparser::Validator::new()
+            .validate_all(&wasm)
+            .unwrap();
+
+        let mut stacks = Vec::new();
+        let mut locals = Vec::new();
+        for payload in Parser::new(0).parse_all(&wasm) {
+            if let Payload::CustomSection(section) = payload.unwrap() {
+                if let KnownCustom::CoreDumpStack(stack) = section.as_known() {
+                    stacks.push((
+                        stack.name.to_string(),
+                        stack
+                            .frames
+                            .iter()
+                            .map(|frame| (frame.funcidx, frame.codeoffset))
+                            .collect::<Vec<_>>(),
+                    ));
+                    locals.extend(stack.frames.into_iter().map(|frame| frame.locals));
+                }
+            }
+        }
+        assert_eq!(stacks, [("main".to_string(), vec![(3, 12), (1, 4)])]);
+        assert!(matches!(
+            locals[0][..],
+            [
+                CoreDumpValue::I32(-7),
+                CoreDumpValue::F64(value),
+                CoreDumpValue::Missing,
+                CoreDumpValue::Missing,
+            ] if value == 1.5
+        ));
+        assert!(locals[1].is_empty());
+    }
+}
@@ -0,0 +1,566 @@
+//! A GDB remote serial protocol server for `wasmer run --gdb-listen`.
+//!
+//! The debugger sees a WebAssembly process the way LLDB expects it: the
+//! program counter and the breakpoints are offsets in the module, and the
+//! state of the instance is read with the `qWasmMem`, `qWasmGlobal` and
+//! `qWasmCallStack` packets. Addresses carry the space they belong to in
+//! their upper bits, see [`WasmAddress`].
+//!
+//! The locals of the stopped frames are read with `qWasmLocal`. Cranelift
+//! records where it keeps them when it's asked for debug info, which
+//! `--gdb-listen` does, and the other compilers don't: their locals and
+//! those that are optimized out at a point are reported as unavailable.
+//! `qWasmStackValue` always fails, the operand stack isn't recorded.
+
+mod target;
+
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use wasmer::Module;
+
+use self::target::{Resume, Stop, StopReason, Target};
+
+/// Id of the only module in the debugged process
+const MODULE_ID: u32 = 0;
+/// `SIGTRAP`, the signal of every stop
+const STOP_SIGNAL: u8 = 5;
+
+static EVENTS: OnceLock<mpsc::Sender<Event>> = OnceLock::new();
+
+/// Everything the server reacts to
+enum Event {
+    Packet(String),
+    /// The debugger sent `^C`
+    Interrupt,
+    Disconnected,
+    Stop(Stop),
+    /// The process exits with a code once the server acknowledges it
+    Exit(i32, mpsc::Sender<()>),
+}
+
+/// Waits for a debugger to connect on `addr`, the first function of
+/// `module` that runs stops until the debugger resumes it.
+///
+/// `wasm` is the binary of the module, debuggers read it from the process
+/// to get its symbols and debug info.
+pub(crate) fn listen(addr: SocketAddr, module: &Module, wasm: Vec<u8>) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("unable to listen on {addr}"))?;
+    eprintln!(
+        "Waiting for a debugger to connect on {}",
+        listener.local_addr()?
+    );
+    let (stream, peer) = listener
+        .accept()
+        .context("unable to accept the debugger connection")?;
+    stream.set_nodelay(true)?;
+    eprintln!("Debugger connected from {peer}");
+
+    let (events_tx, events) = mpsc::channel();
+    let (resume_tx, resume) = mpsc::channel();
+    let target = Target::install(module.clone(), events_tx.clone(), resume)?;
+    let _ = EVENTS.set(events_tx.clone());
+
+    let no_ack = Arc::new(AtomicBool::new(false));
+    let reader = stream.try_clone()?;
+    std::thread::spawn({
+        let no_ack = no_ack.clone();
+        move || read_packets(reader, events_tx, no_ack)
+    });
+
+    let session = Session {
+        target,
+        stream,
+        wasm,
+        resume: resume_tx,
+        stop: None,
+        reply_on_stop: false,
+        pending: VecDeque::new(),
+    };
+    std::thread::spawn(move || session.serve(events));
+    Ok(())
+}
+
+/// Tells the debugger that the process exits, if one is connected
+pub(crate) fn notify_exit(code: i32) {
+    let Some(events) = EVENTS.get() else {
+        return;
+    };
+    let (done, wait) = mpsc::channel();
+    if events.send(Event::Exit(code, done)).is_ok() {
+        let _ = wait.recv_timeout(Duration::from_secs(1));
+    }
+}
+
+/// The kinds of addresses, which make up the two upper bits of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WasmAddress {
+    /// An offset in the linear memory of the module
+    Memory(u32, u64),
+    /// An offset in the binary of the module
+    Object(u32, u64),
+}
+
+impl WasmAddress {
+    fn decode(address: u64) -> Option<Self> {
+        let module = ((address >> 32) & 0x3fff_ffff) as u32;
+        let offset = address & 0xffff_ffff;
+        match address >> 62 {
+            0 => Some(WasmAddress::Memory(module, offset)),
+            1 => Some(WasmAddress::Object(module, offset)),
+            _ => None,
+        }
+    }
+
+    fn encode(self) -> u64 {
+        let (kind, module, offset) = match self {
+            WasmAddress::Memory(module, offset) => (0, module, offset),
+            WasmAddress::Object(module, offset) => (1, module, offset),
+        };
+        (kind << 62) | ((module as u64) << 32) | offset
+    }
+}
+
+fn code_address(offset: u32) -> u64 {
+    WasmAddress::Object(MODULE_ID, offset as u64).encode()
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+/// Frames `data` as a packet, escaping the bytes that are special in one
+fn encode_packet(data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(data.len());
+    for byte in data {
+        if matches!(byte, b'#' | b'$' | b'}' | b'*') {
+            body.push(b'}');
+            body.push(byte ^ 0x20);
+        } else {
+            body.push(*byte);
+        }
+    }
+    let mut packet = Vec::with_capacity(body.len() + 4);
+    packet.push(b'$');
+    packet.extend_from_slice(&body);
+    packet.extend_from_slice(format!("#{:02x}", checksum(&body)).as_bytes());
+    packet
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len());
+    let mut escaped = false;
+    for byte in data {
+        match byte {
+            b'}' if !escaped => escaped = true,
+            _ if escaped => {
+                bytes.push(byte ^ 0x20);
+                escaped = false;
+            }
+            _ => bytes.push(*byte),
+        }
+    }
+    bytes
+}
+
+/// Reads the packets that the debugger sends and acknowledges them
+fn read_packets(mut stream: TcpStream, events: mpsc::Sender<Event>, no_ack: Arc<AtomicBool>) {
+    let mut buffer = [0u8; 4096];
+    let mut packet: Option<Vec<u8>> = None;
+    let mut trailer = Vec::new();
+
+    loop {
+        let read = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        for &byte in &buffer[..read] {
+            let Some(body) = packet.as_mut() else {
+                match byte {
+                    b'$' => packet = Some(Vec::new()),
+                    0x03 => {
+                        if events.send(Event::Interrupt).is_err() {
+                            return;
+                        }
+                    }
+                    // Acknowledgements of our packets
+                    _ => {}
+                }
+                continue;
+            };
+            if trailer.is_empty() && byte != b'#' {
+                body.push(byte);
+                continue;
+            }
+            trailer.push(byte);
+            if trailer.len() < 3 {
+                continue;
+            }
+
+            let body = packet.take().unwrap();
+            let valid = std::str::from_utf8(&trailer[1..])
+                .ok()
+                .and_then(|sum| u8::from_str_radix(sum, 16).ok())
+                == Some(checksum(&body));
+            trailer.clear();
+            if !no_ack.load(Ordering::SeqCst) {
+                let ack: &[u8] = if valid { b"+" } else { b"-" };
+                if stream.write_all(ack).is_err() {
+                    return;
+                }
+            }
+            if !valid {
+                continue;
+            }
+            let body = String::from_utf8_lossy(&unescape(&body)).into_owned();
+            if body == "QStartNoAckMode" {
+                no_ack.store(true, Ordering::SeqCst);
+            }
+            if events.send(Event::Packet(body)).is_err() {
+                return;
+            }
+        }
+    }
+    let _ = events.send(Event::Disconnected);
+}
+
+/// What the session does after a packet
+enum Next {
+    Reply(String),
+    Resume(Resume),
+    Detach,
+}
+
+struct Session {
+    target: &'static Target,
+    stream: TcpStream,
+    wasm: Vec<u8>,
+    resume: mpsc::Sender<Resume>,
+    /// The thread that is stopped, packets are only handled while there
+    /// is one
+    stop: Option<Stop>,
+    /// Whether the debugger waits for the next stop after resuming
+    reply_on_stop: bool,
+    pending: VecDeque<String>,
+}
+
+impl Session {
+    fn serve(mut self, events: mpsc::Receiver<Event>) {
+        for event in events {
+            match event {
+                Event::Packet(packet) => {
+                    self.pending.push_back(packet);
+                    if !self.handle_pending() {
+                        return;
+                    }
+                }
+                Event::Interrupt => {
+                    if self.stop.is_none() {
+                        self.target.interrupt();
+                    }
+                }
+                Event::Stop(stop) => {
+                    let reply = stop_reply(&stop);
+                    self.stop = Some(stop);
+                    if std::mem::take(&mut self.reply_on_stop) {
+                        self.send(&reply);
+                    }
+                    if !self.handle_pending() {
+                        return;
+                    }
+                }
+                Event::Exit(code, done) => {
+                    self.send(&format!("W{:02x}", code as u8));
+                    let _ = done.send(());
+                    return;
+                }
+                Event::Disconnected => {
+                    self.detach();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Handles the packets that came in while the process was running,
+    /// returns `false` once the session is over
+    fn handle_pending(&mut self) -> bool {
+        while self.stop.is_some() {
+            let Some(packet) = self.pending.pop_front() else {
+                break;
+            };
+            match self.handle(&packet) {
+                Next::Reply(reply) => self.send(&reply),
+                Next::Resume(resume) => {
+                    self.stop = None;
+                    self.reply_on_stop = true;
+                    let _ = self.resume.send(resume);
+                }
+                Next::Detach => {
+                    self.send("OK");
+                    self.detach();
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn detach(&mut self) {
+        self.target.detach();
+        if self.stop.take().is_some() {
+            let _ = self.resume.send(Resume::Continue);
+        }
+    }
+
+    fn send(&mut self, reply: &str) {
+        let packet = encode_packet(reply.as_bytes());
+        // Acknowledgements of the debugger are skipped by the reader
+        let _ = self.stream.write_all(&packet);
+    }
+
+    fn handle(&self, packet: &str) -> Next {
+        let stop = self
+            .stop
+            .as_ref()
+            .expect("packets are handled while stopped");
+        let reply = |text: &str| Next::Reply(text.to_string());
+
+        if packet.starts_with("qSupported") {
+            return reply("PacketSize=4000;QStartNoAckMode+;qXfer:libraries:read+;vContSupported+");
+        }
+        if let Some(args) = packet.strip_prefix("qXfer:libraries:read::") {
+            let xml = format!(
+                "<library-list><library name=\"wasm_module_{MODULE_ID}\"><section address=\"{:#x}\"/></library></library-list>",
+                code_address(0)
+            );
+            return Next::Reply(xfer_chunk(xml.as_bytes(), args));
+        }
+        if packet.starts_with("qWasmCallStack") {
+            let frames: Vec<u8> = stop
+                .frames
+                .iter()
+                .flat_map(|frame| code_address(frame.offset).to_le_bytes())
+                .collect();
+            return Next::Reply(to_hex(&frames));
+        }
+        // The frame and the index of `qWasmGlobal` and `qWasmLocal` are
+        // decimal
+        if let Some(args) = packet.strip_prefix("qWasmGlobal:") {
+            let index = args.split(';').nth(1).and_then(|index| index.parse().ok());
+            return Next::Reply(
+                index
+                    .and_then(|index| stop.read_global(self.target, index))
+                    .map_or_else(|| "E03".to_string(), |bytes| to_hex(&bytes)),
+            );
+        }
+        if let Some(args) = packet.strip_prefix("qWasmLocal:") {
+            let mut args = args.split(';').map(|arg| arg.parse::<u32>().ok());
+            let (Some(Some(frame)), Some(Some(index))) = (args.next(), args.next()) else {
+                return reply("E03");
+            };
+            return Next::Reply(
+                stop.read_local(frame as usize, index)
+                    .map_or_else(|| "E03".to_string(), |bytes| to_hex(&bytes)),
+            );
+        }
+        if packet.starts_with("qWasmStackValue:") {
+            // Not supported, see the module documentation
+            return reply("E03");
+        }
+        if let Some(args) = packet.strip_prefix("qWasmMem:") {
+            let mut args = args.split(';').skip(1).map(parse_hex);
+            let (Some(Some(address)), Some(Some(len))) = (args.next(), args.next()) else {
+                return reply("E03");
+            };
+            return Next::Reply(self.read_memory(address, len as usize));
+        }
+        if packet.starts_with("qHostInfo") || packet.starts_with("qProcessInfo") {
+            let mut info = String::new();
+            if packet.starts_with("qProcessInfo") {
+                info.push_str(&format!("pid:{:x};", std::process::id()));
+            }
+            info.push_str(&format!(
+                "triple:{};endian:little;ptrsize:4;",
+                to_hex(b"wasm32-unknown-unknown-wasm")
+            ));
+            return Next::Reply(info);
+        }
+        if let Some(index) = packet.strip_prefix("qRegisterInfo") {
+            return reply(match parse_hex(index) {
+                Some(0) => "name:pc;alt-name:pc;bitsize:64;offset:0;encoding:uint;format:hex;set:General Purpose Registers;gcc:16;dwarf:16;generic:pc;",
+                _ => "E45",
+            });
+        }
+
+        match packet {
+            "QStartNoAckMode" => reply("OK"),
+            "?" => Next::Reply(stop_reply(stop)),
+            "qC" => Next::Reply(format!("QC{:x}", stop.tid)),
+            "qfThreadInfo" => Next::Reply(format!("m{:x}", stop.tid)),
+            "qsThreadInfo" => reply("l"),
+            "qAttached" => reply("1"),
+            "g" | "p0" => Next::Reply(to_hex(&code_address(stop.pc()).to_le_bytes())),
+            "vCont?" => reply("vCont;c;C;s;S"),
+            "k" => {
+                eprintln!("Killed by the debugger");
+                std::process::exit(1);
+            }
+            "D" => Next::Detach,
+            _ => self.handle_other(packet),
+        }
+    }
+
+    fn handle_other(&self, packet: &str) -> Next {
+        let stop = self
+            .stop
+            .as_ref()
+            .expect("packets are handled while stopped");
+        let reply = |text: &str| Next::Reply(text.to_string());
+        let Some(kind) = packet.chars().next() else {
+            return reply("");
+        };
+        let args = &packet[1..];
+
+        match kind {
+            'H' => reply("OK"),
+            'p' => reply("E45"),
+            'T' => reply("OK"),
+            'q' if packet.starts_with("qThreadStopInfo") => Next::Reply(stop_reply(stop)),
+            'm' => {
+                let Some((address, len)) = args.split_once(',') else {
+                    return reply("E01");
+                };
+                let (Some(address), Some(len)) = (parse_hex(address), parse_hex(len)) else {
+                    return reply("E01");
+                };
+                Next::Reply(self.read_memory(address, len as usize))
+            }
+            'Z' | 'z' => {
+                let mut fields = args.split(',');
+                if fields.next() != Some("0") {
+                    return reply("");
+                }
+                let Some(Some(WasmAddress::Object(MODULE_ID, offset))) =
+                    fields.next().and_then(parse_hex).map(WasmAddress::decode)
+                else {
+                    return reply("E01");
+                };
+                let done = if kind == 'Z' {
+                    self.target.add_breakpoint(offset as u32)
+                } else {
+                    self.target.remove_breakpoint(offset as u32)
+                };
+                reply(if done { "OK" } else { "E01" })
+            }
+            'c' | 'C' => Next::Resume(Resume::Continue),
+            's' | 'S' => Next::Resume(Resume::Step),
+            'v' if packet.starts_with("vCont;") => match packet["vCont;".len()..].chars().next() {
+                Some('c' | 'C') => Next::Resume(Resume::Continue),
+                Some('s' | 'S') => Next::Resume(Resume::Step),
+                _ => reply(""),
+            },
+            _ => reply(""),
+        }
+    }
+
+    /// Reads from the binary or from the linear memory of the module
+    fn read_memory(&self, address: u64, len: usize) -> String {
+        let stop = self
+            .stop
+            .as_ref()
+            .expect("packets are handled while stopped");
+        match WasmAddress::decode(address) {
+            Some(WasmAddress::Object(MODULE_ID, offset)) => {
+                let start = offset as usize;
+                if start >= self.wasm.len() {
+                    return "E03".to_string();
+                }
+                let end = start.saturating_add(len).min(self.wasm.len());
+                to_hex(&self.wasm[start..end])
+            }
+            Some(WasmAddress::Memory(MODULE_ID, offset)) => stop
+                .read_memory(self.target, offset, len)
+                .map_or_else(|| "E03".to_string(), |bytes| to_hex(&bytes)),
+            _ => "E03".to_string(),
+        }
+    }
+}
+
+fn stop_reply(stop: &Stop) -> String {
+    let pc = code_address(stop.pc());
+    let reason = match stop.reason {
+        StopReason::Breakpoint => "breakpoint",
+        StopReason::Trace => "trace",
+    };
+    format!(
+        "T{STOP_SIGNAL:02x}thread:{tid:x};thread-pcs:{pc:x};00:{pc_le};reason:{reason};",
+        tid = stop.tid,
+        pc_le = to_hex(&pc.to_le_bytes()),
+    )
+}
+
+/// Answers a `qXfer` read of `offset,length` in `data`
+fn xfer_chunk(data: &[u8], args: &str) -> String {
+    let Some((offset, len)) = args.split_once(',') else {
+        return "E01".to_string();
+    };
+    let (Some(offset), Some(len)) = (parse_hex(offset), parse_hex(len)) else {
+        return "E01".to_string();
+    };
+    let start = (offset as usize).min(data.len());
+    let end = start.saturating_add(len as usize).min(data.len());
+    let marker = if end == data.len() { 'l' } else { 'm' };
+    format!("{marker}{}", String::from_utf8_lossy(&data[start..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_are_escaped_and_checksummed() {
+        assert_eq!(encode_packet(b"OK"), b"$OK#9a");
+        assert_eq!(encode_packet(b"a#b"), b"$a}\x03b#43");
+        assert_eq!(unescape(b"a}\x03b"), b"a#b");
+    }
+
+    #[test]
+    fn addresses_carry_their_space() {
+        assert_eq!(code_address(0x2a), 0x4000_0000_0000_002a);
+        assert_eq!(
+            WasmAddress::decode(0x4000_0000_0000_002a),
+            Some(WasmAddress::Object(0, 0x2a))
+        );
+        assert_eq!(
+            WasmAddress::decode(0x1000),
+            Some(WasmAddress::Memory(0, 0x1000))
+        );
+        assert_eq!(WasmAddress::decode(0xc000_0000_0000_0000), None);
+    }
+
+    #[test]
+    fn xfer_reads_are_chunked() {
+        assert_eq!(xfer_chunk(b"abcdef", "0,4"), "mabcd");
+        assert_eq!(xfer_chunk(b"abcdef", "4,10"), "lef");
+        assert_eq!(xfer_chunk(b"abcdef", "6,10"), "l");
+    }
+}
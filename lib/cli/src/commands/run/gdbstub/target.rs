@@ -0,0 +1,613 @@
+//! Breakpoints and single-stepping in the compiled code of a module.
+//!
+//! Breakpoints are `int3` instructions written over the first machine
+//! instruction of a WebAssembly instruction, single-stepping uses the trap
+//! flag of the CPU. Both raise `SIGTRAP` on the thread that runs the code,
+//! the handler stops that thread until the debugger resumes it.
+
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Mutex, OnceLock},
+};
+
+use wasmer::Module;
+use wasmer_compiler::FRAME_INFO;
+use wasmer_types::{FrameInfo, GlobalIndex, MemoryIndex};
+
+use super::{
+    super::unwind::{self, Registers},
+    Event,
+};
+
+const INT3: u8 = 0xcc;
+/// `si_code` of the `SIGTRAP` raised after a single step
+const TRAP_TRACE: i32 = 2;
+/// Trap flag of `EFLAGS`
+const EFLAGS_TF: i64 = 0x100;
+/// Module offset of the machine code that no WebAssembly instruction was
+/// compiled to, like the prologue of a function
+const NO_OFFSET: usize = u32::MAX as usize;
+
+static TARGET: OnceLock<Target> = OnceLock::new();
+static PREVIOUS_HANDLER: OnceLock<libc::sigaction> = OnceLock::new();
+
+/// Why a thread stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum StopReason {
+    Breakpoint,
+    Trace,
+}
+
+/// A thread that is stopped in the code of the module
+#[derive(Debug, Clone)]
+pub(super) struct Stop {
+    pub tid: i32,
+    pub reason: StopReason,
+    /// The frames of the module on the call stack, the innermost first
+    pub frames: Vec<Frame>,
+    /// Registers of the thread where it stopped, boxed to keep the events
+    /// small: they are sent from the signal handler, which runs on a small
+    /// stack
+    registers: Box<Registers>,
+    /// `vmctx` of the instance that the thread runs, if known
+    pub vmctx: Option<usize>,
+}
+
+/// A frame of the module on the call stack of a stopped thread
+#[derive(Debug, Clone)]
+pub(super) struct Frame {
+    /// Offset in the module of the instruction the frame is at
+    pub offset: u32,
+    /// The frame as seen by the unwinder, if the stack could be unwound
+    unwound: Option<unwind::Frame>,
+}
+
+impl Stop {
+    /// Offset in the module of the instruction the thread stopped at
+    pub fn pc(&self) -> u32 {
+        self.frames
+            .first()
+            .map(|frame| frame.offset)
+            .unwrap_or_default()
+    }
+
+    /// Reads the value of a local of a frame as little-endian bytes, as
+    /// long as the compiler recorded where it is kept at that point
+    pub fn read_local(&self, frame: usize, index: u32) -> Option<Vec<u8>> {
+        let frame = self.frames.get(frame)?.unwound?;
+        let locals = FRAME_INFO.read().unwrap().lookup_locals(frame.ip)?;
+        let (ty, location) = *locals.get(index as usize)?;
+        // Safety: the thread is stopped, its frames stay on its stack
+        unsafe { frame.read(location?, unwind::value_size(ty), Some(&self.registers)) }
+    }
+
+    /// Reads `len` bytes of the linear memory of the instance at `offset`
+    pub fn read_memory(&self, target: &Target, offset: u64, len: usize) -> Option<Vec<u8>> {
+        let vmctx = self.vmctx? as *mut wasmer_vm::VMContext;
+        if target.module.info().memories.is_empty() {
+            return None;
+        }
+        // Safety: the thread that runs the instance is stopped, the memory
+        // can't be grown or dropped until it's resumed
+        unsafe {
+            let definition = (*vmctx).memory_definition(MemoryIndex::from_u32(0));
+            let end = offset.checked_add(len as u64)?;
+            if end > definition.current_length as u64 {
+                return None;
+            }
+            let bytes = std::slice::from_raw_parts(definition.base.add(offset as usize), len);
+            Some(bytes.to_vec())
+        }
+    }
+
+    /// Reads the value of a global of the instance as little-endian bytes
+    pub fn read_global(&self, target: &Target, index: u32) -> Option<Vec<u8>> {
+        let vmctx = self.vmctx? as *mut wasmer_vm::VMContext;
+        let index = GlobalIndex::from_u32(index);
+        let size = unwind::value_size(target.module.info().globals.get(index)?.ty);
+        // Safety: see `read_memory`
+        unsafe {
+            let definition = (*vmctx).global_definition(index);
+            let bytes = std::slice::from_raw_parts(definition.as_ptr() as *const u8, size);
+            Some(bytes.to_vec())
+        }
+    }
+}
+
+/// What a stopped thread does next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Resume {
+    Continue,
+    Step,
+}
+
+#[derive(Debug, Default)]
+struct Patch {
+    original: u8,
+    /// Set by the debugger
+    user: bool,
+    /// Stops the first function that runs
+    entry: bool,
+    /// Return address of a call out of the module made while stepping
+    step_return: bool,
+}
+
+impl Patch {
+    fn is_used(&self) -> bool {
+        self.user || self.entry || self.step_return
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Stepping {
+    /// Executing the instruction under a breakpoint, which is put back
+    /// after it. `from` is the offset of the WebAssembly instruction that
+    /// is stepped over, if the thread keeps stepping afterwards.
+    OverBreakpoint { address: usize, from: Option<usize> },
+    /// Stepping until the thread reaches another WebAssembly instruction
+    Wasm { from: usize },
+    /// Running a call out of the module until it returns
+    Return { from: usize },
+}
+
+#[derive(Default)]
+struct Inner {
+    patches: HashMap<usize, Patch>,
+    stepping: HashMap<i32, Stepping>,
+}
+
+/// What the `SIGTRAP` handler does with the thread that raised it
+enum Action {
+    NotOurs,
+    Resume { rip: usize, step: bool },
+    Stop { rip: usize, reason: StopReason },
+}
+
+pub(super) struct Target {
+    module: Module,
+    inner: Mutex<Inner>,
+    /// Held by the thread that is stopped, the debugger only deals with
+    /// one of them at a time
+    stop_lock: Mutex<()>,
+    events: mpsc::Sender<Event>,
+    resume: Mutex<mpsc::Receiver<Resume>>,
+}
+
+impl Target {
+    /// Installs the `SIGTRAP` handler and stops the first function of
+    /// `module` that runs
+    pub fn install(
+        module: Module,
+        events: mpsc::Sender<Event>,
+        resume: mpsc::Receiver<Resume>,
+    ) -> anyhow::Result<&'static Target> {
+        let target = Target {
+            module,
+            inner: Mutex::default(),
+            stop_lock: Mutex::default(),
+            events,
+            resume: Mutex::new(resume),
+        };
+        if TARGET.set(target).is_err() {
+            anyhow::bail!("a debugger is already attached");
+        }
+        let target = TARGET.get().unwrap();
+        // Stops are reported with the instance that the thread runs
+        wasmer_vm::track_current_vmctx();
+
+        // Safety: the handler only deals with traps in the code of the
+        // module, others are passed on to the previous handler
+        unsafe {
+            let mut handler: libc::sigaction = std::mem::zeroed();
+            handler.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+            handler.sa_sigaction = on_sigtrap as usize;
+            libc::sigemptyset(&mut handler.sa_mask);
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(libc::SIGTRAP, &handler, &mut previous) != 0 {
+                anyhow::bail!(
+                    "unable to install the SIGTRAP handler: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            let _ = PREVIOUS_HANDLER.set(previous);
+        }
+
+        target.interrupt();
+        Ok(target)
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Stops the next function of the module that gets called
+    pub fn interrupt(&self) {
+        let entries = FRAME_INFO
+            .read()
+            .unwrap()
+            .function_entries(self.module.info());
+        let mut inner = self.inner.lock().unwrap();
+        for address in entries {
+            inner.patch(address, |patch| patch.entry = true);
+        }
+    }
+
+    /// Puts a breakpoint on the WebAssembly instruction at `offset`
+    pub fn add_breakpoint(&self, offset: u32) -> bool {
+        let Some(address) = FRAME_INFO
+            .read()
+            .unwrap()
+            .lookup_native_address(self.module.info(), offset)
+        else {
+            return false;
+        };
+        self.inner
+            .lock()
+            .unwrap()
+            .patch(address, |patch| patch.user = true);
+        true
+    }
+
+    /// Removes the breakpoint on the WebAssembly instruction at `offset`
+    pub fn remove_breakpoint(&self, offset: u32) -> bool {
+        let Some(address) = FRAME_INFO
+            .read()
+            .unwrap()
+            .lookup_native_address(self.module.info(), offset)
+        else {
+            return false;
+        };
+        self.inner
+            .lock()
+            .unwrap()
+            .unpatch(address, |patch| patch.user = false);
+        true
+    }
+
+    /// Removes every breakpoint, threads that are stepping finish their
+    /// step on their own
+    pub fn detach(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let addresses: Vec<usize> = inner.patches.keys().copied().collect();
+        for address in addresses {
+            inner.unpatch(address, |patch| {
+                patch.user = false;
+                patch.entry = false;
+            });
+        }
+    }
+
+    /// Tells what to do with a `SIGTRAP` raised at `rip`, `trace` is set
+    /// when it follows a single step
+    fn on_trap(&self, tid: i32, rip: usize, rsp: usize, trace: bool) -> Action {
+        let frame_info = FRAME_INFO.read().unwrap();
+        let module = self.module.info();
+        let mut inner = self.inner.lock().unwrap();
+
+        let from = if trace {
+            match inner.stepping.remove(&tid) {
+                None => return Action::NotOurs,
+                Some(Stepping::OverBreakpoint { address, from }) => {
+                    if let Some(patch) = inner.patches.get(&address) {
+                        if patch.is_used() {
+                            // Safety: the address is in the code of the module
+                            unsafe { write_code(address, INT3) };
+                        }
+                    }
+                    match from {
+                        Some(from) => from,
+                        None => return Action::Resume { rip, step: false },
+                    }
+                }
+                Some(Stepping::Wasm { from }) => from,
+                Some(Stepping::Return { .. }) => return Action::NotOurs,
+            }
+        } else {
+            let address = rip.wrapping_sub(1);
+            let Some(patch) = inner.patches.get(&address) else {
+                // The breakpoint may have been removed after the thread
+                // hit it
+                if frame_info.is_module_code(module, address)
+                    // Safety: the address is in the code of the module
+                    && unsafe { *(address as *const u8) } != INT3
+                {
+                    return Action::Resume {
+                        rip: address,
+                        step: false,
+                    };
+                }
+                return Action::NotOurs;
+            };
+
+            let (entry, user, original) = (patch.entry, patch.user, patch.original);
+            if entry {
+                inner.clear_entries();
+                inner.stepping.remove(&tid);
+                return Action::Stop {
+                    rip: address,
+                    reason: StopReason::Breakpoint,
+                };
+            }
+            if user {
+                inner.stepping.remove(&tid);
+                return Action::Stop {
+                    rip: address,
+                    reason: StopReason::Breakpoint,
+                };
+            }
+            match inner.stepping.get(&tid).copied() {
+                Some(Stepping::Return { from }) => {
+                    inner.stepping.remove(&tid);
+                    inner.unpatch(address, |patch| patch.step_return = false);
+                    from
+                }
+                _ => {
+                    // The return breakpoint of another thread
+                    // Safety: the address is in the code of the module
+                    unsafe { write_code(address, original) };
+                    inner.stepping.insert(
+                        tid,
+                        Stepping::OverBreakpoint {
+                            address,
+                            from: None,
+                        },
+                    );
+                    return Action::Resume {
+                        rip: address,
+                        step: true,
+                    };
+                }
+            }
+        };
+
+        let rip = if trace { rip } else { rip - 1 };
+        if frame_info.is_module_code(module, rip) {
+            let offset = frame_info
+                .lookup_frame_info(rip)
+                .map(|frame| frame.module_offset());
+            if offset.is_some_and(|offset| offset != from && offset != NO_OFFSET) {
+                return Action::Stop {
+                    rip,
+                    reason: StopReason::Trace,
+                };
+            }
+            inner.stepping.insert(tid, Stepping::Wasm { from });
+            return Action::Resume { rip, step: true };
+        }
+
+        // The step called a function out of the module, which runs until
+        // it returns
+        // Safety: a call has just pushed the return address
+        let return_address = unsafe { *(rsp as *const usize) };
+        if frame_info.is_module_code(module, return_address) {
+            inner.patch(return_address, |patch| patch.step_return = true);
+            inner.stepping.insert(tid, Stepping::Return { from });
+        }
+        Action::Resume { rip, step: false }
+    }
+
+    /// Sets up a thread stopped at `rip` to run again, tells whether it
+    /// single-steps
+    fn resume(&self, tid: i32, rip: usize, resume: Resume) -> bool {
+        let from = match resume {
+            Resume::Continue => None,
+            Resume::Step => FRAME_INFO
+                .read()
+                .unwrap()
+                .lookup_frame_info(rip)
+                .map(|frame| frame.module_offset()),
+        };
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(patch) = inner.patches.get(&rip) {
+            // Safety: the address is in the code of the module
+            unsafe { write_code(rip, patch.original) };
+            inner
+                .stepping
+                .insert(tid, Stepping::OverBreakpoint { address: rip, from });
+            return true;
+        }
+        match from {
+            Some(from) => {
+                inner.stepping.insert(tid, Stepping::Wasm { from });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reports a stopped thread to the debugger and waits until it's resumed
+    fn stop(&self, tid: i32, rip: usize, reason: StopReason, registers: Registers) -> Resume {
+        let frame_info = FRAME_INFO.read().unwrap();
+        let mut frames = Vec::new();
+        let mut found = false;
+        // Safety: this thread is stopped in a signal handler, the other
+        // threads don't unwind the stack while it's unwound
+        unsafe {
+            unwind::trace(|frame| {
+                // The first frame is the interrupted instruction, the
+                // others are return addresses
+                let pc = if found {
+                    frame.ip.wrapping_sub(1)
+                } else {
+                    frame.ip
+                };
+                if !found && frame.ip != rip {
+                    return true;
+                }
+                found = true;
+                if let Some(info) = frame_info.lookup_frame_info(pc) {
+                    frames.push(Frame {
+                        offset: wasm_offset(&info),
+                        unwound: Some(*frame),
+                    });
+                }
+                true
+            });
+        }
+        if frames.is_empty() {
+            if let Some(info) = frame_info.lookup_frame_info(rip) {
+                frames.push(Frame {
+                    offset: wasm_offset(&info),
+                    unwound: None,
+                });
+            }
+        }
+        drop(frame_info);
+
+        let vmctx = wasmer_vm::current_vmctx()
+            // Safety: the thread runs WebAssembly code
+            .filter(|vmctx| unsafe {
+                std::ptr::eq(vmctx.as_ref().module_info(), self.module.info())
+            })
+            .map(|vmctx| vmctx.as_ptr() as usize);
+
+        let _guard = self.stop_lock.lock().unwrap();
+        let stop = Stop {
+            tid,
+            reason,
+            frames,
+            registers: Box::new(registers),
+            vmctx,
+        };
+        if self.events.send(Event::Stop(stop)).is_err() {
+            return Resume::Continue;
+        }
+        self.resume
+            .lock()
+            .unwrap()
+            .recv()
+            .unwrap_or(Resume::Continue)
+    }
+}
+
+impl Inner {
+    fn patch(&mut self, address: usize, set: impl FnOnce(&mut Patch)) {
+        let patch = self.patches.entry(address).or_insert_with(|| Patch {
+            // Safety: breakpoints are only put in the code of the module
+            original: unsafe { *(address as *const u8) },
+            ..Default::default()
+        });
+        set(patch);
+        // Safety: see above
+        unsafe { write_code(address, INT3) };
+    }
+
+    fn unpatch(&mut self, address: usize, clear: impl FnOnce(&mut Patch)) {
+        let Some(patch) = self.patches.get_mut(&address) else {
+            return;
+        };
+        clear(patch);
+        if !patch.is_used() {
+            let original = patch.original;
+            self.patches.remove(&address);
+            // Safety: see `patch`
+            unsafe { write_code(address, original) };
+        }
+    }
+
+    fn clear_entries(&mut self) {
+        let addresses: Vec<usize> = self
+            .patches
+            .iter()
+            .filter(|(_, patch)| patch.entry)
+            .map(|(address, _)| *address)
+            .collect();
+        for address in addresses {
+            self.unpatch(address, |patch| patch.entry = false);
+        }
+    }
+}
+
+/// Offset in the module of a frame, the prologue of a function belongs to
+/// the start of the function
+fn wasm_offset(frame: &FrameInfo) -> u32 {
+    if frame.module_offset() == NO_OFFSET {
+        (frame.module_offset() - frame.func_offset()) as u32
+    } else {
+        frame.module_offset() as u32
+    }
+}
+
+/// Writes a byte of compiled code, which is mapped read-only
+unsafe fn write_code(address: usize, byte: u8) {
+    let page_size = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+    let page = address & !(page_size - 1);
+    libc::mprotect(
+        page as *mut libc::c_void,
+        page_size,
+        libc::PROT_READ | libc::PROT_WRITE | libc::PROT_EXEC,
+    );
+    std::ptr::write_volatile(address as *mut u8, byte);
+    libc::mprotect(
+        page as *mut libc::c_void,
+        page_size,
+        libc::PROT_READ | libc::PROT_EXEC,
+    );
+}
+
+fn gettid() -> i32 {
+    // Safety: the syscall has no arguments
+    unsafe { libc::syscall(libc::SYS_gettid) as i32 }
+}
+
+extern "C" fn on_sigtrap(
+    signum: libc::c_int,
+    siginfo: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    let Some(target) = TARGET.get() else {
+        return chain(signum, siginfo, context);
+    };
+    // Safety: the kernel passes a `ucontext_t` to `SA_SIGINFO` handlers
+    let (registers, regs, trace) = unsafe {
+        let registers = Registers::from_context(context);
+        let context = &mut *(context as *mut libc::ucontext_t);
+        (
+            registers,
+            &mut context.uc_mcontext.gregs,
+            (*siginfo).si_code == TRAP_TRACE,
+        )
+    };
+    let rip = regs[libc::REG_RIP as usize] as usize;
+    let rsp = regs[libc::REG_RSP as usize] as usize;
+    let tid = gettid();
+
+    let (rip, step) = match target.on_trap(tid, rip, rsp, trace) {
+        Action::NotOurs => return chain(signum, siginfo, context),
+        Action::Resume { rip, step } => (rip, step),
+        Action::Stop { rip, reason } => {
+            // The stack is unwound from the context of the signal
+            regs[libc::REG_RIP as usize] = rip as i64;
+            let resume = target.stop(tid, rip, reason, registers);
+            (rip, target.resume(tid, rip, resume))
+        }
+    };
+    regs[libc::REG_RIP as usize] = rip as i64;
+    if step {
+        regs[libc::REG_EFL as usize] |= EFLAGS_TF;
+    } else {
+        regs[libc::REG_EFL as usize] &= !EFLAGS_TF;
+    }
+}
+
+/// Passes a `SIGTRAP` that isn't a breakpoint of the debugger on to the
+/// handler that was installed before
+fn chain(signum: libc::c_int, siginfo: *mut libc::siginfo_t, context: *mut libc::c_void) {
+    let Some(previous) = PREVIOUS_HANDLER.get() else {
+        return;
+    };
+    // Safety: the previous handler expects to be called like this
+    unsafe {
+        if previous.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                std::mem::transmute(previous.sa_sigaction);
+            handler(signum, siginfo, context);
+        } else if previous.sa_sigaction == libc::SIG_DFL {
+            libc::signal(signum, libc::SIG_DFL);
+            libc::raise(signum);
+        } else if previous.sa_sigaction != libc::SIG_IGN {
+            let handler: extern "C" fn(libc::c_int) = std::mem::transmute(previous.sa_sigaction);
+            handler(signum);
+        }
+    }
+}
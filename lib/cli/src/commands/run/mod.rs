@@ -1,7 +1,19 @@
 #![allow(missing_docs, unused)]
 
 mod capabilities;
+#[cfg(feature = "coredump")]
+mod coredump;
+#[cfg(all(feature = "sys", target_os = "linux", target_arch = "x86_64"))]
+mod gdbstub;
+mod profiler;
+#[cfg(all(
+    feature = "sys",
+    target_os = "linux",
+    any(feature = "coredump", target_arch = "x86_64")
+))]
+mod unwind;
 mod wasi;
+mod watch;
 
 use std::{
     collections::{hash_map::DefaultHasher, BTreeMap},
@@ -53,14 +65,16 @@ use wasmer_wasix::{
         module_cache::CacheError, package_loader::PackageLoader, resolver::QueryError,
         task_manager::VirtualTaskManagerExt,
     },
+    types::wasi::Signal,
     Runtime, WasiError,
 };
 use webc::metadata::Manifest;
 use webc::Container;
 
+use self::profiler::ProfileFormat;
 use crate::{
     backend::RuntimeOptions, commands::run::wasi::Wasi, common::HashAlgorithm, config::WasmerEnv,
-    error::PrettyError, logging::Output,
+    error::PrettyError, logging::Output, strace::StraceOptions,
 };
 
 const TICK: Duration = Duration::from_millis(250);
@@ -85,9 +99,49 @@ pub struct Run {
     /// The function to invoke.
     #[clap(short, long)]
     invoke: Option<String>,
-    /// Generate a coredump at this path if a WebAssembly trap occurs
-    #[clap(name = "COREDUMP_PATH", long)]
+    /// Generate a coredump at this path if a WebAssembly trap occurs, or
+    /// if a WASIX process is terminated by `SIGQUIT` or `SIGABRT`.
+    ///
+    /// The coredump holds the call stacks with the values of the locals,
+    /// and the memories and globals. Locals are only captured on Linux
+    /// with Cranelift, and not the ones that were optimized out.
+    #[clap(long = "coredump-on-trap", value_name = "PATH")]
     coredump_on_trap: Option<PathBuf>,
+    /// Generate a coredump at this path each time `wasmer` receives
+    /// `SIGUSR1`, the program keeps running
+    #[clap(long = "coredump-on-signal", value_name = "PATH")]
+    coredump_on_signal: Option<PathBuf>,
+    /// Wait for a debugger to connect on this address before running the
+    /// module, with the GDB remote protocol and the LLDB extensions for
+    /// WebAssembly.
+    ///
+    /// The debugger can set breakpoints, step, and read the call stack, the
+    /// linear memory and the globals. The values of locals and of the
+    /// operand stack can't be read, the compiled code doesn't describe
+    /// where they are kept.
+    #[clap(long = "gdb-listen", value_name = "ADDR")]
+    gdb_listen: Option<SocketAddr>,
+    /// Sample the running WebAssembly code and write a profile to this
+    /// path when the program exits
+    #[clap(long = "profile", value_name = "PATH")]
+    profile: Option<PathBuf>,
+    /// Format of the profile, guessed from the extension of its path by
+    /// default: `.json` for the Firefox profiler, `.pb` for pprof and
+    /// collapsed stacks otherwise
+    #[clap(long = "profile-format", value_enum, requires = "profile")]
+    profile_format: Option<ProfileFormat>,
+    #[clap(flatten)]
+    strace: StraceOptions,
+    /// Run the program again when the input file or package directory
+    /// changes. WCGI and DCGI servers keep their listener and serve the new
+    /// version of the package.
+    ///
+    /// WebAssembly files must be WASI or WASIX modules, other modules can't
+    /// be stopped to load the new version.
+    #[clap(long = "watch")]
+    watch: bool,
+    #[clap(skip)]
+    reload: watch::Reload,
     /// The file, URL, or package to run.
     #[clap(value_parser = PackageSource::infer)]
     input: PackageSource,
@@ -170,22 +224,32 @@ impl Run {
             tracing::info!("Input is not a file, skipping WebAssembly feature detection");
         }
 
+        // The debugger and the coredumps read the locals of the stopped
+        // frames
+        let rt = if self.gdb_listen.is_some()
+            || self.coredump_on_trap.is_some()
+            || self.coredump_on_signal.is_some()
+        {
+            self.rt.with_recorded_locals()
+        } else {
+            self.rt.clone()
+        };
+
         // Get engine with feature-based backend selection if possible
         let mut engine = match &wasm_bytes {
             Some(wasm_bytes) => {
                 tracing::info!("Attempting to detect WebAssembly features from binary");
 
-                self.rt
-                    .get_engine_for_module(wasm_bytes, &Target::default())?
+                rt.get_engine_for_module(wasm_bytes, &Target::default())?
             }
             None => {
                 // No WebAssembly file available for analysis, check if we have a webc package
                 if let PackageSource::Package(ref pkg_source) = &self.input {
                     tracing::info!("Checking package for WebAssembly features: {}", pkg_source);
-                    self.rt.get_engine(&Target::default())?
+                    rt.get_engine(&Target::default())?
                 } else {
                     tracing::info!("No feature detection possible, using default engine");
-                    self.rt.get_engine(&Target::default())?
+                    rt.get_engine(&Target::default())?
                 }
             }
         };
@@ -228,74 +292,19 @@ impl Run {
 
         pb.finish_and_clear();
 
+        if let Some(path) = &self.profile {
+            profiler::start(path.clone(), self.profile_format)?;
+        }
+
+        self.install_coredumps()?;
+
         // push the TTY state so we can restore it after the program finishes
         let tty = runtime.tty().map(|tty| tty.tty_get());
 
-        let result = {
-            match target {
-                ExecutableTarget::WebAssembly {
-                    module,
-                    module_hash,
-                    path,
-                } => self.execute_wasm(&path, module, module_hash, runtime.clone()),
-                ExecutableTarget::Package(pkg) => {
-                    // Check if we should update the engine based on the WebC package features
-                    if let Some(cmd) = pkg.get_entrypoint_command() {
-                        if let Some(features) = cmd.wasm_features() {
-                            // Get the right engine for these features
-                            let backends = self.rt.get_available_backends()?;
-                            let available_engines = backends
-                                .iter()
-                                .map(|b| b.to_string())
-                                .collect::<Vec<_>>()
-                                .join(", ");
-
-                            let filtered_backends = RuntimeOptions::filter_backends_by_features(
-                                backends.clone(),
-                                &features,
-                                &Target::default(),
-                            );
-
-                            if !filtered_backends.is_empty() {
-                                let engine_id = filtered_backends[0].to_string();
-
-                                // Get a new engine that's compatible with the required features
-                                if let Ok(new_engine) = filtered_backends[0].get_engine(
-                                    &Target::default(),
-                                    &features,
-                                    &self.rt,
-                                ) {
-                                    tracing::info!(
-                                        "The command '{}' requires to run the Wasm module with the features {:?}. The backends available are {}. Choosing {}.",
-                                        cmd.name(),
-                                        features,
-                                        available_engines,
-                                        engine_id
-                                    );
-                                    // Create a new runtime with the updated engine
-                                    let new_runtime = self.wasi.prepare_runtime(
-                                        new_engine,
-                                        &self.env,
-                                        &capabilities::get_capability_cache_path(
-                                            &self.env,
-                                            &self.input,
-                                        )?,
-                                        tokio::runtime::Builder::new_multi_thread()
-                                            .enable_all()
-                                            .build()?,
-                                        preferred_webc_version,
-                                    )?;
-
-                                    let new_runtime =
-                                        Arc::new(MonitoringRuntime::new(new_runtime, pb.clone()));
-                                    return self.execute_webc(&pkg, new_runtime);
-                                }
-                            }
-                        }
-                    }
-                    self.execute_webc(&pkg, runtime.clone())
-                }
-            }
+        let result = if self.watch {
+            self.execute_watched(target, runtime.clone(), &pb, preferred_webc_version)
+        } else {
+            self.execute_target(target, runtime.clone(), &pb, preferred_webc_version)
         };
 
         // restore the TTY state as the execution may have changed it
@@ -312,6 +321,168 @@ impl Run {
         result
     }
 
+    fn execute_target(
+        &self,
+        target: ExecutableTarget,
+        runtime: Arc<dyn Runtime + Send + Sync>,
+        pb: &ProgressBar,
+        preferred_webc_version: webc::Version,
+    ) -> Result<(), Error> {
+        match target {
+            ExecutableTarget::WebAssembly {
+                module,
+                module_hash,
+                path,
+            } => self.execute_wasm(&path, module, module_hash, runtime.clone()),
+            ExecutableTarget::Package(_) if self.gdb_listen.is_some() => {
+                Err(anyhow!("--gdb-listen can only debug WebAssembly files"))
+            }
+            ExecutableTarget::Package(pkg) => {
+                // Check if we should update the engine based on the WebC package features
+                if let Some(cmd) = pkg.get_entrypoint_command() {
+                    if let Some(features) = cmd.wasm_features() {
+                        // Get the right engine for these features
+                        let backends = self.rt.get_available_backends()?;
+                        let available_engines = backends
+                            .iter()
+                            .map(|b| b.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+
+                        let filtered_backends = RuntimeOptions::filter_backends_by_features(
+                            backends.clone(),
+                            &features,
+                            &Target::default(),
+                        );
+
+                        if !filtered_backends.is_empty() {
+                            let engine_id = filtered_backends[0].to_string();
+
+                            // Get a new engine that's compatible with the required features
+                            if let Ok(new_engine) = filtered_backends[0].get_engine(
+                                &Target::default(),
+                                &features,
+                                &self.rt,
+                            ) {
+                                tracing::info!(
+                                    "The command '{}' requires to run the Wasm module with the features {:?}. The backends available are {}. Choosing {}.",
+                                    cmd.name(),
+                                    features,
+                                    available_engines,
+                                    engine_id
+                                );
+                                // Create a new runtime with the updated engine
+                                let new_runtime = self.wasi.prepare_runtime(
+                                    new_engine,
+                                    &self.env,
+                                    &capabilities::get_capability_cache_path(
+                                        &self.env,
+                                        &self.input,
+                                    )?,
+                                    tokio::runtime::Builder::new_multi_thread()
+                                        .enable_all()
+                                        .build()?,
+                                    preferred_webc_version,
+                                )?;
+
+                                let new_runtime =
+                                    Arc::new(MonitoringRuntime::new(new_runtime, pb.clone()));
+                                return self.execute_webc(&pkg, new_runtime);
+                            }
+                        }
+                    }
+                }
+                self.execute_webc(&pkg, runtime.clone())
+            }
+        }
+    }
+
+    /// Runs the program again each time its input changes, until Ctrl-C is
+    /// pressed
+    fn execute_watched(
+        &self,
+        target: ExecutableTarget,
+        runtime: Arc<dyn Runtime + Send + Sync>,
+        pb: &ProgressBar,
+        preferred_webc_version: webc::Version,
+    ) -> Result<(), Error> {
+        let path = match &self.input {
+            PackageSource::File(path) | PackageSource::Dir(path) => path,
+            PackageSource::Package(_) => bail!("--watch needs a file or a directory to watch"),
+        };
+        check_watchable(&target)?;
+        let mut watcher = watch::Watcher::new(path);
+        let interrupted = watch::interrupted();
+        let handle = tokio::runtime::Handle::current();
+
+        std::thread::scope(|scope| {
+            let run = |target| {
+                let runtime = runtime.clone();
+                let handle = handle.clone();
+                scope.spawn(move || {
+                    let _guard = handle.enter();
+                    self.execute_target(target, runtime, pb, preferred_webc_version)
+                })
+            };
+            let mut running = Some(run(target));
+            loop {
+                if interrupted.load(std::sync::atomic::Ordering::SeqCst) {
+                    match running {
+                        // The process was sent `SIGINT`
+                        Some(thread) if self.reload.process.process().is_some() => {
+                            return join(thread);
+                        }
+                        _ => exit_with_wasi_exit_code(Ok(())),
+                    }
+                }
+                if running.as_ref().is_some_and(|thread| thread.is_finished()) {
+                    if let Err(e) = join(running.take().unwrap()) {
+                        report_watched_error(e);
+                    }
+                    eprintln!("Waiting for changes to \"{}\"", path.display());
+                }
+                if !watcher.changed() {
+                    std::thread::sleep(watch::POLL);
+                    continue;
+                }
+
+                eprintln!("\"{}\" changed, reloading", path.display());
+                // The module cache of the runtime only compiles the modules
+                // that changed
+                let target = match self
+                    .input
+                    .resolve_target(&runtime, &ProgressBar::hidden())
+                    .and_then(|target| check_watchable(&target).map(|_| target))
+                {
+                    Ok(target) => target,
+                    Err(e) => {
+                        // Keep running the previous version
+                        report_watched_error(e);
+                        continue;
+                    }
+                };
+                if let (Some(_), ExecutableTarget::Package(pkg)) = (&running, &target) {
+                    match self.reload.server.reload(pkg) {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(e) => {
+                            report_watched_error(e);
+                            continue;
+                        }
+                    }
+                }
+                if let Some(thread) = running.take() {
+                    match self.reload.process.process() {
+                        Some(process) => process.signal_process(Signal::Sigkill),
+                        None => eprintln!("Waiting for the module to exit"),
+                    }
+                    let _ = join(thread);
+                }
+                running = Some(run(target));
+            }
+        })
+    }
+
     #[tracing::instrument(skip_all)]
     fn execute_wasm(
         &self,
@@ -320,6 +491,10 @@ impl Run {
         module_hash: ModuleHash,
         runtime: Arc<dyn Runtime + Send + Sync>,
     ) -> Result<(), Error> {
+        if let Some(addr) = self.gdb_listen {
+            listen_for_debugger(addr, path, &module)?;
+        }
+
         if wasmer_wasix::is_wasi_module(&module) || wasmer_wasix::is_wasix_module(&module) {
             self.execute_wasi_module(path, module, module_hash, runtime)
         } else {
@@ -424,6 +599,9 @@ impl Run {
         if self.wasi.forward_host_env {
             config.forward_host_env();
         }
+        if self.watch {
+            config.reloader(self.reload.server.clone());
+        }
 
         #[cfg(feature = "journal")]
         {
@@ -531,7 +709,7 @@ impl Run {
             .with_injected_packages(packages)
             .with_envs(self.wasi.env_vars.clone())
             .with_mapped_host_commands(self.wasi.build_mapped_commands()?)
-            .with_mapped_directories(mapped_diretories)
+            .with_mounted_directories(self.wasi.build_mounted_directories(mapped_diretories)?)
             .with_home_mapped(is_home_mapped)
             .with_tmp_mapped(is_tmp_mapped)
             .with_forward_host_env(self.wasi.forward_host_env)
@@ -541,6 +719,10 @@ impl Run {
             runner.with_entry_function(entry_function);
         }
 
+        if self.watch {
+            runner.with_process_handle(self.reload.process.clone());
+        }
+
         #[cfg(feature = "journal")]
         {
             for trigger in self.wasi.snapshot_on.iter().cloned() {
@@ -587,11 +769,39 @@ impl Run {
         runner.run_wasm(runtime, &program_name, module, module_hash)
     }
 
+    /// The layer that prints the syscalls with `--strace`, which is set up
+    /// with the logging
+    pub(crate) fn strace_layer(
+        &self,
+    ) -> Result<
+        Option<Box<dyn tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync>>,
+        Error,
+    > {
+        self.strace.layer()
+    }
+
+    fn install_coredumps(&self) -> Result<(), Error> {
+        if self.coredump_on_trap.is_none() && self.coredump_on_signal.is_none() {
+            return Ok(());
+        }
+        #[cfg(feature = "coredump")]
+        return coredump::install(coredump::Config {
+            executable: self.input.to_string(),
+            on_trap: self.coredump_on_trap.clone(),
+            on_signal: self.coredump_on_signal.clone(),
+        });
+        #[cfg(not(feature = "coredump"))]
+        if self.coredump_on_signal.is_some() {
+            bail!("wasmer was built without coredump support");
+        }
+        Ok(())
+    }
+
     #[allow(unused_variables)]
     fn maybe_save_coredump(&self, e: &Error) {
         #[cfg(feature = "coredump")]
         if let Some(coredump) = &self.coredump_on_trap {
-            if let Err(e) = generate_coredump(e, self.input.to_string(), coredump) {
+            if let Err(e) = coredump::save_trace(e, &self.input.to_string(), coredump) {
                 tracing::warn!(
                     error = &*e as &dyn std::error::Error,
                     coredump_path=%coredump.display(),
@@ -633,6 +843,13 @@ impl Run {
             entrypoint: Some(original_executable.to_string()),
             invoke: None,
             coredump_on_trap: None,
+            coredump_on_signal: None,
+            gdb_listen: None,
+            profile: None,
+            profile_format: None,
+            strace: StraceOptions::default(),
+            watch: false,
+            reload: watch::Reload::default(),
             input: PackageSource::infer(executable)?,
             args: args.to_vec(),
             hash_algorithm: None,
@@ -671,7 +888,7 @@ fn invoke_function(
     Ok(return_values)
 }
 
-fn parse_value(s: &str, ty: wasmer_types::Type) -> Result<Value, Error> {
+pub(super) fn parse_value(s: &str, ty: wasmer_types::Type) -> Result<Value, Error> {
     let value = match ty {
         Type::I32 => Value::I32(s.parse()?),
         Type::I64 => Value::I64(s.parse()?),
@@ -879,46 +1096,6 @@ impl ExecutableTarget {
     }
 }
 
-#[cfg(feature = "coredump")]
-fn generate_coredump(err: &Error, source_name: String, coredump_path: &Path) -> Result<(), Error> {
-    let err: &wasmer::RuntimeError = match err.downcast_ref() {
-        Some(e) => e,
-        None => {
-            log::warn!("no runtime error found to generate coredump with");
-            return Ok(());
-        }
-    };
-
-    let mut coredump_builder =
-        wasm_coredump_builder::CoredumpBuilder::new().executable_name(&source_name);
-
-    let mut thread_builder = wasm_coredump_builder::ThreadBuilder::new().thread_name("main");
-
-    for frame in err.trace() {
-        let coredump_frame = wasm_coredump_builder::FrameBuilder::new()
-            .codeoffset(frame.func_offset() as u32)
-            .funcidx(frame.func_index())
-            .build();
-        thread_builder.add_frame(coredump_frame);
-    }
-
-    coredump_builder.add_thread(thread_builder.build());
-
-    let coredump = coredump_builder
-        .serialize()
-        .map_err(Error::msg)
-        .context("Coredump serializing failed")?;
-
-    std::fs::write(coredump_path, &coredump).with_context(|| {
-        format!(
-            "Unable to save the coredump to \"{}\"",
-            coredump_path.display()
-        )
-    })?;
-
-    Ok(())
-}
-
 #[derive(Debug, Clone, Parser)]
 pub(crate) struct WcgiOptions {
     /// The address to serve on.
@@ -965,6 +1142,31 @@ impl wasmer_wasix::runners::wcgi::Callbacks for Callbacks {
     }
 }
 
+fn join(thread: std::thread::ScopedJoinHandle<'_, Result<(), Error>>) -> Result<(), Error> {
+    thread
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// `--watch` stops the running program through its WASIX process, which
+/// modules that don't import WASI don't have
+fn check_watchable(target: &ExecutableTarget) -> Result<(), Error> {
+    if let ExecutableTarget::WebAssembly { module, .. } = target {
+        if !wasmer_wasix::is_wasi_module(module) && !wasmer_wasix::is_wasix_module(module) {
+            bail!("--watch can only run WASI and WASIX modules");
+        }
+    }
+    Ok(())
+}
+
+/// Prints why the program stopped in `--watch` mode, which keeps running
+fn report_watched_error(error: Error) {
+    match error.chain().find_map(get_exit_code) {
+        Some(exit_code) => eprintln!("The program exited with status {}", exit_code.raw()),
+        None => eprintln!("{:?}", PrettyError::new(error)),
+    }
+}
+
 /// Exit the current process, using the WASI exit code if the error contains
 /// one.
 fn exit_with_wasi_exit_code(result: Result<(), Error>) -> ! {
@@ -985,9 +1187,29 @@ fn exit_with_wasi_exit_code(result: Result<(), Error>) -> ! {
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
 
+    profiler::finish();
+    #[cfg(all(feature = "sys", target_os = "linux", target_arch = "x86_64"))]
+    gdbstub::notify_exit(exit_code);
+
     std::process::exit(exit_code);
 }
 
+/// Starts the GDB remote stub for `module`, which was loaded from `path`
+#[cfg(all(feature = "sys", target_os = "linux", target_arch = "x86_64"))]
+fn listen_for_debugger(addr: SocketAddr, path: &Path, module: &Module) -> Result<(), Error> {
+    let wasm =
+        std::fs::read(path).with_context(|| format!("Unable to read \"{}\"", path.display()))?;
+    if !wasm.starts_with(b"\0asm") {
+        bail!("--gdb-listen needs a WebAssembly file, not a precompiled module");
+    }
+    gdbstub::listen(addr, module, wasm)
+}
+
+#[cfg(not(all(feature = "sys", target_os = "linux", target_arch = "x86_64")))]
+fn listen_for_debugger(_addr: SocketAddr, _path: &Path, _module: &Module) -> Result<(), Error> {
+    bail!("--gdb-listen is only supported on x86_64 Linux with the native engines")
+}
+
 fn get_exit_code(
     error: &(dyn std::error::Error + 'static),
 ) -> Option<wasmer_wasix::types::wasi::ExitCode> {
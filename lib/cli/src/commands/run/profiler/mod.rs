@@ -0,0 +1,128 @@
+//! A sampling profiler for `wasmer run --profile`.
+//!
+//! A timer interrupts the process at a fixed rate of CPU time and the
+//! signal handler records the return addresses on the stack of the
+//! interrupted thread, along with the WASIX process and thread that it
+//! runs. The addresses are mapped to WebAssembly functions afterwards with
+//! the frame info of the compiled modules, which take their names from the
+//! name section.
+
+mod output;
+#[cfg(all(
+    feature = "sys",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod sampler;
+
+#[cfg(not(all(
+    feature = "sys",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+mod sampler {
+    use super::*;
+
+    pub(super) fn start(_interval: Duration) -> anyhow::Result<()> {
+        anyhow::bail!(
+            "--profile is only supported on x86-64 and AArch64 Linux with the native engines"
+        )
+    }
+
+    pub(super) fn stop() -> Profile {
+        unreachable!("the profiler can't be started")
+    }
+}
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use indexmap::IndexSet;
+
+/// Time between two samples of a thread that keeps running
+const INTERVAL: Duration = Duration::from_millis(1);
+
+static OUTPUT: Mutex<Option<(PathBuf, ProfileFormat)>> = Mutex::new(None);
+
+/// The formats a profile can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileFormat {
+    /// Collapsed stacks, as read by `flamegraph.pl` and `inferno`
+    Collapsed,
+    /// A gzipped pprof protobuf
+    Pprof,
+    /// The JSON format of the Firefox profiler
+    Firefox,
+}
+
+impl ProfileFormat {
+    /// Guesses the format from the extension of the output file
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ProfileFormat::Firefox,
+            Some("pb" | "pprof" | "gz") => ProfileFormat::Pprof,
+            _ => ProfileFormat::Collapsed,
+        }
+    }
+}
+
+/// A WebAssembly function that shows up in the samples
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Frame {
+    module: String,
+    func_index: u32,
+    name: String,
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    /// WASIX process and thread ids, when the module runs under WASIX
+    thread: Option<(u32, u32)>,
+    /// Time since the profiler started
+    time: Duration,
+    /// Indices in [`Profile::frames`], the outermost function first
+    stack: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Profile {
+    frames: IndexSet<Frame>,
+    samples: Vec<Sample>,
+    start: SystemTime,
+    duration: Duration,
+}
+
+/// Starts sampling the threads that run WebAssembly code, the profile is written to `path`
+/// by [`finish`]
+pub(crate) fn start(path: PathBuf, format: Option<ProfileFormat>) -> anyhow::Result<()> {
+    let format = format.unwrap_or_else(|| ProfileFormat::from_path(&path));
+    sampler::start(INTERVAL)?;
+    *OUTPUT.lock().unwrap() = Some((path, format));
+    Ok(())
+}
+
+/// Stops the profiler, if it was started, and writes the profile
+pub(crate) fn finish() {
+    let Some((path, format)) = OUTPUT.lock().unwrap().take() else {
+        return;
+    };
+    let profile = sampler::stop();
+    if profile.samples.is_empty() {
+        eprintln!("warning: the profiler didn't collect any sample of WebAssembly code");
+    }
+    let result = File::create(&path)
+        .map(BufWriter::new)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut file| output::write(&profile, format, INTERVAL, &mut file))
+        .with_context(|| format!("Unable to write the profile to \"{}\"", path.display()));
+    if let Err(error) = result {
+        eprintln!("{error:?}");
+    }
+}
@@ -0,0 +1,356 @@
+//! Writers of the profile formats.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use indexmap::IndexSet;
+use serde_json::{json, Value};
+
+use super::{Profile, ProfileFormat};
+
+pub(super) fn write(
+    profile: &Profile,
+    format: ProfileFormat,
+    interval: Duration,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    match format {
+        ProfileFormat::Collapsed => write_collapsed(profile, out)?,
+        ProfileFormat::Pprof => {
+            let mut encoder = GzEncoder::new(out, Compression::default());
+            encoder.write_all(&encode_pprof(profile, interval))?;
+            encoder.finish()?.flush()?;
+        }
+        ProfileFormat::Firefox => {
+            serde_json::to_writer(&mut *out, &firefox_profile(profile, interval))?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// One line per distinct stack, rooted at the WASIX process and thread,
+/// followed by the number of samples of that stack
+fn write_collapsed(profile: &Profile, out: &mut impl Write) -> std::io::Result<()> {
+    let mut stacks = BTreeMap::<String, usize>::new();
+    for sample in &profile.samples {
+        let mut stack: Vec<String> = match sample.thread {
+            Some((pid, tid)) => vec![format!("pid {pid}"), format!("tid {tid}")],
+            None => Vec::new(),
+        };
+        stack.extend(
+            sample
+                .stack
+                .iter()
+                .map(|frame| profile.frames[*frame].name.replace([';', '\n'], "_")),
+        );
+        let stack = stack.join(";");
+        *stacks.entry(stack).or_default() += 1;
+    }
+    for (stack, count) in stacks {
+        writeln!(out, "{stack} {count}")?;
+    }
+    out.flush()
+}
+
+/// A protobuf message, written field by field
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn uint(&mut self, field: u32, value: u64) -> &mut Self {
+        self.varint((field as u64) << 3);
+        self.varint(value);
+        self
+    }
+
+    fn bytes(&mut self, field: u32, bytes: &[u8]) -> &mut Self {
+        self.varint(((field as u64) << 3) | 2);
+        self.varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn message(&mut self, field: u32, message: &Message) -> &mut Self {
+        self.bytes(field, &message.0)
+    }
+
+    fn packed(&mut self, field: u32, values: impl IntoIterator<Item = u64>) -> &mut Self {
+        let mut packed = Message::default();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(field, &packed.0)
+    }
+}
+
+/// Encodes the `Profile` message of pprof, see `profile.proto` in
+/// <https://github.com/google/pprof>
+fn encode_pprof(profile: &Profile, interval: Duration) -> Vec<u8> {
+    let mut strings = IndexSet::new();
+    let mut string = |text: &str| strings.insert_full(text.to_string()).0 as u64;
+    string("");
+
+    let value_type = |kind: u64, unit: u64| {
+        let mut message = Message::default();
+        message.uint(1, kind).uint(2, unit);
+        message
+    };
+    let interval_nanos = interval.as_nanos() as u64;
+
+    let mut encoded = Message::default();
+    encoded.message(1, &value_type(string("samples"), string("count")));
+    encoded.message(1, &value_type(string("cpu"), string("nanoseconds")));
+
+    let (pid, tid) = (string("pid"), string("tid"));
+    for sample in &profile.samples {
+        let mut message = Message::default();
+        // Locations are numbered from 1, the leaf comes first
+        message.packed(1, sample.stack.iter().rev().map(|frame| *frame as u64 + 1));
+        message.packed(2, [1, interval_nanos]);
+        if let Some((pid_value, tid_value)) = sample.thread {
+            for (key, value) in [(pid, pid_value), (tid, tid_value)] {
+                let mut label = Message::default();
+                label.uint(1, key).uint(3, value as u64);
+                message.message(3, &label);
+            }
+        }
+        encoded.message(2, &message);
+    }
+
+    for (index, frame) in profile.frames.iter().enumerate() {
+        let id = index as u64 + 1;
+        let mut line = Message::default();
+        line.uint(1, id);
+        let mut location = Message::default();
+        location.uint(1, id).message(4, &line);
+        encoded.message(4, &location);
+
+        let name = string(&frame.name);
+        let mut function = Message::default();
+        function
+            .uint(1, id)
+            .uint(2, name)
+            .uint(3, name)
+            .uint(4, string(&frame.module));
+        encoded.message(5, &function);
+    }
+
+    let start = profile
+        .start
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let period_type = value_type(string("cpu"), string("nanoseconds"));
+    for text in &strings {
+        encoded.bytes(6, text.as_bytes());
+    }
+    encoded
+        .uint(9, start)
+        .uint(10, profile.duration.as_nanos() as u64)
+        .message(11, &period_type)
+        .uint(12, interval_nanos);
+    encoded.0
+}
+
+/// Builds a profile in the Gecko format, which the Firefox profiler
+/// imports, with one thread per WASIX thread
+fn firefox_profile(profile: &Profile, interval: Duration) -> Value {
+    let mut threads = BTreeMap::<Option<(u32, u32)>, Vec<&super::Sample>>::new();
+    for sample in &profile.samples {
+        threads.entry(sample.thread).or_default().push(sample);
+    }
+
+    let threads: Vec<Value> = threads
+        .into_iter()
+        .map(|(thread, samples)| {
+            let mut strings = IndexSet::new();
+            let mut frames = IndexSet::new();
+            let mut stacks = IndexSet::<(Option<usize>, usize)>::new();
+            let mut data = Vec::new();
+            for sample in samples {
+                let mut prefix = None;
+                for frame in &sample.stack {
+                    let name = strings.insert_full(&profile.frames[*frame].name).0;
+                    let frame = frames.insert_full(name).0;
+                    prefix = Some(stacks.insert_full((prefix, frame)).0);
+                }
+                data.push(json!([prefix, sample.time.as_secs_f64() * 1000.0, 0]));
+            }
+
+            let (pid, tid) = thread.unwrap_or_default();
+            let (name, process_name) = match thread {
+                Some((pid, tid)) => (
+                    format!("WASIX thread {tid}"),
+                    format!("WASIX process {pid}"),
+                ),
+                None => ("main".to_string(), "wasmer".to_string()),
+            };
+            json!({
+                "name": name,
+                "processType": "default",
+                "processName": process_name,
+                "pid": pid,
+                "tid": tid,
+                "registerTime": 0,
+                "unregisterTime": null,
+                "samples": {
+                    "schema": { "stack": 0, "time": 1, "eventDelay": 2 },
+                    "data": data,
+                },
+                "markers": {
+                    "schema": {
+                        "name": 0, "startTime": 1, "endTime": 2,
+                        "phase": 3, "category": 4, "data": 5,
+                    },
+                    "data": [],
+                },
+                "stackTable": {
+                    "schema": { "prefix": 0, "frame": 1 },
+                    "data": stacks.iter().map(|(prefix, frame)| json!([prefix, frame])).collect::<Vec<_>>(),
+                },
+                "frameTable": {
+                    "schema": {
+                        "location": 0, "relevantForJS": 1, "innerWindowID": 2,
+                        "implementation": 3, "optimizations": 4, "line": 5,
+                        "column": 6, "category": 7, "subcategory": 8,
+                    },
+                    "data": frames
+                        .iter()
+                        .map(|name| json!([name, false, 0, null, null, null, null, 0, 0]))
+                        .collect::<Vec<_>>(),
+                },
+                "stringTable": strings.iter().collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let start = profile.start.duration_since(UNIX_EPOCH).unwrap_or_default();
+    json!({
+        "meta": {
+            "version": 24,
+            "interval": interval.as_secs_f64() * 1000.0,
+            "startTime": start.as_secs_f64() * 1000.0,
+            "shutdownTime": null,
+            "processType": 0,
+            "product": "wasmer",
+            "stackwalk": 1,
+            "debug": 0,
+            "gcpoison": 0,
+            "asyncstack": 0,
+            "presymbolicated": true,
+            "categories": [
+                { "name": "WebAssembly", "color": "blue", "subcategories": ["Other"] },
+            ],
+            "markerSchema": [],
+        },
+        "libs": [],
+        "threads": threads,
+        "processes": [],
+        "pausedRanges": [],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use super::*;
+    use crate::commands::run::profiler::{Frame, Sample};
+
+    fn profile() -> Profile {
+        let frame = |func_index: u32, name: &str| Frame {
+            module: "main".to_string(),
+            func_index,
+            name: name.to_string(),
+        };
+        let sample = |tid: u32, stack: Vec<usize>| Sample {
+            thread: Some((1, tid)),
+            time: Duration::from_millis(stack.len() as u64),
+            stack,
+        };
+        Profile {
+            frames: [frame(0, "_start"), frame(1, "main"), frame(2, "fib")]
+                .into_iter()
+                .collect(),
+            samples: vec![
+                sample(1, vec![0, 1, 2]),
+                sample(1, vec![0, 1]),
+                sample(1, vec![0, 1, 2]),
+                sample(2, vec![2]),
+            ],
+            start: SystemTime::now(),
+            duration: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn collapsed_stacks_are_counted_per_thread() {
+        let mut out = Vec::new();
+        write_collapsed(&profile(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "pid 1;tid 1;_start;main 1\npid 1;tid 1;_start;main;fib 2\npid 1;tid 2;fib 1\n"
+        );
+    }
+
+    #[test]
+    fn pprof_has_a_sample_per_stack_and_a_function_per_frame() {
+        let encoded = encode_pprof(&profile(), Duration::from_millis(1));
+
+        let mut fields = BTreeMap::<u64, usize>::new();
+        let mut bytes = encoded.as_slice();
+        let mut varint = |bytes: &mut &[u8]| {
+            let mut value = 0u64;
+            for shift in (0..).step_by(7) {
+                let byte = bytes[0];
+                *bytes = &bytes[1..];
+                value |= ((byte & 0x7f) as u64) << shift;
+                if byte < 0x80 {
+                    break;
+                }
+            }
+            value
+        };
+        while !bytes.is_empty() {
+            let key = varint(&mut bytes);
+            if key & 7 == 2 {
+                let len = varint(&mut bytes) as usize;
+                bytes = &bytes[len..];
+            } else {
+                varint(&mut bytes);
+            }
+            *fields.entry(key >> 3).or_default() += 1;
+        }
+        assert_eq!(fields[&1], 2, "sample types");
+        assert_eq!(fields[&2], 4, "samples");
+        assert_eq!(fields[&4], 3, "locations");
+        assert_eq!(fields[&5], 3, "functions");
+    }
+
+    #[test]
+    fn firefox_threads_share_stack_prefixes() {
+        let profile = firefox_profile(&profile(), Duration::from_millis(1));
+        let threads = profile["threads"].as_array().unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0]["tid"], 1);
+        assert_eq!(
+            threads[0]["stackTable"]["data"],
+            json!([[null, 0], [0, 1], [1, 2]])
+        );
+        assert_eq!(threads[0]["samples"]["data"][1][0], 1);
+        assert_eq!(threads[0]["stringTable"], json!(["_start", "main", "fib"]));
+    }
+}
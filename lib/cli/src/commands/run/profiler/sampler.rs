@@ -0,0 +1,406 @@
+//! Sampling of the threads with `SIGPROF`.
+//!
+//! The handler can interrupt any code, so it only uses atomics, const
+//! initialised thread locals and async-signal-safe system calls: it
+//! follows the frame pointers from the registers of the interrupted thread
+//! into a fixed-size sample and claims a slot of a static ring for it,
+//! dropping the sample when the slot is still in use. The frames are read
+//! with `process_vm_readv`, which fails rather than faulting when a frame
+//! pointer is bogus, since host code doesn't have to keep them. A collector
+//! thread empties the ring and maps the addresses to functions.
+
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+
+use indexmap::IndexSet;
+use wasmer_compiler::FRAME_INFO;
+use wasmer_wasix::os::task::thread::current_thread_ids;
+
+use super::{Frame, Profile, Sample};
+
+/// Deepest stack that is recorded, the outermost frames are dropped
+const MAX_DEPTH: usize = 64;
+/// Samples that the ring holds until the collector empties it
+const RING_CAPACITY: usize = 4096;
+/// How often the collector empties the ring
+const COLLECT_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy)]
+struct RawSample {
+    /// WASIX process and thread ids
+    thread: Option<(u32, u32)>,
+    /// Value of the monotonic clock, in nanoseconds
+    time: u64,
+    depth: usize,
+    /// Return addresses, the innermost first
+    addresses: [usize; MAX_DEPTH],
+}
+
+impl RawSample {
+    const EMPTY: RawSample = RawSample {
+        thread: None,
+        time: 0,
+        depth: 0,
+        addresses: [0; MAX_DEPTH],
+    };
+}
+
+/// The slot is free
+const SLOT_EMPTY: u8 = 0;
+/// A handler is writing a sample into the slot
+const SLOT_WRITING: u8 = 1;
+/// The slot holds a sample that wasn't collected yet
+const SLOT_FULL: u8 = 2;
+/// The collector is copying the sample out of the slot
+const SLOT_READING: u8 = 3;
+
+struct Slot {
+    state: AtomicU8,
+    sample: UnsafeCell<RawSample>,
+}
+
+/// A fixed number of slots that handlers on any thread put samples into
+/// and that the collector takes them out of, without locks.
+///
+/// Whoever moves a slot out of [`SLOT_EMPTY`] (a handler) or out of
+/// [`SLOT_FULL`] (the collector) has exclusive access to its sample until
+/// it stores the next state.
+struct Ring {
+    next: AtomicUsize,
+    slots: [Slot; RING_CAPACITY],
+}
+
+// Safety: a sample is only accessed by whoever claimed its slot
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const SLOT: Slot = Slot {
+            state: AtomicU8::new(SLOT_EMPTY),
+            sample: UnsafeCell::new(RawSample::EMPTY),
+        };
+        Ring {
+            next: AtomicUsize::new(0),
+            slots: [SLOT; RING_CAPACITY],
+        }
+    }
+
+    /// Stores a sample, unless the next slot wasn't collected yet. This is
+    /// async-signal-safe.
+    fn push(&self, sample: &RawSample) -> bool {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % RING_CAPACITY;
+        let slot = &self.slots[index];
+        if slot
+            .state
+            .compare_exchange(
+                SLOT_EMPTY,
+                SLOT_WRITING,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+        // Safety: the slot was claimed above
+        unsafe { *slot.sample.get() = *sample };
+        slot.state.store(SLOT_FULL, Ordering::Release);
+        true
+    }
+
+    /// Takes every sample out of the ring, the oldest first
+    fn drain(&self) -> Vec<RawSample> {
+        let mut samples = Vec::new();
+        for slot in &self.slots {
+            if slot
+                .state
+                .compare_exchange(
+                    SLOT_FULL,
+                    SLOT_READING,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                // Safety: the slot was claimed above
+                samples.push(unsafe { *slot.sample.get() });
+                slot.state.store(SLOT_EMPTY, Ordering::Release);
+            }
+        }
+        samples.sort_by_key(|sample| sample.time);
+        samples
+    }
+}
+
+extern "C" {
+    // Not bound by `libc` on every platform
+    fn setitimer(
+        which: libc::c_int,
+        new_value: *const libc::itimerval,
+        old_value: *mut libc::itimerval,
+    ) -> libc::c_int;
+}
+
+/// Samples waiting for the collector. It is static so that nothing has to
+/// be allocated or freed while a handler might be running.
+static RING: Ring = Ring::new();
+static START: AtomicU64 = AtomicU64::new(0);
+static STOPPED: AtomicBool = AtomicBool::new(false);
+static COLLECTOR: Mutex<Option<JoinHandle<Profile>>> = Mutex::new(None);
+
+fn monotonic_nanos() -> u64 {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Safety: `clock_gettime` is async-signal-safe and only writes `now`
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    now.tv_sec as u64 * 1_000_000_000 + now.tv_nsec as u64
+}
+
+/// Installs the handler and starts the timer
+pub(super) fn start(interval: Duration) -> anyhow::Result<()> {
+    let mut collector = COLLECTOR.lock().unwrap();
+    if collector.is_some() {
+        anyhow::bail!("the profiler is already running");
+    }
+    // Only threads that run WebAssembly are sampled
+    wasmer_vm::track_current_vmctx();
+    RING.drain();
+    START.store(monotonic_nanos(), Ordering::SeqCst);
+    STOPPED.store(false, Ordering::SeqCst);
+
+    // Safety: the handler only touches memory that was set up above
+    unsafe {
+        let mut handler: libc::sigaction = std::mem::zeroed();
+        handler.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        handler.sa_sigaction = on_sigprof as usize;
+        libc::sigemptyset(&mut handler.sa_mask);
+        if libc::sigaction(libc::SIGPROF, &handler, std::ptr::null_mut()) != 0 {
+            anyhow::bail!(
+                "unable to install the SIGPROF handler: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+        set_timer(interval)?;
+    }
+
+    let start = SystemTime::now();
+    *collector = Some(std::thread::spawn(move || {
+        let mut profile = Profile {
+            frames: IndexSet::new(),
+            samples: Vec::new(),
+            start,
+            duration: Duration::ZERO,
+        };
+        while !STOPPED.load(Ordering::SeqCst) {
+            std::thread::sleep(COLLECT_INTERVAL);
+            collect(&mut profile);
+        }
+        collect(&mut profile);
+        profile.duration = start.elapsed().unwrap_or_default();
+        profile
+    }));
+    Ok(())
+}
+
+/// Stops the timer and returns the samples collected so far
+pub(super) fn stop() -> Profile {
+    // Safety: disarming the timer has no other effect
+    let _ = unsafe { set_timer(Duration::ZERO) };
+    STOPPED.store(true, Ordering::SeqCst);
+    COLLECTOR
+        .lock()
+        .unwrap()
+        .take()
+        .expect("the profiler was started")
+        .join()
+        .expect("the profile collector panicked")
+}
+
+/// Arms the CPU time timer of the process, or disarms it with a zero
+/// interval
+unsafe fn set_timer(interval: Duration) -> anyhow::Result<()> {
+    let interval = libc::timeval {
+        tv_sec: interval.as_secs() as _,
+        tv_usec: interval.subsec_micros() as _,
+    };
+    let timer = libc::itimerval {
+        it_interval: interval,
+        it_value: interval,
+    };
+    if setitimer(libc::ITIMER_PROF, &timer, std::ptr::null_mut()) != 0 {
+        anyhow::bail!(
+            "unable to set the profiling timer: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    Ok(())
+}
+
+/// Maps the addresses of the samples in the ring to functions
+fn collect(profile: &mut Profile) {
+    let raw_samples = RING.drain();
+
+    let start = START.load(Ordering::SeqCst);
+    let frame_info = FRAME_INFO.read().unwrap();
+    for raw in raw_samples {
+        // Return addresses point after the call, the one of the
+        // interrupted frame is an instruction that didn't run yet, and
+        // either way the instruction before belongs to the same function
+        let mut stack: Vec<usize> = raw.addresses[..raw.depth]
+            .iter()
+            .filter_map(|address| frame_info.lookup_frame_info(address.wrapping_sub(1)))
+            .map(|info| {
+                let func_index = info.func_index();
+                let frame = Frame {
+                    module: info.module_name().to_string(),
+                    func_index,
+                    name: info
+                        .function_name()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("wasm-function[{func_index}]")),
+                };
+                profile.frames.insert_full(frame).0
+            })
+            .collect();
+        if stack.is_empty() {
+            continue;
+        }
+        stack.reverse();
+        profile.samples.push(Sample {
+            thread: raw.thread,
+            time: Duration::from_nanos(raw.time.saturating_sub(start)),
+            stack,
+        });
+    }
+}
+
+/// Program counter, frame pointer and stack pointer of the interrupted
+/// thread
+fn interrupted_registers(context: *mut libc::c_void) -> (usize, usize, usize) {
+    let context = context as *const libc::ucontext_t;
+    // Safety: the kernel passes the context of the interrupted thread
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let gregs = &(*context).uc_mcontext.gregs;
+            (
+                gregs[libc::REG_RIP as usize] as usize,
+                gregs[libc::REG_RBP as usize] as usize,
+                gregs[libc::REG_RSP as usize] as usize,
+            )
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let mcontext = &(*context).uc_mcontext;
+            (
+                mcontext.pc as usize,
+                mcontext.regs[29] as usize,
+                mcontext.sp as usize,
+            )
+        }
+    }
+}
+
+/// Reads the frame record at `fp`, which holds the frame pointer of the
+/// caller followed by the return address on both x86-64 and AArch64
+fn read_frame_record(fp: usize) -> Option<(usize, usize)> {
+    let mut record = [0usize; 2];
+    let len = std::mem::size_of_val(&record);
+    let local = libc::iovec {
+        iov_base: record.as_mut_ptr().cast(),
+        iov_len: len,
+    };
+    let remote = libc::iovec {
+        iov_base: fp as *mut libc::c_void,
+        iov_len: len,
+    };
+    // Safety: the kernel checks the remote range and only writes `record`
+    let read = unsafe { libc::process_vm_readv(libc::getpid(), &local, 1, &remote, 1, 0) };
+    (read == len as isize).then_some((record[0], record[1]))
+}
+
+extern "C" fn on_sigprof(
+    _signum: libc::c_int,
+    _siginfo: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    if wasmer_vm::current_vmctx().is_none() {
+        return;
+    }
+    let (pc, mut fp, sp) = interrupted_registers(context);
+    let mut sample = RawSample {
+        thread: current_thread_ids().map(|(pid, tid)| (pid.raw(), tid.raw())),
+        time: monotonic_nanos(),
+        depth: 1,
+        addresses: [0; MAX_DEPTH],
+    };
+    sample.addresses[0] = pc;
+
+    // Callers are further up the stack, the walk stops at the first frame
+    // pointer that goes down or can't be read
+    let mut floor = sp;
+    while sample.depth < MAX_DEPTH && fp >= floor && fp % std::mem::align_of::<usize>() == 0 {
+        let Some((caller_fp, return_address)) = read_frame_record(fp) else {
+            break;
+        };
+        if return_address == 0 {
+            break;
+        }
+        sample.addresses[sample.depth] = return_address;
+        sample.depth += 1;
+        floor = fp + 1;
+        fp = caller_fp;
+    }
+    RING.push(&sample);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_records_are_read_without_faulting() {
+        let record = [0x1000usize, 0x2000];
+        assert_eq!(
+            read_frame_record(record.as_ptr() as usize),
+            Some((0x1000, 0x2000))
+        );
+        assert_eq!(read_frame_record(0), None);
+        assert_eq!(read_frame_record(usize::MAX - 8), None);
+    }
+
+    #[test]
+    fn ring_keeps_samples_until_collected() {
+        let sample = |time| RawSample {
+            time,
+            depth: 1,
+            ..RawSample::EMPTY
+        };
+        RING.drain();
+
+        for time in [3, 1, 2] {
+            assert!(RING.push(&sample(time)));
+        }
+        let times: Vec<u64> = RING.drain().iter().map(|s| s.time).collect();
+        assert_eq!(times, [1, 2, 3]);
+
+        // Samples are dropped rather than overwritten once the ring is full
+        for time in 0..RING_CAPACITY as u64 {
+            assert!(RING.push(&sample(time)));
+        }
+        assert!(!RING.push(&sample(u64::MAX)));
+        assert_eq!(RING.drain().len(), RING_CAPACITY);
+        assert!(RING.push(&sample(0)));
+        assert_eq!(RING.drain().len(), 1);
+    }
+}
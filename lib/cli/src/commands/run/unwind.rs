@@ -0,0 +1,266 @@
+//! Walking the stack of the calling thread with the registers of each frame.
+//!
+//! The compilers record where the locals of a function live as DWARF
+//! registers and offsets from the canonical frame address (CFA) of its
+//! frame. The unwinder of libgcc gives both for every frame. Only the
+//! callee-saved registers keep their value across a call, so they are
+//! the only ones read from the frames that are waiting for a call to
+//! return. The frame that a signal interrupted has all of its registers
+//! in the context of the signal instead.
+
+use std::ffi::{c_int, c_void};
+
+use wasmer_compiler::types::address_map::ValueLocation;
+use wasmer_types::Type;
+
+/// DWARF numbers of the registers that a call preserves
+#[cfg(target_arch = "x86_64")]
+const CALLEE_SAVED: [u16; 6] = [3, 6, 12, 13, 14, 15];
+#[cfg(target_arch = "aarch64")]
+const CALLEE_SAVED: [u16; 11] = [19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29];
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const CALLEE_SAVED: [u16; 0] = [];
+
+/// General purpose registers in the context of a signal, by DWARF number
+#[cfg(target_arch = "x86_64")]
+const GENERAL: usize = 17;
+#[cfg(target_arch = "aarch64")]
+const GENERAL: usize = 32;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const GENERAL: usize = 0;
+
+/// DWARF number of the first vector register
+#[cfg(target_arch = "x86_64")]
+const FIRST_VECTOR: usize = 17;
+#[cfg(target_arch = "aarch64")]
+const FIRST_VECTOR: usize = 64;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const FIRST_VECTOR: usize = 0;
+
+/// Vector registers in the context of a signal
+const VECTORS: usize = 16;
+
+/// `_Unwind_Reason_Code` values
+const URC_NO_REASON: c_int = 0;
+const URC_FAILURE: c_int = 9;
+
+#[repr(C)]
+struct UnwindContext {
+    _private: [u8; 0],
+}
+
+type TraceFn = extern "C" fn(*mut UnwindContext, *mut c_void) -> c_int;
+
+extern "C" {
+    fn _Unwind_Backtrace(trace: TraceFn, arg: *mut c_void) -> c_int;
+    fn _Unwind_GetIPInfo(context: *mut UnwindContext, ip_before_insn: *mut c_int) -> usize;
+    fn _Unwind_GetCFA(context: *mut UnwindContext) -> usize;
+    fn _Unwind_GetGR(context: *mut UnwindContext, index: c_int) -> usize;
+}
+
+/// A frame of the stack, as seen by the unwinder
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Frame {
+    /// The return address, or the address of the interrupted instruction
+    /// in the frame that a signal interrupted
+    pub ip: usize,
+    /// Set for the frames that a signal interrupted
+    pub interrupted: bool,
+    /// Set for the innermost of them, which the signal being handled
+    /// interrupted and whose registers are in the context of that signal
+    innermost_signal: bool,
+    /// Canonical frame address, unknown for the outermost frame
+    cfa: Option<usize>,
+    /// Values of [`CALLEE_SAVED`]
+    saved: [u64; CALLEE_SAVED.len()],
+}
+
+impl Frame {
+    /// Reads the `size` low bytes of the value at `location`, `registers`
+    /// are those of the context of the signal being handled
+    ///
+    /// # Safety
+    ///
+    /// The thread that the frame belongs to must be stopped, with the
+    /// frame still on its stack.
+    pub unsafe fn read(
+        &self,
+        location: ValueLocation,
+        size: usize,
+        registers: Option<&Registers>,
+    ) -> Option<Vec<u8>> {
+        let bits = match location {
+            ValueLocation::FrameOffset(offset) => {
+                let address = (self.cfa? as i64).checked_add(offset)? as usize;
+                let mut bytes = [0; 16];
+                std::ptr::copy_nonoverlapping(address as *const u8, bytes.as_mut_ptr(), size);
+                u128::from_le_bytes(bytes)
+            }
+            ValueLocation::Register(reg) => match registers {
+                Some(registers) if self.innermost_signal => registers.get(reg)?,
+                _ => {
+                    let index = CALLEE_SAVED.iter().position(|saved| *saved == reg)?;
+                    self.saved[index] as u128
+                }
+            },
+        };
+        Some(bits.to_le_bytes()[..size].to_vec())
+    }
+}
+
+/// The registers of a thread when a signal interrupted it
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Registers {
+    general: [u64; GENERAL],
+    vectors: Option<[u128; VECTORS]>,
+}
+
+impl Registers {
+    /// Reads the registers from the context of a signal, only the general
+    /// purpose ones of x86_64 and aarch64 and the vector ones of x86_64
+    /// are known
+    ///
+    /// # Safety
+    ///
+    /// `context` is the `ucontext_t` that a `SA_SIGINFO` handler was given.
+    pub unsafe fn from_context(context: *const c_void) -> Self {
+        let context = &*(context as *const libc::ucontext_t);
+        #[cfg(target_arch = "x86_64")]
+        {
+            let gregs = &context.uc_mcontext.gregs;
+            let general = [
+                libc::REG_RAX,
+                libc::REG_RDX,
+                libc::REG_RCX,
+                libc::REG_RBX,
+                libc::REG_RSI,
+                libc::REG_RDI,
+                libc::REG_RBP,
+                libc::REG_RSP,
+                libc::REG_R8,
+                libc::REG_R9,
+                libc::REG_R10,
+                libc::REG_R11,
+                libc::REG_R12,
+                libc::REG_R13,
+                libc::REG_R14,
+                libc::REG_R15,
+                libc::REG_RIP,
+            ]
+            .map(|index| gregs[index as usize] as u64);
+            let fpregs = context.uc_mcontext.fpregs;
+            let vectors = (!fpregs.is_null()).then(|| {
+                (*fpregs)._xmm.map(|xmm| {
+                    let [a, b, c, d] = xmm.element.map(u128::from);
+                    a | b << 32 | c << 64 | d << 96
+                })
+            });
+            Registers { general, vectors }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let mcontext = &context.uc_mcontext;
+            let mut general = [0; GENERAL];
+            general[..31].copy_from_slice(&mcontext.regs);
+            general[31] = mcontext.sp;
+            // The vector registers are in a record of the reserved area,
+            // which isn't looked for
+            Registers {
+                general,
+                vectors: None,
+            }
+        }
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        Registers {
+            general: [],
+            vectors: None,
+        }
+    }
+
+    fn get(&self, reg: u16) -> Option<u128> {
+        let reg = reg as usize;
+        if reg < GENERAL {
+            Some(self.general[reg] as u128)
+        } else {
+            let vectors = self.vectors.as_ref()?;
+            vectors.get(reg.checked_sub(FIRST_VECTOR)?).copied()
+        }
+    }
+}
+
+/// Size of a value of type `ty` in a register or a stack slot
+pub(super) fn value_size(ty: Type) -> usize {
+    match ty {
+        Type::I32 | Type::F32 => 4,
+        Type::I64 | Type::F64 => 8,
+        Type::V128 => 16,
+        Type::ExternRef | Type::FuncRef | Type::ExceptionRef => std::mem::size_of::<usize>(),
+    }
+}
+
+/// Walks the stack of the calling thread, the innermost frame first, until
+/// `f` returns `false`
+///
+/// # Safety
+///
+/// This may be called from a signal handler, as long as no other thread
+/// unwinds at the same time. `f` is called from the unwinder and must not
+/// unwind itself.
+pub(super) unsafe fn trace<F: FnMut(&Frame) -> bool>(f: F) {
+    struct Walk<F> {
+        f: F,
+        /// The previous frame, which gets its CFA from the next one
+        pending: Option<Frame>,
+        stopped: bool,
+        /// Whether a frame that a signal interrupted was found
+        signal_found: bool,
+    }
+
+    extern "C" fn callback<F: FnMut(&Frame) -> bool>(
+        context: *mut UnwindContext,
+        arg: *mut c_void,
+    ) -> c_int {
+        // Safety: `arg` is the walk given to `_Unwind_Backtrace`, and
+        // `context` is valid while the callback runs
+        unsafe {
+            let walk = &mut *(arg as *mut Walk<F>);
+            // The unwinder has stepped out of the previous frame, so this
+            // is the CFA of the previous frame rather than of this one
+            let cfa = _Unwind_GetCFA(context);
+            if let Some(mut frame) = walk.pending.take() {
+                frame.cfa = Some(cfa);
+                if !(walk.f)(&frame) {
+                    walk.stopped = true;
+                    return URC_FAILURE;
+                }
+            }
+            let mut ip_before_insn = 0;
+            let ip = _Unwind_GetIPInfo(context, &mut ip_before_insn);
+            if ip == 0 {
+                return URC_FAILURE;
+            }
+            let interrupted = ip_before_insn != 0;
+            walk.pending = Some(Frame {
+                ip,
+                interrupted,
+                innermost_signal: interrupted && !walk.signal_found,
+                cfa: None,
+                saved: CALLEE_SAVED.map(|reg| _Unwind_GetGR(context, reg as c_int) as u64),
+            });
+            walk.signal_found |= interrupted;
+            URC_NO_REASON
+        }
+    }
+
+    let mut walk = Walk {
+        f,
+        pending: None,
+        stopped: false,
+        signal_found: false,
+    };
+    _Unwind_Backtrace(callback::<F>, &mut walk as *mut Walk<F> as *mut c_void);
+    // The outermost frame, whose CFA isn't known
+    if let (false, Some(frame)) = (walk.stopped, walk.pending) {
+        (walk.f)(&frame);
+    }
+}
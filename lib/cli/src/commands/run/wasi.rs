@@ -8,10 +8,14 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use bytes::Bytes;
+use bytesize::ByteSize;
 use clap::Parser;
 use tokio::runtime::Handle;
 use url::Url;
-use virtual_fs::{DeviceFile, FileSystem, PassthruFileSystem, RootFileSystemBuilder};
+use virtual_fs::{
+    DeviceFile, FileSystem, PassthruFileSystem, QuotaError, QuotaFileSystem, QuotaLimits,
+    RootFileSystemBuilder,
+};
 use virtual_net::ruleset::Ruleset;
 use wasmer::{Engine, Function, Instance, Memory32, Memory64, Module, RuntimeError, Store, Value};
 use wasmer_config::package::PackageSource as PackageSpecifier;
@@ -27,10 +31,10 @@ use wasmer_wasix::{
     os::{tty_sys::SysTty, TtyBridge},
     rewind_ext,
     runners::MAPPED_CURRENT_DIR_DEFAULT_PATH,
-    runners::{MappedCommand, MappedDirectory},
+    runners::{MappedCommand, MappedDirectory, MountedDirectory},
     runtime::{
         module_cache::{FileSystemCache, ModuleCache},
-        package_loader::{BuiltinPackageLoader, PackageLoader},
+        package_loader::{BlobStore, BuiltinPackageLoader, PackageLoader},
         resolver::{
             BackendSource, FileSystemSource, InMemorySource, MultiSource, Source, WebSource,
         },
@@ -72,6 +76,16 @@ pub struct Wasi {
     )]
     pub(crate) mapped_dirs: Vec<MappedDirectory>,
 
+    /// Limit the disk space and number of files a mapped directory may use
+    ///
+    /// Limits are given as a comma-separated list containing a size (e.g.
+    /// `100MiB`), an inode count (e.g. `inodes=1000`) and, optionally,
+    /// `enospc` to report `ENOSPC` instead of `EDQUOT` once a limit is reached.
+    ///
+    /// Example: `--dir-quota /data=1GiB,inodes=10000`
+    #[clap(long = "dir-quota", name = "GUEST_DIR=LIMITS")]
+    pub(crate) dir_quotas: Vec<DirQuota>,
+
     /// Pass custom environment variables
     #[clap(
         long = "env",
@@ -300,98 +314,9 @@ impl Wasi {
                 .with_tty(Box::new(DeviceFile::new(__WASI_STDIN_FILENO)))
                 .build();
 
-            let mut mapped_dirs = Vec::new();
-
-            // Process the --dirs flag and merge it with --mapdir.
-            let mut have_current_dir = false;
-            for dir in &self.pre_opened_directories {
-                let mapping = if dir == Path::new(".") {
-                    if have_current_dir {
-                        bail!("Cannot pre-open the current directory twice: --dir=. must only be specified once");
-                    }
-                    have_current_dir = true;
-
-                    let current_dir =
-                        std::env::current_dir().context("could not determine current directory")?;
-
-                    MappedDirectory {
-                        host: current_dir,
-                        guest: MAPPED_CURRENT_DIR_DEFAULT_PATH.to_string(),
-                    }
-                } else {
-                    let resolved = dir.canonicalize().with_context(|| {
-                        format!(
-                            "could not canonicalize path for argument '--dir {}'",
-                            dir.display()
-                        )
-                    })?;
-
-                    if &resolved != dir {
-                        bail!(
-                            "Invalid argument '--dir {}': path must either be absolute, or '.'",
-                            dir.display(),
-                        );
-                    }
-
-                    let guest = resolved
-                        .to_str()
-                        .with_context(|| {
-                            format!(
-                                "invalid argument '--dir {}': path must be valid utf-8",
-                                dir.display(),
-                            )
-                        })?
-                        .to_string();
-
-                    MappedDirectory {
-                        host: resolved,
-                        guest,
-                    }
-                };
-
-                mapped_dirs.push(mapping);
-            }
-
-            for MappedDirectory { host, guest } in &self.mapped_dirs {
-                let resolved_host = host.canonicalize().with_context(|| {
-                    format!(
-                        "could not canonicalize path for argument '--mapdir {}:{}'",
-                        host.display(),
-                        guest,
-                    )
-                })?;
-
-                let mapping = if guest == "." {
-                    if have_current_dir {
-                        bail!("Cannot pre-open the current directory twice: '--mapdir=?:.' / '--dir=.' must only be specified once");
-                    }
-                    have_current_dir = true;
-
-                    MappedDirectory {
-                        host: resolved_host,
-                        guest: MAPPED_CURRENT_DIR_DEFAULT_PATH.to_string(),
-                    }
-                } else {
-                    MappedDirectory {
-                        host: resolved_host,
-                        guest: guest.clone(),
-                    }
-                };
-                mapped_dirs.push(mapping);
-            }
-
-            if !mapped_dirs.is_empty() {
-                // TODO: should we expose the common ancestor instead of root?
-                let fs_backing: Arc<dyn FileSystem + Send + Sync> =
-                    Arc::new(PassthruFileSystem::new(default_fs_backing()));
-                for MappedDirectory { host, guest } in self.mapped_dirs.clone() {
-                    let host = if !host.is_absolute() {
-                        Path::new("/").join(host)
-                    } else {
-                        host
-                    };
-                    root_fs.mount(guest.into(), &fs_backing, host)?;
-                }
+            let (have_current_dir, _, mapped_dirs) = self.build_mapped_directories()?;
+            for mounted in self.build_mounted_directories(mapped_dirs)? {
+                root_fs.mount(mounted.guest.into(), &mounted.fs, "/".into())?;
             }
 
             // Open the root of the new filesystem
@@ -560,6 +485,46 @@ impl Wasi {
         Ok((have_current_dir, is_tmp_mapped, mapped_dirs))
     }
 
+    /// Turn the mapped directories into mounts, applying any `--dir-quota`
+    /// limits along the way.
+    ///
+    /// Every `--dir-quota` must refer to one of the mapped directories.
+    pub fn build_mounted_directories(
+        &self,
+        mapped_dirs: Vec<MappedDirectory>,
+    ) -> Result<Vec<MountedDirectory>, anyhow::Error> {
+        for quota in &self.dir_quotas {
+            if !mapped_dirs.iter().any(|dir| quota.matches(&dir.guest)) {
+                bail!(
+                    "Invalid argument '--dir-quota {}': the directory is not mapped",
+                    quota.guest
+                );
+            }
+        }
+
+        mapped_dirs
+            .into_iter()
+            .map(|dir| {
+                let limits = self.dir_quota(&dir.guest);
+                let mut mounted = MountedDirectory::from(dir);
+                if let Some(limits) = limits {
+                    let fs = QuotaFileSystem::new(mounted.fs, limits).with_context(|| {
+                        format!("Unable to apply the quota for \"{}\"", mounted.guest)
+                    })?;
+                    mounted.fs = Arc::new(fs);
+                }
+                Ok(mounted)
+            })
+            .collect()
+    }
+
+    fn dir_quota(&self, guest: &str) -> Option<QuotaLimits> {
+        self.dir_quotas
+            .iter()
+            .find(|quota| quota.matches(guest))
+            .map(|quota| quota.limits)
+    }
+
     pub fn build_mapped_commands(&self) -> Result<Vec<MappedCommand>, anyhow::Error> {
         self.map_commands
             .iter()
@@ -658,7 +623,7 @@ impl Wasi {
         }
 
         if !self.no_tty {
-            let tty = Arc::new(SysTty);
+            let tty = Arc::new(SysTty::default());
             tty.reset();
             rt.set_tty(tty);
         }
@@ -723,6 +688,7 @@ impl Wasi {
 
         let loader = BuiltinPackageLoader::new()
             .with_cache_dir(checkout_dir)
+            .with_blob_store(BlobStore::new(env.cache_dir().join("blobs")))
             .with_shared_http_client(client)
             .with_tokens(tokens);
 
@@ -784,6 +750,54 @@ impl Wasi {
     }
 }
 
+/// The limits passed to `--dir-quota`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DirQuota {
+    guest: String,
+    limits: QuotaLimits,
+}
+
+impl DirQuota {
+    fn matches(&self, guest: &str) -> bool {
+        self.guest == guest || (self.guest == "." && guest == MAPPED_CURRENT_DIR_DEFAULT_PATH)
+    }
+}
+
+impl FromStr for DirQuota {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (guest, limits) = s.split_once('=').with_context(|| {
+            format!("Invalid --dir-quota flag: expected <GUEST_DIR>=<LIMITS>, got '{s}'")
+        })?;
+
+        let mut quota = DirQuota {
+            guest: guest.trim().to_string(),
+            limits: QuotaLimits::default(),
+        };
+        if quota.guest.is_empty() {
+            bail!("Invalid --dir-quota flag - the guest directory cannot be empty: '{s}'");
+        }
+
+        for limit in limits.split(',').map(|limit| limit.trim()) {
+            if limit.eq_ignore_ascii_case("enospc") {
+                quota.limits.error = QuotaError::StorageFull;
+            } else if let Some(inodes) = limit.strip_prefix("inodes=") {
+                let inodes = inodes
+                    .parse()
+                    .with_context(|| format!("Invalid inode limit '{inodes}' in '{s}'"))?;
+                quota.limits.max_inodes = Some(inodes);
+            } else {
+                let size = ByteSize::from_str(limit)
+                    .map_err(|e| anyhow::anyhow!("Invalid size '{limit}' in '{s}': {e}"))?;
+                quota.limits.max_bytes = Some(size.as_u64());
+            }
+        }
+
+        Ok(quota)
+    }
+}
+
 fn parse_registry(r: &str) -> Result<Url> {
     UserRegistry::from(r).graphql_endpoint()
 }
@@ -0,0 +1,152 @@
+//! Change detection for `wasmer run --watch`.
+//!
+//! The input is polled: the modification times and sizes of its files are
+//! compared every [`POLL`], which works the same on every platform and for
+//! files that are replaced rather than written in place by compilers.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+use wasmer_wasix::runners::{wasi::ProcessHandle, wcgi::PackageReloader};
+
+/// How often the input is checked for changes
+pub(super) const POLL: Duration = Duration::from_millis(250);
+/// A change is reported once the files stop changing for this long, so
+/// that a build is picked up once it is finished
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// What the runners register so that the program can be restarted or
+/// reloaded when its input changes
+#[derive(Debug, Clone, Default)]
+pub(super) struct Reload {
+    /// The WASIX process of the WASI runner, killed to restart it
+    pub process: ProcessHandle,
+    /// The package of the WCGI and DCGI runners, swapped without closing
+    /// the listener
+    pub server: PackageReloader,
+}
+
+/// The files of a directory, or a single file, as they were last seen
+#[derive(Debug)]
+pub(super) struct Watcher {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Watcher {
+    pub fn new(root: &Path) -> Self {
+        Watcher {
+            root: root.to_path_buf(),
+            files: scan(root),
+        }
+    }
+
+    /// Whether files were modified, added or removed since the last call
+    pub fn changed(&mut self) -> bool {
+        let mut files = scan(&self.root);
+        if files == self.files {
+            return false;
+        }
+        loop {
+            std::thread::sleep(SETTLE);
+            let next = scan(&self.root);
+            if next == files {
+                break;
+            }
+            files = next;
+        }
+        self.files = files;
+        true
+    }
+}
+
+/// The modification time and the size of the files under `root`, hidden
+/// files and directories like `.git` are skipped
+fn scan(root: &Path) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if !metadata.is_dir() {
+            files.insert(path, (metadata.modified().ok(), metadata.len()));
+            continue;
+        }
+        // Symlinked directories could make a cycle
+        let is_symlink = std::fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink());
+        if is_symlink && path != root {
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                pending.push(entry.path());
+            }
+        }
+    }
+    files
+}
+
+/// A flag that is set when Ctrl-C is pressed
+///
+/// Once this is called, Ctrl-C no longer stops `wasmer`.
+pub(super) fn interrupted() -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    let interrupted = Arc::clone(&flag);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            interrupted.store(true, Ordering::SeqCst);
+        }
+    });
+    flag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_to_the_files_are_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.wasm"), "a").unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+
+        let mut watcher = Watcher::new(dir.path());
+        assert!(!watcher.changed());
+
+        std::fs::write(dir.path().join("main.wasm"), "ab").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        std::fs::write(dir.path().join(".git").join("index"), "a").unwrap();
+        assert!(!watcher.changed());
+
+        std::fs::write(dir.path().join("wasmer.toml"), "").unwrap();
+        assert!(watcher.changed());
+        std::fs::remove_file(dir.path().join("wasmer.toml")).unwrap();
+        assert!(watcher.changed());
+    }
+
+    #[test]
+    fn a_single_file_can_be_watched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.wasm");
+        std::fs::write(&path, "a").unwrap();
+
+        let mut watcher = Watcher::new(&path);
+        std::fs::write(dir.path().join("other.wasm"), "a").unwrap();
+        assert!(!watcher.changed());
+        std::fs::write(&path, "ab").unwrap();
+        assert!(watcher.changed());
+    }
+}
@@ -0,0 +1,222 @@
+//! Skip lists in the format of `tests/ignores.txt`.
+//!
+//! Each line holds a pattern, optionally preceded by `+`-separated aliases
+//! restricting it to an OS, target environment, architecture, engine or
+//! compiler. A pattern skips the tests whose name contains it, or every
+//! test if it is `*`. Everything after a `#` is a comment.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// The engine that the test names end with
+pub(super) const ENGINE: &str = "universal";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct IgnorePattern {
+    os: Option<String>,
+    arch: Option<String>,
+    target_env: Option<String>,
+    engine: Option<String>,
+    compiler: Option<String>,
+    pattern_to_ignore: String,
+}
+
+impl IgnorePattern {
+    fn should_ignore(&self, host: &Host, compiler: &str, test_name: &str) -> bool {
+        self.os.as_ref().map_or(true, |val| val == host.os)
+            && self.arch.as_ref().map_or(true, |val| val == host.arch)
+            && self
+                .target_env
+                .as_ref()
+                .map_or(true, |val| val == host.target_env)
+            && self.engine.as_ref().map_or(true, |val| val == ENGINE)
+            && self.compiler.as_ref().map_or(true, |val| val == compiler)
+            && (self.pattern_to_ignore == "*" || test_name.contains(&*self.pattern_to_ignore))
+    }
+}
+
+/// The platform that the patterns are matched against
+#[derive(Debug, Clone, Copy)]
+struct Host {
+    os: &'static str,
+    arch: &'static str,
+    target_env: &'static str,
+}
+
+impl Host {
+    const CURRENT: Host = Host {
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        target_env: if cfg!(target_env = "musl") {
+            "musl"
+        } else if cfg!(target_env = "gnu") {
+            "gnu"
+        } else if cfg!(target_env = "msvc") {
+            "msvc"
+        } else {
+            ""
+        },
+    };
+}
+
+/// The tests to skip
+#[derive(Debug, Default, Clone)]
+pub(super) struct Ignores {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl Ignores {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read \"{}\"", path.display()))?;
+        contents
+            .parse()
+            .with_context(|| format!("invalid skip list \"{}\"", path.display()))
+    }
+
+    pub fn extend(&mut self, other: Ignores) {
+        self.patterns.extend(other.patterns);
+    }
+
+    /// If the test should be skipped with this compiler on the current host
+    pub fn should_ignore(&self, compiler: &str, test_name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|p| p.should_ignore(&Host::CURRENT, compiler, test_name))
+    }
+}
+
+impl std::str::FromStr for Ignores {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut patterns = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (aliases, pattern_to_ignore) = match line.split_once(char::is_whitespace) {
+                Some((aliases, pattern)) => (aliases, pattern.trim()),
+                None => ("", line),
+            };
+
+            let mut pattern = IgnorePattern {
+                pattern_to_ignore: pattern_to_ignore.to_string(),
+                ..Default::default()
+            };
+            for alias in aliases.split('+').filter(|alias| !alias.is_empty()) {
+                let field = match alias {
+                    "windows" | "macos" | "linux" => &mut pattern.os,
+                    "musl" => &mut pattern.target_env,
+                    "aarch64" | "x86" | "x64" | "riscv64" | "loongarch64" => &mut pattern.arch,
+                    "universal" | "engine" => &mut pattern.engine,
+                    "cranelift" | "llvm" | "singlepass" => &mut pattern.compiler,
+                    other => bail!("unsupported alias \"{other}\" on line {}", i + 1),
+                };
+                *field = Some(alias.to_string());
+            }
+            patterns.push(pattern);
+        }
+        Ok(Ignores { patterns })
+    }
+}
+
+/// The name of the test for a `.wast` file, as generated by `build.rs` for
+/// the compiler tests, e.g. `spec::simd::simd_lane::cranelift::universal`
+///
+/// The name starts with the directory that was passed on the command line,
+/// and `proposals` directories are left out.
+pub(super) fn test_name(root: &Path, file: &Path, compiler: &str) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    root.file_name()
+        .into_iter()
+        .chain(&relative)
+        .map(|component| component.to_string_lossy().replace('-', "_"))
+        .filter(|component| component != "proposals")
+        .chain([compiler.to_string(), ENGINE.to_string()])
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINUX: Host = Host {
+        os: "linux",
+        arch: "x86_64",
+        target_env: "gnu",
+    };
+
+    fn ignores(s: &str, host: &Host, compiler: &str, test_name: &str) -> bool {
+        let ignores: Ignores = s.parse().unwrap();
+        ignores
+            .patterns
+            .iter()
+            .any(|p| p.should_ignore(host, compiler, test_name))
+    }
+
+    #[test]
+    fn patterns_match_on_the_platform_and_compiler() {
+        let list = "
+# Compilers
+singlepass spec::simd # No SIMD
+linux+llvm   spec::memory_copy
+macos spec::align
+spec::names::cranelift
+";
+
+        assert!(ignores(
+            list,
+            &LINUX,
+            "singlepass",
+            "spec::simd::simd_lane::singlepass::universal"
+        ));
+        assert!(!ignores(
+            list,
+            &LINUX,
+            "cranelift",
+            "spec::simd::simd_lane::cranelift::universal"
+        ));
+        assert!(ignores(
+            list,
+            &LINUX,
+            "llvm",
+            "spec::memory_copy::llvm::universal"
+        ));
+        assert!(!ignores(
+            list,
+            &LINUX,
+            "cranelift",
+            "spec::align::cranelift::universal"
+        ));
+        assert!(ignores(
+            list,
+            &LINUX,
+            "cranelift",
+            "spec::names::cranelift::universal"
+        ));
+        assert!(ignores("*", &LINUX, "llvm", "wasmer::fac::llvm::universal"));
+    }
+
+    #[test]
+    fn unknown_aliases_are_rejected() {
+        let err = "solaris spec::simd".parse::<Ignores>().unwrap_err();
+        assert_eq!(err.to_string(), "unsupported alias \"solaris\" on line 1");
+    }
+
+    #[test]
+    fn test_names_follow_the_generated_tests() {
+        let root = Path::new("tests/wast/spec");
+        assert_eq!(
+            test_name(root, &root.join("binary-leb128.wast"), "cranelift"),
+            "spec::binary_leb128::cranelift::universal"
+        );
+        assert_eq!(
+            test_name(root, &root.join("proposals/multi-value/block.wast"), "llvm"),
+            "spec::multi_value::block::llvm::universal"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! JUnit XML reports, with a `<testsuite>` per `.wast` file and backend and
+//! a `<testcase>` per directive.
+
+use std::io::{self, Write};
+
+use wasmer_wast::DirectiveOutcome;
+
+use super::{Outcome, Run};
+
+pub(super) fn write(out: &mut impl Write, runs: &[Run]) -> io::Result<()> {
+    let total = runs.iter().map(Run::counts).sum::<super::Counts>();
+    let time: f64 = runs.iter().map(|run| run.time.as_secs_f64()).sum();
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="wasmer wast" tests="{}" failures="{}" errors="{}" skipped="{}" time="{time:.3}">"#,
+        total.tests, total.failures, total.errors, total.skipped,
+    )?;
+    for run in runs {
+        let counts = run.counts();
+        let name = escape(&run.job.name);
+        let file = escape(&run.job.file.display().to_string());
+        writeln!(
+            out,
+            r#"  <testsuite name="{name}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}" file="{file}">"#,
+            counts.tests,
+            counts.failures,
+            counts.errors,
+            counts.skipped,
+            run.time.as_secs_f64(),
+        )?;
+        let file_name = run
+            .job
+            .file
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let file_name = escape(&file_name);
+        match &run.outcome {
+            Outcome::Ignored => {
+                writeln!(
+                    out,
+                    r#"    <testcase name="{file_name}" classname="{name}" file="{file}">"#
+                )?;
+                writeln!(out, r#"      <skipped message="ignored"/>"#)?;
+                writeln!(out, "    </testcase>")?;
+            }
+            Outcome::Errored(message) => {
+                writeln!(
+                    out,
+                    r#"    <testcase name="{file_name}" classname="{name}" file="{file}">"#
+                )?;
+                writeln!(out, r#"      <error message="{}"/>"#, escape(message))?;
+                writeln!(out, "    </testcase>")?;
+            }
+            Outcome::Ran(reports) => {
+                for report in reports {
+                    let open = format!(
+                        r#"<testcase name="{} at {}:{}" classname="{name}" file="{file}" line="{}""#,
+                        report.kind, report.line, report.col, report.line,
+                    );
+                    match &report.outcome {
+                        DirectiveOutcome::Passed => writeln!(out, "    {open}/>")?,
+                        DirectiveOutcome::Failed(message) => {
+                            writeln!(out, "    {open}>")?;
+                            writeln!(out, r#"      <failure message="{}"/>"#, escape(message))?;
+                            writeln!(out, "    </testcase>")?;
+                        }
+                        DirectiveOutcome::Skipped(message) => {
+                            writeln!(out, "    {open}>")?;
+                            writeln!(out, r#"      <skipped message="{}"/>"#, escape(message))?;
+                            writeln!(out, "    </testcase>")?;
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(out, "  </testsuite>")?;
+    }
+    writeln!(out, "</testsuites>")
+}
+
+/// Escapes text for attribute values, dropping the characters that can't
+/// be represented in XML 1.0
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\r' => escaped.push_str("&#13;"),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use wasmer_wast::DirectiveReport;
+
+    use super::*;
+    use crate::{backend::BackendType, commands::wast::Job};
+
+    fn run(name: &str, outcome: Outcome) -> Run {
+        Run {
+            job: Job {
+                file: PathBuf::from(format!("tests/{name}.wast")),
+                name: format!("tests::{name}::cranelift::universal"),
+                backend: BackendType::Cranelift,
+                ignored: matches!(outcome, Outcome::Ignored),
+            },
+            outcome,
+            time: Duration::from_millis(1500),
+        }
+    }
+
+    #[test]
+    fn directives_are_reported_as_testcases() {
+        let runs = [
+            run(
+                "fac",
+                Outcome::Ran(vec![
+                    DirectiveReport {
+                        kind: "module",
+                        line: 1,
+                        col: 1,
+                        outcome: DirectiveOutcome::Passed,
+                    },
+                    DirectiveReport {
+                        kind: "assert_return",
+                        line: 9,
+                        col: 2,
+                        outcome: DirectiveOutcome::Failed("expected <\"1\">, got 2".to_string()),
+                    },
+                ]),
+            ),
+            run("simd", Outcome::Ignored),
+            run("bad", Outcome::Errored("unexpected token".to_string())),
+        ];
+        let mut out = Vec::new();
+        write(&mut out, &runs).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="wasmer wast" tests="4" failures="1" errors="1" skipped="1" time="4.500">
+  <testsuite name="tests::fac::cranelift::universal" tests="2" failures="1" errors="0" skipped="0" time="1.500" file="tests/fac.wast">
+    <testcase name="module at 1:1" classname="tests::fac::cranelift::universal" file="tests/fac.wast" line="1"/>
+    <testcase name="assert_return at 9:2" classname="tests::fac::cranelift::universal" file="tests/fac.wast" line="9">
+      <failure message="expected &lt;&quot;1&quot;&gt;, got 2"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="tests::simd::cranelift::universal" tests="1" failures="0" errors="0" skipped="1" time="1.500" file="tests/simd.wast">
+    <testcase name="simd.wast" classname="tests::simd::cranelift::universal" file="tests/simd.wast">
+      <skipped message="ignored"/>
+    </testcase>
+  </testsuite>
+  <testsuite name="tests::bad::cranelift::universal" tests="1" failures="0" errors="1" skipped="0" time="1.500" file="tests/bad.wast">
+    <testcase name="bad.wast" classname="tests::bad::cranelift::universal" file="tests/bad.wast">
+      <error message="unexpected token"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
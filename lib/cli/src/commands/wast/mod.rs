@@ -0,0 +1,347 @@
+//! Runs a .wast WebAssembly test suites
+use std::{
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use wasmer::{
+    sys::{engine::NativeEngineExt, Target},
+    Store,
+};
+use wasmer_wast::{DirectiveOutcome, DirectiveReport, Wast as WastSpectest};
+
+use crate::{
+    backend::{BackendType, RuntimeOptions},
+    common::HashAlgorithm,
+};
+
+use self::ignores::Ignores;
+
+mod ignores;
+mod junit;
+
+#[derive(Debug, Parser)]
+/// The options for the `wasmer wast` subcommand
+pub struct Wast {
+    /// Wast files to run, or directories searched for `.wast` files
+    #[clap(name = "PATH", required = true)]
+    paths: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    rt: RuntimeOptions,
+
+    #[clap(short, long)]
+    /// A flag to indicate wast stop at the first error or continue.
+    fail_fast: bool,
+
+    /// The number of files to run at the same time, by default one per CPU
+    #[clap(short, long)]
+    jobs: Option<NonZeroUsize>,
+
+    /// Skip the tests listed in this file, in the format of
+    /// `tests/ignores.txt`
+    #[clap(long = "ignores", name = "IGNORES")]
+    ignores: Vec<PathBuf>,
+
+    /// Write a JUnit XML report to this file
+    #[clap(long, name = "JUNIT")]
+    junit: Option<PathBuf>,
+
+    /// Hashing algorithm to be used for module hash
+    #[clap(long, value_enum)]
+    hash_algorithm: Option<HashAlgorithm>,
+}
+
+/// A `.wast` file to run with a backend
+#[derive(Debug)]
+struct Job {
+    file: PathBuf,
+    /// The name used for the skip lists and the reports
+    name: String,
+    backend: BackendType,
+    ignored: bool,
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Ignored,
+    Ran(Vec<DirectiveReport>),
+    /// The file couldn't be read or parsed, or the backend failed
+    Errored(String),
+}
+
+#[derive(Debug)]
+struct Run {
+    job: Job,
+    outcome: Outcome,
+    time: Duration,
+}
+
+/// The number of directives by outcome, errors being whole files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Counts {
+    tests: usize,
+    failures: usize,
+    errors: usize,
+    skipped: usize,
+}
+
+impl std::iter::Sum for Counts {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Counts::default(), |total, counts| Counts {
+            tests: total.tests + counts.tests,
+            failures: total.failures + counts.failures,
+            errors: total.errors + counts.errors,
+            skipped: total.skipped + counts.skipped,
+        })
+    }
+}
+
+impl Run {
+    fn counts(&self) -> Counts {
+        match &self.outcome {
+            Outcome::Ignored => Counts {
+                tests: 1,
+                skipped: 1,
+                ..Default::default()
+            },
+            Outcome::Errored(_) => Counts {
+                tests: 1,
+                errors: 1,
+                ..Default::default()
+            },
+            Outcome::Ran(reports) => Counts {
+                tests: reports.len(),
+                failures: reports
+                    .iter()
+                    .filter(|r| matches!(r.outcome, DirectiveOutcome::Failed(_)))
+                    .count(),
+                skipped: reports
+                    .iter()
+                    .filter(|r| matches!(r.outcome, DirectiveOutcome::Skipped(_)))
+                    .count(),
+                errors: 0,
+            },
+        }
+    }
+
+    fn print(&self) {
+        let counts = self.counts();
+        let name = &self.job.name;
+        match &self.outcome {
+            Outcome::Ignored => eprintln!("SKIP  {name}"),
+            Outcome::Errored(message) => eprintln!("ERROR {name}: {message}"),
+            Outcome::Ran(reports) => {
+                let passed = counts.tests - counts.failures - counts.skipped;
+                let status = if counts.failures > 0 {
+                    "FAIL "
+                } else {
+                    "ok   "
+                };
+                eprint!("{status} {name}: {passed} passed");
+                if counts.failures > 0 {
+                    eprint!(", {} failed", counts.failures);
+                }
+                if counts.skipped > 0 {
+                    eprint!(", {} skipped", counts.skipped);
+                }
+                eprintln!(" ({:.2}s)", self.time.as_secs_f64());
+                for report in reports {
+                    if let DirectiveOutcome::Failed(message) = &report.outcome {
+                        eprintln!(
+                            "  • {} at {}:{}:{}: {message}",
+                            report.kind,
+                            self.job.file.display(),
+                            report.line,
+                            report.col
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Wast {
+    /// Runs logic for the `validate` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context("failed to test the wast files")
+    }
+    fn inner_execute(&self) -> Result<()> {
+        let mut ignores = Ignores::default();
+        for path in &self.ignores {
+            ignores.extend(Ignores::from_path(path)?);
+        }
+
+        let backends = self.rt.get_available_backends()?;
+        let mut jobs = Vec::new();
+        for path in &self.paths {
+            let (root, files) = wast_files(path)?;
+            for file in files {
+                for backend in &backends {
+                    let name = ignores::test_name(&root, &file, &backend.to_string());
+                    jobs.push(Job {
+                        ignored: ignores.should_ignore(&backend.to_string(), &name),
+                        file: file.clone(),
+                        name,
+                        backend: *backend,
+                    });
+                }
+            }
+        }
+
+        let runs = self.run_all(jobs);
+
+        if let Some(path) = &self.junit {
+            let mut file = std::io::BufWriter::new(
+                std::fs::File::create(path)
+                    .with_context(|| format!("unable to create \"{}\"", path.display()))?,
+            );
+            junit::write(&mut file, &runs)
+                .with_context(|| format!("unable to write \"{}\"", path.display()))?;
+        }
+
+        let total = runs.iter().map(Run::counts).sum::<Counts>();
+        let ran = runs
+            .iter()
+            .filter(|run| !matches!(run.outcome, Outcome::Ignored))
+            .count();
+        let backends = backends
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!(
+            "\n{ran} of {} runs ({backends}): {} directives passed, {} failed, {} skipped, {} files errored",
+            runs.len(),
+            total.tests - total.failures - total.skipped - total.errors,
+            total.failures,
+            total.skipped,
+            total.errors,
+        );
+        if total.failures > 0 || total.errors > 0 {
+            bail!("tests failed");
+        }
+        eprintln!("Wast tests succeeded.");
+        Ok(())
+    }
+
+    /// Runs the jobs on a pool of threads, printing each result as it
+    /// finishes, and returns the results in the order of the jobs
+    fn run_all(&self, jobs: Vec<Job>) -> Vec<Run> {
+        let threads = self
+            .jobs
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+            .min(jobs.len());
+
+        let queue = Mutex::new(jobs.into_iter().enumerate());
+        let failed = AtomicBool::new(false);
+        let runs = Mutex::new(Vec::new());
+        std::thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| loop {
+                    if self.fail_fast && failed.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Some((i, job)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let run = self.run(job);
+                    run.print();
+                    let counts = run.counts();
+                    if counts.failures > 0 || counts.errors > 0 {
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                    runs.lock().unwrap().push((i, run));
+                });
+            }
+        });
+
+        let mut runs = runs.into_inner().unwrap();
+        runs.sort_by_key(|(i, _)| *i);
+        runs.into_iter().map(|(_, run)| run).collect()
+    }
+
+    fn run(&self, job: Job) -> Run {
+        let start = Instant::now();
+        let outcome = if job.ignored {
+            Outcome::Ignored
+        } else {
+            // The runner has `todo!()`s for the directives it doesn't support
+            match panic::catch_unwind(AssertUnwindSafe(|| self.report(&job))) {
+                Ok(Ok(reports)) => Outcome::Ran(reports),
+                Ok(Err(e)) => Outcome::Errored(format!("{e:#}")),
+                Err(payload) => Outcome::Errored(
+                    payload
+                        .downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "panicked".to_string()),
+                ),
+            }
+        };
+        Run {
+            job,
+            outcome,
+            time: start.elapsed(),
+        }
+    }
+
+    fn report(&self, job: &Job) -> Result<Vec<DirectiveReport>> {
+        let target = Target::default();
+        let backend_kind = wasmer::BackendKind::from(&job.backend);
+        let features = wasmer::Engine::default_features_for_backend(&backend_kind, &target);
+        let mut engine = job.backend.get_engine(&target, &features, &self.rt)?;
+
+        let hash_algorithm = self.hash_algorithm.unwrap_or_default().into();
+        engine.set_hash_algorithm(Some(hash_algorithm));
+
+        let store: Store = Store::new(engine);
+        let mut wast = WastSpectest::new_with_spectest(store);
+        // Same wording differences as allowed by `tests/compilers/wast.rs`
+        wast.allow_trap_message("uninitialized element 2", "uninitialized element");
+        wast.allow_trap_message("out of bounds memory access", "memory out of bounds");
+        wast.fail_fast = self.fail_fast;
+        wast.report_file(&job.file)
+    }
+}
+
+/// The `.wast` files under `path` in a stable order, with the directory
+/// that their test names start from
+fn wast_files(path: &Path) -> Result<(PathBuf, Vec<PathBuf>)> {
+    if !path.is_dir() {
+        if !path.exists() {
+            bail!("\"{}\" does not exist", path.display());
+        }
+        let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        return Ok((root, vec![path.to_path_buf()]));
+    }
+
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("unable to read \"{}\"", path.display()))?;
+        let file = entry.path();
+        // Skip files starting with `.`, which could be editor temporary files
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if entry.file_type().is_file()
+            && !hidden
+            && file.extension().is_some_and(|ext| ext == "wast")
+        {
+            files.push(file.to_path_buf());
+        }
+    }
+    if files.is_empty() {
+        bail!("no .wast files found in \"{}\"", path.display());
+    }
+    Ok((path.to_path_buf(), files))
+}
@@ -28,6 +28,7 @@ mod backend;
 mod c_gen;
 mod logging;
 mod opts;
+mod strace;
 mod types;
 mod utils;
 
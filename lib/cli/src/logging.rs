@@ -2,7 +2,9 @@
 
 use is_terminal::IsTerminal;
 use tracing::level_filters::LevelFilter;
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
 
 const WHITELISTED_LOG_TARGETS: &[&str] = &["wasmer", "wasmer_wasix", "virtual_fs"];
 
@@ -34,7 +36,10 @@ impl Output {
 
     /// Initialize logging based on the `$RUST_LOG` environment variable and
     /// command-line flags.
-    pub fn initialize_logging(&self) {
+    ///
+    /// The `extra` layer gets the events and spans that the logs filter
+    /// out, like the one of `wasmer run --strace`.
+    pub fn initialize_logging(&self, extra: Option<Box<dyn Layer<Registry> + Send + Sync>>) {
         let fmt_layer = fmt::layer()
             .with_target(true)
             .with_ansi(self.should_emit_colors())
@@ -53,12 +58,17 @@ impl Output {
 
         match self.log_format {
             LogFormat::Text => tracing_subscriber::registry()
-                .with(filter_layer)
-                .with(fmt_layer.compact().with_target(true))
+                .with(extra)
+                .with(
+                    fmt_layer
+                        .compact()
+                        .with_target(true)
+                        .with_filter(filter_layer),
+                )
                 .init(),
             LogFormat::Json => tracing_subscriber::registry()
-                .with(filter_layer)
-                .with(fmt_layer.json().with_target(true))
+                .with(extra)
+                .with(fmt_layer.json().with_target(true).with_filter(filter_layer))
                 .init(),
         }
     }
@@ -0,0 +1,383 @@
+//! Tracing of the WASI and WASIX syscalls for `wasmer run --strace`.
+//!
+//! The syscalls of `wasmer-wasix` are instrumented with a span that holds
+//! their decoded arguments, and that records the return value. This layer
+//! prints one line per syscall when its span closes, instead of the span
+//! events of the logs.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{LineWriter, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::{filter_fn, LevelFilter},
+    layer::Context as LayerContext,
+    registry::LookupSpan,
+    Layer, Registry,
+};
+use wasmer_wasix::os::task::thread::current_thread_ids;
+
+/// Modules that hold the syscalls
+const SYSCALL_TARGETS: &[&str] = &[
+    "wasmer_wasix::syscalls::wasi::",
+    "wasmer_wasix::syscalls::wasix::",
+    "wasmer_wasix::syscalls::legacy::",
+];
+
+/// The options of `wasmer run` that trace the syscalls
+#[derive(Debug, Clone, Default, clap::Parser)]
+pub struct StraceOptions {
+    /// Print each WASI and WASIX syscall with its arguments, its result and
+    /// its duration. The optional filter is a comma-separated list of
+    /// syscall names, which may end with `*` (`--strace=fd_*,path_open`)
+    #[clap(
+        long = "strace",
+        value_name = "FILTER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "*"
+    )]
+    filter: Option<String>,
+    /// Format of the syscall trace
+    #[clap(
+        long = "strace-format",
+        value_enum,
+        default_value_t,
+        requires = "filter"
+    )]
+    format: StraceFormat,
+    /// Write the syscall trace to this file instead of stderr
+    #[clap(long = "strace-output", value_name = "PATH", requires = "filter")]
+    output: Option<PathBuf>,
+}
+
+/// The formats of the syscall trace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StraceFormat {
+    /// One line per syscall, like `strace`
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl StraceOptions {
+    /// The layer that prints the syscalls, if they are traced
+    pub(crate) fn layer(&self) -> anyhow::Result<Option<Box<dyn Layer<Registry> + Send + Sync>>> {
+        let Some(filter) = &self.filter else {
+            return Ok(None);
+        };
+        let output: Box<dyn Write + Send> = match &self.output {
+            Some(path) => Box::new(File::create(path).with_context(|| {
+                format!("Unable to create the syscall trace \"{}\"", path.display())
+            })?),
+            None => Box::new(std::io::stderr()),
+        };
+        let layer = StraceLayer {
+            patterns: filter
+                .split(',')
+                .map(str::trim)
+                .filter(|pattern| !pattern.is_empty())
+                .map(str::to_string)
+                .collect(),
+            format: self.format,
+            output: Mutex::new(LineWriter::new(output)),
+        };
+        // The logs have their own filter, the syscall spans are always
+        // enabled for this layer
+        let filter = filter_fn(|metadata| {
+            SYSCALL_TARGETS
+                .iter()
+                .any(|target| metadata.target().starts_with(target))
+        })
+        .with_max_level_hint(LevelFilter::TRACE);
+        Ok(Some(Box::new(layer.with_filter(filter))))
+    }
+}
+
+struct StraceLayer {
+    patterns: Vec<String>,
+    format: StraceFormat,
+    output: Mutex<LineWriter<Box<dyn Write + Send>>>,
+}
+
+/// A syscall that is running, stored in the extensions of its span
+#[derive(Debug)]
+struct Syscall {
+    name: &'static str,
+    /// WASIX process and thread ids
+    thread: Option<(u32, u32)>,
+    /// The fields of the span, in the order they were recorded
+    args: Vec<(&'static str, Value)>,
+    ret: Option<String>,
+    start: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Bool(bool),
+    Int(i128),
+    Other(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(value) => write!(f, "{value:?}"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Other(value) => f.write_str(value),
+        }
+    }
+}
+
+impl Value {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Str(value) | Value::Other(value) => value.clone().into(),
+            Value::Bool(value) => (*value).into(),
+            Value::Int(value) => i64::try_from(*value)
+                .map(Into::into)
+                .unwrap_or_else(|_| value.to_string().into()),
+        }
+    }
+}
+
+impl Visit for Syscall {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, Value::Other(format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::Str(value.to_string()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::Bool(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Value::Int(value.into()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Value::Int(value.into()));
+    }
+}
+
+impl Syscall {
+    fn set(&mut self, field: &Field, value: Value) {
+        match self.args.iter_mut().find(|(name, _)| *name == field.name()) {
+            Some((_, previous)) => *previous = value,
+            None => self.args.push((field.name(), value)),
+        }
+    }
+
+    fn to_text(&self, duration: Duration) -> String {
+        let mut line = String::new();
+        if let Some((pid, tid)) = self.thread {
+            line.push_str(&format!("[pid {pid} tid {tid}] "));
+        }
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        line.push_str(&format!(
+            "{}({args}) = {} <{:.6}>",
+            self.name,
+            self.ret.as_deref().unwrap_or("?"),
+            duration.as_secs_f64()
+        ));
+        line
+    }
+
+    fn to_json(&self, duration: Duration) -> serde_json::Value {
+        let args: serde_json::Map<String, serde_json::Value> = self
+            .args
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_json()))
+            .collect();
+        serde_json::json!({
+            "pid": self.thread.map(|(pid, _)| pid),
+            "tid": self.thread.map(|(_, tid)| tid),
+            "syscall": self.name,
+            "args": args,
+            "ret": self.ret,
+            "duration_us": duration.as_micros() as u64,
+        })
+    }
+}
+
+/// Turns the return value recorded by `#[instrument(ret)]`, like
+/// `Ok(Errno::noent)`, into the name of the errno
+fn decode_return(value: &str) -> String {
+    let value = value
+        .strip_prefix("Ok(")
+        .and_then(|value| value.strip_suffix(')'))
+        .unwrap_or(value);
+    value.strip_prefix("Errno::").unwrap_or(value).to_string()
+}
+
+impl StraceLayer {
+    fn is_traced(&self, name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern,
+            })
+    }
+}
+
+impl<S> Layer<S> for StraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: LayerContext<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        // The legacy syscalls call the current ones, only the outer one is
+        // printed
+        let nested = span
+            .parent()
+            .is_some_and(|parent| parent.extensions().get::<Syscall>().is_some());
+        if nested || !self.is_traced(attrs.metadata().name()) {
+            return;
+        }
+        let mut syscall = Syscall {
+            name: attrs.metadata().name(),
+            thread: current_thread_ids().map(|(pid, tid)| (pid.raw(), tid.raw())),
+            args: Vec::new(),
+            ret: None,
+            start: Instant::now(),
+        };
+        attrs.record(&mut syscall);
+        span.extensions_mut().insert(syscall);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: LayerContext<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(syscall) = span.extensions_mut().get_mut::<Syscall>() {
+                values.record(syscall);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        struct Return(Option<String>);
+        impl Visit for Return {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "return" {
+                    self.0 = Some(format!("{value:?}"));
+                }
+            }
+        }
+
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(syscall) = extensions.get_mut::<Syscall>() else {
+            return;
+        };
+        let mut ret = Return(None);
+        event.record(&mut ret);
+        if let Some(value) = ret.0 {
+            syscall.ret = Some(decode_return(&value));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: LayerContext<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(syscall) = span.extensions_mut().remove::<Syscall>() else {
+            return;
+        };
+        let duration = syscall.start.elapsed();
+        let line = match self.format {
+            StraceFormat::Text => syscall.to_text(duration),
+            StraceFormat::Json => syscall.to_json(duration).to_string(),
+        };
+        let mut output = self.output.lock().unwrap();
+        let _ = writeln!(output, "{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(filter: &str) -> StraceLayer {
+        StraceLayer {
+            patterns: filter.split(',').map(str::to_string).collect(),
+            format: StraceFormat::Text,
+            output: Mutex::new(LineWriter::new(Box::new(std::io::sink()))),
+        }
+    }
+
+    #[test]
+    fn syscalls_are_filtered_by_name() {
+        let layer = layer("fd_*,path_open");
+        assert!(layer.is_traced("fd_read"));
+        assert!(layer.is_traced("path_open"));
+        assert!(!layer.is_traced("path_open2"));
+        assert!(!layer.is_traced("sock_connect"));
+        assert!(self::layer("*").is_traced("sock_connect"));
+    }
+
+    #[test]
+    fn return_values_are_decoded() {
+        assert_eq!(decode_return("Ok(Errno::noent)"), "noent");
+        assert_eq!(decode_return("Errno::success"), "success");
+        assert_eq!(
+            decode_return("Err(Exit(ExitCode(1)))"),
+            "Err(Exit(ExitCode(1)))"
+        );
+    }
+
+    #[test]
+    fn syscalls_are_printed_as_text_and_json() {
+        let syscall = Syscall {
+            name: "path_open",
+            thread: Some((1, 2)),
+            args: vec![
+                ("dirfd", Value::Other("3".to_string())),
+                ("path", Value::Str("data/in.txt".to_string())),
+                ("follow_symlinks", Value::Bool(true)),
+            ],
+            ret: Some("success".to_string()),
+            start: Instant::now(),
+        };
+        let duration = Duration::from_micros(42);
+        assert_eq!(
+            syscall.to_text(duration),
+            r#"[pid 1 tid 2] path_open(dirfd=3, path="data/in.txt", follow_symlinks=true) = success <0.000042>"#
+        );
+        assert_eq!(
+            syscall.to_json(duration),
+            serde_json::json!({
+                "pid": 1,
+                "tid": 2,
+                "syscall": "path_open",
+                "args": {"dirfd": "3", "path": "data/in.txt", "follow_symlinks": true},
+                "ret": "success",
+                "duration_us": 42,
+            })
+        );
+    }
+}
@@ -1,9 +1,16 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/main/docs/ATTRIBUTIONS.md
 
+use cranelift_codegen::isa::TargetIsa;
+#[cfg(feature = "unwind")]
+use cranelift_codegen::LabelValueLoc;
 use cranelift_codegen::{Context, MachSrcLoc};
 use std::ops::Range;
-use wasmer_compiler::types::address_map::{FunctionAddressMap, InstructionAddressMap};
+#[cfg(feature = "unwind")]
+use wasmer_compiler::types::address_map::ValueLocation;
+use wasmer_compiler::types::address_map::{
+    FunctionAddressMap, InstructionAddressMap, LocalLocation,
+};
 use wasmer_types::SourceLoc;
 
 pub fn get_function_address_map(
@@ -36,5 +43,58 @@ pub fn get_function_address_map(
         end_srcloc,
         body_offset: 0,
         body_len,
+        local_types: vec![],
+        local_locations: vec![],
     }
 }
+
+/// Returns where the first `num_locals` WebAssembly locals live in the
+/// compiled code of `context`.
+///
+/// Cranelift only tracks them when `collect_debug_info` was called on the
+/// function before it was compiled.
+#[cfg(feature = "unwind")]
+pub fn get_local_locations(
+    context: &Context,
+    isa: &dyn TargetIsa,
+    num_locals: usize,
+) -> Vec<LocalLocation> {
+    let mcr = context.compiled_code().unwrap();
+    let mut locations = Vec::new();
+    for (label, ranges) in mcr.value_labels_ranges.iter() {
+        let local = label.as_u32();
+        // The labels past the locals are the ones Cranelift uses for
+        // itself, such as the one for the `vmctx`.
+        if local as usize >= num_locals {
+            continue;
+        }
+        for range in ranges {
+            let location = match range.loc {
+                LabelValueLoc::Reg(reg) => match isa.map_regalloc_reg_to_dwarf(reg) {
+                    Ok(reg) => ValueLocation::Register(reg),
+                    Err(_) => continue,
+                },
+                LabelValueLoc::CFAOffset(offset) => ValueLocation::FrameOffset(offset),
+            };
+            locations.push(LocalLocation {
+                local,
+                start: range.start as usize,
+                end: range.end as usize,
+                location,
+            });
+        }
+    }
+    locations.sort_by_key(|l| (l.local, l.start));
+    locations
+}
+
+/// Without unwinding support there is no mapping from Cranelift's
+/// registers to DWARF ones, so no locations are recorded.
+#[cfg(not(feature = "unwind"))]
+pub fn get_local_locations(
+    _context: &Context,
+    _isa: &dyn TargetIsa,
+    _num_locals: usize,
+) -> Vec<LocalLocation> {
+    Vec::new()
+}
@@ -4,15 +4,15 @@
 use crate::dwarf::WriterRelocate;
 
 use crate::{
-    address_map::get_function_address_map,
-    config::Cranelift,
+    address_map::{get_function_address_map, get_local_locations},
+    config::{Cranelift, CraneliftOptLevel},
     func_environ::{get_function_name, FuncEnvironment},
     trampoline::{
         make_trampoline_dynamic_function, make_trampoline_function_call, FunctionBuilderContext,
     },
     translator::{
         compiled_function_unwind_info, irlibcall_to_libcall, irreloc_to_relocationkind,
-        signature_to_cranelift_ir, CraneliftUnwindInfo, FuncTranslator,
+        signature_to_cranelift_ir, CraneliftUnwindInfo, FuncEnvironment as _, FuncTranslator,
     },
 };
 use cranelift_codegen::{
@@ -144,9 +144,9 @@ impl CraneliftCompiler {
                     _ => UserFuncName::default(),
                 };
                 context.func.signature = signatures[module.functions[func_index]].clone();
-                // if generate_debug_info {
-                //     context.func.collect_debug_info();
-                // }
+                if self.config.enable_debug_info {
+                    context.func.collect_debug_info();
+                }
                 let mut reader =
                     MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
                 reader.set_middleware_chain(
@@ -211,7 +211,12 @@ impl CraneliftCompiler {
                 };
 
                 let range = reader.range();
-                let address_map = get_function_address_map(&context, range, code_buf.len());
+                let mut address_map = get_function_address_map(&context, range, code_buf.len());
+                if self.config.enable_debug_info {
+                    address_map.local_types = func_env.get_local_types().to_vec();
+                    address_map.local_locations =
+                        get_local_locations(&context, &*isa, address_map.local_types.len());
+                }
 
                 Ok((
                     CompiledFunction {
@@ -256,9 +261,9 @@ impl CraneliftCompiler {
                     _ => UserFuncName::default(),
                 };
                 context.func.signature = signatures[module.functions[func_index]].clone();
-                // if generate_debug_info {
-                //     context.func.collect_debug_info();
-                // }
+                if self.config.enable_debug_info {
+                    context.func.collect_debug_info();
+                }
 
                 let mut reader =
                     MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
@@ -324,7 +329,12 @@ impl CraneliftCompiler {
                 };
 
                 let range = reader.range();
-                let address_map = get_function_address_map(&context, range, code_buf.len());
+                let mut address_map = get_function_address_map(&context, range, code_buf.len());
+                if self.config.enable_debug_info {
+                    address_map.local_types = func_env.get_local_types().to_vec();
+                    address_map.local_locations =
+                        get_local_locations(&context, &*isa, address_map.local_types.len());
+                }
 
                 Ok((
                     CompiledFunction {
@@ -431,8 +441,26 @@ impl Compiler for CraneliftCompiler {
         self.config.enable_perfmap
     }
 
+    fn get_debug_info_enabled(&self) -> bool {
+        self.config.enable_debug_info
+    }
+
     fn deterministic_id(&self) -> String {
-        String::from("cranelift")
+        let mut ret = format!(
+            "cranelift-{}",
+            match self.config.opt_level {
+                CraneliftOptLevel::None => "opt0",
+                CraneliftOptLevel::Speed => "opts",
+                CraneliftOptLevel::SpeedAndSize => "optsz",
+            }
+        );
+
+        // The artifacts record where the locals live
+        if self.config.enable_debug_info {
+            ret.push_str("-debug");
+        }
+
+        ret
     }
 
     /// Get the middlewares for this compiler
@@ -35,8 +35,9 @@ pub struct Cranelift {
     enable_nan_canonicalization: bool,
     enable_verifier: bool,
     pub(crate) enable_perfmap: bool,
+    pub(crate) enable_debug_info: bool,
     enable_pic: bool,
-    opt_level: CraneliftOptLevel,
+    pub(crate) opt_level: CraneliftOptLevel,
     /// The number of threads to use for compilation.
     pub num_threads: NonZero<usize>,
     /// The middleware chain.
@@ -55,6 +56,7 @@ impl Cranelift {
             num_threads: std::thread::available_parallelism().unwrap_or(NonZero::new(1).unwrap()),
             middlewares: vec![],
             enable_perfmap: false,
+            enable_debug_info: false,
         }
     }
 
@@ -210,6 +212,10 @@ impl CompilerConfig for Cranelift {
         self.enable_perfmap = true;
     }
 
+    fn enable_debug_info(&mut self) {
+        self.enable_debug_info = true;
+    }
+
     fn canonicalize_nans(&mut self, enable: bool) {
         self.enable_nan_canonicalization = enable;
     }
@@ -139,10 +139,11 @@ fn declare_wasm_parameters<FE: FuncEnvironment + ?Sized>(
             // This is a normal WebAssembly signature parameter, so create a local for it.
             let local = Variable::new(next_local);
             builder.declare_var(local, param_type.value_type);
-            next_local += 1;
 
             let param_value = builder.block_params(entry_block)[i];
             builder.def_var(local, param_value);
+            builder.set_val_label(param_value, ValueLabel::new(next_local));
+            next_local += 1;
         }
         if param_type.purpose == ir::ArgumentPurpose::VMContext {
             let param_value = builder.block_params(entry_block)[i];
@@ -267,6 +267,10 @@ impl Compiler for LLVMCompiler {
         self.config.enable_perfmap
     }
 
+    fn get_debug_info_enabled(&self) -> bool {
+        self.config.enable_debug_info
+    }
+
     fn deterministic_id(&self) -> String {
         let mut ret = format!(
             "llvm-{}",
@@ -47,6 +47,7 @@ pub struct LLVM {
     pub(crate) enable_g0m0_opt: bool,
     pub(crate) enable_verifier: bool,
     pub(crate) enable_perfmap: bool,
+    pub(crate) enable_debug_info: bool,
     pub(crate) opt_level: LLVMOptLevel,
     is_pic: bool,
     pub(crate) callbacks: Option<Arc<dyn LLVMCallbacks>>,
@@ -64,6 +65,7 @@ impl LLVM {
             enable_nan_canonicalization: false,
             enable_verifier: false,
             enable_perfmap: false,
+            enable_debug_info: false,
             opt_level: LLVMOptLevel::Aggressive,
             is_pic: false,
             callbacks: None,
@@ -315,6 +317,10 @@ impl CompilerConfig for LLVM {
         self.enable_perfmap = true
     }
 
+    fn enable_debug_info(&mut self) {
+        self.enable_debug_info = true
+    }
+
     /// Whether to verify compiler IR.
     fn enable_verifier(&mut self) {
         self.enable_verifier = true;
@@ -653,6 +653,8 @@ where
         end_srcloc: SourceLoc::default(),
         body_offset: 0,
         body_len: function_body.body.len(),
+        local_types: vec![],
+        local_locations: vec![],
     };
 
     Ok(CompiledFunction {
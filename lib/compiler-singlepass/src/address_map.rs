@@ -18,5 +18,7 @@ pub fn get_function_address_map(
         end_srcloc,
         body_offset: 0,
         body_len,
+        local_types: vec![],
+        local_locations: vec![],
     }
 }
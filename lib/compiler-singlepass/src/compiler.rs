@@ -64,6 +64,10 @@ impl Compiler for SinglepassCompiler {
         String::from("singlepass")
     }
 
+    fn get_debug_info_enabled(&self) -> bool {
+        self.config.enable_debug_info
+    }
+
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>] {
         &self.config.middlewares
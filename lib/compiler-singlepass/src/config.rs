@@ -12,6 +12,7 @@ use wasmer_types::{
 #[derive(Debug, Clone)]
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
+    pub(crate) enable_debug_info: bool,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -22,6 +23,7 @@ impl Singlepass {
     pub fn new() -> Self {
         Self {
             enable_nan_canonicalization: true,
+            enable_debug_info: false,
             middlewares: vec![],
         }
     }
@@ -38,6 +40,10 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn enable_debug_info(&mut self) {
+        self.enable_debug_info = true;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
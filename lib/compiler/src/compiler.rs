@@ -45,6 +45,16 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Enable the registration of native debug information for the compiled
+    /// code with debuggers such as GDB and LLDB.
+    ///
+    /// The code is only registered when the `gdb-jit` feature is enabled.
+    fn enable_debug_info(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they keep the information that is needed to map the
+        // native code back to the WebAssembly module.
+    }
+
     /// Enable NaN canonicalization.
     ///
     /// NaN canonicalization is useful when trying to run WebAssembly
@@ -185,4 +195,9 @@ pub trait Compiler: Send + std::fmt::Debug {
     fn get_perfmap_enabled(&self) -> bool {
         false
     }
+
+    /// Get whether the debug information is registered with debuggers.
+    fn get_debug_info_enabled(&self) -> bool {
+        false
+    }
 }
@@ -451,6 +451,21 @@ impl Artifact {
         #[cfg(not(target_arch = "wasm32"))]
         {
             engine_inner.register_perfmap(&finished_functions, module_info)?;
+            #[cfg(feature = "gdb-jit")]
+            if engine_inner.debug_info_enabled() {
+                match &artifact {
+                    ArtifactBuildVariant::Plain(p) => engine_inner.register_debug_info(
+                        &finished_functions,
+                        module_info,
+                        p.get_frame_info_ref(),
+                    ),
+                    ArtifactBuildVariant::Archived(a) => engine_inner.register_debug_info(
+                        &finished_functions,
+                        module_info,
+                        &a.deserialize_frame_info_ref()?,
+                    ),
+                }
+            }
         }
 
         // Make all code compiled thus far executable.
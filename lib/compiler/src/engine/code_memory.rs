@@ -2,6 +2,8 @@
 // Attributions: https://github.com/wasmerio/wasmer/blob/main/docs/ATTRIBUTIONS.md
 
 //! Memory management for executable code.
+#[cfg(feature = "gdb-jit")]
+use super::debug::GdbJitImageRegistration;
 use super::unwind::UnwindRegistry;
 use crate::{
     types::{
@@ -28,6 +30,9 @@ const DATA_SECTION_ALIGNMENT: usize = 64;
 pub struct CodeMemory {
     // frame info is placed first, to ensure it's dropped before the mmap
     frame_info_registration: Option<GlobalFrameInfoRegistration>,
+    // the debuggers are told the code is gone before the mmap is dropped
+    #[cfg(feature = "gdb-jit")]
+    debug_registration: Option<GdbJitImageRegistration>,
     unwind_registry: UnwindRegistry,
     mmap: Mmap,
     start_of_nonexecutable_pages: usize,
@@ -41,6 +46,8 @@ impl CodeMemory {
             mmap: Mmap::new(),
             start_of_nonexecutable_pages: 0,
             frame_info_registration: None,
+            #[cfg(feature = "gdb-jit")]
+            debug_registration: None,
         }
     }
 
@@ -234,6 +241,12 @@ impl CodeMemory {
     pub fn register_frame_info(&mut self, frame_info: GlobalFrameInfoRegistration) {
         self.frame_info_registration = Some(frame_info);
     }
+
+    /// Keep the debug info registered for as long as the code lives.
+    #[cfg(feature = "gdb-jit")]
+    pub fn register_debug_info(&mut self, registration: GdbJitImageRegistration) {
+        self.debug_registration = Some(registration);
+    }
 }
 
 fn round_up(size: usize, multiple: usize) -> usize {
@@ -0,0 +1,498 @@
+//! Translation of the DWARF sections of a WebAssembly module so that they
+//! describe the native code instead of the WebAssembly code section.
+//!
+//! Only the line programs and the subprograms are translated, which is what
+//! debuggers need to resolve function names, set breakpoints on source lines
+//! and show where a backtrace is. Variables and their locations are dropped.
+
+use std::collections::HashMap;
+
+use gimli::{
+    constants,
+    read::{self, EndianSlice},
+    write::{
+        self, Address, AttributeValue, DirectoryId, EndianVec, FileId, LineProgram, LineString,
+        Range, RangeList, Sections, UnitEntryId,
+    },
+    Encoding, Format, LineEncoding, LittleEndian,
+};
+use wasmer_types::{entity::PrimaryMap, LocalFunctionIndex, ModuleInfo};
+
+use crate::types::address_map::FunctionAddressMap;
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+
+/// Where a function was compiled from and where its native code lives
+pub(crate) struct FunctionRange {
+    /// Offset in the code section where the function, including its size,
+    /// starts
+    wasm_start: u64,
+    /// Offset in the code section where the function ends
+    wasm_end: u64,
+    /// Offsets in the code section and the native code that they were
+    /// compiled to, sorted by the offset in the code section
+    positions: Vec<(u64, u64)>,
+    /// Address of the native code
+    address: u64,
+    /// Length of the native code
+    len: u64,
+}
+
+/// Maps offsets in the WebAssembly code section, which is what the DWARF of
+/// a module refers to, to native addresses
+pub(crate) struct AddressTransform {
+    functions: Vec<FunctionRange>,
+}
+
+fn leb_len(mut value: u64) -> u64 {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+impl AddressTransform {
+    pub(crate) fn new(
+        address_maps: &PrimaryMap<LocalFunctionIndex, &FunctionAddressMap>,
+        native: &PrimaryMap<LocalFunctionIndex, (u64, u64)>,
+    ) -> Option<Self> {
+        let first = address_maps.values().next()?;
+        // The source locations are offsets in the module, the code section
+        // starts with the number of functions and every function with the
+        // size of its body
+        let first_start = first.start_srcloc.bits() as u64;
+        let first_len = first.end_srcloc.bits() as u64 - first_start;
+        let code_section =
+            first_start.checked_sub(leb_len(first_len) + leb_len(address_maps.len() as u64))?;
+
+        let mut functions = Vec::with_capacity(address_maps.len());
+        for (index, map) in address_maps.iter() {
+            let (address, len) = native[index];
+            let start = map.start_srcloc.bits() as u64;
+            let end = map.end_srcloc.bits() as u64;
+            if start < code_section || end < start {
+                return None;
+            }
+
+            let mut positions: Vec<_> = map
+                .instructions
+                .iter()
+                .filter(|i| !i.srcloc.is_default())
+                .map(|i| (i.srcloc.bits() as u64 - code_section, i.code_offset as u64))
+                .collect();
+            positions.sort_unstable();
+            positions.dedup_by_key(|(wasm, _)| *wasm);
+
+            functions.push(FunctionRange {
+                wasm_start: start - code_section - leb_len(end - start),
+                wasm_end: end - code_section,
+                positions,
+                address,
+                len,
+            });
+        }
+        Some(Self { functions })
+    }
+
+    fn function(&self, addr: u64) -> Option<(usize, &FunctionRange)> {
+        let index = self
+            .functions
+            .partition_point(|f| f.wasm_start <= addr)
+            .checked_sub(1)?;
+        let function = &self.functions[index];
+        (addr <= function.wasm_end).then_some((index, function))
+    }
+
+    /// The function that contains an offset in the code section and the
+    /// native address it was compiled to
+    pub(crate) fn translate(&self, addr: u64) -> Option<(usize, u64)> {
+        let (index, function) = self.function(addr)?;
+        if addr == function.wasm_end {
+            return Some((index, function.address + function.len));
+        }
+        let offset = match function
+            .positions
+            .partition_point(|(wasm, _)| *wasm <= addr)
+        {
+            0 => 0,
+            n => function.positions[n - 1].1,
+        };
+        Some((index, function.address + offset))
+    }
+}
+
+/// A row of a line program of the module
+#[derive(Clone, Copy)]
+struct WasmRow {
+    address: u64,
+    file: u64,
+    line: u64,
+    column: u64,
+    is_stmt: bool,
+}
+
+/// Translates the DWARF custom sections of a module, returns the sections
+/// to put in the object file or `None` if the module has none
+pub(crate) fn translate(
+    module_info: &ModuleInfo,
+    transform: &AddressTransform,
+) -> Option<Vec<(&'static str, Vec<u8>)>> {
+    if !module_info.custom_sections.contains_key(".debug_info") {
+        return None;
+    }
+    let section = |id: gimli::SectionId| -> Result<Reader<'_>, gimli::Error> {
+        let data = module_info
+            .custom_sections
+            .get(id.name())
+            .map(|index| &*module_info.custom_sections_data[*index])
+            .unwrap_or_default();
+        Ok(EndianSlice::new(data, LittleEndian))
+    };
+    let dwarf = read::Dwarf::load(section).ok()?;
+
+    let mut output = write::Dwarf::new();
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        let Ok(unit) = dwarf.unit(header) else {
+            continue;
+        };
+        // A unit that can not be read is left out, the others are still
+        // useful
+        let _ = translate_unit(&dwarf, &unit, transform, &mut output);
+    }
+
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    output.write(&mut sections).ok()?;
+    let mut translated = Vec::new();
+    sections
+        .for_each(|id, data| {
+            if !data.slice().is_empty() {
+                translated.push((id.name(), data.slice().to_vec()));
+            }
+            Ok::<_, ()>(())
+        })
+        .ok()?;
+    Some(translated)
+}
+
+fn attr_string(
+    dwarf: &read::Dwarf<Reader<'_>>,
+    unit: &read::Unit<Reader<'_>>,
+    value: Option<read::AttributeValue<Reader<'_>>>,
+) -> Option<Vec<u8>> {
+    let name = dwarf.attr_string(unit, value?).ok()?;
+    Some(name.slice().to_vec()).filter(|name| !name.is_empty() && !name.contains(&0))
+}
+
+fn translate_unit(
+    dwarf: &read::Dwarf<Reader<'_>>,
+    unit: &read::Unit<Reader<'_>>,
+    transform: &AddressTransform,
+    output: &mut write::Dwarf,
+) -> read::Result<()> {
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 4,
+        address_size: 8,
+    };
+    let root = {
+        let mut entries = unit.entries();
+        let Some((_, root)) = entries.next_dfs()? else {
+            return Ok(());
+        };
+        root.clone()
+    };
+    let name = attr_string(dwarf, unit, root.attr_value(constants::DW_AT_name)?);
+    let comp_dir = attr_string(dwarf, unit, root.attr_value(constants::DW_AT_comp_dir)?);
+
+    let mut program = LineProgram::new(
+        encoding,
+        LineEncoding::default(),
+        LineString::String(comp_dir.clone().unwrap_or_default()),
+        LineString::String(name.clone().unwrap_or_default()),
+        None,
+    );
+    let files = match &unit.line_program {
+        Some(line_program) => translate_lines(dwarf, unit, line_program, transform, &mut program)?,
+        None => HashMap::new(),
+    };
+
+    let unit_id = output.units.add(write::Unit::new(encoding, program));
+    let out = output.units.get_mut(unit_id);
+    let out_root = out.root();
+    {
+        let entry = out.get_mut(out_root);
+        if let Some(name) = name {
+            entry.set(constants::DW_AT_name, AttributeValue::String(name));
+        }
+        if let Some(comp_dir) = comp_dir {
+            entry.set(constants::DW_AT_comp_dir, AttributeValue::String(comp_dir));
+        }
+        if let Some(producer) =
+            attr_string(dwarf, unit, root.attr_value(constants::DW_AT_producer)?)
+        {
+            entry.set(constants::DW_AT_producer, AttributeValue::String(producer));
+        }
+        if let Some(read::AttributeValue::Language(language)) =
+            root.attr_value(constants::DW_AT_language)?
+        {
+            entry.set(
+                constants::DW_AT_language,
+                AttributeValue::Language(language),
+            );
+        }
+        entry.set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0)),
+        );
+    }
+
+    let mut ranges = Vec::new();
+    let mut entries = unit.entries();
+    while let Some((_, entry)) = entries.next_dfs()? {
+        if entry.tag() != constants::DW_TAG_subprogram {
+            continue;
+        }
+        let Some(read::AttributeValue::Addr(low_pc)) = entry.attr_value(constants::DW_AT_low_pc)?
+        else {
+            continue;
+        };
+        let Some((index, address)) = transform.translate(low_pc) else {
+            continue;
+        };
+        let function = &transform.functions[index];
+        let len = function.address + function.len - address;
+        ranges.push(Range::StartLength {
+            begin: Address::Constant(address),
+            length: len,
+        });
+
+        // Declarations are split from definitions in C++, the name is on
+        // the declaration
+        let origin = [
+            constants::DW_AT_specification,
+            constants::DW_AT_abstract_origin,
+        ]
+        .into_iter()
+        .find_map(|attr| match entry.attr_value(attr) {
+            Ok(Some(read::AttributeValue::UnitRef(offset))) => unit.entry(offset).ok(),
+            _ => None,
+        });
+        let lookup = |attr| -> read::Result<Option<read::AttributeValue<Reader<'_>>>> {
+            match entry.attr_value(attr)? {
+                Some(value) => Ok(Some(value)),
+                None => match &origin {
+                    Some(origin) => origin.attr_value(attr),
+                    None => Ok(None),
+                },
+            }
+        };
+
+        let id: UnitEntryId = out.add(out_root, constants::DW_TAG_subprogram);
+        let subprogram = out.get_mut(id);
+        if let Some(name) = attr_string(dwarf, unit, lookup(constants::DW_AT_name)?) {
+            subprogram.set(constants::DW_AT_name, AttributeValue::String(name));
+        }
+        let linkage_name = match lookup(constants::DW_AT_linkage_name)? {
+            Some(value) => Some(value),
+            None => lookup(constants::DW_AT_MIPS_linkage_name)?,
+        };
+        if let Some(linkage_name) = attr_string(dwarf, unit, linkage_name) {
+            subprogram.set(
+                constants::DW_AT_linkage_name,
+                AttributeValue::String(linkage_name),
+            );
+        }
+        if let Some(read::AttributeValue::FileIndex(file)) = lookup(constants::DW_AT_decl_file)? {
+            if let Some(file) = files.get(&file) {
+                subprogram.set(
+                    constants::DW_AT_decl_file,
+                    AttributeValue::FileIndex(Some(*file)),
+                );
+            }
+        }
+        if let Some(line) = lookup(constants::DW_AT_decl_line)?.and_then(|v| v.udata_value()) {
+            subprogram.set(constants::DW_AT_decl_line, AttributeValue::Udata(line));
+        }
+        subprogram.set(
+            constants::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(address)),
+        );
+        subprogram.set(constants::DW_AT_high_pc, AttributeValue::Udata(len));
+        subprogram.set(constants::DW_AT_external, AttributeValue::Flag(true));
+    }
+
+    let ranges = out.ranges.add(RangeList(ranges));
+    out.get_mut(out_root).set(
+        constants::DW_AT_ranges,
+        AttributeValue::RangeListRef(ranges),
+    );
+    Ok(())
+}
+
+/// Copies the files of a line program and emits one sequence for every
+/// function that has rows, returns the new identifiers of the files
+fn translate_lines(
+    dwarf: &read::Dwarf<Reader<'_>>,
+    unit: &read::Unit<Reader<'_>>,
+    line_program: &read::IncompleteLineProgram<Reader<'_>>,
+    transform: &AddressTransform,
+    program: &mut LineProgram,
+) -> read::Result<HashMap<u64, FileId>> {
+    let header = line_program.header();
+    let string = |value| -> Option<Vec<u8>> {
+        let value = dwarf.attr_string(unit, value).ok()?;
+        Some(value.slice().to_vec()).filter(|s| !s.is_empty() && !s.contains(&0))
+    };
+
+    let mut directories = HashMap::<u64, DirectoryId>::new();
+    let mut files = HashMap::new();
+    // File indices start at 1 before DWARF 5
+    let first_file = if header.version() >= 5 { 0 } else { 1 };
+    for (index, file) in header.file_names().iter().enumerate() {
+        let Some(name) = string(file.path_name()) else {
+            continue;
+        };
+        let directory = match directories.get(&file.directory_index()) {
+            Some(directory) => *directory,
+            None => {
+                let directory = match file.directory(header).and_then(string) {
+                    Some(directory) => program.add_directory(LineString::String(directory)),
+                    None => program.default_directory(),
+                };
+                directories.insert(file.directory_index(), directory);
+                directory
+            }
+        };
+        let id = program.add_file(LineString::String(name), directory, None);
+        files.insert(index as u64 + first_file, id);
+    }
+
+    let mut rows = Vec::new();
+    let mut state = line_program.clone().rows();
+    while let Some((_, row)) = state.next_row()? {
+        if row.end_sequence() {
+            continue;
+        }
+        let Some(line) = row.line() else {
+            continue;
+        };
+        let column = match row.column() {
+            gimli::ColumnType::LeftEdge => 0,
+            gimli::ColumnType::Column(column) => column.get(),
+        };
+        rows.push(WasmRow {
+            address: row.address(),
+            file: row.file_index(),
+            line: line.get(),
+            column,
+            is_stmt: row.is_stmt(),
+        });
+    }
+    rows.sort_by_key(|row| row.address);
+
+    for function in &transform.functions {
+        let first = rows.partition_point(|row| row.address < function.wasm_start);
+        let last = rows.partition_point(|row| row.address < function.wasm_end);
+        let rows = &rows[first..last];
+        if rows.is_empty() {
+            continue;
+        }
+
+        // Every native instruction takes the row that covers the
+        // WebAssembly instruction it was compiled from
+        let mut native: Vec<(u64, WasmRow)> = function
+            .positions
+            .iter()
+            .filter_map(|(wasm, code_offset)| {
+                let n = rows.partition_point(|row| row.address <= *wasm);
+                Some((*code_offset, rows[n.checked_sub(1)?]))
+            })
+            .collect();
+        native.sort_by_key(|(code_offset, _)| *code_offset);
+        // The prologue has no source location, it belongs to the first row
+        // so that breakpoints on the function entry are hit
+        if native
+            .first()
+            .map_or(true, |(code_offset, _)| *code_offset > 0)
+        {
+            native.insert(0, (0, rows[0]));
+        }
+
+        program.begin_sequence(Some(Address::Constant(function.address)));
+        let mut previous: Option<WasmRow> = None;
+        for (code_offset, row) in native {
+            let Some(file) = files.get(&row.file) else {
+                continue;
+            };
+            if previous
+                .is_some_and(|p| (p.file, p.line, p.column) == (row.file, row.line, row.column))
+            {
+                continue;
+            }
+            previous = Some(row);
+            let out = program.row();
+            out.address_offset = code_offset;
+            out.file = *file;
+            out.line = row.line;
+            out.column = row.column;
+            out.is_statement = row.is_stmt;
+            program.generate_row();
+        }
+        program.end_sequence(function.len);
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address_map::InstructionAddressMap;
+    use wasmer_types::SourceLoc;
+
+    fn address_map(start: u32, end: u32, positions: &[(u32, usize)]) -> FunctionAddressMap {
+        FunctionAddressMap {
+            instructions: positions
+                .iter()
+                .map(|(srcloc, code_offset)| InstructionAddressMap {
+                    srcloc: SourceLoc::new(*srcloc),
+                    code_offset: *code_offset,
+                    code_len: 4,
+                })
+                .collect(),
+            start_srcloc: SourceLoc::new(start),
+            end_srcloc: SourceLoc::new(end),
+            body_offset: 0,
+            body_len: 0,
+            local_types: vec![],
+            local_locations: vec![],
+        }
+    }
+
+    #[test]
+    fn code_section_offsets_map_to_native_code() {
+        // The code section starts at 100 in the module, after the number of
+        // functions and the size of the first body
+        let first = address_map(102, 110, &[(104, 0), (106, 8)]);
+        let second = address_map(111, 120, &[(115, 4)]);
+        let maps: PrimaryMap<LocalFunctionIndex, _> = [&first, &second].into_iter().collect();
+        let native: PrimaryMap<LocalFunctionIndex, _> =
+            [(0x1000, 16), (0x2000, 32)].into_iter().collect();
+        let transform = AddressTransform::new(&maps, &native).unwrap();
+
+        // The low pc of a function points to its size
+        assert_eq!(transform.translate(1), Some((0, 0x1000)));
+        assert_eq!(transform.translate(4), Some((0, 0x1000)));
+        assert_eq!(transform.translate(7), Some((0, 0x1008)));
+        assert_eq!(transform.translate(9), Some((0, 0x1008)));
+        // The end of a function is where the size of the next one is
+        assert_eq!(transform.translate(10), Some((1, 0x2000)));
+        assert_eq!(transform.translate(15), Some((1, 0x2004)));
+        assert_eq!(transform.translate(20), Some((1, 0x2020)));
+        assert_eq!(transform.translate(21), None);
+        assert_eq!(transform.translate(0), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! A minimal ELF object file that describes code that is already in memory.
+//!
+//! The `.text` section takes no space in the file, its address is the
+//! address of the compiled code so that debuggers place the symbols (which
+//! are offsets into `.text`) and the DWARF sections (which use absolute
+//! addresses) on top of it.
+
+/// Machine of the ELF header for the architecture that runs the code, if
+/// it is one that debuggers are known to support
+pub(crate) fn native_machine() -> Option<u16> {
+    if cfg!(target_endian = "big") {
+        None
+    } else if cfg!(target_arch = "x86_64") {
+        Some(62) // EM_X86_64
+    } else if cfg!(target_arch = "aarch64") {
+        Some(183) // EM_AARCH64
+    } else if cfg!(target_arch = "riscv64") {
+        Some(243) // EM_RISCV
+    } else if cfg!(target_arch = "loongarch64") {
+        Some(258) // EM_LOONGARCH
+    } else {
+        None
+    }
+}
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOBITS: u32 = 8;
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+const STB_GLOBAL_STT_FUNC: u8 = 0x12;
+const TEXT_SECTION: u16 = 1;
+
+const HEADER_SIZE: usize = 64;
+const SECTION_HEADER_SIZE: usize = 64;
+const SYMBOL_SIZE: usize = 24;
+
+/// A function symbol, its address is an offset into `.text`
+pub(crate) struct ElfSymbol {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+struct Section {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+#[derive(Default)]
+struct StringTable(Vec<u8>);
+
+impl StringTable {
+    fn add(&mut self, name: &str) -> u32 {
+        if self.0.is_empty() {
+            self.0.push(0);
+        }
+        let offset = self.0.len() as u32;
+        self.0.extend(name.bytes().filter(|b| *b != 0));
+        self.0.push(0);
+        offset
+    }
+}
+
+/// Writes an object file for a machine whose `.text` section is loaded at
+/// `text_address` and is `text_size` bytes long
+pub(crate) fn write_elf(
+    machine: u16,
+    text_address: u64,
+    text_size: u64,
+    symbols: &[ElfSymbol],
+    debug_sections: &[(&str, Vec<u8>)],
+) -> Vec<u8> {
+    let mut section_names = StringTable::default();
+    let mut names = StringTable::default();
+
+    let mut contents = Vec::new();
+    let mut sections = Vec::new();
+
+    let push = |contents: &mut Vec<u8>, data: &[u8], align: usize| {
+        while (HEADER_SIZE + contents.len()) % align != 0 {
+            contents.push(0);
+        }
+        let offset = (HEADER_SIZE + contents.len()) as u64;
+        contents.extend_from_slice(data);
+        offset
+    };
+
+    sections.push(Section {
+        name: section_names.add(""),
+        kind: 0,
+        flags: 0,
+        addr: 0,
+        offset: 0,
+        size: 0,
+        link: 0,
+        info: 0,
+        align: 0,
+        entsize: 0,
+    });
+    sections.push(Section {
+        name: section_names.add(".text"),
+        kind: SHT_NOBITS,
+        flags: SHF_ALLOC | SHF_EXECINSTR,
+        addr: text_address,
+        offset: HEADER_SIZE as u64,
+        size: text_size,
+        link: 0,
+        info: 0,
+        align: 16,
+        entsize: 0,
+    });
+
+    let mut symtab = vec![0u8; SYMBOL_SIZE];
+    for symbol in symbols {
+        symtab.extend_from_slice(&names.add(&symbol.name).to_le_bytes());
+        symtab.push(STB_GLOBAL_STT_FUNC);
+        symtab.push(0);
+        symtab.extend_from_slice(&TEXT_SECTION.to_le_bytes());
+        symtab.extend_from_slice(&symbol.offset.to_le_bytes());
+        symtab.extend_from_slice(&symbol.size.to_le_bytes());
+    }
+    let strtab_index = sections.len() as u32 + 1;
+    let offset = push(&mut contents, &symtab, 8);
+    sections.push(Section {
+        name: section_names.add(".symtab"),
+        kind: SHT_SYMTAB,
+        flags: 0,
+        addr: 0,
+        offset,
+        size: symtab.len() as u64,
+        link: strtab_index,
+        // Every symbol after the null one is global
+        info: 1,
+        align: 8,
+        entsize: SYMBOL_SIZE as u64,
+    });
+    let offset = push(&mut contents, &names.0, 1);
+    sections.push(Section {
+        name: section_names.add(".strtab"),
+        kind: SHT_STRTAB,
+        flags: 0,
+        addr: 0,
+        offset,
+        size: names.0.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entsize: 0,
+    });
+
+    for (name, data) in debug_sections {
+        let offset = push(&mut contents, data, 1);
+        sections.push(Section {
+            name: section_names.add(name),
+            kind: SHT_PROGBITS,
+            flags: 0,
+            addr: 0,
+            offset,
+            size: data.len() as u64,
+            link: 0,
+            info: 0,
+            align: 1,
+            entsize: 0,
+        });
+    }
+
+    let shstrtab_name = section_names.add(".shstrtab");
+    let offset = push(&mut contents, &section_names.0, 1);
+    let shstrtab_index = sections.len() as u16;
+    sections.push(Section {
+        name: shstrtab_name,
+        kind: SHT_STRTAB,
+        flags: 0,
+        addr: 0,
+        offset,
+        size: section_names.0.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entsize: 0,
+    });
+
+    let section_headers = push(&mut contents, &[], 8);
+
+    let mut elf =
+        Vec::with_capacity(HEADER_SIZE + contents.len() + sections.len() * SECTION_HEADER_SIZE);
+    // ELFCLASS64, ELFDATA2LSB, EV_CURRENT
+    elf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+    elf.extend_from_slice(&[0; 8]);
+    elf.extend_from_slice(&1u16.to_le_bytes()); // ET_REL
+    elf.extend_from_slice(&machine.to_le_bytes());
+    elf.extend_from_slice(&1u32.to_le_bytes());
+    elf.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+    elf.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+    elf.extend_from_slice(&section_headers.to_le_bytes());
+    elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+    elf.extend_from_slice(&(HEADER_SIZE as u16).to_le_bytes());
+    elf.extend_from_slice(&0u16.to_le_bytes()); // e_phentsize
+    elf.extend_from_slice(&0u16.to_le_bytes()); // e_phnum
+    elf.extend_from_slice(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+    elf.extend_from_slice(&(sections.len() as u16).to_le_bytes());
+    elf.extend_from_slice(&shstrtab_index.to_le_bytes());
+    debug_assert_eq!(elf.len(), HEADER_SIZE);
+
+    elf.extend_from_slice(&contents);
+    for section in &sections {
+        elf.extend_from_slice(&section.name.to_le_bytes());
+        elf.extend_from_slice(&section.kind.to_le_bytes());
+        elf.extend_from_slice(&section.flags.to_le_bytes());
+        elf.extend_from_slice(&section.addr.to_le_bytes());
+        elf.extend_from_slice(&section.offset.to_le_bytes());
+        elf.extend_from_slice(&section.size.to_le_bytes());
+        elf.extend_from_slice(&section.link.to_le_bytes());
+        elf.extend_from_slice(&section.info.to_le_bytes());
+        elf.extend_from_slice(&section.align.to_le_bytes());
+        elf.extend_from_slice(&section.entsize.to_le_bytes());
+    }
+    elf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::object::{Object, ObjectSection, ObjectSymbol};
+
+    #[test]
+    fn elf_describes_code_in_memory() {
+        let symbols = [
+            ElfSymbol {
+                name: "add".to_string(),
+                offset: 0,
+                size: 16,
+            },
+            ElfSymbol {
+                name: "wasm-function[1]".to_string(),
+                offset: 32,
+                size: 8,
+            },
+        ];
+        let elf = write_elf(62, 0x1000, 40, &symbols, &[(".debug_line", vec![1, 2, 3])]);
+
+        let file = ::object::File::parse(&*elf).unwrap();
+        let text = file.section_by_name(".text").unwrap();
+        assert_eq!((text.address(), text.size()), (0x1000, 40));
+        assert_eq!(
+            file.section_by_name(".debug_line").unwrap().data().unwrap(),
+            &[1, 2, 3]
+        );
+        let names: Vec<_> = file
+            .symbols()
+            .filter(|symbol| symbol.is_definition())
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.address()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("add".to_string(), 0), ("wasm-function[1]".to_string(), 32)]
+        );
+    }
+}
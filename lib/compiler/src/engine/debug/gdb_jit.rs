@@ -0,0 +1,134 @@
+//! Registration of in-memory object files through the GDB JIT interface.
+//!
+//! Debuggers put a breakpoint on `__jit_debug_register_code` and, every
+//! time it is called, read the list of object files that starts at
+//! `__jit_debug_descriptor`. Both GDB and LLDB follow this protocol, which
+//! is described in <https://sourceware.org/gdb/current/onlinedocs/gdb.html/JIT-Interface.html>.
+
+use std::{pin::Pin, ptr, sync::Mutex};
+
+const JIT_NOACTION: u32 = 0;
+const JIT_REGISTER_FN: u32 = 1;
+const JIT_UNREGISTER_FN: u32 = 2;
+
+#[repr(C)]
+struct JitCodeEntry {
+    next_entry: *mut JitCodeEntry,
+    prev_entry: *mut JitCodeEntry,
+    symfile_addr: *const u8,
+    symfile_size: u64,
+}
+
+#[repr(C)]
+struct JitDescriptor {
+    version: u32,
+    action_flag: u32,
+    relevant_entry: *mut JitCodeEntry,
+    first_entry: *mut JitCodeEntry,
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+static mut __jit_debug_descriptor: JitDescriptor = JitDescriptor {
+    version: 1,
+    action_flag: JIT_NOACTION,
+    relevant_entry: ptr::null_mut(),
+    first_entry: ptr::null_mut(),
+};
+
+/// Debuggers set a breakpoint on this function, so it must not be inlined
+/// or optimized away.
+#[no_mangle]
+#[inline(never)]
+extern "C" fn __jit_debug_register_code() {
+    let x = 0u8;
+    unsafe {
+        ptr::read_volatile(&x);
+    }
+}
+
+/// Serializes the changes to the list of object files, the debugger only
+/// looks at it while the process is stopped in `__jit_debug_register_code`.
+static REGISTRATION: Mutex<()> = Mutex::new(());
+
+/// An object file that has been registered with the debuggers, it is
+/// unregistered when dropped.
+pub struct GdbJitImageRegistration {
+    entry: Pin<Box<JitCodeEntry>>,
+    file: Pin<Box<[u8]>>,
+}
+
+impl GdbJitImageRegistration {
+    /// Registers an object file that describes code in this process.
+    pub fn register(file: Vec<u8>) -> Self {
+        let file = Pin::new(file.into_boxed_slice());
+        let mut entry = Box::pin(JitCodeEntry {
+            next_entry: ptr::null_mut(),
+            prev_entry: ptr::null_mut(),
+            symfile_addr: file.as_ptr(),
+            symfile_size: file.len() as u64,
+        });
+        unsafe {
+            register_entry(&mut *entry);
+        }
+        Self { entry, file }
+    }
+
+    /// The object file that was registered.
+    pub fn file(&self) -> &[u8] {
+        &self.file
+    }
+}
+
+impl Drop for GdbJitImageRegistration {
+    fn drop(&mut self) {
+        unsafe {
+            unregister_entry(&mut *self.entry);
+        }
+    }
+}
+
+// The entry is only reached through the global list while the lock is held.
+unsafe impl Send for GdbJitImageRegistration {}
+unsafe impl Sync for GdbJitImageRegistration {}
+
+unsafe fn register_entry(entry: *mut JitCodeEntry) {
+    let _guard = REGISTRATION.lock().unwrap_or_else(|e| e.into_inner());
+    let descriptor = ptr::addr_of_mut!(__jit_debug_descriptor);
+
+    let first = (*descriptor).first_entry;
+    (*entry).next_entry = first;
+    if !first.is_null() {
+        (*first).prev_entry = entry;
+    }
+    (*descriptor).first_entry = entry;
+
+    (*descriptor).relevant_entry = entry;
+    (*descriptor).action_flag = JIT_REGISTER_FN;
+    __jit_debug_register_code();
+    (*descriptor).action_flag = JIT_NOACTION;
+    (*descriptor).relevant_entry = ptr::null_mut();
+}
+
+unsafe fn unregister_entry(entry: *mut JitCodeEntry) {
+    let _guard = REGISTRATION.lock().unwrap_or_else(|e| e.into_inner());
+    let descriptor = ptr::addr_of_mut!(__jit_debug_descriptor);
+
+    let prev = (*entry).prev_entry;
+    let next = (*entry).next_entry;
+    if prev.is_null() {
+        (*descriptor).first_entry = next;
+    } else {
+        (*prev).next_entry = next;
+    }
+    if !next.is_null() {
+        (*next).prev_entry = prev;
+    }
+
+    // The entry is unlinked before the debugger is told about it
+    (*descriptor).relevant_entry = entry;
+    (*descriptor).action_flag = JIT_UNREGISTER_FN;
+    __jit_debug_register_code();
+    (*descriptor).action_flag = JIT_NOACTION;
+    (*descriptor).relevant_entry = ptr::null_mut();
+}
@@ -0,0 +1,68 @@
+//! Debug information for the code compiled by the engine.
+//!
+//! When it is enabled in the compiler config, every artifact that is
+//! loaded describes its functions in an ELF object file that is registered
+//! through the GDB JIT interface, so that GDB and LLDB can show the names of
+//! the WebAssembly functions in backtraces. If the module carries DWARF,
+//! its line programs and subprograms are translated to the native code so
+//! that breakpoints can be set on source lines.
+
+mod dwarf;
+mod elf;
+mod gdb_jit;
+
+pub use self::gdb_jit::GdbJitImageRegistration;
+
+use self::{
+    dwarf::AddressTransform,
+    elf::{native_machine, write_elf, ElfSymbol},
+};
+use crate::{types::function::CompiledFunctionFrameInfo, FunctionExtent};
+use wasmer_types::{entity::PrimaryMap, LocalFunctionIndex, ModuleInfo};
+
+/// Builds the object file that describes the functions of a module and
+/// registers it with the debuggers.
+///
+/// Returns `None` if the architecture is not supported or the module has
+/// no functions.
+pub(crate) fn register_debug_info(
+    finished_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+    module_info: &ModuleInfo,
+    frame_infos: &PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+) -> Option<GdbJitImageRegistration> {
+    let machine = native_machine()?;
+    let native: PrimaryMap<LocalFunctionIndex, (u64, u64)> = finished_functions
+        .values()
+        .map(|extent| (extent.ptr.0 as *const u8 as u64, extent.length as u64))
+        .collect();
+    let start = native.values().map(|(address, _)| *address).min()?;
+    let end = native.values().map(|(address, len)| address + len).max()?;
+
+    let symbols: Vec<_> = native
+        .iter()
+        .map(|(index, (address, len))| {
+            let func_index = module_info.func_index(index);
+            let name = match module_info.function_names.get(&func_index) {
+                Some(name) => name.clone(),
+                None => format!("wasm-function[{}]", func_index.as_u32()),
+            };
+            ElfSymbol {
+                name,
+                offset: address - start,
+                size: *len,
+            }
+        })
+        .collect();
+
+    // Compilers that don't keep the source locations still get symbols
+    let address_maps: PrimaryMap<LocalFunctionIndex, _> = frame_infos
+        .values()
+        .map(|frame_info| &frame_info.address_map)
+        .collect();
+    let sections = AddressTransform::new(&address_maps, &native)
+        .and_then(|transform| dwarf::translate(module_info, &transform))
+        .unwrap_or_default();
+
+    let file = write_elf(machine, start, end - start, &symbols, &sections);
+    Some(GdbJitImageRegistration::register(file))
+}
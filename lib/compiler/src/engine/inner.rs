@@ -1,6 +1,8 @@
 //! Universal compilation.
 
 use crate::engine::builder::EngineBuilder;
+#[cfg(all(not(target_arch = "wasm32"), feature = "gdb-jit"))]
+use crate::types::function::CompiledFunctionFrameInfo;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::{
     types::{
@@ -536,6 +538,37 @@ impl EngineInner {
             .register_frame_info(frame_info);
     }
 
+    #[cfg(all(not(target_arch = "wasm32"), feature = "gdb-jit"))]
+    /// Whether the compiler was asked to register debug info for the code
+    pub(crate) fn debug_info_enabled(&self) -> bool {
+        #[cfg(feature = "compiler")]
+        return self
+            .compiler
+            .as_ref()
+            .is_some_and(|v| v.get_debug_info_enabled());
+        #[cfg(not(feature = "compiler"))]
+        false
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), feature = "gdb-jit"))]
+    /// Register the functions of the code memory with debuggers through the
+    /// GDB JIT interface
+    pub(crate) fn register_debug_info(
+        &mut self,
+        finished_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+        module_info: &ModuleInfo,
+        frame_infos: &PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+    ) {
+        if let Some(registration) =
+            super::debug::register_debug_info(finished_functions, module_info, frame_infos)
+        {
+            self.code_memory
+                .last_mut()
+                .unwrap()
+                .register_debug_info(registration);
+        }
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub(crate) fn register_perfmap(
         &self,
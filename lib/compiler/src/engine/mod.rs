@@ -13,6 +13,8 @@ mod artifact;
 mod builder;
 #[cfg(not(target_arch = "wasm32"))]
 mod code_memory;
+#[cfg(all(not(target_arch = "wasm32"), feature = "gdb-jit"))]
+mod debug;
 mod inner;
 #[cfg(not(target_arch = "wasm32"))]
 mod link;
@@ -32,6 +34,8 @@ pub use self::artifact::Artifact;
 pub use self::builder::EngineBuilder;
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::code_memory::CodeMemory;
+#[cfg(all(not(target_arch = "wasm32"), feature = "gdb-jit"))]
+pub use self::debug::GdbJitImageRegistration;
 pub use self::inner::{Engine, EngineInner};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::link::link_module;
@@ -14,7 +14,7 @@
 
 use crate::types::address_map::{
     ArchivedFunctionAddressMap, ArchivedInstructionAddressMap, FunctionAddressMap,
-    InstructionAddressMap,
+    InstructionAddressMap, LocalLocation, ValueLocation,
 };
 use crate::types::function::{ArchivedCompiledFunctionFrameInfo, CompiledFunctionFrameInfo};
 use crate::ArtifactBuildFromArchive;
@@ -24,7 +24,7 @@ use std::sync::{Arc, LazyLock, RwLock};
 use wasmer_types::lib::std::{cmp, ops::Deref};
 use wasmer_types::{
     entity::{BoxedSlice, EntityRef, PrimaryMap},
-    FrameInfo, LocalFunctionIndex, ModuleInfo, SourceLoc, TrapInformation,
+    FrameInfo, LocalFunctionIndex, ModuleInfo, SourceLoc, TrapInformation, Type,
 };
 use wasmer_vm::FunctionBodyPtr;
 
@@ -159,6 +159,76 @@ impl GlobalFrameInfo {
         Some(traps[idx])
     }
 
+    /// Fetches the locals of the function a backtrace is in at `pc`, with
+    /// the type of each and where it is kept at that point.
+    ///
+    /// `pc` is a return address, or the address of the instruction about
+    /// to run in the innermost frame. Locals are only known for code that
+    /// was compiled with debug info, the list is empty otherwise.
+    pub fn lookup_locals(&self, pc: usize) -> Option<Vec<(Type, Option<ValueLocation>)>> {
+        let module = self.module_info(pc)?;
+        let func = module.function_info(pc)?;
+        let rel_pos = pc - func.start;
+        let debug_info = module.function_debug_info(func.local_index);
+        let instr_map = debug_info.address_map();
+        Some(
+            instr_map
+                .local_types()
+                .into_iter()
+                .enumerate()
+                .map(|(local, ty)| (ty, instr_map.local_location(local as u32, rel_pos)))
+                .collect(),
+        )
+    }
+
+    /// Checks whether `pc` is in the code of the given module.
+    pub fn is_module_code(&self, module: &ModuleInfo, pc: usize) -> bool {
+        self.module_info(pc)
+            .is_some_and(|info| std::ptr::eq(&*info.module, module))
+    }
+
+    /// Gets the entry points of all the compiled functions of a module.
+    pub fn function_entries(&self, module: &ModuleInfo) -> Vec<usize> {
+        self.ranges
+            .values()
+            .filter(|info| std::ptr::eq(&*info.module, module))
+            .flat_map(|info| info.functions.values().map(|func| func.start))
+            .collect()
+    }
+
+    /// Finds the first machine instruction that was compiled from the
+    /// WebAssembly instruction at `offset` in the module, this is where a
+    /// debugger puts a breakpoint on that instruction.
+    ///
+    /// If no machine code was emitted for the instruction at `offset`, the
+    /// next instruction of the same function that has any is used instead.
+    pub fn lookup_native_address(&self, module: &ModuleInfo, offset: u32) -> Option<usize> {
+        for info in self
+            .ranges
+            .values()
+            .filter(|info| std::ptr::eq(&*info.module, module))
+        {
+            for func in info.functions.values() {
+                let debug_info = info.function_debug_info(func.local_index);
+                let instr_map = debug_info.address_map();
+                if offset < instr_map.start_srcloc().bits()
+                    || offset >= instr_map.end_srcloc().bits()
+                {
+                    continue;
+                }
+
+                let instructions = instr_map.instructions();
+                let code_offset = (0..instructions.len())
+                    .map(|index| instructions.get(index))
+                    .filter(|instr| !instr.srcloc.is_default() && instr.srcloc.bits() >= offset)
+                    .min_by_key(|instr| (instr.srcloc.bits(), instr.code_offset))
+                    .map_or(0, |instr| instr.code_offset);
+                return Some(func.start + code_offset);
+            }
+        }
+        None
+    }
+
     /// Gets a module given a pc
     fn module_info(&self, pc: usize) -> Option<&ModuleInfoFrameInfo> {
         let (end, module_info) = self.ranges.range(pc..).next()?;
@@ -317,6 +387,34 @@ impl FunctionAddressMapVariant<'_> {
             FunctionAddressMapVariant::Archived(map) => map.body_len.to_native() as usize,
         }
     }
+
+    pub fn local_types(&self) -> Vec<Type> {
+        match self {
+            FunctionAddressMapVariant::Ref(map) => map.local_types.clone(),
+            FunctionAddressMapVariant::Archived(map) => {
+                rkyv::deserialize::<_, rkyv::rancor::Error>(&map.local_types).unwrap()
+            }
+        }
+    }
+
+    pub fn local_location(&self, local: u32, offset: usize) -> Option<ValueLocation> {
+        match self {
+            FunctionAddressMapVariant::Ref(map) => map.local_location(local, offset),
+            FunctionAddressMapVariant::Archived(map) => map
+                .local_locations
+                .iter()
+                .find(|l| {
+                    l.local.to_native() == local
+                        && l.start.to_native() as usize <= offset
+                        && offset < l.end.to_native() as usize
+                })
+                .map(|l| {
+                    rkyv::deserialize::<LocalLocation, rkyv::rancor::Error>(l)
+                        .unwrap()
+                        .location
+                }),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -337,6 +435,17 @@ impl FunctionAddressMapInstructionVariant<'_> {
         }
     }
 
+    pub fn len(&self) -> usize {
+        match self {
+            FunctionAddressMapInstructionVariant::Owned(instructions) => instructions.len(),
+            FunctionAddressMapInstructionVariant::Archived(instructions) => instructions.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn get(&self, index: usize) -> InstructionAddressMap {
         match self {
             FunctionAddressMapInstructionVariant::Owned(instructions) => instructions[index],
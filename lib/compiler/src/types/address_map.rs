@@ -11,7 +11,7 @@ use crate::lib::std::vec::Vec;
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
-use wasmer_types::SourceLoc;
+use wasmer_types::{SourceLoc, Type};
 
 /// Single source location to generated address mapping.
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -29,6 +29,42 @@ pub struct InstructionAddressMap {
     pub code_len: usize,
 }
 
+/// Where the native code keeps a value.
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+#[derive(RkyvSerialize, RkyvDeserialize, Archive, Debug, Clone, Copy, PartialEq, Eq)]
+#[rkyv(derive(Debug))]
+pub enum ValueLocation {
+    /// In the register with this DWARF register number.
+    Register(u16),
+
+    /// In the stack slot at this offset from the canonical frame address.
+    FrameOffset(i64),
+}
+
+/// Where a WebAssembly local lives over a range of the generated code.
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
+#[derive(RkyvSerialize, RkyvDeserialize, Archive, Debug, Clone, Copy, PartialEq, Eq)]
+#[rkyv(derive(Debug))]
+pub struct LocalLocation {
+    /// Index of the local, counting the parameters first.
+    pub local: u32,
+
+    /// Start of the range, relative to the function body.
+    ///
+    /// Offsets are those of the end of an instruction, so a return
+    /// address or the address of the instruction about to run is in the
+    /// range when `start <= address < end`.
+    pub start: usize,
+
+    /// End of the range (exclusive).
+    pub end: usize,
+
+    /// Where the local is kept over the range.
+    pub location: ValueLocation,
+}
+
 /// Function and its instructions addresses mappings.
 #[cfg_attr(feature = "artifact-size", derive(loupe::MemoryUsage))]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -50,4 +86,25 @@ pub struct FunctionAddressMap {
 
     /// Generated function body length.
     pub body_len: usize,
+
+    /// Types of the function's locals, counting the parameters first.
+    ///
+    /// Only recorded when the compiler was asked for debug info.
+    pub local_types: Vec<Type>,
+
+    /// Where the function's locals live in the generated code.
+    ///
+    /// Only recorded when the compiler was asked for debug info. A local
+    /// without a range covering an address is not available there.
+    pub local_locations: Vec<LocalLocation>,
+}
+
+impl FunctionAddressMap {
+    /// Returns where `local` is kept at `offset` into the function body.
+    pub fn local_location(&self, local: u32, offset: usize) -> Option<ValueLocation> {
+        self.local_locations
+            .iter()
+            .find(|l| l.local == local && l.start <= offset && offset < l.end)
+            .map(|l| l.location)
+    }
 }
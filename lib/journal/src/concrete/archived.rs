@@ -90,6 +90,11 @@ pub enum JournalEntryRecordType {
     DuplicateFileDescriptorV2 = 62,
     FileDescriptorSetFdFlagsV1 = 63,
     SocketPairV1 = 64,
+    FileDescriptorFallocateV1 = 65,
+    SocketBindUnixV1 = 66,
+    SocketConnectUnixV1 = 67,
+    PathSetXattrV1 = 68,
+    PathRemoveXattrV1 = 69,
 }
 
 impl JournalEntryRecordType {
@@ -175,6 +180,15 @@ impl JournalEntryRecordType {
             JournalEntryRecordType::FileDescriptorAllocateV1 => {
                 ArchivedJournalEntry::FileDescriptorAllocateV1(rkyv::access_unchecked(data))
             }
+            JournalEntryRecordType::FileDescriptorFallocateV1 => {
+                ArchivedJournalEntry::FileDescriptorFallocateV1(rkyv::access_unchecked(data))
+            }
+            JournalEntryRecordType::PathSetXattrV1 => {
+                ArchivedJournalEntry::PathSetXattrV1(rkyv::access_unchecked(data))
+            }
+            JournalEntryRecordType::PathRemoveXattrV1 => {
+                ArchivedJournalEntry::PathRemoveXattrV1(rkyv::access_unchecked(data))
+            }
             JournalEntryRecordType::CreateHardLinkV1 => {
                 ArchivedJournalEntry::CreateHardLinkV1(rkyv::access_unchecked(data))
             }
@@ -244,6 +258,12 @@ impl JournalEntryRecordType {
             JournalEntryRecordType::SocketConnectedV1 => {
                 ArchivedJournalEntry::SocketConnectedV1(rkyv::access_unchecked(data))
             }
+            JournalEntryRecordType::SocketBindUnixV1 => {
+                ArchivedJournalEntry::SocketBindUnixV1(rkyv::access_unchecked(data))
+            }
+            JournalEntryRecordType::SocketConnectUnixV1 => {
+                ArchivedJournalEntry::SocketConnectUnixV1(rkyv::access_unchecked(data))
+            }
             JournalEntryRecordType::SocketAcceptedV1 => {
                 ArchivedJournalEntry::SocketAcceptedV1(rkyv::access_unchecked(data))
             }
@@ -332,6 +352,11 @@ impl<'a> JournalEntry<'a> {
             Self::FileDescriptorAllocateV1 { .. } => {
                 JournalEntryRecordType::FileDescriptorAllocateV1
             }
+            Self::FileDescriptorFallocateV1 { .. } => {
+                JournalEntryRecordType::FileDescriptorFallocateV1
+            }
+            Self::PathSetXattrV1 { .. } => JournalEntryRecordType::PathSetXattrV1,
+            Self::PathRemoveXattrV1 { .. } => JournalEntryRecordType::PathRemoveXattrV1,
             Self::CreateHardLinkV1 { .. } => JournalEntryRecordType::CreateHardLinkV1,
             Self::CreateSymbolicLinkV1 { .. } => JournalEntryRecordType::CreateSymbolicLinkV1,
             Self::UnlinkFileV1 { .. } => JournalEntryRecordType::UnlinkFileV1,
@@ -357,6 +382,8 @@ impl<'a> JournalEntry<'a> {
             Self::SocketListenV1 { .. } => JournalEntryRecordType::SocketListenV1,
             Self::SocketBindV1 { .. } => JournalEntryRecordType::SocketBindV1,
             Self::SocketConnectedV1 { .. } => JournalEntryRecordType::SocketConnectedV1,
+            Self::SocketBindUnixV1 { .. } => JournalEntryRecordType::SocketBindUnixV1,
+            Self::SocketConnectUnixV1 { .. } => JournalEntryRecordType::SocketConnectUnixV1,
             Self::SocketAcceptedV1 { .. } => JournalEntryRecordType::SocketAcceptedV1,
             Self::SocketJoinIpv4MulticastV1 { .. } => {
                 JournalEntryRecordType::SocketJoinIpv4MulticastV1
@@ -637,6 +664,52 @@ impl<'a> JournalEntry<'a> {
                 &JournalEntryFileDescriptorAllocateV1 { fd, offset, len },
                 serializer,
             ),
+            JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            } => serialize_using(
+                &JournalEntryFileDescriptorFallocateV1 {
+                    fd,
+                    flags: flags.bits(),
+                    offset,
+                    len,
+                },
+                serializer,
+            ),
+            JournalEntry::PathSetXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+                value,
+                xflags,
+            } => serialize_using(
+                &JournalEntryPathSetXattrV1 {
+                    fd,
+                    flags,
+                    path: path.into(),
+                    name: name.into(),
+                    value: value.into(),
+                    xflags: xflags.bits(),
+                },
+                serializer,
+            ),
+            JournalEntry::PathRemoveXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+            } => serialize_using(
+                &JournalEntryPathRemoveXattrV1 {
+                    fd,
+                    flags,
+                    path: path.into(),
+                    name: name.into(),
+                },
+                serializer,
+            ),
             JournalEntry::CreateHardLinkV1 {
                 old_fd,
                 old_path,
@@ -809,6 +882,20 @@ impl<'a> JournalEntry<'a> {
                 },
                 serializer,
             ),
+            JournalEntry::SocketBindUnixV1 { fd, path } => serialize_using(
+                &JournalEntrySocketBindUnixV1 {
+                    fd,
+                    path: path.into(),
+                },
+                serializer,
+            ),
+            JournalEntry::SocketConnectUnixV1 { fd, path } => serialize_using(
+                &JournalEntrySocketConnectUnixV1 {
+                    fd,
+                    path: path.into(),
+                },
+                serializer,
+            ),
             JournalEntry::SocketAcceptedV1 {
                 listen_fd,
                 fd,
@@ -1004,6 +1091,9 @@ pub enum ArchivedJournalEntry<'a> {
     FileDescriptorSetRightsV1(&'a ArchivedJournalEntryFileDescriptorSetRightsV1),
     FileDescriptorAdviseV1(&'a ArchivedJournalEntryFileDescriptorAdviseV1),
     FileDescriptorAllocateV1(&'a ArchivedJournalEntryFileDescriptorAllocateV1),
+    FileDescriptorFallocateV1(&'a ArchivedJournalEntryFileDescriptorFallocateV1),
+    PathSetXattrV1(&'a ArchivedJournalEntryPathSetXattrV1<'a>),
+    PathRemoveXattrV1(&'a ArchivedJournalEntryPathRemoveXattrV1<'a>),
     CreateHardLinkV1(&'a ArchivedJournalEntryCreateHardLinkV1<'a>),
     CreateSymbolicLinkV1(&'a ArchivedJournalEntryCreateSymbolicLinkV1<'a>),
     UnlinkFileV1(&'a ArchivedJournalEntryUnlinkFileV1<'a>),
@@ -1029,6 +1119,8 @@ pub enum ArchivedJournalEntry<'a> {
     SocketListenV1(&'a ArchivedJournalEntrySocketListenV1),
     SocketBindV1(&'a ArchivedJournalEntrySocketBindV1),
     SocketConnectedV1(&'a ArchivedJournalEntrySocketConnectedV1),
+    SocketBindUnixV1(&'a ArchivedJournalEntrySocketBindUnixV1<'a>),
+    SocketConnectUnixV1(&'a ArchivedJournalEntrySocketConnectUnixV1<'a>),
     SocketAcceptedV1(&'a ArchivedJournalEntrySocketAcceptedV1),
     SocketJoinIpv4MulticastV1(&'a ArchivedJournalEntrySocketJoinIpv4MulticastV1),
     SocketJoinIpv6MulticastV1(&'a ArchivedJournalEntrySocketJoinIpv6MulticastV1),
@@ -1302,6 +1394,41 @@ pub struct JournalEntryFileDescriptorAllocateV1 {
     pub len: u64,
 }
 
+#[repr(C)]
+#[repr(align(8))]
+#[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
+#[rkyv(derive(Debug), attr(repr(align(8))))]
+pub struct JournalEntryFileDescriptorFallocateV1 {
+    pub fd: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub len: u64,
+}
+
+#[repr(C)]
+#[repr(align(8))]
+#[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
+#[rkyv(attr(repr(align(8))))]
+pub struct JournalEntryPathSetXattrV1<'a> {
+    pub fd: u32,
+    pub flags: u32,
+    pub path: AlignedCowStr<'a>,
+    pub name: AlignedCowStr<'a>,
+    pub value: AlignedCowVec<'a, u8>,
+    pub xflags: u32,
+}
+
+#[repr(C)]
+#[repr(align(8))]
+#[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
+#[rkyv(attr(repr(align(8))))]
+pub struct JournalEntryPathRemoveXattrV1<'a> {
+    pub fd: u32,
+    pub flags: u32,
+    pub path: AlignedCowStr<'a>,
+    pub name: AlignedCowStr<'a>,
+}
+
 #[repr(C)]
 #[repr(align(8))]
 #[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
@@ -1507,6 +1634,24 @@ pub struct JournalEntrySocketConnectedV1 {
     pub peer_addr: SocketAddr,
 }
 
+#[repr(C)]
+#[repr(align(8))]
+#[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
+#[rkyv(attr(repr(align(8))))]
+pub struct JournalEntrySocketBindUnixV1<'a> {
+    pub fd: u32,
+    pub path: AlignedCowStr<'a>,
+}
+
+#[repr(C)]
+#[repr(align(8))]
+#[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
+#[rkyv(attr(repr(align(8))))]
+pub struct JournalEntrySocketConnectUnixV1<'a> {
+    pub fd: u32,
+    pub path: AlignedCowStr<'a>,
+}
+
 #[repr(C)]
 #[repr(align(8))]
 #[derive(Debug, Clone, RkyvSerialize, RkyvDeserialize, Archive)]
@@ -1661,6 +1806,9 @@ pub enum JournalWhenceV1 {
     Cur,
     End,
     Unknown = 255,
+    // Added after `Unknown` so existing journals keep their encoding
+    Data = 3,
+    Hole = 4,
 }
 
 #[repr(C)]
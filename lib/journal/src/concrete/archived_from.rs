@@ -51,6 +51,8 @@ impl From<wasi::Whence> for JournalWhenceV1 {
             wasi::Whence::Set => JournalWhenceV1::Set,
             wasi::Whence::Cur => JournalWhenceV1::Cur,
             wasi::Whence::End => JournalWhenceV1::End,
+            wasi::Whence::Data => JournalWhenceV1::Data,
+            wasi::Whence::Hole => JournalWhenceV1::Hole,
             wasi::Whence::Unknown => JournalWhenceV1::Unknown,
         }
     }
@@ -62,6 +64,8 @@ impl From<JournalWhenceV1> for wasi::Whence {
             JournalWhenceV1::Set => wasi::Whence::Set,
             JournalWhenceV1::Cur => wasi::Whence::Cur,
             JournalWhenceV1::End => wasi::Whence::End,
+            JournalWhenceV1::Data => wasi::Whence::Data,
+            JournalWhenceV1::Hole => wasi::Whence::Hole,
             JournalWhenceV1::Unknown => wasi::Whence::Unknown,
         }
     }
@@ -73,6 +77,8 @@ impl From<&'_ ArchivedJournalWhenceV1> for wasi::Whence {
             ArchivedJournalWhenceV1::Set => wasi::Whence::Set,
             ArchivedJournalWhenceV1::Cur => wasi::Whence::Cur,
             ArchivedJournalWhenceV1::End => wasi::Whence::End,
+            ArchivedJournalWhenceV1::Data => wasi::Whence::Data,
+            ArchivedJournalWhenceV1::Hole => wasi::Whence::Hole,
             ArchivedJournalWhenceV1::Unknown => wasi::Whence::Unknown,
         }
     }
@@ -919,6 +925,45 @@ impl<'a> TryFrom<ArchivedJournalEntry<'a>> for JournalEntry<'a> {
                 offset: offset.to_native(),
                 len: len.to_native(),
             },
+            ArchivedJournalEntry::FileDescriptorFallocateV1(
+                ArchivedJournalEntryFileDescriptorFallocateV1 {
+                    fd,
+                    flags,
+                    offset,
+                    len,
+                },
+            ) => Self::FileDescriptorFallocateV1 {
+                fd: fd.to_native(),
+                flags: wasi::Fallocflags::from_bits_truncate(flags.to_native()),
+                offset: offset.to_native(),
+                len: len.to_native(),
+            },
+            ArchivedJournalEntry::PathSetXattrV1(ArchivedJournalEntryPathSetXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+                value,
+                xflags,
+            }) => Self::PathSetXattrV1 {
+                fd: fd.to_native(),
+                flags: flags.to_native(),
+                path: String::from_utf8_lossy(path.as_ref()),
+                name: String::from_utf8_lossy(name.as_ref()),
+                value: value.as_ref().into(),
+                xflags: wasi::Xattrflags::from_bits_truncate(xflags.to_native()),
+            },
+            ArchivedJournalEntry::PathRemoveXattrV1(ArchivedJournalEntryPathRemoveXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+            }) => Self::PathRemoveXattrV1 {
+                fd: fd.to_native(),
+                flags: flags.to_native(),
+                path: String::from_utf8_lossy(path.as_ref()),
+                name: String::from_utf8_lossy(name.as_ref()),
+            },
             ArchivedJournalEntry::CreateHardLinkV1(ArchivedJournalEntryCreateHardLinkV1 {
                 old_fd,
                 old_path,
@@ -1081,6 +1126,19 @@ impl<'a> TryFrom<ArchivedJournalEntry<'a>> for JournalEntry<'a> {
                 local_addr: local_addr.as_socket_addr(),
                 peer_addr: peer_addr.as_socket_addr(),
             },
+            ArchivedJournalEntry::SocketBindUnixV1(ArchivedJournalEntrySocketBindUnixV1 {
+                fd,
+                path,
+            }) => Self::SocketBindUnixV1 {
+                fd: fd.to_native(),
+                path: String::from_utf8_lossy(path.as_ref()),
+            },
+            ArchivedJournalEntry::SocketConnectUnixV1(
+                ArchivedJournalEntrySocketConnectUnixV1 { fd, path },
+            ) => Self::SocketConnectUnixV1 {
+                fd: fd.to_native(),
+                path: String::from_utf8_lossy(path.as_ref()),
+            },
             ArchivedJournalEntry::SocketAcceptedV1(ArchivedJournalEntrySocketAcceptedV1 {
                 listen_fd,
                 fd,
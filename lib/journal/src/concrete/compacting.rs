@@ -593,6 +593,7 @@ impl WritableJournal for CompactingJournalTx {
             // no longer suspect and thus it needs to be kept
             JournalEntry::FileDescriptorAdviseV1 { fd, .. }
             | JournalEntry::FileDescriptorAllocateV1 { fd, .. }
+            | JournalEntry::FileDescriptorFallocateV1 { fd, .. }
             | JournalEntry::FileDescriptorSetTimesV1 { fd, .. }
             | JournalEntry::FileDescriptorWriteV1 { fd, .. }
             | JournalEntry::FileDescriptorSetRightsV1 { fd, .. }
@@ -625,6 +626,8 @@ impl WritableJournal for CompactingJournalTx {
             | JournalEntry::FileDescriptorSetFdFlagsV1 { fd, .. }
             | JournalEntry::FileDescriptorSetFlagsV1 { fd, .. }
             | JournalEntry::SocketBindV1 { fd, .. }
+            | JournalEntry::SocketBindUnixV1 { fd, .. }
+            | JournalEntry::SocketConnectUnixV1 { fd, .. }
             | JournalEntry::SocketSendFileV1 { socket_fd: fd, .. }
             | JournalEntry::SocketSendToV1 { fd, .. }
             | JournalEntry::SocketSendV1 { fd, .. }
@@ -751,7 +754,9 @@ impl WritableJournal for CompactingJournalTx {
                 state.whitelist.insert(event_index);
             }
             // Update all the directory operations
-            JournalEntry::PathSetTimesV1 { path, .. } => {
+            JournalEntry::PathSetTimesV1 { path, .. }
+            | JournalEntry::PathSetXattrV1 { path, .. }
+            | JournalEntry::PathRemoveXattrV1 { path, .. } => {
                 let path = path.to_string();
                 if let Some(lookup) = state.create_directory.get(&path).cloned() {
                     state.append_to_sub_events(&lookup, event_index);
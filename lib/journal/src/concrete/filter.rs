@@ -285,6 +285,7 @@ impl<W: WritableJournal> WritableJournal for FilteredJournalTx<W> {
             | JournalEntry::FileDescriptorSetFlagsV1 { fd, .. }
             | JournalEntry::FileDescriptorAdviseV1 { fd, .. }
             | JournalEntry::FileDescriptorAllocateV1 { fd, .. }
+            | JournalEntry::FileDescriptorFallocateV1 { fd, .. }
             | JournalEntry::FileDescriptorSetRightsV1 { fd, .. }
             | JournalEntry::FileDescriptorSetTimesV1 { fd, .. }
             | JournalEntry::FileDescriptorSetSizeV1 { fd, .. } => {
@@ -307,6 +308,8 @@ impl<W: WritableJournal> WritableJournal for FilteredJournalTx<W> {
             | JournalEntry::PathRenameV1 { .. }
             | JournalEntry::CreateDirectoryV1 { .. }
             | JournalEntry::PathSetTimesV1 { .. }
+            | JournalEntry::PathSetXattrV1 { .. }
+            | JournalEntry::PathRemoveXattrV1 { .. }
             | JournalEntry::CreateHardLinkV1 { .. }
             | JournalEntry::CreateSymbolicLinkV1 { .. }
             | JournalEntry::ChangeDirectoryV1 { .. }
@@ -344,6 +347,8 @@ impl<W: WritableJournal> WritableJournal for FilteredJournalTx<W> {
             | JournalEntry::SocketListenV1 { .. }
             | JournalEntry::SocketBindV1 { .. }
             | JournalEntry::SocketConnectedV1 { .. }
+            | JournalEntry::SocketBindUnixV1 { .. }
+            | JournalEntry::SocketConnectUnixV1 { .. }
             | JournalEntry::SocketAcceptedV1 { .. }
             | JournalEntry::SocketJoinIpv4MulticastV1 { .. }
             | JournalEntry::SocketJoinIpv6MulticastV1 { .. }
@@ -189,6 +189,29 @@ impl<'a> fmt::Display for JournalEntry<'a> {
             JournalEntry::FileDescriptorAllocateV1 { fd, offset, len } => {
                 write!(f, "fd-allocate (fd={fd}, offset={offset}, len={len})")
             }
+            JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            } => write!(
+                f,
+                "fd-fallocate (fd={fd}, flags={flags:?}, offset={offset}, len={len})"
+            ),
+            JournalEntry::PathSetXattrV1 {
+                path,
+                name,
+                value,
+                xflags,
+                ..
+            } => write!(
+                f,
+                "path-set-xattr (path={path}, name={name}, len={}, flags={xflags:?})",
+                value.len()
+            ),
+            JournalEntry::PathRemoveXattrV1 { path, name, .. } => {
+                write!(f, "path-remove-xattr (path={path}, name={name})")
+            }
             JournalEntry::CreateHardLinkV1 {
                 old_path, new_path, ..
             } => write!(f, "path-link (from={old_path}, to={new_path})"),
@@ -250,6 +273,12 @@ impl<'a> fmt::Display for JournalEntry<'a> {
             } => {
                 write!(f, "sock-connect (fd={fd}, addr={local_addr}, peer={peer_addr})")
             }
+            JournalEntry::SocketBindUnixV1 { fd, path } => {
+                write!(f, "sock-bind-unix (fd={fd}, path={path})")
+            }
+            JournalEntry::SocketConnectUnixV1 { fd, path } => {
+                write!(f, "sock-connect-unix (fd={fd}, path={path})")
+            }
             JournalEntry::SocketAcceptedV1 {
                 listen_fd,
                 fd,
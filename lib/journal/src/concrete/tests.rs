@@ -264,6 +264,41 @@ pub fn test_record_file_descriptor_allocate() {
     });
 }
 
+#[tracing_test::traced_test]
+#[test]
+pub fn test_record_file_descriptor_fallocate() {
+    run_test(JournalEntry::FileDescriptorFallocateV1 {
+        fd: 2934852,
+        flags: wasi::Fallocflags::KEEP_SIZE | wasi::Fallocflags::PUNCH_HOLE,
+        offset: 23489582934523,
+        len: 9845982345,
+    });
+}
+
+#[tracing_test::traced_test]
+#[test]
+pub fn test_record_path_set_xattr() {
+    run_test(JournalEntry::PathSetXattrV1 {
+        fd: 1234,
+        flags: 1,
+        path: "/data/file.txt".into(),
+        name: "user.mime_type".into(),
+        value: b"text/plain".to_vec().into(),
+        xflags: wasi::Xattrflags::CREATE,
+    });
+}
+
+#[tracing_test::traced_test]
+#[test]
+pub fn test_record_path_remove_xattr() {
+    run_test(JournalEntry::PathRemoveXattrV1 {
+        fd: 1234,
+        flags: 0,
+        path: "/data/file.txt".into(),
+        name: "user.mime_type".into(),
+    });
+}
+
 #[tracing_test::traced_test]
 #[test]
 pub fn test_record_create_hard_link() {
@@ -499,6 +534,24 @@ pub fn test_record_socket_connected() {
     });
 }
 
+#[tracing_test::traced_test]
+#[test]
+pub fn test_record_socket_bind_unix() {
+    run_test(JournalEntry::SocketBindUnixV1 {
+        fd: 2341234,
+        path: "/run/app.sock".into(),
+    });
+}
+
+#[tracing_test::traced_test]
+#[test]
+pub fn test_record_socket_connect_unix() {
+    run_test(JournalEntry::SocketConnectUnixV1 {
+        fd: 12341,
+        path: "/run/app.sock".into(),
+    });
+}
+
 #[tracing_test::traced_test]
 #[test]
 pub fn test_record_socket_accepted() {
@@ -682,6 +735,12 @@ pub fn test_record_alignment() {
         std::mem::align_of::<JournalEntryFileDescriptorAllocateV1>(),
         8
     );
+    assert_eq!(
+        std::mem::align_of::<JournalEntryFileDescriptorFallocateV1>(),
+        8
+    );
+    assert_eq!(std::mem::align_of::<JournalEntryPathSetXattrV1>(), 8);
+    assert_eq!(std::mem::align_of::<JournalEntryPathRemoveXattrV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntryCreateHardLinkV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntryCreateSymbolicLinkV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntryUnlinkFileV1>(), 8);
@@ -702,6 +761,8 @@ pub fn test_record_alignment() {
     assert_eq!(std::mem::align_of::<JournalEntrySocketListenV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntrySocketBindV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntrySocketConnectedV1>(), 8);
+    assert_eq!(std::mem::align_of::<JournalEntrySocketBindUnixV1>(), 8);
+    assert_eq!(std::mem::align_of::<JournalEntrySocketConnectUnixV1>(), 8);
     assert_eq!(std::mem::align_of::<JournalEntrySocketAcceptedV1>(), 8);
     assert_eq!(
         std::mem::align_of::<JournalEntrySocketJoinIpv4MulticastV1>(),
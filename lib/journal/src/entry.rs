@@ -5,9 +5,9 @@ use std::time::{Duration, SystemTime};
 use std::{borrow::Cow, ops::Range};
 use virtual_net::{IpCidr, StreamSecurity};
 use wasmer_wasix_types::wasi::{
-    Addressfamily, Advice, EpollCtl, EpollEventCtl, EventFdFlags, ExitCode, Fdflags, Fdflagsext,
-    FileDelta, Filesize, Fstflags, LookupFlags, Oflags, Rights, SiFlags, Snapshot0Clockid,
-    SockProto, Sockoption, Socktype, Timestamp, Tty, Whence,
+    Addressfamily, Advice, EpollCtl, EpollEventCtl, EventFdFlags, ExitCode, Fallocflags, Fdflags,
+    Fdflagsext, FileDelta, Filesize, Fstflags, LookupFlags, Oflags, Rights, SiFlags,
+    Snapshot0Clockid, SockProto, Sockoption, Socktype, Timestamp, Tty, Whence, Xattrflags,
 };
 use wasmer_wasix_types::wasix::{ThreadStartType, WasiMemoryLayout};
 
@@ -221,6 +221,28 @@ pub enum JournalEntry<'a> {
         offset: Filesize,
         len: Filesize,
     },
+    FileDescriptorFallocateV1 {
+        fd: Fd,
+        flags: Fallocflags,
+        offset: Filesize,
+        len: Filesize,
+    },
+    PathSetXattrV1 {
+        fd: Fd,
+        flags: LookupFlags,
+        path: Cow<'a, str>,
+        name: Cow<'a, str>,
+        #[debug(ignore)]
+        #[serde(with = "base64")]
+        value: Cow<'a, [u8]>,
+        xflags: Xattrflags,
+    },
+    PathRemoveXattrV1 {
+        fd: Fd,
+        flags: LookupFlags,
+        path: Cow<'a, str>,
+        name: Cow<'a, str>,
+    },
     CreateHardLinkV1 {
         old_fd: Fd,
         old_path: Cow<'a, str>,
@@ -318,6 +340,14 @@ pub enum JournalEntry<'a> {
         local_addr: SocketAddr,
         peer_addr: SocketAddr,
     },
+    SocketBindUnixV1 {
+        fd: Fd,
+        path: Cow<'a, str>,
+    },
+    SocketConnectUnixV1 {
+        fd: Fd,
+        path: Cow<'a, str>,
+    },
     SocketAcceptedV1 {
         listen_fd: Fd,
         fd: Fd,
@@ -569,6 +599,43 @@ impl<'a> JournalEntry<'a> {
             Self::FileDescriptorAllocateV1 { fd, offset, len } => {
                 JournalEntry::FileDescriptorAllocateV1 { fd, offset, len }
             }
+            Self::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            } => JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            },
+            Self::PathSetXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+                value,
+                xflags,
+            } => JournalEntry::PathSetXattrV1 {
+                fd,
+                flags,
+                path: path.into_owned().into(),
+                name: name.into_owned().into(),
+                value: value.into_owned().into(),
+                xflags,
+            },
+            Self::PathRemoveXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+            } => JournalEntry::PathRemoveXattrV1 {
+                fd,
+                flags,
+                path: path.into_owned().into(),
+                name: name.into_owned().into(),
+            },
             Self::CreateHardLinkV1 {
                 old_fd,
                 old_path,
@@ -675,6 +742,14 @@ impl<'a> JournalEntry<'a> {
                 local_addr,
                 peer_addr,
             },
+            Self::SocketBindUnixV1 { fd, path } => JournalEntry::SocketBindUnixV1 {
+                fd,
+                path: path.into_owned().into(),
+            },
+            Self::SocketConnectUnixV1 { fd, path } => JournalEntry::SocketConnectUnixV1 {
+                fd,
+                path: path.into_owned().into(),
+            },
             Self::SocketAcceptedV1 {
                 listen_fd,
                 fd,
@@ -810,6 +885,13 @@ impl<'a> JournalEntry<'a> {
             JournalEntry::FileDescriptorSetSizeV1 { .. } => base_size,
             JournalEntry::FileDescriptorAdviseV1 { .. } => base_size,
             JournalEntry::FileDescriptorAllocateV1 { .. } => base_size,
+            JournalEntry::FileDescriptorFallocateV1 { .. } => base_size,
+            JournalEntry::PathSetXattrV1 {
+                path, name, value, ..
+            } => base_size + path.as_bytes().len() + name.as_bytes().len() + value.len(),
+            JournalEntry::PathRemoveXattrV1 { path, name, .. } => {
+                base_size + path.as_bytes().len() + name.as_bytes().len()
+            }
             JournalEntry::CreateHardLinkV1 {
                 old_path, new_path, ..
             } => base_size + old_path.as_bytes().len() + new_path.as_bytes().len(),
@@ -843,6 +925,8 @@ impl<'a> JournalEntry<'a> {
             JournalEntry::SocketListenV1 { .. } => base_size,
             JournalEntry::SocketBindV1 { .. } => base_size,
             JournalEntry::SocketConnectedV1 { .. } => base_size,
+            JournalEntry::SocketBindUnixV1 { path, .. } => base_size + path.as_bytes().len(),
+            JournalEntry::SocketConnectUnixV1 { path, .. } => base_size + path.as_bytes().len(),
             JournalEntry::SocketAcceptedV1 { .. } => base_size,
             JournalEntry::SocketJoinIpv4MulticastV1 { .. } => base_size,
             JournalEntry::SocketJoinIpv6MulticastV1 { .. } => base_size,
@@ -0,0 +1,801 @@
+//! `coverage` is a middleware for measuring the code coverage of a
+//! WebAssembly module, for instance of a test suite that was compiled
+//! to WebAssembly. It counts how many times each function, or each
+//! basic block, of the module runs.
+//!
+//! The counters are mutable `i64` globals that the middleware appends to
+//! the module and exports, so they can be read from the instance with
+//! [`get_coverage`], even in a headless engine. [`write_lcov`] maps them
+//! to the source lines of the DWARF debug info of the module and writes
+//! an lcov tracefile, which `genhtml`, `grcov` and most coverage services
+//! read.
+//!
+//! LLVM profdata is not supported: it is keyed by the hashes that the
+//! `-fprofile-instr-generate` instrumentation of LLVM embeds in the
+//! binary, which a module compiled without it doesn't have.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use wasmer::sys::CompilerConfig;
+//! use wasmer_middlewares::coverage::{Coverage, CoverageGranularity};
+//!
+//! fn create_coverage_middleware(
+//!     compiler_config: &mut dyn CompilerConfig,
+//!     wasm: &[u8],
+//! ) -> Arc<Coverage> {
+//!     let coverage = Arc::new(Coverage::new(wasm, CoverageGranularity::BasicBlock).unwrap());
+//!     compiler_config.push_middleware(coverage.clone());
+//!     coverage
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use gimli::{EndianSlice, LittleEndian};
+use wasmer::wasmparser::{KnownCustom, Name, Operator, Parser, Payload, TypeRef};
+use wasmer::{
+    sys::{FunctionMiddleware, MiddlewareError, MiddlewareReaderState, ModuleMiddleware},
+    AsStoreMut, ExportIndex, GlobalInit, GlobalType, Instance, LocalFunctionIndex, Mutability,
+    Type,
+};
+use wasmer_types::{entity::EntityRef, FunctionIndex, GlobalIndex, ModuleInfo};
+
+/// What the counters of [`Coverage`] count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageGranularity {
+    /// One counter per function, incremented when the function is called.
+    Function,
+    /// One counter per basic block, incremented when the block is entered.
+    ///
+    /// Blocks start at the entry of the function and after the operators
+    /// that branch or that are branched to, like `loop`, `if`, `else`,
+    /// `br_if` or the `end` of a block. Calls don't end a block, so the
+    /// code after a call that never returns is still counted as run.
+    BasicBlock,
+}
+
+/// A piece of code that has a counter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageBlock {
+    /// The function that the block belongs to.
+    pub function_index: FunctionIndex,
+    /// The offsets of the operators of the block in the module.
+    pub range: Range<usize>,
+}
+
+/// The module-level coverage middleware.
+///
+/// The blocks of the functions are found when the middleware is created,
+/// from the module that it instruments, so that they can be mapped back to
+/// the module afterwards. The middleware must be the first one of the
+/// chain of the compiler, so that it sees the operators of the module as
+/// they are.
+///
+/// # Panic
+///
+/// An instance of `Coverage` should _not_ be shared among different
+/// modules, since it describes the blocks of a single module and tracks
+/// the global indexes of its counters. Attempts to use a `Coverage`
+/// instance from multiple modules will result in a panic.
+pub struct Coverage {
+    granularity: CoverageGranularity,
+
+    /// The blocks of all the functions, in the order of their counters.
+    blocks: Vec<CoverageBlock>,
+
+    /// The blocks of each local function, as a range of `blocks`.
+    functions: Vec<Range<usize>>,
+
+    /// The global index of the first counter, the others follow it.
+    first_counter: Mutex<Option<GlobalIndex>>,
+}
+
+/// The function-level coverage middleware.
+pub struct FunctionCoverage {
+    /// The global index of the counter of the next block.
+    next_counter: GlobalIndex,
+
+    /// The counters that are left for the function.
+    remaining_counters: usize,
+
+    tracker: BlockTracker,
+}
+
+impl Coverage {
+    /// Creates a `Coverage` middleware for the given module.
+    pub fn new(wasm: &[u8], granularity: CoverageGranularity) -> Result<Self, MiddlewareError> {
+        let error = |error: wasmer::wasmparser::BinaryReaderError| {
+            MiddlewareError::new("Coverage", error.to_string())
+        };
+
+        let mut blocks = Vec::new();
+        let mut functions = Vec::new();
+        let mut num_imported_functions = 0;
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.map_err(error)? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        if matches!(import.map_err(error)?.ty, TypeRef::Func(_)) {
+                            num_imported_functions += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let function_index =
+                        FunctionIndex::new(num_imported_functions + functions.len());
+                    let first_block = blocks.len();
+                    let mut tracker = BlockTracker::new(granularity);
+                    let mut starts = Vec::new();
+                    let mut reader = body.get_operators_reader().map_err(error)?;
+                    while !reader.eof() {
+                        let (operator, offset) = reader.read_with_offset().map_err(error)?;
+                        if tracker.starts_block(&operator) {
+                            starts.push(offset);
+                        }
+                    }
+                    // A block ends where the next one starts
+                    let ends = starts.iter().skip(1).copied().chain([body.range().end]);
+                    blocks.extend(starts.iter().zip(ends).map(|(&start, end)| CoverageBlock {
+                        function_index,
+                        range: start..end,
+                    }));
+                    functions.push(first_block..blocks.len());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            granularity,
+            blocks,
+            functions,
+            first_counter: Mutex::new(None),
+        })
+    }
+
+    /// Returns the granularity of the counters.
+    pub fn granularity(&self) -> CoverageGranularity {
+        self.granularity
+    }
+
+    /// Returns the blocks that have a counter, in the order of the
+    /// counts of [`get_coverage`].
+    pub fn blocks(&self) -> &[CoverageBlock] {
+        &self.blocks
+    }
+}
+
+impl fmt::Debug for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coverage")
+            .field("granularity", &self.granularity)
+            .field("blocks", &self.blocks.len())
+            .field("first_counter", &self.first_counter)
+            .finish()
+    }
+}
+
+impl ModuleMiddleware for Coverage {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let first_counter = self.first_counter.lock().unwrap().unwrap();
+        let blocks = self.functions[local_function_index.index()].clone();
+        Box::new(FunctionCoverage {
+            next_counter: GlobalIndex::new(first_counter.index() + blocks.start),
+            remaining_counters: blocks.len(),
+            tracker: BlockTracker::new(self.granularity),
+        })
+    }
+
+    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+        let mut first_counter = self.first_counter.lock().unwrap();
+
+        if first_counter.is_some() {
+            panic!("Coverage::transform_module_info: Attempting to use a `Coverage` middleware from multiple modules.");
+        }
+
+        let num_local_functions = module_info.functions.len() - module_info.num_imported_functions;
+        if num_local_functions != self.functions.len() {
+            return Err(MiddlewareError::new(
+                "Coverage",
+                format!(
+                    "the module has {num_local_functions} functions, but the `Coverage` middleware was created for a module with {}",
+                    self.functions.len()
+                ),
+            ));
+        }
+
+        // Append a global for each counter and initialize it.
+        for counter in 0..self.blocks.len() {
+            let global_index = module_info
+                .globals
+                .push(GlobalType::new(Type::I64, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I64Const(0));
+            module_info
+                .exports
+                .insert(counter_name(counter), ExportIndex::Global(global_index));
+            first_counter.get_or_insert(global_index);
+        }
+        first_counter.get_or_insert(GlobalIndex::new(module_info.globals.len()));
+
+        Ok(())
+    }
+}
+
+fn counter_name(counter: usize) -> String {
+    format!("wasmer_coverage_counter_{counter}")
+}
+
+impl fmt::Debug for FunctionCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionCoverage")
+            .field("next_counter", &self.next_counter)
+            .field("remaining_counters", &self.remaining_counters)
+            .finish()
+    }
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        // The blocks were found with the same tracker when the middleware
+        // was created, they run out only if a middleware before this one
+        // changed the operators.
+        if self.tracker.starts_block(&operator) && self.remaining_counters > 0 {
+            let global_index = self.next_counter.as_u32();
+            state.extend(&[
+                // globals[counter] += 1;
+                Operator::GlobalGet { global_index },
+                Operator::I64Const { value: 1 },
+                Operator::I64Add,
+                Operator::GlobalSet { global_index },
+            ]);
+            self.next_counter = GlobalIndex::new(self.next_counter.index() + 1);
+            self.remaining_counters -= 1;
+        }
+        state.push_operator(operator);
+
+        Ok(())
+    }
+}
+
+/// Finds the first operator of the blocks of a function body.
+#[derive(Debug)]
+struct BlockTracker {
+    granularity: CoverageGranularity,
+    /// Whether the next operator is the first one of the body.
+    entry: bool,
+    /// Whether the previous operator ended a block.
+    pending: bool,
+    /// The nesting of the blocks, loops and ifs.
+    depth: u32,
+}
+
+impl BlockTracker {
+    fn new(granularity: CoverageGranularity) -> Self {
+        Self {
+            granularity,
+            entry: true,
+            pending: false,
+            depth: 0,
+        }
+    }
+
+    /// Returns `true` if a block starts at the given operator, which is
+    /// the next one of the body.
+    fn starts_block(&mut self, operator: &Operator) -> bool {
+        // The operators that close a block would make a block of their
+        // own, that only runs when the previous one falls through.
+        let starts = std::mem::take(&mut self.entry)
+            || (self.pending
+                && !matches!(
+                    operator,
+                    Operator::End
+                        | Operator::Else
+                        | Operator::Catch { .. }
+                        | Operator::CatchAll
+                        | Operator::Delegate { .. }
+                ));
+        if self.granularity == CoverageGranularity::Function {
+            return starts;
+        }
+
+        self.pending = match operator {
+            Operator::Block { .. } | Operator::Try { .. } | Operator::TryTable { .. } => {
+                self.depth += 1;
+                false
+            }
+            // loop headers and the code of an "if" are branch targets
+            Operator::Loop { .. } | Operator::If { .. } => {
+                self.depth += 1;
+                true
+            }
+            // the "end" of the function body doesn't start a block
+            Operator::End | Operator::Delegate { .. } => match self.depth.checked_sub(1) {
+                Some(depth) => {
+                    self.depth = depth;
+                    true
+                }
+                None => false,
+            },
+            Operator::Else
+            | Operator::Catch { .. }
+            | Operator::CatchAll
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Unreachable
+            | Operator::Throw { .. }
+            | Operator::ThrowRef
+            | Operator::Rethrow { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. }
+            | Operator::ReturnCallRef { .. }
+            | Operator::BrOnNull { .. }
+            | Operator::BrOnNonNull { .. }
+            | Operator::BrOnCast { .. }
+            | Operator::BrOnCastFail { .. } => true,
+            _ => false,
+        };
+        starts
+    }
+}
+
+/// Get the counters of an [`Instance`][wasmer::Instance], in the order
+/// of [`Coverage::blocks`].
+///
+/// # Panic
+///
+/// The [`Instance`][wasmer::Instance] must have been processed with
+/// the given [`Coverage`] middleware at compile time, otherwise this will
+/// panic.
+///
+/// # Example
+///
+/// ```rust
+/// use wasmer::{AsStoreMut, Instance};
+/// use wasmer_middlewares::coverage::{get_coverage, Coverage};
+///
+/// /// Returns the number of blocks that didn't run.
+/// fn uncovered_blocks(store: &mut impl AsStoreMut, instance: &Instance, coverage: &Coverage) -> usize {
+///     get_coverage(store, instance, coverage)
+///         .into_iter()
+///         .filter(|hits| *hits == 0)
+///         .count()
+/// }
+/// ```
+pub fn get_coverage(
+    ctx: &mut impl AsStoreMut,
+    instance: &Instance,
+    coverage: &Coverage,
+) -> Vec<u64> {
+    (0..coverage.blocks.len())
+        .map(|counter| {
+            let hits: i64 = instance
+                .exports
+                .get_global(&counter_name(counter))
+                .expect("Can't get `wasmer_coverage_counter` from Instance")
+                .get(ctx)
+                .try_into()
+                .expect("`wasmer_coverage_counter` from Instance has wrong type");
+            hits as u64
+        })
+        .collect()
+}
+
+/// Set the counters of an [`Instance`][wasmer::Instance] back to zero.
+///
+/// # Panic
+///
+/// The [`Instance`][wasmer::Instance] must have been processed with
+/// the given [`Coverage`] middleware at compile time, otherwise this will
+/// panic.
+pub fn reset_coverage(ctx: &mut impl AsStoreMut, instance: &Instance, coverage: &Coverage) {
+    for counter in 0..coverage.blocks.len() {
+        instance
+            .exports
+            .get_global(&counter_name(counter))
+            .expect("Can't get `wasmer_coverage_counter` from Instance")
+            .set(ctx, 0i64.into())
+            .expect("Can't set `wasmer_coverage_counter` in Instance");
+    }
+}
+
+/// Write the counters returned by [`get_coverage`] as an lcov tracefile,
+/// with the source lines and the function names of the debug info of the
+/// module that the [`Coverage`] middleware was created for.
+///
+/// A line is counted as many times as the block that ran it the most.
+/// Functions and lines without DWARF debug info are left out, so a module
+/// without debug info produces an empty tracefile.
+pub fn write_lcov(
+    wasm: &[u8],
+    coverage: &Coverage,
+    hits: &[u64],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let source = SourceInfo::parse(wasm)?;
+    write_records(&source, coverage, hits, out)
+}
+
+/// A row of the DWARF line programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct LineRow {
+    /// The offset of the first operator of the row in the module.
+    offset: usize,
+    /// Whether the row ends a sequence, rather than starting a line.
+    end_sequence: bool,
+    /// An index in [`SourceInfo::files`].
+    file: usize,
+    line: u64,
+}
+
+/// The function names and the source lines of a module.
+#[derive(Debug, Default)]
+struct SourceInfo {
+    function_names: HashMap<u32, String>,
+    files: Vec<String>,
+    /// Sorted by offset, the end of a sequence coming before the start of
+    /// the next one.
+    rows: Vec<LineRow>,
+}
+
+impl SourceInfo {
+    fn parse(wasm: &[u8]) -> io::Result<Self> {
+        let invalid_data = |error: &dyn fmt::Display| {
+            io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+        };
+
+        let mut info = Self::default();
+        let mut sections = HashMap::new();
+        let mut code_start = 0;
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload.map_err(|error| invalid_data(&error))? {
+                Payload::CodeSectionStart { range, .. } => code_start = range.start,
+                Payload::CustomSection(section) => match section.as_known() {
+                    KnownCustom::Name(names) => {
+                        for name in names {
+                            // The name section is only informative, don't
+                            // reject the module if it is malformed.
+                            let Ok(Name::Function(functions)) = name else {
+                                continue;
+                            };
+                            for naming in functions.into_iter().flatten() {
+                                info.function_names
+                                    .insert(naming.index, naming.name.to_string());
+                            }
+                        }
+                    }
+                    _ if section.name().starts_with(".debug_") => {
+                        sections.insert(section.name(), section.data());
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        info.read_line_programs(&sections, code_start)
+            .map_err(|error| invalid_data(&error))?;
+        info.rows.sort_unstable();
+        Ok(info)
+    }
+
+    /// Reads the rows of the line programs, whose addresses are offsets
+    /// in the code section.
+    fn read_line_programs(
+        &mut self,
+        sections: &HashMap<&str, &[u8]>,
+        code_start: usize,
+    ) -> gimli::Result<()> {
+        let dwarf = gimli::Dwarf::load(|id| {
+            let data = sections.get(id.name()).copied().unwrap_or_default();
+            Ok::<_, gimli::Error>(EndianSlice::new(data, LittleEndian))
+        })?;
+
+        let mut file_indexes = HashMap::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            let mut rows = program.rows();
+            // Sequences of functions that the linker dropped are moved to
+            // address 0, or to a tombstone address in recent versions.
+            let mut dropped = None;
+            while let Some((header, row)) = rows.next_row()? {
+                let sequence_dropped = *dropped
+                    .get_or_insert(row.address() == 0 || row.address() >= u32::MAX as u64 - 1);
+                if row.end_sequence() {
+                    dropped = None;
+                    if !sequence_dropped {
+                        self.rows.push(LineRow {
+                            offset: code_start + row.address() as usize,
+                            end_sequence: true,
+                            file: 0,
+                            line: 0,
+                        });
+                    }
+                    continue;
+                }
+                let (Some(file), Some(line)) = (row.file(header), row.line()) else {
+                    continue;
+                };
+                if sequence_dropped {
+                    continue;
+                }
+
+                // Each component replaces the previous ones when it is absolute
+                let mut path = PathBuf::new();
+                if let Some(comp_dir) = unit.comp_dir {
+                    path.push(&*comp_dir.to_string_lossy());
+                }
+                if let Some(directory) = file.directory(header) {
+                    path.push(&*dwarf.attr_string(&unit, directory)?.to_string_lossy());
+                }
+                path.push(
+                    &*dwarf
+                        .attr_string(&unit, file.path_name())?
+                        .to_string_lossy(),
+                );
+                let path = path.to_string_lossy().into_owned();
+
+                let next_file = self.files.len();
+                let file = *file_indexes.entry(path.clone()).or_insert(next_file);
+                if file == next_file {
+                    self.files.push(path);
+                }
+                self.rows.push(LineRow {
+                    offset: code_start + row.address() as usize,
+                    end_sequence: false,
+                    file,
+                    line: line.get(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the lines of the operators in `range`, including the line
+    /// of a row that starts before it.
+    fn lines(&self, range: Range<usize>) -> impl Iterator<Item = (usize, u64)> + '_ {
+        let first = self
+            .rows
+            .partition_point(|row| row.offset <= range.start)
+            .saturating_sub(1);
+        self.rows[first..]
+            .iter()
+            .take_while(move |row| row.offset < range.end)
+            .filter(|row| !row.end_sequence)
+            .map(|row| (row.file, row.line))
+    }
+}
+
+/// The functions and the lines of a source file.
+#[derive(Debug, Default)]
+struct FileCoverage {
+    /// The line, name and hits of each function.
+    functions: Vec<(u64, String, u64)>,
+    lines: BTreeMap<u64, u64>,
+}
+
+fn write_records(
+    source: &SourceInfo,
+    coverage: &Coverage,
+    hits: &[u64],
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut files = BTreeMap::<&str, FileCoverage>::new();
+    for blocks in &coverage.functions {
+        let mut entry_line = None;
+        for counter in blocks.clone() {
+            let block_hits = hits.get(counter).copied().unwrap_or_default();
+            for (file, line) in source.lines(coverage.blocks[counter].range.clone()) {
+                entry_line.get_or_insert((file, line));
+                let line_hits = files
+                    .entry(&source.files[file])
+                    .or_default()
+                    .lines
+                    .entry(line)
+                    .or_default();
+                *line_hits = (*line_hits).max(block_hits);
+            }
+        }
+
+        // The entry block runs once per call
+        let Some((file, line)) = entry_line else {
+            continue;
+        };
+        let function_index = coverage.blocks[blocks.start].function_index.as_u32();
+        let name = source
+            .function_names
+            .get(&function_index)
+            .cloned()
+            .unwrap_or_else(|| format!("wasm-function[{function_index}]"));
+        let function_hits = hits.get(blocks.start).copied().unwrap_or_default();
+        files
+            .entry(&source.files[file])
+            .or_default()
+            .functions
+            .push((line, name, function_hits));
+    }
+
+    for (path, file) in files {
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{path}")?;
+        for (line, name, _) in &file.functions {
+            writeln!(out, "FN:{line},{name}")?;
+        }
+        for (_, name, hits) in &file.functions {
+            writeln!(out, "FNDA:{hits},{name}")?;
+        }
+        let functions_hit = file.functions.iter().filter(|(_, _, hits)| *hits > 0);
+        writeln!(out, "FNF:{}", file.functions.len())?;
+        writeln!(out, "FNH:{}", functions_hit.count())?;
+        for (line, hits) in &file.lines {
+            writeln!(out, "DA:{line},{hits}")?;
+        }
+        let lines_hit = file.lines.values().filter(|hits| **hits > 0);
+        writeln!(out, "LF:{}", file.lines.len())?;
+        writeln!(out, "LH:{}", lines_hit.count())?;
+        writeln!(out, "end_of_record")?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use wasmer::sys::EngineBuilder;
+    use wasmer::{
+        imports,
+        sys::{CompilerConfig, Cranelift},
+        wat2wasm, Module, Store, TypedFunction,
+    };
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"(module
+            (func $sum_f (param $n i32) (result i32) (local $acc i32)
+                block
+                    loop
+                        ;; stop when $n is 0
+                        local.get $n
+                        i32.eqz
+                        br_if 1
+
+                        ;; $acc += $n; $n -= 1
+                        local.get $acc
+                        local.get $n
+                        i32.add
+                        local.set $acc
+                        local.get $n
+                        i32.const 1
+                        i32.sub
+                        local.set $n
+                        br 0
+                    end
+                end
+                local.get $acc)
+            (func $unused_f)
+            (export "sum" (func $sum_f))
+            (export "unused" (func $unused_f))
+        )"#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn instantiate(granularity: CoverageGranularity) -> (Arc<Coverage>, Store, Instance) {
+        let wasm = bytecode();
+        let coverage = Arc::new(Coverage::new(&wasm, granularity).unwrap());
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(coverage.clone());
+        let mut store = Store::new(EngineBuilder::new(compiler_config));
+        let module = Module::new(&store, wasm).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        (coverage, store, instance)
+    }
+
+    fn call_sum(store: &mut Store, instance: &Instance, n: i32) -> i32 {
+        let sum: TypedFunction<i32, i32> = instance
+            .exports
+            .get_function("sum")
+            .unwrap()
+            .typed(store)
+            .unwrap();
+        sum.call(store, n).unwrap()
+    }
+
+    #[test]
+    fn basic_blocks_are_counted() {
+        let (coverage, mut store, instance) = instantiate(CoverageGranularity::BasicBlock);
+        let functions: Vec<u32> = coverage
+            .blocks()
+            .iter()
+            .map(|block| block.function_index.as_u32())
+            .collect();
+        assert_eq!(functions, [0, 0, 0, 0, 1]);
+        assert_eq!(get_coverage(&mut store, &instance, &coverage), [0; 5]);
+
+        assert_eq!(call_sum(&mut store, &instance, 3), 6);
+        // The entry, the loop header, the loop body, the code after the
+        // loop, and the function that isn't called
+        assert_eq!(
+            get_coverage(&mut store, &instance, &coverage),
+            [1, 4, 3, 1, 0]
+        );
+
+        reset_coverage(&mut store, &instance, &coverage);
+        assert_eq!(get_coverage(&mut store, &instance, &coverage), [0; 5]);
+    }
+
+    #[test]
+    fn functions_are_counted() {
+        let (coverage, mut store, instance) = instantiate(CoverageGranularity::Function);
+        assert_eq!(coverage.blocks().len(), 2);
+
+        call_sum(&mut store, &instance, 3);
+        call_sum(&mut store, &instance, 5);
+        assert_eq!(get_coverage(&mut store, &instance, &coverage), [2, 0]);
+    }
+
+    #[test]
+    fn lcov_has_the_hits_of_the_lines_and_functions() {
+        let coverage = Coverage::new(&bytecode(), CoverageGranularity::BasicBlock).unwrap();
+        let blocks = coverage.blocks();
+        let row = |offset: usize, line: u64| LineRow {
+            offset,
+            end_sequence: false,
+            file: 0,
+            line,
+        };
+        // One line per block of `sum`, the header and the body of the
+        // loop share line 3, and `unused` has no debug info
+        let source = SourceInfo {
+            function_names: [(0, "sum".to_string())].into_iter().collect(),
+            files: vec!["/src/sum.c".to_string()],
+            rows: vec![
+                row(blocks[0].range.start, 1),
+                row(blocks[1].range.start, 3),
+                row(blocks[2].range.start, 3),
+                row(blocks[3].range.start, 5),
+                LineRow {
+                    offset: blocks[3].range.end,
+                    end_sequence: true,
+                    file: 0,
+                    line: 0,
+                },
+            ],
+        };
+
+        let mut out = Vec::new();
+        write_records(&source, &coverage, &[1, 4, 3, 0, 0], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "TN:\nSF:/src/sum.c\nFN:1,sum\nFNDA:1,sum\nFNF:1\nFNH:1\n\
+             DA:1,1\nDA:3,4\nDA:5,0\nLF:3\nLH:2\nend_of_record\n"
+        );
+    }
+}
@@ -111,6 +111,18 @@ impl VirtualFile for ArcBoxFile {
         let mut inner = self.inner.lock().unwrap();
         inner.set_len(new_size)
     }
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.seek_data(offset)
+    }
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.seek_hole(offset)
+    }
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.punch_hole(offset, len)
+    }
     fn unlink(&mut self) -> crate::Result<()> {
         let mut inner = self.inner.lock().unwrap();
         inner.unlink()
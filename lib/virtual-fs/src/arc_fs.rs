@@ -2,7 +2,11 @@
 //! can pass clonable file systems with a `Box<dyn FileSystem>` to other
 //! interfaces
 
-use std::{path::Path, sync::Arc};
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    sync::Arc,
+};
 
 use crate::*;
 
@@ -62,4 +66,30 @@ impl FileSystem for ArcFileSystem {
     ) -> Result<()> {
         self.fs.mount(name, path, fs)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        self.fs.get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        self.fs.set_xattr(path, name, value, mode)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        self.fs.list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        self.fs.remove_xattr(path, name)
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        self.fs.create_socket(path)
+    }
 }
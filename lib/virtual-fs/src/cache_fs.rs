@@ -0,0 +1,1386 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    hash::Hash,
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use shared_buffer::OwnedBuffer;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+
+use crate::{
+    FileOpener, FileSystem, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir, StaticFile,
+    VirtualFile, XattrMode,
+};
+
+/// Settings for a [`CachingFileSystem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// The maximum number of bytes of file contents kept in memory.
+    pub max_memory: u64,
+    /// Files larger than this are never cached.
+    pub max_file_size: u64,
+    /// The maximum number of metadata entries to keep.
+    pub max_metadata_entries: u64,
+    /// Where file contents evicted from memory are written to, if anywhere.
+    pub spill: Option<SpillConfig>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            max_memory: 64 * 1024 * 1024,
+            max_file_size: 8 * 1024 * 1024,
+            max_metadata_entries: 4096,
+            spill: None,
+        }
+    }
+}
+
+/// An on-disk directory that a [`CachingFileSystem`] spills file contents to
+/// when they are evicted from memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpillConfig {
+    /// The directory spilled files are written to. It will be created if it
+    /// doesn't already exist.
+    pub dir: PathBuf,
+    /// The maximum number of bytes to keep in the spill directory.
+    pub max_bytes: u64,
+}
+
+/// Counters describing how effective a [`CachingFileSystem`] has been.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Files that were opened using contents from memory.
+    pub hits: u64,
+    /// Files that were opened using contents from the spill directory.
+    pub spill_hits: u64,
+    /// Files that had to be opened on the underlying file system.
+    pub misses: u64,
+    /// Metadata lookups answered by the cache.
+    pub metadata_hits: u64,
+    /// Metadata lookups that went to the underlying file system.
+    pub metadata_misses: u64,
+    /// File contents that were evicted from memory.
+    pub evictions: u64,
+    /// Paths that were invalidated because they were modified.
+    pub invalidations: u64,
+    /// The number of bytes of file contents currently held in memory.
+    pub memory_bytes: u64,
+    /// The number of bytes currently held in the spill directory.
+    pub spill_bytes: u64,
+}
+
+/// A read-through caching layer for slow file systems (e.g. network mounts or
+/// compressed packages).
+///
+/// File contents and metadata are kept in a bounded LRU cache, optionally
+/// spilling evicted contents into a directory on disk. A file is only cached
+/// once it has been read sequentially from start to end, so reading part of
+/// a large file won't pull all of it into memory.
+///
+/// Anything modified through this layer is invalidated, but changes made to
+/// the underlying file system directly won't be noticed.
+#[derive(Debug, Clone)]
+pub struct CachingFileSystem<F> {
+    inner: F,
+    cache: Arc<Cache>,
+}
+
+impl<F> CachingFileSystem<F> {
+    pub fn new(inner: F, config: CacheConfig) -> Self {
+        CachingFileSystem {
+            inner,
+            cache: Arc::new(Cache::new(config)),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.state.lock().unwrap().stats
+    }
+
+    /// Throw away everything that has been cached.
+    pub fn clear(&self) {
+        self.cache.invalidate_where(|_| true);
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F> CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn cached_metadata(
+        &self,
+        path: &Path,
+        follow_symlinks: bool,
+        lookup: impl FnOnce() -> crate::Result<Metadata>,
+    ) -> crate::Result<Metadata> {
+        let key = (path.to_path_buf(), follow_symlinks);
+        {
+            let mut state = self.cache.state.lock().unwrap();
+            if let Some(result) = state.metadata.get(&key).cloned() {
+                state.stats.metadata_hits += 1;
+                return result;
+            }
+            state.stats.metadata_misses += 1;
+        }
+
+        let generation = self.cache.record_alias(path, &self.resolve(path));
+
+        let result = lookup();
+        // Missing files are cached too, which helps with things like module
+        // resolution that probe lots of paths that don't exist.
+        if matches!(result, Ok(_) | Err(FsError::EntryNotFound)) {
+            let mut state = self.cache.state.lock().unwrap();
+            if state.generation == generation {
+                state.metadata.insert(key, result.clone(), 1);
+            }
+        }
+
+        result
+    }
+
+    /// Follow every symlink in `path`, so writes made through one path can
+    /// invalidate what was cached under another path to the same file.
+    ///
+    /// Anything that can't be resolved (e.g. because it doesn't exist yet) is
+    /// taken literally.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        let mut remaining: Vec<OsString> = Vec::new();
+        push_components(&mut remaining, path);
+        let mut resolved = PathBuf::from("/");
+        let mut hops = 0;
+
+        while let Some(name) = remaining.pop() {
+            if name == ".." {
+                resolved.pop();
+                continue;
+            }
+
+            let candidate = resolved.join(&name);
+            let is_symlink = self
+                .inner
+                .symlink_metadata(&candidate)
+                .map(|m| m.ft.is_symlink())
+                .unwrap_or(false);
+            let target = match is_symlink {
+                true => self.inner.readlink(&candidate).ok(),
+                false => None,
+            };
+
+            match target {
+                Some(_) if hops >= MAX_SYMLINK_HOPS => return path.to_path_buf(),
+                Some(target) => {
+                    hops += 1;
+                    if target.has_root() {
+                        resolved = PathBuf::from("/");
+                    }
+                    push_components(&mut remaining, &target);
+                }
+                None => resolved = candidate,
+            }
+        }
+
+        resolved
+    }
+
+    fn invalidate(&self, path: &Path) {
+        let resolved = match self.cache.has_aliases() {
+            true => self.resolve(path),
+            false => path.to_path_buf(),
+        };
+        self.cache.invalidate(path, &resolved);
+    }
+
+    fn invalidate_tree(&self, path: &Path) {
+        let resolved = match self.cache.has_aliases() {
+            true => self.resolve(path),
+            false => path.to_path_buf(),
+        };
+        self.cache.invalidate_tree(path, &resolved);
+    }
+}
+
+/// The same limit Linux uses before giving up with `ELOOP`.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Push the components of `path` onto a stack so they are popped in order.
+fn push_components(stack: &mut Vec<OsString>, path: &Path) {
+    let start = stack.len();
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(name) => stack.push(name.to_os_string()),
+            std::path::Component::ParentDir => stack.push(OsString::from("..")),
+            _ => {}
+        }
+    }
+    stack[start..].reverse();
+}
+
+impl<F> FileSystem for CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn readlink(&self, path: &Path) -> crate::Result<PathBuf> {
+        self.inner.readlink(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> crate::Result<ReadDir> {
+        self.inner.read_dir(path)
+    }
+
+    // Invalidation happens after the underlying file system has been
+    // modified, so nothing read in the meantime can be cached again.
+
+    fn create_dir(&self, path: &Path) -> crate::Result<()> {
+        let result = self.inner.create_dir(path);
+        self.invalidate(path);
+        result
+    }
+
+    fn remove_dir(&self, path: &Path) -> crate::Result<()> {
+        // Resolve before removing, while any symlinks on the way still exist
+        let resolved = self.resolve(path);
+        let result = self.inner.remove_dir(path);
+        self.cache.invalidate_tree(path, &resolved);
+        result
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move {
+            let resolved_from = self.resolve(from);
+            let result = self.inner.rename(from, to).await;
+            self.cache.invalidate_tree(from, &resolved_from);
+            self.invalidate_tree(to);
+            result
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> crate::Result<Metadata> {
+        self.cached_metadata(path, true, || self.inner.metadata(path))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> crate::Result<Metadata> {
+        self.cached_metadata(path, false, || self.inner.symlink_metadata(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> crate::Result<()> {
+        let resolved = self.resolve(path);
+        let result = self.inner.remove_file(path);
+        self.cache.invalidate(path, &resolved);
+        result
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        name: String,
+        path: &Path,
+        fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> crate::Result<()> {
+        let result = self.inner.mount(name, path, fs);
+        self.invalidate_tree(path);
+        result
+    }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> crate::Result<Vec<u8>> {
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        self.inner.set_xattr(path, name, value, mode)
+    }
+
+    fn list_xattr(&self, path: &Path) -> crate::Result<Vec<OsString>> {
+        self.inner.list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn create_socket(&self, path: &Path) -> crate::Result<()> {
+        let result = self.inner.create_socket(path);
+        self.invalidate(path);
+        result
+    }
+}
+
+impl<F> FileOpener for CachingFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> crate::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let read_only =
+            !(conf.write || conf.append || conf.truncate || conf.create || conf.create_new);
+
+        if !read_only {
+            let resolved = self.resolve(path);
+            let file = self
+                .inner
+                .new_open_options()
+                .options(conf.clone())
+                .open(path);
+            // Opening can create or truncate the file, even if it then fails
+            self.cache.invalidate(path, &resolved);
+            return Ok(Box::new(InvalidatingFile {
+                path: path.to_path_buf(),
+                resolved,
+                file: file?,
+                cache: self.cache.clone(),
+            }));
+        }
+
+        if let Some(file) = self.cache.lookup(path) {
+            return Ok(Box::new(file));
+        }
+
+        let file = self
+            .inner
+            .new_open_options()
+            .options(conf.clone())
+            .open(path)?;
+        if file.size() > self.cache.config.max_file_size {
+            return Ok(file);
+        }
+
+        let resolved = self.resolve(path);
+        let generation = self.cache.record_alias(path, &resolved);
+        Ok(Box::new(ReadThroughFile {
+            path: path.to_path_buf(),
+            resolved,
+            file,
+            cache: self.cache.clone(),
+            buffer: Some(Vec::new()),
+            pos: 0,
+            generation,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct Cache {
+    config: CacheConfig,
+    state: Mutex<CacheState>,
+    next_spill_id: AtomicU64,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    contents: Lru<PathBuf, CachedContents>,
+    spilled: Lru<PathBuf, SpilledContents>,
+    metadata: Lru<(PathBuf, bool), crate::Result<Metadata>>,
+    /// Paths that were cached through a symlink, keyed by what they resolve
+    /// to.
+    aliases: HashMap<PathBuf, Vec<PathBuf>>,
+    /// Incremented whenever something is invalidated, so a fill that started
+    /// before the invalidation doesn't re-insert stale data.
+    generation: u64,
+    stats: CacheStats,
+}
+
+#[derive(Debug, Clone)]
+struct CachedContents {
+    data: OwnedBuffer,
+    times: FileTimes,
+}
+
+#[derive(Debug, Clone)]
+struct SpilledContents {
+    file: PathBuf,
+    times: FileTimes,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct FileTimes {
+    accessed: u64,
+    modified: u64,
+    created: u64,
+}
+
+impl Cache {
+    fn new(config: CacheConfig) -> Self {
+        let spill_capacity = config.spill.as_ref().map(|s| s.max_bytes).unwrap_or(0);
+        let state = CacheState {
+            contents: Lru::new(config.max_memory),
+            spilled: Lru::new(spill_capacity),
+            metadata: Lru::new(config.max_metadata_entries),
+            aliases: HashMap::new(),
+            generation: 0,
+            stats: CacheStats::default(),
+        };
+
+        Cache {
+            config,
+            state: Mutex::new(state),
+            next_spill_id: AtomicU64::new(0),
+        }
+    }
+
+    fn lookup(&self, path: &Path) -> Option<CachedFile> {
+        let (spilled, generation) = {
+            let mut state = self.state.lock().unwrap();
+
+            if let Some(contents) = state.contents.get(path).cloned() {
+                state.stats.hits += 1;
+                return Some(CachedFile::new(contents));
+            }
+
+            let spilled = state.spilled.remove(path);
+            state.stats.spill_bytes = state.spilled.used;
+            if spilled.is_none() {
+                state.stats.misses += 1;
+            }
+            (spilled?, state.generation)
+        };
+
+        // The spill directory is never touched while holding the lock
+        let data = std::fs::read(&spilled.file);
+        let _ = std::fs::remove_file(&spilled.file);
+        let Ok(data) = data else {
+            self.state.lock().unwrap().stats.misses += 1;
+            return None;
+        };
+
+        let contents = CachedContents {
+            data: data.into(),
+            times: spilled.times,
+        };
+        self.state.lock().unwrap().stats.spill_hits += 1;
+        self.insert(path.to_path_buf(), contents.clone(), generation);
+        Some(CachedFile::new(contents))
+    }
+
+    fn insert(&self, path: PathBuf, contents: CachedContents, generation: u64) {
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            if state.generation != generation {
+                return;
+            }
+
+            let weight = contents.data.len() as u64;
+            let evicted = state.contents.insert(path, contents, weight);
+            state.stats.evictions += evicted.len() as u64;
+            state.stats.memory_bytes = state.contents.used;
+            evicted
+        };
+
+        self.spill(evicted, generation);
+    }
+
+    /// Write contents that were evicted from memory to the spill directory.
+    fn spill(&self, evicted: Vec<(PathBuf, CachedContents)>, generation: u64) {
+        let Some(spill) = &self.config.spill else {
+            return;
+        };
+        if evicted.is_empty() {
+            return;
+        }
+
+        let mut written = Vec::new();
+        for (path, contents) in evicted {
+            // Spill directories may be shared by several processes
+            let file = spill.dir.join(format!(
+                "{}-{}-{}.bin",
+                std::process::id(),
+                self as *const Cache as usize,
+                self.next_spill_id.fetch_add(1, Ordering::Relaxed),
+            ));
+            if let Err(e) = write_new_file(&spill.dir, &file, contents.data.as_slice()) {
+                tracing::debug!(
+                    error = &e as &dyn std::error::Error,
+                    path = %file.display(),
+                    "Unable to spill cached file contents",
+                );
+                continue;
+            }
+            let weight = contents.data.len() as u64;
+            let spilled = SpilledContents {
+                file,
+                times: contents.times,
+            };
+            written.push((path, spilled, weight));
+        }
+
+        let mut stale = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            for (path, spilled, weight) in written {
+                // Something was invalidated while the contents were written
+                if state.generation != generation {
+                    stale.push(spilled.file);
+                    continue;
+                }
+                let evicted = state.spilled.insert(path, spilled, weight);
+                stale.extend(evicted.into_iter().map(|(_, evicted)| evicted.file));
+            }
+            state.stats.spill_bytes = state.spilled.used;
+        }
+        for file in stale {
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    /// Remember that `path` is another name for `resolved`, returning the
+    /// generation to fill the cache with.
+    ///
+    /// This must happen before anything is read, so a write racing with the
+    /// read is guaranteed to invalidate it.
+    fn record_alias(&self, path: &Path, resolved: &Path) -> u64 {
+        if path != resolved {
+            let mut state = self.state.lock().unwrap();
+            if state.aliases.len() as u64 >= self.config.max_metadata_entries {
+                // Forgetting an alias would leave stale entries behind, so
+                // start again from scratch instead.
+                drop(state);
+                self.invalidate_where(|_| true);
+                state = self.state.lock().unwrap();
+            }
+
+            let aliases = state.aliases.entry(resolved.to_path_buf()).or_default();
+            if !aliases.iter().any(|p| p == path) {
+                aliases.push(path.to_path_buf());
+            }
+        }
+
+        self.state.lock().unwrap().generation
+    }
+
+    fn has_aliases(&self) -> bool {
+        !self.state.lock().unwrap().aliases.is_empty()
+    }
+
+    /// Invalidate a single path, what it resolves to, any other path known to
+    /// resolve to the same file, and the metadata of their parents. This only
+    /// looks up their own entries so it can be done on every write.
+    fn invalidate(&self, path: &Path, resolved: &Path) {
+        let stale = {
+            let mut state = self.state.lock().unwrap();
+            state.generation += 1;
+
+            let mut paths = vec![path.to_path_buf(), resolved.to_path_buf()];
+            for p in [path, resolved] {
+                paths.extend(state.aliases.remove(p).unwrap_or_default());
+            }
+            paths.sort();
+            paths.dedup();
+
+            let mut invalidated = 0;
+            let mut stale = Vec::new();
+            for path in &paths {
+                invalidated += usize::from(state.contents.remove(path).is_some());
+                stale.extend(state.spilled.remove(path));
+                for p in std::iter::once(path.as_path()).chain(path.parent()) {
+                    for follow_symlinks in [true, false] {
+                        state.metadata.remove(&(p.to_path_buf(), follow_symlinks));
+                    }
+                }
+            }
+            invalidated += stale.len();
+
+            state.stats.invalidations += invalidated as u64;
+            state.stats.memory_bytes = state.contents.used;
+            state.stats.spill_bytes = state.spilled.used;
+            stale
+        };
+
+        for spilled in stale {
+            let _ = std::fs::remove_file(&spilled.file);
+        }
+    }
+
+    fn invalidate_tree(&self, path: &Path, resolved: &Path) {
+        let parents = [path.parent(), resolved.parent()];
+        self.invalidate_where(|p| {
+            p.starts_with(path) || p.starts_with(resolved) || parents.contains(&Some(p))
+        });
+    }
+
+    /// Invalidate every path matching the predicate, along with anything that
+    /// was cached through a symlink to one of them.
+    fn invalidate_where(&self, mut predicate: impl FnMut(&Path) -> bool) {
+        let stale = {
+            let mut state = self.state.lock().unwrap();
+            state.generation += 1;
+
+            let mut aliased = HashSet::new();
+            state.aliases.retain(|resolved, aliases| {
+                let matches = predicate(resolved);
+                if matches {
+                    aliased.extend(aliases.drain(..));
+                }
+                !matches
+            });
+            let mut predicate = |p: &Path| aliased.contains(p) || predicate(p);
+
+            let mut invalidated = state.contents.remove_where(|p| predicate(p)).len();
+            let stale = state.spilled.remove_where(|p| predicate(p));
+            invalidated += stale.len();
+            state.metadata.remove_where(|(p, _)| predicate(p));
+
+            state.stats.invalidations += invalidated as u64;
+            state.stats.memory_bytes = state.contents.used;
+            state.stats.spill_bytes = state.spilled.used;
+            stale
+        };
+
+        for (_, spilled) in stale {
+            let _ = std::fs::remove_file(&spilled.file);
+        }
+    }
+}
+
+/// Write a file that must not exist yet, creating its directory if needed.
+fn write_new_file(dir: &Path, path: &Path, data: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    std::fs::create_dir_all(dir)?;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(data)
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if let Ok(state) = self.state.get_mut() {
+            for spilled in state.spilled.entries.values() {
+                let _ = std::fs::remove_file(&spilled.value.file);
+            }
+        }
+    }
+}
+
+/// A least-recently-used cache where each entry has a weight.
+#[derive(Debug)]
+struct Lru<K, V> {
+    capacity: u64,
+    used: u64,
+    tick: u64,
+    entries: HashMap<K, LruEntry<V>>,
+    order: BTreeMap<u64, K>,
+}
+
+#[derive(Debug)]
+struct LruEntry<V> {
+    tick: u64,
+    weight: u64,
+    value: V,
+}
+
+impl<K, V> Lru<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn new(capacity: u64) -> Self {
+        Lru {
+            capacity,
+            used: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.get_mut(key)?;
+        let k = self.order.remove(&entry.tick)?;
+        self.tick += 1;
+        entry.tick = self.tick;
+        self.order.insert(self.tick, k);
+        Some(&entry.value)
+    }
+
+    /// Insert an entry, returning anything that had to be evicted to make
+    /// room for it.
+    fn insert(&mut self, key: K, value: V, weight: u64) -> Vec<(K, V)> {
+        self.remove(&key);
+        if weight > self.capacity {
+            return vec![(key, value)];
+        }
+
+        let mut evicted = Vec::new();
+        while self.used + weight > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.used -= entry.weight;
+                evicted.push((oldest, entry.value));
+            }
+        }
+
+        self.tick += 1;
+        self.used += weight;
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            LruEntry {
+                tick: self.tick,
+                weight,
+                value,
+            },
+        );
+
+        evicted
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.tick);
+        self.used -= entry.weight;
+        Some(entry.value)
+    }
+
+    fn remove_where(&mut self, mut predicate: impl FnMut(&K) -> bool) -> Vec<(K, V)> {
+        let keys: Vec<K> = self
+            .entries
+            .keys()
+            .filter(|k| predicate(k))
+            .cloned()
+            .collect();
+
+        keys.into_iter()
+            .filter_map(|k| {
+                let value = self.remove(&k)?;
+                Some((k, value))
+            })
+            .collect()
+    }
+}
+
+/// A file that is served straight from the cache.
+#[derive(Debug)]
+struct CachedFile {
+    file: StaticFile,
+    times: FileTimes,
+}
+
+impl CachedFile {
+    fn new(contents: CachedContents) -> Self {
+        CachedFile {
+            file: StaticFile::new(contents.data),
+            times: contents.times,
+        }
+    }
+}
+
+impl VirtualFile for CachedFile {
+    fn last_accessed(&self) -> u64 {
+        self.times.accessed
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.times.modified
+    }
+
+    fn created_time(&self) -> u64 {
+        self.times.created
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> crate::Result<()> {
+        self.file.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> crate::Result<()> {
+        self.file.unlink()
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for CachedFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for CachedFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.file).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for CachedFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.file).poll_complete(cx)
+    }
+}
+
+/// A file opened on the underlying file system which adds its contents to the
+/// cache once it has been read from start to end.
+#[derive(Debug)]
+struct ReadThroughFile {
+    path: PathBuf,
+    /// What `path` resolved to when the file was opened.
+    resolved: PathBuf,
+    file: Box<dyn VirtualFile + Send + Sync + 'static>,
+    cache: Arc<Cache>,
+    /// Everything read so far, as long as the file is being read sequentially.
+    buffer: Option<Vec<u8>>,
+    pos: u64,
+    generation: u64,
+}
+
+impl ReadThroughFile {
+    fn fill(&mut self, data: Vec<u8>) {
+        if data.len() as u64 != self.file.size() {
+            return;
+        }
+
+        let contents = CachedContents {
+            data: data.into(),
+            times: FileTimes {
+                accessed: self.file.last_accessed(),
+                modified: self.file.last_modified(),
+                created: self.file.created_time(),
+            },
+        };
+        self.cache
+            .insert(self.path.clone(), contents, self.generation);
+    }
+}
+
+impl VirtualFile for ReadThroughFile {
+    fn last_accessed(&self) -> u64 {
+        self.file.last_accessed()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.file.last_modified()
+    }
+
+    fn created_time(&self) -> u64 {
+        self.file.created_time()
+    }
+
+    fn set_times(&mut self, atime: Option<u64>, mtime: Option<u64>) -> crate::Result<()> {
+        self.file.set_times(atime, mtime)
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> crate::Result<()> {
+        self.buffer = None;
+        let result = self.file.set_len(new_size);
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_data(offset)
+    }
+
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_hole(offset)
+    }
+
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        self.buffer = None;
+        let result = self.file.punch_hole(offset, len);
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn unlink(&mut self) -> crate::Result<()> {
+        self.buffer = None;
+        let result = self.file.unlink();
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn get_special_fd(&self) -> Option<u32> {
+        self.file.get_special_fd()
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for ReadThroughFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let before = buf.filled().len();
+        let result = Pin::new(&mut *this.file).poll_read(cx, buf);
+
+        if let Poll::Ready(Ok(())) = &result {
+            let read = &buf.filled()[before..];
+            this.pos += read.len() as u64;
+
+            if read.is_empty() && buf.remaining() > 0 {
+                // We've hit EOF
+                if let Some(data) = this.buffer.take() {
+                    this.fill(data);
+                }
+            } else if let Some(buffer) = &mut this.buffer {
+                buffer.extend_from_slice(read);
+                if buffer.len() as u64 > this.cache.config.max_file_size {
+                    this.buffer = None;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl AsyncWrite for ReadThroughFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        this.buffer = None;
+        let result = Pin::new(&mut *this.file).poll_write(cx, buf);
+        if let Poll::Ready(Ok(_)) = &result {
+            this.cache.invalidate(&this.path, &this.resolved);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for ReadThroughFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut *self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut *this.file).poll_complete(cx);
+
+        if let Poll::Ready(Ok(pos)) = &result {
+            this.pos = *pos;
+            // Only sequential reads from the start can be cached
+            if this.buffer.as_ref().map(|b| b.len() as u64) != Some(*pos) {
+                this.buffer = None;
+            }
+        }
+
+        result
+    }
+}
+
+/// A file opened for writing, which makes sure nothing stale stays in the
+/// cache while it is being modified.
+#[derive(Debug)]
+struct InvalidatingFile {
+    path: PathBuf,
+    /// What `path` resolved to when the file was opened.
+    resolved: PathBuf,
+    file: Box<dyn VirtualFile + Send + Sync + 'static>,
+    cache: Arc<Cache>,
+}
+
+impl Drop for InvalidatingFile {
+    fn drop(&mut self) {
+        self.cache.invalidate(&self.path, &self.resolved);
+    }
+}
+
+impl VirtualFile for InvalidatingFile {
+    fn last_accessed(&self) -> u64 {
+        self.file.last_accessed()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.file.last_modified()
+    }
+
+    fn created_time(&self) -> u64 {
+        self.file.created_time()
+    }
+
+    fn set_times(&mut self, atime: Option<u64>, mtime: Option<u64>) -> crate::Result<()> {
+        let result = self.file.set_times(atime, mtime);
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> crate::Result<()> {
+        let result = self.file.set_len(new_size);
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_data(offset)
+    }
+
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_hole(offset)
+    }
+
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        let result = self.file.punch_hole(offset, len);
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn unlink(&mut self) -> crate::Result<()> {
+        let result = self.file.unlink();
+        self.cache.invalidate(&self.path, &self.resolved);
+        result
+    }
+
+    fn get_special_fd(&self) -> Option<u32> {
+        self.file.get_special_fd()
+    }
+
+    fn poll_read_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for InvalidatingFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for InvalidatingFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut *self.file).poll_write(cx, buf);
+        if let Poll::Ready(Ok(_)) = &result {
+            self.cache.invalidate(&self.path, &self.resolved);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for InvalidatingFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        Pin::new(&mut *self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut *self.file).poll_complete(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mem_fs::FileSystem as MemFS, ops, OverlayFileSystem, UnionFileSystem};
+
+    #[tokio::test]
+    async fn file_contents_are_cached() {
+        let inner = MemFS::default();
+        ops::write(&inner, "/file.txt", b"Hello, World!")
+            .await
+            .unwrap();
+        let fs = CachingFileSystem::new(inner, CacheConfig::default());
+
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"Hello, World!");
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"Hello, World!");
+
+        let stats = fs.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.memory_bytes, 13);
+    }
+
+    #[tokio::test]
+    async fn writes_invalidate_the_cache() {
+        let fs = CachingFileSystem::new(MemFS::default(), CacheConfig::default());
+        ops::write(&fs, "/file.txt", b"first").await.unwrap();
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"first");
+        assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len(), 5);
+
+        ops::write(&fs, "/file.txt", b"second").await.unwrap();
+
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"second");
+        assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len(), 6);
+        assert!(fs.stats().invalidations > 0);
+
+        fs.remove_file(Path::new("/file.txt")).unwrap();
+        assert_eq!(
+            fs.metadata(Path::new("/file.txt")),
+            Err(FsError::EntryNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn flushing_invalidates_what_was_read_while_writing() {
+        use tokio::io::AsyncWriteExt;
+
+        let fs = CachingFileSystem::new(MemFS::default(), CacheConfig::default());
+        let mut file = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open("/file.txt")
+            .unwrap();
+
+        file.write_all(b"first").await.unwrap();
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"first");
+        file.write_all(b", second").await.unwrap();
+        file.flush().await.unwrap();
+
+        assert_eq!(ops::read(&fs, "/file.txt").await.unwrap(), b"first, second");
+    }
+
+    #[tokio::test]
+    async fn metadata_is_invalidated_by_every_write() {
+        use tokio::io::AsyncWriteExt;
+
+        let fs = CachingFileSystem::new(MemFS::default(), CacheConfig::default());
+        let mut file = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open("/file.txt")
+            .unwrap();
+
+        file.write_all(b"first").await.unwrap();
+        assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len(), 5);
+        file.write_all(b", second").await.unwrap();
+        assert_eq!(fs.metadata(Path::new("/file.txt")).unwrap().len(), 13);
+    }
+
+    #[cfg(all(unix, feature = "host-fs"))]
+    #[tokio::test]
+    async fn writes_invalidate_paths_through_symlinks() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp.path().join("dir")).unwrap();
+        std::fs::write(temp.path().join("dir/file.txt"), b"first").unwrap();
+        std::os::unix::fs::symlink("dir/file.txt", temp.path().join("link.txt")).unwrap();
+        std::os::unix::fs::symlink("dir", temp.path().join("dir-link")).unwrap();
+        let inner = crate::host_fs::FileSystem::new(tokio::runtime::Handle::current(), temp.path())
+            .unwrap();
+        let fs = CachingFileSystem::new(inner, CacheConfig::default());
+
+        for path in ["/link.txt", "/dir-link/file.txt"] {
+            assert_eq!(ops::read(&fs, path).await.unwrap(), b"first");
+            assert_eq!(fs.metadata(Path::new(path)).unwrap().len(), 5);
+        }
+        assert_eq!(ops::read(&fs, "/link.txt").await.unwrap(), b"first");
+        assert_eq!(fs.stats().hits, 1);
+
+        ops::write(&fs, "/dir/file.txt", b"second").await.unwrap();
+
+        for path in ["/link.txt", "/dir-link/file.txt"] {
+            assert_eq!(ops::read(&fs, path).await.unwrap(), b"second");
+            assert_eq!(fs.metadata(Path::new(path)).unwrap().len(), 6);
+        }
+
+        // Writing through one symlink invalidates what was read through another
+        ops::write(&fs, "/dir-link/file.txt", b"third")
+            .await
+            .unwrap();
+        assert_eq!(ops::read(&fs, "/link.txt").await.unwrap(), b"third");
+
+        fs.remove_file(Path::new("/dir/file.txt")).unwrap();
+        assert_eq!(
+            fs.metadata(Path::new("/link.txt")),
+            Err(FsError::EntryNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_files_are_cached() {
+        let fs = CachingFileSystem::new(MemFS::default(), CacheConfig::default());
+
+        assert_eq!(
+            fs.metadata(Path::new("/missing.txt")),
+            Err(FsError::EntryNotFound)
+        );
+        assert_eq!(
+            fs.metadata(Path::new("/missing.txt")),
+            Err(FsError::EntryNotFound)
+        );
+        assert_eq!(fs.stats().metadata_hits, 1);
+
+        ops::touch(&fs, "/missing.txt").unwrap();
+        assert!(fs.metadata(Path::new("/missing.txt")).unwrap().is_file());
+    }
+
+    #[tokio::test]
+    async fn evicted_contents_are_spilled_to_disk() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = CacheConfig {
+            max_memory: 10,
+            spill: Some(SpillConfig {
+                dir: temp.path().to_path_buf(),
+                max_bytes: 1024,
+            }),
+            ..Default::default()
+        };
+        let inner = MemFS::default();
+        ops::write(&inner, "/first.txt", b"0123456789")
+            .await
+            .unwrap();
+        ops::write(&inner, "/second.txt", b"abcdefghij")
+            .await
+            .unwrap();
+        let fs = CachingFileSystem::new(inner, config);
+
+        ops::read(&fs, "/first.txt").await.unwrap();
+        ops::read(&fs, "/second.txt").await.unwrap();
+        let stats = fs.stats();
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.spill_bytes, 10);
+
+        assert_eq!(ops::read(&fs, "/first.txt").await.unwrap(), b"0123456789");
+        assert_eq!(fs.stats().spill_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn composes_with_overlay_and_union() {
+        let secondary = MemFS::default();
+        ops::write(&secondary, "/file.txt", b"secondary")
+            .await
+            .unwrap();
+        let cached = CachingFileSystem::new(secondary, CacheConfig::default());
+        let overlay = OverlayFileSystem::new(MemFS::default(), [cached.clone()]);
+
+        assert_eq!(
+            ops::read(&overlay, "/file.txt").await.unwrap(),
+            b"secondary"
+        );
+        assert_eq!(
+            ops::read(&overlay, "/file.txt").await.unwrap(),
+            b"secondary"
+        );
+        assert_eq!(cached.stats().hits, 1);
+
+        let union = UnionFileSystem::new();
+        union
+            .mount(
+                "cached".to_string(),
+                Path::new("/mnt"),
+                Box::new(cached.clone()),
+            )
+            .unwrap();
+        assert_eq!(
+            ops::read(&union, "/mnt/file.txt").await.unwrap(),
+            b"secondary"
+        );
+        assert_eq!(cached.stats().hits, 2);
+
+        // Writing through the union should invalidate the cached copy
+        ops::write(&union, "/mnt/file.txt", b"updated")
+            .await
+            .unwrap();
+        assert_eq!(
+            ops::read(&union, "/mnt/file.txt").await.unwrap(),
+            b"updated"
+        );
+        assert_eq!(ops::read(&overlay, "/file.txt").await.unwrap(), b"updated");
+    }
+}
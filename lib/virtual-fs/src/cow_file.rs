@@ -74,12 +74,17 @@ impl CopyOnWriteFile {
                     Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
                     Poll::Ready(Ok(())) => {}
                 }
-                if read_temp.remaining() == 0 {
-                    return Poll::Pending;
+                let read = read_temp.filled().len();
+                if read == 0 {
+                    return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
                 }
-                *pos += read_temp.remaining() as u64;
 
+                // The copy goes where it was read from, wherever the cursor is
+                let cursor = self.buf.data.position();
+                self.buf.data.set_position(*pos);
                 self.buf.data.write_all(read_temp.filled()).unwrap();
+                self.buf.data.set_position(cursor);
+                *pos += read as u64;
             }
             self.state = CowState::Copied;
         }
@@ -1,12 +1,13 @@
 use crate::{
     DirEntry, FileType, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir, Result,
-    VirtualFile,
+    VirtualFile, XattrMode,
 };
 use bytes::{Buf, Bytes};
 use futures::future::BoxFuture;
 #[cfg(feature = "enable-serde")]
 use serde::{de, Deserialize, Serialize};
 use std::convert::TryInto;
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::io::{self, Seek};
 use std::path::{Component, Path, PathBuf};
@@ -244,6 +245,279 @@ impl crate::FileSystem for FileSystem {
     ) -> Result<()> {
         Err(FsError::Unsupported)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        let path = self.prepare_path(path);
+
+        xattr::get(&path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.prepare_path(path);
+
+            xattr::set(&path, name, value, mode)
+        })
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        let path = self.prepare_path(path);
+
+        xattr::list(&path)
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.prepare_path(path);
+
+            xattr::remove(&path, name)
+        })
+    }
+
+    #[cfg(unix)]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        let path = self.prepare_path(path);
+
+        if path.parent().is_none() {
+            return Err(FsError::BaseNotDirectory);
+        }
+
+        // Binding a host socket is the only portable way to create the
+        // socket inode, the listener itself is not needed afterwards.
+        std::os::unix::net::UnixListener::bind(path)
+            .map(drop)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::AddrInUse => FsError::AlreadyExists,
+                _ => err.into(),
+            })
+    }
+}
+
+/// Thin wrappers around the Linux calls for working with sparse files.
+#[cfg(target_os = "linux")]
+mod sparse {
+    use std::fs;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    use crate::{FsError, Result};
+
+    fn last_error() -> FsError {
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ENXIO) => FsError::OffsetOutOfRange,
+            Some(libc::EOPNOTSUPP) => FsError::Unsupported,
+            Some(libc::ENOSPC) => FsError::StorageFull,
+            _ => err.into(),
+        }
+    }
+
+    /// Seek with `SEEK_DATA` or `SEEK_HOLE`, leaving the file's position
+    /// untouched.
+    pub(super) fn seek(file: &fs::File, offset: u64, whence: libc::c_int) -> Result<u64> {
+        let offset = i64::try_from(offset).map_err(|_| FsError::InvalidInput)?;
+        let fd = file.as_raw_fd();
+
+        unsafe {
+            let current = libc::lseek(fd, 0, libc::SEEK_CUR);
+            if current < 0 {
+                return Err(last_error());
+            }
+
+            let found = libc::lseek(fd, offset, whence);
+            let result = if found < 0 {
+                Err(last_error())
+            } else {
+                Ok(found as u64)
+            };
+
+            libc::lseek(fd, current, libc::SEEK_SET);
+            result
+        }
+    }
+
+    pub(super) fn punch_hole(file: &fs::File, offset: u64, len: u64) -> Result<()> {
+        let offset = i64::try_from(offset).map_err(|_| FsError::InvalidInput)?;
+        let len = i64::try_from(len).map_err(|_| FsError::InvalidInput)?;
+
+        let ret = unsafe {
+            libc::fallocate(
+                file.as_raw_fd(),
+                libc::FALLOC_FL_PUNCH_HOLE | libc::FALLOC_FL_KEEP_SIZE,
+                offset,
+                len,
+            )
+        };
+        if ret < 0 {
+            return Err(last_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Thin wrappers around the libc extended attribute calls.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod xattr {
+    use std::ffi::{CString, OsStr, OsString};
+    use std::io;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::Path;
+
+    use crate::{FsError, Result, XattrMode};
+
+    #[cfg(target_os = "linux")]
+    const ENOATTR: i32 = libc::ENODATA;
+    #[cfg(target_os = "macos")]
+    const ENOATTR: i32 = libc::ENOATTR;
+
+    fn c_string(value: &OsStr) -> Result<CString> {
+        CString::new(value.as_bytes()).map_err(|_| FsError::InvalidInput)
+    }
+
+    fn last_error() -> FsError {
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(ENOATTR) => FsError::NoAttribute,
+            Some(libc::ENOTSUP) => FsError::Unsupported,
+            Some(libc::E2BIG) | Some(libc::ENAMETOOLONG) => FsError::InvalidInput,
+            Some(libc::ENOSPC) => FsError::StorageFull,
+            _ => err.into(),
+        }
+    }
+
+    /// Calls `f` first to find out how big the buffer must be and then again
+    /// to fill it, retrying if the value grew in between.
+    fn read_buffer(mut f: impl FnMut(*mut u8, usize) -> isize) -> Result<Vec<u8>> {
+        loop {
+            let len = f(std::ptr::null_mut(), 0);
+            if len < 0 {
+                return Err(last_error());
+            }
+            let mut buf = vec![0u8; len as usize];
+            let len = f(buf.as_mut_ptr(), buf.len());
+            if len < 0 {
+                if io::Error::last_os_error().raw_os_error() == Some(libc::ERANGE) {
+                    continue;
+                }
+                return Err(last_error());
+            }
+            buf.truncate(len as usize);
+            return Ok(buf);
+        }
+    }
+
+    pub(super) fn get(path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        let path = c_string(path.as_os_str())?;
+        let name = c_string(name)?;
+        read_buffer(|buf, size| unsafe {
+            #[cfg(target_os = "linux")]
+            let ret = libc::getxattr(path.as_ptr(), name.as_ptr(), buf.cast(), size);
+            #[cfg(target_os = "macos")]
+            let ret = libc::getxattr(path.as_ptr(), name.as_ptr(), buf.cast(), size, 0, 0);
+            ret
+        })
+    }
+
+    pub(super) fn set(path: &Path, name: &OsStr, value: &[u8], mode: XattrMode) -> Result<()> {
+        let path = c_string(path.as_os_str())?;
+        let name = c_string(name)?;
+        let flags = match mode {
+            XattrMode::Upsert => 0,
+            XattrMode::Create => libc::XATTR_CREATE,
+            XattrMode::Replace => libc::XATTR_REPLACE,
+        };
+        let ret = unsafe {
+            #[cfg(target_os = "linux")]
+            let ret = libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                flags,
+            );
+            #[cfg(target_os = "macos")]
+            let ret = libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+                flags,
+            );
+            ret
+        };
+        if ret < 0 {
+            return Err(match io::Error::last_os_error().raw_os_error() {
+                Some(libc::EEXIST) => FsError::AlreadyExists,
+                _ => last_error(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(super) fn list(path: &Path) -> Result<Vec<OsString>> {
+        let path = c_string(path.as_os_str())?;
+        let names = read_buffer(|buf, size| unsafe {
+            #[cfg(target_os = "linux")]
+            let ret = libc::listxattr(path.as_ptr(), buf.cast(), size);
+            #[cfg(target_os = "macos")]
+            let ret = libc::listxattr(path.as_ptr(), buf.cast(), size, 0);
+            ret
+        })?;
+        Ok(names
+            .split(|b| *b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| OsString::from_vec(name.to_vec()))
+            .collect())
+    }
+
+    pub(super) fn remove(path: &Path, name: &OsStr) -> Result<()> {
+        let path = c_string(path.as_os_str())?;
+        let name = c_string(name)?;
+        let ret = unsafe {
+            #[cfg(target_os = "linux")]
+            let ret = libc::removexattr(path.as_ptr(), name.as_ptr());
+            #[cfg(target_os = "macos")]
+            let ret = libc::removexattr(path.as_ptr(), name.as_ptr(), 0);
+            ret
+        };
+        if ret < 0 {
+            return Err(last_error());
+        }
+        Ok(())
+    }
+}
+
+/// Extended attributes are not available on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod xattr {
+    use std::ffi::{OsStr, OsString};
+    use std::path::Path;
+
+    use crate::{FsError, Result, XattrMode};
+
+    pub(super) fn get(_path: &Path, _name: &OsStr) -> Result<Vec<u8>> {
+        Err(FsError::Unsupported)
+    }
+
+    pub(super) fn set(_path: &Path, _name: &OsStr, _value: &[u8], _mode: XattrMode) -> Result<()> {
+        Err(FsError::Unsupported)
+    }
+
+    pub(super) fn list(_path: &Path) -> Result<Vec<OsString>> {
+        Err(FsError::Unsupported)
+    }
+
+    pub(super) fn remove(_path: &Path, _name: &OsStr) -> Result<()> {
+        Err(FsError::Unsupported)
+    }
 }
 
 impl TryInto<Metadata> for std::fs::Metadata {
@@ -543,6 +817,56 @@ impl VirtualFile for File {
         fs::File::set_len(&self.inner_std, new_size).map_err(Into::into)
     }
 
+    #[cfg(target_os = "linux")]
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        sparse::seek(&self.inner_std, offset, libc::SEEK_DATA)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        sparse::seek(&self.inner_std, offset, libc::SEEK_HOLE)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        sparse::punch_hole(&self.inner_std, offset, len)
+    }
+
+    #[cfg(unix)]
+    fn read_at<'a>(
+        &'a mut self,
+        buf: &'a mut [u8],
+        offset: u64,
+    ) -> BoxFuture<'a, io::Result<usize>> {
+        use std::os::unix::fs::FileExt;
+        Box::pin(async move {
+            // Waits for writes that are still in flight
+            tokio::io::AsyncWriteExt::flush(self).await?;
+            let mut total_read = 0;
+            while total_read < buf.len() {
+                match self
+                    .inner_std
+                    .read_at(&mut buf[total_read..], offset + total_read as u64)
+                {
+                    Ok(0) => break,
+                    Ok(read) => total_read += read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(total_read)
+        })
+    }
+
+    #[cfg(unix)]
+    fn write_all_at<'a>(&'a mut self, buf: &'a [u8], offset: u64) -> BoxFuture<'a, io::Result<()>> {
+        use std::os::unix::fs::FileExt;
+        Box::pin(async move {
+            tokio::io::AsyncWriteExt::flush(self).await?;
+            self.inner_std.write_all_at(buf, offset)
+        })
+    }
+
     fn unlink(&mut self) -> Result<()> {
         fs::remove_file(&self.host_path).map_err(Into::into)
     }
@@ -1490,4 +1814,66 @@ mod tests {
             panic!("next: {s:?}");
         }
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_create_socket() {
+        let temp = TempDir::new().unwrap();
+        let fs = FileSystem::new(Handle::current(), temp.path()).expect("get filesystem");
+
+        assert_eq!(fs.create_socket(Path::new("/app.sock")), Ok(()));
+        assert!(fs
+            .metadata(Path::new("/app.sock"))
+            .unwrap()
+            .file_type()
+            .is_socket());
+        assert_eq!(
+            fs.create_socket(Path::new("/app.sock")),
+            Err(FsError::AlreadyExists),
+            "binding to a path that is already taken",
+        );
+
+        assert_eq!(fs.remove_file(Path::new("/app.sock")), Ok(()));
+        assert!(!temp.path().join("app.sock").exists());
+    }
+
+    #[tokio::test]
+    async fn test_xattr() {
+        use crate::XattrMode;
+        use std::ffi::OsStr;
+
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("foo.txt"), b"").unwrap();
+        let fs = FileSystem::new(Handle::current(), temp.path()).expect("get filesystem");
+
+        let name = OsStr::new("user.wasmer.test");
+        match fs
+            .set_xattr(Path::new("/foo.txt"), name, b"bar", XattrMode::Create)
+            .await
+        {
+            Ok(()) => {}
+            // Not every host file system supports extended attributes
+            Err(FsError::Unsupported) => return,
+            Err(e) => panic!("unable to set the attribute: {e}"),
+        }
+
+        assert_eq!(
+            fs.get_xattr(Path::new("/foo.txt"), name),
+            Ok(b"bar".to_vec())
+        );
+        assert!(fs
+            .list_xattr(Path::new("/foo.txt"))
+            .unwrap()
+            .contains(&name.to_owned()));
+        assert_eq!(
+            fs.set_xattr(Path::new("/foo.txt"), name, b"baz", XattrMode::Create)
+                .await,
+            Err(FsError::AlreadyExists),
+        );
+        assert_eq!(fs.remove_xattr(Path::new("/foo.txt"), name).await, Ok(()));
+        assert_eq!(
+            fs.get_xattr(Path::new("/foo.txt"), name),
+            Err(FsError::NoAttribute)
+        );
+    }
 }
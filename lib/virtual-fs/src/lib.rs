@@ -7,7 +7,7 @@ extern crate pretty_assertions;
 use futures::future::BoxFuture;
 use shared_buffer::OwnedBuffer;
 use std::any::Any;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::io;
 use std::ops::Deref;
@@ -22,6 +22,7 @@ pub mod arc_file;
 pub mod arc_fs;
 pub mod buffer_file;
 pub mod builder;
+mod cache_fs;
 pub mod combine_file;
 pub mod cow_file;
 pub mod dual_write_file;
@@ -32,6 +33,7 @@ pub mod mem_fs;
 pub mod null_file;
 pub mod passthru_fs;
 pub mod random_file;
+pub mod socket_file;
 pub mod special_file;
 pub mod tmp_fs;
 pub mod union_fs;
@@ -41,6 +43,7 @@ mod filesystems;
 pub(crate) mod ops;
 mod overlay_fs;
 pub mod pipe;
+mod quota_fs;
 mod static_file;
 #[cfg(feature = "static-fs")]
 pub mod static_fs;
@@ -55,6 +58,7 @@ pub use arc_file::*;
 pub use arc_fs::*;
 pub use buffer_file::*;
 pub use builder::*;
+pub use cache_fs::{CacheConfig, CacheStats, CachingFileSystem, SpillConfig};
 pub use combine_file::*;
 pub use cow_file::*;
 pub use dual_write_file::*;
@@ -64,6 +68,8 @@ pub use null_file::*;
 pub use overlay_fs::OverlayFileSystem;
 pub use passthru_fs::*;
 pub use pipe::*;
+pub use quota_fs::{Quota, QuotaError, QuotaFileSystem, QuotaLimits};
+pub use socket_file::*;
 pub use special_file::*;
 pub use static_file::StaticFile;
 pub use tmp_fs::*;
@@ -102,6 +108,52 @@ pub trait FileSystem: fmt::Debug + Send + Sync + 'static + Upcastable {
 
     fn mount(&self, name: String, path: &Path, fs: Box<dyn FileSystem + Send + Sync>)
         -> Result<()>;
+
+    /// Reads the value of the extended attribute `name` on `path`.
+    ///
+    /// Returns [`FsError::NoAttribute`] when the entry exists but does not
+    /// carry the attribute.
+    #[allow(unused_variables)]
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        Err(FsError::Unsupported)
+    }
+
+    /// Sets the value of the extended attribute `name` on `path`.
+    ///
+    /// This is asynchronous because layered file systems may need to copy
+    /// the entry into a writable layer before the attribute can be stored.
+    #[allow(unused_variables)]
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Err(FsError::Unsupported) })
+    }
+
+    /// Lists the names of all the extended attributes set on `path`.
+    #[allow(unused_variables)]
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        Err(FsError::Unsupported)
+    }
+
+    /// Removes the extended attribute `name` from `path`.
+    #[allow(unused_variables)]
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Err(FsError::Unsupported) })
+    }
+
+    /// Creates a socket entry at `path` that an `AF_UNIX` socket can be
+    /// bound to (see [`SocketFile`]). The entry is removed again with
+    /// [`FileSystem::remove_file`].
+    ///
+    /// Fails with [`FsError::AlreadyExists`] if anything exists at `path`.
+    #[allow(unused_variables)]
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        Err(FsError::Unsupported)
+    }
 }
 
 impl dyn FileSystem + 'static {
@@ -165,6 +217,45 @@ where
     ) -> Result<()> {
         (**self).mount(name, path, fs)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        (**self).get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { (**self).set_xattr(path, name, value, mode).await })
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        (**self).list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { (**self).remove_xattr(path, name).await })
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        (**self).create_socket(path)
+    }
+}
+
+/// Controls how [`FileSystem::set_xattr`] behaves when the attribute
+/// already exists (or doesn't).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum XattrMode {
+    /// Create the attribute, or replace its value if it already exists
+    #[default]
+    Upsert,
+    /// Fail with [`FsError::AlreadyExists`] if the attribute already exists
+    Create,
+    /// Fail with [`FsError::NoAttribute`] if the attribute does not exist
+    Replace,
 }
 
 pub trait FileOpener {
@@ -412,6 +503,89 @@ pub trait VirtualFile:
         })
     }
 
+    /// Reads from the file at `offset` without moving its cursor (i.e.
+    /// `pread`), fewer bytes than the buffer holds are only returned at the
+    /// end of the file.
+    ///
+    /// The default seeks to the offset and back again, file systems that
+    /// support positional reads should override it.
+    fn read_at<'a>(
+        &'a mut self,
+        buf: &'a mut [u8],
+        offset: u64,
+    ) -> BoxFuture<'a, std::io::Result<usize>> {
+        Box::pin(async move {
+            let cursor = self.stream_position().await?;
+            self.seek(io::SeekFrom::Start(offset)).await?;
+            let mut total_read = 0;
+            let ret = loop {
+                if total_read >= buf.len() {
+                    break Ok(total_read);
+                }
+                match self.read(&mut buf[total_read..]).await {
+                    Ok(0) => break Ok(total_read),
+                    Ok(read) => total_read += read,
+                    Err(err) => break Err(err),
+                }
+            };
+            self.seek(io::SeekFrom::Start(cursor)).await?;
+            ret
+        })
+    }
+
+    /// Writes all of `buf` to the file at `offset` without moving its
+    /// cursor (i.e. `pwrite`).
+    ///
+    /// The default seeks to the offset and back again, file systems that
+    /// support positional writes should override it.
+    fn write_all_at<'a>(
+        &'a mut self,
+        buf: &'a [u8],
+        offset: u64,
+    ) -> BoxFuture<'a, std::io::Result<()>> {
+        Box::pin(async move {
+            let cursor = self.stream_position().await?;
+            self.seek(io::SeekFrom::Start(offset)).await?;
+            let ret = self.write_all(buf).await;
+            self.seek(io::SeekFrom::Start(cursor)).await?;
+            ret
+        })
+    }
+
+    /// Returns the offset of the first byte of data at or after `offset`,
+    /// skipping over any holes (i.e. `SEEK_DATA`).
+    ///
+    /// Files that do not keep track of holes treat their whole contents as
+    /// data. Seeking at or past the end of the file returns
+    /// [`FsError::OffsetOutOfRange`].
+    fn seek_data(&mut self, offset: u64) -> Result<u64> {
+        if offset < self.size() {
+            Ok(offset)
+        } else {
+            Err(FsError::OffsetOutOfRange)
+        }
+    }
+
+    /// Returns the offset of the first hole at or after `offset` (i.e.
+    /// `SEEK_HOLE`). The end of the file always counts as a hole.
+    ///
+    /// Seeking at or past the end of the file returns
+    /// [`FsError::OffsetOutOfRange`].
+    fn seek_hole(&mut self, offset: u64) -> Result<u64> {
+        let size = self.size();
+        if offset < size {
+            Ok(size)
+        } else {
+            Err(FsError::OffsetOutOfRange)
+        }
+    }
+
+    /// Deallocates the byte range `offset..offset + len` so it reads back as
+    /// zeroes, without changing the size of the file
+    fn punch_hole(&mut self, _offset: u64, _len: u64) -> Result<()> {
+        Err(FsError::Unsupported)
+    }
+
     /// Polls the file for when there is data to be read
     fn poll_read_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<usize>>;
 
@@ -533,16 +707,33 @@ pub enum FsError {
     DirectoryNotEmpty,
     #[error("storage full")]
     StorageFull,
+    /// The disk quota has been exceeded
+    #[error("disk quota exceeded")]
+    QuotaExceeded,
     /// Some other unhandled error. If you see this, it's probably a bug.
     #[error("unknown error found")]
     UnknownError,
     /// Operation is not supported on this filesystem
     #[error("unsupported")]
     Unsupported,
+    /// The requested extended attribute does not exist
+    #[error("no such attribute")]
+    NoAttribute,
+    /// The offset is at or beyond the end of the file, or there is no more
+    /// data (or hole) to seek to
+    #[error("offset out of range")]
+    OffsetOutOfRange,
 }
 
 impl From<io::Error> for FsError {
     fn from(io_error: io::Error) -> Self {
+        if let Some(fs_error) = io_error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<FsError>())
+        {
+            return *fs_error;
+        }
+
         match io_error.kind() {
             io::ErrorKind::AddrInUse => FsError::AddressInUse,
             io::ErrorKind::AddrNotAvailable => FsError::AddressNotAvailable,
@@ -598,10 +789,16 @@ impl From<FsError> for io::Error {
             FsError::NoDevice => io::ErrorKind::Other,
             FsError::DirectoryNotEmpty => io::ErrorKind::Other,
             FsError::UnknownError => io::ErrorKind::Other,
-            FsError::StorageFull => io::ErrorKind::Other,
             FsError::Unsupported => io::ErrorKind::Unsupported,
-            // NOTE: Add this once the "io_error_more" Rust feature is stabilized
-            // FsError::StorageFull => io::ErrorKind::StorageFull,
+            FsError::NoAttribute | FsError::OffsetOutOfRange => {
+                return io::Error::new(io::ErrorKind::Other, val);
+            }
+            // NOTE: Use io::ErrorKind::StorageFull and io::ErrorKind::FilesystemQuotaExceeded
+            // once the "io_error_more" Rust feature is stabilized. Until then the original
+            // error is kept around so it survives a round trip through io::Error.
+            FsError::StorageFull | FsError::QuotaExceeded => {
+                return io::Error::new(io::ErrorKind::Other, val);
+            }
         };
         kind.into()
     }
@@ -726,6 +923,13 @@ impl FileType {
         }
     }
 
+    pub fn new_socket() -> Self {
+        Self {
+            socket: true,
+            ..Default::default()
+        }
+    }
+
     pub fn is_dir(&self) -> bool {
         self.dir
     }
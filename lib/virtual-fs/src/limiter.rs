@@ -82,11 +82,14 @@ mod tracked_vec {
         }
 
         pub fn resize(&mut self, new_len: usize, value: u8) -> Result<(), FsError> {
+            let old_len = self.data.len();
             let old_capacity = self.data.capacity();
             self.data.resize(new_len, value);
             if let Some(limiter) = &self.limiter {
                 let new = self.data.capacity() - old_capacity;
-                limiter.on_grow(new)?;
+                limiter
+                    .on_grow(new)
+                    .inspect_err(|_| self.undo_grow(old_len, old_capacity))?;
             }
             Ok(())
         }
@@ -102,14 +105,24 @@ mod tracked_vec {
         }
 
         pub fn reserve_exact(&mut self, additional: usize) -> Result<(), FsError> {
+            let old_len = self.data.len();
             let old_capacity = self.data.capacity();
             self.data.reserve_exact(additional);
             if let Some(limiter) = &self.limiter {
                 let new = self.data.capacity() - old_capacity;
-                limiter.on_grow(new)?;
+                limiter
+                    .on_grow(new)
+                    .inspect_err(|_| self.undo_grow(old_len, old_capacity))?;
             }
             Ok(())
         }
+
+        /// Gives back memory that the limiter refused, so it isn't released
+        /// when the vector is dropped without ever having been counted
+        fn undo_grow(&mut self, old_len: usize, old_capacity: usize) {
+            self.data.truncate(old_len);
+            self.data.shrink_to(old_capacity);
+        }
     }
 
     impl Drop for TrackedVec {
@@ -13,6 +13,7 @@ use super::*;
 use crate::limiter::TrackedVec;
 use crate::{CopyOnWriteFile, FsError, Result, VirtualFile};
 use std::cmp;
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::fmt;
 use std::io;
@@ -173,7 +174,7 @@ impl VirtualFile for FileHandle {
 
         let inode = fs.storage.get(self.inode);
         match inode {
-            Some(Node::File(node)) => node.file.len().try_into().unwrap_or(0),
+            Some(Node::File(node)) => node.file.len(),
             Some(Node::OffloadedFile(node)) => node.file.len(),
             Some(Node::ReadOnlyFile(node)) => node.file.len().try_into().unwrap_or(0),
             Some(Node::CustomFile(node)) => {
@@ -202,8 +203,7 @@ impl VirtualFile for FileHandle {
         let inode = fs.storage.get_mut(self.inode);
         match inode {
             Some(Node::File(FileNode { file, metadata, .. })) => {
-                file.buffer
-                    .resize(new_size.try_into().map_err(|_| FsError::UnknownError)?, 0)?;
+                file.set_len(new_size)?;
                 metadata.len = new_size;
             }
             Some(Node::OffloadedFile(OffloadedFileNode { file, metadata, .. })) => {
@@ -227,6 +227,90 @@ impl VirtualFile for FileHandle {
         Ok(())
     }
 
+    fn seek_data(&mut self, offset: u64) -> Result<u64> {
+        let mut fs = self.filesystem.inner.write().map_err(|_| FsError::Lock)?;
+
+        let inode = fs.storage.get_mut(self.inode);
+        let len = match inode {
+            Some(Node::File(node)) => return node.file.seek_data(offset),
+            Some(Node::OffloadedFile(node)) => node.file.len(),
+            Some(Node::ReadOnlyFile(node)) => node.file.len() as u64,
+            Some(Node::CustomFile(node)) => {
+                let mut file = node.file.lock().unwrap();
+                return file.seek_data(offset);
+            }
+            Some(Node::ArcFile(_)) => {
+                drop(fs);
+                return self.lazy_load_arc_file_mut()?.seek_data(offset);
+            }
+            _ => return Err(FsError::NotAFile),
+        };
+
+        // Everything else is stored densely
+        if offset < len {
+            Ok(offset)
+        } else {
+            Err(FsError::OffsetOutOfRange)
+        }
+    }
+
+    fn seek_hole(&mut self, offset: u64) -> Result<u64> {
+        let mut fs = self.filesystem.inner.write().map_err(|_| FsError::Lock)?;
+
+        let inode = fs.storage.get_mut(self.inode);
+        let len = match inode {
+            Some(Node::File(node)) => return node.file.seek_hole(offset),
+            Some(Node::OffloadedFile(node)) => node.file.len(),
+            Some(Node::ReadOnlyFile(node)) => node.file.len() as u64,
+            Some(Node::CustomFile(node)) => {
+                let mut file = node.file.lock().unwrap();
+                return file.seek_hole(offset);
+            }
+            Some(Node::ArcFile(_)) => {
+                drop(fs);
+                return self.lazy_load_arc_file_mut()?.seek_hole(offset);
+            }
+            _ => return Err(FsError::NotAFile),
+        };
+
+        // Everything else is stored densely, so the only hole is at the end
+        if offset < len {
+            Ok(len)
+        } else {
+            Err(FsError::OffsetOutOfRange)
+        }
+    }
+
+    fn punch_hole(&mut self, offset: u64, len: u64) -> Result<()> {
+        if !self.writable {
+            return Err(FsError::PermissionDenied);
+        }
+
+        let mut fs = self.filesystem.inner.write().map_err(|_| FsError::Lock)?;
+
+        let inode = fs.storage.get_mut(self.inode);
+        match inode {
+            Some(Node::File(FileNode { file, metadata, .. })) => {
+                file.punch_hole(offset, len)?;
+                metadata.modified = time();
+            }
+            Some(Node::CustomFile(node)) => {
+                let mut file = node.file.lock().unwrap();
+                file.punch_hole(offset, len)?;
+            }
+            Some(Node::ReadOnlyFile { .. }) => return Err(FsError::PermissionDenied),
+            Some(Node::ArcFile { .. }) => {
+                drop(fs);
+                let file = self.lazy_load_arc_file_mut()?;
+                file.punch_hole(offset, len)?;
+            }
+            Some(Node::OffloadedFile { .. }) => return Err(FsError::Unsupported),
+            _ => return Err(FsError::NotAFile),
+        }
+
+        Ok(())
+    }
+
     fn unlink(&mut self) -> Result<()> {
         let filesystem = self.filesystem.clone();
         let inode = self.inode;
@@ -266,7 +350,7 @@ impl VirtualFile for FileHandle {
             let mut fs = filesystem.inner.write().map_err(|_| FsError::Lock)?;
 
             // Remove the file from the storage.
-            fs.storage.remove(inode_of_file);
+            fs.remove_node(inode_of_file);
 
             // Remove the child from the parent directory.
             fs.remove_child_from_node(inode_of_parent, position)?;
@@ -393,7 +477,7 @@ impl VirtualFile for FileHandle {
         let inode = fs.storage.get_mut(self.inode);
         match inode {
             Some(Node::File(node)) => {
-                let remaining = node.file.buffer.len() - (self.cursor as usize);
+                let remaining = node.file.len().saturating_sub(self.cursor) as usize;
                 Poll::Ready(Ok(remaining))
             }
             Some(Node::OffloadedFile(node)) => {
@@ -756,9 +840,21 @@ impl AsyncRead for FileHandle {
                     Poll::Ready(read.map(|_| ()))
                 }
                 Some(Node::CustomFile(node)) => {
-                    let mut file = node.file.lock().unwrap();
-                    let file = Pin::new(file.as_mut());
-                    file.poll_read(cx, buf)
+                    let mut guard = node.file.lock().unwrap();
+
+                    let file = Pin::new(guard.as_mut());
+                    if let Err(err) = file.start_seek(io::SeekFrom::Start(cursor)) {
+                        return Poll::Ready(Err(err));
+                    }
+
+                    let file = Pin::new(guard.as_mut());
+                    let _ = file.poll_complete(cx);
+
+                    let filled = buf.filled().len();
+                    let file = Pin::new(guard.as_mut());
+                    let ret = file.poll_read(cx, buf);
+                    cursor += (buf.filled().len() - filled) as u64;
+                    ret
                 }
                 Some(Node::ArcFile(_)) => {
                     drop(fs);
@@ -815,9 +911,21 @@ impl AsyncSeek for FileHandle {
                     Ok(())
                 }
                 Some(Node::CustomFile(node)) => {
+                    // The cursor is kept here rather than in the file, which
+                    // other handles of the same file share
                     let mut file = node.file.lock().unwrap();
+                    let next_cursor = match position {
+                        io::SeekFrom::Start(offset) => Some(offset),
+                        io::SeekFrom::End(offset) => file.size().checked_add_signed(offset),
+                        io::SeekFrom::Current(offset) => cursor.checked_add_signed(offset),
+                    }
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "seeking before the byte 0")
+                    })?;
                     let file = Pin::new(file.as_mut());
-                    file.start_seek(position)
+                    file.start_seek(io::SeekFrom::Start(next_cursor))?;
+                    cursor = next_cursor;
+                    Ok(())
                 }
                 Some(Node::ArcFile(_)) => {
                     drop(fs);
@@ -927,7 +1035,7 @@ impl AsyncWrite for FileHandle {
             match inode {
                 Some(Node::File(node)) => {
                     let bytes_written = node.file.write(buf, &mut cursor)?;
-                    node.metadata.len = node.file.len().try_into().unwrap();
+                    node.metadata.len = node.file.len();
                     bytes_written
                 }
                 Some(Node::OffloadedFile(node)) => {
@@ -1007,7 +1115,7 @@ impl AsyncWrite for FileHandle {
                         .find(|b| !b.is_empty())
                         .map_or(&[][..], |b| &**b);
                     let bytes_written = node.file.write(buf, &mut cursor)?;
-                    node.metadata.len = node.file.buffer.len() as u64;
+                    node.metadata.len = node.file.len();
                     Poll::Ready(Ok(bytes_written))
                 }
                 Some(Node::OffloadedFile(node)) => {
@@ -1159,7 +1267,8 @@ impl AsyncWrite for FileHandle {
 mod test_read_write_seek {
     use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
-    use crate::{mem_fs::*, FileSystem as FS};
+    use super::CHUNK_SIZE;
+    use crate::{mem_fs::*, FileSystem as FS, FsError};
     use std::io;
 
     macro_rules! path {
@@ -1168,6 +1277,55 @@ mod test_read_write_seek {
         };
     }
 
+    #[cfg(feature = "tracking")]
+    #[test]
+    fn test_writes_stop_where_the_limiter_fails() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        use crate::limiter::FsMemoryLimiter;
+
+        #[derive(Debug)]
+        struct Budget(AtomicUsize);
+
+        impl FsMemoryLimiter for Budget {
+            fn on_grow(&self, grown_bytes: usize) -> Result<(), FsError> {
+                self.0
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                        left.checked_sub(grown_bytes)
+                    })
+                    .map(|_| ())
+                    .map_err(|_| FsError::StorageFull)
+            }
+
+            fn on_shrink(&self, shrunk_bytes: usize) {
+                self.0.fetch_add(shrunk_bytes, Ordering::SeqCst);
+            }
+        }
+
+        let budget = Arc::new(Budget(AtomicUsize::new(CHUNK_SIZE as usize)));
+        let mut file = super::File::new(Some(budget.clone()));
+        let mut cursor = 0;
+
+        // Only the first chunk fits
+        let data = vec![1u8; 2 * CHUNK_SIZE as usize];
+        assert_eq!(file.write(&data, &mut cursor).unwrap(), CHUNK_SIZE as usize);
+        assert_eq!(cursor, CHUNK_SIZE);
+        assert_eq!(file.len(), CHUNK_SIZE);
+
+        // Nothing fits, and the chunk that was added for the write is gone
+        let err = file.write(&data, &mut cursor).unwrap_err();
+        assert_eq!(FsError::from(err), FsError::StorageFull);
+        assert_eq!(cursor, CHUNK_SIZE);
+        assert_eq!(file.len(), CHUNK_SIZE);
+        assert_eq!(file.chunks.len(), 1);
+
+        drop(file);
+        assert_eq!(budget.0.load(Ordering::SeqCst), CHUNK_SIZE as usize);
+    }
+
     #[tokio::test]
     async fn test_writing_at_various_positions() {
         let fs = FileSystem::default();
@@ -1438,6 +1596,211 @@ mod test_read_write_seek {
             "failing to read an exact buffer",
         );
     }
+
+    #[test]
+    pub fn sparse_writes_leave_holes() {
+        let mut file = File::new(None);
+
+        // Writing far past the end only allocates the chunk being written to
+        let mut cursor = 10 * CHUNK_SIZE + 5;
+        file.write(b"hello", &mut cursor).unwrap();
+        assert_eq!(file.len(), 10 * CHUNK_SIZE + 10);
+        assert_eq!(file.chunks.len(), 1);
+
+        // The hole reads back as zeroes
+        let mut buf = vec![0xff; 16];
+        let mut cursor = 10 * CHUNK_SIZE - 6;
+        assert_eq!(file.read(&mut buf, &mut cursor).unwrap(), 16);
+        assert_eq!(&buf[..11], &[0; 11]);
+        assert_eq!(&buf[11..], b"hello");
+
+        // Writes that straddle two chunks end up in both of them
+        let mut cursor = CHUNK_SIZE - 2;
+        file.write(b"abcd", &mut cursor).unwrap();
+        assert_eq!(file.chunks.len(), 3);
+        let mut buf = [0; 4];
+        let mut cursor = CHUNK_SIZE - 2;
+        file.read(&mut buf, &mut cursor).unwrap();
+        assert_eq!(&buf, b"abcd");
+
+        // Reading past the end of the file reads nothing
+        let mut cursor = file.len() + 1;
+        assert_eq!(file.read(&mut buf, &mut cursor).unwrap(), 0);
+    }
+
+    #[test]
+    pub fn seek_data_and_hole() {
+        let mut file = File::new(None);
+
+        let mut cursor = 2 * CHUNK_SIZE;
+        file.write(&[1; 100], &mut cursor).unwrap();
+        file.set_len(4 * CHUNK_SIZE).unwrap();
+
+        assert_eq!(file.seek_data(0), Ok(2 * CHUNK_SIZE));
+        assert_eq!(file.seek_data(2 * CHUNK_SIZE + 50), Ok(2 * CHUNK_SIZE + 50));
+        assert_eq!(file.seek_hole(0), Ok(0));
+        assert_eq!(file.seek_hole(2 * CHUNK_SIZE), Ok(2 * CHUNK_SIZE + 100));
+        assert_eq!(file.seek_hole(3 * CHUNK_SIZE), Ok(3 * CHUNK_SIZE));
+
+        // There is no data after the last chunk, and nothing at all past EOF
+        assert_eq!(
+            file.seek_data(2 * CHUNK_SIZE + 100),
+            Err(FsError::OffsetOutOfRange)
+        );
+        assert_eq!(
+            file.seek_hole(4 * CHUNK_SIZE),
+            Err(FsError::OffsetOutOfRange)
+        );
+
+        // Contiguous full chunks are a single data region
+        let mut cursor = 0;
+        file.write(&vec![1; 2 * CHUNK_SIZE as usize], &mut cursor)
+            .unwrap();
+        assert_eq!(file.seek_hole(0), Ok(2 * CHUNK_SIZE + 100));
+    }
+
+    #[test]
+    pub fn punching_holes() {
+        let mut file = File::new(None);
+
+        let mut cursor = 0;
+        file.write(&vec![1; 3 * CHUNK_SIZE as usize], &mut cursor)
+            .unwrap();
+
+        // Punching a whole chunk releases it
+        file.punch_hole(CHUNK_SIZE, CHUNK_SIZE).unwrap();
+        assert_eq!(file.chunks.len(), 2);
+        assert_eq!(file.len(), 3 * CHUNK_SIZE);
+        assert_eq!(file.seek_hole(0), Ok(CHUNK_SIZE));
+        assert_eq!(file.seek_data(CHUNK_SIZE), Ok(2 * CHUNK_SIZE));
+
+        // Punching part of a chunk zeroes it out
+        file.punch_hole(10, 20).unwrap();
+        let mut buf = [0xff; 40];
+        let mut cursor = 0;
+        file.read(&mut buf, &mut cursor).unwrap();
+        assert_eq!(&buf[..10], &[1; 10]);
+        assert_eq!(&buf[10..30], &[0; 20]);
+        assert_eq!(&buf[30..], &[1; 10]);
+
+        // Punching past the end of the file doesn't change its size
+        file.punch_hole(3 * CHUNK_SIZE - 10, 100).unwrap();
+        assert_eq!(file.len(), 3 * CHUNK_SIZE);
+        assert_eq!(file.seek_hole(2 * CHUNK_SIZE), Ok(3 * CHUNK_SIZE - 10));
+    }
+
+    #[test]
+    pub fn shrinking_releases_chunks() {
+        let mut file = File::new(None);
+
+        let mut cursor = 0;
+        file.write(&vec![1; 3 * CHUNK_SIZE as usize], &mut cursor)
+            .unwrap();
+
+        file.set_len(CHUNK_SIZE + 10).unwrap();
+        assert_eq!(file.chunks.len(), 2);
+
+        // Growing the file again must not resurrect the old contents
+        file.set_len(3 * CHUNK_SIZE).unwrap();
+        let mut buf = [0xff; 20];
+        let mut cursor = CHUNK_SIZE;
+        file.read(&mut buf, &mut cursor).unwrap();
+        assert_eq!(&buf[..10], &[1; 10]);
+        assert_eq!(&buf[10..], &[0; 10]);
+    }
+
+    #[tokio::test]
+    async fn test_seeking_past_the_end() {
+        let fs = FileSystem::default();
+
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path!("/foo.txt"))
+            .expect("failed to create a new file");
+
+        assert!(
+            matches!(file.seek(io::SeekFrom::Start(100)).await, Ok(100)),
+            "seeking past the end",
+        );
+        file.write_all(b"foo").await.unwrap();
+        assert_eq!(file.size(), 103, "writing extended the file");
+
+        assert_eq!(file.seek_data(0), Ok(0));
+        assert_eq!(file.seek_hole(0), Ok(103));
+
+        file.punch_hole(0, 103).unwrap();
+        assert_eq!(file.size(), 103, "punching a hole keeps the size");
+        assert_eq!(file.seek_data(0), Err(FsError::OffsetOutOfRange));
+    }
+
+    #[tokio::test]
+    async fn test_positional_io_keeps_the_cursor() {
+        let fs = FileSystem::default();
+
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path!("/foo.txt"))
+            .expect("failed to create a new file");
+
+        file.write_all(b"foobarbaz").await.unwrap();
+        file.seek(io::SeekFrom::Start(3)).await.unwrap();
+
+        file.write_all_at(b"BAZ", 6).await.unwrap();
+        let mut buf = [0u8; 8];
+        assert_eq!(file.read_at(&mut buf, 4).await.unwrap(), 5);
+        assert_eq!(&buf[..5], b"arBAZ");
+
+        assert_eq!(file.stream_position().await.unwrap(), 3);
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "barBAZ");
+    }
+
+    #[tokio::test]
+    async fn test_handles_keep_their_cursor_after_copy_reference() {
+        let fs = FileSystem::default();
+
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path!("/foo.txt"))
+            .expect("failed to create a new file");
+        let mut other = fs
+            .new_open_options()
+            .read(true)
+            .open(path!("/foo.txt"))
+            .expect("failed to open the file again");
+
+        file.copy_reference(Box::new(crate::StaticFile::new(b"foobarbaz".to_vec())))
+            .await
+            .unwrap();
+        assert_eq!(file.size(), 9);
+
+        file.seek(io::SeekFrom::Start(3)).await.unwrap();
+        file.write_all(b"BAR").await.unwrap();
+        other.seek(io::SeekFrom::End(-3)).await.unwrap();
+
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "baz");
+
+        let mut rest = String::new();
+        other.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "baz");
+
+        let mut contents = String::new();
+        other.rewind().await.unwrap();
+        other.read_to_string(&mut contents).await.unwrap();
+        assert_eq!(contents, "fooBARbaz");
+    }
 }
 
 impl fmt::Debug for FileHandle {
@@ -1451,38 +1814,174 @@ impl fmt::Debug for FileHandle {
     }
 }
 
-/// The real file! It is simply a buffer of bytes with a cursor that
-/// represents a read/write position in the buffer.
+/// Files are stored in chunks of this many bytes. Chunks that have never
+/// been written to are not allocated, which is how holes are represented.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// The real file! It is a sparse buffer of bytes, split into chunks of
+/// [`CHUNK_SIZE`] bytes that are only allocated once something is written
+/// to them. Any byte that isn't backed by a chunk reads as zero.
 #[derive(Debug)]
 pub(super) struct File {
-    buffer: TrackedVec,
+    /// The allocated chunks, keyed by their index. A chunk only grows as far
+    /// as the last byte written to it, and never holds data past `len`.
+    chunks: BTreeMap<u64, TrackedVec>,
+    len: u64,
+    limiter: Option<crate::limiter::DynFsMemoryLimiter>,
 }
 
 impl File {
     pub(super) fn new(limiter: Option<crate::limiter::DynFsMemoryLimiter>) -> Self {
         Self {
-            buffer: TrackedVec::new(limiter),
+            chunks: BTreeMap::new(),
+            len: 0,
+            limiter,
         }
     }
 
     pub(super) fn truncate(&mut self) {
-        self.buffer.clear();
+        self.chunks.clear();
+        self.len = 0;
     }
 
-    pub(super) fn len(&self) -> usize {
-        self.buffer.len()
+    pub(super) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Change the size of the file. Growing the file only creates a hole at
+    /// the end, while shrinking it releases every chunk past the new end.
+    pub(super) fn set_len(&mut self, new_len: u64) -> Result<()> {
+        if new_len < self.len {
+            let index = new_len / CHUNK_SIZE;
+            let within = (new_len % CHUNK_SIZE) as usize;
+
+            // Drop the chunks that are entirely past the new end...
+            drop(self.chunks.split_off(&index.saturating_add(1)));
+            // ... and trim the one the new end falls into.
+            if let Some(chunk) = self.chunks.get_mut(&index) {
+                if within == 0 {
+                    self.chunks.remove(&index);
+                } else if chunk.len() > within {
+                    chunk.resize(within, 0)?;
+                }
+            }
+        }
+
+        self.len = new_len;
+        Ok(())
+    }
+
+    /// See [`VirtualFile::seek_data()`].
+    pub(super) fn seek_data(&self, offset: u64) -> Result<u64> {
+        if offset >= self.len {
+            return Err(FsError::OffsetOutOfRange);
+        }
+
+        let index = offset / CHUNK_SIZE;
+        let within = (offset % CHUNK_SIZE) as usize;
+
+        for (&i, chunk) in self.chunks.range(index..) {
+            if i == index {
+                if within < chunk.len() {
+                    return Ok(offset);
+                }
+            } else if !chunk.is_empty() {
+                return Ok(i * CHUNK_SIZE);
+            }
+        }
+
+        // Nothing but a hole until the end of the file
+        Err(FsError::OffsetOutOfRange)
+    }
+
+    /// See [`VirtualFile::seek_hole()`].
+    pub(super) fn seek_hole(&self, offset: u64) -> Result<u64> {
+        if offset >= self.len {
+            return Err(FsError::OffsetOutOfRange);
+        }
+
+        let mut position = offset;
+        while position < self.len {
+            let index = position / CHUNK_SIZE;
+            let within = (position % CHUNK_SIZE) as usize;
+
+            match self.chunks.get(&index) {
+                Some(chunk) if within < chunk.len() => {
+                    position = index * CHUNK_SIZE + chunk.len() as u64;
+                    if chunk.len() < CHUNK_SIZE as usize {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        Ok(cmp::min(position, self.len))
+    }
+
+    /// Deallocate the byte range `offset..offset + len`. Chunks that end up
+    /// entirely inside the hole are released, while partially covered chunks
+    /// are trimmed or zeroed. The size of the file is never changed.
+    pub(super) fn punch_hole(&mut self, offset: u64, len: u64) -> Result<()> {
+        let end = cmp::min(offset.saturating_add(len), self.len);
+        if offset >= end {
+            return Ok(());
+        }
+
+        let first = offset / CHUNK_SIZE;
+        let last = (end - 1) / CHUNK_SIZE;
+        let indices: Vec<u64> = self.chunks.range(first..=last).map(|(&i, _)| i).collect();
+
+        for index in indices {
+            let chunk_start = index * CHUNK_SIZE;
+            let start = offset.saturating_sub(chunk_start) as usize;
+            let stop = cmp::min(end - chunk_start, CHUNK_SIZE) as usize;
+
+            let chunk = self.chunks.get_mut(&index).unwrap();
+            if start == 0 && stop >= chunk.len() {
+                self.chunks.remove(&index);
+            } else if stop >= chunk.len() {
+                if start < chunk.len() {
+                    chunk.resize(start, 0)?;
+                }
+            } else if start < chunk.len() {
+                chunk[start..stop].fill(0);
+            }
+        }
+
+        Ok(())
     }
 }
 
 impl File {
     pub fn read(&self, buf: &mut [u8], cursor: &mut u64) -> io::Result<usize> {
-        let cur_pos = *cursor as usize;
-        let max_to_read = cmp::min(self.buffer.len() - cur_pos, buf.len());
-        let data_to_copy = &self.buffer[cur_pos..][..max_to_read];
+        let position = *cursor;
+        if position >= self.len {
+            return Ok(0);
+        }
 
-        // SAFETY: `buf[..max_to_read]` and `data_to_copy` have the same size, due to
-        // how `max_to_read` is computed.
-        buf[..max_to_read].copy_from_slice(data_to_copy);
+        let max_to_read = cmp::min(self.len - position, buf.len() as u64) as usize;
+        let mut read = 0;
+
+        while read < max_to_read {
+            let offset = position + read as u64;
+            let index = offset / CHUNK_SIZE;
+            let within = (offset % CHUNK_SIZE) as usize;
+            let amount = cmp::min(CHUNK_SIZE as usize - within, max_to_read - read);
+            let dest = &mut buf[read..read + amount];
+
+            match self.chunks.get(&index) {
+                Some(chunk) if within < chunk.len() => {
+                    let available = cmp::min(chunk.len() - within, amount);
+                    dest[..available].copy_from_slice(&chunk[within..within + available]);
+                    dest[available..].fill(0);
+                }
+                // Holes read as zeroes
+                _ => dest.fill(0),
+            }
+
+            read += amount;
+        }
 
         *cursor += max_to_read as u64;
 
@@ -1499,9 +1998,9 @@ impl File {
             // Calculate from the beginning, so `0 + offset`.
             io::SeekFrom::Start(offset) => offset.try_into().map_err(to_err)?,
 
-            // Calculate from the end, so `buffer.len() + offset`.
+            // Calculate from the end, so `len + offset`.
             io::SeekFrom::End(offset) => {
-                TryInto::<i64>::try_into(self.buffer.len()).map_err(to_err)? + offset
+                TryInto::<i64>::try_into(self.len).map_err(to_err)? + offset
             }
 
             // Calculate from the current cursor, so `cursor + offset`.
@@ -1518,10 +2017,9 @@ impl File {
             ));
         }
 
-        // In this implementation, it's an error to seek beyond the
-        // end of the buffer.
-        let next_cursor = next_cursor.try_into().map_err(to_err)?;
-        *cursor = cmp::min(self.buffer.len() as u64, next_cursor);
+        // Seeking beyond the end of the file is allowed, a subsequent write
+        // will leave a hole behind.
+        *cursor = next_cursor.try_into().map_err(to_err)?;
 
         let cursor = *cursor;
         Ok(cursor)
@@ -1530,21 +2028,53 @@ impl File {
 
 impl File {
     pub fn write(&mut self, buf: &[u8], cursor: &mut u64) -> io::Result<usize> {
-        let position = *cursor as usize;
-
-        if position + buf.len() > self.buffer.len() {
-            // Writing past the end of the current buffer, must reallocate
-            let len_after_end = (position + buf.len()) - self.buffer.len();
-            let let_to_end = buf.len() - len_after_end;
-            self.buffer[position..position + let_to_end].copy_from_slice(&buf[0..let_to_end]);
-            self.buffer.extend_from_slice(&buf[let_to_end..buf.len()])?;
-        } else {
-            self.buffer[position..position + buf.len()].copy_from_slice(buf);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let position = *cursor;
+        let mut written = 0;
+
+        while written < buf.len() {
+            let offset = position + written as u64;
+            let index = offset / CHUNK_SIZE;
+            let within = (offset % CHUNK_SIZE) as usize;
+            let amount = cmp::min(CHUNK_SIZE as usize - within, buf.len() - written);
+
+            let limiter = &self.limiter;
+            let inserted = !self.chunks.contains_key(&index);
+            let chunk = self
+                .chunks
+                .entry(index)
+                .or_insert_with(|| TrackedVec::new(limiter.clone()));
+
+            let needed = within + amount;
+            if chunk.len() < needed {
+                // Grow geometrically, but never past the size of a chunk
+                let target = cmp::min(needed.next_power_of_two(), CHUNK_SIZE as usize);
+                let grown = chunk
+                    .reserve_exact(target - chunk.len())
+                    .and_then(|()| chunk.resize(needed, 0));
+                if let Err(err) = grown {
+                    if inserted {
+                        self.chunks.remove(&index);
+                    }
+                    // Whatever made it into the file so far was written
+                    if written == 0 {
+                        return Err(err.into());
+                    }
+                    break;
+                }
+            }
+            chunk[within..needed].copy_from_slice(&buf[written..written + amount]);
+
+            written += amount;
         }
 
-        *cursor += buf.len() as u64;
+        *cursor += written as u64;
+        self.len = cmp::max(self.len, *cursor);
 
-        Ok(buf.len())
+        Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
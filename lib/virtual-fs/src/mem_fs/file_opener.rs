@@ -245,6 +245,18 @@ impl FileSystem {
             // TODO: restore previous inode?
             return Err(FsError::AlreadyExists);
         }
+
+        self.insert_custom_file(inode_of_parent, name_of_file, file, FileType::new_file())
+    }
+
+    /// Adds a [`Node::CustomFile`] named `name_of_file` to a directory
+    pub(super) fn insert_custom_file(
+        &self,
+        inode_of_parent: Inode,
+        name_of_file: OsString,
+        file: Box<dyn crate::VirtualFile + Send + Sync>,
+        ft: FileType,
+    ) -> Result<()> {
         // Write lock.
         let mut fs_lock = self.inner.write().map_err(|_| FsError::Lock)?;
 
@@ -257,10 +269,7 @@ impl FileSystem {
             metadata: {
                 let time = time();
                 Metadata {
-                    ft: FileType {
-                        file: true,
-                        ..Default::default()
-                    },
+                    ft,
                     accessed: time,
                     created: time,
                     modified: time,
@@ -398,7 +407,7 @@ impl crate::FileOpener for FileSystem {
 
                         // Move the cursor to the end if needed.
                         if append {
-                            cursor = file.len() as u64;
+                            cursor = file.len();
                         }
                     }
 
@@ -3,12 +3,12 @@
 use self::offloaded_file::OffloadBackingStore;
 
 use super::*;
-use crate::{DirEntry, FileType, FsError, Metadata, OpenOptions, ReadDir, Result};
+use crate::{DirEntry, FileType, FsError, Metadata, OpenOptions, ReadDir, Result, XattrMode};
 use futures::future::{BoxFuture, Either};
 use slab::Slab;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::convert::identity;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fmt;
 use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, RwLock};
@@ -437,7 +437,7 @@ impl crate::FileSystem for FileSystem {
             let mut fs = self.inner.write().map_err(|_| FsError::Lock)?;
 
             // Remove the directory from the storage.
-            fs.storage.remove(inode_of_directory);
+            fs.remove_node(inode_of_directory);
 
             // Remove the child from the parent directory.
             fs.remove_child_from_node(inode_of_parent, position)?;
@@ -468,6 +468,13 @@ impl crate::FileSystem for FileSystem {
                     .to_os_string();
                 name_of_to = to.file_name().ok_or(FsError::InvalidInput)?.to_os_string();
 
+                // Renaming an entry onto itself does nothing, as long as it
+                // exists.
+                if from == to {
+                    drop(fs);
+                    return self.symlink_metadata(&from).map(|_| ());
+                }
+
                 // Find the parent inodes.
                 let inode_of_from_parent = match fs.inode_of_parent(parent_of_from)? {
                     InodeResolution::Found(a) => Either::Left(a),
@@ -537,7 +544,7 @@ impl crate::FileSystem for FileSystem {
                             // Remove the file from the storage.
                             match inode_of_file {
                                 InodeResolution::Found(inode_of_file) => {
-                                    fs.storage.remove(inode_of_file);
+                                    fs.remove_node(inode_of_file);
                                 }
                                 InodeResolution::Redirect(..) => {
                                     return Err(FsError::InvalidInput);
@@ -687,7 +694,7 @@ impl crate::FileSystem for FileSystem {
             let mut fs = self.inner.write().map_err(|_| FsError::Lock)?;
 
             // Remove the file from the storage.
-            fs.storage.remove(inode_of_file);
+            fs.remove_node(inode_of_file);
 
             // Remove the child from the parent directory.
             fs.remove_child_from_node(inode_of_parent, position)?;
@@ -709,6 +716,136 @@ impl crate::FileSystem for FileSystem {
         let fs: Arc<dyn crate::FileSystem + Send + Sync> = Arc::new(fs);
         self.mount(path.to_owned(), &fs, PathBuf::from("/"))
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        // Read lock.
+        let guard = self.inner.read().map_err(|_| FsError::Lock)?;
+        match guard.inode_of(path)? {
+            InodeResolution::Found(inode) => guard
+                .xattrs
+                .get(&inode)
+                .and_then(|attrs| attrs.get(name))
+                .cloned()
+                .ok_or(FsError::NoAttribute),
+            InodeResolution::Redirect(fs, path) => {
+                drop(guard);
+                fs.get_xattr(path.as_path(), name)
+            }
+        }
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (fs, path) = {
+                // Write lock.
+                let mut guard = self.inner.write().map_err(|_| FsError::Lock)?;
+                match guard.inode_of(path)? {
+                    InodeResolution::Found(inode) => {
+                        let attrs = guard.xattrs.entry(inode).or_default();
+                        match (mode, attrs.contains_key(name)) {
+                            (XattrMode::Create, true) => return Err(FsError::AlreadyExists),
+                            (XattrMode::Replace, false) => return Err(FsError::NoAttribute),
+                            _ => {}
+                        }
+                        attrs.insert(name.to_owned(), value.to_vec());
+                        return Ok(());
+                    }
+                    InodeResolution::Redirect(fs, path) => (fs, path),
+                }
+            };
+            fs.set_xattr(path.as_path(), name, value, mode).await
+        })
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        // Read lock.
+        let guard = self.inner.read().map_err(|_| FsError::Lock)?;
+        match guard.inode_of(path)? {
+            InodeResolution::Found(inode) => Ok(guard
+                .xattrs
+                .get(&inode)
+                .map(|attrs| attrs.keys().cloned().collect())
+                .unwrap_or_default()),
+            InodeResolution::Redirect(fs, path) => {
+                drop(guard);
+                fs.list_xattr(path.as_path())
+            }
+        }
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (fs, path) = {
+                // Write lock.
+                let mut guard = self.inner.write().map_err(|_| FsError::Lock)?;
+                match guard.inode_of(path)? {
+                    InodeResolution::Found(inode) => {
+                        let attrs = guard.xattrs.get_mut(&inode).ok_or(FsError::NoAttribute)?;
+                        attrs.remove(name).ok_or(FsError::NoAttribute)?;
+                        if attrs.is_empty() {
+                            guard.xattrs.remove(&inode);
+                        }
+                        return Ok(());
+                    }
+                    InodeResolution::Redirect(fs, path) => (fs, path),
+                }
+            };
+            fs.remove_xattr(path.as_path(), name).await
+        })
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        let (inode_of_parent, name_of_socket) = {
+            // Read lock.
+            let guard = self.inner.read().map_err(|_| FsError::Lock)?;
+
+            // Canonicalize the path without checking the path exists,
+            // because it's about to be created.
+            let path = guard.canonicalize_without_inode(path)?;
+
+            // Check the path has a parent.
+            let parent_of_path = path.parent().ok_or(FsError::BaseNotDirectory)?;
+
+            // Check the socket name.
+            let name_of_socket = path
+                .file_name()
+                .ok_or(FsError::InvalidInput)?
+                .to_os_string();
+
+            // Find the parent inode.
+            let inode_of_parent = match guard.inode_of_parent(parent_of_path)? {
+                InodeResolution::Found(a) => a,
+                InodeResolution::Redirect(fs, mut path) => {
+                    drop(guard);
+                    path.push(name_of_socket);
+                    return fs.create_socket(path.as_path());
+                }
+            };
+
+            // Sockets can't replace anything that already exists.
+            if guard
+                .as_parent_get_position_and_inode(inode_of_parent, &name_of_socket)?
+                .is_some()
+            {
+                return Err(FsError::AlreadyExists);
+            }
+
+            (inode_of_parent, name_of_socket)
+        };
+
+        self.insert_custom_file(
+            inode_of_parent,
+            name_of_socket,
+            Box::<crate::SocketFile>::default(),
+            FileType::new_socket(),
+        )
+    }
 }
 
 impl fmt::Debug for FileSystem {
@@ -725,6 +862,8 @@ pub(super) struct FileSystemInner {
     pub(super) storage: Slab<Node>,
     pub(super) backing_offload: Option<OffloadBackingStore>,
     pub(super) limiter: Option<crate::limiter::DynFsMemoryLimiter>,
+    /// Extended attributes, indexed by the `Inode` they are attached to.
+    pub(super) xattrs: HashMap<Inode, BTreeMap<OsString, Vec<u8>>>,
 }
 
 #[derive(Debug)]
@@ -746,6 +885,13 @@ impl InodeResolution {
 }
 
 impl FileSystemInner {
+    /// Remove a node from the storage, along with any extended attributes
+    /// that were attached to it (inodes are recycled by the slab).
+    pub(super) fn remove_node(&mut self, inode: Inode) -> Node {
+        self.xattrs.remove(&inode);
+        self.storage.remove(inode)
+    }
+
     /// Get the inode associated to a path if it exists.
     pub(super) fn inode_of(&self, path: &Path) -> Result<InodeResolution> {
         // SAFETY: The root node always exists, so it's safe to unwrap here.
@@ -1129,6 +1275,7 @@ impl Default for FileSystemInner {
             storage: slab,
             backing_offload: None,
             limiter: None,
+            xattrs: HashMap::new(),
         }
     }
 }
@@ -1380,6 +1527,23 @@ mod test_filesystem {
                 .is_ok(),
             "creating a new file (`hello1.txt`)",
         );
+
+        assert_eq!(
+            fs.rename(path!("/bar/hello1.txt"), path!("/bar/hello1.txt"))
+                .await,
+            Ok(()),
+            "renaming a file onto itself",
+        );
+        assert!(
+            fs.metadata(path!("/bar/hello1.txt")).is_ok(),
+            "the file is still there after renaming it onto itself",
+        );
+        assert_eq!(
+            fs.rename(path!("/bar/missing.txt"), path!("/bar/missing.txt"))
+                .await,
+            Err(FsError::EntryNotFound),
+            "renaming a file that doesn't exist onto itself",
+        );
         assert!(
             fs.new_open_options()
                 .write(true)
@@ -1936,6 +2100,127 @@ mod test_filesystem {
         assert!(ops::is_file(&fs, "/top-level/nested/another-file.txt"));
     }
 
+    #[tokio::test]
+    async fn test_xattr() {
+        use crate::XattrMode;
+        use std::ffi::OsStr;
+
+        let fs = FileSystem::default();
+
+        assert_eq!(fs.create_dir(path!("/foo")), Ok(()));
+        assert_eq!(fs.list_xattr(path!("/foo")), Ok(Vec::new()));
+        assert_eq!(
+            fs.get_xattr(path!("/foo"), OsStr::new("user.a")),
+            Err(FsError::NoAttribute),
+        );
+        assert_eq!(
+            fs.get_xattr(path!("/bar"), OsStr::new("user.a")),
+            Err(FsError::EntryNotFound),
+        );
+
+        assert_eq!(
+            fs.set_xattr(
+                path!("/foo"),
+                OsStr::new("user.a"),
+                b"1",
+                XattrMode::Replace
+            )
+            .await,
+            Err(FsError::NoAttribute),
+            "replacing an attribute that doesn't exist",
+        );
+        assert_eq!(
+            fs.set_xattr(path!("/foo"), OsStr::new("user.a"), b"1", XattrMode::Create)
+                .await,
+            Ok(()),
+        );
+        assert_eq!(
+            fs.set_xattr(path!("/foo"), OsStr::new("user.a"), b"2", XattrMode::Create)
+                .await,
+            Err(FsError::AlreadyExists),
+            "creating an attribute that already exists",
+        );
+        assert_eq!(
+            fs.set_xattr(path!("/foo"), OsStr::new("user.b"), b"3", XattrMode::Upsert)
+                .await,
+            Ok(()),
+        );
+        assert_eq!(
+            fs.get_xattr(path!("/foo"), OsStr::new("user.a")),
+            Ok(b"1".to_vec())
+        );
+        assert_eq!(
+            fs.list_xattr(path!("/foo")),
+            Ok(vec!["user.a".into(), "user.b".into()])
+        );
+
+        assert_eq!(
+            fs.remove_xattr(path!("/foo"), OsStr::new("user.a")).await,
+            Ok(())
+        );
+        assert_eq!(
+            fs.remove_xattr(path!("/foo"), OsStr::new("user.a")).await,
+            Err(FsError::NoAttribute),
+        );
+        assert_eq!(fs.list_xattr(path!("/foo")), Ok(vec!["user.b".into()]));
+
+        // Attributes follow the node when it is renamed...
+        assert_eq!(fs.rename(path!("/foo"), path!("/baz")).await, Ok(()));
+        assert_eq!(fs.list_xattr(path!("/baz")), Ok(vec!["user.b".into()]));
+
+        // ...but don't leak into a new node that reuses the same inode
+        assert_eq!(fs.remove_dir(path!("/baz")), Ok(()));
+        assert_eq!(fs.create_dir(path!("/qux")), Ok(()));
+        assert_eq!(fs.list_xattr(path!("/qux")), Ok(Vec::new()));
+    }
+
+    #[tokio::test]
+    async fn test_create_socket() {
+        let fs = FileSystem::default();
+
+        assert_eq!(fs.create_dir(path!("/run")), Ok(()));
+        assert_eq!(fs.create_socket(path!("/run/app.sock")), Ok(()));
+        assert!(fs
+            .metadata(path!("/run/app.sock"))
+            .unwrap()
+            .file_type()
+            .is_socket());
+        assert_eq!(
+            fs.create_socket(path!("/run/app.sock")),
+            Err(FsError::AlreadyExists),
+            "binding to a path that is already taken",
+        );
+        assert_eq!(
+            fs.create_socket(path!("/run")),
+            Err(FsError::AlreadyExists),
+            "binding to an existing directory",
+        );
+        assert_eq!(
+            fs.create_socket(path!("/missing/app.sock")),
+            Err(FsError::EntryNotFound),
+        );
+
+        let entries = fs
+            .read_dir(path!("/run"))
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].file_type().unwrap().is_socket());
+
+        let mut file = fs
+            .new_open_options()
+            .read(true)
+            .open(path!("/run/app.sock"))
+            .unwrap();
+        let mut buf = Vec::new();
+        assert!(file.read_to_end(&mut buf).await.is_err());
+
+        // Unlinking the entry frees the path up again
+        assert_eq!(fs.remove_file(path!("/run/app.sock")), Ok(()));
+        assert_eq!(fs.create_socket(path!("/run/app.sock")), Ok(()));
+    }
+
     #[tokio::test]
     async fn test_merge_flat() {
         let main = FileSystem::default();
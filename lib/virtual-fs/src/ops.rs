@@ -9,7 +9,7 @@ use std::{
 use futures::future::BoxFuture;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::{DirEntry, FileSystem, FsError};
+use crate::{DirEntry, FileSystem, FsError, XattrMode};
 
 /// Does this item exists?
 pub fn exists<F>(fs: &F, path: impl AsRef<Path>) -> bool
@@ -161,6 +161,40 @@ pub fn copy_reference_ext<'a>(
     })
 }
 
+/// Copies all the extended attributes of an entry from one file system to
+/// another.
+///
+/// File systems that don't support extended attributes (on either side) and
+/// attributes that disappear while they are being copied are skipped, any
+/// other error is returned.
+pub async fn copy_xattrs<'a>(
+    source: &'a (impl FileSystem + ?Sized),
+    destination: &'a (impl FileSystem + ?Sized),
+    path: &'a Path,
+) -> Result<(), FsError> {
+    let names = match source.list_xattr(path) {
+        Ok(names) => names,
+        Err(FsError::Unsupported) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for name in names {
+        let value = match source.get_xattr(path, &name) {
+            Ok(value) => value,
+            Err(FsError::NoAttribute) => continue,
+            Err(e) => return Err(e),
+        };
+        match destination
+            .set_xattr(path, &name, &value, XattrMode::Upsert)
+            .await
+        {
+            Ok(()) => {}
+            Err(FsError::Unsupported) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
 /// Asynchronously write some bytes to a file.
 ///
 /// This is analogous to [`std::fs::write()`].
@@ -328,4 +362,50 @@ mod tests {
 
         assert_eq!(super::read(&fs, "/file.txt").await.unwrap(), b"");
     }
+
+    #[tokio::test]
+    async fn copy_xattrs() {
+        let source = MemFS::default();
+        super::touch(&source, "/file.txt").unwrap();
+        source
+            .set_xattr(
+                Path::new("/file.txt"),
+                std::ffi::OsStr::new("user.tag"),
+                b"value",
+                XattrMode::Upsert,
+            )
+            .await
+            .unwrap();
+
+        // Missing support is not an error
+        let destination = MemFS::default();
+        super::touch(&destination, "/file.txt").unwrap();
+        super::copy_xattrs(
+            &crate::EmptyFileSystem::default(),
+            &destination,
+            Path::new("/file.txt"),
+        )
+        .await
+        .unwrap();
+        assert!(destination
+            .list_xattr(Path::new("/file.txt"))
+            .unwrap()
+            .is_empty());
+
+        super::copy_xattrs(&source, &destination, Path::new("/file.txt"))
+            .await
+            .unwrap();
+        assert_eq!(
+            destination
+                .get_xattr(Path::new("/file.txt"), std::ffi::OsStr::new("user.tag"))
+                .unwrap(),
+            b"value"
+        );
+
+        // Anything else is reported
+        assert_eq!(
+            super::copy_xattrs(&source, &MemFS::default(), Path::new("/file.txt")).await,
+            Err(FsError::EntryNotFound)
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use std::{
     collections::HashSet,
+    ffi::{OsStr, OsString},
     fmt::Debug,
     io::{self, SeekFrom},
     path::{Path, PathBuf},
@@ -14,7 +15,7 @@ use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
 
 use crate::{
     ops, FileOpener, FileSystem, FileSystems, FsError, Metadata, OpenOptions, OpenOptionsConfig,
-    ReadDir, VirtualFile,
+    ReadDir, VirtualFile, XattrMode,
 };
 
 /// A primary filesystem and chain of secondary filesystems that are overlayed
@@ -96,6 +97,39 @@ where
         &mut self.secondaries
     }
 
+    /// Makes sure `path` exists on the primary file system, copying it up
+    /// (along with its extended attributes) from whichever secondary it
+    /// currently lives on.
+    async fn copy_up(&self, path: &Path) -> Result<(), FsError> {
+        if ops::exists(self.primary.as_ref(), path) {
+            return Ok(());
+        }
+        if ops::has_white_out(&self.primary, path) {
+            return Err(FsError::EntryNotFound);
+        }
+
+        for fs in self.secondaries.filesystems() {
+            let meta = match fs.metadata(path) {
+                Ok(meta) => meta,
+                Err(e) if should_continue(e) => continue,
+                Err(e) => return Err(e),
+            };
+
+            if meta.is_dir() {
+                ops::create_dir_all(self.primary.as_ref(), path)?;
+            } else {
+                if let Some(parent) = path.parent() {
+                    ops::create_dir_all(self.primary.as_ref(), parent)?;
+                }
+                ops::copy_reference(fs, self.primary.as_ref(), path).await?;
+            }
+            ops::copy_xattrs(fs, self.primary.as_ref(), path).await?;
+            return Ok(());
+        }
+
+        Err(FsError::EntryNotFound)
+    }
+
     fn permission_error_or_not_found(&self, path: &Path) -> Result<(), FsError> {
         for fs in self.secondaries.filesystems() {
             if ops::exists(fs, path) {
@@ -417,11 +451,131 @@ where
 
     fn mount(
         &self,
-        _name: String,
-        _path: &Path,
-        _fs: Box<dyn FileSystem + Send + Sync>,
+        name: String,
+        path: &Path,
+        fs: Box<dyn FileSystem + Send + Sync>,
     ) -> Result<(), FsError> {
-        Err(FsError::Unsupported)
+        // Mounts go into the writable layer, which shadows the secondaries
+        self.primary.mount(name, path, fs)
+    }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> crate::Result<Vec<u8>> {
+        // Whiteout files can not be read, they are just markers
+        if ops::is_white_out(path).is_some() {
+            return Err(FsError::EntryNotFound);
+        }
+
+        // Check if the entry is in the primary
+        match self.primary.get_xattr(path, name) {
+            Ok(value) => return Ok(value),
+            Err(e) if should_continue(e) => {}
+            Err(e) => return Err(e),
+        }
+
+        // There might be a whiteout, search for this
+        if ops::has_white_out(&self.primary, path) {
+            return Err(FsError::EntryNotFound);
+        }
+
+        // Otherwise scan the secondaries
+        for fs in self.secondaries.filesystems() {
+            match fs.get_xattr(path, name) {
+                Err(e) if should_continue(e) => continue,
+                other => return other,
+            }
+        }
+
+        Err(FsError::EntryNotFound)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move {
+            // Whiteout files can not be modified, they are just markers
+            if ops::is_white_out(path).is_some() {
+                return Err(FsError::EntryNotFound);
+            }
+
+            // Attributes can only be stored on the primary, so entries that
+            // only exist in a secondary must be copied up first
+            self.copy_up(path).await?;
+            self.primary.set_xattr(path, name, value, mode).await
+        })
+    }
+
+    fn list_xattr(&self, path: &Path) -> crate::Result<Vec<OsString>> {
+        // Whiteout files can not be read, they are just markers
+        if ops::is_white_out(path).is_some() {
+            return Err(FsError::EntryNotFound);
+        }
+
+        // Check if the entry is in the primary
+        match self.primary.list_xattr(path) {
+            Ok(names) => return Ok(names),
+            Err(e) if should_continue(e) => {}
+            Err(e) => return Err(e),
+        }
+
+        // There might be a whiteout, search for this
+        if ops::has_white_out(&self.primary, path) {
+            return Err(FsError::EntryNotFound);
+        }
+
+        // Otherwise scan the secondaries
+        for fs in self.secondaries.filesystems() {
+            match fs.list_xattr(path) {
+                Err(e) if should_continue(e) => continue,
+                other => return other,
+            }
+        }
+
+        Err(FsError::EntryNotFound)
+    }
+
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move {
+            // Whiteout files can not be modified, they are just markers
+            if ops::is_white_out(path).is_some() {
+                return Err(FsError::EntryNotFound);
+            }
+
+            // Make sure the attribute actually exists before copying the
+            // entry up to the primary
+            self.get_xattr(path, name)?;
+            self.copy_up(path).await?;
+            self.primary.remove_xattr(path, name).await
+        })
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<(), FsError> {
+        // Sockets can not be bound to paths that use the whiteout prefix
+        if ops::is_white_out(path).is_some() {
+            return Err(FsError::InvalidInput);
+        }
+
+        // The path must not already exist in any of the layers
+        if self.symlink_metadata(path).is_ok() {
+            return Err(FsError::AlreadyExists);
+        }
+        ops::remove_white_out(self.primary.as_ref(), path);
+
+        // Make sure the parent tree is in place on the primary
+        if let Some(parent) = path.parent() {
+            if self.read_dir(parent).is_ok() {
+                ops::create_dir_all(&self.primary, parent).ok();
+            }
+        }
+
+        self.primary.create_socket(path)
     }
 }
 
@@ -1573,6 +1727,105 @@ mod tests {
         assert!(!ops::is_file(&fs.primary, "/secondary/file.txt"));
     }
 
+    #[tokio::test]
+    async fn set_xattr_copies_up_from_secondary() {
+        let primary = MemFS::default();
+        let secondary = MemFS::default();
+        ops::create_dir_all(&secondary, "/secondary").unwrap();
+        ops::write(&secondary, "/secondary/file.txt", b"Hello, World!")
+            .await
+            .unwrap();
+        secondary
+            .set_xattr(
+                Path::new("/secondary/file.txt"),
+                OsStr::new("user.original"),
+                b"yes",
+                XattrMode::Upsert,
+            )
+            .await
+            .unwrap();
+
+        let fs = OverlayFileSystem::new(primary, [secondary]);
+
+        // Reading falls through to the secondary without copying anything
+        assert_eq!(
+            fs.get_xattr(
+                Path::new("/secondary/file.txt"),
+                OsStr::new("user.original")
+            )
+            .unwrap(),
+            b"yes"
+        );
+        assert!(!ops::is_file(&fs.primary, "/secondary/file.txt"));
+
+        fs.set_xattr(
+            Path::new("/secondary/file.txt"),
+            OsStr::new("user.added"),
+            b"new",
+            XattrMode::Create,
+        )
+        .await
+        .unwrap();
+
+        // The file, its contents and its existing attributes were copied up
+        assert!(ops::is_file(&fs.primary, "/secondary/file.txt"));
+        assert_eq!(
+            ops::read_to_string(&fs.primary, "/secondary/file.txt")
+                .await
+                .unwrap(),
+            "Hello, World!"
+        );
+        let mut names = fs.list_xattr(Path::new("/secondary/file.txt")).unwrap();
+        names.sort();
+        assert_eq!(names, vec!["user.added", "user.original"]);
+
+        // The secondary is left untouched
+        assert_eq!(
+            fs.secondaries[0]
+                .list_xattr(Path::new("/secondary/file.txt"))
+                .unwrap(),
+            vec!["user.original"]
+        );
+    }
+
+    #[tokio::test]
+    async fn remove_xattr_from_secondary_dir() {
+        let primary = MemFS::default();
+        let secondary = MemFS::default();
+        ops::create_dir_all(&secondary, "/secondary").unwrap();
+        secondary
+            .set_xattr(
+                Path::new("/secondary"),
+                OsStr::new("user.tag"),
+                b"value",
+                XattrMode::Upsert,
+            )
+            .await
+            .unwrap();
+
+        let fs = OverlayFileSystem::new(primary, [secondary]);
+
+        assert_eq!(
+            fs.remove_xattr(Path::new("/secondary"), OsStr::new("user.missing"))
+                .await,
+            Err(FsError::NoAttribute)
+        );
+        assert!(!ops::is_dir(&fs.primary, "/secondary"));
+
+        fs.remove_xattr(Path::new("/secondary"), OsStr::new("user.tag"))
+            .await
+            .unwrap();
+
+        assert!(ops::is_dir(&fs.primary, "/secondary"));
+        assert_eq!(
+            fs.get_xattr(Path::new("/secondary"), OsStr::new("user.tag")),
+            Err(FsError::NoAttribute)
+        );
+        assert!(fs.secondaries[0]
+            .get_xattr(Path::new("/secondary"), OsStr::new("user.tag"))
+            .is_ok());
+    }
+
     // OLD tests that used WebcFileSystem.
     // Should be re-implemented with WebcVolumeFs
     // #[tokio::test]
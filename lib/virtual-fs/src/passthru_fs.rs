@@ -2,6 +2,7 @@
 //! needed so that a `Box<dyn VirtualFileSystem>` can be wrapped in an Arc and
 //! shared - some of the interfaces pass around a `Box<dyn VirtualFileSystem>`
 
+use std::ffi::{OsStr, OsString};
 use std::path::Path;
 
 use crate::*;
@@ -62,6 +63,32 @@ impl FileSystem for PassthruFileSystem {
     ) -> Result<()> {
         Err(FsError::Unsupported)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        self.fs.get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        self.fs.set_xattr(path, name, value, mode)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        self.fs.list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        self.fs.remove_xattr(path, name)
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        self.fs.create_socket(path)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,690 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+
+use crate::{
+    FileOpener, FileSystem, FsError, Metadata, OpenOptions, OpenOptionsConfig, ReadDir,
+    VirtualFile, XattrMode,
+};
+
+/// The limits enforced by a [`QuotaFileSystem`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaLimits {
+    /// The maximum number of bytes that may be stored in files.
+    pub max_bytes: Option<u64>,
+    /// The maximum number of files, directories and symlinks.
+    pub max_inodes: Option<u64>,
+    /// The error that is reported once a limit has been reached.
+    pub error: QuotaError,
+}
+
+/// How a [`QuotaFileSystem`] reports that it has run out of space.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaError {
+    /// Fail with [`FsError::QuotaExceeded`] (`EDQUOT`).
+    #[default]
+    QuotaExceeded,
+    /// Fail with [`FsError::StorageFull`] (`ENOSPC`), as if the underlying
+    /// device was full.
+    StorageFull,
+}
+
+impl From<QuotaError> for FsError {
+    fn from(value: QuotaError) -> Self {
+        match value {
+            QuotaError::QuotaExceeded => FsError::QuotaExceeded,
+            QuotaError::StorageFull => FsError::StorageFull,
+        }
+    }
+}
+
+/// Usage counters for a [`QuotaFileSystem`].
+///
+/// A single [`Quota`] can be shared between several file systems so they are
+/// all charged against the same limits.
+#[derive(Debug, Default)]
+pub struct Quota {
+    limits: QuotaLimits,
+    bytes: AtomicU64,
+    inodes: AtomicU64,
+}
+
+impl Quota {
+    pub fn new(limits: QuotaLimits) -> Self {
+        Quota {
+            limits,
+            bytes: AtomicU64::new(0),
+            inodes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        self.limits
+    }
+
+    /// The number of bytes currently charged against this quota.
+    pub fn bytes_used(&self) -> u64 {
+        self.bytes.load(Ordering::SeqCst)
+    }
+
+    /// The number of inodes currently charged against this quota.
+    pub fn inodes_used(&self) -> u64 {
+        self.inodes.load(Ordering::SeqCst)
+    }
+
+    fn error(&self) -> FsError {
+        self.limits.error.into()
+    }
+
+    /// Reserve up to `wanted` bytes, returning how many were granted.
+    fn reserve_bytes(&self, wanted: u64) -> u64 {
+        let max = match self.limits.max_bytes {
+            Some(max) => max,
+            None => {
+                self.bytes.fetch_add(wanted, Ordering::SeqCst);
+                return wanted;
+            }
+        };
+
+        let mut granted = 0;
+        let _ = self
+            .bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                granted = wanted.min(max.saturating_sub(used));
+                Some(used + granted)
+            });
+        granted
+    }
+
+    fn try_reserve_bytes(&self, wanted: u64) -> Result<(), FsError> {
+        let granted = self.reserve_bytes(wanted);
+        if granted < wanted {
+            self.release_bytes(granted);
+            return Err(self.error());
+        }
+        Ok(())
+    }
+
+    fn release_bytes(&self, amount: u64) {
+        let _ = self
+            .bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                Some(used.saturating_sub(amount))
+            });
+    }
+
+    fn try_reserve_inode(&self) -> Result<(), FsError> {
+        let max = self.limits.max_inodes.unwrap_or(u64::MAX);
+        self.inodes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                (used < max).then_some(used + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| self.error())
+    }
+
+    fn release_inode(&self) {
+        let _ = self
+            .inodes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                Some(used.saturating_sub(1))
+            });
+    }
+}
+
+/// A [`FileSystem`] wrapper that limits the number of bytes and inodes which
+/// can be stored in the wrapped file system.
+///
+/// Writes, truncation and [`VirtualFile::set_len()`] (which backs
+/// `fd_allocate`) are all charged against the quota, and any operation that
+/// would exceed it fails with the error configured in [`QuotaLimits`].
+///
+/// Usage is tracked by file length rather than by the number of blocks used
+/// on disk, and each open handle keeps track of the size of its own file.
+/// Concurrently growing the same file through several handles may therefore
+/// charge the quota more than once.
+#[derive(Debug, Clone)]
+pub struct QuotaFileSystem<F> {
+    inner: F,
+    quota: Arc<Quota>,
+}
+
+impl<F> QuotaFileSystem<F>
+where
+    F: FileSystem,
+{
+    /// Wrap a file system, walking its contents to work out how much of the
+    /// quota is already in use.
+    pub fn new(inner: F, limits: QuotaLimits) -> Result<Self, FsError> {
+        let quota = Quota::new(limits);
+        scan_usage(&inner, Path::new("/"), &quota)?;
+        Ok(QuotaFileSystem::with_quota(inner, Arc::new(quota)))
+    }
+
+    /// Wrap a file system, charging all usage against an existing [`Quota`].
+    ///
+    /// The current contents of `inner` are not scanned.
+    pub fn with_quota(inner: F, quota: Arc<Quota>) -> Self {
+        QuotaFileSystem { inner, quota }
+    }
+
+    pub fn quota(&self) -> &Arc<Quota> {
+        &self.quota
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+
+    /// Work out how much of the quota would be freed by removing `path`.
+    fn usage_of(&self, path: &Path) -> Option<u64> {
+        self.inner
+            .symlink_metadata(path)
+            .ok()
+            .map(|meta| if meta.is_file() { meta.len() } else { 0 })
+    }
+}
+
+fn scan_usage<F>(fs: &F, dir: &Path, quota: &Quota) -> Result<(), FsError>
+where
+    F: FileSystem + ?Sized,
+{
+    for entry in fs.read_dir(dir)? {
+        let entry = entry?;
+        let meta = match entry.metadata {
+            Ok(meta) => meta,
+            Err(_) => continue,
+        };
+
+        quota.inodes.fetch_add(1, Ordering::SeqCst);
+        if meta.is_file() {
+            quota.bytes.fetch_add(meta.len(), Ordering::SeqCst);
+        } else if meta.is_dir() {
+            scan_usage(fs, &entry.path, quota)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<F> FileSystem for QuotaFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn readlink(&self, path: &Path) -> crate::Result<PathBuf> {
+        self.inner.readlink(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> crate::Result<ReadDir> {
+        self.inner.read_dir(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> crate::Result<()> {
+        self.quota.try_reserve_inode()?;
+        self.inner.create_dir(path).inspect_err(|_| {
+            self.quota.release_inode();
+        })
+    }
+
+    fn remove_dir(&self, path: &Path) -> crate::Result<()> {
+        self.inner.remove_dir(path)?;
+        self.quota.release_inode();
+        Ok(())
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move {
+            let replaced = self.usage_of(to);
+            self.inner.rename(from, to).await?;
+
+            // Renaming onto the same file (the same path or another hard link
+            // to it) does nothing, so `from` is still there
+            if self.inner.symlink_metadata(from).is_ok() {
+                return Ok(());
+            }
+            if let Some(bytes) = replaced {
+                self.quota.release_bytes(bytes);
+                self.quota.release_inode();
+            }
+            Ok(())
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> crate::Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> crate::Result<Metadata> {
+        self.inner.symlink_metadata(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> crate::Result<()> {
+        let bytes = self.usage_of(path).unwrap_or_default();
+        self.inner.remove_file(path)?;
+        self.quota.release_bytes(bytes);
+        self.quota.release_inode();
+        Ok(())
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        name: String,
+        path: &Path,
+        fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> crate::Result<()> {
+        self.inner.mount(name, path, fs)
+    }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> crate::Result<Vec<u8>> {
+        self.inner.get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        self.inner.set_xattr(path, name, value, mode)
+    }
+
+    fn list_xattr(&self, path: &Path) -> crate::Result<Vec<OsString>> {
+        self.inner.list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        self.inner.remove_xattr(path, name)
+    }
+
+    fn create_socket(&self, path: &Path) -> crate::Result<()> {
+        self.quota.try_reserve_inode()?;
+        self.inner.create_socket(path).inspect_err(|_| {
+            self.quota.release_inode();
+        })
+    }
+}
+
+impl<F> FileOpener for QuotaFileSystem<F>
+where
+    F: FileSystem,
+{
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> crate::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let existing = self.usage_of(path);
+
+        let creating = existing.is_none() && (conf.create || conf.create_new);
+        if creating {
+            self.quota.try_reserve_inode()?;
+        }
+
+        let file = match self
+            .inner
+            .new_open_options()
+            .options(conf.clone())
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                if creating {
+                    self.quota.release_inode();
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(bytes) = existing {
+            if conf.truncate {
+                self.quota.release_bytes(bytes);
+            }
+        }
+
+        Ok(Box::new(QuotaFile {
+            size: file.size(),
+            file,
+            quota: self.quota.clone(),
+            append: conf.append,
+            pos: 0,
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct QuotaFile {
+    file: Box<dyn VirtualFile + Send + Sync + 'static>,
+    quota: Arc<Quota>,
+    append: bool,
+    /// The cursor position, as last reported by the inner file.
+    pos: u64,
+    /// The size of the file that has been charged against the quota.
+    size: u64,
+}
+
+impl VirtualFile for QuotaFile {
+    fn last_accessed(&self) -> u64 {
+        self.file.last_accessed()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.file.last_modified()
+    }
+
+    fn created_time(&self) -> u64 {
+        self.file.created_time()
+    }
+
+    fn set_times(&mut self, atime: Option<u64>, mtime: Option<u64>) -> crate::Result<()> {
+        self.file.set_times(atime, mtime)
+    }
+
+    fn size(&self) -> u64 {
+        self.file.size()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> crate::Result<()> {
+        let size = self.size.max(self.file.size());
+
+        if new_size > size {
+            self.quota.try_reserve_bytes(new_size - size)?;
+            if let Err(e) = self.file.set_len(new_size) {
+                self.quota.release_bytes(new_size - size);
+                return Err(e);
+            }
+        } else {
+            self.file.set_len(new_size)?;
+            self.quota.release_bytes(size - new_size);
+        }
+
+        self.size = new_size;
+        Ok(())
+    }
+
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_data(offset)
+    }
+
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_hole(offset)
+    }
+
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        // Quotas are charged on the size of the file, which doesn't change
+        self.file.punch_hole(offset, len)
+    }
+
+    fn unlink(&mut self) -> crate::Result<()> {
+        self.file.unlink()?;
+        self.quota.release_bytes(self.size);
+        self.quota.release_inode();
+        self.size = 0;
+        Ok(())
+    }
+
+    fn get_special_fd(&self) -> Option<u32> {
+        self.file.get_special_fd()
+    }
+
+    fn poll_read_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut *self.file).poll_write_ready(cx)
+    }
+}
+
+impl AsyncRead for QuotaFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut *self.file).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = &result {
+            self.pos += (buf.filled().len() - before) as u64;
+        }
+        result
+    }
+}
+
+impl AsyncWrite for QuotaFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        let this = &mut *self;
+
+        // Other handles may have grown the file in the meantime
+        this.size = this.size.max(this.file.size());
+        let start = if this.append { this.size } else { this.pos };
+        let end = start + buf.len() as u64;
+
+        let wanted = end.saturating_sub(this.size);
+        let granted = this.quota.reserve_bytes(wanted);
+        let buf = if granted < wanted {
+            // Only write as much as still fits within the quota
+            let allowed = (this.size + granted).saturating_sub(start) as usize;
+            if allowed == 0 {
+                this.quota.release_bytes(granted);
+                return Poll::Ready(Err(io::Error::from(this.quota.error())));
+            }
+            &buf[..allowed.min(buf.len())]
+        } else {
+            buf
+        };
+
+        match Pin::new(&mut *this.file).poll_write(cx, buf) {
+            Poll::Ready(Ok(written)) => {
+                let end = start + written as u64;
+                let grown = end.saturating_sub(this.size);
+                this.quota.release_bytes(granted - grown);
+                this.size += grown;
+                this.pos = end;
+                Poll::Ready(Ok(written))
+            }
+            other => {
+                this.quota.release_bytes(granted);
+                other
+            }
+        }
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        Pin::new(&mut *self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        Pin::new(&mut *self.file).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for QuotaFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> std::io::Result<()> {
+        Pin::new(&mut *self.file).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        let result = Pin::new(&mut *self.file).poll_complete(cx);
+        if let Poll::Ready(Ok(pos)) = &result {
+            self.pos = *pos;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::{mem_fs::FileSystem as MemFS, ops};
+
+    fn limits(max_bytes: Option<u64>, max_inodes: Option<u64>) -> QuotaLimits {
+        QuotaLimits {
+            max_bytes,
+            max_inodes,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn existing_usage_is_counted() {
+        let inner = MemFS::default();
+        ops::create_dir_all(&inner, "/a/b").unwrap();
+        ops::write(&inner, "/a/b/file.txt", b"Hello, World!")
+            .await
+            .unwrap();
+
+        let fs = QuotaFileSystem::new(inner, limits(None, None)).unwrap();
+
+        assert_eq!(fs.quota().bytes_used(), 13);
+        assert_eq!(fs.quota().inodes_used(), 3);
+    }
+
+    #[tokio::test]
+    async fn writes_are_limited() {
+        let fs = QuotaFileSystem::new(MemFS::default(), limits(Some(10), None)).unwrap();
+
+        let mut f = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open("/file.txt")
+            .unwrap();
+        let written = f.write(b"Hello, World!").await.unwrap();
+        assert_eq!(written, 10);
+        assert_eq!(fs.quota().bytes_used(), 10);
+
+        let err = f.write(b"!").await.unwrap_err();
+        assert_eq!(FsError::from(err), FsError::QuotaExceeded);
+
+        // Overwriting existing bytes doesn't need any more space
+        f.seek(SeekFrom::Start(0)).await.unwrap();
+        f.write_all(b"0123456789").await.unwrap();
+        assert_eq!(fs.quota().bytes_used(), 10);
+    }
+
+    #[tokio::test]
+    async fn set_len_and_truncate_are_counted() {
+        let limits = QuotaLimits {
+            error: QuotaError::StorageFull,
+            ..limits(Some(100), None)
+        };
+        let fs = QuotaFileSystem::new(MemFS::default(), limits).unwrap();
+
+        let mut f = fs
+            .new_open_options()
+            .create(true)
+            .write(true)
+            .open("/file.txt")
+            .unwrap();
+        f.set_len(60).unwrap();
+        assert_eq!(fs.quota().bytes_used(), 60);
+        assert_eq!(f.set_len(101), Err(FsError::StorageFull));
+        f.set_len(20).unwrap();
+        assert_eq!(fs.quota().bytes_used(), 20);
+        drop(f);
+
+        fs.new_open_options()
+            .write(true)
+            .truncate(true)
+            .open("/file.txt")
+            .unwrap();
+        assert_eq!(fs.quota().bytes_used(), 0);
+    }
+
+    #[tokio::test]
+    async fn inodes_are_limited() {
+        let fs = QuotaFileSystem::new(MemFS::default(), limits(None, Some(2))).unwrap();
+
+        fs.create_dir(Path::new("/dir")).unwrap();
+        ops::touch(&fs, "/dir/file.txt").unwrap();
+        assert_eq!(
+            fs.create_dir(Path::new("/other")),
+            Err(FsError::QuotaExceeded)
+        );
+
+        fs.remove_file(Path::new("/dir/file.txt")).unwrap();
+        assert_eq!(fs.quota().inodes_used(), 1);
+        fs.create_dir(Path::new("/other")).unwrap();
+    }
+
+    #[tokio::test]
+    async fn renames_release_what_they_replace() {
+        let fs = QuotaFileSystem::new(MemFS::default(), limits(None, None)).unwrap();
+        ops::write(&fs, "/a.txt", b"Hello").await.unwrap();
+        ops::write(&fs, "/b.txt", b"World!").await.unwrap();
+
+        fs.rename(Path::new("/a.txt"), Path::new("/a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(fs.quota().bytes_used(), 11);
+        assert_eq!(fs.quota().inodes_used(), 2);
+
+        fs.rename(Path::new("/a.txt"), Path::new("/b.txt"))
+            .await
+            .unwrap();
+        assert_eq!(fs.quota().bytes_used(), 5);
+        assert_eq!(fs.quota().inodes_used(), 1);
+    }
+
+    #[cfg(all(unix, feature = "host-fs"))]
+    #[tokio::test]
+    async fn renaming_onto_a_hard_link_releases_nothing() {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(temp.path().join("a.txt"), b"Hello").unwrap();
+        std::fs::hard_link(temp.path().join("a.txt"), temp.path().join("b.txt")).unwrap();
+        let inner = crate::host_fs::FileSystem::new(tokio::runtime::Handle::current(), temp.path())
+            .unwrap();
+        let fs = QuotaFileSystem::new(inner, limits(None, None)).unwrap();
+        let (bytes, inodes) = (fs.quota().bytes_used(), fs.quota().inodes_used());
+
+        fs.rename(Path::new("/a.txt"), Path::new("/b.txt"))
+            .await
+            .unwrap();
+
+        assert!(temp.path().join("a.txt").exists());
+        assert_eq!(fs.quota().bytes_used(), bytes);
+        assert_eq!(fs.quota().inodes_used(), inodes);
+    }
+}
@@ -3,13 +3,14 @@
 //! readonly files, etc...
 
 use std::{
+    ffi::{OsStr, OsString},
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use crate::{
     limiter::DynFsMemoryLimiter, mem_fs, BoxFuture, FileSystem, Metadata, OpenOptions, ReadDir,
-    Result,
+    Result, XattrMode,
 };
 
 #[derive(Debug, Default, Clone)]
@@ -105,4 +106,30 @@ impl FileSystem for TmpFileSystem {
     ) -> Result<()> {
         FileSystem::mount(&self.fs, name, path, fs)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        self.fs.get_xattr(path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        self.fs.set_xattr(path, name, value, mode)
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        self.fs.list_xattr(path)
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        self.fs.remove_xattr(path, name)
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        self.fs.create_socket(path)
+    }
 }
@@ -1,4 +1,5 @@
 use std::{
+    ffi::{OsStr, OsString},
     path::{Path, PathBuf},
     pin::Pin,
     task::{Context, Poll},
@@ -97,6 +98,41 @@ where
     ) -> crate::Result<()> {
         self.0.mount(name, path, fs)
     }
+
+    #[tracing::instrument(level = "trace", skip(self), err)]
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> crate::Result<Vec<u8>> {
+        self.0.get_xattr(path, name)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, value), err)]
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: crate::XattrMode,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move { self.0.set_xattr(path, name, value, mode).await })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self), err)]
+    fn list_xattr(&self, path: &Path) -> crate::Result<Vec<OsString>> {
+        self.0.list_xattr(path)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self), err)]
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, crate::Result<()>> {
+        Box::pin(async move { self.0.remove_xattr(path, name).await })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self), err)]
+    fn create_socket(&self, path: &std::path::Path) -> crate::Result<()> {
+        self.0.create_socket(path)
+    }
 }
 
 impl<F> FileOpener for TraceFileSystem<F>
@@ -154,6 +190,21 @@ impl VirtualFile for TraceFile {
         self.file.set_len(new_size)
     }
 
+    #[tracing::instrument(level = "trace", skip(self), fields(path=%self.path.display()), err)]
+    fn seek_data(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_data(offset)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self), fields(path=%self.path.display()), err)]
+    fn seek_hole(&mut self, offset: u64) -> crate::Result<u64> {
+        self.file.seek_hole(offset)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self), fields(path=%self.path.display()), err)]
+    fn punch_hole(&mut self, offset: u64, len: u64) -> crate::Result<()> {
+        self.file.punch_hole(offset, len)
+    }
+
     fn unlink(&mut self) -> crate::Result<()> {
         self.file.unlink()
     }
@@ -6,7 +6,11 @@ use dashmap::DashMap;
 
 use crate::*;
 
-use std::{path::Path, sync::Arc};
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    sync::Arc,
+};
 
 #[derive(Debug)]
 pub struct MountPoint {
@@ -270,6 +274,76 @@ impl FileSystem for UnionFileSystem {
 
         Ok(())
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> Result<Vec<u8>> {
+        let path = self.prepare_path(path);
+
+        if path.as_os_str().is_empty() {
+            Err(FsError::NoAttribute)
+        } else if let Some((_, path, fs)) = self.find_mount(path.to_owned()) {
+            fs.get_xattr(&path, name)
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.prepare_path(path);
+
+            if path.as_os_str().is_empty() {
+                Err(FsError::PermissionDenied)
+            } else if let Some((_, path, fs)) = self.find_mount(path.to_owned()) {
+                fs.set_xattr(&path, name, value, mode).await
+            } else {
+                Err(FsError::EntryNotFound)
+            }
+        })
+    }
+
+    fn list_xattr(&self, path: &Path) -> Result<Vec<OsString>> {
+        let path = self.prepare_path(path);
+
+        if path.as_os_str().is_empty() {
+            Ok(Vec::new())
+        } else if let Some((_, path, fs)) = self.find_mount(path.to_owned()) {
+            fs.list_xattr(&path)
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
+
+    fn remove_xattr<'a>(&'a self, path: &'a Path, name: &'a OsStr) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let path = self.prepare_path(path);
+
+            if path.as_os_str().is_empty() {
+                Err(FsError::PermissionDenied)
+            } else if let Some((_, path, fs)) = self.find_mount(path.to_owned()) {
+                fs.remove_xattr(&path, name).await
+            } else {
+                Err(FsError::EntryNotFound)
+            }
+        })
+    }
+
+    fn create_socket(&self, path: &Path) -> Result<()> {
+        let path = self.prepare_path(path);
+
+        if path.as_os_str().is_empty() {
+            Err(FsError::AlreadyExists)
+        } else if let Some((_, path, fs)) = self.find_mount(path.to_owned()) {
+            fs.create_socket(&path)
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
 }
 
 #[derive(Debug)]
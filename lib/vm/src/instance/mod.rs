@@ -224,9 +224,8 @@ impl Instance {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_tables_begin()) }
     }
 
-    #[allow(dead_code)]
     /// Get a locally defined or imported memory.
-    fn get_memory(&self, index: MemoryIndex) -> VMMemoryDefinition {
+    pub(crate) fn get_memory(&self, index: MemoryIndex) -> VMMemoryDefinition {
         if let Some(local_index) = self.module.local_memory_index(index) {
             self.memory(local_index)
         } else {
@@ -319,6 +318,15 @@ impl Instance {
         NonNull::new(unsafe { *self.globals_ptr().add(index) }).unwrap()
     }
 
+    /// Return the `VMGlobalDefinition` of a locally defined or imported global.
+    pub(crate) fn get_global_ptr(&self, index: GlobalIndex) -> NonNull<VMGlobalDefinition> {
+        if let Some(local_index) = self.module.local_global_index(index) {
+            self.global_ptr(local_index)
+        } else {
+            self.imported_global(index).definition
+        }
+    }
+
     /// Return a pointer to the `VMGlobalDefinition`s.
     fn globals_ptr(&self) -> *mut *mut VMGlobalDefinition {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_globals_begin()) }
@@ -1007,6 +1015,7 @@ impl Drop for VMInstance {
         let instance_ptr = self.instance.as_ptr();
 
         unsafe {
+            crate::trap::unregister_instance_vmctx((*instance_ptr).vmctx_ptr());
             // Need to drop all the actual Instance members
             instance_ptr.drop_in_place();
             // And then free the memory allocated for the Instance itself
@@ -1151,6 +1160,7 @@ impl VMInstance {
         // initialization is deferred to the `initialize` method.
         initialize_passive_elements(instance);
         initialize_globals(instance);
+        crate::trap::register_instance_vmctx(instance.vmctx_ptr());
 
         Ok(handle)
     }
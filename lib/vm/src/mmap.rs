@@ -70,7 +70,7 @@ impl Mmap {
         mut backing_file: Option<std::path::PathBuf>,
         memory_type: MmapType,
     ) -> Result<Self, String> {
-        use std::os::fd::IntoRawFd;
+        use std::os::fd::AsRawFd;
 
         let page_size = region::page::size();
         assert_le!(accessible_size, mapping_size);
@@ -85,7 +85,10 @@ impl Mmap {
 
         // If there is a backing file, resize the file so that its at least
         // `mapping_size` bytes.
+        // The file is closed once it has been mapped, the mapping keeps it
+        // alive
         let mut memory_fd = -1;
+        let mut _memory_file = None;
         if let Some(backing_file_path) = &mut backing_file {
             let file = std::fs::OpenOptions::new()
                 .read(true)
@@ -115,7 +118,8 @@ impl Mmap {
             }
 
             accessible_size = accessible_size.min(mapping_size);
-            memory_fd = file.into_raw_fd();
+            memory_fd = file.as_raw_fd();
+            _memory_file = Some(file);
         }
 
         // Compute the flags
@@ -10,8 +10,9 @@ mod traphandlers;
 
 pub use trap::Trap;
 pub use traphandlers::{
-    catch_traps, on_host_stack, raise_lib_trap, raise_user_trap, set_stack_size,
-    wasmer_call_trampoline, TrapHandlerFn, VMConfig,
+    catch_traps, current_vmctx, on_host_stack, raise_lib_trap, raise_user_trap, set_stack_size,
+    track_current_vmctx, wasmer_call_trampoline, TrapHandlerFn, VMConfig,
 };
 pub use traphandlers::{init_traps, resume_panic};
+pub(crate) use traphandlers::{register_instance_vmctx, unregister_instance_vmctx};
 pub use wasmer_types::TrapCode;
@@ -16,14 +16,15 @@ use corosensei::{Coroutine, CoroutineResult, Yielder};
 use scopeguard::defer;
 use std::any::Any;
 use std::cell::Cell;
+use std::collections::BTreeSet;
 use std::error::Error;
 use std::io;
 use std::mem;
 #[cfg(unix)]
 use std::mem::MaybeUninit;
 use std::ptr::{self, NonNull};
-use std::sync::atomic::{compiler_fence, AtomicPtr, AtomicUsize, Ordering};
-use std::sync::{LazyLock, Once};
+use std::sync::atomic::{compiler_fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Once, RwLock};
 use wasmer_types::TrapCode;
 
 /// Configuration for the runtime VM
@@ -685,16 +686,82 @@ pub unsafe fn wasmer_call_trampoline(
     callee: *const VMFunctionBody,
     values_vec: *mut u8,
 ) -> Result<(), Trap> {
-    catch_traps(trap_handler, config, move || {
-        mem::transmute::<
-            unsafe extern "C" fn(
-                *mut VMContext,
-                *const VMFunctionBody,
-                *mut wasmer_types::RawValue,
-            ),
-            extern "C" fn(VMFunctionContext, *const VMFunctionBody, *mut u8),
-        >(trampoline)(vmctx, callee, values_vec);
-    })
+    let call = move || {
+        catch_traps(trap_handler, config, move || {
+            mem::transmute::<
+                unsafe extern "C" fn(
+                    *mut VMContext,
+                    *const VMFunctionBody,
+                    *mut wasmer_types::RawValue,
+                ),
+                extern "C" fn(VMFunctionContext, *const VMFunctionBody, *mut u8),
+            >(trampoline)(vmctx, callee, values_vec);
+        })
+    };
+    if !TRACK_VMCTX.load(Ordering::Relaxed) {
+        return call();
+    }
+
+    // Host functions get their environment in place of a `vmctx`
+    let instance = INSTANCE_VMCTXS
+        .read()
+        .unwrap()
+        .contains(&(vmctx.vmctx as usize));
+    let recorded = if instance {
+        vmctx.vmctx
+    } else {
+        ptr::null_mut()
+    };
+    let previous = CURRENT_VMCTX.with(|current| current.replace(recorded));
+    defer! {
+        CURRENT_VMCTX.with(|current| current.set(previous));
+    }
+    call()
+}
+
+thread_local! {
+    /// Read from signal handlers (see [`current_vmctx`]), so it has to stay
+    /// const initialised and without a destructor.
+    static CURRENT_VMCTX: Cell<*mut VMContext> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Whether the instance that each thread runs is tracked for
+/// [`current_vmctx`], which costs a lookup on every call into WebAssembly
+static TRACK_VMCTX: AtomicBool = AtomicBool::new(false);
+
+/// The `vmctx` of the instances that were created while tracking
+static INSTANCE_VMCTXS: RwLock<BTreeSet<usize>> = RwLock::new(BTreeSet::new());
+
+/// Makes [`current_vmctx`] return the instance that each thread runs.
+///
+/// Only the instances that are created after this was called are known,
+/// so it has to be called before the module is instantiated.
+pub fn track_current_vmctx() {
+    TRACK_VMCTX.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn register_instance_vmctx(vmctx: *mut VMContext) {
+    if TRACK_VMCTX.load(Ordering::Relaxed) {
+        INSTANCE_VMCTXS.write().unwrap().insert(vmctx as usize);
+    }
+}
+
+pub(crate) fn unregister_instance_vmctx(vmctx: *mut VMContext) {
+    if TRACK_VMCTX.load(Ordering::Relaxed) {
+        INSTANCE_VMCTXS.write().unwrap().remove(&(vmctx as usize));
+    }
+}
+
+/// Returns the `vmctx` of the instance whose function was last called from
+/// the host on this thread and is still running.
+///
+/// This is what debuggers use to inspect the instance that a thread stopped
+/// in. It is always `None` unless [`track_current_vmctx`] was called, and
+/// while a host function that was called from the host runs.
+///
+/// It only reads a thread local, so it may be called from signal handlers.
+pub fn current_vmctx() -> Option<NonNull<VMContext>> {
+    NonNull::new(CURRENT_VMCTX.with(|current| current.get()))
 }
 
 /// Catches any wasm traps that happen within the execution of `closure`,
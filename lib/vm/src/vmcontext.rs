@@ -15,7 +15,7 @@ use crate::{VMFunctionBody, VMTag};
 use std::convert::TryFrom;
 use std::ptr::{self, NonNull};
 use std::sync::atomic::{AtomicPtr, Ordering};
-use wasmer_types::RawValue;
+use wasmer_types::{GlobalIndex, MemoryIndex, ModuleInfo, RawValue};
 
 /// Union representing the first parameter passed when calling a function.
 ///
@@ -806,6 +806,32 @@ impl VMContext {
     pub(crate) unsafe fn instance_mut(&mut self) -> &mut Instance {
         &mut *((self as *const Self as *mut u8).offset(-Instance::vmctx_offset()) as *mut Instance)
     }
+
+    /// Return the module of the associated `Instance`.
+    ///
+    /// # Safety
+    /// Same as [`VMContext::instance`].
+    pub unsafe fn module_info(&self) -> &ModuleInfo {
+        self.instance().module_ref()
+    }
+
+    /// Return the definition of a locally defined or imported memory of the
+    /// associated `Instance`.
+    ///
+    /// # Safety
+    /// Same as [`VMContext::instance`].
+    pub unsafe fn memory_definition(&self, index: MemoryIndex) -> VMMemoryDefinition {
+        self.instance().get_memory(index)
+    }
+
+    /// Return the definition of a locally defined or imported global of the
+    /// associated `Instance`.
+    ///
+    /// # Safety
+    /// Same as [`VMContext::instance`].
+    pub unsafe fn global_definition(&self, index: GlobalIndex) -> NonNull<VMGlobalDefinition> {
+        self.instance().get_global_ptr(index)
+    }
 }
 
 /// The type for tramplines in the VM.
@@ -1193,25 +1193,6 @@ impl core::fmt::Debug for Snapshot0Whence {
         }
     }
 }
-#[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
-pub enum Whence {
-    Set,
-    Cur,
-    End,
-    Unknown = 255,
-}
-impl core::fmt::Debug for Whence {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        match self {
-            Whence::Set => f.debug_tuple("Whence::Set").finish(),
-            Whence::Cur => f.debug_tuple("Whence::Cur").finish(),
-            Whence::End => f.debug_tuple("Whence::End").finish(),
-            Whence::Unknown => f.debug_tuple("Whence::Unknown").finish(),
-        }
-    }
-}
 #[repr(C)]
 #[derive(Copy, Clone, PartialEq)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -3203,37 +3184,6 @@ unsafe impl wasmer::FromToNativeWasmType for Snapshot0Whence {
     }
 }
 
-// TODO: if necessary, must be implemented in wit-bindgen
-unsafe impl ValueType for Whence {
-    #[inline]
-    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
-}
-
-unsafe impl wasmer::FromToNativeWasmType for Whence {
-    type Native = i32;
-
-    fn to_native(self) -> Self::Native {
-        self as i32
-    }
-
-    fn from_native(n: Self::Native) -> Self {
-        match n {
-            0 => Self::Set,
-            1 => Self::Cur,
-            2 => Self::End,
-
-            q => {
-                tracing::debug!("could not serialize number {q} to enum Whence");
-                Self::Unknown
-            }
-        }
-    }
-
-    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
-        false
-    }
-}
-
 // TODO: if necessary, must be implemented in wit-bindgen
 unsafe impl ValueType for Tty {
     #[inline]
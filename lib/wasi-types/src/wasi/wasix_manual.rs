@@ -5,9 +5,9 @@ use std::mem::MaybeUninit;
 use wasmer::{FromToNativeWasmType, MemorySize, ValueType};
 
 use super::{
-    Errno, ErrnoSignal, EventFdReadwrite, Eventtype, Fd, JoinStatusType, ProcSpawnFdOp, Signal,
-    SignalDisposition, Snapshot0SubscriptionClock, SubscriptionClock, SubscriptionFsReadwrite,
-    Userdata,
+    Errno, ErrnoSignal, EventFdReadwrite, Eventtype, Fd, Filesize, JoinStatusType, ProcSpawnFdOp,
+    Signal, SignalDisposition, Snapshot0SubscriptionClock, SubscriptionClock,
+    SubscriptionFsReadwrite, Timestamp, Userdata,
 };
 
 /// Thread local key
@@ -516,3 +516,414 @@ unsafe impl<M: MemorySize> ValueType for ProcSpawnFdOp<M> {
     #[inline]
     fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
 }
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags used when setting an extended attribute."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Xattrflags : u32 {
+        #[doc = " Fail if the attribute already exists."]
+        const CREATE = 1 << 0;
+        #[doc = " Fail if the attribute does not already exist."]
+        const REPLACE = 1 << 1;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Xattrflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Xattrflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags used when allocating or deallocating space in a file."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Fallocflags : u32 {
+        #[doc = " Do not change the size of the file, even if the range extends past its end."]
+        const KEEP_SIZE = 1 << 0;
+        #[doc = " Deallocate the range so it reads back as zeroes. Requires `KEEP_SIZE`."]
+        const PUNCH_HOLE = 1 << 1;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Fallocflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Fallocflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+#[doc = " The position relative to which to set the offset of the file descriptor."]
+#[doc = " WASIX adds `data` and `hole` to the values of WASI."]
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum Whence {
+    #[doc = " Seek relative to start-of-file."]
+    Set,
+    #[doc = " Seek relative to current position."]
+    Cur,
+    #[doc = " Seek relative to end-of-file."]
+    End,
+    #[doc = " Seek to the first byte of data at or after the offset."]
+    Data,
+    #[doc = " Seek to the first hole at or after the offset."]
+    Hole,
+    Unknown = 255,
+}
+impl core::fmt::Debug for Whence {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Whence::Set => f.debug_tuple("Whence::Set").finish(),
+            Whence::Cur => f.debug_tuple("Whence::Cur").finish(),
+            Whence::End => f.debug_tuple("Whence::End").finish(),
+            Whence::Data => f.debug_tuple("Whence::Data").finish(),
+            Whence::Hole => f.debug_tuple("Whence::Hole").finish(),
+            Whence::Unknown => f.debug_tuple("Whence::Unknown").finish(),
+        }
+    }
+}
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Whence {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Whence {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        match n {
+            0 => Self::Set,
+            1 => Self::Cur,
+            2 => Self::End,
+            3 => Self::Data,
+            4 => Self::Hole,
+
+            q => {
+                tracing::debug!("could not serialize number {q} to enum Whence");
+                Self::Unknown
+            }
+        }
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        false
+    }
+}
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Access that is allowed to a region of mapped memory."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Mmapprot : u32 {
+        #[doc = " The mapped memory can be read."]
+        const READ = 1 << 0;
+        #[doc = " The mapped memory can be written, required for changes to reach the file."]
+        const WRITE = 1 << 1;
+        #[doc = " The mapped memory can be executed."]
+        const EXEC = 1 << 2;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Mmapprot {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Mmapprot {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags that control how a file is mapped into memory."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Mmapflags : u32 {
+        #[doc = " Changes to the mapped memory are written back to the file."]
+        const SHARED = 1 << 0;
+        #[doc = " Changes to the mapped memory are private to the process."]
+        const PRIVATE = 1 << 1;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Mmapflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Mmapflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags used when synchronizing mapped memory with its file."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Msyncflags : u32 {
+        #[doc = " Schedule the write back but return immediately."]
+        const ASYNC = 1 << 0;
+        #[doc = " Wait for the write back to complete."]
+        const SYNC = 1 << 1;
+        #[doc = " Discard the cached copies of the mapped data."]
+        const INVALIDATE = 1 << 2;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Msyncflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Msyncflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+/// Handle of a shared library that has been loaded with `dlopen`.
+pub type Dlhandle = u32;
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags used when loading a shared library."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Dlflags : u32 {
+        #[doc = " Resolve symbols when they are first used."]
+        const LAZY = 1 << 0;
+        #[doc = " Resolve all the symbols before returning."]
+        const NOW = 1 << 1;
+        #[doc = " Make the symbols of the library available to libraries loaded later."]
+        const GLOBAL = 1 << 2;
+        #[doc = " Only return a handle if the library is already loaded."]
+        const NOLOAD = 1 << 3;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Dlflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Dlflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+#[doc = " Whose resource usage is returned by `getrusage`."]
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, num_enum :: TryFromPrimitive, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum Rusagewho {
+    #[doc = " The calling process."]
+    Self_,
+    #[doc = " The children of the calling process that have been joined."]
+    Children,
+    #[doc = " Unknown."]
+    Unknown,
+}
+impl core::fmt::Debug for Rusagewho {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Rusagewho::Self_ => f.debug_tuple("RUSAGE_SELF").finish(),
+            Rusagewho::Children => f.debug_tuple("RUSAGE_CHILDREN").finish(),
+            Rusagewho::Unknown => f.debug_tuple("Unknown").finish(),
+        }
+    }
+}
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Rusagewho {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Rusagewho {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        match n {
+            0 => Self::Self_,
+            1 => Self::Children,
+
+            q => {
+                tracing::debug!("could not serialize number {q} to enum Rusagewho");
+                Self::Unknown
+            }
+        }
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        false
+    }
+}
+
+/// Resources used by a process (or by its children)
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Rusage {
+    /// Time that the threads were alive and not blocked waiting on the
+    /// host (in nanoseconds), which is not the CPU time of the host
+    pub busy_time: Timestamp,
+    /// Time since the process started, or until it exited (in nanoseconds)
+    pub wall_time: Timestamp,
+    /// Largest size of the linear memory (in bytes)
+    pub peak_memory: Filesize,
+    /// Number of bytes read from files, pipes and terminals
+    pub bytes_read: Filesize,
+    /// Number of bytes written to files, pipes and terminals
+    pub bytes_written: Filesize,
+    /// Number of bytes received on sockets
+    pub socket_bytes_read: Filesize,
+    /// Number of bytes sent on sockets
+    pub socket_bytes_written: Filesize,
+    /// CPU time that the host measured for the threads (in nanoseconds),
+    /// which is zero on hosts without per-thread CPU clocks
+    pub cpu_time: Timestamp,
+}
+
+unsafe impl ValueType for Rusage {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+/// Handle of a named semaphore that has been opened with `sem_open`.
+pub type Semhandle = u32;
+
+/// Handle of a message queue that has been opened with `mq_open`.
+pub type Mqhandle = u32;
+
+wai_bindgen_rust::bitflags::bitflags! {
+    #[doc = " Flags used when opening a named IPC object."]
+    #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+    pub struct Ipcflags : u32 {
+        #[doc = " Create the object if it does not exist."]
+        const CREATE = 1 << 0;
+        #[doc = " Fail if the object already exists (only with `CREATE`)."]
+        const EXCL = 1 << 1;
+        #[doc = " Truncate a shared memory object to zero length."]
+        const TRUNC = 1 << 2;
+        #[doc = " Open a shared memory object or message queue for reading only."]
+        const RDONLY = 1 << 3;
+        #[doc = " Sending to or receiving from a message queue does not block."]
+        const NONBLOCK = 1 << 4;
+    }
+}
+
+// TODO: if necessary, must be implemented in wit-bindgen
+unsafe impl ValueType for Ipcflags {
+    #[inline]
+    fn zero_padding_bytes(&self, _bytes: &mut [MaybeUninit<u8>]) {}
+}
+
+unsafe impl wasmer::FromToNativeWasmType for Ipcflags {
+    type Native = i32;
+
+    fn to_native(self) -> Self::Native {
+        self.bits() as i32
+    }
+
+    fn from_native(n: Self::Native) -> Self {
+        Self::from_bits_truncate(n as u32)
+    }
+
+    fn is_from_store(&self, _store: &impl wasmer::AsStoreRef) -> bool {
+        // TODO: find correct implementation
+        false
+    }
+}
+
+/// Identifier of a user.
+pub type Uid = u32;
+
+/// Identifier of a group of users.
+pub type Gid = u32;
@@ -13,7 +13,7 @@ use webc::Container;
 
 use crate::{
     runners::MappedDirectory,
-    runtime::resolver::{PackageInfo, ResolveError},
+    runtime::resolver::{PackageInfo, PackageSummary, ResolveError},
     Runtime,
 };
 use wasmer_types::ModuleHash;
@@ -93,6 +93,11 @@ pub struct BinaryPackage {
     pub webc_fs: Arc<dyn FileSystem + Send + Sync>,
     pub commands: Vec<BinaryPackageCommand>,
     pub uses: Vec<String>,
+    /// The packages further down the tree, nearest first, which are only
+    /// loaded through the [`PackageLoader`](crate::runtime::package_loader::PackageLoader)
+    /// when something in their volumes is looked for (e.g. a library that
+    /// is opened with `dlopen()`)
+    pub dependencies: Vec<PackageSummary>,
     pub file_system_memory_footprint: u64,
 
     pub additional_host_mapped_directories: Vec<MappedDirectory>,
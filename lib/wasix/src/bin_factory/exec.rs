@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     os::task::{
-        thread::{RewindResultType, WasiThreadRunGuard},
+        thread::{CurrentThreadGuard, RewindResultType, WasiThreadRunGuard},
         TaskJoinHandle,
     },
     runtime::{
@@ -120,9 +120,14 @@ pub async fn spawn_union_fs(env: &WasiEnv, binary: &BinaryPackage) -> Result<(),
 
 pub fn spawn_exec_module(
     module: Module,
-    env: WasiEnv,
+    mut env: WasiEnv,
     runtime: &Arc<dyn Runtime + Send + Sync + 'static>,
 ) -> Result<TaskJoinHandle, SpawnError> {
+    // The libraries that the previous program loaded do not belong to this
+    // one (`exec` keeps the process), while the program that called `exec`
+    // keeps them in case it fails
+    env.process.dl = Default::default();
+
     // Create a new task manager
     let tasks = runtime.task_manager();
 
@@ -246,6 +251,7 @@ fn call_module(
     let env = ctx.data(&store);
     let pid = env.pid();
     let tasks = env.tasks().clone();
+    let _current = CurrentThreadGuard::enter(pid, env.tid(), &env.process.usage);
     handle.thread.set_status_running();
     let runtime = env.runtime.clone();
 
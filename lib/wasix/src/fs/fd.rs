@@ -14,7 +14,7 @@ use tokio::sync::{watch, Mutex as AsyncMutex};
 use virtual_fs::{Pipe, PipeRx, PipeTx, VirtualFile};
 use wasmer_wasix_types::wasi::{EpollType, Fd as WasiFd, Fdflags, Fdflagsext, Filestat, Rights};
 
-use crate::{net::socket::InodeSocket, syscalls::EpollJoinWaker};
+use crate::{net::socket::InodeSocket, os::credentials::FileOwner, syscalls::EpollJoinWaker};
 
 use super::{
     InodeGuard, InodeValFilePollGuard, InodeValFilePollGuardJoin, InodeValFilePollGuardMode,
@@ -75,6 +75,9 @@ pub struct InodeVal {
     pub is_preopened: bool,
     pub name: RwLock<Cow<'static, str>>,
     pub kind: RwLock<Kind>,
+    /// Owner and mode of the file, shared by all of its hard links, or
+    /// `None` if it belongs to the default owner
+    pub owner: RwLock<Option<FileOwner>>,
 }
 
 impl InodeVal {
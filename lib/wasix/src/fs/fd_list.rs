@@ -4,13 +4,18 @@
 //! Note, The Unix spec requires newly allocated FDs to always be the
 //! lowest-numbered FD available.
 
+use std::sync::Arc;
+
 use super::fd::{Fd, FdInner};
+use crate::os::task::resource_group::ResourceGroup;
 use wasmer_wasix_types::wasi::Fd as WasiFd;
 
 #[derive(Debug)]
 pub struct FdList {
     fds: Vec<Option<Fd>>,
     first_free: Option<usize>,
+    /// Group that counts the FDs in the list
+    group: Option<Arc<ResourceGroup>>,
 }
 
 pub struct FdListIterator<'a> {
@@ -35,6 +40,29 @@ impl FdList {
         Self {
             fds: vec![],
             first_free: None,
+            group: None,
+        }
+    }
+
+    /// Gets the group that counts the FDs in the list
+    pub(crate) fn resource_group(&self) -> Option<&Arc<ResourceGroup>> {
+        self.group.as_ref()
+    }
+
+    /// Moves the FDs in the list (and any that are inserted later) to
+    /// another group
+    pub(crate) fn set_resource_group(&mut self, group: Arc<ResourceGroup>) {
+        let count = self.iter().count();
+        if let Some(previous) = self.group.as_ref() {
+            previous.remove_open_files(count);
+        }
+        group.add_open_files(count);
+        self.group = Some(group);
+    }
+
+    fn count_inserted(&self) {
+        if let Some(group) = self.group.as_ref() {
+            group.add_open_files(1);
         }
     }
 
@@ -66,6 +94,7 @@ impl FdList {
 
     pub fn insert_first_free(&mut self, fd: Fd) -> WasiFd {
         fd.inode.acquire_handle();
+        self.count_inserted();
         match self.first_free {
             Some(free) => {
                 assert!(self.fds[free].is_none());
@@ -106,6 +135,7 @@ impl FdList {
             Some(_) => {
                 // This is handled by insert or insert_first_free in every other case, but not this one
                 fd.inode.acquire_handle();
+                self.count_inserted();
 
                 match self.first_free_after(after_or_equal) {
                     // Found a suitable hole, and it's guaranteed to not be the first since
@@ -159,6 +189,8 @@ impl FdList {
             } else {
                 prev_fd.inode.drop_one_handle();
             }
+        } else {
+            self.count_inserted();
         }
 
         fd.inode.acquire_handle();
@@ -184,12 +216,18 @@ impl FdList {
             }
 
             fd.inode.drop_one_handle();
+            if let Some(group) = self.group.as_ref() {
+                group.remove_open_files(1);
+            }
         }
 
         result
     }
 
     pub fn clear(&mut self) {
+        if let Some(group) = self.group.as_ref() {
+            group.remove_open_files(self.iter().count());
+        }
         for fd in &self.fds {
             if let Some(fd) = fd.as_ref() {
                 fd.inode.drop_one_handle();
@@ -227,9 +265,14 @@ impl Clone for FdList {
             }
         }
 
+        if let Some(group) = self.group.as_ref() {
+            group.add_open_files(self.iter().count());
+        }
+
         Self {
             fds: self.fds.clone(),
             first_free: self.first_free,
+            group: self.group.clone(),
         }
     }
 }
@@ -297,9 +340,12 @@ mod tests {
     };
 
     use assert_panic::assert_panic;
-    use wasmer_wasix_types::wasi::{Fdflags, Fdflagsext, Rights};
+    use wasmer_wasix_types::wasi::{Errno, Fdflags, Fdflagsext, Rights};
 
-    use crate::fs::{fd::FdInner, Inode, InodeGuard, InodeVal, Kind};
+    use crate::{
+        fs::{fd::FdInner, Inode, InodeGuard, InodeVal, Kind},
+        os::task::resource_group::{ResourceGroup, ResourceLimits},
+    };
 
     use super::{Fd, FdList, WasiFd};
 
@@ -313,6 +359,7 @@ mod tests {
                     kind: RwLock::new(Kind::Buffer { buffer: vec![] }),
                     name: RwLock::new(Cow::Borrowed("")),
                     stat: RwLock::new(Default::default()),
+                    owner: RwLock::new(None),
                 }),
                 open_handles: Arc::new(AtomicI32::new(0)),
             },
@@ -688,6 +735,44 @@ mod tests {
         assert_panic!(drop(fd0.inode.write()), String, contains "PoisonError");
     }
 
+    #[test]
+    fn fds_are_counted_by_resource_group() {
+        let root = ResourceGroup::new(Default::default());
+        let group = root.new_child(ResourceLimits {
+            max_open_files: Some(2),
+            ..Default::default()
+        });
+
+        let mut l = FdList::new();
+        l.insert_first_free(useless_fd(0));
+        l.set_resource_group(group.clone());
+        l.insert_first_free(useless_fd(1));
+        assert_eq!(group.open_files(), 2);
+        assert_eq!(root.open_files(), 2);
+        assert_eq!(group.reserve_open_file().err(), Some(Errno::Mfile));
+
+        // Replacing an FD does not open another one
+        l.insert(false, 1, useless_fd(2));
+        assert_eq!(group.open_files(), 2);
+
+        l.remove(0);
+        assert_eq!(group.open_files(), 1);
+        assert!(group.reserve_open_file().is_ok());
+
+        // Forked lists count towards the same group until they move
+        let l2 = l.clone();
+        assert_eq!(group.open_files(), 2);
+        let mut l3 = l2.clone();
+        l3.set_resource_group(root.clone());
+        assert_eq!(group.open_files(), 2);
+        assert_eq!(root.open_files(), 3);
+
+        drop(l);
+        drop(l2);
+        drop(l3);
+        assert_eq!(root.open_files(), 0);
+    }
+
     #[test]
     fn messing_with_inode_causes_panic() {
         // We want to pin this behavior down, as not causing a panic
@@ -9,15 +9,20 @@ mod fd;
 mod fd_list;
 mod inode_guard;
 mod notification;
+mod proc_fs;
+mod pts_fs;
+#[cfg(all(unix, feature = "sys"))]
+mod shm_fs;
 
 use std::{
     borrow::{Borrow, Cow},
     collections::{HashMap, HashSet, VecDeque},
+    ffi::{OsStr, OsString},
     ops::{Deref, DerefMut},
     path::{Component, Path, PathBuf},
     pin::Pin,
     sync::{
-        atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering},
         Arc, Mutex, RwLock, Weak,
     },
     task::{Context, Poll},
@@ -33,12 +38,12 @@ use futures::{future::BoxFuture, Future, TryStreamExt};
 use serde_derive::{Deserialize, Serialize};
 use tokio::io::AsyncWriteExt;
 use tracing::{debug, trace};
-use virtual_fs::{copy_reference, FileSystem, FsError, OpenOptions, VirtualFile};
+use virtual_fs::{copy_reference, FileSystem, FsError, OpenOptions, VirtualFile, XattrMode};
 use wasmer_config::package::PackageId;
 use wasmer_wasix_types::{
     types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO},
     wasi::{
-        Errno, Fd as WasiFd, Fdflags, Fdflagsext, Fdstat, Filesize, Filestat, Filetype,
+        Errno, Fd as WasiFd, Fdflags, Fdflagsext, Fdstat, Filesize, Filestat, Filetype, Fstflags,
         Preopentype, Prestat, PrestatEnum, Rights, Socktype,
     },
 };
@@ -49,8 +54,23 @@ pub(crate) use self::inode_guard::{
     InodeValFileReadGuard, InodeValFileWriteGuard, WasiStateFileGuard, POLL_GUARD_MAX_RET,
 };
 pub use self::notification::NotificationInner;
+pub use self::proc_fs::ProcFileSystem;
+pub(crate) use self::proc_fs::{PROC_ROOT, PROC_SELF};
+pub use self::pts_fs::{PtsFileSystem, PTMX};
+#[cfg(all(unix, feature = "sys"))]
+pub use self::shm_fs::{
+    shm_page_size, shm_unmap, SharedMemoryObject, ShmFile, ShmFileSystem, SHM_MAX_SIZE,
+};
 use crate::syscalls::map_io_err;
-use crate::{bin_factory::BinaryPackage, state::PreopenedDir, ALL_RIGHTS};
+use crate::{
+    bin_factory::BinaryPackage,
+    os::{
+        credentials::{Credentials, FileOwner, FsPermissions, ACCESS_EXECUTE, ACCESS_WRITE},
+        task::resource_group::ResourceGroup,
+    },
+    state::PreopenedDir,
+    WasiProcessId, ALL_RIGHTS,
+};
 
 /// the fd value of the virtual root
 ///
@@ -458,6 +478,46 @@ impl FileSystem for WasiFsRoot {
             WasiFsRoot::Backing(f) => f.mount(name, path, fs),
         }
     }
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> virtual_fs::Result<Vec<u8>> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.get_xattr(path, name),
+            WasiFsRoot::Backing(fs) => fs.get_xattr(path, name),
+        }
+    }
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.set_xattr(path, name, value, mode),
+            WasiFsRoot::Backing(fs) => fs.set_xattr(path, name, value, mode),
+        }
+    }
+    fn list_xattr(&self, path: &Path) -> virtual_fs::Result<Vec<OsString>> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.list_xattr(path),
+            WasiFsRoot::Backing(fs) => fs.list_xattr(path),
+        }
+    }
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.remove_xattr(path, name),
+            WasiFsRoot::Backing(fs) => fs.remove_xattr(path, name),
+        }
+    }
+    fn create_socket(&self, path: &Path) -> virtual_fs::Result<()> {
+        match self {
+            WasiFsRoot::Sandbox(fs) => fs.create_socket(path),
+            WasiFsRoot::Backing(fs) => fs.create_socket(path),
+        }
+    }
 }
 
 /// Merge the contents of one filesystem into another.
@@ -533,6 +593,10 @@ pub struct WasiFs {
     pub root_fs: WasiFsRoot,
     pub root_inode: InodeGuard,
     pub has_unioned: Mutex<HashSet<PackageId>>,
+    // The packages that are in use, including the ones that were already
+    // included, in the order that they were added
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    pub(crate) packages: Mutex<Vec<BinaryPackage>>,
 
     // TODO: remove
     // using an atomic is a hack to enable customization after construction,
@@ -544,6 +608,14 @@ pub struct WasiFs {
     pub(crate) init_preopens: Vec<PreopenedDir>,
     // The virtual file system preopens when this was initialized
     pub(crate) init_vfs_preopens: Vec<String>,
+
+    // The process that `/proc/self` refers to, or zero if it is not known
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    proc_self: AtomicU32,
+
+    // What the permission bits of files are checked against, if they are
+    #[cfg_attr(feature = "enable-serde", serde(skip, default))]
+    permissions: Option<FsPermissions>,
 }
 
 impl WasiFs {
@@ -557,6 +629,133 @@ impl WasiFs {
         self.is_wasix.store(is_wasix, Ordering::SeqCst);
     }
 
+    /// Sets the process that `/proc/self` refers to when this process
+    /// follows it
+    pub(crate) fn set_proc_self(&self, pid: WasiProcessId) {
+        self.proc_self.store(pid.raw(), Ordering::Release);
+    }
+
+    /// Sets the group whose limit on open file descriptors applies, which
+    /// counts the file descriptors of the process from now on
+    pub(crate) fn set_resource_group(&self, group: Arc<ResourceGroup>) {
+        self.fd_map.write().unwrap().set_resource_group(group);
+    }
+
+    /// Gets the group whose limit on open file descriptors applies
+    fn resource_group(&self) -> Option<Arc<ResourceGroup>> {
+        self.fd_map.read().unwrap().resource_group().cloned()
+    }
+
+    /// Enforces the permission bits of files from now on
+    pub(crate) fn set_permissions(&mut self, permissions: FsPermissions) {
+        self.permissions = Some(permissions);
+    }
+
+    /// Checks permissions against other credentials, which a forked
+    /// process gets a copy of
+    pub(crate) fn set_credentials(&mut self, credentials: Arc<Mutex<Credentials>>) {
+        if let Some(permissions) = self.permissions.as_mut() {
+            permissions.credentials = credentials;
+        }
+    }
+
+    /// What the permission bits of files are checked against, `None` if
+    /// they are not checked
+    pub(crate) fn permissions(&self) -> Option<FsPermissions> {
+        self.permissions.clone()
+    }
+
+    /// Owner and mode of a file, which is the default owner for files that
+    /// have no owner of their own while permissions are enforced
+    pub(crate) fn owner_of(&self, inode: &InodeGuard) -> Option<FileOwner> {
+        let owner = *inode.owner.read().unwrap();
+        owner.or_else(|| {
+            let permissions = self.permissions.as_ref()?;
+            let is_dir = inode.stat.read().unwrap().st_filetype == Filetype::Directory;
+            Some(permissions.default_owner.owner(is_dir))
+        })
+    }
+
+    /// Checks that the permission bits of a file grant `access` (a
+    /// combination of the `ACCESS_*` constants) to the process, nothing is
+    /// checked unless permissions are enforced
+    pub(crate) fn check_access(&self, inode: &InodeGuard, access: u32) -> Result<(), Errno> {
+        let (Some(permissions), Some(owner)) = (&self.permissions, self.owner_of(inode)) else {
+            return Ok(());
+        };
+        if permissions
+            .credentials
+            .lock()
+            .unwrap()
+            .may_access(&owner, access)
+        {
+            Ok(())
+        } else {
+            Err(Errno::Access)
+        }
+    }
+
+    /// Checks that the process may add or remove entries in a directory
+    pub(crate) fn check_dir_write(&self, dir: &InodeGuard) -> Result<(), Errno> {
+        self.check_access(dir, ACCESS_WRITE | ACCESS_EXECUTE)
+    }
+
+    /// Checks that the process owns a file (or is privileged), which it
+    /// must to change the mode of the file or to set its times to anything
+    /// other than now
+    pub(crate) fn check_owner(&self, inode: &InodeGuard) -> Result<(), Errno> {
+        let (Some(permissions), Some(owner)) = (&self.permissions, self.owner_of(inode)) else {
+            return Ok(());
+        };
+        let credentials = permissions.credentials.lock().unwrap();
+        if credentials.is_privileged() || credentials.euid == owner.uid {
+            Ok(())
+        } else {
+            Err(Errno::Perm)
+        }
+    }
+
+    /// Checks that the process may change the times of a file, setting them
+    /// to now only needs write access while any other time needs ownership
+    pub(crate) fn check_set_times(
+        &self,
+        inode: &InodeGuard,
+        fst_flags: Fstflags,
+    ) -> Result<(), Errno> {
+        let explicit = fst_flags.intersects(Fstflags::SET_ATIM | Fstflags::SET_MTIM);
+        match self.check_owner(inode) {
+            Err(_) if !explicit => self.check_access(inode, ACCESS_WRITE),
+            result => result,
+        }
+    }
+
+    /// Checks that the process may give files another owner, which only a
+    /// privileged process may do while permissions are enforced
+    pub(crate) fn check_privileged(&self) -> Result<(), Errno> {
+        match &self.permissions {
+            Some(permissions) if !permissions.credentials.lock().unwrap().is_privileged() => {
+                Err(Errno::Perm)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes the process the owner of a file that it has just created
+    pub(crate) fn take_ownership(&self, inode: &InodeGuard, mode: u32) {
+        let Some(permissions) = &self.permissions else {
+            return;
+        };
+        let owner = {
+            let credentials = permissions.credentials.lock().unwrap();
+            FileOwner {
+                uid: credentials.euid,
+                gid: credentials.egid,
+                mode,
+            }
+        };
+        *inode.owner.write().unwrap() = Some(owner);
+    }
+
     /// Forking the WasiState is used when either fork or vfork is called
     pub fn fork(&self) -> Self {
         Self {
@@ -567,8 +766,11 @@ impl WasiFs {
             root_fs: self.root_fs.clone(),
             root_inode: self.root_inode.clone(),
             has_unioned: Mutex::new(self.has_unioned.lock().unwrap().clone()),
+            packages: Mutex::new(self.packages.lock().unwrap().clone()),
             init_preopens: self.init_preopens.clone(),
             init_vfs_preopens: self.init_vfs_preopens.clone(),
+            proc_self: AtomicU32::new(self.proc_self.load(Ordering::Acquire)),
+            permissions: self.permissions.clone(),
         }
     }
 
@@ -633,11 +835,19 @@ impl WasiFs {
     }
 
     /// Will conditionally union the binary file system with this one
-    /// if it has not already been unioned
+    /// if it has not already been unioned, the package is remembered
+    /// either way so that its volumes can be searched for libraries
     pub async fn conditional_union(
         &self,
         binary: &BinaryPackage,
     ) -> Result<(), virtual_fs::FsError> {
+        {
+            let mut packages = self.packages.lock().unwrap();
+            if !packages.iter().any(|pkg| pkg.id == binary.id) {
+                packages.push(binary.clone());
+            }
+        }
+
         let needs_to_be_unioned = self.has_unioned.lock().unwrap().insert(binary.id.clone());
 
         if !needs_to_be_unioned {
@@ -697,6 +907,7 @@ impl WasiFs {
             is_preopened: true,
             name: RwLock::new("/".into()),
             kind: RwLock::new(root_kind),
+            owner: RwLock::new(None),
         });
 
         let wasi_fs = Self {
@@ -707,8 +918,11 @@ impl WasiFs {
             root_fs: fs_backing,
             root_inode,
             has_unioned: Mutex::new(HashSet::new()),
+            packages: Mutex::new(Vec::new()),
             init_preopens: Default::default(),
             init_vfs_preopens: Default::default(),
+            proc_self: AtomicU32::new(0),
+            permissions: None,
         };
         wasi_fs.create_stdin(inodes);
         wasi_fs.create_stdout(inodes);
@@ -1048,6 +1262,9 @@ impl WasiFs {
                         ref parent,
                         ..
                     } => {
+                        // Looking anything up in a directory needs permission
+                        // to search it
+                        self.check_access(&processing_cur_inode, ACCESS_EXECUTE)?;
                         match component.as_os_str().to_string_lossy().borrow() {
                             ".." => {
                                 if let Some(p) = parent.upgrade() {
@@ -1100,16 +1317,25 @@ impl WasiFs {
                                 }
                             } else if file_type.is_symlink() {
                                 should_insert = false;
-                                let link_value =
-                                    self.root_fs.readlink(&file).ok().ok_or(Errno::Noent)?;
+                                let link_value = match self.proc_self.load(Ordering::Acquire) {
+                                    pid if pid != 0 && file == Path::new(PROC_SELF) => {
+                                        PathBuf::from(pid.to_string())
+                                    }
+                                    _ => self.root_fs.readlink(&file).ok().ok_or(Errno::Noent)?,
+                                };
                                 debug!("attempting to decompose path {:?}", link_value);
 
-                                let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(&file)?
-                                } else {
+                                let (pre_open_dir_fd, relative_path) =
+                                    self.path_into_pre_open_and_relative_path(&file)?;
+                                // Absolute links are only followed for the links of
+                                // `/proc`, and only relative to a pre-opened root
+                                if link_value.is_absolute()
+                                    && (!file.starts_with(PROC_ROOT)
+                                        || Path::new("/").join(relative_path) != file)
+                                {
                                     tracing::error!("Absolute symlinks are not yet supported");
                                     return Err(Errno::Notsup);
-                                };
+                                }
                                 loop_for_symlink = true;
                                 symlink_count += 1;
                                 Kind::Symlink {
@@ -1665,6 +1891,7 @@ impl WasiFs {
             is_preopened,
             name: RwLock::new(name),
             kind: RwLock::new(kind),
+            owner: RwLock::new(None),
         })
     }
 
@@ -1729,6 +1956,14 @@ impl WasiFs {
             idx,
             Some(__WASI_STDIN_FILENO) | Some(__WASI_STDOUT_FILENO) | Some(__WASI_STDERR_FILENO)
         );
+        // The group must not let another open in between the check and
+        // the insert
+        let group = self.resource_group();
+        let _reservation = group
+            .as_ref()
+            .map(|group| group.reserve_open_file())
+            .transpose()?;
+
         let fd = Fd {
             inner: FdInner {
                 rights,
@@ -1767,6 +2002,11 @@ impl WasiFs {
         cloexec: Option<bool>,
     ) -> Result<WasiFd, Errno> {
         let fd = self.get_fd(fd)?;
+        let group = self.resource_group();
+        let _reservation = group
+            .as_ref()
+            .map(|group| group.reserve_open_file())
+            .transpose()?;
         Ok(self.fd_map.write().unwrap().insert_first_free_after(
             Fd {
                 inner: FdInner {
@@ -2083,6 +2323,7 @@ impl WasiFs {
                 is_preopened: true,
                 name: RwLock::new(name.to_string().into()),
                 kind: RwLock::new(kind),
+                owner: RwLock::new(None),
             })
         };
         self.fd_map.write().unwrap().insert(
@@ -2280,6 +2521,8 @@ pub fn virtual_file_type_to_wasi_file_type(file_type: virtual_fs::FileType) -> F
         Filetype::RegularFile
     } else if file_type.is_symlink() {
         Filetype::SymbolicLink
+    } else if file_type.is_socket() {
+        Filetype::SocketStream
     } else {
         Filetype::Unknown
     }
@@ -2307,6 +2550,7 @@ pub fn fs_error_from_wasi_err(err: Errno) -> FsError {
         Errno::Again => FsError::WouldBlock,
         Errno::Nospc => FsError::WriteZero,
         Errno::Notempty => FsError::DirectoryNotEmpty,
+        Errno::Dquot => FsError::QuotaExceeded,
         _ => FsError::UnknownError,
     }
 }
@@ -2330,13 +2574,17 @@ pub fn fs_error_into_wasi_err(fs_error: FsError) -> Errno {
         FsError::NotAFile => Errno::Inval,
         FsError::NotConnected => Errno::Notconn,
         FsError::EntryNotFound => Errno::Noent,
+        // WASI has no `ENODATA`/`ENOATTR`, so a missing attribute is reported as `ENOENT`
+        FsError::NoAttribute => Errno::Noent,
+        FsError::OffsetOutOfRange => Errno::Nxio,
         FsError::PermissionDenied => Errno::Perm,
         FsError::TimedOut => Errno::Timedout,
         FsError::UnexpectedEof => Errno::Proto,
         FsError::WouldBlock => Errno::Again,
         FsError::WriteZero => Errno::Nospc,
         FsError::DirectoryNotEmpty => Errno::Notempty,
-        FsError::StorageFull => Errno::Overflow,
+        FsError::StorageFull => Errno::Nospc,
+        FsError::QuotaExceeded => Errno::Dquot,
         FsError::Lock | FsError::UnknownError => Errno::Io,
         FsError::Unsupported => Errno::Notsup,
     }
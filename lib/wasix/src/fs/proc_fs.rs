@@ -0,0 +1,559 @@
+//! A read-only file system that is mounted at `/proc` and describes the
+//! processes that are running.
+//!
+//! Nothing is stored, every entry is generated from the control plane and
+//! from the state of the processes at the time that it is looked at, which
+//! means that the contents of a file are a snapshot taken when it is opened.
+
+use std::{
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::future::BoxFuture;
+use virtual_fs::{
+    DirEntry, FileOpener, FileSystem, FileType, FsError, Metadata, OpenOptions, OpenOptionsConfig,
+    ReadDir, StaticFile, VirtualFile,
+};
+
+use super::Kind;
+use crate::{
+    os::task::{
+        control_plane::{WasiControlPlane, WasiControlPlaneHandle},
+        thread::current_thread_ids,
+    },
+    runtime::task_manager::VirtualTaskManager,
+    state::WasiState,
+    WasiProcess, WasiProcessId,
+};
+
+/// Path that the file system is mounted at in the WASIX root
+pub(crate) const PROC_ROOT: &str = "/proc";
+
+/// Path of the link that refers to the process that follows it, which only
+/// [`super::WasiFs`] can answer as it knows which process is asking
+pub(crate) const PROC_SELF: &str = "/proc/self";
+
+/// Linear memories that do not declare a maximum can grow to fill the
+/// 32-bit address space
+const DEFAULT_MEMORY_LIMIT: u64 = 1 << 32;
+
+/// How many times the file descriptors of a process are tried before
+/// giving up, as the process that reads them may be holding their lock
+const FD_MAP_ATTEMPTS: usize = 16;
+
+/// A read-only view of the running processes in the layout of `/proc`
+///
+/// - `/<pid>/cmdline` and `/<pid>/environ` hold the arguments and the
+///   environment variables, each terminated by a NUL
+/// - `/<pid>/status` holds the name, state, IDs, thread count and memory
+///   size of the process
+/// - `/<pid>/fd/` holds a link for every open file descriptor
+/// - `/<pid>/cwd` and `/<pid>/exe` link to the current directory and to the
+///   program
+/// - `/self` links to the directory of the process that follows it
+/// - `/meminfo` sums the linear memories of the processes
+/// - `/cpuinfo` lists one processor for each thread that can run in parallel
+///
+/// The environment, file descriptors, current directory and program of a
+/// process are only shown to the process itself, to the processes it
+/// descends from and to processes that may inspect its credentials (see
+/// [`Credentials::may_inspect`]). The host can see everything.
+///
+/// [`Credentials::may_inspect`]: crate::os::credentials::Credentials::may_inspect
+#[derive(Debug, Clone)]
+pub struct ProcFileSystem {
+    control_plane: WasiControlPlaneHandle,
+    tasks: Arc<dyn VirtualTaskManager>,
+}
+
+enum Entry {
+    Dir(Vec<String>),
+    File(Vec<u8>),
+    Symlink(PathBuf),
+    /// `/self`, which is resolved by [`super::WasiFs`]
+    SelfLink,
+}
+
+impl ProcFileSystem {
+    pub fn new(control_plane: WasiControlPlaneHandle, tasks: Arc<dyn VirtualTaskManager>) -> Self {
+        Self {
+            control_plane,
+            tasks,
+        }
+    }
+
+    fn lookup(&self, path: &Path) -> Result<Entry, FsError> {
+        let control_plane = self.control_plane.upgrade().ok_or(FsError::EntryNotFound)?;
+
+        let mut names = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::RootDir | Component::CurDir => {}
+                Component::Normal(name) => names.push(name.to_str().ok_or(FsError::InvalidInput)?),
+                _ => return Err(FsError::InvalidInput),
+            }
+        }
+
+        let (pid, names) = match names.as_slice() {
+            [] => {
+                let mut entries: Vec<String> = ["self", "cpuinfo", "meminfo"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                entries.extend(
+                    control_plane
+                        .processes()
+                        .iter()
+                        .map(|process| process.pid().to_string()),
+                );
+                return Ok(Entry::Dir(entries));
+            }
+            ["self"] => return Ok(Entry::SelfLink),
+            ["cpuinfo"] => return Ok(Entry::File(self.cpuinfo())),
+            ["meminfo"] => return Ok(Entry::File(meminfo(&control_plane.processes()))),
+            [pid, names @ ..] => (pid, names),
+        };
+
+        let pid = pid.parse::<u32>().map_err(|_| FsError::EntryNotFound)?;
+        let process = control_plane
+            .get_process(WasiProcessId::from(pid))
+            .filter(|process| process.try_join().is_none())
+            .ok_or(FsError::EntryNotFound)?;
+        if matches!(names, [name, ..] if PRIVATE_ENTRIES.contains(name))
+            && !may_inspect(&control_plane, &process)
+        {
+            return Err(FsError::PermissionDenied);
+        }
+        let state = process.state();
+
+        Ok(match (names, state) {
+            ([], _) => Entry::Dir(
+                ["cmdline", "cwd", "environ", "exe", "fd", "status"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+            (["status"], state) => Entry::File(status(&process, state.as_deref())),
+            (["cmdline"], Some(state)) => Entry::File(nul_terminated(
+                state.args.lock().unwrap().iter().map(|arg| arg.as_bytes()),
+            )),
+            (["environ"], Some(state)) => Entry::File(nul_terminated(
+                state.envs.lock().unwrap().iter().map(|env| env.as_slice()),
+            )),
+            (["cwd"], Some(state)) => {
+                Entry::Symlink(PathBuf::from(state.fs.current_dir.lock().unwrap().as_str()))
+            }
+            (["exe"], Some(state)) => Entry::Symlink(exe(&state)),
+            (["fd"], Some(state)) => Entry::Dir(
+                fds(&state)?
+                    .into_iter()
+                    .map(|(fd, _)| fd.to_string())
+                    .collect(),
+            ),
+            (["fd", fd], Some(state)) => fds(&state)?
+                .into_iter()
+                .find(|(n, _)| n.to_string() == *fd)
+                .map(|(_, target)| Entry::Symlink(target))
+                .ok_or(FsError::EntryNotFound)?,
+            _ => return Err(FsError::EntryNotFound),
+        })
+    }
+
+    fn cpuinfo(&self) -> Vec<u8> {
+        let cpus = self.tasks.thread_parallelism().unwrap_or(1).max(1);
+        let mut ret = String::new();
+        for n in 0..cpus {
+            let _ = write!(
+                ret,
+                "processor\t: {n}\nvendor_id\t: Wasmer\nmodel name\t: WebAssembly\n\n"
+            );
+        }
+        ret.into_bytes()
+    }
+}
+
+/// Entries of a process that only those who may inspect it can see
+const PRIVATE_ENTRIES: [&str; 4] = ["cwd", "environ", "exe", "fd"];
+
+/// Returns true if the process that runs on the calling host thread (if any)
+/// may look into the private entries of `process`
+fn may_inspect(control_plane: &WasiControlPlane, process: &WasiProcess) -> bool {
+    let Some((caller, _)) = current_thread_ids() else {
+        return true;
+    };
+
+    if process.descends_from(caller) {
+        return true;
+    }
+
+    let credentials = |process: &WasiProcess| {
+        process
+            .state()
+            .map(|state| state.credentials.lock().unwrap().clone())
+    };
+    match (
+        control_plane
+            .get_process(caller)
+            .and_then(|c| credentials(&c)),
+        credentials(process),
+    ) {
+        (Some(caller), Some(target)) => caller.may_inspect(&target),
+        _ => false,
+    }
+}
+
+fn nul_terminated<'a>(items: impl Iterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut ret = Vec::new();
+    for item in items {
+        ret.extend_from_slice(item);
+        ret.push(0);
+    }
+    ret
+}
+
+fn status(process: &WasiProcess, state: Option<&WasiState>) -> Vec<u8> {
+    let name = state
+        .and_then(|state| state.args.lock().unwrap().first().cloned())
+        .map(|arg| match arg.rsplit_once('/') {
+            Some((_, name)) => name.to_string(),
+            None => arg,
+        })
+        .unwrap_or_default();
+    let run_state = if process.is_stopped() {
+        "T (stopped)"
+    } else {
+        "R (running)"
+    };
+    let (memory_size, _) = process.memory_size();
+    format!(
+        "Name:\t{name}\nState:\t{run_state}\nTgid:\t{pid}\nPid:\t{pid}\nPPid:\t{ppid}\n\
+         Threads:\t{threads}\nVmSize:\t{kb:>8} kB\nVmRSS:\t{kb:>8} kB\n",
+        pid = process.pid(),
+        ppid = process.ppid(),
+        threads = process.active_threads(),
+        kb = memory_size / 1024,
+    )
+    .into_bytes()
+}
+
+fn meminfo(processes: &[WasiProcess]) -> Vec<u8> {
+    let (used, total) = processes
+        .iter()
+        .map(|process| process.memory_size())
+        .fold((0u64, 0u64), |(used, total), (size, limit)| {
+            (used + size, total.max(limit))
+        });
+    let total = if total == 0 {
+        DEFAULT_MEMORY_LIMIT
+    } else {
+        total
+    };
+    let free = total.saturating_sub(used);
+
+    let mut ret = String::new();
+    for (name, value) in [
+        ("MemTotal:", total),
+        ("MemFree:", free),
+        ("MemAvailable:", free),
+    ] {
+        let _ = writeln!(ret, "{name:<16}{:>8} kB", value / 1024);
+    }
+    ret.into_bytes()
+}
+
+/// The program of a process, which is found in `/bin` unless it was run
+/// with a path
+fn exe(state: &WasiState) -> PathBuf {
+    let arg0 = state
+        .args
+        .lock()
+        .unwrap()
+        .first()
+        .cloned()
+        .unwrap_or_default();
+    if arg0.starts_with('/') {
+        PathBuf::from(arg0)
+    } else if arg0.contains('/') {
+        PathBuf::from(state.fs.current_dir.lock().unwrap().as_str()).join(arg0)
+    } else {
+        Path::new("/bin").join(arg0)
+    }
+}
+
+/// The open file descriptors of a process and what they refer to
+///
+/// A process that reads its own file descriptors may do so while it holds
+/// the lock on them, so the lock is only tried rather than waited on.
+fn fds(state: &WasiState) -> Result<Vec<(u32, PathBuf)>, FsError> {
+    let fd_map = (0..FD_MAP_ATTEMPTS)
+        .find_map(|_| {
+            let fd_map = state.fs.fd_map.try_read().ok();
+            if fd_map.is_none() {
+                std::thread::yield_now();
+            }
+            fd_map
+        })
+        .ok_or(FsError::Lock)?;
+    Ok(fd_map
+        .iter()
+        .map(|(fd, entry)| {
+            let inode = &entry.inode;
+            let ino = inode.ino().as_u64();
+            let name = || {
+                inode
+                    .name
+                    .try_read()
+                    .map(|name| name.to_string())
+                    .unwrap_or_default()
+            };
+            // The inode may be locked by the caller while it resolves a
+            // path, in which case all that can be shown is its name
+            let target = match inode.kind.try_read().as_deref() {
+                Ok(Kind::File { path, .. }) if path.as_os_str().is_empty() => {
+                    format!("/dev/{}", name())
+                }
+                Ok(Kind::File { path, .. }) | Ok(Kind::Dir { path, .. }) => {
+                    path.to_string_lossy().into_owned()
+                }
+                Ok(Kind::Root { .. }) => "/".to_string(),
+                Ok(Kind::Socket { .. }) => format!("socket:[{ino}]"),
+                Ok(Kind::PipeTx { .. } | Kind::PipeRx { .. } | Kind::DuplexPipe { .. }) => {
+                    format!("pipe:[{ino}]")
+                }
+                Ok(Kind::Epoll { .. }) => "anon_inode:[eventpoll]".to_string(),
+                Ok(Kind::EventNotifications { .. }) => "anon_inode:[eventfd]".to_string(),
+                Ok(Kind::Symlink { .. } | Kind::Buffer { .. }) | Err(_) => name(),
+            };
+            (fd, PathBuf::from(target))
+        })
+        .collect())
+}
+
+impl Entry {
+    fn metadata(&self) -> Metadata {
+        let (ft, len) = match self {
+            Entry::Dir(_) => (FileType::new_dir(), 0),
+            Entry::File(data) => (FileType::new_file(), data.len() as u64),
+            Entry::Symlink(target) => (
+                FileType {
+                    symlink: true,
+                    ..Default::default()
+                },
+                target.as_os_str().len() as u64,
+            ),
+            Entry::SelfLink => (
+                FileType {
+                    symlink: true,
+                    ..Default::default()
+                },
+                0,
+            ),
+        };
+        Metadata {
+            ft,
+            accessed: 0,
+            created: 0,
+            modified: 0,
+            len,
+        }
+    }
+}
+
+impl FileSystem for ProcFileSystem {
+    fn readlink(&self, path: &Path) -> virtual_fs::Result<PathBuf> {
+        match self.lookup(path)? {
+            Entry::Symlink(target) => Ok(target),
+            // Only the process that follows the link knows where it goes
+            Entry::SelfLink => Err(FsError::Unsupported),
+            _ => Err(FsError::InvalidInput),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> virtual_fs::Result<ReadDir> {
+        let Entry::Dir(names) = self.lookup(path)? else {
+            return Err(FsError::BaseNotDirectory);
+        };
+        let entries = names
+            .into_iter()
+            .map(|name| {
+                let path = path.join(name);
+                let metadata = self.lookup(&path).map(|entry| entry.metadata());
+                DirEntry { path, metadata }
+            })
+            .collect();
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename<'a>(
+        &'a self,
+        _from: &'a Path,
+        _to: &'a Path,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(async { Err(FsError::PermissionDenied) })
+    }
+
+    fn metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        self.lookup(path).map(|entry| entry.metadata())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    fn remove_file(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        _name: String,
+        _path: &Path,
+        _fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> virtual_fs::Result<()> {
+        Err(FsError::Unsupported)
+    }
+}
+
+impl FileOpener for ProcFileSystem {
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> virtual_fs::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        if conf.would_mutate() {
+            return Err(FsError::PermissionDenied);
+        }
+        match self.lookup(path)? {
+            Entry::File(data) => Ok(Box::new(StaticFile::new(data))),
+            _ => Err(FsError::NotAFile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::{
+        os::task::{
+            control_plane::ControlPlaneConfig,
+            thread::{CurrentThreadGuard, WasiThreadId},
+        },
+        runtime::task_manager::tokio::TokioTaskManager,
+        utils::xxhash_random,
+        WasiEnvBuilder,
+    };
+
+    fn read(fs: &ProcFileSystem, path: &str) -> String {
+        let mut file = fs.new_open_options().read(true).open(path).unwrap();
+        let mut ret = String::new();
+        futures::executor::block_on(file.read_to_string(&mut ret)).unwrap();
+        ret
+    }
+
+    #[tokio::test]
+    async fn proc_lists_running_processes() {
+        let control_plane = WasiControlPlane::new(ControlPlaneConfig::default());
+        let tasks = Arc::new(TokioTaskManager::new(tokio::runtime::Handle::current()));
+        let fs = ProcFileSystem::new(control_plane.handle(), tasks);
+
+        let process = control_plane.new_process(xxhash_random()).unwrap();
+        process.set_memory_size(2 << 20, 4 << 20);
+
+        let names: Vec<_> = fs
+            .read_dir(Path::new("/"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path.to_string_lossy().into_owned())
+            .collect();
+        assert!(names.contains(&format!("/{}", process.pid())));
+        assert!(fs.metadata(Path::new("/self")).unwrap().ft.is_symlink());
+
+        let status = read(&fs, &format!("/{}/status", process.pid()));
+        assert!(status.contains(&format!("Pid:\t{}\n", process.pid())));
+        assert!(status.contains("VmSize:\t    2048 kB\n"));
+
+        let meminfo = read(&fs, "/meminfo");
+        assert!(meminfo.starts_with("MemTotal:           4096 kB\nMemFree:            2048 kB\n"));
+        assert!(read(&fs, "/cpuinfo").starts_with("processor\t: 0\n"));
+
+        // Nothing can be written
+        assert_eq!(
+            fs.new_open_options()
+                .write(true)
+                .open("/meminfo")
+                .unwrap_err(),
+            FsError::PermissionDenied
+        );
+        assert_eq!(
+            fs.metadata(Path::new("/12345")).unwrap_err(),
+            FsError::EntryNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn proc_hides_private_entries_from_other_processes() {
+        let control_plane = WasiControlPlane::new(ControlPlaneConfig::default());
+        let tasks = Arc::new(TokioTaskManager::new(tokio::runtime::Handle::current()));
+        let fs = ProcFileSystem::new(control_plane.handle(), tasks);
+
+        let parent = control_plane.new_process(xxhash_random()).unwrap();
+        let child = control_plane.new_process(xxhash_random()).unwrap();
+        parent.add_child(child.clone());
+        let other = control_plane.new_process(xxhash_random()).unwrap();
+
+        let init = WasiEnvBuilder::new("child")
+            .env("SECRET", "1")
+            .build_init()
+            .unwrap();
+        let state = Arc::new(init.state);
+        child.set_state(&state);
+
+        let environ = format!("/{}/environ", child.pid());
+        let fd = format!("/{}/fd", child.pid());
+        let as_process = |process: &WasiProcess| {
+            CurrentThreadGuard::enter(process.pid(), WasiThreadId::from(1), &process.usage)
+        };
+
+        // The host, the process itself and its parent may look
+        assert!(read(&fs, &environ).contains("SECRET=1\0"));
+        {
+            let _current = as_process(&child);
+            assert!(read(&fs, &environ).contains("SECRET=1\0"));
+            assert!(fs.read_dir(Path::new(&fd)).is_ok());
+
+            // Reading its own file descriptors while it holds their lock
+            // fails rather than deadlocking
+            let _fd_map = state.fs.fd_map.write().unwrap();
+            assert_eq!(fs.read_dir(Path::new(&fd)).unwrap_err(), FsError::Lock);
+        }
+        {
+            let _current = as_process(&parent);
+            assert!(read(&fs, &environ).contains("SECRET=1\0"));
+        }
+
+        // Other processes only see the public entries
+        let _current = as_process(&other);
+        for path in [&environ, &fd] {
+            assert_eq!(
+                fs.metadata(Path::new(path)).unwrap_err(),
+                FsError::PermissionDenied
+            );
+        }
+        assert!(read(&fs, &format!("/{}/status", child.pid())).contains("Name:\tchild\n"));
+    }
+}
@@ -0,0 +1,203 @@
+//! The file system that is mounted at `/dev/pts` and holds the PTYs of a
+//! control plane.
+//!
+//! Opening `ptmx` allocates a new PTY and returns its master side, the
+//! slave side of every PTY whose master is still open is listed by its
+//! index.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::future::BoxFuture;
+use virtual_fs::{
+    DirEntry, FileOpener, FileSystem, FileType, FsError, Metadata, OpenOptions, OpenOptionsConfig,
+    ReadDir, VirtualFile,
+};
+
+use crate::os::pty::PtyRegistry;
+
+/// Name of the PTY multiplexer in the file system
+pub const PTMX: &str = "ptmx";
+
+/// The PTYs of a control plane in the layout of `/dev/pts`
+#[derive(Debug, Clone)]
+pub struct PtsFileSystem {
+    registry: Arc<PtyRegistry>,
+}
+
+enum Entry {
+    Root,
+    Ptmx,
+    Slave(u32),
+}
+
+impl PtsFileSystem {
+    pub fn new(registry: Arc<PtyRegistry>) -> Self {
+        Self { registry }
+    }
+
+    fn lookup(&self, path: &Path) -> Result<Entry, FsError> {
+        let mut names = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::RootDir | Component::CurDir => {}
+                Component::Normal(name) => names.push(name.to_str().ok_or(FsError::InvalidInput)?),
+                _ => return Err(FsError::InvalidInput),
+            }
+        }
+
+        match names.as_slice() {
+            [] => Ok(Entry::Root),
+            [PTMX] => Ok(Entry::Ptmx),
+            [index] => index
+                .parse::<u32>()
+                .ok()
+                .filter(|index| self.registry.get(*index).is_some())
+                .map(Entry::Slave)
+                .ok_or(FsError::EntryNotFound),
+            _ => Err(FsError::EntryNotFound),
+        }
+    }
+}
+
+impl Entry {
+    fn metadata(&self) -> Metadata {
+        let ft = match self {
+            Entry::Root => FileType::new_dir(),
+            Entry::Ptmx | Entry::Slave(_) => FileType {
+                char_device: true,
+                ..Default::default()
+            },
+        };
+        Metadata {
+            ft,
+            accessed: 0,
+            created: 0,
+            modified: 0,
+            len: 0,
+        }
+    }
+}
+
+impl FileSystem for PtsFileSystem {
+    fn readlink(&self, _path: &Path) -> virtual_fs::Result<PathBuf> {
+        Err(FsError::InvalidInput)
+    }
+
+    fn read_dir(&self, path: &Path) -> virtual_fs::Result<ReadDir> {
+        let Entry::Root = self.lookup(path)? else {
+            return Err(FsError::BaseNotDirectory);
+        };
+        let entries = std::iter::once(PTMX.to_string())
+            .chain(self.registry.indices().into_iter().map(|n| n.to_string()))
+            .map(|name| {
+                let path = path.join(name);
+                let metadata = self.lookup(&path).map(|entry| entry.metadata());
+                DirEntry { path, metadata }
+            })
+            .collect();
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename<'a>(
+        &'a self,
+        _from: &'a Path,
+        _to: &'a Path,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(async { Err(FsError::PermissionDenied) })
+    }
+
+    fn metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        self.lookup(path).map(|entry| entry.metadata())
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    fn remove_file(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        _name: String,
+        _path: &Path,
+        _fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> virtual_fs::Result<()> {
+        Err(FsError::Unsupported)
+    }
+}
+
+impl FileOpener for PtsFileSystem {
+    fn open(
+        &self,
+        path: &Path,
+        _conf: &OpenOptionsConfig,
+    ) -> virtual_fs::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        match self.lookup(path)? {
+            Entry::Root => Err(FsError::NotAFile),
+            Entry::Ptmx => Ok(Box::new(
+                self.registry.allocate().map_err(|_| FsError::StorageFull)?,
+            )),
+            Entry::Slave(index) => {
+                let pty = self.registry.get(index).ok_or(FsError::EntryNotFound)?;
+                Ok(Box::new(pty.open_slave()?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::os::pty::pty_of_file;
+
+    #[tokio::test]
+    async fn pts_opens_both_sides() {
+        let fs = PtsFileSystem::new(Arc::new(PtyRegistry::new()));
+        let mut master = fs.new_open_options().read(true).open("/ptmx").unwrap();
+        let index = pty_of_file(master.as_ref()).unwrap().index();
+
+        let names: Vec<_> = fs
+            .read_dir(Path::new("/"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["/ptmx".to_string(), format!("/{index}")]);
+        assert!(fs.metadata(Path::new("/ptmx")).unwrap().ft.is_char_device());
+
+        let mut slave = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .open(format!("/{index}"))
+            .unwrap();
+        slave.write_all(b"hi").await.unwrap();
+        let mut buf = [0u8; 2];
+        master.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hi");
+
+        drop(master);
+        assert_eq!(
+            fs.metadata(Path::new(&format!("/{index}"))).unwrap_err(),
+            FsError::EntryNotFound
+        );
+    }
+}
@@ -0,0 +1,785 @@
+//! The file system that is mounted at `/dev/shm` and holds the POSIX shared
+//! memory objects of the processes of a control plane.
+//!
+//! Every object is a [`VMSharedMemory`] that maps a host file, and mapping
+//! an object with `fd_mmap` maps the same file over the range of linear
+//! memory, so all the processes that map an object share its pages rather
+//! than copies of them.
+//!
+//! The number of objects and their total size are limited by [`ShmLimits`],
+//! and their size is also charged to a
+//! [`FsMemoryLimiter`](virtual_fs::limiter::FsMemoryLimiter).
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, SeekFrom},
+    os::fd::AsRawFd,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tempfile::TempPath;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+use virtual_fs::{
+    limiter::DynFsMemoryLimiter, DirEntry, FileOpener, FileSystem, FileType, FsError, Metadata,
+    OpenOptions, OpenOptionsConfig, ReadDir, VirtualFile,
+};
+use wasmer::{MemoryType, Pages, WASM_PAGE_SIZE};
+use wasmer_vm::{LinearMemory, MemoryStyle, MmapType, VMSharedMemory};
+use wasmer_wasix_types::wasi::Snapshot0Clockid;
+
+use crate::{os::ipc::ShmLimits, syscalls::platform_clock_time_get};
+
+/// Largest size that a single shared memory object can grow to
+pub const SHM_MAX_SIZE: u64 = 1 << 30;
+
+/// Size of the pages of the host, which mappings of shared memory objects
+/// must be aligned to
+pub fn shm_page_size() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 }
+}
+
+fn now() -> u64 {
+    platform_clock_time_get(Snapshot0Clockid::Realtime, 1_000)
+        .map(|time| time as u64)
+        .unwrap_or_default()
+}
+
+/// Number of objects and bytes used by the objects of a [`ShmFileSystem`]
+#[derive(Debug)]
+struct ShmUsage {
+    limits: ShmLimits,
+    limiter: Option<DynFsMemoryLimiter>,
+    objects: AtomicUsize,
+    bytes: AtomicU64,
+}
+
+impl ShmUsage {
+    fn add_object(&self) -> virtual_fs::Result<()> {
+        self.objects
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |objects| {
+                (objects < self.limits.max_objects).then_some(objects + 1)
+            })
+            .map_err(|_| FsError::StorageFull)?;
+        Ok(())
+    }
+
+    fn remove_object(&self) {
+        self.objects.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    fn charge(&self, bytes: u64) -> virtual_fs::Result<()> {
+        self.bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes)
+                    .filter(|used| *used <= self.limits.max_bytes)
+            })
+            .map_err(|_| FsError::StorageFull)?;
+        if let Some(limiter) = &self.limiter {
+            if let Err(err) = limiter.on_grow(bytes as usize) {
+                self.bytes.fetch_sub(bytes, Ordering::AcqRel);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn release(&self, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+        self.bytes.fetch_sub(bytes, Ordering::AcqRel);
+        if let Some(limiter) = &self.limiter {
+            limiter.on_shrink(bytes as usize);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ObjectState {
+    memory: VMSharedMemory,
+    /// Address of the memory, which only moves when the memory is mapped
+    /// again to make room for the object to grow
+    base: usize,
+    /// Size of the object, the memory covers it in whole pages
+    len: u64,
+    modified: u64,
+}
+
+/// Maps the host file of an object with room for `pages`, which is only
+/// as much as the object needs as the memory can not grow in place
+fn map_object(path: &Path, pages: u32) -> io::Result<(VMSharedMemory, usize)> {
+    let memory = VMSharedMemory::new_with_file(
+        &MemoryType::new(pages, Some(pages), true),
+        &MemoryStyle::Static {
+            bound: Pages(pages),
+            offset_guard_size: 0,
+        },
+        path.to_path_buf(),
+        MmapType::Shared,
+    )
+    .map_err(io::Error::other)?;
+
+    // The size that was accessible is only read while mapping the file
+    std::fs::remove_file(path.with_extension("accessible")).ok();
+
+    let base = unsafe { memory.vmmemory().as_ref().base } as usize;
+    Ok((memory, base))
+}
+
+/// A POSIX shared memory object
+#[derive(Debug)]
+pub struct SharedMemoryObject {
+    state: Mutex<ObjectState>,
+    /// Host file that the memory maps, which is mapped again for every
+    /// mapping of the object
+    file: File,
+    /// Name of the host file, which is removed with the object
+    path: TempPath,
+    usage: Arc<ShmUsage>,
+    created: u64,
+}
+
+impl SharedMemoryObject {
+    fn new(usage: Arc<ShmUsage>) -> virtual_fs::Result<Self> {
+        usage.add_object()?;
+        let mapped = tempfile::Builder::new()
+            .prefix("wasix-shm-")
+            .tempfile()
+            .and_then(|file| {
+                let (file, path) = file.into_parts();
+                Ok((file, map_object(&path, 0)?, path))
+            });
+        let (file, (memory, base), path) = match mapped {
+            Ok(mapped) => mapped,
+            Err(err) => {
+                usage.remove_object();
+                return Err(err.into());
+            }
+        };
+
+        let created = now();
+        Ok(Self {
+            state: Mutex::new(ObjectState {
+                memory,
+                base,
+                len: 0,
+                modified: created,
+            }),
+            file,
+            path,
+            usage,
+            created,
+        })
+    }
+
+    /// Size of the object in bytes
+    pub fn len(&self) -> u64 {
+        self.state.lock().unwrap().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn set_len_locked(&self, state: &mut ObjectState, len: u64) -> virtual_fs::Result<()> {
+        if len > SHM_MAX_SIZE {
+            return Err(FsError::StorageFull);
+        }
+        if len > state.len {
+            self.usage.charge(len - state.len)?;
+        }
+
+        let pages = len.div_ceil(WASM_PAGE_SIZE as u64) as u32;
+        if pages > state.memory.size().0 {
+            match map_object(&self.path, pages) {
+                Ok((memory, base)) => {
+                    state.memory = memory;
+                    state.base = base;
+                }
+                Err(err) => {
+                    tracing::debug!(%err, "unable to grow a shared memory object");
+                    self.usage.release(len - state.len);
+                    return Err(FsError::StorageFull);
+                }
+            }
+        }
+
+        // Bytes past the end read as zero when the object grows again
+        if len < state.len {
+            unsafe {
+                std::ptr::write_bytes(
+                    (state.base + len as usize) as *mut u8,
+                    0,
+                    (state.len - len) as usize,
+                );
+            }
+            self.usage.release(state.len - len);
+        }
+        state.len = len;
+        state.modified = now();
+        Ok(())
+    }
+
+    /// Changes the size of the object, bytes that are added read as zero
+    pub fn set_len(&self, len: u64) -> virtual_fs::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        self.set_len_locked(&mut state, len)
+    }
+
+    /// Reads from the object at `offset`, returning the number of bytes
+    /// that were read
+    pub fn read_at(&self, buf: &mut [u8], offset: u64) -> usize {
+        let state = self.state.lock().unwrap();
+        if offset >= state.len {
+            return 0;
+        }
+        let amt = ((state.len - offset) as usize).min(buf.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                (state.base + offset as usize) as *const u8,
+                buf.as_mut_ptr(),
+                amt,
+            );
+        }
+        amt
+    }
+
+    /// Writes to the object at `offset`, growing it when the write goes
+    /// past its end
+    pub fn write_at(&self, data: &[u8], offset: u64) -> virtual_fs::Result<()> {
+        let end = offset
+            .checked_add(data.len() as u64)
+            .ok_or(FsError::StorageFull)?;
+        let mut state = self.state.lock().unwrap();
+        if end > state.len {
+            self.set_len_locked(&mut state, end)?;
+        }
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                (state.base + offset as usize) as *mut u8,
+                data.len(),
+            );
+        }
+        state.modified = now();
+        Ok(())
+    }
+
+    /// Maps `len` bytes of the object from `offset` over the memory at
+    /// `addr`, changes made through a `shared` mapping are seen by every
+    /// other mapping and the others are copy-on-write
+    ///
+    /// # Safety
+    /// - `addr`, `offset` and `len` must be aligned to [`shm_page_size`]
+    /// - the range must be part of a memory that the caller owns and that
+    ///   does not move, as the pages that were there are replaced
+    pub unsafe fn map_into(
+        &self,
+        addr: *mut u8,
+        offset: u64,
+        len: u64,
+        shared: bool,
+    ) -> io::Result<()> {
+        if offset.saturating_add(len) > SHM_MAX_SIZE {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let flags = libc::MAP_FIXED
+            | if shared {
+                libc::MAP_SHARED
+            } else {
+                libc::MAP_PRIVATE
+            };
+        let ptr = libc::mmap(
+            addr as *mut libc::c_void,
+            len as usize,
+            libc::PROT_READ | libc::PROT_WRITE,
+            flags,
+            self.file.as_raw_fd(),
+            offset as libc::off_t,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SharedMemoryObject {
+    fn drop(&mut self) {
+        let len = self.state.get_mut().map(|state| state.len).unwrap_or(0);
+        self.usage.release(len);
+        self.usage.remove_object();
+    }
+}
+
+/// Replaces the pages at `addr` that were mapped with
+/// [`SharedMemoryObject::map_into`] with pages of zeros that belong to the
+/// memory again
+///
+/// # Safety
+/// - `addr` and `len` must be aligned to [`shm_page_size`]
+/// - the range must be part of a memory that the caller owns
+pub unsafe fn shm_unmap(addr: *mut u8, len: u64) -> io::Result<()> {
+    let ptr = libc::mmap(
+        addr as *mut libc::c_void,
+        len as usize,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_FIXED | libc::MAP_PRIVATE | libc::MAP_ANON,
+        -1,
+        0,
+    );
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// A shared memory object that has been opened
+#[derive(Debug)]
+pub struct ShmFile {
+    object: Arc<SharedMemoryObject>,
+    cursor: u64,
+    writable: bool,
+    append: bool,
+}
+
+impl ShmFile {
+    /// The object that the file refers to
+    pub fn object(&self) -> &Arc<SharedMemoryObject> {
+        &self.object
+    }
+}
+
+impl AsyncRead for ShmFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let amt = self.object.read_at(buf.initialize_unfilled(), self.cursor);
+        buf.advance(amt);
+        self.cursor += amt as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ShmFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.writable {
+            return Poll::Ready(Err(io::ErrorKind::PermissionDenied.into()));
+        }
+        if self.append {
+            self.cursor = self.object.len();
+        }
+        self.object.write_at(buf, self.cursor)?;
+        self.cursor += buf.len() as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for ShmFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let cursor = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.object.len().checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.cursor.checked_add_signed(delta),
+        };
+        self.cursor = cursor.ok_or(io::ErrorKind::InvalidInput)?;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.cursor))
+    }
+}
+
+impl VirtualFile for ShmFile {
+    fn last_accessed(&self) -> u64 {
+        self.last_modified()
+    }
+
+    fn last_modified(&self) -> u64 {
+        self.object.state.lock().unwrap().modified
+    }
+
+    fn created_time(&self) -> u64 {
+        self.object.created
+    }
+
+    fn size(&self) -> u64 {
+        self.object.len()
+    }
+
+    fn set_len(&mut self, new_size: u64) -> virtual_fs::Result<()> {
+        if !self.writable {
+            return Err(FsError::PermissionDenied);
+        }
+        self.object.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> virtual_fs::Result<()> {
+        Ok(())
+    }
+
+    fn read_at<'a>(
+        &'a mut self,
+        buf: &'a mut [u8],
+        offset: u64,
+    ) -> BoxFuture<'a, io::Result<usize>> {
+        let amt = self.object.read_at(buf, offset);
+        Box::pin(async move { Ok(amt) })
+    }
+
+    fn write_all_at<'a>(&'a mut self, buf: &'a [u8], offset: u64) -> BoxFuture<'a, io::Result<()>> {
+        let res = if self.writable {
+            self.object.write_at(buf, offset).map_err(Into::into)
+        } else {
+            Err(io::ErrorKind::PermissionDenied.into())
+        };
+        Box::pin(async move { res })
+    }
+
+    fn poll_read_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(self.object.len().saturating_sub(self.cursor) as usize))
+    }
+
+    fn poll_write_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(8192))
+    }
+}
+
+/// A flat directory of [`SharedMemoryObject`]s, where a name that has been
+/// removed stays valid for the files and mappings that still refer to it
+#[derive(Debug, Clone)]
+pub struct ShmFileSystem {
+    objects: Arc<RwLock<BTreeMap<String, Arc<SharedMemoryObject>>>>,
+    usage: Arc<ShmUsage>,
+}
+
+impl Default for ShmFileSystem {
+    fn default() -> Self {
+        Self::new(ShmLimits::default(), None)
+    }
+}
+
+enum Entry<'a> {
+    Root,
+    Object(&'a str),
+}
+
+fn lookup(path: &Path) -> virtual_fs::Result<Entry<'_>> {
+    let mut names = path
+        .components()
+        .filter(|c| !matches!(c, Component::RootDir | Component::CurDir));
+    match (names.next(), names.next()) {
+        (None, _) => Ok(Entry::Root),
+        (Some(Component::Normal(name)), None) => name
+            .to_str()
+            .map(Entry::Object)
+            .ok_or(FsError::InvalidInput),
+        _ => Err(FsError::EntryNotFound),
+    }
+}
+
+impl ShmFileSystem {
+    /// Creates a file system whose objects are subject to `limits`, and
+    /// whose bytes are also charged to `limiter`
+    pub fn new(limits: ShmLimits, limiter: Option<DynFsMemoryLimiter>) -> Self {
+        Self {
+            objects: Default::default(),
+            usage: Arc::new(ShmUsage {
+                limits,
+                limiter,
+                objects: AtomicUsize::new(0),
+                bytes: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    fn object(&self, path: &Path) -> virtual_fs::Result<Arc<SharedMemoryObject>> {
+        match lookup(path)? {
+            Entry::Root => Err(FsError::NotAFile),
+            Entry::Object(name) => self
+                .objects
+                .read()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+}
+
+fn object_metadata(object: &SharedMemoryObject) -> Metadata {
+    let state = object.state.lock().unwrap();
+    Metadata {
+        ft: FileType::new_file(),
+        accessed: state.modified,
+        created: object.created,
+        modified: state.modified,
+        len: state.len,
+    }
+}
+
+impl FileSystem for ShmFileSystem {
+    fn readlink(&self, _path: &Path) -> virtual_fs::Result<PathBuf> {
+        Err(FsError::InvalidInput)
+    }
+
+    fn read_dir(&self, path: &Path) -> virtual_fs::Result<ReadDir> {
+        let Entry::Root = lookup(path)? else {
+            return Err(FsError::BaseNotDirectory);
+        };
+        let entries = self
+            .objects
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, object)| DirEntry {
+                path: path.join(name),
+                metadata: Ok(object_metadata(object)),
+            })
+            .collect();
+        Ok(ReadDir::new(entries))
+    }
+
+    fn create_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn remove_dir(&self, _path: &Path) -> virtual_fs::Result<()> {
+        Err(FsError::PermissionDenied)
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(async move {
+            let (Entry::Object(from), Entry::Object(to)) = (lookup(from)?, lookup(to)?) else {
+                return Err(FsError::PermissionDenied);
+            };
+            let mut objects = self.objects.write().unwrap();
+            let object = objects.remove(from).ok_or(FsError::EntryNotFound)?;
+            objects.insert(to.to_string(), object);
+            Ok(())
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        match lookup(path)? {
+            Entry::Root => Ok(Metadata {
+                ft: FileType::new_dir(),
+                ..Default::default()
+            }),
+            Entry::Object(_) => Ok(object_metadata(&*self.object(path)?)),
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> virtual_fs::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> virtual_fs::Result<()> {
+        let Entry::Object(name) = lookup(path)? else {
+            return Err(FsError::NotAFile);
+        };
+        self.objects
+            .write()
+            .unwrap()
+            .remove(name)
+            .map(|_| ())
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn new_open_options(&self) -> OpenOptions {
+        OpenOptions::new(self)
+    }
+
+    fn mount(
+        &self,
+        _name: String,
+        _path: &Path,
+        _fs: Box<dyn FileSystem + Send + Sync>,
+    ) -> virtual_fs::Result<()> {
+        Err(FsError::Unsupported)
+    }
+}
+
+impl FileOpener for ShmFileSystem {
+    fn open(
+        &self,
+        path: &Path,
+        conf: &OpenOptionsConfig,
+    ) -> virtual_fs::Result<Box<dyn VirtualFile + Send + Sync + 'static>> {
+        let Entry::Object(name) = lookup(path)? else {
+            return Err(FsError::NotAFile);
+        };
+        let writable = conf.write() || conf.append();
+        if conf.truncate() && !writable {
+            return Err(FsError::PermissionDenied);
+        }
+
+        let object = {
+            let mut objects = self.objects.write().unwrap();
+            match objects.get(name) {
+                Some(_) if conf.create_new() => return Err(FsError::AlreadyExists),
+                Some(object) => object.clone(),
+                None if conf.create() || conf.create_new() => {
+                    let object = Arc::new(SharedMemoryObject::new(self.usage.clone())?);
+                    objects.insert(name.to_string(), object.clone());
+                    object
+                }
+                None => return Err(FsError::EntryNotFound),
+            }
+        };
+        if conf.truncate() {
+            object.set_len(0)?;
+        }
+
+        Ok(Box::new(ShmFile {
+            object,
+            cursor: 0,
+            writable,
+            append: conf.append(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::task::resource_group::{ResourceGroup, ResourceLimits};
+
+    #[test]
+    fn shm_objects_are_shared_between_opens() {
+        let fs = ShmFileSystem::default();
+        let mut a = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open("/obj")
+            .unwrap();
+        a.set_len(10).unwrap();
+        futures::executor::block_on(a.write_all_at(b"hello", 2)).unwrap();
+
+        let mut b = fs.new_open_options().read(true).open("/obj").unwrap();
+        let mut buf = [0u8; 16];
+        let amt = futures::executor::block_on(b.read_at(&mut buf, 0)).unwrap();
+        assert_eq!(&buf[..amt], b"\0\0hello\0\0\0");
+        assert_eq!(b.set_len(0), Err(FsError::PermissionDenied));
+
+        // Shrinking and growing again leaves zeros behind
+        a.set_len(4).unwrap();
+        a.set_len(8).unwrap();
+        let amt = futures::executor::block_on(b.read_at(&mut buf, 0)).unwrap();
+        assert_eq!(&buf[..amt], b"\0\0he\0\0\0\0");
+
+        // The name goes away but the object stays while it is open
+        fs.remove_file(Path::new("/obj")).unwrap();
+        assert!(fs.metadata(Path::new("/obj")).is_err());
+        assert_eq!(b.size(), 8);
+    }
+
+    #[test]
+    fn shm_objects_are_limited_and_charged() {
+        let page = WASM_PAGE_SIZE as u64;
+        let group = ResourceGroup::new(ResourceLimits {
+            max_memory: Some(3 * page),
+            ..Default::default()
+        });
+        let limits = ShmLimits {
+            max_objects: 2,
+            max_bytes: 4 * page,
+        };
+        let fs = ShmFileSystem::new(limits, Some(group.clone()));
+        let open = |name: &str| {
+            fs.new_open_options()
+                .read(true)
+                .write(true)
+                .create(true)
+                .open(name)
+        };
+
+        let mut a = open("/a").unwrap();
+        let mut b = open("/b").unwrap();
+        assert_eq!(open("/c").unwrap_err(), FsError::StorageFull);
+
+        // Growing past the reserved pages keeps the contents
+        futures::executor::block_on(a.write_all_at(b"hello", 0)).unwrap();
+        a.set_len(2 * page).unwrap();
+        let mut buf = [0u8; 5];
+        futures::executor::block_on(a.read_at(&mut buf, 0)).unwrap();
+        assert_eq!(&buf, b"hello");
+        assert_eq!(group.memory_usage(), 2 * page);
+
+        // The memory limit is reached before the limit on the bytes
+        assert_eq!(b.set_len(2 * page), Err(FsError::StorageFull));
+        b.set_len(page).unwrap();
+        assert_eq!(group.memory_usage(), 3 * page);
+
+        // Objects are released once they are removed and closed
+        fs.remove_file(Path::new("/a")).unwrap();
+        drop(a);
+        assert_eq!(group.memory_usage(), page);
+        open("/c").unwrap();
+    }
+
+    #[test]
+    fn shm_mappings_alias_the_object() {
+        let fs = ShmFileSystem::default();
+        let file = fs
+            .new_open_options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open("/obj")
+            .unwrap();
+        let object = file
+            .upcast_any_ref()
+            .downcast_ref::<ShmFile>()
+            .unwrap()
+            .object()
+            .clone();
+        object.set_len(shm_page_size()).unwrap();
+
+        // Two regions of "linear memory" that map the same page
+        let page = shm_page_size() as usize;
+        let mut memory = vec![0u8; page * 4];
+        let base = (memory.as_mut_ptr() as usize).next_multiple_of(page) as *mut u8;
+        unsafe {
+            object.map_into(base, 0, page as u64, true).unwrap();
+            object
+                .map_into(base.add(page), 0, page as u64, true)
+                .unwrap();
+
+            *base = 7;
+            assert_eq!(*base.add(page), 7);
+            let mut buf = [0u8; 1];
+            object.read_at(&mut buf, 0);
+            assert_eq!(buf, [7]);
+
+            shm_unmap(base, page as u64).unwrap();
+            shm_unmap(base.add(page), page as u64).unwrap();
+            assert_eq!(*base, 0);
+        }
+    }
+}
@@ -7,8 +7,9 @@ pub(super) use wasmer_types::MemorySize;
 pub(super) use wasmer_wasix_types::{
     types::__wasi_ciovec_t,
     wasi::{
-        Advice, EpollCtl, EpollEventCtl, Errno, ExitCode, Fd, Fdflags, Fdflagsext, Filesize,
-        Fstflags, LookupFlags, Oflags, Rights, Snapshot0Clockid, Timestamp, Whence,
+        Advice, EpollCtl, EpollEventCtl, Errno, ExitCode, Fallocflags, Fd, Fdflags, Fdflagsext,
+        Filesize, Fstflags, LookupFlags, Oflags, Rights, Snapshot0Clockid, Timestamp, Whence,
+        Xattrflags,
     },
 };
 
@@ -34,6 +35,7 @@ mod syscalls {
     mod fd_close;
     mod fd_duplicate;
     mod fd_event;
+    mod fd_fallocate;
     mod fd_pipe;
     mod fd_renumber;
     mod fd_seek;
@@ -47,8 +49,10 @@ mod syscalls {
     mod path_link;
     mod path_open;
     mod path_remove_directory;
+    mod path_removexattr;
     mod path_rename;
     mod path_set_times;
+    mod path_setxattr;
     mod path_symlink;
     mod path_unlink;
     mod port_addr_add;
@@ -63,7 +67,9 @@ mod syscalls {
     mod port_unbridge;
     mod sock_accept;
     mod sock_bind;
+    mod sock_bind_unix;
     mod sock_connect;
+    mod sock_connect_unix;
     mod sock_join_ipv4_multicast;
     mod sock_join_ipv6_multicast;
     mod sock_leave_ipv4_multicast;
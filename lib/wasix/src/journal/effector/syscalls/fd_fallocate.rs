@@ -0,0 +1,42 @@
+use super::*;
+
+impl JournalEffector {
+    pub fn save_fd_fallocate(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: Fallocflags,
+        offset: Filesize,
+        len: Filesize,
+    ) -> anyhow::Result<()> {
+        Self::save_event(
+            ctx,
+            JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            },
+        )
+    }
+
+    pub fn apply_fd_fallocate(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: Fallocflags,
+        offset: Filesize,
+        len: Filesize,
+    ) -> anyhow::Result<()> {
+        crate::syscalls::fd_fallocate_internal(ctx, fd, flags, offset, len)
+            .map_err(|err| {
+                anyhow::format_err!(
+                    "journal restore error: failed to fallocate on file descriptor (fd={}, flags={:?}, offset={}, len={}) - {}",
+                    fd,
+                    flags,
+                    offset,
+                    len,
+                    err
+                )
+            })?;
+        Ok(())
+    }
+}
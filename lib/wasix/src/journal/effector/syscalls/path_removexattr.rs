@@ -0,0 +1,44 @@
+use super::*;
+
+impl JournalEffector {
+    pub fn save_path_removexattr(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: LookupFlags,
+        path: String,
+        name: String,
+    ) -> anyhow::Result<()> {
+        Self::save_event(
+            ctx,
+            JournalEntry::PathRemoveXattrV1 {
+                fd,
+                flags,
+                path: path.into(),
+                name: name.into(),
+            },
+        )
+    }
+
+    pub fn apply_path_removexattr(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: LookupFlags,
+        path: &str,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        crate::syscalls::path_removexattr_internal(ctx, fd, flags, path, name)
+            .map(|r| r.map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| Err(err.to_string()))
+            .map_err(|err| {
+                anyhow::format_err!(
+                    "journal restore error: failed to remove extended attribute (fd={}, flags={}, path={}, name={}) - {}",
+                    fd,
+                    flags,
+                    path,
+                    name,
+                    err
+                )
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+impl JournalEffector {
+    pub fn save_path_setxattr(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: LookupFlags,
+        path: String,
+        name: String,
+        value: Vec<u8>,
+        xflags: Xattrflags,
+    ) -> anyhow::Result<()> {
+        Self::save_event(
+            ctx,
+            JournalEntry::PathSetXattrV1 {
+                fd,
+                flags,
+                path: path.into(),
+                name: name.into(),
+                value: value.into(),
+                xflags,
+            },
+        )
+    }
+
+    pub fn apply_path_setxattr(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        flags: LookupFlags,
+        path: &str,
+        name: &str,
+        value: &[u8],
+        xflags: Xattrflags,
+    ) -> anyhow::Result<()> {
+        crate::syscalls::path_setxattr_internal(ctx, fd, flags, path, name, value, xflags)
+            .map(|r| r.map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| Err(err.to_string()))
+            .map_err(|err| {
+                anyhow::format_err!(
+                    "journal restore error: failed to set extended attribute (fd={}, flags={}, path={}, name={}, xflags={:?}) - {}",
+                    fd,
+                    flags,
+                    path,
+                    name,
+                    xflags,
+                    err
+                )
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use super::*;
+
+impl JournalEffector {
+    pub fn save_sock_bind_unix(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        path: String,
+    ) -> anyhow::Result<()> {
+        Self::save_event(
+            ctx,
+            JournalEntry::SocketBindUnixV1 {
+                fd,
+                path: path.into(),
+            },
+        )
+    }
+
+    pub fn apply_sock_bind_unix(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let mut ret = crate::syscalls::sock_bind_unix_internal(ctx, fd, path);
+
+        // The socket entry outlives the process when the file system is
+        // persistent, it is stale now so it can be bound again
+        if let Ok(Err(Errno::Addrinuse)) = ret {
+            let state = ctx.data().state();
+            if let Ok(path) = crate::syscalls::unix_socket_path(state, path) {
+                state.fs_remove_file(path).ok();
+            }
+            ret = crate::syscalls::sock_bind_unix_internal(ctx, fd, path);
+        }
+
+        ret.map(|r| r.map_err(|err| err.to_string()))
+            .unwrap_or_else(|err| Err(err.to_string()))
+            .map_err(|err| {
+                anyhow::format_err!(
+                    "journal restore error: failed to bind unix socket to path (fd={}, path={}) - {}",
+                    fd,
+                    path,
+                    err
+                )
+            })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+impl JournalEffector {
+    pub fn save_sock_connect_unix(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        path: String,
+    ) -> anyhow::Result<()> {
+        Self::save_event(
+            ctx,
+            JournalEntry::SocketConnectUnixV1 {
+                fd,
+                path: path.into(),
+            },
+        )
+    }
+
+    /// The socket on the other end may no longer exist after a restore, in
+    /// which case the socket is left unconnected rather than failing the
+    /// whole restore (much like connected network sockets are left dead).
+    pub fn apply_sock_connect_unix(
+        ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+        fd: Fd,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let ret = crate::syscalls::sock_connect_unix_internal(ctx, fd, path).map_err(|err| {
+            anyhow::format_err!(
+                "journal restore error: failed to connect unix socket (fd={}, path={}) - {}",
+                fd,
+                path,
+                err
+            )
+        })?;
+        if let Err(err) = ret {
+            tracing::debug!(%fd, %path, "unix socket could not be reconnected - {}", err);
+        }
+        Ok(())
+    }
+}
@@ -508,6 +508,7 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "epoll_wait" => Function::new_typed_with_env(&mut store, env, epoll_wait::<Memory32>),
         "fd_advise" => Function::new_typed_with_env(&mut store, env, fd_advise),
         "fd_allocate" => Function::new_typed_with_env(&mut store, env, fd_allocate),
+        "fd_fallocate" => Function::new_typed_with_env(&mut store, env, fd_fallocate),
         "fd_close" => Function::new_typed_with_env(&mut store, env, fd_close),
         "fd_datasync" => Function::new_typed_with_env(&mut store, env, fd_datasync),
         "fd_fdstat_get" => Function::new_typed_with_env(&mut store, env, fd_fdstat_get::<Memory32>),
@@ -539,6 +540,10 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "path_link" => Function::new_typed_with_env(&mut store, env, path_link::<Memory32>),
         "path_open" => Function::new_typed_with_env(&mut store, env, path_open::<Memory32>),
         "path_open2" => Function::new_typed_with_env(&mut store, env, path_open2::<Memory32>),
+        "path_getxattr" => Function::new_typed_with_env(&mut store, env, path_getxattr::<Memory32>),
+        "path_setxattr" => Function::new_typed_with_env(&mut store, env, path_setxattr::<Memory32>),
+        "path_listxattr" => Function::new_typed_with_env(&mut store, env, path_listxattr::<Memory32>),
+        "path_removexattr" => Function::new_typed_with_env(&mut store, env, path_removexattr::<Memory32>),
         "path_readlink" => Function::new_typed_with_env(&mut store, env, path_readlink::<Memory32>),
         "path_remove_directory" => Function::new_typed_with_env(&mut store, env, path_remove_directory::<Memory32>),
         "path_rename" => Function::new_typed_with_env(&mut store, env, path_rename::<Memory32>),
@@ -548,7 +553,44 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "proc_exit" => Function::new_typed_with_env(&mut store, env, proc_exit::<Memory32>),
         "proc_fork" => Function::new_typed_with_env(&mut store, env, proc_fork::<Memory32>),
         "proc_join" => Function::new_typed_with_env(&mut store, env, proc_join::<Memory32>),
+        "proc_join2" => Function::new_typed_with_env(&mut store, env, proc_join2::<Memory32>),
+        "getrusage" => Function::new_typed_with_env(&mut store, env, getrusage::<Memory32>),
+        "pty_open" => Function::new_typed_with_env(&mut store, env, pty_open::<Memory32>),
+        "pty_index" => Function::new_typed_with_env(&mut store, env, pty_index::<Memory32>),
+        "fd_tty_get" => Function::new_typed_with_env(&mut store, env, fd_tty_get::<Memory32>),
+        "fd_tty_set" => Function::new_typed_with_env(&mut store, env, fd_tty_set::<Memory32>),
+        "fd_tty_getpgrp" => Function::new_typed_with_env(&mut store, env, fd_tty_getpgrp::<Memory32>),
+        "fd_tty_setpgrp" => Function::new_typed_with_env(&mut store, env, fd_tty_setpgrp),
+        "getuid" => Function::new_typed_with_env(&mut store, env, getuid::<Memory32>),
+        "geteuid" => Function::new_typed_with_env(&mut store, env, geteuid::<Memory32>),
+        "getgid" => Function::new_typed_with_env(&mut store, env, getgid::<Memory32>),
+        "getegid" => Function::new_typed_with_env(&mut store, env, getegid::<Memory32>),
+        "getgroups" => Function::new_typed_with_env(&mut store, env, getgroups::<Memory32>),
+        "setuid" => Function::new_typed_with_env(&mut store, env, setuid),
+        "seteuid" => Function::new_typed_with_env(&mut store, env, seteuid),
+        "setgid" => Function::new_typed_with_env(&mut store, env, setgid),
+        "setegid" => Function::new_typed_with_env(&mut store, env, setegid),
+        "setgroups" => Function::new_typed_with_env(&mut store, env, setgroups::<Memory32>),
+        "shm_open" => Function::new_typed_with_env(&mut store, env, shm_open::<Memory32>),
+        "shm_unlink" => Function::new_typed_with_env(&mut store, env, shm_unlink::<Memory32>),
+        "sem_open" => Function::new_typed_with_env(&mut store, env, sem_open::<Memory32>),
+        "sem_close" => Function::new_typed_with_env(&mut store, env, sem_close),
+        "sem_unlink" => Function::new_typed_with_env(&mut store, env, sem_unlink::<Memory32>),
+        "sem_post" => Function::new_typed_with_env(&mut store, env, sem_post),
+        "sem_trywait" => Function::new_typed_with_env(&mut store, env, sem_trywait),
+        "sem_wait" => Function::new_typed_with_env(&mut store, env, sem_wait::<Memory32>),
+        "sem_getvalue" => Function::new_typed_with_env(&mut store, env, sem_getvalue::<Memory32>),
+        "mq_open" => Function::new_typed_with_env(&mut store, env, mq_open::<Memory32>),
+        "mq_close" => Function::new_typed_with_env(&mut store, env, mq_close),
+        "mq_unlink" => Function::new_typed_with_env(&mut store, env, mq_unlink::<Memory32>),
+        "mq_send" => Function::new_typed_with_env(&mut store, env, mq_send::<Memory32>),
+        "mq_receive" => Function::new_typed_with_env(&mut store, env, mq_receive::<Memory32>),
         "proc_signal" => Function::new_typed_with_env(&mut store, env, proc_signal::<Memory32>),
+        "proc_signal_group" => Function::new_typed_with_env(&mut store, env, proc_signal_group),
+        "proc_setpgid" => Function::new_typed_with_env(&mut store, env, proc_setpgid),
+        "proc_getpgid" => Function::new_typed_with_env(&mut store, env, proc_getpgid::<Memory32>),
+        "proc_setsid" => Function::new_typed_with_env(&mut store, env, proc_setsid::<Memory32>),
+        "proc_getsid" => Function::new_typed_with_env(&mut store, env, proc_getsid::<Memory32>),
         "proc_signals_get" => Function::new_typed_with_env(&mut store, env, proc_signals_get::<Memory32>),
         "proc_signals_sizes_get" => Function::new_typed_with_env(&mut store, env, proc_signals_sizes_get::<Memory32>),
         "proc_exec" => Function::new_typed_with_env(&mut store, env, proc_exec::<Memory32>),
@@ -564,6 +606,8 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "random_get" => Function::new_typed_with_env(&mut store, env, random_get::<Memory32>),
         "tty_get" => Function::new_typed_with_env(&mut store, env, tty_get::<Memory32>),
         "tty_set" => Function::new_typed_with_env(&mut store, env, tty_set::<Memory32>),
+        "tty_getpgrp" => Function::new_typed_with_env(&mut store, env, tty_getpgrp::<Memory32>),
+        "tty_setpgrp" => Function::new_typed_with_env(&mut store, env, tty_setpgrp),
         "getcwd" => Function::new_typed_with_env(&mut store, env, getcwd::<Memory32>),
         "chdir" => Function::new_typed_with_env(&mut store, env, chdir::<Memory32>),
         "callback_signal" => Function::new_typed_with_env(&mut store, env, callback_signal::<Memory32>),
@@ -578,6 +622,13 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sched_yield" => Function::new_typed_with_env(&mut store, env, sched_yield::<Memory32>),
         "stack_checkpoint" => Function::new_typed_with_env(&mut store, env, stack_checkpoint::<Memory32>),
         "stack_restore" => Function::new_typed_with_env(&mut store, env, stack_restore::<Memory32>),
+        "fd_mmap" => Function::new_typed_with_env(&mut store, env, fd_mmap::<Memory32>),
+        "dlopen" => Function::new_typed_with_env(&mut store, env, dlopen::<Memory32>),
+        "dlsym" => Function::new_typed_with_env(&mut store, env, dlsym::<Memory32>),
+        "dlclose" => Function::new_typed_with_env(&mut store, env, dlclose),
+        "munmap" => Function::new_typed_with_env(&mut store, env, munmap::<Memory32>),
+        "msync" => Function::new_typed_with_env(&mut store, env, msync::<Memory32>),
+        "mprotect" => Function::new_typed_with_env(&mut store, env, mprotect::<Memory32>),
         "futex_wait" => Function::new_typed_with_env(&mut store, env, futex_wait::<Memory32>),
         "futex_wake" => Function::new_typed_with_env(&mut store, env, futex_wake::<Memory32>),
         "futex_wake_all" => Function::new_typed_with_env(&mut store, env, futex_wake_all::<Memory32>),
@@ -597,6 +648,8 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_status" => Function::new_typed_with_env(&mut store, env, sock_status::<Memory32>),
         "sock_addr_local" => Function::new_typed_with_env(&mut store, env, sock_addr_local::<Memory32>),
         "sock_addr_peer" => Function::new_typed_with_env(&mut store, env, sock_addr_peer::<Memory32>),
+        "sock_addr_local_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_local_unix::<Memory32>),
+        "sock_addr_peer_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_peer_unix::<Memory32>),
         "sock_open" => Function::new_typed_with_env(&mut store, env, sock_open::<Memory32>),
         "sock_pair" => Function::new_typed_with_env(&mut store, env, sock_pair::<Memory32>),
         "sock_set_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_set_opt_flag),
@@ -610,10 +663,12 @@ fn wasix_exports_32(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory32>),
         "sock_leave_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v6::<Memory32>),
         "sock_bind" => Function::new_typed_with_env(&mut store, env, sock_bind::<Memory32>),
+        "sock_bind_unix" => Function::new_typed_with_env(&mut store, env, sock_bind_unix::<Memory32>),
         "sock_listen" => Function::new_typed_with_env(&mut store, env, sock_listen::<Memory32>),
         "sock_accept" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory32>),
         "sock_accept_v2" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory32>),
         "sock_connect" => Function::new_typed_with_env(&mut store, env, sock_connect::<Memory32>),
+        "sock_connect_unix" => Function::new_typed_with_env(&mut store, env, sock_connect_unix::<Memory32>),
         "sock_recv" => Function::new_typed_with_env(&mut store, env, sock_recv::<Memory32>),
         "sock_recv_from" => Function::new_typed_with_env(&mut store, env, sock_recv_from::<Memory32>),
         "sock_send" => Function::new_typed_with_env(&mut store, env, sock_send::<Memory32>),
@@ -640,6 +695,7 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "epoll_wait" => Function::new_typed_with_env(&mut store, env, epoll_wait::<Memory64>),
         "fd_advise" => Function::new_typed_with_env(&mut store, env, fd_advise),
         "fd_allocate" => Function::new_typed_with_env(&mut store, env, fd_allocate),
+        "fd_fallocate" => Function::new_typed_with_env(&mut store, env, fd_fallocate),
         "fd_close" => Function::new_typed_with_env(&mut store, env, fd_close),
         "fd_datasync" => Function::new_typed_with_env(&mut store, env, fd_datasync),
         "fd_fdstat_get" => Function::new_typed_with_env(&mut store, env, fd_fdstat_get::<Memory64>),
@@ -671,6 +727,10 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "path_link" => Function::new_typed_with_env(&mut store, env, path_link::<Memory64>),
         "path_open" => Function::new_typed_with_env(&mut store, env, path_open::<Memory64>),
         "path_open2" => Function::new_typed_with_env(&mut store, env, path_open2::<Memory64>),
+        "path_getxattr" => Function::new_typed_with_env(&mut store, env, path_getxattr::<Memory64>),
+        "path_setxattr" => Function::new_typed_with_env(&mut store, env, path_setxattr::<Memory64>),
+        "path_listxattr" => Function::new_typed_with_env(&mut store, env, path_listxattr::<Memory64>),
+        "path_removexattr" => Function::new_typed_with_env(&mut store, env, path_removexattr::<Memory64>),
         "path_readlink" => Function::new_typed_with_env(&mut store, env, path_readlink::<Memory64>),
         "path_remove_directory" => Function::new_typed_with_env(&mut store, env, path_remove_directory::<Memory64>),
         "path_rename" => Function::new_typed_with_env(&mut store, env, path_rename::<Memory64>),
@@ -680,7 +740,44 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "proc_exit" => Function::new_typed_with_env(&mut store, env, proc_exit::<Memory64>),
         "proc_fork" => Function::new_typed_with_env(&mut store, env, proc_fork::<Memory64>),
         "proc_join" => Function::new_typed_with_env(&mut store, env, proc_join::<Memory64>),
+        "proc_join2" => Function::new_typed_with_env(&mut store, env, proc_join2::<Memory64>),
+        "getrusage" => Function::new_typed_with_env(&mut store, env, getrusage::<Memory64>),
+        "pty_open" => Function::new_typed_with_env(&mut store, env, pty_open::<Memory64>),
+        "pty_index" => Function::new_typed_with_env(&mut store, env, pty_index::<Memory64>),
+        "fd_tty_get" => Function::new_typed_with_env(&mut store, env, fd_tty_get::<Memory64>),
+        "fd_tty_set" => Function::new_typed_with_env(&mut store, env, fd_tty_set::<Memory64>),
+        "fd_tty_getpgrp" => Function::new_typed_with_env(&mut store, env, fd_tty_getpgrp::<Memory64>),
+        "fd_tty_setpgrp" => Function::new_typed_with_env(&mut store, env, fd_tty_setpgrp),
+        "getuid" => Function::new_typed_with_env(&mut store, env, getuid::<Memory64>),
+        "geteuid" => Function::new_typed_with_env(&mut store, env, geteuid::<Memory64>),
+        "getgid" => Function::new_typed_with_env(&mut store, env, getgid::<Memory64>),
+        "getegid" => Function::new_typed_with_env(&mut store, env, getegid::<Memory64>),
+        "getgroups" => Function::new_typed_with_env(&mut store, env, getgroups::<Memory64>),
+        "setuid" => Function::new_typed_with_env(&mut store, env, setuid),
+        "seteuid" => Function::new_typed_with_env(&mut store, env, seteuid),
+        "setgid" => Function::new_typed_with_env(&mut store, env, setgid),
+        "setegid" => Function::new_typed_with_env(&mut store, env, setegid),
+        "setgroups" => Function::new_typed_with_env(&mut store, env, setgroups::<Memory64>),
+        "shm_open" => Function::new_typed_with_env(&mut store, env, shm_open::<Memory64>),
+        "shm_unlink" => Function::new_typed_with_env(&mut store, env, shm_unlink::<Memory64>),
+        "sem_open" => Function::new_typed_with_env(&mut store, env, sem_open::<Memory64>),
+        "sem_close" => Function::new_typed_with_env(&mut store, env, sem_close),
+        "sem_unlink" => Function::new_typed_with_env(&mut store, env, sem_unlink::<Memory64>),
+        "sem_post" => Function::new_typed_with_env(&mut store, env, sem_post),
+        "sem_trywait" => Function::new_typed_with_env(&mut store, env, sem_trywait),
+        "sem_wait" => Function::new_typed_with_env(&mut store, env, sem_wait::<Memory64>),
+        "sem_getvalue" => Function::new_typed_with_env(&mut store, env, sem_getvalue::<Memory64>),
+        "mq_open" => Function::new_typed_with_env(&mut store, env, mq_open::<Memory64>),
+        "mq_close" => Function::new_typed_with_env(&mut store, env, mq_close),
+        "mq_unlink" => Function::new_typed_with_env(&mut store, env, mq_unlink::<Memory64>),
+        "mq_send" => Function::new_typed_with_env(&mut store, env, mq_send::<Memory64>),
+        "mq_receive" => Function::new_typed_with_env(&mut store, env, mq_receive::<Memory64>),
         "proc_signal" => Function::new_typed_with_env(&mut store, env, proc_signal::<Memory64>),
+        "proc_signal_group" => Function::new_typed_with_env(&mut store, env, proc_signal_group),
+        "proc_setpgid" => Function::new_typed_with_env(&mut store, env, proc_setpgid),
+        "proc_getpgid" => Function::new_typed_with_env(&mut store, env, proc_getpgid::<Memory64>),
+        "proc_setsid" => Function::new_typed_with_env(&mut store, env, proc_setsid::<Memory64>),
+        "proc_getsid" => Function::new_typed_with_env(&mut store, env, proc_getsid::<Memory64>),
         "proc_signals_get" => Function::new_typed_with_env(&mut store, env, proc_signals_get::<Memory64>),
         "proc_signals_sizes_get" => Function::new_typed_with_env(&mut store, env, proc_signals_sizes_get::<Memory64>),
         "proc_exec" => Function::new_typed_with_env(&mut store, env, proc_exec::<Memory64>),
@@ -696,6 +793,8 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "random_get" => Function::new_typed_with_env(&mut store, env, random_get::<Memory64>),
         "tty_get" => Function::new_typed_with_env(&mut store, env, tty_get::<Memory64>),
         "tty_set" => Function::new_typed_with_env(&mut store, env, tty_set::<Memory64>),
+        "tty_getpgrp" => Function::new_typed_with_env(&mut store, env, tty_getpgrp::<Memory64>),
+        "tty_setpgrp" => Function::new_typed_with_env(&mut store, env, tty_setpgrp),
         "getcwd" => Function::new_typed_with_env(&mut store, env, getcwd::<Memory64>),
         "chdir" => Function::new_typed_with_env(&mut store, env, chdir::<Memory64>),
         "callback_signal" => Function::new_typed_with_env(&mut store, env, callback_signal::<Memory64>),
@@ -710,6 +809,13 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sched_yield" => Function::new_typed_with_env(&mut store, env, sched_yield::<Memory64>),
         "stack_checkpoint" => Function::new_typed_with_env(&mut store, env, stack_checkpoint::<Memory64>),
         "stack_restore" => Function::new_typed_with_env(&mut store, env, stack_restore::<Memory64>),
+        "fd_mmap" => Function::new_typed_with_env(&mut store, env, fd_mmap::<Memory64>),
+        "dlopen" => Function::new_typed_with_env(&mut store, env, dlopen::<Memory64>),
+        "dlsym" => Function::new_typed_with_env(&mut store, env, dlsym::<Memory64>),
+        "dlclose" => Function::new_typed_with_env(&mut store, env, dlclose),
+        "munmap" => Function::new_typed_with_env(&mut store, env, munmap::<Memory64>),
+        "msync" => Function::new_typed_with_env(&mut store, env, msync::<Memory64>),
+        "mprotect" => Function::new_typed_with_env(&mut store, env, mprotect::<Memory64>),
         "futex_wait" => Function::new_typed_with_env(&mut store, env, futex_wait::<Memory64>),
         "futex_wake" => Function::new_typed_with_env(&mut store, env, futex_wake::<Memory64>),
         "futex_wake_all" => Function::new_typed_with_env(&mut store, env, futex_wake_all::<Memory64>),
@@ -729,6 +835,8 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_status" => Function::new_typed_with_env(&mut store, env, sock_status::<Memory64>),
         "sock_addr_local" => Function::new_typed_with_env(&mut store, env, sock_addr_local::<Memory64>),
         "sock_addr_peer" => Function::new_typed_with_env(&mut store, env, sock_addr_peer::<Memory64>),
+        "sock_addr_local_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_local_unix::<Memory64>),
+        "sock_addr_peer_unix" => Function::new_typed_with_env(&mut store, env, sock_addr_peer_unix::<Memory64>),
         "sock_open" => Function::new_typed_with_env(&mut store, env, sock_open::<Memory64>),
         "sock_pair" => Function::new_typed_with_env(&mut store, env, sock_pair::<Memory64>),
         "sock_set_opt_flag" => Function::new_typed_with_env(&mut store, env, sock_set_opt_flag),
@@ -742,10 +850,12 @@ fn wasix_exports_64(mut store: &mut impl AsStoreMut, env: &FunctionEnv<WasiEnv>)
         "sock_join_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_join_multicast_v6::<Memory64>),
         "sock_leave_multicast_v6" => Function::new_typed_with_env(&mut store, env, sock_leave_multicast_v6::<Memory64>),
         "sock_bind" => Function::new_typed_with_env(&mut store, env, sock_bind::<Memory64>),
+        "sock_bind_unix" => Function::new_typed_with_env(&mut store, env, sock_bind_unix::<Memory64>),
         "sock_listen" => Function::new_typed_with_env(&mut store, env, sock_listen::<Memory64>),
         "sock_accept" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory64>),
         "sock_accept_v2" => Function::new_typed_with_env(&mut store, env, sock_accept_v2::<Memory64>),
         "sock_connect" => Function::new_typed_with_env(&mut store, env, sock_connect::<Memory64>),
+        "sock_connect_unix" => Function::new_typed_with_env(&mut store, env, sock_connect_unix::<Memory64>),
         "sock_recv" => Function::new_typed_with_env(&mut store, env, sock_recv::<Memory64>),
         "sock_recv_from" => Function::new_typed_with_env(&mut store, env, sock_recv_from::<Memory64>),
         "sock_send" => Function::new_typed_with_env(&mut store, env, sock_send::<Memory64>),
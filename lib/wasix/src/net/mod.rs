@@ -17,6 +17,7 @@ use wasmer_wasix_types::{
 };
 
 pub mod socket;
+pub mod unix;
 
 #[allow(dead_code)]
 pub(crate) fn read_ip<M: MemorySize>(
@@ -187,6 +188,23 @@ pub(crate) fn read_ip_port<M: MemorySize>(
     })
 }
 
+/// Unix sockets have no address that fits in here, their paths are read
+/// with `sock_addr_local_unix` and `sock_addr_peer_unix` instead
+pub(crate) fn write_unix_addr_port<M: MemorySize>(
+    memory: &MemoryView,
+    ptr: WasmPtr<__wasi_addr_port_t, M>,
+) -> Result<(), Errno> {
+    let addr_ptr = ptr.deref(memory);
+    addr_ptr
+        .write(__wasi_addr_port_t {
+            tag: Addressfamily::Unix,
+            _padding: 0,
+            u: __wasi_addr_port_u { octs: [0; 18] },
+        })
+        .map_err(crate::mem_error_to_wasi)?;
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub(crate) fn write_ip_port<M: MemorySize>(
     memory: &MemoryView,
@@ -3,6 +3,8 @@ use std::{
     io,
     mem::MaybeUninit,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    ops::DerefMut,
+    path::{Path, PathBuf},
     pin::Pin,
     sync::{Arc, RwLock},
     task::{Context, Poll},
@@ -14,12 +16,18 @@ use serde_derive::{Deserialize, Serialize};
 use virtual_mio::InterestHandler;
 use virtual_net::{
     net_error_into_io_err, NetworkError, VirtualIcmpSocket, VirtualNetworking, VirtualRawSocket,
-    VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
+    VirtualSocket, VirtualTcpListener, VirtualTcpSocket, VirtualUdpSocket,
 };
 use wasmer_types::MemorySize;
 use wasmer_wasix_types::wasi::{Addressfamily, Errno, Rights, SockProto, Sockoption, Socktype};
 
-use crate::{net::net_error_into_wasi_err, VirtualTaskManager};
+use crate::{
+    net::{
+        net_error_into_wasi_err,
+        unix::{UnixEndpoint, UnixSocketRegistry},
+    },
+    VirtualTaskManager,
+};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -199,6 +207,8 @@ impl From<wasmer_journal::SocketOptTimeType> for TimeType {
 //#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub(crate) struct InodeSocketProtected {
     pub kind: InodeSocketKind,
+    /// Set for `AF_UNIX` sockets
+    pub unix: Option<UnixEndpoint>,
 }
 
 #[derive(Debug)]
@@ -215,7 +225,22 @@ pub struct InodeSocket {
 
 impl InodeSocket {
     pub fn new(kind: InodeSocketKind) -> Self {
-        let protected = InodeSocketProtected { kind };
+        let unix = match &kind {
+            InodeSocketKind::PreSocket { props, .. } if props.family == Addressfamily::Unix => {
+                Some(UnixEndpoint::default())
+            }
+            _ => None,
+        };
+        Self::with_unix(kind, unix)
+    }
+
+    /// Creates an `AF_UNIX` socket
+    pub fn new_unix(kind: InodeSocketKind, unix: UnixEndpoint) -> Self {
+        Self::with_unix(kind, Some(unix))
+    }
+
+    fn with_unix(kind: InodeSocketKind, unix: Option<UnixEndpoint>) -> Self {
+        let protected = InodeSocketProtected { kind, unix };
         Self {
             inner: Arc::new(InodeSocketInner {
                 protected: RwLock::new(protected),
@@ -349,10 +374,57 @@ impl InodeSocket {
         }
     }
 
+    /// Binds an `AF_UNIX` socket to a path, the socket entry for the path
+    /// must already have been created in the file system.
+    pub fn bind_unix(
+        &self,
+        unix: &Arc<UnixSocketRegistry>,
+        path: PathBuf,
+    ) -> Result<Option<InodeSocket>, Errno> {
+        let mut guard = self.inner.protected.write().unwrap();
+        let inner = guard.deref_mut();
+        match &mut inner.kind {
+            InodeSocketKind::PreSocket { props, addr, .. } => {
+                if props.family != Addressfamily::Unix {
+                    return Err(Errno::Afnosupport);
+                }
+                if addr.is_some() {
+                    return Err(Errno::Inval);
+                }
+
+                let binding = unix.bind(path);
+                match props.ty {
+                    Socktype::Stream => {
+                        // the listener is only registered once `listen` is called
+                        addr.replace(binding.addr());
+                        inner.unix.replace(UnixEndpoint {
+                            binding: Some(binding),
+                            peer: None,
+                        });
+                        Ok(None)
+                    }
+                    Socktype::Dgram => {
+                        let socket = Box::new(unix.bind_datagram(binding.addr()));
+                        Ok(Some(InodeSocket::new_unix(
+                            InodeSocketKind::UdpSocket { socket, peer: None },
+                            UnixEndpoint {
+                                binding: Some(binding),
+                                peer: None,
+                            },
+                        )))
+                    }
+                    _ => Err(Errno::Inval),
+                }
+            }
+            _ => Err(Errno::Inval),
+        }
+    }
+
     pub async fn listen(
         &self,
         tasks: &dyn VirtualTaskManager,
         net: &dyn VirtualNetworking,
+        unix: &UnixSocketRegistry,
         _backlog: usize,
     ) -> Result<Option<InodeSocket>, Errno> {
         let timeout = self
@@ -371,6 +443,16 @@ impl InodeSocket {
                             return Err(Errno::Inval);
                         }
                         let addr = *addr.as_ref().unwrap();
+                        if let Some(endpoint) = inner.unix.as_ref() {
+                            let socket = unix.listen(addr).map_err(net_error_into_wasi_err)?;
+                            return Ok(Some(InodeSocket::new_unix(
+                                InodeSocketKind::TcpListener {
+                                    socket: Box::new(socket),
+                                    accept_timeout: Some(timeout),
+                                },
+                                endpoint.clone(),
+                            )));
+                        }
                         let only_v6 = props.only_v6;
                         let reuse_port = props.reuse_port;
                         let reuse_addr = props.reuse_addr;
@@ -619,6 +701,72 @@ impl InodeSocket {
         Ok(Some(socket))
     }
 
+    /// Connects an `AF_UNIX` socket to the socket bound to `path`.
+    pub fn connect_unix(
+        &mut self,
+        unix: &Arc<UnixSocketRegistry>,
+        path: &Path,
+    ) -> Result<Option<InodeSocket>, Errno> {
+        let mut guard = self.inner.protected.write().unwrap();
+        let inner = guard.deref_mut();
+        let Some(endpoint) = inner.unix.as_mut() else {
+            return Err(Errno::Afnosupport);
+        };
+        match &mut inner.kind {
+            InodeSocketKind::PreSocket { props, addr, .. } => {
+                let endpoint = UnixEndpoint {
+                    binding: endpoint.binding.clone(),
+                    peer: Some(path.to_path_buf()),
+                };
+                match props.ty {
+                    Socktype::Stream => {
+                        let mut socket =
+                            unix.connect(path, *addr).map_err(net_error_into_wasi_err)?;
+                        if let Some(handler) = props.handler.take() {
+                            socket
+                                .set_handler(handler)
+                                .map_err(net_error_into_wasi_err)?;
+                        }
+                        Ok(Some(InodeSocket::new_unix(
+                            InodeSocketKind::TcpStream {
+                                socket: Box::new(socket),
+                                write_timeout: props.write_timeout,
+                                read_timeout: props.read_timeout,
+                            },
+                            endpoint,
+                        )))
+                    }
+                    Socktype::Dgram => {
+                        // Unbound sockets are given an address nobody can
+                        // send to, just like unnamed sockets on Linux
+                        let peer = unix
+                            .resolve_datagram(path)
+                            .map_err(net_error_into_wasi_err)?;
+                        let socket = Box::new(unix.bind_datagram(unix.allocate_addr()));
+                        Ok(Some(InodeSocket::new_unix(
+                            InodeSocketKind::UdpSocket {
+                                socket,
+                                peer: Some(peer),
+                            },
+                            endpoint,
+                        )))
+                    }
+                    _ => Err(Errno::Notsup),
+                }
+            }
+            InodeSocketKind::UdpSocket { peer, .. } => {
+                let target = unix
+                    .resolve_datagram(path)
+                    .map_err(net_error_into_wasi_err)?;
+                peer.replace(target);
+                endpoint.peer.replace(path.to_path_buf());
+                Ok(None)
+            }
+            InodeSocketKind::TcpStream { .. } => Err(Errno::Isconn),
+            _ => Err(Errno::Notsup),
+        }
+    }
+
     pub fn status(&self) -> Result<WasiSocketStatus, Errno> {
         let inner = self.inner.protected.read().unwrap();
         Ok(match &inner.kind {
@@ -634,6 +782,50 @@ impl InodeSocket {
         })
     }
 
+    /// Returns true for `AF_UNIX` sockets, their addresses are only
+    /// available through [`InodeSocket::unix_path_local`] and
+    /// [`InodeSocket::unix_path_peer`].
+    pub fn is_unix(&self) -> bool {
+        let inner = self.inner.protected.read().unwrap();
+        inner.unix.is_some()
+    }
+
+    /// Path that an `AF_UNIX` socket is bound to, `None` for unnamed sockets
+    pub fn unix_path_local(&self) -> Result<Option<PathBuf>, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        let endpoint = inner.unix.as_ref().ok_or(Errno::Afnosupport)?;
+        Ok(endpoint.path().map(Path::to_path_buf))
+    }
+
+    /// Path of the socket that an `AF_UNIX` socket is connected to
+    pub fn unix_path_peer(&self) -> Result<Option<PathBuf>, Errno> {
+        let inner = self.inner.protected.read().unwrap();
+        let endpoint = inner.unix.as_ref().ok_or(Errno::Afnosupport)?;
+        if endpoint.peer.is_none() && !matches!(&inner.kind, InodeSocketKind::TcpStream { .. }) {
+            return Err(Errno::Notconn);
+        }
+        Ok(endpoint.peer.clone())
+    }
+
+    /// Wraps a connection that was accepted by this listener
+    pub fn accepted(
+        &self,
+        socket: Box<dyn VirtualTcpSocket + Sync>,
+        peer: SocketAddr,
+        unix: &UnixSocketRegistry,
+    ) -> InodeSocket {
+        let inner = self.inner.protected.read().unwrap();
+        let kind = InodeSocketKind::TcpStream {
+            socket,
+            write_timeout: None,
+            read_timeout: None,
+        };
+        match inner.unix.as_ref() {
+            Some(endpoint) => InodeSocket::new_unix(kind, endpoint.accepted(unix, peer)),
+            None => InodeSocket::new(kind),
+        }
+    }
+
     pub fn addr_local(&self) -> Result<SocketAddr, Errno> {
         let inner = self.inner.protected.read().unwrap();
         Ok(match &inner.kind {
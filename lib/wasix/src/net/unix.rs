@@ -0,0 +1,620 @@
+//! Unix domain sockets (`AF_UNIX`) that are shared between all the processes
+//! of a [`WasiControlPlane`](crate::WasiControlPlane).
+//!
+//! The path a socket is bound to is an entry in the virtual file system (see
+//! [`virtual_fs::SocketFile`]) while the socket itself lives in the
+//! [`UnixSocketRegistry`]. Internally every socket is given a synthetic
+//! address so that it can reuse the loopback implementations of the other
+//! socket types, these addresses are only used to route packets inside the
+//! registry and are never reported to the guest. What makes a socket an
+//! `AF_UNIX` socket is its [`UnixEndpoint`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    mem::MaybeUninit,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use virtual_mio::{InterestHandler, InterestType};
+use virtual_net::{
+    loopback::LoopbackTcpListener, tcp_pair::TcpSocketHalf, NetworkError, SocketStatus,
+    VirtualConnectionlessSocket, VirtualIoSource, VirtualSocket, VirtualTcpListener,
+    VirtualTcpSocket, VirtualUdpSocket,
+};
+
+/// Maximum number of datagrams that can be queued on a socket before
+/// senders are made to wait.
+const MAX_QUEUED_DATAGRAMS: usize = 256;
+
+const UNIX_ADDR_PREFIX: [u16; 3] = [0xfd00, 0x756e, 0x6978];
+
+/// The unix side of a socket, the path it is bound to and the path of the
+/// socket it is connected to.
+#[derive(Debug, Clone, Default)]
+pub struct UnixEndpoint {
+    /// Binding of the socket, connections accepted by a listener share the
+    /// binding of the listener
+    pub binding: Option<Arc<UnixBinding>>,
+    /// Path of the socket on the other end, if it is bound to one
+    pub peer: Option<PathBuf>,
+}
+
+impl UnixEndpoint {
+    /// Path the socket is bound to, unnamed sockets return `None`
+    pub fn path(&self) -> Option<&Path> {
+        self.binding.as_ref().map(|binding| binding.path())
+    }
+
+    /// Endpoint of a connection that was accepted by this listener.
+    pub fn accepted(&self, registry: &UnixSocketRegistry, peer: SocketAddr) -> Self {
+        Self {
+            binding: self.binding.clone(),
+            peer: registry.path_of(&peer),
+        }
+    }
+}
+
+/// A path that a socket is bound to, the path and everything that was
+/// registered for the socket is released when the binding is dropped.
+#[derive(Debug)]
+pub struct UnixBinding {
+    registry: Weak<UnixSocketRegistry>,
+    path: PathBuf,
+    addr: SocketAddr,
+}
+
+impl UnixBinding {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for UnixBinding {
+    fn drop(&mut self) {
+        let Some(registry) = self.registry.upgrade() else {
+            return;
+        };
+        let mut state = registry.state.lock().unwrap();
+        // The path may have been unlinked and bound again by another socket
+        if state.paths.get(&self.path) == Some(&self.addr) {
+            state.paths.remove(&self.path);
+        }
+        state.names.remove(&self.addr);
+        state.listeners.remove(&self.addr);
+        state.datagrams.remove(&self.addr);
+    }
+}
+
+#[derive(Debug)]
+struct StreamEndpoint {
+    listener: LoopbackTcpListener,
+    alive: Weak<()>,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    /// Paths that sockets have been bound to and that can be connected to
+    paths: HashMap<PathBuf, SocketAddr>,
+    /// Paths of the bound sockets, unlike `paths` these remain after the
+    /// path is unlinked
+    names: HashMap<SocketAddr, PathBuf>,
+    /// Stream sockets that are listening for connections
+    listeners: HashMap<SocketAddr, StreamEndpoint>,
+    /// Datagram sockets that are able to receive packets
+    datagrams: HashMap<SocketAddr, Weak<DatagramQueue>>,
+}
+
+/// Keeps track of the unix sockets that have been bound to a path so that
+/// other processes are able to connect to them.
+#[derive(Debug, Default)]
+pub struct UnixSocketRegistry {
+    seed: AtomicU64,
+    state: Mutex<RegistryState>,
+}
+
+impl UnixSocketRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hands out a new unique address for a unix socket.
+    pub fn allocate_addr(&self) -> SocketAddr {
+        let id = self.seed.fetch_add(1, Ordering::SeqCst) + 1;
+        let [a, b, c] = UNIX_ADDR_PREFIX;
+        let ip = Ipv6Addr::new(
+            a,
+            b,
+            c,
+            0,
+            (id >> 48) as u16,
+            (id >> 32) as u16,
+            (id >> 16) as u16,
+            id as u16,
+        );
+        SocketAddr::new(ip.into(), 0)
+    }
+
+    /// Associates `path` with a new socket address, any previous socket that
+    /// was bound to the same path is no longer reachable through it.
+    ///
+    /// The caller is expected to have created the socket entry in the file
+    /// system which is what prevents two live sockets using the same path.
+    pub fn bind(self: &Arc<Self>, path: PathBuf) -> Arc<UnixBinding> {
+        let addr = self.allocate_addr();
+        let mut state = self.state.lock().unwrap();
+        if let Some(previous) = state.paths.insert(path.clone(), addr) {
+            state.listeners.remove(&previous);
+            state.datagrams.remove(&previous);
+        }
+        state.names.insert(addr, path.clone());
+        Arc::new(UnixBinding {
+            registry: Arc::downgrade(self),
+            path,
+            addr,
+        })
+    }
+
+    /// Called when the socket entry of `path` is removed from the file
+    /// system, the socket bound to it can no longer be connected to.
+    pub fn unlink(&self, path: &Path) {
+        let mut state = self.state.lock().unwrap();
+        state.paths.remove(path);
+    }
+
+    /// Returns the path that the socket with this address is bound to.
+    pub fn path_of(&self, addr: &SocketAddr) -> Option<PathBuf> {
+        let state = self.state.lock().unwrap();
+        state.names.get(addr).cloned()
+    }
+
+    /// Starts listening for stream connections on a bound address.
+    pub fn listen(&self, addr: SocketAddr) -> Result<UnixListener, NetworkError> {
+        let mut state = self.state.lock().unwrap();
+        if state.listeners.contains_key(&addr) {
+            return Err(NetworkError::AddressInUse);
+        }
+        let listener = LoopbackTcpListener::new(addr);
+        let alive = Arc::new(());
+        state.listeners.insert(
+            addr,
+            StreamEndpoint {
+                listener: listener.clone(),
+                alive: Arc::downgrade(&alive),
+            },
+        );
+        Ok(UnixListener {
+            inner: listener,
+            _alive: alive,
+        })
+    }
+
+    /// Connects a stream socket to the listener bound to `path`.
+    pub fn connect(
+        &self,
+        path: &Path,
+        addr_local: Option<SocketAddr>,
+    ) -> Result<TcpSocketHalf, NetworkError> {
+        let addr_local = addr_local.unwrap_or_else(|| self.allocate_addr());
+        let mut state = self.state.lock().unwrap();
+        let addr = *state
+            .paths
+            .get(path)
+            .ok_or(NetworkError::ConnectionRefused)?;
+        let endpoint = state
+            .listeners
+            .get(&addr)
+            .ok_or(NetworkError::ConnectionRefused)?;
+        if endpoint.alive.strong_count() == 0 {
+            state.listeners.remove(&addr);
+            return Err(NetworkError::ConnectionRefused);
+        }
+        Ok(endpoint.listener.connect_to(addr_local))
+    }
+
+    /// Creates a datagram socket that can receive packets sent to `addr`.
+    pub fn bind_datagram(self: &Arc<Self>, addr: SocketAddr) -> UnixDatagramSocket {
+        let queue = Arc::new(DatagramQueue::default());
+        let mut state = self.state.lock().unwrap();
+        state.datagrams.insert(addr, Arc::downgrade(&queue));
+        UnixDatagramSocket {
+            registry: self.clone(),
+            addr,
+            queue,
+            ttl: 64,
+        }
+    }
+
+    /// Returns the address of the datagram socket bound to `path`.
+    pub fn resolve_datagram(&self, path: &Path) -> Result<SocketAddr, NetworkError> {
+        let state = self.state.lock().unwrap();
+        state
+            .paths
+            .get(path)
+            .filter(|addr| state.datagrams.contains_key(addr))
+            .copied()
+            .ok_or(NetworkError::ConnectionRefused)
+    }
+
+    fn datagram_queue(&self, addr: &SocketAddr) -> Option<Arc<DatagramQueue>> {
+        let mut state = self.state.lock().unwrap();
+        let queue = state.datagrams.get(addr)?.upgrade();
+        if queue.is_none() {
+            state.datagrams.remove(addr);
+        }
+        queue
+    }
+}
+
+/// Listener side of a unix stream socket, connections are removed from the
+/// registry again once the listener is dropped.
+#[derive(Debug)]
+pub struct UnixListener {
+    inner: LoopbackTcpListener,
+    _alive: Arc<()>,
+}
+
+impl VirtualIoSource for UnixListener {
+    fn remove_handler(&mut self) {
+        self.inner.remove_handler()
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        self.inner.poll_read_ready(cx)
+    }
+
+    fn poll_write_ready(&mut self, cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        self.inner.poll_write_ready(cx)
+    }
+}
+
+impl VirtualTcpListener for UnixListener {
+    fn try_accept(
+        &mut self,
+    ) -> virtual_net::Result<(Box<dyn VirtualTcpSocket + Sync>, SocketAddr)> {
+        self.inner.try_accept()
+    }
+
+    fn set_handler(
+        &mut self,
+        handler: Box<dyn InterestHandler + Send + Sync>,
+    ) -> virtual_net::Result<()> {
+        self.inner.set_handler(handler)
+    }
+
+    fn addr_local(&self) -> virtual_net::Result<SocketAddr> {
+        self.inner.addr_local()
+    }
+
+    fn set_ttl(&mut self, ttl: u8) -> virtual_net::Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    fn ttl(&self) -> virtual_net::Result<u8> {
+        self.inner.ttl()
+    }
+}
+
+#[derive(Debug, Default)]
+struct DatagramQueueState {
+    packets: VecDeque<(Vec<u8>, SocketAddr)>,
+    handler: Option<Box<dyn InterestHandler + Send + Sync>>,
+    wakers: Vec<Waker>,
+    /// Senders that are waiting for room in this queue
+    blocked: Vec<Weak<DatagramQueue>>,
+}
+
+#[derive(Debug, Default)]
+struct DatagramQueue {
+    state: Mutex<DatagramQueueState>,
+}
+
+impl DatagramQueue {
+    fn notify(&self, interest: InterestType) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(handler) = state.handler.as_mut() {
+            handler.push_interest(interest);
+        }
+        state.wakers.drain(..).for_each(|w| w.wake());
+    }
+}
+
+/// Datagram unix socket, packets are delivered straight into the queue of
+/// the socket they are addressed to.
+#[derive(Debug)]
+pub struct UnixDatagramSocket {
+    registry: Arc<UnixSocketRegistry>,
+    addr: SocketAddr,
+    queue: Arc<DatagramQueue>,
+    ttl: u32,
+}
+
+impl VirtualIoSource for UnixDatagramSocket {
+    fn remove_handler(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        state.handler.take();
+    }
+
+    fn poll_read_ready(&mut self, cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        let mut state = self.queue.state.lock().unwrap();
+        if let Some((data, _)) = state.packets.front() {
+            return Poll::Ready(Ok(data.len()));
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    fn poll_write_ready(&mut self, _cx: &mut Context<'_>) -> Poll<virtual_net::Result<usize>> {
+        Poll::Ready(Ok(MAX_QUEUED_DATAGRAMS))
+    }
+}
+
+impl VirtualSocket for UnixDatagramSocket {
+    fn set_ttl(&mut self, ttl: u32) -> virtual_net::Result<()> {
+        self.ttl = ttl;
+        Ok(())
+    }
+
+    fn ttl(&self) -> virtual_net::Result<u32> {
+        Ok(self.ttl)
+    }
+
+    fn addr_local(&self) -> virtual_net::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+
+    fn status(&self) -> virtual_net::Result<SocketStatus> {
+        Ok(SocketStatus::Opened)
+    }
+
+    fn set_handler(
+        &mut self,
+        mut handler: Box<dyn InterestHandler + Send + Sync>,
+    ) -> virtual_net::Result<()> {
+        let mut state = self.queue.state.lock().unwrap();
+        if !state.packets.is_empty() {
+            handler.push_interest(InterestType::Readable);
+        }
+        handler.push_interest(InterestType::Writable);
+        state.handler.replace(handler);
+        Ok(())
+    }
+}
+
+impl VirtualConnectionlessSocket for UnixDatagramSocket {
+    fn try_send_to(&mut self, data: &[u8], addr: SocketAddr) -> virtual_net::Result<usize> {
+        let target = self
+            .registry
+            .datagram_queue(&addr)
+            .ok_or(NetworkError::ConnectionRefused)?;
+        {
+            let mut state = target.state.lock().unwrap();
+            if state.packets.len() >= MAX_QUEUED_DATAGRAMS {
+                state.blocked.push(Arc::downgrade(&self.queue));
+                return Err(NetworkError::WouldBlock);
+            }
+            state.packets.push_back((data.to_vec(), self.addr));
+        }
+        target.notify(InterestType::Readable);
+        Ok(data.len())
+    }
+
+    fn try_recv_from(
+        &mut self,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> virtual_net::Result<(usize, SocketAddr)> {
+        let (data, from, blocked) = {
+            let mut state = self.queue.state.lock().unwrap();
+            let (data, from) = state.packets.pop_front().ok_or(NetworkError::WouldBlock)?;
+            (data, from, std::mem::take(&mut state.blocked))
+        };
+        for sender in blocked.iter().filter_map(Weak::upgrade) {
+            sender.notify(InterestType::Writable);
+        }
+
+        // Datagrams that do not fit into the buffer are truncated
+        let amt = data.len().min(buf.len());
+        for (dst, src) in buf.iter_mut().zip(data[..amt].iter()) {
+            dst.write(*src);
+        }
+        Ok((amt, from))
+    }
+}
+
+impl VirtualUdpSocket for UnixDatagramSocket {
+    fn set_broadcast(&mut self, _broadcast: bool) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn broadcast(&self) -> virtual_net::Result<bool> {
+        Ok(false)
+    }
+
+    fn set_multicast_loop_v4(&mut self, _val: bool) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn multicast_loop_v4(&self) -> virtual_net::Result<bool> {
+        Ok(false)
+    }
+
+    fn set_multicast_loop_v6(&mut self, _val: bool) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn multicast_loop_v6(&self) -> virtual_net::Result<bool> {
+        Ok(false)
+    }
+
+    fn set_multicast_ttl_v4(&mut self, _ttl: u32) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn multicast_ttl_v4(&self) -> virtual_net::Result<u32> {
+        Ok(0)
+    }
+
+    fn join_multicast_v4(
+        &mut self,
+        _multiaddr: Ipv4Addr,
+        _iface: Ipv4Addr,
+    ) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn leave_multicast_v4(
+        &mut self,
+        _multiaddr: Ipv4Addr,
+        _iface: Ipv4Addr,
+    ) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn join_multicast_v6(&mut self, _multiaddr: Ipv6Addr, _iface: u32) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn leave_multicast_v6(&mut self, _multiaddr: Ipv6Addr, _iface: u32) -> virtual_net::Result<()> {
+        Err(NetworkError::Unsupported)
+    }
+
+    fn addr_peer(&self) -> virtual_net::Result<Option<SocketAddr>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use virtual_net::VirtualConnectedSocket;
+
+    #[test]
+    fn unix_addresses_are_unique() {
+        let registry = UnixSocketRegistry::new();
+        let a = registry.allocate_addr();
+        let b = registry.allocate_addr();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn stream_connections_reach_the_listener() {
+        let registry = Arc::new(UnixSocketRegistry::new());
+        let path = Path::new("/run/app.sock");
+
+        assert_eq!(
+            registry.connect(path, None).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+
+        let binding = registry.bind(path.to_path_buf());
+        assert_eq!(
+            registry.connect(path, None).unwrap_err(),
+            NetworkError::ConnectionRefused,
+            "bound but not listening yet",
+        );
+
+        let mut listener = registry.listen(binding.addr()).unwrap();
+        let client_binding = registry.bind(PathBuf::from("/run/client.sock"));
+        let mut client = registry.connect(path, Some(client_binding.addr())).unwrap();
+        let (mut server, peer) = listener.try_accept().unwrap();
+        let endpoint = UnixEndpoint {
+            binding: Some(binding.clone()),
+            peer: None,
+        }
+        .accepted(&registry, peer);
+        assert_eq!(endpoint.path(), Some(path));
+        assert_eq!(
+            endpoint.peer.as_deref(),
+            Some(Path::new("/run/client.sock"))
+        );
+
+        client.try_send(b"ping").unwrap();
+        let mut buf = [MaybeUninit::new(0u8); 4];
+        assert_eq!(server.try_recv(&mut buf).unwrap(), 4);
+
+        // Connections are refused once the listener goes away
+        drop(listener);
+        assert_eq!(
+            registry.connect(path, None).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+    }
+
+    #[test]
+    fn datagrams_are_routed_by_address() {
+        let registry = Arc::new(UnixSocketRegistry::new());
+        let path = Path::new("/run/log.sock");
+
+        let binding = registry.bind(path.to_path_buf());
+        let mut server = registry.bind_datagram(binding.addr());
+        let mut client = registry.bind_datagram(registry.allocate_addr());
+
+        let target = registry.resolve_datagram(path).unwrap();
+        assert_eq!(client.try_send_to(b"hello", target).unwrap(), 5);
+
+        let mut buf = [MaybeUninit::new(0u8); 16];
+        let (amt, from) = server.try_recv_from(&mut buf).unwrap();
+        assert_eq!(amt, 5);
+        assert_eq!(from, client.addr_local().unwrap());
+
+        // Replies go back to the sender
+        server.try_send_to(b"ok", from).unwrap();
+        assert_eq!(client.try_recv_from(&mut buf).unwrap().0, 2);
+        assert_eq!(
+            client.try_recv_from(&mut buf).unwrap_err(),
+            NetworkError::WouldBlock
+        );
+
+        drop(server);
+        assert_eq!(
+            client.try_send_to(b"hello", target).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+    }
+
+    #[test]
+    fn bindings_are_released() {
+        let registry = Arc::new(UnixSocketRegistry::new());
+        let path = Path::new("/run/app.sock");
+
+        // Unlinking the path makes the socket unreachable but it keeps its name
+        let binding = registry.bind(path.to_path_buf());
+        let _listener = registry.listen(binding.addr()).unwrap();
+        registry.connect(path, None).unwrap();
+        registry.unlink(path);
+        assert_eq!(
+            registry.connect(path, None).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+        assert_eq!(registry.path_of(&binding.addr()).as_deref(), Some(path));
+
+        // A new socket can be bound to the path, closing the old one must
+        // not release it
+        let rebound = registry.bind(path.to_path_buf());
+        drop(binding);
+        assert_eq!(
+            registry.resolve_datagram(path).unwrap_err(),
+            NetworkError::ConnectionRefused
+        );
+        let _server = registry.bind_datagram(rebound.addr());
+        assert_eq!(registry.resolve_datagram(path).unwrap(), rebound.addr());
+
+        // Closing the socket releases everything it registered
+        let addr = rebound.addr();
+        drop(rebound);
+        assert!(registry.state.lock().unwrap().paths.is_empty());
+        assert!(registry.state.lock().unwrap().datagrams.is_empty());
+        assert_eq!(registry.path_of(&addr), None);
+    }
+}
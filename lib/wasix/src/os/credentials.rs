@@ -0,0 +1,386 @@
+//! Users and groups that processes run as.
+//!
+//! Every process has a set of [`Credentials`] that start off as the
+//! [`Identity`] configured on the `WasiEnvBuilder` and are inherited by
+//! the processes it forks. The virtual file systems do not track ownership
+//! and nothing should be written to the files of the host, so the owner
+//! and mode of a file are kept on its inode in the sandbox. Files that
+//! nobody has taken ownership of, such as those of package volumes, belong
+//! to the [`DefaultOwner`].
+
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+use wasmer_wasix_types::wasi::{Errno, Gid, Uid};
+
+/// Extended attribute through which the owner and mode of a file are read
+/// and changed as `uid:gid:mode` (with the mode in octal), its value comes
+/// from the inode of the file rather than from the file
+pub const OWNER_XATTR: &str = "user.wasix.owner";
+
+/// Permission to read a file or list a directory
+pub const ACCESS_READ: u32 = 0o4;
+/// Permission to write a file or to add and remove directory entries
+pub const ACCESS_WRITE: u32 = 0o2;
+/// Permission to execute a file or to search a directory
+pub const ACCESS_EXECUTE: u32 = 0o1;
+
+/// User and groups that a program is started as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    /// Name of the user in `/etc/passwd`
+    pub user: String,
+    pub uid: Uid,
+    /// Primary group of the user
+    pub gid: Gid,
+    /// Supplementary groups of the user
+    pub groups: Vec<Gid>,
+    /// Home directory of the user in `/etc/passwd`
+    pub home: String,
+}
+
+impl Identity {
+    pub fn new(user: impl Into<String>, uid: Uid, gid: Gid) -> Self {
+        let user = user.into();
+        let home = if uid == 0 {
+            "/root".to_string()
+        } else {
+            format!("/home/{user}")
+        };
+        Self {
+            user,
+            uid,
+            gid,
+            groups: Vec::new(),
+            home,
+        }
+    }
+
+    /// Sets the supplementary groups of the user
+    pub fn with_groups(mut self, groups: impl IntoIterator<Item = Gid>) -> Self {
+        self.groups = groups.into_iter().collect();
+        self
+    }
+
+    /// Contents of the `/etc/passwd` file that describes this user
+    pub fn passwd(&self) -> String {
+        let mut passwd = String::from("root:x:0:0:root:/root:/bin/sh\n");
+        if self.uid != 0 {
+            let _ = writeln!(
+                passwd,
+                "{user}:x:{uid}:{gid}:{user}:{home}:/bin/sh",
+                user = self.user,
+                uid = self.uid,
+                gid = self.gid,
+                home = self.home
+            );
+        }
+        passwd
+    }
+
+    /// Contents of the `/etc/group` file that describes the groups of
+    /// this user
+    pub fn group(&self) -> String {
+        let mut gids = vec![0, self.gid];
+        gids.extend(self.groups.iter().copied());
+        gids.sort_unstable();
+        gids.dedup();
+
+        let mut group = String::new();
+        for gid in gids {
+            let name = match gid {
+                0 => "root".to_string(),
+                gid if gid == self.gid => self.user.clone(),
+                gid => format!("group{gid}"),
+            };
+            let members = if self.uid != 0 && self.groups.contains(&gid) {
+                self.user.as_str()
+            } else {
+                ""
+            };
+            let _ = writeln!(group, "{name}:x:{gid}:{members}");
+        }
+        group
+    }
+}
+
+impl Default for Identity {
+    /// Programs run as `root` unless another identity is configured
+    fn default() -> Self {
+        Self::new("root", 0, 0)
+    }
+}
+
+/// Owner and permission bits of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct FileOwner {
+    pub uid: Uid,
+    pub gid: Gid,
+    pub mode: u32,
+}
+
+impl FileOwner {
+    /// Parses the value of the [`OWNER_XATTR`] attribute
+    pub fn from_xattr(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?;
+        let mut parts = value.trim().split(':');
+        let uid = parts.next()?.parse().ok()?;
+        let gid = parts.next()?.parse().ok()?;
+        let mode = u32::from_str_radix(parts.next()?, 8).ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            uid,
+            gid,
+            mode: mode & 0o7777,
+        })
+    }
+
+    /// Value of the [`OWNER_XATTR`] attribute
+    pub fn to_xattr(&self) -> Vec<u8> {
+        format!("{}:{}:{:o}", self.uid, self.gid, self.mode).into_bytes()
+    }
+}
+
+/// Owner and mode of the files and directories that nobody has taken
+/// ownership of, which are the files that existed before the program
+/// started and those of the packages that it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultOwner {
+    pub uid: Uid,
+    pub gid: Gid,
+    /// Permission bits of files
+    pub file_mode: u32,
+    /// Permission bits of directories
+    pub dir_mode: u32,
+}
+
+impl DefaultOwner {
+    /// Owner of a file or directory that has no owner of its own
+    pub fn owner(&self, is_dir: bool) -> FileOwner {
+        FileOwner {
+            uid: self.uid,
+            gid: self.gid,
+            mode: if is_dir {
+                self.dir_mode
+            } else {
+                self.file_mode
+            },
+        }
+    }
+}
+
+impl Default for DefaultOwner {
+    /// Files belong to `root` and may be read by everyone
+    fn default() -> Self {
+        Self {
+            uid: 0,
+            gid: 0,
+            file_mode: 0o644,
+            dir_mode: 0o755,
+        }
+    }
+}
+
+/// What the file system checks the permission bits of files against
+#[derive(Debug, Clone)]
+pub(crate) struct FsPermissions {
+    /// Credentials of the process, shared with its `WasiState`
+    pub credentials: Arc<Mutex<Credentials>>,
+    pub default_owner: DefaultOwner,
+}
+
+/// Real, effective and saved IDs of a process along with its
+/// supplementary groups
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct Credentials {
+    pub uid: Uid,
+    pub euid: Uid,
+    pub suid: Uid,
+    pub gid: Gid,
+    pub egid: Gid,
+    pub sgid: Gid,
+    pub groups: Vec<Gid>,
+}
+
+impl Credentials {
+    pub fn new(identity: &Identity) -> Self {
+        Self {
+            uid: identity.uid,
+            euid: identity.uid,
+            suid: identity.uid,
+            gid: identity.gid,
+            egid: identity.gid,
+            sgid: identity.gid,
+            groups: identity.groups.clone(),
+        }
+    }
+
+    /// Returns true if the process runs with an effective user ID of `root`
+    pub fn is_privileged(&self) -> bool {
+        self.euid == 0
+    }
+
+    /// Returns true if this process may look into the private details of
+    /// a process with the `other` credentials (such as its environment and
+    /// its file descriptors), which needs `root` or all the user IDs of the
+    /// other process to be the effective user ID of this one
+    pub fn may_inspect(&self, other: &Credentials) -> bool {
+        self.is_privileged()
+            || (self.euid == other.uid && self.euid == other.euid && self.euid == other.suid)
+    }
+
+    /// Returns true if the process is a member of a group, either through
+    /// its effective group ID or its supplementary groups
+    pub fn in_group(&self, gid: Gid) -> bool {
+        self.egid == gid || self.groups.contains(&gid)
+    }
+
+    /// Behaves like `setuid()`, a privileged process changes all of its
+    /// user IDs while others may only change their effective user ID to
+    /// their real or saved user ID
+    pub fn set_uid(&mut self, uid: Uid) -> Result<(), Errno> {
+        if self.is_privileged() {
+            self.uid = uid;
+            self.euid = uid;
+            self.suid = uid;
+            Ok(())
+        } else {
+            self.set_euid(uid)
+        }
+    }
+
+    /// Behaves like `seteuid()`
+    pub fn set_euid(&mut self, uid: Uid) -> Result<(), Errno> {
+        if !self.is_privileged() && uid != self.uid && uid != self.suid {
+            return Err(Errno::Perm);
+        }
+        self.euid = uid;
+        Ok(())
+    }
+
+    /// Behaves like `setgid()`
+    pub fn set_gid(&mut self, gid: Gid) -> Result<(), Errno> {
+        if self.is_privileged() {
+            self.gid = gid;
+            self.egid = gid;
+            self.sgid = gid;
+            Ok(())
+        } else {
+            self.set_egid(gid)
+        }
+    }
+
+    /// Behaves like `setegid()`
+    pub fn set_egid(&mut self, gid: Gid) -> Result<(), Errno> {
+        if !self.is_privileged() && gid != self.gid && gid != self.sgid {
+            return Err(Errno::Perm);
+        }
+        self.egid = gid;
+        Ok(())
+    }
+
+    /// Replaces the supplementary groups, which only a privileged process
+    /// may do
+    pub fn set_groups(&mut self, groups: Vec<Gid>) -> Result<(), Errno> {
+        if !self.is_privileged() {
+            return Err(Errno::Perm);
+        }
+        self.groups = groups;
+        Ok(())
+    }
+
+    /// Returns true if the permission bits of a file grant all the
+    /// accesses in `access` (a combination of the `ACCESS_*` constants)
+    /// to this process, `root` is granted everything
+    pub fn may_access(&self, owner: &FileOwner, access: u32) -> bool {
+        if self.is_privileged() {
+            return true;
+        }
+        let bits = if self.euid == owner.uid {
+            owner.mode >> 6
+        } else if self.in_group(owner.gid) {
+            owner.mode >> 3
+        } else {
+            owner.mode
+        };
+        bits & access == access
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::new(&Identity::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credentials_follow_posix_rules() {
+        let mut creds = Credentials::default();
+        creds.set_groups(vec![10]).unwrap();
+        creds.set_euid(1000).unwrap();
+        assert!(!creds.is_privileged());
+        assert_eq!(creds.set_groups(vec![]), Err(Errno::Perm));
+        assert_eq!(creds.set_uid(2000), Err(Errno::Perm));
+
+        // The saved user ID allows the process to become root again
+        creds.set_uid(0).unwrap();
+        creds.set_uid(1000).unwrap();
+        assert_eq!((creds.uid, creds.euid, creds.suid), (1000, 1000, 1000));
+        assert_eq!(creds.set_euid(0), Err(Errno::Perm));
+
+        let owner = FileOwner::from_xattr(b"0:10:750").unwrap();
+        assert_eq!(owner.to_xattr(), b"0:10:750".to_vec());
+        assert!(creds.may_access(&owner, ACCESS_READ | ACCESS_EXECUTE));
+        assert!(!creds.may_access(&owner, ACCESS_WRITE));
+        creds.groups.clear();
+        assert!(!creds.may_access(&owner, ACCESS_READ));
+    }
+
+    #[test]
+    fn test_identity_generates_user_database() {
+        let identity = Identity::new("alice", 1000, 1000).with_groups([0, 27]);
+        assert_eq!(
+            identity.passwd(),
+            "root:x:0:0:root:/root:/bin/sh\nalice:x:1000:1000:alice:/home/alice:/bin/sh\n"
+        );
+        assert_eq!(
+            identity.group(),
+            "root:x:0:alice\ngroup27:x:27:alice\nalice:x:1000:\n"
+        );
+        assert_eq!(
+            Identity::default().passwd(),
+            "root:x:0:0:root:/root:/bin/sh\n"
+        );
+    }
+
+    #[test]
+    fn test_default_owner_depends_on_the_kind_of_file() {
+        let owner = DefaultOwner::default();
+        assert_eq!(
+            owner.owner(false),
+            FileOwner::from_xattr(b"0:0:644").unwrap()
+        );
+        assert_eq!(
+            owner.owner(true),
+            FileOwner::from_xattr(b"0:0:755").unwrap()
+        );
+
+        // Everyone may read and search the directories of root but not
+        // change them
+        let creds = Credentials::new(&Identity::new("alice", 1000, 1000));
+        assert!(creds.may_access(&owner.owner(true), ACCESS_READ | ACCESS_EXECUTE));
+        assert!(!creds.may_access(&owner.owner(true), ACCESS_WRITE));
+    }
+}
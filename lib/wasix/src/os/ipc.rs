@@ -0,0 +1,479 @@
+//! Named inter-process communication objects (POSIX shared memory,
+//! semaphores and message queues) that are shared between all the
+//! processes of a [`WasiControlPlane`](crate::WasiControlPlane).
+//!
+//! Shared memory objects live in the file system that is mounted at
+//! `/dev/shm` (see [`ShmFileSystem`](crate::fs::ShmFileSystem)), which is
+//! only available on Unix hosts with the `sys` feature as it maps the pages
+//! of the objects into linear memory.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Notify, Semaphore};
+#[cfg(all(unix, feature = "sys"))]
+use virtual_fs::FileSystem;
+use virtual_fs::{limiter::DynFsMemoryLimiter, VirtualFile};
+use wasmer_wasix_types::wasi::Errno;
+
+#[cfg(all(unix, feature = "sys"))]
+use crate::fs::ShmFileSystem;
+
+/// Longest name that an IPC object can have
+pub const IPC_NAME_MAX: usize = 255;
+
+/// Largest value that a named semaphore can hold
+pub const SEM_VALUE_MAX: u32 = i32::MAX as u32;
+
+/// Limits on the shared memory objects of a control plane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShmLimits {
+    /// Maximum number of objects, including those that were removed but
+    /// are still open or mapped
+    pub max_objects: usize,
+    /// Maximum total size of the objects in bytes
+    pub max_bytes: u64,
+}
+
+impl Default for ShmLimits {
+    fn default() -> Self {
+        Self {
+            max_objects: 1024,
+            max_bytes: 1 << 30,
+        }
+    }
+}
+
+/// Validates the name of an IPC object, which is a leading slash followed
+/// by a name that contains no other slashes, and strips the slash
+pub fn ipc_name(name: &str) -> Result<&str, Errno> {
+    let name = name.strip_prefix('/').unwrap_or(name);
+    if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+        return Err(Errno::Inval);
+    }
+    if name.len() > IPC_NAME_MAX {
+        return Err(Errno::Nametoolong);
+    }
+    Ok(name)
+}
+
+/// A semaphore that has been opened with `sem_open`
+#[derive(Debug)]
+pub struct NamedSemaphore {
+    permits: Semaphore,
+}
+
+impl NamedSemaphore {
+    fn new(value: u32) -> Self {
+        Self {
+            permits: Semaphore::new(value as usize),
+        }
+    }
+
+    /// Current value of the semaphore
+    pub fn value(&self) -> u32 {
+        self.permits.available_permits() as u32
+    }
+
+    /// Increments the semaphore, waking one of the waiters
+    pub fn post(&self) -> Result<(), Errno> {
+        if self.value() >= SEM_VALUE_MAX {
+            return Err(Errno::Overflow);
+        }
+        self.permits.add_permits(1);
+        Ok(())
+    }
+
+    /// Decrements the semaphore if that can be done without blocking
+    pub fn try_wait(&self) -> Result<(), Errno> {
+        self.permits
+            .try_acquire()
+            .map(|permit| permit.forget())
+            .map_err(|_| Errno::Again)
+    }
+
+    /// Waits until the semaphore can be decremented
+    pub async fn wait(&self) -> Result<(), Errno> {
+        self.permits
+            .acquire()
+            .await
+            .map(|permit| permit.forget())
+            .map_err(|_| Errno::Inval)
+    }
+}
+
+/// A message queue that has been opened with `mq_open`
+#[derive(Debug)]
+pub struct MessageQueue {
+    max_messages: usize,
+    max_message_size: usize,
+    /// Messages keyed by their priority, the highest priority is received
+    /// first and messages of the same priority in the order they were sent
+    messages: Mutex<BTreeMap<u32, VecDeque<Vec<u8>>>>,
+    count: Mutex<usize>,
+    readable: Notify,
+    writable: Notify,
+}
+
+impl MessageQueue {
+    fn new(max_messages: usize, max_message_size: usize) -> Self {
+        Self {
+            max_messages,
+            max_message_size,
+            messages: Default::default(),
+            count: Mutex::new(0),
+            readable: Notify::new(),
+            writable: Notify::new(),
+        }
+    }
+
+    /// Largest number of messages that the queue can hold
+    pub fn max_messages(&self) -> usize {
+        self.max_messages
+    }
+
+    /// Largest size of a message
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Number of messages that are waiting to be received
+    pub fn len(&self) -> usize {
+        *self.count.lock().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a message to the queue if it is not full
+    pub fn try_send(&self, data: &[u8], priority: u32) -> Result<(), Errno> {
+        if data.len() > self.max_message_size {
+            return Err(Errno::Msgsize);
+        }
+        {
+            let mut count = self.count.lock().unwrap();
+            if *count >= self.max_messages {
+                return Err(Errno::Again);
+            }
+            *count += 1;
+            self.messages
+                .lock()
+                .unwrap()
+                .entry(priority)
+                .or_default()
+                .push_back(data.to_vec());
+        }
+        self.readable.notify_one();
+        Ok(())
+    }
+
+    /// Removes the oldest message with the highest priority from the queue
+    pub fn try_receive(&self) -> Result<(Vec<u8>, u32), Errno> {
+        let message = {
+            let mut count = self.count.lock().unwrap();
+            let mut messages = self.messages.lock().unwrap();
+            let mut entry = messages.last_entry().ok_or(Errno::Again)?;
+            let priority = *entry.key();
+            let data = entry.get_mut().pop_front().ok_or(Errno::Again)?;
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+            *count -= 1;
+            (data, priority)
+        };
+        self.writable.notify_one();
+        Ok(message)
+    }
+
+    /// Waits until there is room in the queue and then adds the message
+    pub async fn send(&self, data: &[u8], priority: u32) -> Result<(), Errno> {
+        loop {
+            let writable = self.writable.notified();
+            match self.try_send(data, priority) {
+                Err(Errno::Again) => writable.await,
+                res => return res,
+            }
+        }
+    }
+
+    /// Waits until there is a message in the queue and then removes it
+    pub async fn receive(&self) -> Result<(Vec<u8>, u32), Errno> {
+        loop {
+            let readable = self.readable.notified();
+            match self.try_receive() {
+                Err(Errno::Again) => readable.await,
+                res => return res,
+            }
+        }
+    }
+}
+
+/// How a named object should be opened
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IpcOpenOptions {
+    /// Create the object if it does not exist
+    pub create: bool,
+    /// Fail if the object already exists (only with `create`)
+    pub exclusive: bool,
+}
+
+impl IpcOpenOptions {
+    fn open<T>(
+        &self,
+        objects: &mut HashMap<String, Arc<T>>,
+        name: &str,
+        create: impl FnOnce() -> T,
+    ) -> Result<Arc<T>, Errno> {
+        match objects.get(name) {
+            Some(_) if self.create && self.exclusive => Err(Errno::Exist),
+            Some(object) => Ok(object.clone()),
+            None if self.create => {
+                let object = Arc::new(create());
+                objects.insert(name.to_string(), object.clone());
+                Ok(object)
+            }
+            None => Err(Errno::Noent),
+        }
+    }
+}
+
+/// Keeps track of the named IPC objects so that they can be opened by
+/// every process
+#[derive(Debug, Default)]
+pub struct IpcRegistry {
+    #[cfg(all(unix, feature = "sys"))]
+    shm: ShmFileSystem,
+    semaphores: Mutex<HashMap<String, Arc<NamedSemaphore>>>,
+    queues: Mutex<HashMap<String, Arc<MessageQueue>>>,
+}
+
+impl IpcRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry whose shared memory objects are subject to
+    /// `limits`, their bytes are also charged to `limiter`
+    #[cfg_attr(not(all(unix, feature = "sys")), allow(unused_variables))]
+    pub fn with_shm_limits(limits: ShmLimits, limiter: DynFsMemoryLimiter) -> Self {
+        Self {
+            #[cfg(all(unix, feature = "sys"))]
+            shm: ShmFileSystem::new(limits, Some(limiter)),
+            ..Default::default()
+        }
+    }
+
+    /// File system that holds the shared memory objects
+    #[cfg(all(unix, feature = "sys"))]
+    pub fn shm_fs(&self) -> &ShmFileSystem {
+        &self.shm
+    }
+
+    /// Path of a shared memory object within [`IpcRegistry::shm_fs`]
+    pub fn shm_path(name: &str) -> Result<PathBuf, Errno> {
+        Ok(PathBuf::from("/").join(ipc_name(name)?))
+    }
+
+    /// Opens (or creates) a shared memory object
+    #[cfg(all(unix, feature = "sys"))]
+    pub fn shm_open(
+        &self,
+        name: &str,
+        options: IpcOpenOptions,
+        writable: bool,
+        truncate: bool,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>, Errno> {
+        self.shm
+            .new_open_options()
+            .read(true)
+            .write(writable)
+            .create(options.create)
+            .create_new(options.create && options.exclusive)
+            .truncate(truncate)
+            .open(Self::shm_path(name)?)
+            .map_err(crate::fs::fs_error_into_wasi_err)
+    }
+
+    /// Shared memory objects are not supported on this host
+    #[cfg(not(all(unix, feature = "sys")))]
+    pub fn shm_open(
+        &self,
+        name: &str,
+        _options: IpcOpenOptions,
+        _writable: bool,
+        _truncate: bool,
+    ) -> Result<Box<dyn VirtualFile + Send + Sync + 'static>, Errno> {
+        Self::shm_path(name)?;
+        Err(Errno::Notsup)
+    }
+
+    /// Removes the name of a shared memory object, mappings and file
+    /// descriptors that refer to it remain valid
+    #[cfg(all(unix, feature = "sys"))]
+    pub fn shm_unlink(&self, name: &str) -> Result<(), Errno> {
+        self.shm
+            .remove_file(&Self::shm_path(name)?)
+            .map_err(crate::fs::fs_error_into_wasi_err)
+    }
+
+    /// Shared memory objects are not supported on this host
+    #[cfg(not(all(unix, feature = "sys")))]
+    pub fn shm_unlink(&self, name: &str) -> Result<(), Errno> {
+        Self::shm_path(name)?;
+        Err(Errno::Notsup)
+    }
+
+    /// Opens (or creates with `value`) a named semaphore
+    pub fn sem_open(
+        &self,
+        name: &str,
+        options: IpcOpenOptions,
+        value: u32,
+    ) -> Result<Arc<NamedSemaphore>, Errno> {
+        let name = ipc_name(name)?;
+        if value > SEM_VALUE_MAX {
+            return Err(Errno::Inval);
+        }
+        let mut semaphores = self.semaphores.lock().unwrap();
+        options.open(&mut semaphores, name, || NamedSemaphore::new(value))
+    }
+
+    /// Removes the name of a semaphore, it is destroyed once every process
+    /// has closed it
+    pub fn sem_unlink(&self, name: &str) -> Result<(), Errno> {
+        let name = ipc_name(name)?;
+        let mut semaphores = self.semaphores.lock().unwrap();
+        semaphores.remove(name).map(|_| ()).ok_or(Errno::Noent)
+    }
+
+    /// Opens (or creates with the given capacity) a named message queue
+    pub fn mq_open(
+        &self,
+        name: &str,
+        options: IpcOpenOptions,
+        max_messages: usize,
+        max_message_size: usize,
+    ) -> Result<Arc<MessageQueue>, Errno> {
+        let name = ipc_name(name)?;
+        if options.create && (max_messages == 0 || max_message_size == 0) {
+            return Err(Errno::Inval);
+        }
+        let mut queues = self.queues.lock().unwrap();
+        options.open(&mut queues, name, || {
+            MessageQueue::new(max_messages, max_message_size)
+        })
+    }
+
+    /// Removes the name of a message queue, it is destroyed once every
+    /// process has closed it
+    pub fn mq_unlink(&self, name: &str) -> Result<(), Errno> {
+        let name = ipc_name(name)?;
+        let mut queues = self.queues.lock().unwrap();
+        queues.remove(name).map(|_| ()).ok_or(Errno::Noent)
+    }
+}
+
+/// A message queue that a process has open
+#[derive(Debug, Clone)]
+pub(crate) struct OpenQueue {
+    pub queue: Arc<MessageQueue>,
+    /// Sending and receiving fail with `Again` rather than waiting
+    pub nonblocking: bool,
+}
+
+/// The named semaphores and message queues that a process has open,
+/// which are inherited by forked processes
+#[derive(Debug, Default, Clone)]
+pub(crate) struct IpcHandles {
+    seed: u32,
+    pub semaphores: HashMap<u32, Arc<NamedSemaphore>>,
+    pub queues: HashMap<u32, OpenQueue>,
+}
+
+impl IpcHandles {
+    fn next_handle(&mut self) -> u32 {
+        self.seed += 1;
+        self.seed
+    }
+
+    pub fn insert_semaphore(&mut self, sem: Arc<NamedSemaphore>) -> u32 {
+        let handle = self.next_handle();
+        self.semaphores.insert(handle, sem);
+        handle
+    }
+
+    pub fn insert_queue(&mut self, queue: OpenQueue) -> u32 {
+        let handle = self.next_handle();
+        self.queues.insert(handle, queue);
+        handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipc_names_are_validated() {
+        assert_eq!(ipc_name("/queue"), Ok("queue"));
+        assert_eq!(ipc_name("queue"), Ok("queue"));
+        assert_eq!(ipc_name("/"), Err(Errno::Inval));
+        assert_eq!(ipc_name("/a/b"), Err(Errno::Inval));
+        assert_eq!(ipc_name(&"x".repeat(300)), Err(Errno::Nametoolong));
+    }
+
+    #[test]
+    fn named_semaphores_are_shared() {
+        let registry = IpcRegistry::new();
+        let create = IpcOpenOptions {
+            create: true,
+            exclusive: true,
+        };
+        let a = registry.sem_open("/sem", create, 1).unwrap();
+        assert_eq!(
+            registry.sem_open("/sem", create, 1).unwrap_err(),
+            Errno::Exist
+        );
+        let b = registry
+            .sem_open("/sem", IpcOpenOptions::default(), 0)
+            .unwrap();
+
+        a.try_wait().unwrap();
+        assert_eq!(b.try_wait(), Err(Errno::Again));
+        b.post().unwrap();
+        assert_eq!(a.value(), 1);
+
+        registry.sem_unlink("/sem").unwrap();
+        assert_eq!(
+            registry
+                .sem_open("/sem", IpcOpenOptions::default(), 0)
+                .unwrap_err(),
+            Errno::Noent
+        );
+        assert_eq!(b.value(), 1);
+    }
+
+    #[tokio::test]
+    async fn message_queues_order_by_priority() {
+        let registry = IpcRegistry::new();
+        let create = IpcOpenOptions {
+            create: true,
+            exclusive: false,
+        };
+        let queue = registry.mq_open("/mq", create, 2, 8).unwrap();
+
+        queue.send(b"low", 1).await.unwrap();
+        queue.send(b"high", 5).await.unwrap();
+        assert_eq!(queue.try_send(b"full", 0), Err(Errno::Again));
+        assert_eq!(queue.try_send(b"too large!", 0), Err(Errno::Msgsize));
+
+        assert_eq!(queue.receive().await.unwrap(), (b"high".to_vec(), 5));
+        assert_eq!(queue.receive().await.unwrap(), (b"low".to_vec(), 1));
+        assert!(queue.is_empty());
+        assert_eq!(queue.try_receive(), Err(Errno::Again));
+    }
+}
@@ -1,5 +1,8 @@
 pub mod common;
 pub mod console;
+pub mod credentials;
+pub mod ipc;
+pub mod pty;
 pub mod tty;
 
 pub mod command;
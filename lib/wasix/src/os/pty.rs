@@ -0,0 +1,807 @@
+//! Pseudo-terminals that processes allocate for the programs they run.
+//!
+//! A PTY is a pair of files, the master side is held by the program that
+//! emulates the terminal (`script`, `tmux`, an SSH server) and the slave
+//! side becomes the terminal of the programs that it runs. Whatever is
+//! written to the master passes through a line discipline before it can be
+//! read from the slave, which echoes the input, edits lines in canonical
+//! mode and turns `^C`, `^Z` and `^\` into signals for the process group in
+//! the foreground. Whatever is written to the slave is read from the master.
+//!
+//! New PTYs are allocated by opening `/dev/pts/ptmx` and their slave sides
+//! are found at `/dev/pts/<index>`.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, SeekFrom},
+    ops::Deref,
+    pin::Pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll, Waker},
+};
+
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite};
+use virtual_fs::{FsError, VirtualFile};
+use wasmer_wasix_types::wasi::{Errno, Fd as WasiFd, Signal, Tty};
+
+use super::tty::TtyForeground;
+use crate::fs::{Kind, WasiFs};
+
+/// Most PTYs that can be open at the same time
+pub const PTY_MAX: u32 = 4096;
+
+/// Most bytes that are buffered in each direction of a PTY, writers wait
+/// (or get `EAGAIN`) until the other side reads some of them
+pub const PTY_BUFFER_SIZE: usize = 4096;
+
+const CTRL_C: u8 = 0x03;
+const CTRL_D: u8 = 0x04;
+const CTRL_U: u8 = 0x15;
+const CTRL_Z: u8 = 0x1a;
+const CTRL_BACKSLASH: u8 = 0x1c;
+const BACKSPACE: u8 = 0x08;
+const DEL: u8 = 0x7f;
+
+/// Size of the window of a PTY in characters and in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtySize {
+    pub cols: u32,
+    pub rows: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for PtySize {
+    fn default() -> Self {
+        Self {
+            cols: 80,
+            rows: 25,
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+/// Settings of the line discipline of a PTY
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtyModes {
+    /// Input is echoed back to the master
+    pub echo: bool,
+    /// Input is edited a line at a time
+    pub canonical: bool,
+    /// The keyboard generates signals (`^C`, `^Z` and `^\`), in canonical
+    /// and in raw mode alike
+    pub signals: bool,
+    /// Line feeds written to the slave are sent to the master as `\r\n`
+    pub line_feeds: bool,
+}
+
+impl Default for PtyModes {
+    fn default() -> Self {
+        Self {
+            echo: true,
+            canonical: true,
+            signals: true,
+            line_feeds: true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PtyState {
+    modes: PtyModes,
+    size: PtySize,
+    /// Input that the slave can read
+    input: VecDeque<u8>,
+    /// Lengths of the lines in the input in canonical mode, a read never
+    /// returns more than one of them
+    lines: VecDeque<usize>,
+    /// Line that is being edited in canonical mode
+    line: Vec<u8>,
+    /// An end-of-file was typed at the start of a line
+    eof: bool,
+    /// Output that the master can read
+    output: VecDeque<u8>,
+    master_open: bool,
+    slaves_open: usize,
+    slave_opened: bool,
+    /// Slave that waits for input
+    input_reader: Option<Waker>,
+    /// Master that waits for room in the input
+    input_writer: Option<Waker>,
+    /// Master that waits for output
+    output_reader: Option<Waker>,
+    /// Slave that waits for room in the output
+    output_writer: Option<Waker>,
+}
+
+/// Remembers the task that waits on one side of a buffer, the waker is
+/// only cloned when another task starts waiting
+fn register_waker(slot: &mut Option<Waker>, cx: &Context<'_>) {
+    if !slot
+        .as_ref()
+        .is_some_and(|waker| waker.will_wake(cx.waker()))
+    {
+        *slot = Some(cx.waker().clone());
+    }
+}
+
+fn wake(slot: &mut Option<Waker>) {
+    if let Some(waker) = slot.take() {
+        waker.wake();
+    }
+}
+
+impl PtyState {
+    /// Writes to the master after the output processing, returning how
+    /// many bytes fit in the output
+    fn write_output(&mut self, data: &[u8]) -> usize {
+        let mut written = 0;
+        for byte in data.iter().copied() {
+            let crlf = byte == b'\n' && self.modes.line_feeds;
+            if self.output.len() + crlf as usize >= PTY_BUFFER_SIZE {
+                break;
+            }
+            if crlf {
+                self.output.push_back(b'\r');
+            }
+            self.output.push_back(byte);
+            written += 1;
+        }
+        if written > 0 {
+            wake(&mut self.output_reader);
+        }
+        written
+    }
+
+    /// Echoes input back to the master, whatever does not fit in the
+    /// output is dropped
+    fn echo(&mut self, data: &[u8]) {
+        if self.modes.echo {
+            self.write_output(data);
+        }
+    }
+
+    fn push_input(&mut self, data: &[u8]) {
+        self.input.extend(data.iter().copied());
+        wake(&mut self.input_reader);
+    }
+
+    /// Makes the line that is being edited available to the slave
+    fn push_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.lines.push_back(line.len());
+        self.push_input(&line);
+    }
+
+    /// Passes input from the master through the line discipline, returning
+    /// how many bytes were taken before the input filled up and the
+    /// signals that the keyboard generated
+    fn receive(&mut self, data: &[u8]) -> (usize, Vec<Signal>) {
+        let mut signals = Vec::new();
+        let mut taken = 0;
+        for byte in data.iter().copied() {
+            if self.input.len() >= PTY_BUFFER_SIZE {
+                break;
+            }
+            taken += 1;
+            let signal = match byte {
+                CTRL_C => Some(Signal::Sigint),
+                CTRL_Z => Some(Signal::Sigtstp),
+                CTRL_BACKSLASH => Some(Signal::Sigquit),
+                _ => None,
+            };
+            match signal {
+                Some(signal) if self.modes.signals => {
+                    signals.push(signal);
+                    self.line.clear();
+                    self.echo(&[b'^', byte + b'@', b'\n']);
+                    continue;
+                }
+                _ if !self.modes.canonical => {
+                    self.push_input(&[byte]);
+                    self.echo(&[byte]);
+                    continue;
+                }
+                _ => {}
+            }
+            match byte {
+                CTRL_D => {
+                    if self.line.is_empty() {
+                        self.eof = true;
+                        wake(&mut self.input_reader);
+                    } else {
+                        self.push_line();
+                    }
+                }
+                b'\r' | b'\n' => {
+                    self.line.push(b'\n');
+                    self.push_line();
+                    self.echo(b"\n");
+                }
+                BACKSPACE | DEL => {
+                    if self.line.pop().is_some() {
+                        self.echo(b"\x08 \x08");
+                    }
+                }
+                CTRL_U => {
+                    let erase = b"\x08 \x08".repeat(self.line.len());
+                    self.line.clear();
+                    self.echo(&erase);
+                }
+                // Like a real terminal, a line only takes as much as the
+                // input can hold and the rest of what is typed is dropped
+                byte if self.input.len() + self.line.len() + 1 < PTY_BUFFER_SIZE => {
+                    self.line.push(byte);
+                    self.echo(&[byte]);
+                }
+                _ => {}
+            }
+        }
+        (taken, signals)
+    }
+}
+
+/// A pseudo-terminal that is shared by its master and slave sides
+#[derive(Debug)]
+pub struct Pty {
+    index: u32,
+    foreground: TtyForeground,
+    state: Mutex<PtyState>,
+}
+
+impl Pty {
+    fn new(index: u32) -> Self {
+        Self {
+            index,
+            foreground: TtyForeground::default(),
+            state: Mutex::new(PtyState {
+                master_open: true,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Number of the PTY, its slave side is `/dev/pts/<index>`
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Process group in the foreground of the PTY, which receives the
+    /// signals that are generated by the keyboard
+    pub fn foreground(&self) -> &TtyForeground {
+        &self.foreground
+    }
+
+    pub fn modes(&self) -> PtyModes {
+        self.state.lock().unwrap().modes
+    }
+
+    /// Changes the line discipline, any line that was being edited is made
+    /// available to the slave when canonical mode is turned off and the
+    /// input that was typed in raw mode reads as one line when it is
+    /// turned back on
+    pub fn set_modes(&self, modes: PtyModes) {
+        let mut state = self.state.lock().unwrap();
+        if state.modes.canonical && !modes.canonical {
+            if !state.line.is_empty() {
+                let line = std::mem::take(&mut state.line);
+                state.push_input(&line);
+            }
+            state.lines.clear();
+        } else if !state.modes.canonical && modes.canonical && !state.input.is_empty() {
+            let len = state.input.len();
+            state.lines.push_back(len);
+        }
+        state.modes = modes;
+    }
+
+    pub fn size(&self) -> PtySize {
+        self.state.lock().unwrap().size
+    }
+
+    /// Resizes the window, which sends `SIGWINCH` to the foreground process
+    /// group if the size changed
+    pub fn set_size(&self, size: PtySize) {
+        let changed = {
+            let mut state = self.state.lock().unwrap();
+            std::mem::replace(&mut state.size, size) != size
+        };
+        if changed {
+            self.foreground.signal(Signal::Sigwinch);
+        }
+    }
+
+    /// State of the PTY in the layout used by `tty_get`
+    pub fn tty(&self) -> Tty {
+        let state = self.state.lock().unwrap();
+        Tty {
+            cols: state.size.cols,
+            rows: state.size.rows,
+            width: state.size.width,
+            height: state.size.height,
+            stdin_tty: false,
+            stdout_tty: false,
+            stderr_tty: false,
+            echo: state.modes.echo,
+            line_buffered: state.modes.canonical,
+        }
+    }
+
+    /// Applies the state given to `tty_set`
+    pub fn set_tty(&self, tty: &Tty) {
+        let modes = PtyModes {
+            echo: tty.echo,
+            canonical: tty.line_buffered,
+            ..self.modes()
+        };
+        self.set_modes(modes);
+        self.set_size(PtySize {
+            cols: tty.cols,
+            rows: tty.rows,
+            width: tty.width,
+            height: tty.height,
+        });
+    }
+
+    /// Opens another slave side of the PTY
+    pub fn open_slave(self: &Arc<Self>) -> Result<PtySlave, FsError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.master_open {
+            return Err(FsError::EntryNotFound);
+        }
+        state.slaves_open += 1;
+        state.slave_opened = true;
+        Ok(PtySlave { pty: self.clone() })
+    }
+
+    fn poll_write_master(&self, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        let (taken, signals) = {
+            let mut state = self.state.lock().unwrap();
+            let (taken, signals) = state.receive(data);
+            if taken == 0 && !data.is_empty() {
+                register_waker(&mut state.input_writer, cx);
+                return Poll::Pending;
+            }
+            (taken, signals)
+        };
+        for signal in signals {
+            self.foreground.signal(signal);
+        }
+        Poll::Ready(Ok(taken))
+    }
+
+    fn poll_write_slave(&self, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+        let mut state = self.state.lock().unwrap();
+        if !state.master_open {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let written = state.write_output(data);
+        if written == 0 && !data.is_empty() {
+            register_waker(&mut state.output_writer, cx);
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_read_master(&self, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.output.is_empty() {
+            if state.slave_opened && state.slaves_open == 0 {
+                return Poll::Ready(());
+            }
+            register_waker(&mut state.output_reader, cx);
+            return Poll::Pending;
+        }
+        let len = buf.remaining().min(state.output.len());
+        let data: Vec<u8> = state.output.drain(..len).collect();
+        buf.put_slice(&data);
+        wake(&mut state.output_writer);
+        Poll::Ready(())
+    }
+
+    fn poll_read_slave(&self, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.input.is_empty() {
+            if state.eof || !state.master_open {
+                state.eof = false;
+                return Poll::Ready(());
+            }
+            register_waker(&mut state.input_reader, cx);
+            return Poll::Pending;
+        }
+        // Canonical reads return at most one line
+        let mut len = buf.remaining().min(state.input.len());
+        if state.modes.canonical {
+            if let Some(line) = state.lines.front_mut() {
+                len = len.min(*line);
+                *line -= len;
+                if *line == 0 {
+                    state.lines.pop_front();
+                }
+            }
+        }
+        let data: Vec<u8> = state.input.drain(..len).collect();
+        buf.put_slice(&data);
+        wake(&mut state.input_writer);
+        Poll::Ready(())
+    }
+
+    fn poll_master_ready(&self, cx: &mut Context<'_>) -> Poll<usize> {
+        let mut state = self.state.lock().unwrap();
+        if !state.output.is_empty() || (state.slave_opened && state.slaves_open == 0) {
+            return Poll::Ready(state.output.len());
+        }
+        register_waker(&mut state.output_reader, cx);
+        Poll::Pending
+    }
+
+    fn poll_slave_ready(&self, cx: &mut Context<'_>) -> Poll<usize> {
+        let mut state = self.state.lock().unwrap();
+        if !state.input.is_empty() || state.eof || !state.master_open {
+            return Poll::Ready(state.input.len());
+        }
+        register_waker(&mut state.input_reader, cx);
+        Poll::Pending
+    }
+
+    fn poll_master_write_ready(&self, cx: &mut Context<'_>) -> Poll<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.input.len() < PTY_BUFFER_SIZE {
+            return Poll::Ready(PTY_BUFFER_SIZE - state.input.len());
+        }
+        register_waker(&mut state.input_writer, cx);
+        Poll::Pending
+    }
+
+    fn poll_slave_write_ready(&self, cx: &mut Context<'_>) -> Poll<usize> {
+        let mut state = self.state.lock().unwrap();
+        if state.output.len() < PTY_BUFFER_SIZE || !state.master_open {
+            return Poll::Ready(PTY_BUFFER_SIZE.saturating_sub(state.output.len()));
+        }
+        register_waker(&mut state.output_writer, cx);
+        Poll::Pending
+    }
+}
+
+/// Allocates the PTYs of a [`WasiControlPlane`](crate::WasiControlPlane)
+/// and finds them again by their index
+#[derive(Debug, Default)]
+pub struct PtyRegistry {
+    ptys: Mutex<BTreeMap<u32, Weak<Pty>>>,
+}
+
+impl PtyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a PTY with the lowest free index and returns its master
+    pub fn allocate(&self) -> Result<PtyMaster, Errno> {
+        let mut ptys = self.ptys.lock().unwrap();
+        // The index of a PTY can be reused once its master is closed
+        ptys.retain(|_, pty| {
+            pty.upgrade()
+                .map(|pty| pty.state.lock().unwrap().master_open)
+                .unwrap_or(false)
+        });
+        let index = (0..PTY_MAX)
+            .find(|index| !ptys.contains_key(index))
+            .ok_or(Errno::Nfile)?;
+        let pty = Arc::new(Pty::new(index));
+        ptys.insert(index, Arc::downgrade(&pty));
+        Ok(PtyMaster { pty })
+    }
+
+    /// Returns the PTY with an index if its master is still open
+    pub fn get(&self, index: u32) -> Option<Arc<Pty>> {
+        let ptys = self.ptys.lock().unwrap();
+        ptys.get(&index)
+            .and_then(Weak::upgrade)
+            .filter(|pty| pty.state.lock().unwrap().master_open)
+    }
+
+    /// Indices of the PTYs whose masters are open
+    pub fn indices(&self) -> Vec<u32> {
+        let ptys = self.ptys.lock().unwrap();
+        ptys.iter()
+            .filter(|(_, pty)| {
+                pty.upgrade()
+                    .map(|pty| pty.state.lock().unwrap().master_open)
+                    .unwrap_or(false)
+            })
+            .map(|(index, _)| *index)
+            .collect()
+    }
+}
+
+/// Returns the PTY that a file is one of the sides of
+pub fn pty_of_file(file: &(dyn VirtualFile + Send + Sync)) -> Option<&Arc<Pty>> {
+    let file = file.upcast_any_ref();
+    file.downcast_ref::<PtyMaster>()
+        .map(|master| &master.pty)
+        .or_else(|| file.downcast_ref::<PtySlave>().map(|slave| &slave.pty))
+}
+
+/// Returns the PTY that a file descriptor refers to, failing with
+/// `ENOTTY` if it is not either side of a PTY
+pub(crate) fn fd_pty(fs: &WasiFs, fd: WasiFd) -> Result<Arc<Pty>, Errno> {
+    let fd_entry = fs.get_fd(fd)?;
+    let guard = fd_entry.inode.read();
+    let Kind::File {
+        handle: Some(handle),
+        ..
+    } = guard.deref()
+    else {
+        return Err(Errno::Notty);
+    };
+    let handle = handle.read().unwrap();
+    pty_of_file(handle.as_ref()).cloned().ok_or(Errno::Notty)
+}
+
+/// The side of a PTY that is held by the terminal emulator, closing it
+/// hangs up the terminal
+#[derive(Debug)]
+pub struct PtyMaster {
+    pty: Arc<Pty>,
+}
+
+impl PtyMaster {
+    pub fn pty(&self) -> &Arc<Pty> {
+        &self.pty
+    }
+}
+
+impl Drop for PtyMaster {
+    fn drop(&mut self) {
+        {
+            let mut state = self.pty.state.lock().unwrap();
+            state.master_open = false;
+            wake(&mut state.input_reader);
+            wake(&mut state.output_writer);
+        }
+        self.pty.foreground.signal(Signal::Sighup);
+    }
+}
+
+/// The side of a PTY that is the terminal of the programs it runs
+#[derive(Debug)]
+pub struct PtySlave {
+    pty: Arc<Pty>,
+}
+
+impl PtySlave {
+    pub fn pty(&self) -> &Arc<Pty> {
+        &self.pty
+    }
+}
+
+impl Drop for PtySlave {
+    fn drop(&mut self) {
+        let mut state = self.pty.state.lock().unwrap();
+        state.slaves_open -= 1;
+        wake(&mut state.output_reader);
+    }
+}
+
+macro_rules! impl_pty_file {
+    ($ty:ty, $read:ident, $ready:ident, $write:ident, $write_ready:ident) => {
+        impl AsyncRead for $ty {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut tokio::io::ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                self.pty.$read(cx, buf).map(Ok)
+            }
+        }
+
+        impl AsyncWrite for $ty {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                self.pty.$write(cx, buf)
+            }
+
+            fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        impl AsyncSeek for $ty {
+            fn start_seek(self: Pin<&mut Self>, _position: SeekFrom) -> io::Result<()> {
+                Ok(())
+            }
+
+            fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+                Poll::Ready(Ok(0))
+            }
+        }
+
+        impl VirtualFile for $ty {
+            fn last_accessed(&self) -> u64 {
+                0
+            }
+
+            fn last_modified(&self) -> u64 {
+                0
+            }
+
+            fn created_time(&self) -> u64 {
+                0
+            }
+
+            fn size(&self) -> u64 {
+                0
+            }
+
+            fn set_len(&mut self, _new_size: u64) -> virtual_fs::Result<()> {
+                Err(FsError::PermissionDenied)
+            }
+
+            fn unlink(&mut self) -> virtual_fs::Result<()> {
+                Ok(())
+            }
+
+            fn poll_read_ready(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<usize>> {
+                self.pty.$ready(cx).map(Ok)
+            }
+
+            fn poll_write_ready(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<usize>> {
+                self.pty.$write_ready(cx).map(Ok)
+            }
+        }
+    };
+}
+
+impl_pty_file!(
+    PtyMaster,
+    poll_read_master,
+    poll_master_ready,
+    poll_write_master,
+    poll_master_write_ready
+);
+impl_pty_file!(
+    PtySlave,
+    poll_read_slave,
+    poll_slave_ready,
+    poll_write_slave,
+    poll_slave_write_ready
+);
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    async fn read(file: &mut (impl AsyncRead + Unpin)) -> String {
+        let mut buf = [0u8; 256];
+        let n = file.read(&mut buf).await.unwrap();
+        String::from_utf8_lossy(&buf[..n]).into_owned()
+    }
+
+    #[tokio::test]
+    async fn pty_line_discipline() {
+        let registry = PtyRegistry::new();
+        let mut master = registry.allocate().unwrap();
+        let pty = master.pty().clone();
+        assert_eq!(registry.indices(), vec![0]);
+        let mut slave = registry.get(0).unwrap().open_slave().unwrap();
+
+        // Lines are edited before the slave sees them and are echoed back
+        master.write_all(b"lsx\x7f -l\r").await.unwrap();
+        assert_eq!(read(&mut slave).await, "ls -l\n");
+        assert_eq!(read(&mut master).await, "lsx\x08 \x08 -l\r\n");
+
+        // Output gets carriage returns
+        slave.write_all(b"a\nb\n").await.unwrap();
+        assert_eq!(read(&mut master).await, "a\r\nb\r\n");
+
+        // Raw input is passed through as it is typed
+        pty.set_modes(PtyModes {
+            echo: false,
+            canonical: false,
+            signals: false,
+            ..pty.modes()
+        });
+        master.write_all(b"\x03q").await.unwrap();
+        assert_eq!(read(&mut slave).await, "\x03q");
+
+        pty.set_modes(PtyModes::default());
+        master.write_all(b"\x04").await.unwrap();
+        assert_eq!(read(&mut slave).await, "");
+
+        // Hanging up the master leaves the slave at the end of the file
+        drop(master);
+        assert_eq!(read(&mut slave).await, "");
+        assert!(registry.get(0).is_none());
+        assert!(registry.indices().is_empty());
+        drop(slave);
+        assert_eq!(registry.allocate().unwrap().pty().index(), 0);
+    }
+
+    #[tokio::test]
+    async fn pty_keeps_lines_apart() {
+        let registry = PtyRegistry::new();
+        let mut master = registry.allocate().unwrap();
+        let pty = master.pty().clone();
+        let mut slave = pty.open_slave().unwrap();
+
+        // An end-of-file ends a line without a line feed, which is still
+        // read apart from the next line
+        master.write_all(b"abc\x04def\n").await.unwrap();
+        assert_eq!(read(&mut slave).await, "abc");
+        assert_eq!(read(&mut slave).await, "def\n");
+
+        // Input that was typed in raw mode reads as one line
+        pty.set_modes(PtyModes {
+            canonical: false,
+            ..pty.modes()
+        });
+        master.write_all(b"x\ny").await.unwrap();
+        pty.set_modes(PtyModes::default());
+        master.write_all(b"z\n").await.unwrap();
+        assert_eq!(read(&mut slave).await, "x\ny");
+        assert_eq!(read(&mut slave).await, "z\n");
+    }
+
+    #[test]
+    fn pty_signals_can_be_turned_off() {
+        let mut state = PtyState::default();
+        assert_eq!(state.receive(b"a\x03"), (2, vec![Signal::Sigint]));
+        assert!(state.line.is_empty());
+
+        // The keyboard generates signals in raw mode too
+        state.modes.canonical = false;
+        assert_eq!(state.receive(b"b\x1a"), (2, vec![Signal::Sigtstp]));
+        assert_eq!(state.input, b"b");
+
+        // Unless they are turned off, then the characters are input
+        state.modes.signals = false;
+        assert_eq!(state.receive(b"\x03"), (1, vec![]));
+        state.modes.canonical = true;
+        assert_eq!(state.receive(b"\x1c\n"), (2, vec![]));
+        assert_eq!(state.input, b"b\x03\x1c\n");
+    }
+
+    #[tokio::test]
+    async fn pty_buffers_are_bounded() {
+        use futures::FutureExt;
+
+        let registry = PtyRegistry::new();
+        let mut master = registry.allocate().unwrap();
+        let mut slave = master.pty().open_slave().unwrap();
+        master.pty().set_modes(PtyModes {
+            echo: false,
+            canonical: false,
+            signals: false,
+            line_feeds: false,
+        });
+
+        // Writers only get as far as the buffer goes and then have to wait
+        let data = vec![b'x'; PTY_BUFFER_SIZE + 10];
+        assert_eq!(master.write(&data).await.unwrap(), PTY_BUFFER_SIZE);
+        assert!(master.write(b"y").now_or_never().is_none());
+        assert_eq!(slave.write(&data).await.unwrap(), PTY_BUFFER_SIZE);
+        assert!(slave.write(b"y").now_or_never().is_none());
+
+        // Reading makes room again
+        assert_eq!(read(&mut slave).await.len(), 256);
+        assert_eq!(master.write(&data).await.unwrap(), 256);
+        assert_eq!(read(&mut master).await.len(), 256);
+        assert_eq!(slave.write(b"y").await.unwrap(), 1);
+    }
+}
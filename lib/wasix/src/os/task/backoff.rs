@@ -70,6 +70,9 @@ pub struct CpuBackoffToken {
     wait: Pin<Box<dyn Future<Output = ()> + Send + Sync + 'static>>,
     /// ID used to unregister the wakers
     waker_id: Option<u64>,
+    /// Throttles imposed by a CPU quota are not released early by
+    /// run tokens and do not grow the exponential backoff
+    quota: bool,
     /// The inner protected region of the process with a conditional
     /// variable that is used for coordination such as checksums.
     inner: LockableWasiProcessInner,
@@ -85,6 +88,10 @@ impl Future for CpuBackoffToken {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.quota {
+            return self.wait.poll_unpin(cx);
+        }
+
         let inner = self.inner.clone();
         let mut inner = inner.0.lock().unwrap();
 
@@ -193,6 +200,23 @@ impl WasiProcess {
             cpu_backoff_time,
             wait: how_long,
             waker_id: None,
+            quota: false,
+            inner: self.inner.clone(),
+        })
+    }
+
+    // Determine if the CPU should be throttled because the resource group
+    // of the process has used up its CPU quota
+    pub fn acquire_cpu_quota_token(
+        &self,
+        tasks: &Arc<dyn VirtualTaskManager>,
+    ) -> Option<CpuBackoffToken> {
+        let cpu_backoff_time = self.resource_group().cpu_throttle()?;
+        Some(CpuBackoffToken {
+            cpu_backoff_time,
+            wait: tasks.sleep_now(cpu_backoff_time),
+            waker_id: None,
+            quota: true,
             inner: self.inner.clone(),
         })
     }
@@ -7,8 +7,18 @@ use std::{
     time::Duration,
 };
 
-use crate::{WasiProcess, WasiProcessId};
+use crate::{
+    net::unix::UnixSocketRegistry,
+    os::{
+        ipc::{IpcRegistry, ShmLimits},
+        pty::PtyRegistry,
+    },
+    WasiProcess, WasiProcessId,
+};
+
+use super::resource_group::{ResourceGroup, ResourceLimits};
 use wasmer_types::ModuleHash;
+use wasmer_wasix_types::types::Signal;
 
 #[derive(Debug, Clone)]
 pub struct WasiControlPlane {
@@ -48,6 +58,12 @@ pub struct ControlPlaneConfig {
     /// time that it will pause the CPU)
     /// (default = off)
     pub enable_exponential_cpu_backoff: Option<Duration>,
+    /// Limits that apply to all the processes together, nested groups
+    /// with their own limits can be created from the root resource group
+    pub resource_limits: ResourceLimits,
+    /// Limits on the shared memory objects of all the processes, whose
+    /// bytes are also charged to the memory limit of the root resource group
+    pub shm_limits: ShmLimits,
 }
 
 impl ControlPlaneConfig {
@@ -56,6 +72,8 @@ impl ControlPlaneConfig {
             max_task_count: None,
             enable_asynchronous_threading: false,
             enable_exponential_cpu_backoff: None,
+            resource_limits: ResourceLimits::default(),
+            shm_limits: ShmLimits::default(),
         }
     }
 }
@@ -73,6 +91,18 @@ struct State {
     /// Total number of active tasks (threads) across all processes.
     task_count: Arc<AtomicUsize>,
 
+    /// Unix sockets that have been bound by any of the processes.
+    unix_sockets: Arc<UnixSocketRegistry>,
+
+    /// Named shared memory, semaphores and message queues.
+    ipc: Arc<IpcRegistry>,
+
+    /// Pseudo-terminals that have been allocated by any of the processes.
+    ptys: Arc<PtyRegistry>,
+
+    /// Resource group that all other groups are nested in.
+    root_group: Arc<ResourceGroup>,
+
     /// Mutable state.
     mutable: RwLock<MutableState>,
 }
@@ -88,10 +118,18 @@ struct MutableState {
 
 impl WasiControlPlane {
     pub fn new(config: ControlPlaneConfig) -> Self {
+        let root_group = ResourceGroup::new(config.resource_limits);
         Self {
             state: Arc::new(State {
+                ipc: Arc::new(IpcRegistry::with_shm_limits(
+                    config.shm_limits,
+                    root_group.clone(),
+                )),
+                root_group,
                 config,
                 task_count: Arc::new(AtomicUsize::new(0)),
+                unix_sockets: Arc::new(UnixSocketRegistry::new()),
+                ptys: Arc::new(PtyRegistry::new()),
                 mutable: RwLock::new(MutableState {
                     process_seed: 0,
                     processes: Default::default(),
@@ -104,6 +142,27 @@ impl WasiControlPlane {
         WasiControlPlaneHandle::new(&self.state)
     }
 
+    /// Returns the registry of unix sockets shared by all the processes
+    pub fn unix_sockets(&self) -> &Arc<UnixSocketRegistry> {
+        &self.state.unix_sockets
+    }
+
+    /// Returns the registry of named IPC objects shared by all the processes
+    pub fn ipc(&self) -> &Arc<IpcRegistry> {
+        &self.state.ipc
+    }
+
+    /// Returns the registry of pseudo-terminals shared by all the processes
+    pub fn ptys(&self) -> &Arc<PtyRegistry> {
+        &self.state.ptys
+    }
+
+    /// Returns the resource group that new processes join unless they
+    /// inherit the group of their parent
+    pub fn root_resource_group(&self) -> &Arc<ResourceGroup> {
+        &self.state.root_group
+    }
+
     /// Get the current count of active tasks (threads).
     fn active_task_count(&self) -> usize {
         self.state.task_count.load(Ordering::SeqCst)
@@ -167,6 +226,45 @@ impl WasiControlPlane {
             .get(&pid)
             .cloned()
     }
+
+    /// Gets the processes that are still running, ordered by their ID
+    pub fn processes(&self) -> Vec<WasiProcess> {
+        let mut processes: Vec<_> = self
+            .state
+            .mutable
+            .read()
+            .unwrap()
+            .processes
+            .values()
+            .filter(|process| process.try_join().is_none())
+            .cloned()
+            .collect();
+        processes.sort_by_key(|process| process.pid());
+        processes
+    }
+
+    /// Gets the processes that are still running in a process group
+    pub fn get_process_group(&self, pgid: WasiProcessId) -> Vec<WasiProcess> {
+        self.state
+            .mutable
+            .read()
+            .unwrap()
+            .processes
+            .values()
+            .filter(|process| process.try_join().is_none() && process.pgid() == pgid)
+            .cloned()
+            .collect()
+    }
+
+    /// Sends a signal to every process in a process group, returning false
+    /// if no process is running in the group
+    pub fn signal_process_group(&self, pgid: WasiProcessId, signal: Signal) -> bool {
+        let group = self.get_process_group(pgid);
+        for process in group.iter() {
+            process.signal_process(signal);
+        }
+        !group.is_empty()
+    }
 }
 
 impl MutableState {
@@ -211,7 +309,7 @@ pub enum ControlPlaneError {
 
 #[cfg(test)]
 mod tests {
-    use wasmer_wasix_types::wasix::ThreadStartType;
+    use wasmer_wasix_types::{wasi::ExitCode, wasix::ThreadStartType};
 
     use crate::{os::task::thread::WasiMemoryLayout, utils::xxhash_random};
 
@@ -224,6 +322,8 @@ mod tests {
             max_task_count: Some(2),
             enable_asynchronous_threading: false,
             enable_exponential_cpu_backoff: None,
+            resource_limits: ResourceLimits::default(),
+            shm_limits: ShmLimits::default(),
         });
 
         let p1 = p.new_process(xxhash_random()).unwrap();
@@ -247,6 +347,8 @@ mod tests {
             max_task_count: Some(2),
             enable_asynchronous_threading: false,
             enable_exponential_cpu_backoff: None,
+            resource_limits: ResourceLimits::default(),
+            shm_limits: ShmLimits::default(),
         });
 
         let p1 = p.new_process(xxhash_random()).unwrap();
@@ -269,4 +371,107 @@ mod tests {
             ControlPlaneError::TaskLimitReached { max: 2 }
         );
     }
+
+    /// Forked processes stay in the group of their parent until moved.
+    #[test]
+    fn test_control_plane_process_groups() {
+        let p = WasiControlPlane::new(ControlPlaneConfig::default());
+
+        let p1 = p.new_process(xxhash_random()).unwrap();
+        assert_eq!(p1.pgid(), p1.pid());
+        assert_eq!(p1.sid(), p1.pid());
+
+        let p2 = p.new_process(xxhash_random()).unwrap();
+        p2.inherit_group(&p1);
+        assert_eq!(p2.pgid(), p1.pid());
+        assert_eq!(p2.sid(), p1.pid());
+
+        let pids = |pgid| {
+            let mut pids = p
+                .get_process_group(pgid)
+                .iter()
+                .map(|process| process.pid())
+                .collect::<Vec<_>>();
+            pids.sort();
+            pids
+        };
+        assert_eq!(pids(p1.pid()), vec![p1.pid(), p2.pid()]);
+
+        p2.set_pgid(p2.pid());
+        assert_eq!(pids(p1.pid()), vec![p1.pid()]);
+        assert_eq!(pids(p2.pid()), vec![p2.pid()]);
+        assert_eq!(p2.sid(), p1.pid());
+    }
+
+    /// A continue that arrives while a thread is about to stop is not lost.
+    #[tokio::test]
+    async fn test_control_plane_stop_and_continue() {
+        let p = WasiControlPlane::new(ControlPlaneConfig::default());
+        let p1 = p.new_process(xxhash_random()).unwrap();
+        let t1 = p1
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+
+        // The continue is sent after the stop signal has been popped
+        p1.signal_process(Signal::Sigstop);
+        assert_eq!(t1.pop_signals(), vec![Signal::Sigstop]);
+        p1.signal_process(Signal::Sigcont);
+        assert!(!p1.stop(&t1, Signal::Sigstop));
+        assert!(!p1.is_stopped());
+        t1.pop_signals();
+
+        // A stopped process is resumed by a continue
+        p1.signal_process(Signal::Sigstop);
+        assert_eq!(t1.pop_signals(), vec![Signal::Sigstop]);
+        assert!(p1.stop(&t1, Signal::Sigstop));
+        assert!(p1.is_stopped());
+        let stopped = tokio::spawn({
+            let p1 = p1.clone();
+            async move { p1.wait_while_stopped().await }
+        });
+        p1.signal_process(Signal::Sigcont);
+        stopped.await.unwrap();
+        assert!(!p1.is_stopped());
+    }
+
+    /// The parent of a child that stops is sent `SIGCHLD` and can join the
+    /// stop once, and then the exit after the child is continued.
+    #[tokio::test]
+    async fn test_control_plane_join_stopped_child() {
+        let p = WasiControlPlane::new(ControlPlaneConfig::default());
+        let parent = p.new_process(xxhash_random()).unwrap();
+        let parent_thread = parent
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+        let child = p.new_process(xxhash_random()).unwrap();
+        let child_thread = child
+            .new_thread(WasiMemoryLayout::default(), ThreadStartType::MainThread)
+            .unwrap();
+        parent.add_child(child.clone());
+        assert_eq!(child.ppid(), parent.pid());
+
+        // The join is already waiting when the child stops
+        let join = tokio::spawn({
+            let parent = parent.clone();
+            async move { parent.join_any_stopped_child().await }
+        });
+        child.signal_process(Signal::Sigtstp);
+        assert_eq!(child_thread.pop_signals(), vec![Signal::Sigtstp]);
+        assert!(child.stop(&child_thread, Signal::Sigtstp));
+        assert_eq!(parent_thread.pop_signals(), vec![Signal::Sigchld]);
+        assert_eq!(join.await.unwrap(), (child.pid(), Signal::Sigtstp));
+        assert_eq!(parent.try_join_any_stopped_child(), None);
+
+        // Once continued the next join waits for the exit
+        child.signal_process(Signal::Sigcont);
+        assert!(!child.is_stopped());
+        assert_eq!(child.try_join_stopped(), None);
+        child.terminate(ExitCode::from(3u16));
+        let exit_code = tokio::select! {
+            biased;
+            exit_code = child.join() => exit_code.unwrap(),
+            _ = child.join_stopped() => panic!("the stop was reported twice"),
+        };
+        assert_eq!(exit_code, ExitCode::from(3u16));
+    }
 }
@@ -3,6 +3,8 @@
 pub mod backoff;
 pub mod control_plane;
 pub mod process;
+pub mod resource_group;
+pub mod rusage;
 pub mod signal;
 mod task_join_handle;
 pub mod thread;
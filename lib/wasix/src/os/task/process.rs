@@ -1,6 +1,10 @@
 #[cfg(feature = "journal")]
 use crate::{journal::JournalEffector, syscalls::do_checkpoint_from_outside, unwind, WasiResult};
-use crate::{journal::SnapshotTrigger, WasiEnv, WasiRuntimeError};
+use crate::{
+    journal::SnapshotTrigger,
+    state::{DlState, WasiState},
+    WasiEnv, WasiRuntimeError,
+};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "journal")]
 use std::collections::HashSet;
@@ -9,12 +13,13 @@ use std::{
     convert::TryInto,
     ops::Range,
     sync::{
-        atomic::{AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Arc, Condvar, Mutex, MutexGuard, RwLock, Weak,
     },
     task::Waker,
     time::Duration,
 };
+use tokio::sync::watch;
 use tracing::trace;
 use wasmer::FunctionEnvMut;
 use wasmer_types::ModuleHash;
@@ -32,6 +37,8 @@ use crate::{
 use super::{
     backoff::WasiProcessCpuBackoff,
     control_plane::{ControlPlaneError, WasiControlPlaneHandle},
+    resource_group::ResourceGroup,
+    rusage::{ResourceCounters, ResourceUsage},
     signal::{SignalDeliveryError, SignalHandlerAbi},
     task_join_handle::OwnedTaskStatus,
     thread::WasiMemoryLayout,
@@ -94,8 +101,6 @@ pub struct WasiProcess {
     pub(crate) pid: WasiProcessId,
     /// Hash of the module that this process is using
     pub(crate) module_hash: ModuleHash,
-    /// List of all the children spawned from this thread
-    pub(crate) parent: Option<Weak<RwLock<WasiProcessInner>>>,
     /// The inner protected region of the process with a conditional
     /// variable that is used for coordination such as snapshots.
     pub(crate) inner: LockableWasiProcessInner,
@@ -111,6 +116,31 @@ pub struct WasiProcess {
     /// the exponential backoff of CPU is halted (as in CPU
     /// is allowed to run freely)
     pub(crate) cpu_run_tokens: Arc<AtomicU32>,
+    /// Size of the linear memory of the process when it was last sampled
+    pub(crate) memory_size: Arc<AtomicU64>,
+    /// Largest size that the linear memory of the process can grow to
+    pub(crate) memory_limit: Arc<AtomicU64>,
+    /// True while the process is over the memory limit of its group and
+    /// has already been sent the OOM signal
+    pub(crate) oom_signalled: Arc<AtomicBool>,
+    /// Resources consumed by the process and its joined children
+    pub(crate) usage: Arc<ResourceCounters>,
+    /// Group whose limits apply to this process
+    pub(crate) resource_group: Arc<RwLock<Arc<ResourceGroup>>>,
+    /// Side modules that have been loaded into the process with `dlopen`
+    pub(crate) dl: Arc<Mutex<DlState>>,
+    /// Whether the process is stopped by a job control signal, which the
+    /// threads of the process and the joins of its parent wait on
+    pub(crate) job: Arc<watch::Sender<JobState>>,
+}
+
+/// Job control state of a process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct JobState {
+    /// Signal that stopped the process, while it is stopped
+    pub stopped: Option<Signal>,
+    /// True until a join with `JoinFlags::WAKE_STOPPED` has reported the stop
+    pub unreported: bool,
 }
 
 /// Represents a freeze of all threads to perform some action
@@ -155,6 +185,15 @@ impl Into<Range<u64>> for MemorySnapshotRegion {
 pub struct WasiProcessInner {
     /// Unique ID of this process
     pub pid: WasiProcessId,
+    /// Process group that this process belongs to
+    pub pgid: WasiProcessId,
+    /// Session that this process belongs to
+    pub sid: WasiProcessId,
+    /// Process that spawned or forked this one
+    pub(crate) parent: Weak<(Mutex<WasiProcessInner>, Condvar)>,
+    /// State of the environment that runs the process, which is what
+    /// `/proc` reports the arguments, environment and files of
+    pub(crate) state: Weak<WasiState>,
     /// Number of threads waiting for children to exit
     pub(crate) waiting: Arc<AtomicU32>,
     /// The threads that make up this process
@@ -426,6 +465,10 @@ impl WasiProcess {
         let inner = Arc::new((
             Mutex::new(WasiProcessInner {
                 pid,
+                pgid: pid,
+                sid: pid,
+                parent: Weak::new(),
+                state: Weak::new(),
                 threads: Default::default(),
                 thread_count: Default::default(),
                 signal_intervals: Default::default(),
@@ -445,11 +488,11 @@ impl WasiProcess {
         ));
 
         #[derive(Debug)]
-        struct SignalHandler(LockableWasiProcessInner);
+        struct SignalHandler(LockableWasiProcessInner, Arc<watch::Sender<JobState>>);
         impl SignalHandlerAbi for SignalHandler {
             fn signal(&self, signal: u8) -> Result<(), SignalDeliveryError> {
                 if let Ok(signal) = signal.try_into() {
-                    signal_process_internal(&self.0, signal);
+                    signal_process_internal(&self.0, &self.1, signal);
                     Ok(())
                 } else {
                     Err(SignalDeliveryError)
@@ -457,23 +500,44 @@ impl WasiProcess {
             }
         }
 
+        let job = Arc::new(watch::Sender::new(JobState::default()));
+        let usage = Arc::new(ResourceCounters::new());
+        let resource_group = plane
+            .upgrade()
+            .map(|p| p.root_resource_group().clone())
+            .unwrap_or_else(|| ResourceGroup::new(Default::default()));
+        usage.set_group(resource_group.clone());
+
         WasiProcess {
             pid,
             module_hash,
-            parent: None,
             compute: plane,
             inner: inner.clone(),
             finished: Arc::new(
                 OwnedTaskStatus::new(TaskStatus::Pending)
-                    .with_signal_handler(Arc::new(SignalHandler(inner))),
+                    .with_signal_handler(Arc::new(SignalHandler(inner, job.clone())))
+                    .with_usage(usage.clone()),
             ),
             waiting,
             cpu_run_tokens: Arc::new(AtomicU32::new(0)),
+            memory_size: Arc::new(AtomicU64::new(0)),
+            memory_limit: Arc::new(AtomicU64::new(0)),
+            oom_signalled: Arc::new(AtomicBool::new(false)),
+            usage,
+            resource_group: Arc::new(RwLock::new(resource_group)),
+            dl: Default::default(),
+            job,
         }
     }
 
     pub(super) fn set_pid(&mut self, pid: WasiProcessId) {
         self.pid = pid;
+
+        // A new process starts off leading its own process group and session
+        let mut inner = self.inner.0.lock().unwrap();
+        inner.pid = pid;
+        inner.pgid = pid;
+        inner.sid = pid;
     }
 
     /// Gets the process ID of this process
@@ -483,14 +547,223 @@ impl WasiProcess {
 
     /// Gets the process ID of the parent process
     pub fn ppid(&self) -> WasiProcessId {
-        self.parent
-            .iter()
-            .filter_map(|parent| parent.upgrade())
-            .map(|parent| parent.read().unwrap().pid)
-            .next()
+        let parent = self.inner.0.lock().unwrap().parent.upgrade();
+        parent
+            .map(|parent| parent.0.lock().unwrap().pid)
             .unwrap_or(WasiProcessId(0))
     }
 
+    /// Returns true if this process is `pid` or was spawned or forked
+    /// (directly or not) from it
+    pub(crate) fn descends_from(&self, pid: WasiProcessId) -> bool {
+        let mut current = Some(self.inner.clone());
+        while let Some(process) = current {
+            let inner = process.0.lock().unwrap();
+            if inner.pid == pid {
+                return true;
+            }
+            current = inner.parent.upgrade();
+        }
+        false
+    }
+
+    /// Adds a process that was spawned or forked from this one to its
+    /// children
+    pub(crate) fn add_child(&self, child: WasiProcess) {
+        child.inner.0.lock().unwrap().parent = Arc::downgrade(&self.inner);
+        self.inner.0.lock().unwrap().children.push(child);
+    }
+
+    /// Gets the ID of the process group that this process belongs to
+    pub fn pgid(&self) -> WasiProcessId {
+        self.inner.0.lock().unwrap().pgid
+    }
+
+    /// Gets the ID of the session that this process belongs to
+    pub fn sid(&self) -> WasiProcessId {
+        self.inner.0.lock().unwrap().sid
+    }
+
+    /// Moves this process into a process group within its current session
+    pub(crate) fn set_pgid(&self, pgid: WasiProcessId) {
+        self.inner.0.lock().unwrap().pgid = pgid;
+    }
+
+    /// Makes this process the leader of a new session and of a new process
+    /// group within that session
+    pub(crate) fn set_sid(&self) -> WasiProcessId {
+        let mut inner = self.inner.0.lock().unwrap();
+        inner.pgid = self.pid;
+        inner.sid = self.pid;
+        self.pid
+    }
+
+    /// Places this process in the same process group and session as another
+    /// process, which is what happens to a child when it is forked
+    pub(crate) fn inherit_group(&self, parent: &WasiProcess) {
+        let (pgid, sid) = {
+            let parent = parent.inner.0.lock().unwrap();
+            (parent.pgid, parent.sid)
+        };
+        let mut inner = self.inner.0.lock().unwrap();
+        inner.pgid = pgid;
+        inner.sid = sid;
+    }
+
+    /// Copies the libraries that the parent loaded with `dlopen`, which a
+    /// forked process links into its instance before it resumes
+    pub(crate) fn inherit_libraries(&self, parent: &WasiProcess) {
+        let dl = parent.dl.lock().unwrap().clone();
+        *self.dl.lock().unwrap() = dl;
+    }
+
+    /// Returns true if the process has been stopped by a job control signal
+    pub fn is_stopped(&self) -> bool {
+        self.job.borrow().stopped.is_some()
+    }
+
+    /// Stops the process, which is the default action of the job control
+    /// signals, and sends `SIGCHLD` to its parent. The threads of the process
+    /// then wait in [`WasiProcess::wait_while_stopped`] until it is sent
+    /// `SIGCONT` or `SIGKILL`. Returns false if the process was continued
+    /// before it stopped.
+    pub(crate) fn stop(&self, thread: &WasiThread, signal: Signal) -> bool {
+        let parent = {
+            let inner = self.inner.0.lock().unwrap();
+
+            // A `SIGCONT` that was sent after the stop signal was popped, but
+            // before the process was marked as stopped, would not have
+            // continued it. It is still queued on the thread though, and it
+            // can not be queued while we hold the lock without it seeing the
+            // process as stopped.
+            if thread.has_signal(&[Signal::Sigcont, Signal::Sigkill]) {
+                return false;
+            }
+
+            tracing::trace!(pid = %self.pid, ?signal, "process stopped");
+            self.job.send_replace(JobState {
+                stopped: Some(signal),
+                unreported: true,
+            });
+            inner.parent.upgrade()
+        };
+
+        // The signal goes straight to the threads of the parent, as it
+        // would otherwise be passed on to its children while it joins them
+        if let Some(parent) = parent {
+            let parent = parent.0.lock().unwrap();
+            for thread in parent.threads.values() {
+                thread.signal(Signal::Sigchld);
+            }
+        }
+        true
+    }
+
+    /// Waits for as long as the process is stopped
+    pub(crate) async fn wait_while_stopped(&self) {
+        let mut rx = self.job.subscribe();
+        rx.wait_for(|job| job.stopped.is_none()).await.ok();
+    }
+
+    /// Waits until the process is stopped and reports the signal that
+    /// stopped it, each stop is only reported once
+    pub async fn join_stopped(&self) -> Signal {
+        let mut rx = self.job.subscribe();
+        loop {
+            if let Some(signal) = self.try_join_stopped() {
+                return signal;
+            }
+            rx.changed().await.ok();
+        }
+    }
+
+    /// Reports the signal that stopped the process, if it is stopped and the
+    /// stop has not been reported yet
+    pub fn try_join_stopped(&self) -> Option<Signal> {
+        let mut ret = None;
+        self.job.send_if_modified(|job| {
+            if !job.unreported {
+                return false;
+            }
+            job.unreported = false;
+            ret = job.stopped;
+            true
+        });
+        ret
+    }
+
+    /// Attaches the state of the environment that runs this process
+    pub(crate) fn set_state(&self, state: &Arc<WasiState>) {
+        self.inner.0.lock().unwrap().state = Arc::downgrade(state);
+        state.fs.set_resource_group(self.resource_group());
+    }
+
+    /// Gets the state of the environment that runs this process, if it
+    /// is still running
+    pub(crate) fn state(&self) -> Option<Arc<WasiState>> {
+        self.inner.0.lock().unwrap().state.upgrade()
+    }
+
+    /// Records the current and largest size of the linear memory
+    pub(crate) fn set_memory_size(&self, size: u64, limit: u64) {
+        let prev = self.memory_size.swap(size, Ordering::Relaxed);
+        self.memory_limit.store(limit, Ordering::Relaxed);
+        self.usage.record_memory(size);
+
+        // The usage of the group only needs to be totalled when the memory
+        // grew, and going over the limit of the group gets the process
+        // killed (it is only signalled once until it is back under it)
+        if size <= prev {
+            return;
+        }
+        match self.resource_group().check_memory() {
+            Ok(()) => self.oom_signalled.store(false, Ordering::Relaxed),
+            Err(signal) => {
+                if !self.oom_signalled.swap(true, Ordering::Relaxed) {
+                    tracing::warn!(pid = %self.pid, %size, ?signal, "resource group is out of memory");
+                    self.signal_process(signal);
+                }
+            }
+        }
+    }
+
+    /// Gets the size of the linear memory when it was last sampled,
+    /// along with the largest size that it can grow to
+    pub fn memory_size(&self) -> (u64, u64) {
+        (
+            self.memory_size.load(Ordering::Relaxed),
+            self.memory_limit.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Gets the group whose limits apply to this process
+    pub fn resource_group(&self) -> Arc<ResourceGroup> {
+        self.resource_group.read().unwrap().clone()
+    }
+
+    /// Moves this process into another resource group
+    pub fn set_resource_group(&self, group: Arc<ResourceGroup>) {
+        {
+            let mut guard = self.resource_group.write().unwrap();
+            self.usage.set_group(group.clone());
+            *guard = group.clone();
+        }
+        if let Some(state) = self.state() {
+            state.fs.set_resource_group(group);
+        }
+    }
+
+    /// Gets the resources that have been consumed by this process
+    pub fn usage(&self) -> ResourceUsage {
+        self.usage.usage()
+    }
+
+    /// Gets the resources that have been consumed by all the children
+    /// of this process that were joined (similar to `RUSAGE_CHILDREN`)
+    pub fn children_usage(&self) -> ResourceUsage {
+        self.usage.children_usage()
+    }
+
     /// Gains access to the process internals
     // TODO: Make this private, all inner access should be exposed with methods.
     pub fn lock(&self) -> MutexGuard<'_, WasiProcessInner> {
@@ -553,7 +826,7 @@ impl WasiProcess {
         inner.threads.insert(tid, ctrl.clone());
         inner.thread_count += 1;
 
-        Ok(WasiThreadHandle::new(ctrl, &self.inner))
+        Ok(WasiThreadHandle::new(ctrl, &self.inner, &self.usage))
     }
 
     /// Gets a reference to a particular thread
@@ -589,7 +862,7 @@ impl WasiProcess {
 
     /// Signals all the threads in this process
     pub fn signal_process(&self, signal: Signal) {
-        signal_process_internal(&self.inner, signal);
+        signal_process_internal(&self.inner, &self.job, signal);
     }
 
     /// Takes a snapshot of the process and disables journaling returning
@@ -782,9 +1055,13 @@ impl WasiProcess {
         for child in children {
             if let Some(process) = self.compute.must_upgrade().get_process(child.pid) {
                 let inner = self.inner.clone();
+                let usage = self.usage.clone();
                 waits.push(async move {
                     let join = process.join().await;
                     let mut inner = inner.0.lock().unwrap();
+                    if inner.children.iter().any(|a| a.pid == child.pid) {
+                        usage.add_child(&process.usage);
+                    }
                     inner.children.retain(|a| a.pid != child.pid);
                     join
                 })
@@ -796,8 +1073,38 @@ impl WasiProcess {
             .next()
     }
 
-    /// Waits for any of the children to finished
-    pub async fn join_any_child(&mut self) -> Result<Option<(WasiProcessId, ExitCode)>, Errno> {
+    /// Waits for any of the children to be stopped (see
+    /// [`WasiProcess::join_stopped`])
+    pub async fn join_any_stopped_child(&self) -> (WasiProcessId, Signal) {
+        let children: Vec<_> = {
+            let inner = self.inner.0.lock().unwrap();
+            inner.children.clone()
+        };
+        if children.is_empty() {
+            return std::future::pending().await;
+        }
+
+        let waits = children
+            .into_iter()
+            .map(|child| Box::pin(async move { (child.pid, child.join_stopped().await) }));
+        futures::future::select_all(waits).await.0
+    }
+
+    /// Reports any of the children that are stopped (see
+    /// [`WasiProcess::try_join_stopped`])
+    pub fn try_join_any_stopped_child(&self) -> Option<(WasiProcessId, Signal)> {
+        let inner = self.inner.0.lock().unwrap();
+        inner
+            .children
+            .iter()
+            .find_map(|child| Some((child.pid, child.try_join_stopped()?)))
+    }
+
+    /// Waits for any of the children to finished, returning its exit code
+    /// and the resources that it (and the children it joined) used
+    pub async fn join_any_child(
+        &mut self,
+    ) -> Result<Option<(WasiProcessId, ExitCode, ResourceUsage)>, Errno> {
         let _guard = WasiProcessWait::new(self);
         let children: Vec<_> = {
             let inner = self.inner.0.lock().unwrap();
@@ -811,22 +1118,28 @@ impl WasiProcess {
         for child in children {
             if let Some(process) = self.compute.must_upgrade().get_process(child.pid) {
                 let inner = self.inner.clone();
+                let usage = self.usage.clone();
                 waits.push(async move {
                     let join = process.join().await;
                     let mut inner = inner.0.lock().unwrap();
+                    let child_usage = if inner.children.iter().any(|a| a.pid == child.pid) {
+                        usage.add_child(&process.usage)
+                    } else {
+                        process.usage.total_usage()
+                    };
                     inner.children.retain(|a| a.pid != child.pid);
-                    (child, join)
+                    (child, join, child_usage)
                 })
             }
         }
-        let (child, res) = futures::future::select_all(waits.into_iter().map(Box::pin))
+        let (child, res, usage) = futures::future::select_all(waits.into_iter().map(Box::pin))
             .await
             .0;
 
         let code =
             res.unwrap_or_else(|e| e.as_exit_code().unwrap_or_else(|| Errno::Canceled.into()));
 
-        Ok(Some((child.pid, code)))
+        Ok(Some((child.pid, code, usage)))
     }
 
     /// Terminate the process and all its threads
@@ -841,8 +1154,11 @@ impl WasiProcess {
 }
 
 /// Signals all the threads in this process
-fn signal_process_internal(process: &LockableWasiProcessInner, signal: Signal) {
-    #[allow(unused_mut)]
+fn signal_process_internal(
+    process: &LockableWasiProcessInner,
+    job: &watch::Sender<JobState>,
+    signal: Signal,
+) {
     let mut guard = process.0.lock().unwrap();
     let pid = guard.pid;
     tracing::trace!(%pid, "signal-process({:?})", signal);
@@ -869,6 +1185,15 @@ fn signal_process_internal(process: &LockableWasiProcessInner, signal: Signal) {
         };
     }
 
+    // Resume the process if it was stopped by a job control signal
+    if signal == Signal::Sigcont || signal == Signal::Sigkill {
+        job.send_if_modified(|job| {
+            let stopped = job.stopped.is_some();
+            *job = JobState::default();
+            stopped
+        });
+    }
+
     // Check if there are subprocesses that will receive this signal
     // instead of this process
     if guard.waiting.load(Ordering::Acquire) > 0 {
@@ -0,0 +1,370 @@
+//! Hierarchical limits on the resources used by groups of processes.
+//!
+//! Every process belongs to a [`ResourceGroup`] and the limits of a group
+//! apply to all of its processes and to the processes of its descendant
+//! groups. Processes that are forked or spawned join the group of their
+//! parent, which makes the limits follow a whole process tree.
+//!
+//! Each group keeps counters of the resources that its processes use, which
+//! are updated as the processes use them and include the usage of the
+//! descendant groups, so checking a limit never has to visit the processes.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use virtual_fs::{limiter::FsMemoryLimiter, FsError};
+use wasmer_wasix_types::{types::Signal, wasi::Errno};
+
+use super::{rusage::ResourceCounters, thread::flush_cpu_time};
+
+/// Amount of CPU time (see [`ResourceUsage::cpu_time`]) that a group may
+/// use in each period, similar to `cpu.max` of cgroups
+///
+/// The quota is not enforced on hosts without per-thread CPU clocks.
+///
+/// [`ResourceUsage::cpu_time`]: super::rusage::ResourceUsage::cpu_time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CpuQuota {
+    /// CPU time that the processes may use in each period
+    pub quota: Duration,
+    /// Length of the period
+    pub period: Duration,
+}
+
+/// Limits that apply to all the processes in a [`ResourceGroup`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ResourceLimits {
+    /// Maximum total size of the linear memories of the processes
+    pub max_memory: Option<u64>,
+    /// Maximum total number of file descriptors that the processes
+    /// may have open
+    pub max_open_files: Option<usize>,
+    /// Processes are throttled using the CPU backoff when they exceed
+    /// this quota
+    pub cpu_quota: Option<CpuQuota>,
+    /// Signal delivered to a process that takes the group over its memory
+    /// limit (defaults to `SIGKILL`, which OOM-kills the process)
+    pub oom_signal: Option<Signal>,
+}
+
+#[derive(Debug, Default)]
+struct CpuWindow {
+    /// Monotonic time (in nanoseconds) when the current period started
+    started: u64,
+    /// CPU time that the group had used when the current period started
+    used: Duration,
+}
+
+/// Group of processes that share a set of [`ResourceLimits`]
+///
+/// Memory is sampled when the processes handle syscalls and the CPU quota
+/// is enforced at the same points as the exponential CPU backoff, hence
+/// processes may briefly exceed the limits between syscalls.
+#[derive(Debug)]
+pub struct ResourceGroup {
+    limits: ResourceLimits,
+    parent: Option<Arc<ResourceGroup>>,
+    /// True if this group or any of its ancestors has a CPU quota
+    cpu_limited: bool,
+    /// Total size of the linear memories of the running processes
+    memory: AtomicU64,
+    /// Total CPU time (in nanoseconds) that the processes have used
+    cpu_time: AtomicU64,
+    /// Number of file descriptors that the processes have open
+    open_files: AtomicUsize,
+    cpu_window: Mutex<CpuWindow>,
+    /// Held while a file descriptor is counted and opened, so that
+    /// concurrent opens can not both pass the limit
+    open_files_lock: Mutex<()>,
+    /// Memory used by the group besides the linear memories of its
+    /// processes, such as shared memory objects (see [`FsMemoryLimiter`])
+    charged: AtomicU64,
+}
+
+/// Permission to open a file descriptor in a [`ResourceGroup`], which must
+/// be held until the file descriptor has been inserted
+pub(crate) struct OpenFileReservation<'a> {
+    _guards: Vec<MutexGuard<'a, ()>>,
+}
+
+impl ResourceGroup {
+    /// Creates a group that is not nested in any other group
+    pub fn new(limits: ResourceLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            parent: None,
+            cpu_limited: limits.cpu_quota.is_some(),
+            memory: Default::default(),
+            cpu_time: Default::default(),
+            open_files: Default::default(),
+            cpu_window: Default::default(),
+            open_files_lock: Default::default(),
+            charged: Default::default(),
+        })
+    }
+
+    /// Creates a group nested in this one, its processes are subject to
+    /// both its own limits and the limits of all its ancestors
+    pub fn new_child(self: &Arc<Self>, limits: ResourceLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            parent: Some(self.clone()),
+            cpu_limited: limits.cpu_quota.is_some() || self.cpu_limited,
+            memory: Default::default(),
+            cpu_time: Default::default(),
+            open_files: Default::default(),
+            cpu_window: Default::default(),
+            open_files_lock: Default::default(),
+            charged: Default::default(),
+        })
+    }
+
+    /// Limits of this group (excluding those of its ancestors)
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Group that this group is nested in
+    pub fn parent(&self) -> Option<&Arc<ResourceGroup>> {
+        self.parent.as_ref()
+    }
+
+    /// Total size of the linear memories of the processes in the group,
+    /// plus the memory that was charged to it
+    pub fn memory_usage(&self) -> u64 {
+        self.memory
+            .load(Ordering::Relaxed)
+            .saturating_add(self.charged.load(Ordering::Relaxed))
+    }
+
+    /// Total number of file descriptors that the processes in the group
+    /// have open
+    pub fn open_files(&self) -> usize {
+        self.open_files.load(Ordering::Relaxed)
+    }
+
+    /// Total CPU time that the processes in the group have used, including
+    /// the processes that have since exited or left the group
+    pub fn cpu_time(&self) -> Duration {
+        Duration::from_nanos(self.cpu_time.load(Ordering::Relaxed))
+    }
+
+    /// Adds to the linear memory that the processes of this group and its
+    /// ancestors use
+    pub(crate) fn grow_memory(&self, bytes: u64) {
+        for group in self.ancestors() {
+            group.memory.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Removes from the linear memory that the processes of this group and
+    /// its ancestors use
+    pub(crate) fn shrink_memory(&self, bytes: u64) {
+        for group in self.ancestors() {
+            group.memory.fetch_sub(bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Adds CPU time that a process of this group used
+    pub(crate) fn add_cpu_time(&self, nanos: u64) {
+        for group in self.ancestors() {
+            group.cpu_time.fetch_add(nanos, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts file descriptors that a process of this group opened
+    pub(crate) fn add_open_files(&self, count: usize) {
+        for group in self.ancestors() {
+            group.open_files.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops counting file descriptors that a process of this group closed
+    pub(crate) fn remove_open_files(&self, count: usize) {
+        for group in self.ancestors() {
+            group.open_files.fetch_sub(count, Ordering::Relaxed);
+        }
+    }
+
+    fn ancestors(&self) -> impl Iterator<Item = &ResourceGroup> {
+        std::iter::successors(Some(self), |group| group.parent.as_deref())
+    }
+
+    /// Returns the signal that should be delivered when this group or any
+    /// of its ancestors is over its memory limit
+    pub(crate) fn check_memory(&self) -> Result<(), Signal> {
+        for group in self.ancestors() {
+            if let Some(max) = group.limits.max_memory {
+                if group.memory_usage() > max {
+                    return Err(group.limits.oom_signal.unwrap_or(Signal::Sigkill));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that another file descriptor can be opened without going
+    /// over the limit of this group or any of its ancestors
+    ///
+    /// The groups with a limit stay locked (from the innermost outwards)
+    /// until the returned reservation is dropped.
+    pub(crate) fn reserve_open_file(&self) -> Result<OpenFileReservation<'_>, Errno> {
+        let mut guards = Vec::new();
+        for group in self.ancestors() {
+            if let Some(max) = group.limits.max_open_files {
+                guards.push(group.open_files_lock.lock().unwrap());
+                if group.open_files() >= max {
+                    return Err(Errno::Mfile);
+                }
+            }
+        }
+        Ok(OpenFileReservation { _guards: guards })
+    }
+
+    /// Returns true if the processes in the group are subject to a CPU quota
+    pub(crate) fn is_cpu_limited(&self) -> bool {
+        self.cpu_limited
+    }
+
+    /// Returns how long the processes in the group must be throttled for
+    /// because this group or any of its ancestors used up its CPU quota
+    pub(crate) fn cpu_throttle(&self) -> Option<Duration> {
+        if !self.cpu_limited {
+            return None;
+        }
+        flush_cpu_time();
+        let now = ResourceCounters::now();
+        self.ancestors()
+            .filter_map(|group| {
+                let quota = group.limits.cpu_quota?;
+                let used = group.cpu_time();
+                let mut window = group.cpu_window.lock().unwrap();
+                let elapsed = Duration::from_nanos(now.saturating_sub(window.started));
+                if window.started == 0 || elapsed >= quota.period {
+                    window.started = now;
+                    window.used = used;
+                    return None;
+                }
+                if used.saturating_sub(window.used) > quota.quota {
+                    Some(quota.period - elapsed)
+                } else {
+                    None
+                }
+            })
+            .max()
+    }
+}
+
+/// Memory that is charged to a group is charged to all its ancestors, and
+/// it can not take any of them over its memory limit
+impl FsMemoryLimiter for ResourceGroup {
+    fn on_grow(&self, grown_bytes: usize) -> Result<(), FsError> {
+        let grown_bytes = grown_bytes as u64;
+        for group in self.ancestors() {
+            if let Some(max) = group.limits.max_memory {
+                if group.memory_usage().saturating_add(grown_bytes) > max {
+                    return Err(FsError::StorageFull);
+                }
+            }
+        }
+        for group in self.ancestors() {
+            group.charged.fetch_add(grown_bytes, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    fn on_shrink(&self, shrunk_bytes: usize) {
+        for group in self.ancestors() {
+            group
+                .charged
+                .fetch_sub(shrunk_bytes as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{os::task::control_plane::WasiControlPlane, utils::xxhash_random};
+
+    use super::*;
+
+    #[test]
+    fn test_resource_group_limits_are_hierarchical() {
+        let plane = WasiControlPlane::default();
+        let root = plane.root_resource_group().clone();
+        let group = root.new_child(ResourceLimits {
+            max_memory: Some(1 << 20),
+            oom_signal: Some(Signal::Sigterm),
+            ..Default::default()
+        });
+        let nested = group.new_child(ResourceLimits::default());
+
+        let p1 = plane.new_process(xxhash_random()).unwrap();
+        p1.set_resource_group(group.clone());
+        let p2 = plane.new_process(xxhash_random()).unwrap();
+        p2.set_resource_group(nested.clone());
+
+        p1.usage.record_memory(1 << 19);
+        p2.usage.record_memory(1 << 19);
+        assert_eq!(group.memory_usage(), 1 << 20);
+        assert_eq!(nested.check_memory(), Ok(()));
+
+        // The nested group is subject to the limit of its parent
+        p2.usage.record_memory((1 << 19) + 1);
+        assert_eq!(nested.check_memory(), Err(Signal::Sigterm));
+        assert_eq!(root.check_memory(), Ok(()));
+
+        // Processes that leave the group no longer count towards it
+        p2.set_resource_group(root.clone());
+        assert_eq!(group.memory_usage(), 1 << 19);
+        assert_eq!(nested.check_memory(), Ok(()));
+
+        // Processes that exit no longer count either
+        let started = p1.usage.thread_started();
+        p1.usage.thread_finished(started);
+        assert_eq!(group.memory_usage(), 0);
+    }
+
+    #[test]
+    fn test_resource_group_cpu_quota() {
+        let plane = WasiControlPlane::default();
+        let root = plane.root_resource_group().clone();
+        let group = root.new_child(ResourceLimits {
+            cpu_quota: Some(CpuQuota {
+                quota: Duration::from_millis(10),
+                period: Duration::from_secs(60),
+            }),
+            ..Default::default()
+        });
+        let nested = group.new_child(ResourceLimits::default());
+        assert!(nested.is_cpu_limited());
+        assert!(!root.is_cpu_limited());
+
+        let p = plane.new_process(xxhash_random()).unwrap();
+        p.set_resource_group(nested.clone());
+
+        // The first check starts the period
+        assert_eq!(nested.cpu_throttle(), None);
+        p.usage.add_cpu_time(5_000_000);
+        assert_eq!(nested.cpu_throttle(), None);
+
+        // Going over the quota of the parent throttles the nested group
+        // for the rest of the period
+        p.usage.add_cpu_time(6_000_000);
+        assert_eq!(group.cpu_time(), Duration::from_millis(11));
+        let throttle = nested.cpu_throttle().unwrap();
+        assert!(throttle > Duration::ZERO && throttle <= Duration::from_secs(60));
+
+        // The time that was used stays with the group
+        p.set_resource_group(root.clone());
+        p.usage.add_cpu_time(1_000_000);
+        assert_eq!(group.cpu_time(), Duration::from_millis(11));
+        assert_eq!(root.cpu_time(), Duration::from_millis(12));
+        assert!(group.cpu_throttle().is_some());
+    }
+}
@@ -0,0 +1,338 @@
+//! Accounting of the resources that are consumed by processes.
+
+use std::{
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use wasmer_wasix_types::wasi::{Rusage, Snapshot0Clockid, Timestamp};
+
+use super::resource_group::ResourceGroup;
+use crate::syscalls::platform_clock_time_get;
+
+/// Snapshot of the resources that were used by a process (or by all the
+/// children of a process that have been joined)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Time that the threads of the process were alive and not blocked in
+    /// a syscall that waited on the host
+    ///
+    /// This is not the CPU time that the host measures: a thread that is
+    /// ready to run but is waiting for a core is counted as busy.
+    pub busy_time: Duration,
+    /// CPU time that the host measured for the threads while they ran the
+    /// code of the process, which is zero on hosts without per-thread CPU
+    /// clocks
+    pub cpu_time: Duration,
+    /// Time between the process starting and its last thread exiting
+    pub wall_time: Duration,
+    /// Largest size of the linear memory that was observed
+    pub peak_memory: u64,
+    /// Number of bytes read from files, pipes and terminals
+    pub bytes_read: u64,
+    /// Number of bytes written to files, pipes and terminals
+    pub bytes_written: u64,
+    /// Number of bytes received on sockets
+    pub socket_bytes_read: u64,
+    /// Number of bytes sent on sockets
+    pub socket_bytes_written: u64,
+}
+
+impl AddAssign for ResourceUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.busy_time += rhs.busy_time;
+        self.cpu_time += rhs.cpu_time;
+        self.wall_time += rhs.wall_time;
+        self.peak_memory = self.peak_memory.max(rhs.peak_memory);
+        self.bytes_read += rhs.bytes_read;
+        self.bytes_written += rhs.bytes_written;
+        self.socket_bytes_read += rhs.socket_bytes_read;
+        self.socket_bytes_written += rhs.socket_bytes_written;
+    }
+}
+
+impl From<ResourceUsage> for Rusage {
+    fn from(usage: ResourceUsage) -> Self {
+        Rusage {
+            busy_time: usage.busy_time.as_nanos() as Timestamp,
+            wall_time: usage.wall_time.as_nanos() as Timestamp,
+            peak_memory: usage.peak_memory,
+            bytes_read: usage.bytes_read,
+            bytes_written: usage.bytes_written,
+            socket_bytes_read: usage.socket_bytes_read,
+            socket_bytes_written: usage.socket_bytes_written,
+            cpu_time: usage.cpu_time.as_nanos() as Timestamp,
+        }
+    }
+}
+
+/// Counters that are updated while a process runs
+///
+/// Busy time is the sum of how long each thread has been alive minus the
+/// time it spent blocked inside asynchronous syscalls. Time spent in light
+/// weight syscalls that complete without yielding is counted as busy and
+/// memory is only sampled when the process handles syscalls.
+///
+/// The threads of a process can move between host threads, so CPU time is
+/// read from the CPU clock of the host thread for every stretch of time
+/// that a host thread runs the code of a thread (see
+/// [`CurrentThreadGuard`](super::thread::CurrentThreadGuard)).
+///
+/// The memory and CPU time are also counted by the [`ResourceGroup`] of the
+/// process, the memory only while the process is running.
+#[derive(Debug)]
+pub struct ResourceCounters {
+    /// Monotonic time (in nanoseconds) when the process was created
+    started: u64,
+    /// Monotonic time (in nanoseconds) when the last thread exited
+    finished: AtomicU64,
+    /// Total lifetime (in nanoseconds) of the threads that have exited
+    thread_time: AtomicU64,
+    /// Number of threads that are currently running
+    running_threads: AtomicU64,
+    /// Sum of the start times of the threads that are currently running,
+    /// relative to when the process was created
+    running_starts: AtomicU64,
+    /// Total time (in nanoseconds) that threads spent blocked
+    blocked_time: AtomicU64,
+    /// Total CPU time (in nanoseconds) that the host threads spent running
+    /// the threads
+    cpu_time: AtomicU64,
+    memory: AtomicU64,
+    peak_memory: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    socket_bytes_read: AtomicU64,
+    socket_bytes_written: AtomicU64,
+    /// Resources used by the children that have been joined
+    children: Mutex<ResourceUsage>,
+    /// Group that the memory and CPU time are counted by
+    group: RwLock<Option<Arc<ResourceGroup>>>,
+}
+
+impl ResourceCounters {
+    pub fn new() -> Self {
+        Self {
+            started: Self::now(),
+            finished: AtomicU64::new(0),
+            thread_time: AtomicU64::new(0),
+            running_threads: AtomicU64::new(0),
+            running_starts: AtomicU64::new(0),
+            blocked_time: AtomicU64::new(0),
+            cpu_time: AtomicU64::new(0),
+            memory: AtomicU64::new(0),
+            peak_memory: AtomicU64::new(0),
+            bytes_read: AtomicU64::new(0),
+            bytes_written: AtomicU64::new(0),
+            socket_bytes_read: AtomicU64::new(0),
+            socket_bytes_written: AtomicU64::new(0),
+            children: Mutex::new(ResourceUsage::default()),
+            group: RwLock::new(None),
+        }
+    }
+
+    /// Moves the memory that the process uses to another group, which
+    /// also counts the CPU time that the process uses from now on
+    pub(crate) fn set_group(&self, group: Arc<ResourceGroup>) {
+        let mut guard = self.group.write().unwrap();
+        let memory = self.memory.load(Ordering::Relaxed);
+        if let Some(previous) = guard.as_ref() {
+            previous.shrink_memory(memory);
+        }
+        group.grow_memory(memory);
+        *guard = Some(group);
+    }
+
+    /// Returns the current monotonic time in nanoseconds
+    pub(crate) fn now() -> u64 {
+        platform_clock_time_get(Snapshot0Clockid::Monotonic, 1_000)
+            .map(|time| time as u64)
+            .unwrap_or_default()
+    }
+
+    /// Returns a snapshot of the resources used by the process itself
+    pub fn usage(&self) -> ResourceUsage {
+        let now = Self::now();
+        let running = self.running_threads.load(Ordering::Acquire);
+        let live = running
+            .saturating_mul(now.saturating_sub(self.started))
+            .saturating_sub(self.running_starts.load(Ordering::Acquire));
+        let thread_time = self
+            .thread_time
+            .load(Ordering::Acquire)
+            .saturating_add(live);
+        let busy_time = thread_time.saturating_sub(self.blocked_time.load(Ordering::Acquire));
+
+        let finished = match self.finished.load(Ordering::Acquire) {
+            0 => now,
+            finished => finished,
+        };
+        let wall_time = finished.saturating_sub(self.started);
+
+        ResourceUsage {
+            busy_time: Duration::from_nanos(busy_time),
+            cpu_time: Duration::from_nanos(self.cpu_time.load(Ordering::Acquire)),
+            wall_time: Duration::from_nanos(wall_time),
+            peak_memory: self.peak_memory.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            socket_bytes_read: self.socket_bytes_read.load(Ordering::Relaxed),
+            socket_bytes_written: self.socket_bytes_written.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the resources used by all the children of the process
+    /// that have been joined, including their own children
+    pub fn children_usage(&self) -> ResourceUsage {
+        *self.children.lock().unwrap()
+    }
+
+    /// Returns the resources used by the process and the children that
+    /// it has joined, which is what its parent adds when it joins it
+    pub fn total_usage(&self) -> ResourceUsage {
+        let mut usage = self.usage();
+        usage += self.children_usage();
+        usage
+    }
+
+    /// Records that a thread has started, returning its start time
+    pub(crate) fn thread_started(&self) -> u64 {
+        let now = Self::now();
+        self.running_starts
+            .fetch_add(now.saturating_sub(self.started), Ordering::AcqRel);
+        self.running_threads.fetch_add(1, Ordering::AcqRel);
+        self.finished.store(0, Ordering::Release);
+        now
+    }
+
+    /// Records that a thread which started at `started` has exited
+    pub(crate) fn thread_finished(&self, started: u64) {
+        let now = Self::now();
+        self.thread_time
+            .fetch_add(now.saturating_sub(started), Ordering::AcqRel);
+        self.running_starts
+            .fetch_sub(started.saturating_sub(self.started), Ordering::AcqRel);
+        if self.running_threads.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.finished.store(now, Ordering::Release);
+            self.record_memory(0);
+        }
+    }
+
+    /// Records that a thread was blocked since the monotonic time `since`
+    pub(crate) fn add_blocked(&self, since: u64) {
+        self.blocked_time
+            .fetch_add(Self::now().saturating_sub(since), Ordering::AcqRel);
+    }
+
+    /// Records CPU time that a host thread spent running a thread
+    pub(crate) fn add_cpu_time(&self, nanos: u64) {
+        self.cpu_time.fetch_add(nanos, Ordering::AcqRel);
+        if let Some(group) = self.group.read().unwrap().as_ref() {
+            group.add_cpu_time(nanos);
+        }
+    }
+
+    /// Records the current size of the linear memory
+    pub(crate) fn record_memory(&self, size: u64) {
+        let guard = self.group.read().unwrap();
+        let previous = self.memory.swap(size, Ordering::Relaxed);
+        self.peak_memory.fetch_max(size, Ordering::Relaxed);
+        if let Some(group) = guard.as_ref() {
+            if size > previous {
+                group.grow_memory(size - previous);
+            } else {
+                group.shrink_memory(previous - size);
+            }
+        }
+    }
+
+    pub(crate) fn add_read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_written(&self, bytes: usize) {
+        self.bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_socket_read(&self, bytes: usize) {
+        self.socket_bytes_read
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_socket_written(&self, bytes: usize) {
+        self.socket_bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Adds the resources of a child that has been joined (and those of
+    /// its own children) to the totals of this process, returning them
+    pub(crate) fn add_child(&self, child: &ResourceCounters) -> ResourceUsage {
+        let usage = child.total_usage();
+        *self.children.lock().unwrap() += usage;
+        usage
+    }
+}
+
+impl Default for ResourceCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ResourceCounters {
+    fn drop(&mut self) {
+        self.record_memory(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_counters_aggregate_children() {
+        let parent = ResourceCounters::new();
+        let child = ResourceCounters::new();
+
+        let started = child.thread_started();
+        child.add_read(10);
+        child.add_written(20);
+        child.add_socket_read(30);
+        child.add_socket_written(40);
+        child.record_memory(1 << 16);
+        child.record_memory(1 << 12);
+        child.add_blocked(started);
+        child.add_cpu_time(1_000);
+        child.thread_finished(started);
+
+        let usage = child.usage();
+        assert_eq!(usage.bytes_read, 10);
+        assert_eq!(usage.bytes_written, 20);
+        assert_eq!(usage.socket_bytes_read, 30);
+        assert_eq!(usage.socket_bytes_written, 40);
+        assert_eq!(usage.peak_memory, 1 << 16);
+        assert!(usage.busy_time <= usage.wall_time);
+        assert_eq!(usage.cpu_time, Duration::from_nanos(1_000));
+
+        // The wall time stops when the last thread exits
+        assert_eq!(child.usage().wall_time, usage.wall_time);
+
+        parent.record_memory(1 << 20);
+        assert_eq!(parent.add_child(&child), usage);
+        parent.add_child(&child);
+
+        let children = parent.children_usage();
+        assert_eq!(children.bytes_read, 20);
+        assert_eq!(children.socket_bytes_written, 80);
+        assert_eq!(children.peak_memory, 1 << 16);
+        assert_eq!(children.wall_time, usage.wall_time * 2);
+        assert_eq!(children.cpu_time, usage.cpu_time * 2);
+        assert_eq!(parent.usage().bytes_read, 0);
+    }
+}
@@ -8,7 +8,10 @@ use wasmer_wasix_types::wasi::{Errno, ExitCode};
 
 use crate::WasiRuntimeError;
 
-use super::signal::{default_signal_handler, DynSignalHandlerAbi};
+use super::{
+    rusage::{ResourceCounters, ResourceUsage},
+    signal::{default_signal_handler, DynSignalHandlerAbi},
+};
 
 #[derive(Clone, Debug)]
 pub enum TaskStatus {
@@ -74,6 +77,8 @@ pub trait VirtualTaskHandle: std::fmt::Debug + Send + Sync + 'static {
 pub struct OwnedTaskStatus {
     // The signal handler that can be invoked for this owned task
     signal_handler: Arc<DynSignalHandlerAbi>,
+    // Resources consumed by the task (when it is a process)
+    usage: Option<Arc<ResourceCounters>>,
 
     watch_tx: tokio::sync::watch::Sender<TaskStatus>,
     // Even through unused, without this receive there is a race condition
@@ -87,6 +92,7 @@ impl OwnedTaskStatus {
         let (tx, rx) = tokio::sync::watch::channel(status);
         Self {
             signal_handler: default_signal_handler(),
+            usage: None,
             watch_tx: tx,
             watch_rx: rx,
         }
@@ -103,6 +109,12 @@ impl OwnedTaskStatus {
         self
     }
 
+    /// Attaches the resource counters of the process that runs this task
+    pub fn with_usage(mut self, usage: Arc<ResourceCounters>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
     pub fn new_finished_with_code(code: ExitCode) -> Self {
         Self::new(TaskStatus::Finished(Ok(code)))
     }
@@ -162,6 +174,7 @@ impl OwnedTaskStatus {
     pub fn handle(&self) -> TaskJoinHandle {
         TaskJoinHandle {
             signal_handler: self.signal_handler.clone(),
+            usage: self.usage.clone(),
             watch: self.watch_tx.subscribe(),
         }
     }
@@ -178,6 +191,7 @@ impl Default for OwnedTaskStatus {
 pub struct TaskJoinHandle {
     #[allow(unused)]
     signal_handler: Arc<DynSignalHandlerAbi>,
+    usage: Option<Arc<ResourceCounters>>,
     watch: tokio::sync::watch::Receiver<TaskStatus>,
 }
 
@@ -187,6 +201,17 @@ impl TaskJoinHandle {
         self.watch.borrow().clone()
     }
 
+    /// Retrieve the resources consumed by the task, if it is a process
+    pub fn usage(&self) -> Option<ResourceUsage> {
+        self.usage.as_ref().map(|usage| usage.usage())
+    }
+
+    /// Retrieve the resources consumed by the children of the task
+    /// that were joined, if it is a process
+    pub fn children_usage(&self) -> Option<ResourceUsage> {
+        self.usage.as_ref().map(|usage| usage.children_usage())
+    }
+
     #[cfg(feature = "ctrlc")]
     pub fn install_ctrlc_handler(&self) {
         use wasmer::FromToNativeWasmType;
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
+    cell::{Cell, RefCell},
     collections::HashMap,
     ops::{Deref, DerefMut},
-    sync::{Arc, Condvar, Mutex, Weak},
+    sync::{Arc, Condvar, Mutex, OnceLock, Weak},
     task::Waker,
 };
 
@@ -23,6 +24,7 @@ use crate::{
 
 use super::{
     control_plane::TaskCountGuard,
+    rusage::ResourceCounters,
     task_join_handle::{OwnedTaskStatus, TaskJoinHandle},
 };
 
@@ -212,6 +214,125 @@ impl Drop for WasiThreadRunGuard {
     }
 }
 
+thread_local! {
+    /// Read from signal handlers (see [`current_thread_ids`]), so it has to
+    /// stay const initialised and without a destructor.
+    static CURRENT_THREAD: Cell<Option<(WasiProcessId, WasiThreadId)>> = const { Cell::new(None) };
+    static CURRENT_CPU: RefCell<Option<CpuStretch>> = const { RefCell::new(None) };
+}
+
+/// CPU time that the host thread has spent running the code of a process
+/// since `started` (the CPU clock of the host thread when it started)
+struct CpuStretch {
+    usage: Arc<ResourceCounters>,
+    started: u64,
+}
+
+/// CPU time of the calling host thread in nanoseconds, if the host has
+/// per-thread CPU clocks
+fn host_thread_cpu_time() -> Option<u64> {
+    #[cfg(any(
+        target_os = "freebsd",
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple"
+    ))]
+    {
+        use wasmer_wasix_types::wasi::Snapshot0Clockid;
+
+        crate::syscalls::platform_clock_time_get(Snapshot0Clockid::ThreadCputimeId, 1)
+            .ok()
+            .map(|time| time as u64)
+    }
+    #[cfg(not(any(
+        target_os = "freebsd",
+        target_os = "linux",
+        target_os = "android",
+        target_vendor = "apple"
+    )))]
+    {
+        None
+    }
+}
+
+/// Ends the stretch of CPU time that the host thread is in, recording it
+/// in the usage of its process, and starts a new one for `usage`
+fn switch_cpu_stretch(usage: Option<Arc<ResourceCounters>>) -> Option<Arc<ResourceCounters>> {
+    let now = host_thread_cpu_time();
+    CURRENT_CPU.with(|current| {
+        let next = usage
+            .zip(now)
+            .map(|(usage, started)| CpuStretch { usage, started });
+        let previous = current.replace(next)?;
+        if let Some(now) = now {
+            previous
+                .usage
+                .add_cpu_time(now.saturating_sub(previous.started));
+        }
+        Some(previous.usage)
+    })
+}
+
+/// Records the CPU time that the calling host thread has spent running the
+/// code of its current thread so far
+pub(crate) fn flush_cpu_time() {
+    let usage = switch_cpu_stretch(None);
+    switch_cpu_stretch(usage);
+}
+
+/// Marks the host thread as running the code of a WASIX thread until it is
+/// dropped, see [`current_thread_ids`], and charges the CPU time of the host
+/// thread to the process in the meantime
+pub(crate) struct CurrentThreadGuard {
+    previous: Option<(WasiProcessId, WasiThreadId)>,
+    previous_usage: Option<Arc<ResourceCounters>>,
+}
+
+impl CurrentThreadGuard {
+    pub(crate) fn enter(
+        pid: WasiProcessId,
+        tid: WasiThreadId,
+        usage: &Arc<ResourceCounters>,
+    ) -> Self {
+        let previous = CURRENT_THREAD.with(|current| current.replace(Some((pid, tid))));
+        let previous_usage = switch_cpu_stretch(Some(usage.clone()));
+        Self {
+            previous,
+            previous_usage,
+        }
+    }
+}
+
+impl Drop for CurrentThreadGuard {
+    fn drop(&mut self) {
+        CURRENT_THREAD.with(|current| current.set(self.previous));
+        switch_cpu_stretch(self.previous_usage.take());
+    }
+}
+
+/// Returns the ids of the WASIX process and thread whose code runs on the
+/// calling host thread.
+///
+/// This only reads a thread local, so it may be called from a signal
+/// handler that interrupted the WebAssembly code, which is how sampling
+/// profilers attribute their samples.
+pub fn current_thread_ids() -> Option<(WasiProcessId, WasiThreadId)> {
+    CURRENT_THREAD.with(|current| current.get())
+}
+
+static FATAL_SIGNAL_HOOK: OnceLock<Box<dyn Fn(Signal) + Send + Sync>> = OnceLock::new();
+
+/// Registers a hook that is called when a thread is terminated by a signal
+/// whose default action is to dump core (`SIGQUIT` and `SIGABRT`), and
+/// returns false if a hook was already registered.
+///
+/// The hook runs on the host thread of the terminated thread while its
+/// WebAssembly frames are still on the stack, which is how `wasmer run`
+/// writes coredumps of the process.
+pub fn set_fatal_signal_hook(hook: impl Fn(Signal) + Send + Sync + 'static) -> bool {
+    FATAL_SIGNAL_HOOK.set(Box::new(hook)).is_ok()
+}
+
 /// Represents the memory layout of the parts that the thread itself uses
 pub use wasmer_wasix_types::wasix::WasiMemoryLayout;
 
@@ -321,6 +442,11 @@ impl WasiThread {
             Signal::Sigpipe => Errno::Pipe.into(),
             _ => Errno::Intr.into(),
         };
+        if matches!(sig, Signal::Sigquit | Signal::Sigabrt) && self.try_join().is_none() {
+            if let Some(hook) = FATAL_SIGNAL_HOOK.get() {
+                hook(sig);
+            }
+        }
         // This will only set the status code if its not already set
         self.set_status_finished(Ok(default_exitcode));
         self.try_join()
@@ -558,6 +684,8 @@ impl WasiThread {
 pub struct WasiThreadHandleProtected {
     thread: WasiThread,
     inner: Weak<(Mutex<WasiProcessInner>, Condvar)>,
+    usage: Arc<ResourceCounters>,
+    started: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -569,11 +697,14 @@ impl WasiThreadHandle {
     pub(crate) fn new(
         thread: WasiThread,
         inner: &Arc<(Mutex<WasiProcessInner>, Condvar)>,
+        usage: &Arc<ResourceCounters>,
     ) -> WasiThreadHandle {
         Self {
             protected: Arc::new(WasiThreadHandleProtected {
                 thread,
                 inner: Arc::downgrade(inner),
+                usage: usage.clone(),
+                started: usage.thread_started(),
             }),
         }
     }
@@ -597,6 +728,7 @@ impl Drop for WasiThreadHandleProtected {
             }
             inner.thread_count -= 1;
         }
+        self.usage.thread_finished(self.started);
     }
 }
 
@@ -9,7 +9,8 @@ use wasmer_wasix_types::wasi::{Signal, Snapshot0Clockid};
 
 use crate::syscalls::platform_clock_time_get;
 
-use super::task::signal::SignalHandlerAbi;
+use super::task::{control_plane::WasiControlPlaneHandle, signal::SignalHandlerAbi};
+use crate::WasiProcessId;
 
 const TTY_MOBILE_PAUSE: u128 = std::time::Duration::from_millis(200).as_nanos();
 
@@ -112,11 +113,52 @@ impl TtyOptions {
     }
 }
 
+/// Tracks the process group that is in the foreground of a TTY, which is
+/// the group that the signals generated by the keyboard are sent to
+#[derive(Debug, Clone, Default)]
+pub struct TtyForeground {
+    inner: Arc<Mutex<Option<(WasiProcessId, WasiControlPlaneHandle)>>>,
+}
+
+impl TtyForeground {
+    /// Returns the process group that is in the foreground
+    pub fn pgid(&self) -> Option<WasiProcessId> {
+        let inner = self.inner.lock().unwrap();
+        inner.as_ref().map(|(pgid, _)| *pgid)
+    }
+
+    /// Moves a process group into the foreground
+    pub fn set(&self, pgid: WasiProcessId, control_plane: WasiControlPlaneHandle) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.replace((pgid, control_plane));
+    }
+
+    /// Leaves the TTY without a foreground process group
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.take();
+    }
+
+    /// Sends a signal to the foreground process group, returning false if
+    /// there is no foreground process group or none of its processes are
+    /// still running
+    pub fn signal(&self, signal: Signal) -> bool {
+        let Some((pgid, control_plane)) = self.inner.lock().unwrap().clone() else {
+            return false;
+        };
+        control_plane
+            .upgrade()
+            .map(|control_plane| control_plane.signal_process_group(pgid, signal))
+            .unwrap_or(false)
+    }
+}
+
 #[derive(Debug)]
 pub struct Tty {
     stdin: Box<dyn VirtualFile + Send + Sync + 'static>,
     stdout: Box<dyn VirtualFile + Send + Sync + 'static>,
     signaler: Option<Box<dyn SignalHandlerAbi + Send + Sync + 'static>>,
+    foreground: TtyForeground,
     is_mobile: bool,
     last: Option<(String, u128)>,
     options: TtyOptions,
@@ -134,6 +176,7 @@ impl Tty {
             stdin,
             stdout,
             signaler: None,
+            foreground: TtyForeground::default(),
             last: None,
             options,
             is_mobile,
@@ -167,6 +210,12 @@ impl Tty {
         self.options.clone()
     }
 
+    /// Returns the tracker of the foreground process group, which should be
+    /// shared with the [`TtyBridge`] that the processes see
+    pub fn foreground(&self) -> TtyForeground {
+        self.foreground.clone()
+    }
+
     pub fn set_signaler(&mut self, signaler: Box<dyn SignalHandlerAbi + Send + Sync + 'static>) {
         self.signaler.replace(signaler);
     }
@@ -224,11 +273,32 @@ impl Tty {
         })
     }
 
-    fn on_ctrl_c(mut self, _data: Cow<'static, [u8]>) -> BoxFuture<'static, Self> {
+    fn on_ctrl_c(self, _data: Cow<'static, [u8]>) -> BoxFuture<'static, Self> {
+        self.on_signal(Signal::Sigint)
+    }
+
+    fn on_ctrl_z(self, _data: Cow<'static, [u8]>) -> BoxFuture<'static, Self> {
+        self.on_signal(Signal::Sigtstp)
+    }
+
+    fn on_ctrl_backslash(self, _data: Cow<'static, [u8]>) -> BoxFuture<'static, Self> {
+        self.on_signal(Signal::Sigquit)
+    }
+
+    fn on_signal(mut self, signal: Signal) -> BoxFuture<'static, Self> {
         Box::pin(async move {
-            if let Some(signaler) = self.signaler.as_ref() {
-                signaler.signal(Signal::Sigint as u8).ok();
+            // The foreground process group takes the signal, otherwise it
+            // goes to whoever is listening for signals on this terminal
+            let delivered = if self.foreground.signal(signal) {
+                true
+            } else if let Some(signaler) = self.signaler.as_ref() {
+                signaler.signal(signal as u8).ok();
+                true
+            } else {
+                false
+            };
 
+            if delivered {
                 let (echo, _line_buffering) = {
                     let options = self.options.inner.lock().unwrap();
                     (options.echo, options.line_buffering)
@@ -362,6 +432,8 @@ impl Tty {
             return match String::from_utf8_lossy(data.as_ref()).as_ref() {
                 "\r" | "\u{000A}" => self.on_enter(data),
                 "\u{0003}" => self.on_ctrl_c(data),
+                "\u{001A}" => self.on_ctrl_z(data),
+                "\u{001C}" => self.on_ctrl_backslash(data),
                 "\u{007F}" => self.on_backspace(data),
                 "\u{0009}" => self.on_tab(data),
                 "\u{001B}\u{005B}\u{0044}" => self.on_cursor_left(data),
@@ -451,4 +523,10 @@ pub trait TtyBridge: std::fmt::Debug {
 
     /// Set the TTY state.
     fn tty_set(&self, _tty_state: WasiTtyState);
+
+    /// Returns the tracker of the foreground process group of this TTY, or
+    /// `None` if the TTY does not support job control
+    fn foreground(&self) -> Option<TtyForeground> {
+        None
+    }
 }
@@ -1,9 +1,11 @@
-use super::TtyBridge;
+use super::{TtyBridge, TtyForeground};
 use crate::WasiTtyState;
 
 /// [`TtyBridge`] implementation for Unix systems.
 #[derive(Debug, Default, Clone)]
-pub struct SysTty;
+pub struct SysTty {
+    foreground: TtyForeground,
+}
 
 impl TtyBridge for SysTty {
     fn reset(&self) {
@@ -50,6 +52,10 @@ impl TtyBridge for SysTty {
             sys::set_mode_no_line_feeds().ok();
         }
     }
+
+    fn foreground(&self) -> Option<TtyForeground> {
+        Some(self.foreground.clone())
+    }
 }
 
 mod sys_terminal_size {
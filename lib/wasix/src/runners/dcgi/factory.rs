@@ -41,6 +41,11 @@ impl DcgiInstanceFactory {
     pub async fn acquire(&self, conf: &mut CreateEnvConfig) -> Option<CreateEnvResult> {
         let mut state = self.state.lock().unwrap();
         if let Some(inst) = state.instance.take() {
+            // The package was reloaded, the instance runs the previous module
+            if inst.env.process.module_hash != conf.module_hash {
+                tracing::debug!("discarding the DCGI instance of another module");
+                return None;
+            }
             tracing::debug!("attempting to reinitialize DCGI instance");
             match convert_instance(inst, conf) {
                 Ok(converted) => return Some(converted),
@@ -22,7 +22,7 @@ impl Handler {
     pub(crate) fn new(handler: wcgi::Handler) -> Self {
         Handler {
             state: Arc::new(SharedState {
-                inner: handler.deref().clone(),
+                inner: handler.state(),
                 factory: DcgiInstanceFactory::new(),
                 master_lock: Default::default(),
             }),
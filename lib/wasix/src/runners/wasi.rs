@@ -1,6 +1,9 @@
 //! WebC container support for running WASI modules
 
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::{Context, Error};
 use futures::future::Either;
@@ -16,7 +19,7 @@ use crate::{
     journal::{DynJournal, DynReadableJournal, SnapshotTrigger},
     runners::{wasi_common::CommonWasiOptions, MappedDirectory, MountedDirectory},
     runtime::task_manager::VirtualTaskManagerExt,
-    Runtime, WasiEnvBuilder, WasiError, WasiRuntimeError,
+    Runtime, WasiEnvBuilder, WasiError, WasiProcess, WasiRuntimeError,
 };
 
 use super::wasi_common::{MappedCommand, MAPPED_CURRENT_DIR_DEFAULT_PATH};
@@ -27,6 +30,27 @@ pub struct WasiRunner {
     stdin: Option<ArcBoxFile>,
     stdout: Option<ArcBoxFile>,
     stderr: Option<ArcBoxFile>,
+    process_handle: Option<ProcessHandle>,
+}
+
+/// Gives other threads access to the process started by a [`WasiRunner`],
+/// for instance to kill it, see [`WasiRunner::with_process_handle()`]
+#[derive(Debug, Clone, Default)]
+pub struct ProcessHandle(Arc<Mutex<Option<WasiProcess>>>);
+
+impl ProcessHandle {
+    pub fn new() -> Self {
+        ProcessHandle::default()
+    }
+
+    /// The process that is running, if any
+    pub fn process(&self) -> Option<WasiProcess> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set(&self, process: Option<WasiProcess>) {
+        *self.0.lock().unwrap() = process;
+    }
 }
 
 impl WasiRunner {
@@ -238,6 +262,12 @@ impl WasiRunner {
         self
     }
 
+    /// Store the process in this handle while it runs.
+    pub fn with_process_handle(&mut self, handle: ProcessHandle) -> &mut Self {
+        self.process_handle = Some(handle);
+        self
+    }
+
     /// Add an item to the list of importable items provided to the instance.
     pub fn with_import(
         &mut self,
@@ -372,6 +402,9 @@ impl WasiRunner {
         }
 
         let env = builder.build()?;
+        if let Some(handle) = &self.process_handle {
+            handle.set(Some(env.process.clone()));
+        }
         let tasks = runtime.task_manager().clone();
 
         let exit_code = tasks.spawn_and_block_on(
@@ -402,7 +435,11 @@ impl WasiRunner {
                     .context("Unable to wait for the process to exit")
             }
             .in_current_span(),
-        )??;
+        );
+        if let Some(handle) = &self.process_handle {
+            handle.set(None);
+        }
+        let exit_code = exit_code??;
 
         if exit_code.raw() == 0 {
             Ok(())
@@ -483,6 +520,9 @@ impl crate::runners::Runner for WasiRunner {
         }
 
         let env = builder.build()?;
+        if let Some(handle) = &self.process_handle {
+            handle.set(Some(env.process.clone()));
+        }
         let command_name = command_name.to_string();
         let tasks = runtime.task_manager().clone();
         let pkg = pkg.clone();
@@ -517,7 +557,11 @@ impl crate::runners::Runner for WasiRunner {
                     .context("Unable to wait for the process to exit")
             }
             .in_current_span(),
-        )??;
+        );
+        if let Some(handle) = &self.process_handle {
+            handle.set(None);
+        }
+        let exit_code = exit_code??;
 
         if exit_code.raw() == 0 {
             Ok(())
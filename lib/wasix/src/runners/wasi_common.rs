@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    ffi::{OsStr, OsString},
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -7,7 +8,9 @@ use std::{
 use anyhow::{Context, Error};
 use futures::future::BoxFuture;
 use tokio::runtime::Handle;
-use virtual_fs::{FileSystem, FsError, OverlayFileSystem, RootFileSystemBuilder, TmpFileSystem};
+use virtual_fs::{
+    FileSystem, FsError, OverlayFileSystem, RootFileSystemBuilder, TmpFileSystem, XattrMode,
+};
 use wasmer::Imports;
 use webc::metadata::annotations::Wasi as WasiAnnotation;
 
@@ -331,6 +334,24 @@ impl<F: FileSystem> RelativeOrAbsolutePathHack<F> {
             result
         }
     }
+
+    /// The same as [`Self::execute`], for operations that are asynchronous
+    async fn execute_async<'a, Func, Ret>(
+        &'a self,
+        path: &Path,
+        operation: Func,
+    ) -> Result<Ret, FsError>
+    where
+        Func: Fn(&'a F, PathBuf) -> BoxFuture<'a, Result<Ret, FsError>>,
+    {
+        let result = operation(&self.0, path.to_path_buf()).await;
+
+        if result.is_err() && !path.is_absolute() {
+            operation(&self.0, Path::new("/").join(path)).await
+        } else {
+            result
+        }
+    }
 }
 
 impl<F: FileSystem> virtual_fs::FileSystem for RelativeOrAbsolutePathHack<F> {
@@ -384,6 +405,40 @@ impl<F: FileSystem> virtual_fs::FileSystem for RelativeOrAbsolutePathHack<F> {
             f.mount(name_ref.clone(), p, Box::new(f_ref.clone()))
         })
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> virtual_fs::Result<Vec<u8>> {
+        self.execute(path, |fs, p| fs.get_xattr(p, name))
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(self.execute_async(path, move |fs, p| {
+            Box::pin(async move { fs.set_xattr(&p, name, value, mode).await })
+        }))
+    }
+
+    fn list_xattr(&self, path: &Path) -> virtual_fs::Result<Vec<OsString>> {
+        self.execute(path, |fs, p| fs.list_xattr(p))
+    }
+
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(self.execute_async(path, move |fs, p| {
+            Box::pin(async move { fs.remove_xattr(&p, name).await })
+        }))
+    }
+
+    fn create_socket(&self, path: &Path) -> virtual_fs::Result<()> {
+        self.execute(path, |fs, p| fs.create_socket(p))
+    }
 }
 
 impl<F: FileSystem> virtual_fs::FileOpener for RelativeOrAbsolutePathHack<F> {
@@ -499,6 +554,26 @@ mod tests {
             .is_file());
     }
 
+    #[tokio::test]
+    async fn xattrs_of_relative_paths_fall_back_to_absolute_paths() {
+        let fs = RelativeOrAbsolutePathHack(virtual_fs::mem_fs::FileSystem::default());
+        fs.new_open_options()
+            .create(true)
+            .write(true)
+            .open("/file.txt")
+            .unwrap();
+        let path = Path::new("file.txt");
+        let name = OsStr::new("user.comment");
+
+        fs.set_xattr(path, name, b"hello", XattrMode::Upsert)
+            .await
+            .unwrap();
+        assert_eq!(fs.get_xattr(path, name).unwrap(), b"hello");
+        assert_eq!(fs.list_xattr(path).unwrap(), [name]);
+        fs.remove_xattr(path, name).await.unwrap();
+        assert!(fs.list_xattr(path).unwrap().is_empty());
+    }
+
     fn unix_timestamp_nanos(instant: SystemTime) -> Option<u64> {
         let duration = instant.duration_since(SystemTime::UNIX_EPOCH).ok()?;
         Some(duration.as_nanos() as u64)
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, ops::Deref, pin::Pin, sync::Arc};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
 
 use anyhow::Error;
 use bytes::Bytes;
@@ -33,19 +37,43 @@ use wasmer_types::ModuleHash;
 /// The shared object that manages the instantiaion of WASI executables and
 /// communicating with them via the CGI protocol.
 #[derive(Clone, Debug)]
-pub(crate) struct Handler(Arc<SharedState>);
+pub(crate) struct Handler(Arc<RwLock<Arc<SharedState>>>);
 
 impl Handler {
     pub(crate) fn new(state: Arc<SharedState>) -> Self {
-        Handler(state)
+        Handler(Arc::new(RwLock::new(state)))
+    }
+
+    /// The state used by the new requests
+    pub(crate) fn state(&self) -> Arc<SharedState> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Serves the new requests with another state, the requests that
+    /// already started keep the previous one
+    pub(crate) fn replace(&self, state: Arc<SharedState>) {
+        *self.0.write().unwrap() = state;
     }
 
-    #[tracing::instrument(level = "debug", skip_all, err)]
     pub(crate) async fn handle<T>(
         &self,
         req: Request<hyper::body::Incoming>,
         token: T,
     ) -> Result<Response<Body>, Error>
+    where
+        T: Send + 'static,
+    {
+        self.state().handle(req, token).await
+    }
+}
+
+impl SharedState {
+    #[tracing::instrument(level = "debug", skip_all, err)]
+    async fn handle<T>(
+        &self,
+        req: Request<hyper::body::Incoming>,
+        token: T,
+    ) -> Result<Response<Body>, Error>
     where
         T: Send + 'static,
     {
@@ -226,14 +254,6 @@ impl Handler {
     }
 }
 
-impl Deref for Handler {
-    type Target = Arc<SharedState>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
 /// Drive the request to completion by streaming the request body to the
 /// instance and waiting for it to exit.
 async fn drive_request_to_completion(
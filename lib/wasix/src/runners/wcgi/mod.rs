@@ -1,9 +1,13 @@
 mod callbacks;
 mod create_env;
 mod handler;
+mod reload;
 mod runner;
 
-pub use self::runner::{Config, WcgiRunner};
+pub use self::{
+    reload::PackageReloader,
+    runner::{Config, WcgiRunner},
+};
 pub use callbacks::NoOpWcgiCallbacks;
 pub use callbacks::{Callbacks, CreateEnvConfig, CreateEnvResult, RecycleEnvConfig};
 pub(crate) use create_env::default_create_env;
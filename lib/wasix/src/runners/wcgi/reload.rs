@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Error;
+
+use crate::bin_factory::BinaryPackage;
+
+use super::{runner::HandlerTemplate, Handler};
+
+/// Replaces the package served by a running WCGI or DCGI server, without
+/// closing its listener.
+///
+/// Pass it to [`Config::reloader()`][super::Config::reloader] before the
+/// server starts. The requests that already started finish with the
+/// previous package.
+#[derive(Debug, Clone, Default)]
+pub struct PackageReloader(Arc<Mutex<Option<Target>>>);
+
+#[derive(Debug)]
+struct Target {
+    handler: Handler,
+    template: HandlerTemplate,
+}
+
+impl PackageReloader {
+    pub fn new() -> Self {
+        PackageReloader::default()
+    }
+
+    pub(crate) fn attach(&self, handler: Handler, template: HandlerTemplate) {
+        *self.0.lock().unwrap() = Some(Target { handler, template });
+    }
+
+    /// Serve the same command from another version of the package.
+    ///
+    /// Returns `false` if the server hasn't started yet.
+    pub fn reload(&self, pkg: &BinaryPackage) -> Result<bool, Error> {
+        let guard = self.0.lock().unwrap();
+        let Some(target) = guard.as_ref() else {
+            return Ok(false);
+        };
+        let state = target.template.state(pkg)?;
+        target.handler.replace(Arc::new(state));
+        Ok(true)
+    }
+}
@@ -17,7 +17,10 @@ use crate::{
     capabilities::Capabilities,
     runners::{
         wasi_common::CommonWasiOptions,
-        wcgi::handler::{Handler, SharedState},
+        wcgi::{
+            handler::{Handler, SharedState},
+            PackageReloader,
+        },
         MappedDirectory,
     },
     runtime::task_manager::VirtualTaskManagerExt,
@@ -54,44 +57,20 @@ impl WcgiRunner {
         default_dialect: CgiDialect,
         runtime: Arc<dyn Runtime + Send + Sync>,
     ) -> Result<Handler, Error> {
-        let cmd = pkg
-            .get_command(command_name)
-            .with_context(|| format!("The package doesn't contain a \"{command_name}\" command"))?;
-        let metadata = cmd.metadata();
-        let wasi = metadata
-            .annotation("wasi")?
-            .unwrap_or_else(|| Wasi::new(command_name));
-
-        let module = runtime.load_command_module_sync(cmd)?;
-
-        let Wcgi { dialect, .. } = metadata.annotation("wcgi")?.unwrap_or_default();
-        let dialect = match dialect {
-            Some(d) => d.parse().context("Unable to parse the CGI dialect")?,
-            None => default_dialect,
-        };
-
-        let container_fs = Arc::clone(&pkg.webc_fs);
-
-        let wasi_common = self.config.wasi.clone();
-        let rt = Arc::clone(&runtime);
-        let setup_builder = move |builder: &mut WasiEnvBuilder| {
-            wasi_common.prepare_webc_env(builder, Some(Arc::clone(&container_fs)), &wasi, None)?;
-            builder.set_runtime(Arc::clone(&rt));
-            Ok(())
-        };
-
-        let shared = SharedState {
-            module,
-            module_hash: pkg.hash(),
-            dialect,
+        let template = HandlerTemplate {
+            command_name: command_name.to_string(),
             propagate_stderr,
-            program_name: command_name.to_string(),
-            setup_builder: Arc::new(setup_builder),
+            default_dialect,
+            wasi: self.config.wasi.clone(),
             callbacks: Arc::clone(&self.config.callbacks),
             runtime,
         };
+        let handler = Handler::new(Arc::new(template.state(pkg)?));
+        if let Some(reloader) = &self.config.reloader {
+            reloader.attach(handler.clone(), template);
+        }
 
-        Ok(Handler::new(Arc::new(shared)))
+        Ok(handler)
     }
 
     pub(crate) fn run_command_with_handler<S>(
@@ -159,7 +138,8 @@ impl WcgiRunner {
                         });
                     },
 
-                    _ = futs.next() => {}
+                    // An empty set is always ready
+                    _ = futs.next(), if !futs.is_empty() => {}
 
                     _ = &mut shutdown => {
                         eprintln!("graceful shutdown signal received");
@@ -200,11 +180,66 @@ impl crate::runners::Runner for WcgiRunner {
     }
 }
 
+/// What is needed to serve a command of a package, kept to serve the new
+/// versions of the package with [`PackageReloader`]
+#[derive(Debug)]
+pub(crate) struct HandlerTemplate {
+    command_name: String,
+    propagate_stderr: bool,
+    default_dialect: CgiDialect,
+    wasi: CommonWasiOptions,
+    callbacks: Arc<dyn Callbacks>,
+    runtime: Arc<dyn Runtime + Send + Sync>,
+}
+
+impl HandlerTemplate {
+    pub(crate) fn state(&self, pkg: &BinaryPackage) -> Result<SharedState, Error> {
+        let command_name = self.command_name.as_str();
+        let cmd = pkg
+            .get_command(command_name)
+            .with_context(|| format!("The package doesn't contain a \"{command_name}\" command"))?;
+        let metadata = cmd.metadata();
+        let wasi = metadata
+            .annotation("wasi")?
+            .unwrap_or_else(|| Wasi::new(command_name));
+
+        let module = self.runtime.load_command_module_sync(cmd)?;
+
+        let Wcgi { dialect, .. } = metadata.annotation("wcgi")?.unwrap_or_default();
+        let dialect = match dialect {
+            Some(d) => d.parse().context("Unable to parse the CGI dialect")?,
+            None => self.default_dialect,
+        };
+
+        let container_fs = Arc::clone(&pkg.webc_fs);
+
+        let wasi_common = self.wasi.clone();
+        let rt = Arc::clone(&self.runtime);
+        let setup_builder = move |builder: &mut WasiEnvBuilder| {
+            wasi_common.prepare_webc_env(builder, Some(Arc::clone(&container_fs)), &wasi, None)?;
+            builder.set_runtime(Arc::clone(&rt));
+            Ok(())
+        };
+
+        Ok(SharedState {
+            module,
+            module_hash: pkg.hash(),
+            dialect,
+            propagate_stderr: self.propagate_stderr,
+            program_name: command_name.to_string(),
+            setup_builder: Arc::new(setup_builder),
+            callbacks: Arc::clone(&self.callbacks),
+            runtime: Arc::clone(&self.runtime),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub(crate) wasi: CommonWasiOptions,
     pub(crate) addr: SocketAddr,
     pub(crate) callbacks: Arc<dyn Callbacks>,
+    pub(crate) reloader: Option<PackageReloader>,
 }
 
 impl Config {
@@ -276,6 +311,12 @@ impl Config {
         self
     }
 
+    /// Let this reloader replace the package while the server is running.
+    pub fn reloader(&mut self, reloader: PackageReloader) -> &mut Self {
+        self.reloader = Some(reloader);
+        self
+    }
+
     /// Add a package that should be available to the instance at runtime.
     pub fn inject_package(&mut self, pkg: BinaryPackage) -> &mut Self {
         self.wasi.injected_packages.push(pkg);
@@ -354,6 +395,7 @@ impl Config {
             addr: ([127, 0, 0, 1], 8000).into(),
             wasi: CommonWasiOptions::default(),
             callbacks: Arc::new(callbacks),
+            reloader: None,
         }
     }
 }
@@ -26,7 +26,7 @@ use crate::journal::{DynJournal, DynReadableJournal};
 use crate::{
     bin_factory::BinaryPackageCommand,
     http::{DynHttpClient, HttpClient},
-    os::TtyBridge,
+    os::{TtyBridge, TtyForeground},
     runtime::{
         module_cache::{ModuleCache, ThreadLocalCache},
         package_loader::{PackageLoader, UnsupportedPackageLoader},
@@ -235,6 +235,7 @@ pub async fn load_module(
 #[derive(Debug, Default)]
 pub struct DefaultTty {
     state: Mutex<WasiTtyState>,
+    foreground: TtyForeground,
 }
 
 impl TtyBridge for DefaultTty {
@@ -254,6 +255,10 @@ impl TtyBridge for DefaultTty {
         let mut state = self.state.lock().unwrap();
         *state = tty_state;
     }
+
+    fn foreground(&self) -> Option<TtyForeground> {
+        Some(self.foreground.clone())
+    }
 }
 
 #[derive(Debug, Clone)]
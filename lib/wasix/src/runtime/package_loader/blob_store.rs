@@ -0,0 +1,998 @@
+//! A content-addressed store for packages.
+//!
+//! Different versions of the same package tend to share most of their files,
+//! so instead of keeping a separate copy of every `*.webc`, the [`BlobStore`]
+//! stores each unique file, atom and manifest once, keyed by its SHA-256
+//! hash, and keeps a small record per package describing how those blobs
+//! make up the package.
+//!
+//! The on-disk layout looks like this:
+//!
+//! ```text
+//! $dir/
+//!   objects/ab/cdef0123...   (file contents, named by their SHA-256 hash)
+//!   packages/<webc-hash>.json (one record per package)
+//!   __temp__/                 (partially written files)
+//! ```
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+    io::{ErrorKind, Write as _},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_buffer::OwnedBuffer;
+use tempfile::NamedTempFile;
+use webc::{
+    AbstractVolume, AbstractWebc, Container, Metadata, PathSegment, PathSegments, Timestamps,
+    Version, Volume,
+};
+
+use crate::runtime::resolver::WebcHash;
+
+/// A content-addressed, deduplicating store for packages.
+///
+/// Packages loaded from the store are backed by memory-mapped blobs, so
+/// packages which share files will also share the pages used to hold them.
+///
+/// # Concurrency
+///
+/// Blobs are written atomically, so multiple processes can safely add
+/// packages to the same store. Running [`BlobStore::gc()`] while another
+/// process is adding packages may remove blobs that are about to be
+/// referenced, though.
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    dir: PathBuf,
+}
+
+impl BlobStore {
+    const MANIFEST_SUFFIX: &'static str = ".json";
+    /// How old a partially written file has to be before [`BlobStore::gc()`]
+    /// assumes that whoever was writing it has crashed.
+    const STALE_TEMP_FILE_AGE: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        BlobStore { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.dir.join("objects")
+    }
+
+    fn packages_dir(&self) -> PathBuf {
+        self.dir.join("packages")
+    }
+
+    fn temp_dir(&self) -> PathBuf {
+        self.dir.join("__temp__")
+    }
+
+    fn manifest_path(&self, webc: &WebcHash) -> PathBuf {
+        self.packages_dir()
+            .join(format!("{}{}", webc.as_hex(), Self::MANIFEST_SUFFIX))
+    }
+
+    /// Has this package already been added to the store?
+    pub fn contains(&self, webc: &WebcHash) -> bool {
+        self.manifest_path(webc).exists()
+    }
+
+    /// Add a package's manifest, atoms and the files from its volumes to the
+    /// store.
+    #[tracing::instrument(level = "debug", skip_all, fields(pkg.hash=%webc))]
+    pub fn ingest(&self, webc: &WebcHash, container: &Container) -> Result<IngestReport, Error> {
+        let mut report = IngestReport::default();
+
+        let mut webc_manifest = Vec::new();
+        ciborium::into_writer(container.manifest(), &mut webc_manifest)
+            .context("Unable to serialize the package's manifest")?;
+        let webc_manifest = self.ingest_blob(&webc_manifest, &mut report)?;
+
+        let mut atoms = BTreeMap::new();
+        for (name, atom) in container.atoms() {
+            let blob = self
+                .ingest_blob(&atom, &mut report)
+                .with_context(|| format!("Unable to store the \"{name}\" atom"))?;
+            atoms.insert(name, blob);
+        }
+
+        let mut volumes = BTreeMap::new();
+        for (name, volume) in container.volumes() {
+            let root = self
+                .ingest_dir(&volume, PathSegments::ROOT, None, &mut report)
+                .with_context(|| format!("Unable to store the \"{name}\" volume"))?;
+            volumes.insert(name, root);
+        }
+
+        let manifest = Manifest {
+            version: String::from_utf8_lossy(container.version().as_ref()).into_owned(),
+            webc_hash: container.webc_hash().map(hex::encode),
+            manifest: webc_manifest,
+            atoms,
+            volumes,
+        };
+        let path = self.manifest_path(webc);
+        let contents = serde_json::to_vec(&manifest)?;
+        self.write_atomically(&path, &contents)?;
+
+        tracing::debug!(
+            files = report.files,
+            bytes = report.bytes,
+            new_blobs = report.new_blobs,
+            new_bytes = report.new_bytes,
+            "Added the package to the blob store",
+        );
+
+        Ok(report)
+    }
+
+    fn ingest_dir(
+        &self,
+        volume: &Volume,
+        path: PathSegments,
+        timestamps: Option<Timestamps>,
+        report: &mut IngestReport,
+    ) -> Result<Entry, Error> {
+        let mut entries = BTreeMap::new();
+
+        let children = volume
+            .read_dir(&path)
+            .with_context(|| format!("Unable to read the \"{path}\" directory"))?;
+        for (name, _, meta) in children {
+            let child = path.join(name.clone());
+            let entry = match meta {
+                Metadata::Dir { timestamps } => {
+                    self.ingest_dir(volume, child, timestamps, report)?
+                }
+                Metadata::File { timestamps, .. } => {
+                    let (contents, _) = volume
+                        .read_file(&child)
+                        .with_context(|| format!("Unable to read \"{child}\""))?;
+                    let blob = self.ingest_blob(&contents, report)?;
+                    Entry::File {
+                        sha256: blob.sha256,
+                        length: blob.length,
+                        modified: timestamps.map(|t| t.modified()),
+                    }
+                }
+            };
+            entries.insert(name.as_str().to_string(), entry);
+        }
+
+        Ok(Entry::Dir {
+            modified: timestamps.map(|t| t.modified()),
+            entries,
+        })
+    }
+
+    fn ingest_blob(&self, contents: &[u8], report: &mut IngestReport) -> Result<Blob, Error> {
+        let sha256: [u8; 32] = Sha256::digest(contents).into();
+        let blob = Blob {
+            sha256: hex::encode(sha256),
+            length: contents.len() as u64,
+        };
+
+        report.files += 1;
+        report.bytes += blob.length;
+
+        let path = blob_path(&self.objects_dir(), &blob.sha256)?;
+        if !path.exists() {
+            self.write_atomically(&path, contents)?;
+            report.new_blobs += 1;
+            report.new_bytes += blob.length;
+        }
+
+        Ok(blob)
+    }
+
+    fn write_atomically(&self, path: &Path, contents: &[u8]) -> Result<(), Error> {
+        let temp_dir = self.temp_dir();
+        std::fs::create_dir_all(&temp_dir)
+            .with_context(|| format!("Unable to create directory '{}'", temp_dir.display()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Unable to create directory '{}'", parent.display()))?;
+        }
+
+        let mut temp = NamedTempFile::new_in(&temp_dir)?;
+        temp.write_all(contents)?;
+        temp.flush()?;
+        temp.persist(path)
+            .with_context(|| format!("Unable to save '{}'", path.display()))?;
+
+        Ok(())
+    }
+
+    fn read_manifest(&self, webc: &WebcHash) -> Result<Option<Manifest>, Error> {
+        let path = self.manifest_path(webc);
+
+        match std::fs::read(&path) {
+            Ok(contents) => {
+                let manifest = serde_json::from_slice(&contents)
+                    .with_context(|| format!("Unable to parse '{}'", path.display()))?;
+                Ok(Some(manifest))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::new(e).context(format!("Unable to read '{}'", path.display()))),
+        }
+    }
+
+    /// Load a package from the store, returning `None` if the package hasn't
+    /// been added yet.
+    ///
+    /// This fails if any of the package's blobs are missing, so a package
+    /// never silently loses files.
+    pub fn container(&self, webc: &WebcHash) -> Result<Option<Container>, Error> {
+        let Some(manifest) = self.read_manifest(webc)? else {
+            return Ok(None);
+        };
+        let objects = self.objects_dir();
+
+        let version = <[u8; 3]>::try_from(manifest.version.as_bytes())
+            .map(Version::from)
+            .map_err(|_| Error::msg(format!("Invalid webc version, \"{}\"", manifest.version)))?;
+        let webc_hash = manifest
+            .webc_hash
+            .as_deref()
+            .map(|hash| parse_sha256(hash).context("Invalid webc hash"))
+            .transpose()?;
+
+        let webc_manifest = manifest.manifest.read(&objects)?;
+        let webc_manifest = ciborium::from_reader(&*webc_manifest)
+            .context("Unable to deserialize the package's manifest")?;
+
+        let mut atoms = BTreeMap::new();
+        for (name, blob) in &manifest.atoms {
+            let atom = blob
+                .read(&objects)
+                .with_context(|| format!("Unable to load the \"{name}\" atom"))?;
+            atoms.insert(name.clone(), atom);
+        }
+
+        let mut volumes = BTreeMap::new();
+        for (name, root) in manifest.volumes {
+            let mut error = None;
+            root.visit_files(&mut |sha256, length| {
+                if error.is_some() {
+                    return;
+                }
+                error = match blob_exists(&objects, sha256, length) {
+                    Ok(true) => None,
+                    Ok(false) => Some(Error::msg(format!(
+                        "The \"{name}\" volume refers to a missing blob, {sha256}"
+                    ))),
+                    Err(e) => {
+                        Some(e.context(format!("The \"{name}\" volume refers to an invalid blob")))
+                    }
+                };
+            });
+            if let Some(e) = error {
+                return Err(e);
+            }
+
+            let volume = BlobVolume {
+                objects: objects.clone(),
+                root,
+            };
+            let volume: Arc<dyn AbstractVolume + Send + Sync> = Arc::new(volume);
+            volumes.insert(name, Volume::from(volume));
+        }
+
+        Ok(Some(Container::new(BlobContainer {
+            version,
+            manifest: webc_manifest,
+            webc_hash,
+            atoms,
+            volumes,
+        })))
+    }
+
+    /// Remove a package from the store, leaving its blobs for
+    /// [`BlobStore::gc()`].
+    pub fn remove(&self, webc: &WebcHash) -> Result<(), Error> {
+        remove_file_if_exists(&self.manifest_path(webc))
+    }
+
+    /// Get the hashes of every package in the store.
+    pub fn packages(&self) -> Result<Vec<WebcHash>, Error> {
+        let dir = self.packages_dir();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(Error::new(e).context(format!("Unable to read '{}'", dir.display())))
+            }
+        };
+
+        let mut packages = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let hash = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_suffix(Self::MANIFEST_SUFFIX))
+                .and_then(|hash| WebcHash::parse_hex(hash).ok());
+            if let Some(hash) = hash {
+                packages.push(hash);
+            }
+        }
+
+        packages.sort();
+        Ok(packages)
+    }
+
+    /// Calculate how much space the store is using and how much is being
+    /// saved by deduplication.
+    pub fn stats(&self) -> Result<BlobStoreStats, Error> {
+        let mut stats = BlobStoreStats::default();
+        let mut referenced = HashSet::new();
+
+        for webc in self.packages()? {
+            let Some(manifest) = self.read_manifest(&webc)? else {
+                continue;
+            };
+            stats.packages += 1;
+            manifest.visit_blobs(&mut |sha256, length| {
+                stats.files += 1;
+                stats.logical_bytes += length;
+                referenced.insert(sha256.to_string());
+            });
+        }
+
+        for (_, len) in self.blobs()? {
+            stats.blobs += 1;
+            stats.stored_bytes += len;
+        }
+        stats.referenced_blobs = referenced.len() as u64;
+
+        Ok(stats)
+    }
+
+    /// Find every blob in the store, returning the blob's path and size.
+    fn blobs(&self) -> Result<Vec<(PathBuf, u64)>, Error> {
+        let objects = self.objects_dir();
+        let prefixes = match std::fs::read_dir(&objects) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(Error::new(e).context(format!("Unable to read '{}'", objects.display())))
+            }
+        };
+
+        let mut blobs = Vec::new();
+        for prefix in prefixes {
+            let prefix = prefix?;
+            if !prefix.file_type()?.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(prefix.path())? {
+                let entry = entry?;
+                let meta = entry.metadata()?;
+                if meta.is_file() {
+                    blobs.push((entry.path(), meta.len()));
+                }
+            }
+        }
+
+        Ok(blobs)
+    }
+
+    /// Remove every package which doesn't pass the `retain` filter or can't be
+    /// read, then delete any blobs that are no longer referenced and any
+    /// partially written files that were abandoned.
+    pub fn gc(&self, retain: impl Fn(&WebcHash) -> bool) -> Result<GcReport, Error> {
+        let mut report = GcReport::default();
+        let mut referenced = HashSet::new();
+
+        for webc in self.packages()? {
+            let manifest = if retain(&webc) {
+                self.read_manifest(&webc)
+            } else {
+                Ok(None)
+            };
+
+            match manifest {
+                Ok(Some(manifest)) => {
+                    manifest.visit_blobs(&mut |sha256, _| {
+                        referenced.insert(sha256.to_string());
+                    });
+                }
+                Ok(None) => {
+                    tracing::debug!(pkg.hash=%webc, "Removing package from the blob store");
+                    self.remove(&webc)?;
+                    report.removed_packages += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        error=&*e,
+                        pkg.hash=%webc,
+                        "Removing an unreadable package from the blob store",
+                    );
+                    self.remove(&webc)?;
+                    report.removed_packages += 1;
+                }
+            }
+        }
+
+        for (path, len) in self.blobs()? {
+            let hash = path
+                .parent()
+                .and_then(|p| p.file_name())
+                .zip(path.file_name())
+                .map(|(prefix, rest)| {
+                    format!("{}{}", prefix.to_string_lossy(), rest.to_string_lossy())
+                });
+
+            if hash.is_some_and(|h| referenced.contains(&h)) {
+                continue;
+            }
+
+            tracing::debug!(path=%path.display(), "Removing unreferenced blob");
+            remove_file_if_exists(&path)?;
+            report.removed_blobs += 1;
+            report.freed_bytes += len;
+        }
+
+        for (path, len) in self.stale_temp_files()? {
+            tracing::debug!(path=%path.display(), "Removing an abandoned temporary file");
+            remove_file_if_exists(&path)?;
+            report.removed_temp_files += 1;
+            report.freed_bytes += len;
+        }
+
+        Ok(report)
+    }
+
+    /// Find the files in the temporary directory which haven't been touched
+    /// for [`BlobStore::STALE_TEMP_FILE_AGE`], returning their path and size.
+    fn stale_temp_files(&self) -> Result<Vec<(PathBuf, u64)>, Error> {
+        let temp_dir = self.temp_dir();
+        let entries = match std::fs::read_dir(&temp_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(
+                    Error::new(e).context(format!("Unable to read '{}'", temp_dir.display()))
+                )
+            }
+        };
+
+        let now = SystemTime::now();
+        let mut stale = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let meta = entry.metadata()?;
+            let age = meta
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok());
+            if meta.is_file() && age.is_some_and(|age| age >= Self::STALE_TEMP_FILE_AGE) {
+                stale.push((entry.path(), meta.len()));
+            }
+        }
+
+        Ok(stale)
+    }
+}
+
+/// The path of a blob, which fails unless `sha256` is a SHA-256 hash in
+/// lowercase hex so a tampered record can't point outside the store.
+fn blob_path(objects: &Path, sha256: &str) -> Result<PathBuf, Error> {
+    anyhow::ensure!(
+        sha256.len() == 64
+            && sha256
+                .bytes()
+                .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')),
+        "\"{sha256}\" is not a valid SHA-256 hash",
+    );
+    let (prefix, rest) = sha256.split_at(2);
+    Ok(objects.join(prefix).join(rest))
+}
+
+fn blob_exists(objects: &Path, sha256: &str, length: u64) -> Result<bool, Error> {
+    let path = blob_path(objects, sha256)?;
+    Ok(length == 0 || std::fs::metadata(path).is_ok_and(|meta| meta.len() == length))
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::new(e).context(format!("Unable to delete '{}'", path.display()))),
+    }
+}
+
+/// What happened when a package was added to a [`BlobStore`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IngestReport {
+    /// The number of files in the package, counting its manifest, its atoms
+    /// and the files in its volumes.
+    pub files: u64,
+    /// The total size of those files.
+    pub bytes: u64,
+    /// How many of those files weren't already in the store.
+    pub new_blobs: u64,
+    /// The number of bytes that were added to the store.
+    pub new_bytes: u64,
+}
+
+impl IngestReport {
+    /// The number of files which were already in the store.
+    pub fn deduplicated_files(&self) -> u64 {
+        self.files - self.new_blobs
+    }
+
+    /// The number of bytes that didn't need to be written because they were
+    /// already in the store.
+    pub fn deduplicated_bytes(&self) -> u64 {
+        self.bytes - self.new_bytes
+    }
+}
+
+/// A summary of the contents of a [`BlobStore`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BlobStoreStats {
+    /// The number of packages in the store.
+    pub packages: u64,
+    /// The number of files across all packages.
+    pub files: u64,
+    /// The size of every file in every package, as if it weren't
+    /// deduplicated.
+    pub logical_bytes: u64,
+    /// The number of unique blobs on disk.
+    pub blobs: u64,
+    /// The number of blobs referenced by at least one package.
+    pub referenced_blobs: u64,
+    /// The space used by those blobs.
+    pub stored_bytes: u64,
+}
+
+impl BlobStoreStats {
+    /// The number of bytes saved by deduplication.
+    pub fn saved_bytes(&self) -> u64 {
+        self.logical_bytes.saturating_sub(self.stored_bytes)
+    }
+}
+
+/// What was removed by [`BlobStore::gc()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GcReport {
+    pub removed_packages: u64,
+    pub removed_blobs: u64,
+    pub removed_temp_files: u64,
+    pub freed_bytes: u64,
+}
+
+/// How a package is made up of blobs.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    /// The webc version the package was originally stored as.
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    webc_hash: Option<String>,
+    /// The package's [`webc::metadata::Manifest`], encoded as CBOR.
+    manifest: Blob,
+    atoms: BTreeMap<String, Blob>,
+    volumes: BTreeMap<String, Entry>,
+}
+
+impl Manifest {
+    fn visit_blobs(&self, visit: &mut impl FnMut(&str, u64)) {
+        visit(&self.manifest.sha256, self.manifest.length);
+        for blob in self.atoms.values() {
+            visit(&blob.sha256, blob.length);
+        }
+        for root in self.volumes.values() {
+            root.visit_files(visit);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Blob {
+    sha256: String,
+    length: u64,
+}
+
+impl Blob {
+    fn read(&self, objects: &Path) -> Result<OwnedBuffer, Error> {
+        let path = blob_path(objects, &self.sha256)?;
+        if self.length == 0 {
+            return Ok(OwnedBuffer::new());
+        }
+
+        let buffer = OwnedBuffer::mmap(&path)
+            .with_context(|| format!("Unable to memory-map '{}'", path.display()))?;
+        anyhow::ensure!(
+            buffer.len() as u64 == self.length,
+            "Expected '{}' to be {} bytes long, but it was {}",
+            path.display(),
+            self.length,
+            buffer.len(),
+        );
+
+        Ok(buffer)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Entry {
+    Dir {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        modified: Option<u64>,
+        entries: BTreeMap<String, Entry>,
+    },
+    File {
+        sha256: String,
+        length: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        modified: Option<u64>,
+    },
+}
+
+impl Entry {
+    fn metadata(&self) -> Metadata {
+        match self {
+            Entry::Dir { modified, .. } => Metadata::Dir {
+                timestamps: modified.map(Timestamps::from_modified),
+            },
+            Entry::File {
+                length, modified, ..
+            } => Metadata::File {
+                length: *length as usize,
+                timestamps: modified.map(Timestamps::from_modified),
+            },
+        }
+    }
+
+    fn visit_files(&self, visit: &mut impl FnMut(&str, u64)) {
+        match self {
+            Entry::Dir { entries, .. } => {
+                for entry in entries.values() {
+                    entry.visit_files(visit);
+                }
+            }
+            Entry::File { sha256, length, .. } => visit(sha256, *length),
+        }
+    }
+}
+
+/// A [`Container`] implementation backed by a [`BlobStore`].
+#[derive(Debug)]
+struct BlobContainer {
+    version: Version,
+    manifest: webc::metadata::Manifest,
+    webc_hash: Option<[u8; 32]>,
+    atoms: BTreeMap<String, OwnedBuffer>,
+    volumes: BTreeMap<String, Volume>,
+}
+
+impl AbstractWebc for BlobContainer {
+    fn version(&self) -> Version {
+        self.version
+    }
+
+    fn manifest(&self) -> &webc::metadata::Manifest {
+        &self.manifest
+    }
+
+    fn atom_names(&self) -> Vec<Cow<'_, str>> {
+        self.atoms
+            .keys()
+            .map(|name| Cow::Borrowed(name.as_str()))
+            .collect()
+    }
+
+    fn get_atom(&self, name: &str) -> Option<OwnedBuffer> {
+        self.atoms.get(name).cloned()
+    }
+
+    fn get_webc_hash(&self) -> Option<[u8; 32]> {
+        self.webc_hash
+    }
+
+    fn get_atoms_hash(&self) -> Option<[u8; 32]> {
+        None
+    }
+
+    fn volume_names(&self) -> Vec<Cow<'_, str>> {
+        self.volumes
+            .keys()
+            .map(|name| Cow::Borrowed(name.as_str()))
+            .collect()
+    }
+
+    fn get_volume(&self, name: &str) -> Option<Volume> {
+        self.volumes.get(name).cloned()
+    }
+}
+
+/// A [`Volume`] implementation that reads files from a [`BlobStore`].
+#[derive(Debug)]
+struct BlobVolume {
+    objects: PathBuf,
+    root: Entry,
+}
+
+impl BlobVolume {
+    fn find(&self, path: &PathSegments) -> Option<&Entry> {
+        let mut entry = &self.root;
+
+        for segment in path.iter() {
+            match entry {
+                Entry::Dir { entries, .. } => entry = entries.get(segment.as_str())?,
+                Entry::File { .. } => return None,
+            }
+        }
+
+        Some(entry)
+    }
+}
+
+impl AbstractVolume for BlobVolume {
+    fn metadata(&self, path: &PathSegments) -> Option<Metadata> {
+        self.find(path).map(Entry::metadata)
+    }
+
+    fn read_dir(
+        &self,
+        path: &PathSegments,
+    ) -> Option<Vec<(PathSegment, Option<[u8; 32]>, Metadata)>> {
+        let Entry::Dir { entries, .. } = self.find(path)? else {
+            return None;
+        };
+
+        let mut children = Vec::new();
+        for (name, entry) in entries {
+            let hash = match entry {
+                Entry::File { sha256, .. } => parse_sha256(sha256),
+                Entry::Dir { .. } => None,
+            };
+            children.push((PathSegment::parse(name).ok()?, hash, entry.metadata()));
+        }
+
+        Some(children)
+    }
+
+    fn read_file(&self, path: &PathSegments) -> Option<(OwnedBuffer, Option<[u8; 32]>)> {
+        let Entry::File { sha256, length, .. } = self.find(path)? else {
+            return None;
+        };
+        let hash = parse_sha256(sha256)?;
+        let blob = Blob {
+            sha256: sha256.clone(),
+            length: *length,
+        };
+
+        // Every blob was checked when the package was loaded, so this only
+        // fails if the store was tampered with while the package was in use
+        match blob.read(&self.objects) {
+            Ok(buffer) => Some((buffer, Some(hash))),
+            Err(e) => {
+                tracing::error!(
+                    error = &*e,
+                    path = %path,
+                    "Unable to read a file from the blob store",
+                );
+                None
+            }
+        }
+    }
+}
+
+fn parse_sha256(hex_str: &str) -> Option<[u8; 32]> {
+    let mut hash = [0_u8; 32];
+    hex::decode_to_slice(hex_str, &mut hash).ok()?;
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use virtual_fs::{
+        AsyncReadExt, EmptyFileSystem, FileSystem, OverlayFileSystem, WebcVolumeFileSystem,
+    };
+    use wasmer_package::utils::from_bytes;
+
+    use super::*;
+
+    fn package(files: &[(&str, &str)]) -> Container {
+        let temp = TempDir::new().unwrap();
+        let manifest = r#"
+            [package]
+            name = "test/pkg"
+            version = "0.1.0"
+            description = "A test package"
+
+            [fs]
+            "/data" = "data"
+        "#;
+        std::fs::write(temp.path().join("wasmer.toml"), manifest).unwrap();
+        for (path, contents) in files {
+            let path = temp.path().join("data").join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let pkg = wasmer_package::package::Package::from_manifest(temp.path().join("wasmer.toml"))
+            .unwrap();
+        from_bytes(pkg.serialize().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn identical_files_are_only_stored_once() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let v1 = package(&[("a.txt", "shared"), ("b.txt", "version 1")]);
+        let v2 = package(&[("a.txt", "shared"), ("b.txt", "version 2")]);
+        let v1_hash = WebcHash::sha256("v1");
+        let v2_hash = WebcHash::sha256("v2");
+
+        let first = store.ingest(&v1_hash, &v1).unwrap();
+        let second = store.ingest(&v2_hash, &v2).unwrap();
+
+        assert_eq!(first.deduplicated_files(), 0);
+        assert_eq!(second.files, first.files);
+        // Both packages have the same manifest and "a.txt"
+        assert_eq!(second.deduplicated_files(), 2);
+        assert!(store.contains(&v1_hash));
+        assert_eq!(store.packages().unwrap(), {
+            let mut hashes = vec![v1_hash, v2_hash];
+            hashes.sort();
+            hashes
+        });
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.packages, 2);
+        assert_eq!(stats.blobs, stats.files - 2);
+        assert_eq!(stats.saved_bytes(), second.deduplicated_bytes());
+    }
+
+    fn mount_all(volumes: BTreeMap<String, Volume>) -> impl FileSystem {
+        let volumes: Vec<_> = volumes
+            .into_values()
+            .map(WebcVolumeFileSystem::new)
+            .collect();
+        OverlayFileSystem::new(EmptyFileSystem::default(), volumes)
+    }
+
+    async fn read(fs: &dyn FileSystem, path: &str) -> Vec<u8> {
+        let mut f = fs.new_open_options().read(true).open(path).unwrap();
+        let mut buffer = Vec::new();
+        f.read_to_end(&mut buffer).await.unwrap();
+        buffer
+    }
+
+    #[tokio::test]
+    async fn packages_can_be_loaded_from_the_store() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let pkg = package(&[("nested/file.txt", "Hello, World!"), ("empty.txt", "")]);
+        let hash = WebcHash::sha256("pkg");
+        store.ingest(&hash, &pkg).unwrap();
+
+        let loaded = store.container(&hash).unwrap().unwrap();
+        assert_eq!(loaded.version(), pkg.version());
+        assert_eq!(loaded.manifest(), pkg.manifest());
+        assert_eq!(loaded.webc_hash(), pkg.webc_hash());
+        assert_eq!(loaded.atoms(), pkg.atoms());
+        let volumes = loaded.volumes();
+        let original = pkg.volumes();
+        assert_eq!(
+            volumes.keys().collect::<Vec<_>>(),
+            original.keys().collect::<Vec<_>>()
+        );
+
+        let fs = mount_all(volumes);
+        let original = mount_all(original);
+        for path in ["/nested/file.txt", "/empty.txt"] {
+            assert_eq!(
+                fs.metadata(Path::new(path)).unwrap(),
+                original.metadata(Path::new(path)).unwrap()
+            );
+            assert_eq!(read(&fs, path).await, read(&original, path).await);
+        }
+        assert_eq!(read(&fs, "/nested/file.txt").await, b"Hello, World!");
+        assert!(store
+            .container(&WebcHash::sha256("missing"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn packages_with_missing_blobs_fail_to_load() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let pkg = package(&[("file.txt", "Hello, World!")]);
+        let hash = WebcHash::sha256("pkg");
+        store.ingest(&hash, &pkg).unwrap();
+        let sha256 = hex::encode(Sha256::digest("Hello, World!"));
+        std::fs::remove_file(blob_path(&store.objects_dir(), &sha256).unwrap()).unwrap();
+
+        let err = store.container(&hash).unwrap_err();
+
+        assert!(err.to_string().contains(&sha256), "{err}");
+    }
+
+    #[test]
+    fn packages_with_invalid_hashes_fail_to_load() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let pkg = package(&[("file.txt", "Hello, World!")]);
+        let hash = WebcHash::sha256("pkg");
+        store.ingest(&hash, &pkg).unwrap();
+        let sha256 = hex::encode(Sha256::digest("Hello, World!"));
+        let path = store.manifest_path(&hash);
+        let manifest = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, manifest.replace(&sha256, "../../../../etc/passwd")).unwrap();
+
+        let err = store.container(&hash).unwrap_err();
+
+        assert!(
+            format!("{err:#}").contains("is not a valid SHA-256 hash"),
+            "{err:#}"
+        );
+        assert!(blob_path(&store.objects_dir(), &sha256.to_uppercase()).is_err());
+        assert!(blob_path(&store.objects_dir(), &sha256[1..]).is_err());
+    }
+
+    #[test]
+    fn gc_removes_stale_temp_files() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        std::fs::create_dir_all(store.temp_dir()).unwrap();
+        let stale = store.temp_dir().join("stale");
+        let fresh = store.temp_dir().join("fresh");
+        std::fs::write(&stale, "abandoned").unwrap();
+        std::fs::write(&fresh, "in progress").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - 2 * BlobStore::STALE_TEMP_FILE_AGE)
+            .unwrap();
+
+        let report = store.gc(|_| true).unwrap();
+
+        assert_eq!(report.removed_temp_files, 1);
+        assert_eq!(report.freed_bytes, "abandoned".len() as u64);
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+    }
+
+    #[test]
+    fn gc_removes_unreferenced_blobs() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path());
+        let v1 = package(&[("a.txt", "shared"), ("b.txt", "version 1")]);
+        let v2 = package(&[("a.txt", "shared"), ("b.txt", "version 2")]);
+        let v1_hash = WebcHash::sha256("v1");
+        let v2_hash = WebcHash::sha256("v2");
+        store.ingest(&v1_hash, &v1).unwrap();
+        store.ingest(&v2_hash, &v2).unwrap();
+        let before = store.stats().unwrap();
+
+        let report = store.gc(|hash| *hash == v2_hash).unwrap();
+
+        assert_eq!(report.removed_packages, 1);
+        assert_eq!(report.removed_blobs, 1);
+        assert_eq!(report.freed_bytes, "version 1".len() as u64);
+        let after = store.stats().unwrap();
+        assert_eq!(after.packages, 1);
+        assert_eq!(after.blobs, before.blobs - 1);
+        assert_eq!(after.blobs, after.referenced_blobs);
+        assert!(store.container(&v2_hash).unwrap().is_some());
+    }
+}
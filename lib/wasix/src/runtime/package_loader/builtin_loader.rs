@@ -22,7 +22,7 @@ use crate::{
     bin_factory::BinaryPackage,
     http::{HttpClient, HttpRequest, USER_AGENT},
     runtime::{
-        package_loader::PackageLoader,
+        package_loader::{BlobStore, PackageLoader},
         resolver::{DistributionInfo, PackageSummary, Resolution, WebcHash},
     },
 };
@@ -34,6 +34,7 @@ pub struct BuiltinPackageLoader {
     client: Arc<dyn HttpClient + Send + Sync>,
     in_memory: InMemoryCache,
     cache: Option<FileSystemCache>,
+    blobs: Option<BlobStore>,
     /// A mapping from hostnames to tokens
     tokens: HashMap<String, String>,
 
@@ -58,6 +59,7 @@ impl BuiltinPackageLoader {
             in_memory: InMemoryCache::default(),
             client: Arc::new(crate::http::default_http_client().unwrap()),
             cache: None,
+            blobs: None,
             hash_validation: HashIntegrityValidationMode::NoValidate,
             tokens: HashMap::new(),
         }
@@ -84,6 +86,20 @@ impl BuiltinPackageLoader {
         self.cache.as_ref()
     }
 
+    /// Move every package that gets loaded into a content-addressed
+    /// [`BlobStore`] and serve it from there, deleting its copy in the
+    /// filesystem cache.
+    pub fn with_blob_store(self, blobs: BlobStore) -> Self {
+        BuiltinPackageLoader {
+            blobs: Some(blobs),
+            ..self
+        }
+    }
+
+    pub fn blob_store(&self) -> Option<&BlobStore> {
+        self.blobs.as_ref()
+    }
+
     pub fn validate_cache(
         &self,
         mode: CacheValidationMode,
@@ -165,8 +181,17 @@ impl BuiltinPackageLoader {
             return Ok(Some(cached));
         }
 
+        if let Some(cached) = self.load_from_blob_store(*hash).await {
+            tracing::debug!("Copying from the blob store to the in-memory cache");
+            self.in_memory.save(&cached, *hash);
+            return Ok(Some(cached));
+        }
+
         if let Some(cache) = self.cache.as_ref() {
             if let Some(cached) = cache.lookup(hash).await? {
+                let cached = self
+                    .move_to_blob_store(*hash, cached, Some(cache.path(hash)))
+                    .await?;
                 // Note: We want to propagate it to the in-memory cache, too
                 tracing::debug!("Copying from the filesystem cache to the in-memory cache");
                 self.in_memory.save(&cached, *hash);
@@ -224,6 +249,111 @@ impl BuiltinPackageLoader {
         }
     }
 
+    /// Load a package from the [`BlobStore`], if it has been added to it.
+    ///
+    /// Packages which can't be loaded are removed from the store so they
+    /// will be fetched and added again.
+    async fn load_from_blob_store(&self, hash: WebcHash) -> Option<Container> {
+        let blobs = self.blobs.clone()?;
+
+        let result = crate::spawn_blocking(move || {
+            blobs.container(&hash).inspect_err(|_| {
+                if let Err(e) = blobs.remove(&hash) {
+                    tracing::warn!(
+                        error=&*e,
+                        pkg.hash=%hash,
+                        "Unable to remove the package from the blob store",
+                    );
+                }
+            })
+        })
+        .await;
+
+        match result {
+            Ok(Ok(container)) => container,
+            Ok(Err(e)) => {
+                tracing::warn!(
+                    error=&*e,
+                    pkg.hash=%hash,
+                    "Unable to load the package from the blob store",
+                );
+                None
+            }
+            Err(e) => {
+                tracing::warn!(
+                    error=&e as &dyn std::error::Error,
+                    pkg.hash=%hash,
+                    "Unable to load the package from the blob store",
+                );
+                None
+            }
+        }
+    }
+
+    /// Add a package to the [`BlobStore`] and load it back from there,
+    /// deleting the `checkout` it came from in the filesystem cache.
+    ///
+    /// If there is no blob store or the package can't be added to it, the
+    /// original container is returned and the checkout is kept.
+    async fn move_to_blob_store(
+        &self,
+        hash: WebcHash,
+        container: Container,
+        checkout: Option<PathBuf>,
+    ) -> Result<Container, Error> {
+        let Some(blobs) = self.blobs.clone() else {
+            return Ok(container);
+        };
+
+        crate::spawn_blocking(move || {
+            let stored = blobs.ingest(&hash, &container).and_then(|report| {
+                tracing::debug!(
+                    pkg.hash=%hash,
+                    files=report.files,
+                    deduplicated_files=report.deduplicated_files(),
+                    deduplicated_bytes=report.deduplicated_bytes(),
+                    "Added the package to the blob store",
+                );
+                blobs
+                    .container(&hash)?
+                    .context("The package disappeared from the blob store")
+            });
+
+            let stored = match stored {
+                Ok(stored) => stored,
+                Err(e) => {
+                    tracing::warn!(
+                        error=&*e,
+                        pkg.hash=%hash,
+                        "Unable to add the package to the blob store",
+                    );
+                    return container;
+                }
+            };
+
+            // The checkout may be memory-mapped, so it needs to be closed
+            // before it can be deleted on some platforms
+            drop(container);
+            if let Some(checkout) = checkout {
+                match std::fs::remove_file(&checkout) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == ErrorKind::NotFound => {}
+                    Err(e) => {
+                        tracing::warn!(
+                            error=&e as &dyn std::error::Error,
+                            path=%checkout.display(),
+                            "Unable to delete a package that was moved to the blob store",
+                        );
+                    }
+                }
+            }
+
+            stored
+        })
+        .await
+        .context("tokio runtime failed")
+    }
+
     #[tracing::instrument(level = "debug", skip_all, fields(%dist.webc, %dist.webc_sha256))]
     async fn download(&self, dist: &DistributionInfo) -> Result<Bytes, Error> {
         if dist.webc.scheme() == "file" {
@@ -356,6 +486,13 @@ impl PackageLoader for BuiltinPackageLoader {
             {
                 Ok(container) => {
                     tracing::debug!("Cached to disk");
+                    let container = self
+                        .move_to_blob_store(
+                            summary.dist.webc_sha256,
+                            container,
+                            Some(cache.path(&summary.dist.webc_sha256)),
+                        )
+                        .await?;
                     self.in_memory.save(&container, summary.dist.webc_sha256);
                     // The happy path - we've saved to both caches and loaded the
                     // container from disk (hopefully using mmap) so we're done.
@@ -376,6 +513,9 @@ impl PackageLoader for BuiltinPackageLoader {
         // The sad path - looks like we don't have a filesystem cache so we'll
         // need to keep the whole thing in memory.
         let container = crate::spawn_blocking(move || from_bytes(bytes)).await??;
+        let container = self
+            .move_to_blob_store(summary.dist.webc_sha256, container, None)
+            .await?;
         // We still want to cache it in memory, of course
         self.in_memory.save(&container, summary.dist.webc_sha256);
         Ok(container)
@@ -794,6 +934,75 @@ mod tests {
     async fn cache_misses_will_trigger_a_download() {
         cache_misses_will_trigger_a_download_internal().await
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn downloads_are_moved_into_the_blob_store() {
+        let temp = TempDir::new().unwrap();
+        let manifest = r#"
+            [package]
+            name = "test/pkg"
+            version = "0.1.0"
+            description = "A test package"
+
+            [fs]
+            "/data" = "data"
+        "#;
+        std::fs::write(temp.path().join("wasmer.toml"), manifest).unwrap();
+        std::fs::create_dir(temp.path().join("data")).unwrap();
+        std::fs::write(temp.path().join("data").join("file.txt"), "Hello, World!").unwrap();
+        let webc = wasmer_package::package::Package::from_manifest(temp.path().join("wasmer.toml"))
+            .unwrap()
+            .serialize()
+            .unwrap();
+        let client = Arc::new(DummyClient::with_responses([HttpResponse {
+            body: Some(webc.to_vec()),
+            redirected: false,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+        }]));
+        let cache_dir = temp.path().join("checkouts");
+        let blobs = BlobStore::new(temp.path().join("blobs"));
+        let loader = BuiltinPackageLoader::new()
+            .with_cache_dir(&cache_dir)
+            .with_blob_store(blobs.clone())
+            .with_shared_http_client(client.clone());
+        let summary = PackageSummary {
+            pkg: PackageInfo {
+                id: PackageId::new_named("test/pkg", "0.1.0".parse().unwrap()),
+                dependencies: Vec::new(),
+                commands: Vec::new(),
+                entrypoint: None,
+                filesystem: Vec::new(),
+            },
+            dist: DistributionInfo {
+                webc: "https://wasmer.io/test/pkg".parse().unwrap(),
+                webc_sha256: WebcHash::sha256(&webc),
+            },
+        };
+
+        let container = loader.load(&summary).await.unwrap();
+
+        // The package is served from the blob store instead of a checkout
+        assert!(blobs.contains(&summary.dist.webc_sha256));
+        assert!(!loader
+            .cache
+            .as_ref()
+            .unwrap()
+            .path(&summary.dist.webc_sha256)
+            .exists());
+        let volume = container.get_volume("/data").unwrap();
+        let (contents, _) = volume.read_file("file.txt").unwrap();
+        assert_eq!(contents.as_slice(), b"Hello, World!");
+        // and a fresh loader picks it up without downloading it again
+        let loader = BuiltinPackageLoader::new()
+            .with_cache_dir(&cache_dir)
+            .with_blob_store(blobs)
+            .with_shared_http_client(client.clone());
+        let reloaded = loader.load(&summary).await.unwrap();
+        assert_eq!(reloaded.manifest(), container.manifest());
+        assert_eq!(client.requests.lock().unwrap().len(), 1);
+    }
 }
 
 #[cfg(test)]
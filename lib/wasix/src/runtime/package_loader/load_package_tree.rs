@@ -1,5 +1,6 @@
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
+    ffi::{OsStr, OsString},
     fmt::Debug,
     path::{Path, PathBuf},
     sync::Arc,
@@ -8,8 +9,8 @@ use std::{
 use anyhow::{Context, Error};
 use futures::{future::BoxFuture, StreamExt, TryStreamExt};
 use once_cell::sync::OnceCell;
-use petgraph::visit::EdgeRef;
-use virtual_fs::{FileSystem, OverlayFileSystem, UnionFileSystem, WebcVolumeFileSystem};
+use petgraph::visit::{Bfs, EdgeRef};
+use virtual_fs::{FileSystem, OverlayFileSystem, UnionFileSystem, WebcVolumeFileSystem, XattrMode};
 use wasmer_config::package::{PackageId, SuggestedCompilerOptimizations};
 use wasmer_package::utils::wasm_annotations_to_features;
 use webc::metadata::annotations::Atom as AtomAnnotation;
@@ -20,8 +21,8 @@ use crate::{
     runtime::{
         package_loader::PackageLoader,
         resolver::{
-            DependencyGraph, ItemLocation, PackageSummary, Resolution, ResolvedFileSystemMapping,
-            ResolvedPackage,
+            DependencyGraph, ItemLocation, PackageInfo, PackageSummary, Resolution,
+            ResolvedFileSystemMapping, ResolvedPackage,
         },
     },
 };
@@ -84,6 +85,7 @@ pub async fn load_package_tree(
         webc_fs: Arc::new(fs),
         commands,
         uses: Vec::new(),
+        dependencies: dependencies(&resolution.graph),
         file_system_memory_footprint,
 
         additional_host_mapped_directories: vec![],
@@ -374,6 +376,26 @@ async fn fetch_dependencies(
     Ok(packages)
 }
 
+/// The packages that the root package depends on, directly or not, in the
+/// order that they are reached from the root
+fn dependencies(graph: &DependencyGraph) -> Vec<PackageSummary> {
+    let mut dependencies = Vec::new();
+    let mut bfs = Bfs::new(graph.graph(), graph.root());
+    while let Some(index) = bfs.next(graph.graph()) {
+        if index == graph.root() {
+            continue;
+        }
+        let crate::runtime::resolver::Node { pkg, dist, .. } = &graph[index];
+        if let Some(dist) = dist {
+            dependencies.push(PackageSummary {
+                pkg: pkg.clone(),
+                dist: dist.clone(),
+            });
+        }
+    }
+    dependencies
+}
+
 /// How many bytes worth of files does a directory contain?
 fn count_file_system(fs: &dyn FileSystem, path: &Path) -> u64 {
     let mut total = 0;
@@ -397,6 +419,35 @@ fn count_file_system(fs: &dyn FileSystem, path: &Path) -> u64 {
     total
 }
 
+/// Construct the filesystem of a single package on its own, with its volumes
+/// mounted where its manifest maps them.
+///
+/// Mappings of volumes that come from the package's dependencies are left
+/// out, as those packages are loaded on their own.
+pub(crate) fn package_filesystem(
+    container: &Container,
+    pkg: &PackageInfo,
+) -> Result<Box<dyn FileSystem + Send + Sync>, Error> {
+    let resolved = ResolvedPackage {
+        root_package: pkg.id.clone(),
+        commands: BTreeMap::new(),
+        entrypoint: None,
+        filesystem: pkg
+            .filesystem
+            .iter()
+            .filter(|mapping| mapping.dependency_name.is_none())
+            .map(|mapping| ResolvedFileSystemMapping {
+                mount_path: PathBuf::from(&mapping.mount_path),
+                volume_name: mapping.volume_name.clone(),
+                original_path: mapping.original_path.clone(),
+                package: pkg.id.clone(),
+            })
+            .collect(),
+    };
+    let packages = HashMap::from([(pkg.id.clone(), container.clone())]);
+    filesystem(&packages, &resolved, false)
+}
+
 /// Given a set of [`ResolvedFileSystemMapping`]s and the [`Container`] for each
 /// package in a dependency tree, construct the resulting filesystem.
 fn filesystem(
@@ -686,6 +737,40 @@ where
         let path = self.path(path)?;
         self.inner.mount(name, path.as_path(), fs)
     }
+
+    fn get_xattr(&self, path: &Path, name: &OsStr) -> virtual_fs::Result<Vec<u8>> {
+        let path = self.path(path)?;
+        self.inner.get_xattr(&path, name)
+    }
+
+    fn set_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+        value: &'a [u8],
+        mode: XattrMode,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(async move {
+            let path = self.path(path)?;
+            self.inner.set_xattr(&path, name, value, mode).await
+        })
+    }
+
+    fn list_xattr(&self, path: &Path) -> virtual_fs::Result<Vec<OsString>> {
+        let path = self.path(path)?;
+        self.inner.list_xattr(&path)
+    }
+
+    fn remove_xattr<'a>(
+        &'a self,
+        path: &'a Path,
+        name: &'a OsStr,
+    ) -> BoxFuture<'a, virtual_fs::Result<()>> {
+        Box::pin(async move {
+            let path = self.path(path)?;
+            self.inner.remove_xattr(&path, name).await
+        })
+    }
 }
 
 impl<F, M> virtual_fs::FileOpener for MappedPathFileSystem<F, M>
@@ -1,9 +1,12 @@
+pub mod blob_store;
 pub mod builtin_loader;
 mod load_package_tree;
 mod types;
 mod unsupported;
 
+pub(crate) use self::load_package_tree::package_filesystem;
 pub use self::{
-    builtin_loader::BuiltinPackageLoader, load_package_tree::load_package_tree,
-    types::to_module_hash, types::PackageLoader, unsupported::UnsupportedPackageLoader,
+    blob_store::BlobStore, builtin_loader::BuiltinPackageLoader,
+    load_package_tree::load_package_tree, types::to_module_hash, types::PackageLoader,
+    unsupported::UnsupportedPackageLoader,
 };
@@ -8,7 +8,7 @@ use std::{
 
 use rand::Rng;
 use thiserror::Error;
-use virtual_fs::{ArcFile, FileSystem, FsError, TmpFileSystem, VirtualFile};
+use virtual_fs::{ArcFile, AsyncWriteExt, FileSystem, FsError, TmpFileSystem, VirtualFile};
 use wasmer::{AsStoreMut, Extern, Imports, Instance, Module, Store};
 use wasmer_config::package::PackageId;
 
@@ -17,8 +17,16 @@ use crate::journal::{DynJournal, DynReadableJournal, SnapshotTrigger};
 use crate::{
     bin_factory::{BinFactory, BinaryPackage},
     capabilities::Capabilities,
-    fs::{WasiFs, WasiFsRoot, WasiInodes},
-    os::task::control_plane::{ControlPlaneConfig, ControlPlaneError, WasiControlPlane},
+    fs::{ProcFileSystem, PtsFileSystem, WasiFs, WasiFsRoot, WasiInodes, PROC_ROOT},
+    os::{
+        credentials::{Credentials, DefaultOwner, FsPermissions, Identity},
+        ipc::ShmLimits,
+        task::{
+            control_plane::{ControlPlaneConfig, ControlPlaneError, WasiControlPlane},
+            resource_group::ResourceLimits,
+        },
+    },
+    runtime::task_manager::InlineWaker,
     state::WasiState,
     syscalls::{
         rewind_ext2,
@@ -102,6 +110,22 @@ pub struct WasiEnvBuilder {
 
     pub(super) skip_stdio_during_bootstrap: bool,
 
+    /// Limits that apply to the process and everything that it spawns
+    pub(super) resource_limits: ResourceLimits,
+
+    /// Limits on the shared memory objects of the process and everything
+    /// that it spawns
+    pub(super) shm_limits: ShmLimits,
+
+    /// User and groups that the program runs as
+    pub(super) identity: Identity,
+
+    /// Whether the permission bits of files are checked by path syscalls
+    pub(super) enforce_permissions: bool,
+
+    /// Owner and mode of the files that nobody has taken ownership of
+    pub(super) default_owner: DefaultOwner,
+
     #[cfg(feature = "ctrlc")]
     pub(super) attach_ctrl_c: bool,
 }
@@ -767,6 +791,76 @@ impl WasiEnvBuilder {
         self.skip_stdio_during_bootstrap = skip;
     }
 
+    /// Sets the limits on the memory, file descriptors and CPU time that
+    /// the process and all the processes it spawns may use together
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.set_resource_limits(limits);
+        self
+    }
+
+    /// Sets the limits on the memory, file descriptors and CPU time that
+    /// the process and all the processes it spawns may use together
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        self.resource_limits = limits;
+    }
+
+    /// Sets how many shared memory objects the process and all the
+    /// processes it spawns may create and how large they may be in total
+    pub fn shm_limits(mut self, limits: ShmLimits) -> Self {
+        self.set_shm_limits(limits);
+        self
+    }
+
+    /// Sets how many shared memory objects the process and all the
+    /// processes it spawns may create and how large they may be in total
+    pub fn set_shm_limits(&mut self, limits: ShmLimits) {
+        self.shm_limits = limits;
+    }
+
+    /// Sets the user and groups that the program runs as (`root` by
+    /// default), which are also described by the generated `/etc/passwd`
+    /// and `/etc/group` files
+    pub fn identity(mut self, identity: Identity) -> Self {
+        self.set_identity(identity);
+        self
+    }
+
+    /// Sets the user and groups that the program runs as (`root` by
+    /// default), which are also described by the generated `/etc/passwd`
+    /// and `/etc/group` files
+    pub fn set_identity(&mut self, identity: Identity) {
+        self.identity = identity;
+    }
+
+    /// Enables checking the owner and mode of files in the path syscalls,
+    /// including the directories that are searched on the way to a file
+    ///
+    /// Ownership is recorded on the files that the program creates, files
+    /// without an owner of their own belong to the [`DefaultOwner`].
+    pub fn enforce_permissions(mut self, enforce: bool) -> Self {
+        self.set_enforce_permissions(enforce);
+        self
+    }
+
+    /// Enables checking the owner and mode of files in the path syscalls,
+    /// including the directories that are searched on the way to a file
+    pub fn set_enforce_permissions(&mut self, enforce: bool) {
+        self.enforce_permissions = enforce;
+    }
+
+    /// Sets the owner and mode of the files without an owner of their own
+    /// (`root` with `0644` for files and `0755` for directories by default)
+    pub fn default_owner(mut self, owner: DefaultOwner) -> Self {
+        self.set_default_owner(owner);
+        self
+    }
+
+    /// Sets the owner and mode of the files without an owner of their own
+    /// (`root` with `0644` for files and `0755` for directories by default)
+    pub fn set_default_owner(&mut self, owner: DefaultOwner) {
+        self.default_owner = owner;
+    }
+
     /// Add an item to the list of importable items provided to the instance.
     pub fn import(
         mut self,
@@ -911,7 +1005,7 @@ impl WasiEnvBuilder {
 
         // self.preopens are checked in [`PreopenDirBuilder::build`]
         let inodes = crate::state::WasiInodes::new();
-        let wasi_fs = {
+        let mut wasi_fs = {
             // self.preopens are checked in [`PreopenDirBuilder::build`]
             let mut wasi_fs =
                 WasiFs::new_with_preopen(&inodes, &self.preopens, &self.vfs_preopens, fs_backing)
@@ -954,6 +1048,14 @@ impl WasiEnvBuilder {
             wasi_fs.has_unioned.lock().unwrap().insert(id.clone());
         }
 
+        let credentials = Arc::new(std::sync::Mutex::new(Credentials::new(&self.identity)));
+        if self.enforce_permissions {
+            wasi_fs.set_permissions(FsPermissions {
+                credentials: credentials.clone(),
+                default_owner: self.default_owner,
+            });
+        }
+
         let state = WasiState {
             fs: wasi_fs,
             secret: rand::thread_rng().gen::<[u8; 32]>(),
@@ -961,9 +1063,12 @@ impl WasiEnvBuilder {
             args: std::sync::Mutex::new(self.args.clone()),
             preopen: self.vfs_preopens.clone(),
             futexs: Default::default(),
+            mmaps: Default::default(),
+            ipc: Default::default(),
             clock_offset: Default::default(),
             envs: std::sync::Mutex::new(conv_env_vars(self.envs)),
             signals: std::sync::Mutex::new(self.signals.iter().map(|s| (s.sig, s.disp)).collect()),
+            credentials,
         };
 
         let runtime = self.runtime.unwrap_or_else(|| {
@@ -999,9 +1104,70 @@ impl WasiEnvBuilder {
             max_task_count: capabilities.threading.max_threads,
             enable_asynchronous_threading: capabilities.threading.enable_asynchronous_threading,
             enable_exponential_cpu_backoff: capabilities.threading.enable_exponential_cpu_backoff,
+            resource_limits: self.resource_limits,
+            shm_limits: self.shm_limits,
         };
         let control_plane = WasiControlPlane::new(plane_config);
 
+        // Processes that are forked or spawned from this one share its root
+        // file system and control plane, so they all see the same `/proc`
+        let proc_fs = ProcFileSystem::new(control_plane.handle(), runtime.task_manager().clone());
+        if let Err(err) =
+            state
+                .fs
+                .root_fs
+                .mount("proc".to_string(), Path::new(PROC_ROOT), Box::new(proc_fs))
+        {
+            tracing::debug!(%err, "unable to mount /proc");
+        }
+
+        // `/dev` and the devices below it are only set up in a sandboxed
+        // file system, a host directory keeps its own `/dev`
+        if matches!(state.fs.root_fs, WasiFsRoot::Sandbox(_)) {
+            let _ = state.fs.root_fs.create_dir(Path::new("/dev"));
+
+            // Shared memory objects are visible to all the processes of the
+            // control plane under `/dev/shm`
+            #[cfg(all(unix, feature = "sys"))]
+            {
+                let shm_fs = control_plane.ipc().shm_fs().clone();
+                if let Err(err) = state.fs.root_fs.mount(
+                    "shm".to_string(),
+                    Path::new("/dev/shm"),
+                    Box::new(shm_fs),
+                ) {
+                    tracing::debug!(%err, "unable to mount /dev/shm");
+                }
+            }
+
+            // The PTYs of the control plane are allocated and opened through
+            // `/dev/pts`
+            let pts_fs = PtsFileSystem::new(control_plane.ptys().clone());
+            if let Err(err) =
+                state
+                    .fs
+                    .root_fs
+                    .mount("pts".to_string(), Path::new("/dev/pts"), Box::new(pts_fs))
+            {
+                tracing::debug!(%err, "unable to mount /dev/pts");
+            }
+        }
+
+        // The user database is only generated in a sandboxed file system,
+        // a host directory keeps its own files
+        if let WasiFsRoot::Sandbox(root_fs) = &state.fs.root_fs {
+            let _ = root_fs.create_dir(Path::new("/etc"));
+            let files = [
+                ("/etc/passwd", self.identity.passwd()),
+                ("/etc/group", self.identity.group()),
+            ];
+            for (path, contents) in files {
+                if let Err(err) = write_system_file(root_fs, Path::new(path), contents.as_bytes()) {
+                    tracing::debug!(%err, path, "unable to generate the user database");
+                }
+            }
+        }
+
         let init = WasiEnvInit {
             state,
             runtime,
@@ -1312,6 +1478,17 @@ impl PreopenDirBuilder {
     }
 }
 
+/// Writes a file unless it already exists, leaving it to the default owner
+fn write_system_file(fs: &TmpFileSystem, path: &Path, contents: &[u8]) -> Result<(), FsError> {
+    let mut file = fs
+        .new_open_options()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    InlineWaker::block_on(file.write_all(contents))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1385,4 +1562,77 @@ mod test {
             WasiStateCreationError::ArgumentContainsNulByte(_)
         ));
     }
+
+    #[test]
+    fn user_database_is_generated() {
+        #[cfg(not(target_arch = "wasm32"))]
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        #[cfg(not(target_arch = "wasm32"))]
+        let _guard = runtime.enter();
+
+        let init = WasiEnvBuilder::new("test_prog")
+            .identity(Identity::new("alice", 1000, 100))
+            .enforce_permissions(true)
+            .preopen_dir("/")
+            .unwrap()
+            .build_init()
+            .unwrap();
+        let state = &init.state;
+
+        let mut passwd = String::new();
+        let mut file = state
+            .fs
+            .root_fs
+            .new_open_options()
+            .read(true)
+            .open("/etc/passwd")
+            .unwrap();
+        InlineWaker::block_on(virtual_fs::AsyncReadExt::read_to_string(
+            &mut file,
+            &mut passwd,
+        ))
+        .unwrap();
+        assert!(passwd.contains("alice:x:1000:100:alice:/home/alice:/bin/sh"));
+
+        // The file belongs to the default owner, so the process may read
+        // it but not replace it
+        assert_eq!(state.credentials.lock().unwrap().euid, 1000);
+        let lookup = |path| {
+            state
+                .fs
+                .get_inode_at_path(&state.inodes, crate::fs::VIRTUAL_ROOT_FD, path, true)
+        };
+        let passwd = lookup("/etc/passwd").unwrap();
+        assert_eq!(
+            state
+                .fs
+                .check_access(&passwd, crate::os::credentials::ACCESS_READ),
+            Ok(())
+        );
+        assert_eq!(
+            state
+                .fs
+                .check_access(&passwd, crate::os::credentials::ACCESS_WRITE),
+            Err(crate::syscalls::types::wasi::Errno::Access)
+        );
+        assert_eq!(
+            state.fs_set_owner_xattr(&passwd, b"1000:100:600", virtual_fs::XattrMode::Upsert),
+            Err(crate::syscalls::types::wasi::Errno::Perm)
+        );
+
+        // Files are found only through directories that may be searched
+        let etc = lookup("/etc").unwrap();
+        *etc.owner.write().unwrap() = Some(crate::os::credentials::FileOwner {
+            uid: 0,
+            gid: 0,
+            mode: 0o700,
+        });
+        assert_eq!(
+            lookup("/etc/group").map(|_| ()),
+            Err(crate::syscalls::types::wasi::Errno::Access)
+        );
+    }
 }
@@ -0,0 +1,537 @@
+//! Bookkeeping for the position independent side modules that are loaded
+//! into a running instance with `dlopen`.
+//!
+//! Side modules follow the `dylink.0` convention that LLVM and emscripten
+//! use: they import the memory and `__indirect_function_table` of the main
+//! module and are relocated through the `__memory_base` and `__table_base`
+//! globals that they import.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use virtual_fs::FileSystem;
+use wasmer::{Extern, Instance, Module};
+use wasmer_config::package::PackageId;
+use wasmer_wasix_types::wasi::Dlhandle;
+
+use crate::{Runtime, SpawnError};
+
+/// Name of the custom section that marks a module as a side module
+pub(crate) const DYLINK_SECTION: &str = "dylink.0";
+
+const WASM_DYLINK_MEM_INFO: u8 = 1;
+const WASM_DYLINK_NEEDED: u8 = 2;
+const WASM_DYLINK_IMPORT_INFO: u8 = 4;
+
+const WASM_SYMBOL_BINDING_WEAK: u32 = 1;
+
+const WASM_MAGIC: &[u8] = b"\0asm\x01\0\0\0";
+const WASM_DATA_SECTION: u8 = 11;
+const WASM_DATA_PASSIVE: u32 = 1;
+
+/// The contents of the `dylink.0` section of a side module
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DylinkInfo {
+    /// Number of bytes of memory that the module needs for its data
+    pub memory_size: u32,
+    /// Alignment of the memory, as a power of two
+    pub memory_align: u32,
+    /// Number of table slots that the module needs for its functions
+    pub table_size: u32,
+    /// Alignment of the table slots, as a power of two
+    pub table_align: u32,
+    /// Libraries that must be loaded before this one
+    pub needed: Vec<String>,
+    /// Imports that may be left unresolved
+    pub weak_imports: HashSet<(String, String)>,
+}
+
+impl DylinkInfo {
+    /// Reads the `dylink.0` section of a module, returning `None` if the
+    /// module is not a side module
+    pub fn from_module(module: &Module) -> Option<Self> {
+        let section = module.custom_sections(DYLINK_SECTION).next()?;
+        Self::parse(&section)
+    }
+
+    /// Parses the contents of a `dylink.0` section, returning `None` if the
+    /// section is malformed
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut info = DylinkInfo::default();
+        let mut reader = Reader { data };
+        while !reader.data.is_empty() {
+            let kind = reader.u8()?;
+            let len = reader.leb()? as usize;
+            let mut sub = Reader {
+                data: reader.take(len)?,
+            };
+            match kind {
+                WASM_DYLINK_MEM_INFO => {
+                    info.memory_size = sub.leb()?;
+                    info.memory_align = sub.leb()?;
+                    info.table_size = sub.leb()?;
+                    info.table_align = sub.leb()?;
+                }
+                WASM_DYLINK_NEEDED => {
+                    for _ in 0..sub.leb()? {
+                        info.needed.push(sub.string()?);
+                    }
+                }
+                WASM_DYLINK_IMPORT_INFO => {
+                    for _ in 0..sub.leb()? {
+                        let module = sub.string()?;
+                        let field = sub.string()?;
+                        if sub.leb()? & WASM_SYMBOL_BINDING_WEAK != 0 {
+                            info.weak_imports.insert((module, field));
+                        }
+                    }
+                }
+                // Export info only marks thread locals, which are not
+                // supported, and unknown subsections can be skipped
+                _ => {}
+            }
+        }
+        Some(info)
+    }
+}
+
+/// Turns the active data segments of a module into passive ones, so that
+/// instantiating it leaves the memory alone, returning `None` if the
+/// module is malformed
+///
+/// The indices of the segments stay the same, so any `memory.init` and
+/// `data.drop` of the module still refer to the right ones.
+pub(crate) fn without_active_data(wasm: &[u8]) -> Option<Vec<u8>> {
+    let mut reader = Reader { data: wasm };
+    if reader.take(WASM_MAGIC.len())? != WASM_MAGIC {
+        return None;
+    }
+    let mut out = WASM_MAGIC.to_vec();
+    while !reader.data.is_empty() {
+        let id = reader.u8()?;
+        let len = reader.leb()?;
+        let contents = reader.take(len as usize)?;
+        out.push(id);
+        if id != WASM_DATA_SECTION {
+            write_leb(&mut out, len);
+            out.extend_from_slice(contents);
+            continue;
+        }
+
+        let mut section = Reader { data: contents };
+        let mut segments = Vec::with_capacity(contents.len());
+        let count = section.leb()?;
+        write_leb(&mut segments, count);
+        for _ in 0..count {
+            match section.leb()? {
+                // Active segment of the first memory
+                0 => section.skip_const_expr()?,
+                1 => {}
+                // Active segment of a memory given by its index
+                2 => {
+                    section.leb()?;
+                    section.skip_const_expr()?;
+                }
+                _ => return None,
+            }
+            let size = section.leb()?;
+            write_leb(&mut segments, WASM_DATA_PASSIVE);
+            write_leb(&mut segments, size);
+            segments.extend_from_slice(section.take(size as usize)?);
+        }
+        write_leb(&mut out, u32::try_from(segments.len()).ok()?);
+        out.extend(segments);
+    }
+    Some(out)
+}
+
+fn write_leb(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.data.len() {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn leb(&mut self) -> Option<u32> {
+        let mut ret = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            ret |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(ret);
+            }
+        }
+        None
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.leb()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    /// Skips a signed or unsigned LEB128 number of up to 64 bits
+    fn skip_leb(&mut self) -> Option<()> {
+        for _ in 0..10 {
+            if self.u8()? & 0x80 == 0 {
+                return Some(());
+            }
+        }
+        None
+    }
+
+    /// Skips a constant expression up to and including its `end`
+    fn skip_const_expr(&mut self) -> Option<()> {
+        loop {
+            match self.u8()? {
+                // end
+                0x0b => return Some(()),
+                // i32.const, i64.const, global.get, ref.func
+                0x41 | 0x42 | 0x23 | 0xd2 => self.skip_leb()?,
+                // f32.const, f64.const
+                0x43 => drop(self.take(4)?),
+                0x44 => drop(self.take(8)?),
+                // ref.null
+                0xd0 => drop(self.u8()?),
+                // The arithmetic of extended constant expressions
+                0x6a..=0x6c | 0x7c..=0x7e => {}
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// The module of a library with its data segments made passive, which is
+/// only compiled once another instance needs it and is then shared by every
+/// copy of the library
+#[derive(Debug, Clone)]
+pub(crate) struct DlRelink(Arc<Mutex<DlRelinkModule>>);
+
+#[derive(derive_more::Debug)]
+enum DlRelinkModule {
+    Pending(#[debug(ignore)] Vec<u8>),
+    Compiled(Module),
+}
+
+impl DlRelink {
+    /// Prepares the relink module of a library from the module that was
+    /// compiled from `wasm`, returning `None` if the module is malformed
+    pub fn new(module: &Module, wasm: &[u8]) -> Option<Self> {
+        let passive = without_active_data(wasm)?;
+        // Libraries without active data can be instantiated as they are
+        let relink = if passive == wasm {
+            DlRelinkModule::Compiled(module.clone())
+        } else {
+            DlRelinkModule::Pending(passive)
+        };
+        Some(Self(Arc::new(Mutex::new(relink))))
+    }
+
+    /// Returns the relink module, compiling it the first time
+    pub fn module(&self, runtime: &dyn Runtime) -> Result<Module, SpawnError> {
+        let mut relink = self.0.lock().unwrap();
+        let module = match &*relink {
+            DlRelinkModule::Compiled(module) => return Ok(module.clone()),
+            DlRelinkModule::Pending(wasm) => runtime.load_module_sync(wasm)?,
+        };
+        *relink = DlRelinkModule::Compiled(module.clone());
+        Ok(module)
+    }
+}
+
+/// A side module that has been loaded into the process with `dlopen`
+#[derive(Debug, Clone)]
+pub(crate) struct DlLibrary {
+    /// Absolute path that the library was loaded from
+    pub path: PathBuf,
+    pub module: Module,
+    /// What the instances of the other threads and of forked processes are
+    /// created from, as the data of the library is already in memory by then
+    pub relink: DlRelink,
+    pub info: DylinkInfo,
+    /// Address in memory that the data of the library was placed at
+    pub memory_base: u64,
+    /// Index in the function table that the functions of the library
+    /// were placed at
+    pub table_base: u64,
+    /// The main module or library that each symbol imported by the library
+    /// was bound to, weak symbols that were not found are left out
+    pub bindings: HashMap<String, Dlhandle>,
+    /// Libraries that were loaded because this library needed them
+    pub needed: Vec<Dlhandle>,
+    /// Number of times that the library has been opened and not closed
+    pub refs: usize,
+}
+
+/// A symbol that has been resolved to one of the exports of the main
+/// module or of a library
+#[derive(Debug, Clone)]
+pub(crate) struct DlSymbol {
+    /// Handle of the library that exported the symbol, or
+    /// [`DlState::MAIN`] for the main module
+    pub owner: Dlhandle,
+    pub export: Extern,
+    /// Added to the value of an exported global to get an address
+    pub memory_base: u64,
+}
+
+/// The side modules that have been loaded into a process
+///
+/// Every instance of the process (one per thread) instantiates the
+/// libraries for itself, in the order of their handles, which keeps the
+/// globals of the stores of a process lined up for store snapshots. Closed
+/// libraries are kept for the same reason, they are just no longer found.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DlState {
+    libraries: BTreeMap<Dlhandle, DlLibrary>,
+    next_handle: Dlhandle,
+    /// Number of slots of the function table that are in use by any of the
+    /// instances of the process
+    pub table_size: u32,
+    /// Table slots that have been handed out for functions, keyed by the
+    /// library that exported the function and its name
+    pub func_slots: HashMap<(Dlhandle, String), u32>,
+    /// The volumes of the dependencies of the packages in use that have
+    /// been loaded to look for libraries in
+    pub volumes: HashMap<PackageId, Arc<dyn FileSystem + Send + Sync>>,
+}
+
+impl DlState {
+    /// Handle that refers to the main module
+    pub const MAIN: Dlhandle = 0;
+
+    pub fn is_empty(&self) -> bool {
+        self.libraries.is_empty()
+    }
+
+    /// Returns the handle of a library that is loaded
+    pub fn find(&self, path: &Path) -> Option<Dlhandle> {
+        self.libraries
+            .iter()
+            .find(|(_, library)| library.refs > 0 && library.path == path)
+            .map(|(handle, _)| *handle)
+    }
+
+    /// Returns a library that is loaded
+    pub fn get(&self, handle: Dlhandle) -> Option<&DlLibrary> {
+        self.libraries
+            .get(&handle)
+            .filter(|library| library.refs > 0)
+    }
+
+    /// Every library that was ever loaded, in the order of their handles
+    pub fn libraries(&self) -> impl Iterator<Item = (Dlhandle, &DlLibrary)> {
+        self.libraries
+            .iter()
+            .map(|(handle, library)| (*handle, library))
+    }
+
+    /// Takes another reference to a library that is already loaded
+    pub fn retain(&mut self, handle: Dlhandle) -> bool {
+        match self.libraries.get_mut(&handle) {
+            Some(library) if library.refs > 0 => {
+                library.refs += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Hands out the handle for a library that is about to be linked
+    pub fn reserve(&mut self) -> Dlhandle {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    pub fn insert(&mut self, handle: Dlhandle, library: DlLibrary) {
+        self.libraries.insert(handle, library);
+    }
+
+    /// Drops a reference to a library, releasing the libraries that it
+    /// needed once nothing refers to it
+    pub fn release(&mut self, handle: Dlhandle) -> bool {
+        let Some(library) = self.libraries.get_mut(&handle) else {
+            return false;
+        };
+        if library.refs == 0 {
+            return false;
+        }
+        library.refs -= 1;
+        if library.refs == 0 {
+            for needed in std::mem::take(&mut library.needed) {
+                self.release(needed);
+            }
+        }
+        true
+    }
+
+    /// Looks a symbol up in the main module and then in every library in
+    /// the order that they were loaded
+    pub fn resolve(&self, local: &DlInstances, main: &Instance, name: &str) -> Option<DlSymbol> {
+        self.lookup(local, main, Self::MAIN, name).or_else(|| {
+            self.libraries
+                .keys()
+                .find_map(|handle| self.resolve_in(local, *handle, name))
+        })
+    }
+
+    /// Looks a symbol up in a single library that is loaded
+    pub fn resolve_in(
+        &self,
+        local: &DlInstances,
+        handle: Dlhandle,
+        name: &str,
+    ) -> Option<DlSymbol> {
+        let library = self.get(handle)?;
+        let instance = local.instances.get(&handle)?.as_ref()?;
+        let export = instance.exports.get_extern(name)?;
+        Some(DlSymbol {
+            owner: handle,
+            export: export.clone(),
+            memory_base: library.memory_base,
+        })
+    }
+
+    /// Looks a symbol up in the main module or in a library, whether or not
+    /// the library has been closed since
+    pub fn lookup(
+        &self,
+        local: &DlInstances,
+        main: &Instance,
+        owner: Dlhandle,
+        name: &str,
+    ) -> Option<DlSymbol> {
+        let (instance, memory_base) = match owner {
+            Self::MAIN => (main, 0),
+            _ => (
+                local.instances.get(&owner)?.as_ref()?,
+                self.libraries.get(&owner)?.memory_base,
+            ),
+        };
+        let export = instance.exports.get_extern(name)?;
+        Some(DlSymbol {
+            owner,
+            export: export.clone(),
+            memory_base,
+        })
+    }
+}
+
+/// The instances of the libraries of a process that belong to the store of
+/// one of its threads
+#[derive(Debug, Default)]
+pub(crate) struct DlInstances {
+    /// Instances keyed by the handle of their library, `None` for libraries
+    /// that failed to link
+    pub instances: BTreeMap<Dlhandle, Option<Instance>>,
+    /// Slots of [`DlState::func_slots`] that have been filled in the
+    /// function table of this instance
+    pub slots: HashSet<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dylink_section() {
+        let mut data = vec![WASM_DYLINK_MEM_INFO, 6, 0x80, 0x01, 4, 3, 0];
+        // Padding that is ignored as it belongs to the subsection
+        data.push(0);
+        data.extend([WASM_DYLINK_NEEDED, 12, 2, 4]);
+        data.extend(b"libm");
+        data.push(5);
+        data.extend(b"libz2");
+        data.extend([WASM_DYLINK_IMPORT_INFO, 11, 1, 3]);
+        data.extend(b"env");
+        data.push(4);
+        data.extend(b"weak");
+        data.push(WASM_SYMBOL_BINDING_WEAK as u8);
+        // Unknown subsections are skipped
+        data.extend([9, 2, 0xff, 0xff]);
+
+        let info = DylinkInfo::parse(&data).unwrap();
+        assert_eq!(info.memory_size, 128);
+        assert_eq!(info.memory_align, 4);
+        assert_eq!(info.table_size, 3);
+        assert_eq!(info.table_align, 0);
+        assert_eq!(info.needed, vec!["libm".to_string(), "libz2".to_string()]);
+        assert!(info
+            .weak_imports
+            .contains(&("env".to_string(), "weak".to_string())));
+    }
+
+    #[test]
+    fn data_segments_are_made_passive() {
+        let wasm = wasmer::wat2wasm(
+            br#"(module
+                (import "env" "memory" (memory 1))
+                (import "env" "__memory_base" (global $base i32))
+                (data (global.get $base) "abc")
+                (data (i32.const 8) "de")
+                (data $passive "fgh")
+                (func (export "init")
+                    (memory.init $passive (i32.const 0) (i32.const 0) (i32.const 3))))"#,
+        )
+        .unwrap();
+
+        let passive = without_active_data(&wasm).unwrap();
+        let engine = wasmer::Engine::default();
+        let module = Module::new(&engine, &passive).unwrap();
+        let mut store = wasmer::Store::new(engine);
+        let memory =
+            wasmer::Memory::new(&mut store, wasmer::MemoryType::new(1, None, false)).unwrap();
+        memory.view(&store).write(0, b"xyz").unwrap();
+        let imports = wasmer::imports! {
+            "env" => {
+                "memory" => memory.clone(),
+                "__memory_base" => wasmer::Global::new(&mut store, wasmer::Value::I32(0)),
+            }
+        };
+        let instance = wasmer::Instance::new(&mut store, &module, &imports).unwrap();
+
+        let mut data = [0u8; 10];
+        memory.view(&store).read(0, &mut data).unwrap();
+        assert_eq!(&data, b"xyz\0\0\0\0\0\0\0");
+
+        // The segments that were passive already are still found
+        let init = instance.exports.get_function("init").unwrap();
+        init.call(&mut store, &[]).unwrap();
+        memory.view(&store).read(0, &mut data[..3]).unwrap();
+        assert_eq!(&data[..3], b"fgh");
+
+        assert_eq!(without_active_data(b"(module)"), None);
+        assert_eq!(without_active_data(&wasm[..wasm.len() - 1]), None);
+    }
+
+    #[test]
+    fn parse_truncated_dylink_section() {
+        assert_eq!(DylinkInfo::parse(&[WASM_DYLINK_MEM_INFO, 4, 1, 2]), None);
+        assert_eq!(DylinkInfo::parse(&[WASM_DYLINK_NEEDED, 2, 1, 5]), None);
+    }
+}
@@ -2,7 +2,7 @@ use std::{
     collections::HashMap,
     ops::Deref,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
@@ -29,20 +29,23 @@ use crate::{
     capabilities::Capabilities,
     fs::{WasiFsRoot, WasiInodes},
     import_object_for_all_wasi_versions,
-    os::task::{
-        control_plane::ControlPlaneError,
-        process::{WasiProcess, WasiProcessId},
-        thread::{WasiMemoryLayout, WasiThread, WasiThreadHandle, WasiThreadId},
+    os::{
+        credentials::FsPermissions,
+        task::{
+            control_plane::ControlPlaneError,
+            process::{WasiProcess, WasiProcessId},
+            thread::{WasiMemoryLayout, WasiThread, WasiThreadHandle, WasiThreadId},
+        },
     },
     runtime::{task_manager::InlineWaker, SpawnMemoryType},
-    syscalls::platform_clock_time_get,
+    syscalls::{__asyncify_light, platform_clock_time_get},
     Runtime, VirtualTaskManager, WasiControlPlane, WasiEnvBuilder, WasiError, WasiFunctionEnv,
     WasiResult, WasiRuntimeError, WasiStateCreationError, WasiVFork,
 };
 use wasmer_types::ModuleHash;
 
 pub(crate) use super::handles::*;
-use super::{conv_env_vars, WasiState};
+use super::{conv_env_vars, DlInstances, WasiState};
 
 /// Various [`TypedFunction`] and [`Global`] handles for an active WASI(X) instance.
 ///
@@ -133,6 +136,10 @@ pub struct WasiInstanceHandles {
     /// when to propagate results back.
     #[allow(dead_code)]
     pub(crate) asyncify_get_state: Option<TypedFunction<(), i32>>,
+
+    /// Instances of the side modules of the process that have been linked
+    /// into this instance
+    pub(crate) dl: Arc<Mutex<DlInstances>>,
 }
 
 impl WasiInstanceHandles {
@@ -182,6 +189,7 @@ impl WasiInstanceHandles {
                 .exports
                 .get_typed_function(store, "asyncify_get_state")
                 .ok(),
+            dl: Default::default(),
             instance,
         }
     }
@@ -263,9 +271,18 @@ impl WasiEnvInit {
         let inodes = WasiInodes::new();
 
         // TODO: preserve preopens?
-        let fs =
+        let mut fs =
             crate::fs::WasiFs::new_with_preopen(&inodes, &[], &[], self.state.fs.root_fs.clone())
                 .unwrap();
+        let credentials = Arc::new(std::sync::Mutex::new(
+            self.state.credentials.lock().unwrap().deref().clone(),
+        ));
+        if let Some(permissions) = self.state.fs.permissions() {
+            fs.set_permissions(FsPermissions {
+                credentials: credentials.clone(),
+                ..permissions
+            });
+        }
 
         Self {
             state: WasiState {
@@ -273,12 +290,15 @@ impl WasiEnvInit {
                 inodes,
                 fs,
                 futexs: Default::default(),
+                mmaps: Default::default(),
+                ipc: Default::default(),
                 clock_offset: std::sync::Mutex::new(
                     self.state.clock_offset.lock().unwrap().clone(),
                 ),
                 args: std::sync::Mutex::new(self.state.args.lock().unwrap().clone()),
                 envs: std::sync::Mutex::new(self.state.envs.lock().unwrap().deref().clone()),
                 signals: std::sync::Mutex::new(self.state.signals.lock().unwrap().deref().clone()),
+                credentials,
                 preopen: self.state.preopen.clone(),
             },
             runtime: self.runtime.clone(),
@@ -398,12 +418,17 @@ impl WasiEnv {
     /// Forking the WasiState is used when either fork or vfork is called
     pub fn fork(&self) -> Result<(Self, WasiThreadHandle), ControlPlaneError> {
         let process = self.control_plane.new_process(self.process.module_hash)?;
+        process.inherit_group(&self.process);
+        process.set_resource_group(self.process.resource_group());
+        process.inherit_libraries(&self.process);
         let handle = process.new_thread(self.layout.clone(), ThreadStartType::MainThread)?;
 
         let thread = handle.as_thread();
         thread.copy_stack_from(&self.thread);
 
         let state = Arc::new(self.state.fork());
+        state.fs.set_proc_self(process.pid());
+        process.set_state(&state);
 
         let bin_factory = self.bin_factory.clone();
 
@@ -565,6 +590,8 @@ impl WasiEnv {
             disable_fs_cleanup: false,
         };
         env.owned_handles.push(thread);
+        env.state.fs.set_proc_self(env.process.pid());
+        env.process.set_state(&env.state);
 
         // TODO: should not be here - should be callers responsibility!
         for pkg in &init.webc_dependencies {
@@ -713,19 +740,50 @@ impl WasiEnv {
         self.process.active_threads()
     }
 
+    /// Records the size of the linear memory so that it can be shown in
+    /// `/proc`, as it can only be read from the store that owns it
+    pub(crate) fn sample_memory_size(&self, store: &impl AsStoreRef) {
+        if let Some(inner) = self.try_inner() {
+            let ty = inner.memory.ty(store);
+            let size = inner.memory.view(store).data_size();
+            let limit = ty
+                .maximum
+                .map(|pages| pages.bytes().0 as u64)
+                .unwrap_or(1 << 32);
+            self.process.set_memory_size(size, limit);
+        }
+    }
+
     /// Porcesses any signals that are batched up or any forced exit codes
     pub fn process_signals_and_exit(ctx: &mut FunctionEnvMut<'_, Self>) -> WasiResult<bool> {
+        let env = ctx.data();
+        env.sample_memory_size(ctx);
+
+        // Threads stay here while the process is stopped by a job control
+        // signal, until it is continued
+        if env.process.is_stopped() {
+            Self::wait_while_stopped(env)?;
+        }
+
         // If a signal handler has never been set then we need to handle signals
         // differently
-        let env = ctx.data();
         let inner = env
             .try_inner()
             .ok_or_else(|| WasiError::Exit(Errno::Fault.into()))?;
         if !inner.signal_set {
             let signals = env.thread.pop_signals();
             if !signals.is_empty() {
+                // A stop that is followed by a continue cancels out
+                let continued = signals.contains(&Signal::Sigcont);
                 for sig in signals {
-                    if sig == Signal::Sigint
+                    if matches!(
+                        sig,
+                        Signal::Sigstop | Signal::Sigtstp | Signal::Sigttin | Signal::Sigttou
+                    ) {
+                        if !continued && env.process.stop(&env.thread, sig) {
+                            Self::wait_while_stopped(env)?;
+                        }
+                    } else if sig == Signal::Sigint
                         || sig == Signal::Sigquit
                         || sig == Signal::Sigkill
                         || sig == Signal::Sigabrt
@@ -749,6 +807,17 @@ impl WasiEnv {
         Self::process_signals(ctx)
     }
 
+    /// Waits for as long as the process is stopped
+    fn wait_while_stopped(env: &WasiEnv) -> Result<(), WasiError> {
+        let process = env.process.clone();
+        __asyncify_light(env, None, async move {
+            process.wait_while_stopped().await;
+            Ok(())
+        })?
+        .ok();
+        Ok(())
+    }
+
     /// Porcesses any signals that are batched up
     pub(crate) fn process_signals(ctx: &mut FunctionEnvMut<'_, Self>) -> WasiResult<bool> {
         // If a signal handler has never been set then we need to handle signals
@@ -781,6 +850,11 @@ impl WasiEnv {
         let inner = env
             .try_inner()
             .ok_or_else(|| WasiError::Exit(Errno::Fault.into()))?;
+        // `SIGKILL` can not be caught
+        if signals.contains(&Signal::Sigkill) {
+            let exit_code = env.thread.set_or_get_exit_code_for_signal(Signal::Sigkill);
+            return Err(WasiError::Exit(exit_code));
+        }
         if let Some(handler) = inner.signal.clone() {
             // We might also have signals that trigger on timers
             let mut now = 0;
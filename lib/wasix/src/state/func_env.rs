@@ -183,9 +183,24 @@ impl WasiFunctionEnv {
         let stack_low = new_inner.stack_low.clone();
         let stack_high = new_inner.stack_high.clone();
 
-        let env = self.data_mut(store);
-        env.set_inner(new_inner);
+        self.data_mut(store).set_inner(new_inner);
+        self.data(store).sample_memory_size(store);
+
+        // Link the libraries that the process loaded with `dlopen` into the
+        // new instance
+        crate::syscalls::dl_replay(store, &self.env).map_err(|err| {
+            ExportError::Missing(format!(
+                "failed to link the libraries of the process - {err}"
+            ))
+        })?;
+        #[cfg(all(unix, feature = "sys"))]
+        crate::syscalls::mmap_replay(store, &self.env).map_err(|err| {
+            ExportError::Missing(format!(
+                "failed to map the shared memory of the process - {err}"
+            ))
+        })?;
 
+        let env = self.data_mut(store);
         env.state.fs.set_is_wasix(is_wasix_module);
 
         // If the stack offset and size is not set then do so
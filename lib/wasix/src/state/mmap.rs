@@ -0,0 +1,242 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use virtual_fs::VirtualFile;
+use wasmer_wasix_types::wasi::{Mmapflags, Mmapprot};
+
+#[cfg(all(unix, feature = "sys"))]
+use crate::fs::SharedMemoryObject;
+
+/// The pages of a shared memory object that have been mapped into linear
+/// memory
+#[cfg(all(unix, feature = "sys"))]
+#[derive(Debug, Clone)]
+pub(crate) struct WasiMmapAlias {
+    pub object: Arc<SharedMemoryObject>,
+    /// Address of the linear memory that the pages were mapped into, which
+    /// a forked process uses to tell that it has a copy of that memory
+    pub base: usize,
+    /// Whether changes are made to the object rather than to a private
+    /// copy of its pages
+    pub shared: bool,
+}
+
+/// A range of a file that has been mapped into the linear memory of the
+/// process with `fd_mmap`.
+///
+/// Shared memory objects are mapped by mapping their pages into linear
+/// memory (see [`WasiMmapAlias`]). Other files can not be, so their
+/// contents are copied into memory when they are mapped and copied back
+/// out again when a shared mapping is synchronized. Both leave the cursor
+/// of the file, which is shared with its file descriptors, alone. A region
+/// that covers the whole file is written back with `copy_reference`, which
+/// file systems that keep their files in memory do without another copy.
+#[derive(Debug, Clone)]
+pub(crate) struct WasiMmap {
+    pub len: u64,
+    /// Offset in the file that the start of the region maps to
+    pub offset: u64,
+    pub prot: Mmapprot,
+    pub flags: Mmapflags,
+    /// Whether the file descriptor that was mapped allowed writes, which
+    /// `mprotect` needs to know before a shared region can become writable
+    pub writable: bool,
+    pub file: Arc<RwLock<Box<dyn VirtualFile + Send + Sync + 'static>>>,
+    #[cfg(all(unix, feature = "sys"))]
+    pub alias: Option<WasiMmapAlias>,
+}
+
+impl WasiMmap {
+    /// Returns true if the pages of the region are those of the file
+    pub fn is_alias(&self) -> bool {
+        #[cfg(all(unix, feature = "sys"))]
+        {
+            self.alias.is_some()
+        }
+        #[cfg(not(all(unix, feature = "sys")))]
+        {
+            false
+        }
+    }
+
+    /// Returns true if changes made to the region need to be written back
+    pub fn is_writeback(&self) -> bool {
+        self.flags.contains(Mmapflags::SHARED)
+            && self.prot.contains(Mmapprot::WRITE)
+            && !self.is_alias()
+    }
+}
+
+/// Stores the regions of memory that have files mapped into them, keyed
+/// by the address that they start at
+#[derive(Debug, Default, Clone)]
+pub(crate) struct WasiMmapState {
+    regions: BTreeMap<u64, WasiMmap>,
+}
+
+impl WasiMmapState {
+    /// Returns true if any part of the range is mapped
+    pub fn overlaps(&self, addr: u64, len: u64) -> bool {
+        let end = addr.saturating_add(len);
+        self.regions
+            .range(..end)
+            .next_back()
+            .map(|(start, region)| start + region.len > addr)
+            .unwrap_or(false)
+    }
+
+    /// Returns true if every byte in the range is mapped
+    pub fn covers(&self, addr: u64, len: u64) -> bool {
+        let end = addr.saturating_add(len);
+        let mut cursor = addr;
+        if let Some((start, region)) = self.regions.range(..=addr).next_back() {
+            cursor = cursor.max(start + region.len);
+        }
+        for (start, region) in self.regions.range(addr..end) {
+            if *start > cursor {
+                return false;
+            }
+            cursor = cursor.max(start + region.len);
+        }
+        cursor >= end
+    }
+
+    /// Returns copies of the regions within the range, trimmed so that
+    /// they do not extend past its boundaries
+    pub fn regions(&self, addr: u64, len: u64) -> Vec<(u64, WasiMmap)> {
+        let end = addr.saturating_add(len);
+        let first = self
+            .regions
+            .range(..addr)
+            .next_back()
+            .map(|(start, _)| *start)
+            .unwrap_or(addr);
+        self.regions
+            .range(first..end)
+            .filter_map(|(&start, region)| {
+                let region_end = start + region.len;
+                if region_end <= addr {
+                    return None;
+                }
+                let trimmed_start = start.max(addr);
+                let mut region = region.clone();
+                region.offset += trimmed_start - start;
+                region.len = region_end.min(end) - trimmed_start;
+                Some((trimmed_start, region))
+            })
+            .collect()
+    }
+
+    pub fn insert(&mut self, addr: u64, region: WasiMmap) {
+        self.regions.insert(addr, region);
+    }
+
+    /// Splits the region containing `addr` so that a region starts exactly
+    /// at `addr`
+    fn split_at(&mut self, addr: u64) {
+        let Some((&start, region)) = self.regions.range_mut(..addr).next_back() else {
+            return;
+        };
+        if start + region.len <= addr {
+            return;
+        }
+
+        let head = addr - start;
+        let mut tail = region.clone();
+        tail.len = region.len - head;
+        tail.offset = region.offset + head;
+        region.len = head;
+        self.regions.insert(addr, tail);
+    }
+
+    /// Returns the regions within the range, splitting the regions that
+    /// straddle its boundaries
+    pub fn range_mut(
+        &mut self,
+        addr: u64,
+        len: u64,
+    ) -> impl Iterator<Item = (&u64, &mut WasiMmap)> {
+        let end = addr.saturating_add(len);
+        self.split_at(addr);
+        self.split_at(end);
+        self.regions.range_mut(addr..end)
+    }
+
+    /// Removes the regions within the range, splitting the regions that
+    /// straddle its boundaries
+    pub fn remove_range(&mut self, addr: u64, len: u64) -> Vec<(u64, WasiMmap)> {
+        let starts = self
+            .range_mut(addr, len)
+            .map(|(start, _)| *start)
+            .collect::<Vec<_>>();
+        starts
+            .into_iter()
+            .filter_map(|start| self.regions.remove(&start).map(|region| (start, region)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use virtual_fs::NullFile;
+
+    use super::*;
+
+    fn region(len: u64, offset: u64) -> WasiMmap {
+        WasiMmap {
+            len,
+            offset,
+            prot: Mmapprot::READ | Mmapprot::WRITE,
+            flags: Mmapflags::SHARED,
+            writable: true,
+            file: Arc::new(RwLock::new(Box::<NullFile>::default())),
+            #[cfg(all(unix, feature = "sys"))]
+            alias: None,
+        }
+    }
+
+    #[test]
+    fn overlapping_and_covered_ranges() {
+        let mut state = WasiMmapState::default();
+        state.insert(100, region(100, 0));
+        state.insert(200, region(50, 0));
+
+        assert!(state.overlaps(150, 10));
+        assert!(state.overlaps(50, 51));
+        assert!(!state.overlaps(50, 50));
+        assert!(!state.overlaps(250, 10));
+
+        assert!(state.covers(100, 150));
+        assert!(state.covers(120, 100));
+        assert!(!state.covers(90, 20));
+        assert!(!state.covers(240, 20));
+
+        let regions = state
+            .regions(150, 75)
+            .into_iter()
+            .map(|(start, region)| (start, region.len, region.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(regions, vec![(150, 50, 50), (200, 25, 0)]);
+    }
+
+    #[test]
+    fn removing_part_of_a_region_splits_it() {
+        let mut state = WasiMmapState::default();
+        state.insert(100, region(100, 4096));
+
+        let removed = state.remove_range(120, 30);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].0, 120);
+        assert_eq!(removed[0].1.len, 30);
+        assert_eq!(removed[0].1.offset, 4096 + 20);
+
+        let remaining = state
+            .range_mut(0, u64::MAX)
+            .map(|(start, region)| (*start, region.len, region.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![(100, 20, 4096), (150, 50, 4096 + 50)]);
+        assert!(!state.overlaps(120, 30));
+    }
+}
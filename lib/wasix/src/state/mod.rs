@@ -16,16 +16,18 @@
 #![allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 
 mod builder;
+mod dylink;
 mod env;
 mod func_env;
 mod handles;
+mod mmap;
 mod run;
 mod types;
 
 use std::{
     collections::{BTreeMap, HashMap},
     path::Path,
-    sync::Mutex,
+    sync::{Arc, Mutex},
     task::Waker,
     time::Duration,
 };
@@ -47,10 +49,16 @@ pub use self::{
 pub use crate::fs::{InodeGuard, InodeWeakGuard};
 use crate::{
     fs::{fs_error_into_wasi_err, WasiFs, WasiFsRoot, WasiInodes, WasiStateFileGuard},
+    os::{
+        credentials::{Credentials, FileOwner},
+        ipc::IpcHandles,
+    },
     syscalls::types::*,
     utils::WasiParkingLot,
 };
+pub(crate) use dylink::*;
 pub(crate) use handles::*;
+pub(crate) use mmap::*;
 
 /// all the rights enabled
 pub const ALL_RIGHTS: Rights = Rights::all();
@@ -134,10 +142,16 @@ pub(crate) struct WasiState {
     pub fs: WasiFs,
     pub inodes: WasiInodes,
     pub futexs: Mutex<WasiFutexState>,
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    pub mmaps: Mutex<WasiMmapState>,
+    #[cfg_attr(feature = "enable-serde", serde(skip))]
+    pub ipc: Mutex<IpcHandles>,
     pub clock_offset: Mutex<HashMap<Snapshot0Clockid, i64>>,
     pub args: Mutex<Vec<String>>,
     pub envs: Mutex<Vec<Vec<u8>>>,
     pub signals: Mutex<HashMap<Signal, Disposition>>,
+    /// Shared with the file system, which checks permissions against them
+    pub credentials: Arc<Mutex<Credentials>>,
 
     // TODO: should not be here, since this requires active work to resolve.
     // State should only hold active runtime state that can be reproducibly re-created.
@@ -205,6 +219,47 @@ impl WasiState {
             .map_err(fs_error_into_wasi_err)
     }
 
+    /// Value of the [`OWNER_XATTR`](crate::os::credentials::OWNER_XATTR) of a file, which is read from its inode
+    /// rather than from the file
+    pub(crate) fn fs_get_owner_xattr(&self, inode: &InodeGuard) -> Result<Vec<u8>, Errno> {
+        self.fs
+            .owner_of(inode)
+            .map(|owner| owner.to_xattr())
+            .ok_or(Errno::Noent)
+    }
+
+    /// Changes the owner of a file through its [`OWNER_XATTR`](crate::os::credentials::OWNER_XATTR)
+    pub(crate) fn fs_set_owner_xattr(
+        &self,
+        inode: &InodeGuard,
+        value: &[u8],
+        mode: virtual_fs::XattrMode,
+    ) -> Result<(), Errno> {
+        self.fs.check_privileged()?;
+        let owner = FileOwner::from_xattr(value).ok_or(Errno::Inval)?;
+        let mut current = inode.owner.write().unwrap();
+        match (mode, current.is_some()) {
+            (virtual_fs::XattrMode::Create, true) => Err(Errno::Exist),
+            (virtual_fs::XattrMode::Replace, false) => Err(Errno::Noent),
+            _ => {
+                *current = Some(owner);
+                Ok(())
+            }
+        }
+    }
+
+    /// Gives a file back to the default owner through its [`OWNER_XATTR`](crate::os::credentials::OWNER_XATTR)
+    pub(crate) fn fs_remove_owner_xattr(&self, inode: &InodeGuard) -> Result<(), Errno> {
+        self.fs.check_privileged()?;
+        inode
+            .owner
+            .write()
+            .unwrap()
+            .take()
+            .map(|_| ())
+            .ok_or(Errno::Noent)
+    }
+
     pub(crate) fn fs_new_open_options(&self) -> OpenOptions {
         self.fs.root_fs.new_open_options()
     }
@@ -252,15 +307,21 @@ impl WasiState {
 
     /// Forking the WasiState is used when either fork or vfork is called
     pub fn fork(&self) -> Self {
+        let credentials = Arc::new(Mutex::new(self.credentials.lock().unwrap().clone()));
+        let mut fs = self.fs.fork();
+        fs.set_credentials(credentials.clone());
         WasiState {
-            fs: self.fs.fork(),
+            fs,
             secret: self.secret,
             inodes: self.inodes.clone(),
             futexs: Default::default(),
+            mmaps: Mutex::new(self.mmaps.lock().unwrap().clone()),
+            ipc: Mutex::new(self.ipc.lock().unwrap().clone()),
             clock_offset: Mutex::new(self.clock_offset.lock().unwrap().clone()),
             args: Mutex::new(self.args.lock().unwrap().clone()),
             envs: Mutex::new(self.envs.lock().unwrap().clone()),
             signals: Mutex::new(self.signals.lock().unwrap().clone()),
+            credentials,
             preopen: self.preopen.clone(),
         }
     }
@@ -2,7 +2,10 @@ use virtual_mio::InlineWaker;
 use wasmer::{RuntimeError, Store};
 use wasmer_wasix_types::wasi::ExitCode;
 
-use crate::{os::task::thread::RewindResultType, RewindStateOption, WasiError, WasiRuntimeError};
+use crate::{
+    os::task::thread::{CurrentThreadGuard, RewindResultType},
+    RewindStateOption, WasiError, WasiRuntimeError,
+};
 
 use super::*;
 
@@ -85,6 +88,8 @@ fn run_with_deep_sleep(
     env: WasiFunctionEnv,
     sender: tokio::sync::mpsc::UnboundedSender<Result<Store, WasiRuntimeError>>,
 ) {
+    let data = env.data(&store);
+    let _current = CurrentThreadGuard::enter(data.pid(), data.tid(), &data.process.usage);
     if let Some((rewind_state, rewind_result)) = rewind_state {
         tracing::trace!("Rewinding");
         let mut ctx = env.env.clone().into_mut(&mut store);
@@ -0,0 +1,17 @@
+use super::*;
+
+impl<'a, 'c> JournalSyscallPlayer<'a, 'c> {
+    #[allow(clippy::result_large_err)]
+    pub(crate) unsafe fn action_fd_fallocate(
+        &mut self,
+        fd: Fd,
+        flags: Fallocflags,
+        offset: Filesize,
+        len: Filesize,
+    ) -> Result<(), WasiRuntimeError> {
+        tracing::trace!(%fd, ?flags, %offset, %len, "Replay journal - FdFallocate");
+        JournalEffector::apply_fd_fallocate(&mut self.ctx, fd, flags, offset, len)
+            .map_err(anyhow_err_to_runtime_err)?;
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ mod fd_advise;
 mod fd_allocate;
 mod fd_close;
 mod fd_dup;
+mod fd_fallocate;
 mod fd_open;
 mod fd_renumber;
 mod fd_seek;
@@ -36,6 +37,7 @@ use wasmer_types::Memory32;
 use wasmer_types::Memory64;
 use wasmer_wasix_types::wasi::Advice;
 use wasmer_wasix_types::wasi::ExitCode;
+use wasmer_wasix_types::wasi::Fallocflags;
 use wasmer_wasix_types::wasi::Fd;
 use wasmer_wasix_types::wasi::Filesize;
 use wasmer_wasix_types::wasi::Tty;
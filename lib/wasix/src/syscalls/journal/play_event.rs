@@ -140,6 +140,36 @@ impl<'a, 'c> JournalSyscallPlayer<'a, 'c> {
                 JournalEffector::apply_path_remove_directory(&mut self.ctx, fd, &path)
                     .map_err(anyhow_err_to_runtime_err)?;
             }
+            JournalEntry::PathSetXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+                value,
+                xflags,
+            } => {
+                tracing::trace!(%fd, %path, %name, "Replay journal - PathSetXattr");
+                JournalEffector::apply_path_setxattr(
+                    &mut self.ctx,
+                    fd,
+                    flags,
+                    &path,
+                    &name,
+                    &value,
+                    xflags,
+                )
+                .map_err(anyhow_err_to_runtime_err)?;
+            }
+            JournalEntry::PathRemoveXattrV1 {
+                fd,
+                flags,
+                path,
+                name,
+            } => {
+                tracing::trace!(%fd, %path, %name, "Replay journal - PathRemoveXattr");
+                JournalEffector::apply_path_removexattr(&mut self.ctx, fd, flags, &path, &name)
+                    .map_err(anyhow_err_to_runtime_err)?;
+            }
             JournalEntry::UnlinkFileV1 { fd, path } => {
                 tracing::trace!("Replay journal - UnlinkFile {}", path);
                 JournalEffector::apply_path_unlink(&mut self.ctx, fd, &path)
@@ -344,6 +374,26 @@ impl<'a, 'c> JournalSyscallPlayer<'a, 'c> {
                     self.action_fd_allocate(fd, offset, len)?;
                 }
             }
+            JournalEntry::FileDescriptorFallocateV1 {
+                fd,
+                flags,
+                offset,
+                len,
+            } => {
+                if self.real_fd.contains(&fd) {
+                    self.action_fd_fallocate(fd, flags, offset, len)?;
+                } else if let Some(differ_ethereal) = differ_ethereal {
+                    tracing::trace!(%fd, ?flags, %offset, %len, "Differ(ether) journal - FdFallocate");
+                    differ_ethereal.push(JournalEntry::FileDescriptorFallocateV1 {
+                        fd,
+                        flags,
+                        offset,
+                        len,
+                    });
+                } else {
+                    self.action_fd_fallocate(fd, flags, offset, len)?;
+                }
+            }
             JournalEntry::CreateHardLinkV1 {
                 old_fd,
                 old_path,
@@ -529,6 +579,26 @@ impl<'a, 'c> JournalSyscallPlayer<'a, 'c> {
                         .map_err(anyhow_err_to_runtime_err)?
                 }
             }
+            JournalEntry::SocketBindUnixV1 { fd, path } => {
+                if let Some(differ_ethereal) = differ_ethereal {
+                    tracing::trace!(%fd, %path, "Differ(ether) journal - SocketBindUnix");
+                    differ_ethereal.push(JournalEntry::SocketBindUnixV1 { fd, path });
+                } else {
+                    tracing::trace!(%fd, %path, "Replay journal - SocketBindUnix");
+                    JournalEffector::apply_sock_bind_unix(&mut self.ctx, fd, &path)
+                        .map_err(anyhow_err_to_runtime_err)?
+                }
+            }
+            JournalEntry::SocketConnectUnixV1 { fd, path } => {
+                if let Some(differ_ethereal) = differ_ethereal {
+                    tracing::trace!(%fd, %path, "Differ(ether) journal - SocketConnectUnix");
+                    differ_ethereal.push(JournalEntry::SocketConnectUnixV1 { fd, path });
+                } else {
+                    tracing::trace!(%fd, %path, "Replay journal - SocketConnectUnix");
+                    JournalEffector::apply_sock_connect_unix(&mut self.ctx, fd, &path)
+                        .map_err(anyhow_err_to_runtime_err)?
+                }
+            }
             JournalEntry::SocketConnectedV1 {
                 fd,
                 local_addr,
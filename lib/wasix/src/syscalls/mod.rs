@@ -85,12 +85,14 @@ pub use windows::*;
 
 pub(crate) use self::types::{
     wasi::{
-        Addressfamily, Advice, Clockid, Dircookie, Dirent, Errno, Event, EventFdReadwrite,
-        Eventrwflags, Eventtype, ExitCode, Fd as WasiFd, Fdflags, Fdflagsext, Fdstat, Filesize,
-        Filestat, Filetype, Fstflags, Linkcount, Longsize, OptionFd, Pid, Prestat, ProcSpawnFdOp,
-        Rights, SignalDisposition, Snapshot0Clockid, Sockoption, Sockstatus, Socktype,
-        StackSnapshot, StdioMode as WasiStdioMode, Streamsecurity, Subscription,
-        SubscriptionFsReadwrite, Tid, Timestamp, TlKey, TlUser, TlVal, Tty, Whence,
+        Addressfamily, Advice, Clockid, Dircookie, Dirent, Dlflags, Dlhandle, Errno, Event,
+        EventFdReadwrite, Eventrwflags, Eventtype, ExitCode, Fallocflags, Fd as WasiFd, Fdflags,
+        Fdflagsext, Fdstat, Filesize, Filestat, Filetype, Fstflags, Gid, Ipcflags, Linkcount,
+        Longsize, Mmapflags, Mmapprot, Mqhandle, Msyncflags, OptionFd, Pid, Prestat, ProcSpawnFdOp,
+        Rights, Rusage, Rusagewho, Semhandle, SignalDisposition, Snapshot0Clockid, Sockoption,
+        Sockstatus, Socktype, StackSnapshot, StdioMode as WasiStdioMode, Streamsecurity,
+        Subscription, SubscriptionFsReadwrite, Tid, Timestamp, TlKey, TlUser, TlVal, Tty, Uid,
+        Whence, Xattrflags,
     },
     *,
 };
@@ -108,12 +110,12 @@ pub(crate) use crate::{
     net::{
         read_ip_port,
         socket::{InodeHttpSocketType, InodeSocket, InodeSocketKind},
-        write_ip_port,
+        write_ip_port, write_unix_addr_port,
     },
     runtime::SpawnMemoryType,
     state::{
         self, iterate_poll_events, InodeGuard, InodeWeakGuard, PollEvent, PollEventBuilder,
-        WasiFutex, WasiState,
+        WasiFutex, WasiMmap, WasiState,
     },
     utils::{self, map_io_err},
     Runtime, VirtualTaskManager, WasiEnv, WasiError, WasiFunctionEnv, WasiInstanceHandles,
@@ -127,6 +129,7 @@ use crate::{
     journal::{DynJournal, DynReadableJournal, DynWritableJournal, JournalEffector},
     os::task::{
         process::{MaybeCheckpointResult, WasiProcessCheckpoint},
+        rusage::ResourceCounters,
         thread::{RewindResult, RewindResultType},
     },
     runtime::task_manager::InlineWaker,
@@ -344,11 +347,15 @@ where
         }
     }
 
-    // Block on the work
+    // Block on the work (the time spent blocked is not busy time)
     let mut pinned_work = Box::pin(work);
     let tasks = env.tasks().clone();
+    let usage = env.process.usage.clone();
+    let blocked = ResourceCounters::now();
     let poller = SignalPoller { ctx, pinned_work };
-    block_on_with_timeout(&tasks, timeout, poller)
+    let res = block_on_with_timeout(&tasks, timeout, poller);
+    usage.add_blocked(blocked);
+    res
 }
 
 /// Future that will be polled by asyncify methods
@@ -443,13 +450,21 @@ pub(crate) fn maybe_backoff<M: MemorySize>(
     let env = ctx.data();
 
     // Fast path that exits this high volume call if we do not have
-    // exponential backoff enabled
-    if env.enable_exponential_cpu_backoff.is_none() {
+    // exponential backoff enabled nor a CPU quota
+    let cpu_limited = env.process.resource_group().is_cpu_limited();
+    if env.enable_exponential_cpu_backoff.is_none() && !cpu_limited {
         return Ok(Ok(ctx));
     }
 
     // Determine if we need to do a backoff, if so lets do one
-    if let Some(backoff) = env.process.acquire_cpu_backoff_token(env.tasks()) {
+    let mut backoff = None;
+    if cpu_limited {
+        backoff = env.process.acquire_cpu_quota_token(env.tasks());
+    }
+    if backoff.is_none() && env.enable_exponential_cpu_backoff.is_some() {
+        backoff = env.process.acquire_cpu_backoff_token(env.tasks());
+    }
+    if let Some(backoff) = backoff {
         tracing::trace!("exponential CPU backoff {:?}", backoff.backoff_time());
         if let AsyncifyAction::Finish(mut ctx, _) =
             __asyncify_with_deep_sleep::<M, _, _>(ctx, backoff)?
@@ -491,6 +506,8 @@ where
 
     // Define the work
     let tasks = ctx.data().tasks().clone();
+    let usage = ctx.data().process.usage.clone();
+    let blocked = ResourceCounters::now();
     let work = async move {
         let env = ctx.data();
 
@@ -516,6 +533,7 @@ where
                 work: &mut trigger,
             } => {
                 let result = res?;
+                usage.add_blocked(blocked);
                 AsyncifyAction::Finish(ctx, result)
             },
             // Determines when and if we should go into a deep sleep
@@ -534,6 +552,7 @@ where
                     // After this wakes the background work or waking
                     // event has triggered and its time to result
                     let result = trigger.await;
+                    usage.add_blocked(blocked);
                     tracing::trace!(%pid, %tid, "thread leaving deep sleep");
                     thread.set_deep_sleeping(false);
                     bincode::serialize(&result).unwrap().into()
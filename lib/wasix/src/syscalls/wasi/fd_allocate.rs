@@ -2,7 +2,8 @@ use super::*;
 use crate::syscalls::*;
 
 /// ### `fd_allocate`
-/// Allocate extra space for a file descriptor
+/// Allocate extra space for a file descriptor. Like `posix_fallocate()`
+/// this only ever grows the file, see `fd_fallocate()` for punching holes
 /// Inputs:
 /// - `Fd fd`
 ///     The file descriptor to allocate for
@@ -52,13 +53,17 @@ pub(crate) fn fd_allocate_internal(
             Kind::File { handle, .. } => {
                 if let Some(handle) = handle {
                     let mut handle = handle.write().unwrap();
-                    handle.set_len(new_size).map_err(fs_error_into_wasi_err)?;
+                    if new_size > handle.size() {
+                        handle.set_len(new_size).map_err(fs_error_into_wasi_err)?;
+                    }
                 } else {
                     return Err(Errno::Badf);
                 }
             }
             Kind::Buffer { buffer } => {
-                buffer.resize(new_size as usize, 0);
+                if new_size as usize > buffer.len() {
+                    buffer.resize(new_size as usize, 0);
+                }
             }
             Kind::Socket { .. }
             | Kind::PipeRx { .. }
@@ -70,8 +75,9 @@ pub(crate) fn fd_allocate_internal(
             Kind::Dir { .. } | Kind::Root { .. } => return Err(Errno::Isdir),
         }
     }
-    inode.stat.write().unwrap().st_size = new_size;
-    debug!(%new_size);
+    let mut stat = inode.stat.write().unwrap();
+    stat.st_size = stat.st_size.max(new_size);
+    debug!(new_size = stat.st_size);
 
     Ok(())
 }
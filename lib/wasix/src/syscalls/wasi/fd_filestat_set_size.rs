@@ -1,5 +1,5 @@
 use super::*;
-use crate::syscalls::*;
+use crate::{os::credentials::ACCESS_WRITE, syscalls::*};
 
 /// ### `fd_filestat_set_size()`
 /// Change the size of an open file, zeroing out any new bytes
@@ -41,6 +41,7 @@ pub(crate) fn fd_filestat_set_size_internal(
     if !fd_entry.inner.rights.contains(Rights::FD_FILESTAT_SET_SIZE) {
         return Err(Errno::Access);
     }
+    state.fs.check_access(&inode, ACCESS_WRITE)?;
 
     {
         let mut guard = inode.write();
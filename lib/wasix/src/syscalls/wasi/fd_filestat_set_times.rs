@@ -64,6 +64,7 @@ pub(crate) fn fd_filestat_set_times_internal(
     }
 
     let inode = fd_entry.inode;
+    state.fs.check_set_times(&inode, fst_flags)?;
 
     let mut atime = None;
     let mut mtime = None;
@@ -145,6 +145,7 @@ pub(crate) fn fd_read_internal<M: MemorySize>(
         let inode = fd_entry.inode;
         let fd_flags = fd_entry.inner.flags;
 
+        let mut is_socket = false;
         let (bytes_read, can_update_cursor) = {
             let mut guard = inode.write();
             match guard.deref_mut() {
@@ -221,6 +222,7 @@ pub(crate) fn fd_read_internal<M: MemorySize>(
                 }
                 Kind::Socket { socket } => {
                     let socket = socket.clone();
+                    is_socket = true;
 
                     drop(guard);
 
@@ -455,6 +457,12 @@ pub(crate) fn fd_read_internal<M: MemorySize>(
                 .fetch_add(bytes_read as u64, Ordering::AcqRel);
         }
 
+        if is_socket {
+            env.process.usage.add_socket_read(bytes_read);
+        } else {
+            env.process.usage.add_read(bytes_read);
+        }
+
         bytes_read
     };
 
@@ -145,6 +145,33 @@ pub(crate) fn fd_seek_internal(
             fd_entry.offset.store(offset, Ordering::Release);
             offset
         }
+        Whence::Data | Whence::Hole => {
+            // Negative offsets can never contain data (or holes)
+            let offset: u64 = wasi_try_ok_ok!(u64::try_from(offset).map_err(|_| Errno::Nxio));
+
+            let handle = match fd_entry.inode.read().deref() {
+                Kind::File {
+                    handle: Some(handle),
+                    ..
+                } => handle.clone(),
+                Kind::Dir { .. } | Kind::Root { .. } => return Ok(Err(Errno::Isdir)),
+                _ => return Ok(Err(Errno::Inval)),
+            };
+
+            let mut handle = handle.write().unwrap();
+            let found = if whence == Whence::Data {
+                handle.seek_data(offset)
+            } else {
+                handle.seek_hole(offset)
+            };
+            let found = wasi_try_ok_ok!(found.map_err(fs_error_into_wasi_err));
+            drop(handle);
+
+            let mut fd_map = state.fs.fd_map.write().unwrap();
+            let fd_entry = wasi_try_ok_ok!(fd_map.get_mut(fd).ok_or(Errno::Badf));
+            fd_entry.offset.store(found, Ordering::Release);
+            found
+        }
         _ => return Ok(Err(Errno::Inval)),
     };
 
@@ -143,6 +143,7 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
         let fd_flags = fd_entry.inner.flags;
         let mut memory = unsafe { env.memory_view(&ctx) };
 
+        let mut is_socket = false;
         let (bytes_written, is_file, can_snapshot) = {
             let (mut memory, _) = unsafe { env.get_memory_and_wasi_state(&ctx, 0) };
             let mut guard = fd_entry.inode.write();
@@ -225,6 +226,7 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                 }
                 Kind::Socket { socket } => {
                     let socket = socket.clone();
+                    is_socket = true;
                     drop(guard);
 
                     let nonblocking = fd_flags.contains(Fdflags::NONBLOCK);
@@ -539,6 +541,13 @@ pub(crate) fn fd_write_internal<M: MemorySize>(
                 fd_entry.inode.stat.write().unwrap().st_size += bytes_written as u64;
             }
         }
+
+        if is_socket {
+            env.process.usage.add_socket_written(bytes_written);
+        } else {
+            env.process.usage.add_written(bytes_written);
+        }
+
         bytes_written
     };
 
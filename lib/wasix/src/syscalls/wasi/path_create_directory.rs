@@ -100,6 +100,7 @@ pub(crate) fn path_create_directory_internal(
                 return Err(Errno::Exist);
             }
 
+            state.fs.check_dir_write(&parent_inode)?;
             state.fs_create_dir(&new_dir_path)?;
 
             let kind = Kind::Dir {
@@ -110,6 +111,7 @@ pub(crate) fn path_create_directory_internal(
             let new_inode = state
                 .fs
                 .create_inode(inodes, kind, false, dir_name.clone())?;
+            state.fs.take_ownership(&new_inode, 0o755);
 
             // reborrow to insert
             {
@@ -101,6 +101,7 @@ pub(crate) fn path_link_internal(
         state
             .fs
             .get_parent_inode_at_path(inodes, new_fd, &target_path_arg, false)?;
+    state.fs.check_dir_write(&target_parent_inode)?;
 
     if source_inode.stat.write().unwrap().st_nlink == Linkcount::MAX {
         return Err(Errno::Mlink);
@@ -48,6 +48,7 @@ pub(crate) fn path_remove_directory_internal(
         state
             .fs
             .get_parent_inode_at_path(inodes, fd, Path::new(path), true)?;
+    state.fs.check_dir_write(&parent_inode)?;
 
     let mut guard = parent_inode.write();
     match guard.deref_mut() {
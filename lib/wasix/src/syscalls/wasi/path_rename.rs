@@ -94,6 +94,8 @@ pub fn path_rename_internal(
         Path::new(target_path),
         true
     ));
+    wasi_try_ok!(state.fs.check_dir_write(&source_parent_inode));
+    wasi_try_ok!(state.fs.check_dir_write(&target_parent_inode));
     let mut need_create = true;
     let host_adjusted_target_path = {
         let guard = target_parent_inode.read();
@@ -204,6 +206,7 @@ pub fn path_rename_internal(
     }
 
     let source_size = source_entry.stat.read().unwrap().st_size;
+    let source_owner = *source_entry.owner.read().unwrap();
 
     if need_create {
         let mut guard = target_parent_inode.write();
@@ -223,6 +226,7 @@ pub fn path_rename_internal(
         .expect("Expected target inode to exist, and it's too late to safely fail");
     *target_inode.name.write().unwrap() = target_entry_name.into();
     target_inode.stat.write().unwrap().st_size = source_size;
+    *target_inode.owner.write().unwrap() = source_owner;
 
     Ok(Errno::Success)
 }
@@ -83,6 +83,7 @@ pub fn path_symlink_internal(
         state
             .fs
             .get_parent_inode_at_path(inodes, fd, new_path_path, true)?;
+    state.fs.check_dir_write(&target_parent_inode)?;
 
     // short circuit if anything is wrong, before we create an inode
     {
@@ -122,6 +123,7 @@ pub fn path_symlink_internal(
         state
             .fs
             .create_inode_with_default_stat(inodes, kind, false, entry_name.clone().into());
+    state.fs.take_ownership(&new_inode, 0o777);
 
     {
         let mut guard = target_parent_inode.write();
@@ -60,6 +60,7 @@ pub(crate) fn path_unlink_file_internal(
         std::path::Path::new(path),
         false
     ));
+    wasi_try_ok!(state.fs.check_dir_write(&parent_inode));
 
     let removed_inode = {
         let mut guard = parent_inode.write();
@@ -99,7 +100,9 @@ pub(crate) fn path_unlink_file_internal(
                         // drop mutable borrow on `path`
                         let path = path.clone();
                         drop(guard);
-                        wasi_try_ok!(state.fs_remove_file(path));
+                        wasi_try_ok!(state.fs_remove_file(&path));
+                        // Unix sockets bound to the path can no longer be connected to
+                        env.control_plane.unix_sockets().unlink(&path);
                     }
                 }
                 Kind::Dir { .. } | Kind::Root { .. } => return Ok(Errno::Isdir),
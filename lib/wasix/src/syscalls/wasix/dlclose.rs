@@ -0,0 +1,24 @@
+use super::*;
+use crate::{state::DlState, syscalls::*};
+
+/// ### `dlclose()`
+/// Drops a reference to a library that was loaded with `dlopen()`
+///
+/// Once nothing refers to the library its symbols can no longer be looked
+/// up, however the memory and table slots that it used are not reclaimed.
+///
+/// Inputs:
+/// - `Dlhandle handle`
+///     Handle of the library
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn dlclose(ctx: FunctionEnvMut<'_, WasiEnv>, handle: Dlhandle) -> Errno {
+    if handle == DlState::MAIN {
+        return Errno::Success;
+    }
+
+    let mut dl = ctx.data().process.dl.lock().unwrap();
+    if !dl.release(handle) {
+        return Errno::Badf;
+    }
+    Errno::Success
+}
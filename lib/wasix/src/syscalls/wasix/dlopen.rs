@@ -0,0 +1,811 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use virtual_fs::{AsyncReadExt, FileSystem};
+use wasmer::{
+    AsStoreMut, Extern, ExternType, Function, FunctionEnv, Global, Instance, Memory, Module, Pages,
+    RuntimeError, Table, Type, Value, WASM_PAGE_SIZE,
+};
+
+use super::*;
+use crate::{
+    runtime::{package_loader::package_filesystem, resolver::PackageSummary},
+    state::{DlInstances, DlLibrary, DlRelink, DlState, DlSymbol, DylinkInfo},
+    syscalls::*,
+};
+
+/// Directories that are searched for libraries that are named without a
+/// path, after the directories in `LD_LIBRARY_PATH`
+const DEFAULT_LIBRARY_PATH: &[&str] = &["/lib", "/usr/lib", "/usr/local/lib"];
+
+/// ### `dlopen()`
+/// Loads a position independent side module into the running instance
+///
+/// The library shares the memory and `__indirect_function_table` of the
+/// main module, which must export the table. Its data is placed in memory
+/// allocated with the `malloc` export of the main module (or by growing the
+/// memory if there is none) and its functions are placed at the end of the
+/// table. Libraries listed as needed by the library are loaded first.
+///
+/// Names without a `/` are searched for in `LD_LIBRARY_PATH` followed by
+/// `/lib`, `/usr/lib` and `/usr/local/lib`: first in the volumes of the
+/// packages that the program uses, then in the volumes of the packages that
+/// they depend on (which are loaded through the package loader the first
+/// time that a library is looked for in them) and last in the file system.
+///
+/// Symbols are always bound immediately and are visible to every library
+/// loaded later, whatever the flags. The libraries belong to the process:
+/// the other threads link them into their own instances the next time they
+/// call `dlopen()` or `dlsym()`, and threads spawned and processes forked
+/// later link them before they start.
+///
+/// Inputs:
+/// - `const char *path`
+///     Path or name of the library, an empty path returns the handle of
+///     the main module
+/// - `Dlflags flags`
+///     `NOLOAD` only returns a handle if the library is already loaded
+///
+/// Output:
+/// - `Dlhandle handle`
+///     Handle of the library to pass to `dlsym()` and `dlclose()`
+#[instrument(level = "trace", skip_all, fields(path = field::Empty, ?flags, handle = field::Empty), ret)]
+pub fn dlopen<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    flags: Dlflags,
+    ret_handle: WasmPtr<Dlhandle, M>,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let name = get_input_str_ok!(&memory, path, path_len);
+    Span::current().record("path", name.as_str());
+
+    let handle = wasi_try_ok!(dlopen_internal(&mut ctx, &name, flags)?);
+    Span::current().record("handle", handle);
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem_ok!(ret_handle.write(&memory, handle));
+    Ok(Errno::Success)
+}
+
+pub(crate) fn dlopen_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    name: &str,
+    flags: Dlflags,
+) -> WasiResult<Dlhandle> {
+    if name.is_empty() {
+        return Ok(Ok(DlState::MAIN));
+    }
+    let file = wasi_try_ok_ok!(dl_find_library(ctx.data(), name)?);
+    dl_load(ctx, file, flags, &mut Vec::new())
+}
+
+/// The file of a library and the file system that it was found in
+struct DlFile {
+    path: PathBuf,
+    fs: Arc<dyn FileSystem + Send + Sync>,
+}
+
+/// Loads a library and the libraries that it needs, `loading` holds the
+/// libraries that are part way through being loaded so that libraries that
+/// need each other do not load each other forever
+fn dl_load(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    file: DlFile,
+    flags: Dlflags,
+    loading: &mut Vec<PathBuf>,
+) -> WasiResult<Dlhandle> {
+    let DlFile { path, fs } = file;
+    let env = ctx.data();
+    let dl = env.process.dl.clone();
+    {
+        let mut dl = dl.lock().unwrap();
+        if let Some(handle) = dl.find(&path) {
+            dl.retain(handle);
+            return Ok(Ok(handle));
+        }
+    }
+    if flags.contains(Dlflags::NOLOAD) {
+        return Ok(Err(Errno::Noent));
+    }
+
+    let (module, relink) = {
+        let runtime = env.runtime.clone();
+        let path = path.clone();
+        wasi_try_ok_ok!(__asyncify_light(env, None, async move {
+            let mut file = fs
+                .new_open_options()
+                .read(true)
+                .open(&path)
+                .map_err(fs_error_into_wasi_err)?;
+            let mut data = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut data).await.map_err(map_io_err)?;
+            let failed = |err| {
+                tracing::debug!(path = %path.display(), %err, "failed to compile library");
+                Errno::Noexec
+            };
+            let module = runtime.load_module(&data).await.map_err(failed)?;
+
+            let wasm = wasmer::wat2wasm(&data).map_err(|_| Errno::Noexec)?;
+            let relink = DlRelink::new(&module, &wasm).ok_or(Errno::Noexec)?;
+            Ok((module, relink))
+        })?)
+    };
+    let info = wasi_try_ok_ok!(DylinkInfo::from_module(&module).ok_or(Errno::Noexec));
+
+    // The libraries that this one needs must be loaded before it is linked,
+    // apart from the ones that are already being loaded further up, which
+    // this library is linked without
+    loading.push(path.clone());
+    let mut needed = Vec::new();
+    for name in info.needed.iter() {
+        let result = match dl_find_library(ctx.data(), name)? {
+            Ok(needed) if loading.contains(&needed.path) => {
+                tracing::debug!(
+                    path = %path.display(),
+                    needed = %needed.path.display(),
+                    "library needs a library that is still being loaded"
+                );
+                continue;
+            }
+            Ok(needed) => dl_load(ctx, needed, Dlflags::empty(), loading)?,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(handle) => needed.push(handle),
+            Err(err) => {
+                let mut dl = dl.lock().unwrap();
+                for handle in needed {
+                    dl.release(handle);
+                }
+                loading.pop();
+                return Ok(Err(err));
+            }
+        }
+    }
+    loading.pop();
+
+    Ok(dl_link(ctx, path, module, relink, info, needed))
+}
+
+/// Finds the file of a library in the packages in use or on the file system
+fn dl_find_library(env: &WasiEnv, name: &str) -> WasiResult<DlFile> {
+    let current_dir = PathBuf::from(env.state.fs.current_dir.lock().unwrap().as_str());
+    let root_fs: Arc<dyn FileSystem + Send + Sync> = Arc::new(env.state.fs.root_fs.clone());
+    if name.contains('/') {
+        let path = current_dir.join(name);
+        return Ok(Ok(DlFile { path, fs: root_fs }));
+    }
+
+    let library_path = env
+        .state
+        .envs
+        .lock()
+        .unwrap()
+        .iter()
+        .find_map(|env| env.strip_prefix(b"LD_LIBRARY_PATH="))
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .unwrap_or_default();
+    let candidates = library_path
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .chain(DEFAULT_LIBRARY_PATH.iter().copied())
+        .map(|dir| current_dir.join(dir).join(name))
+        .collect::<Vec<_>>();
+    let find = |fs: &Arc<dyn FileSystem + Send + Sync>| {
+        candidates
+            .iter()
+            .find(|path| {
+                fs.metadata(path)
+                    .map(|metadata| metadata.is_file())
+                    .unwrap_or(false)
+            })
+            .map(|path| DlFile {
+                path: path.clone(),
+                fs: fs.clone(),
+            })
+    };
+
+    let packages = env.state.fs.packages.lock().unwrap().clone();
+    if let Some(file) = packages.iter().find_map(|pkg| find(&pkg.webc_fs)) {
+        return Ok(Ok(file));
+    }
+    let mut searched = HashSet::new();
+    for summary in packages.iter().flat_map(|pkg| pkg.dependencies.iter()) {
+        if !searched.insert(summary.package_id()) {
+            continue;
+        }
+        if let Some(file) = dl_package_volumes(env, summary)?.as_ref().and_then(find) {
+            return Ok(Ok(file));
+        }
+    }
+    Ok(find(&root_fs).ok_or(Errno::Noent))
+}
+
+/// Returns the volumes of a package that the packages in use depend on,
+/// loading the package the first time, or `None` if it fails to load
+fn dl_package_volumes(
+    env: &WasiEnv,
+    summary: &PackageSummary,
+) -> Result<Option<Arc<dyn FileSystem + Send + Sync>>, WasiError> {
+    let id = summary.package_id();
+    if let Some(fs) = env.process.dl.lock().unwrap().volumes.get(&id) {
+        return Ok(Some(fs.clone()));
+    }
+
+    let loader = env.runtime.package_loader();
+    let loaded = __asyncify_light(env, None, async {
+        loader.load(summary).await.map_err(|err| {
+            tracing::debug!(package = %id, %err, "failed to load package to look for libraries");
+            Errno::Noent
+        })
+    })?;
+    let Ok(container) = loaded else {
+        return Ok(None);
+    };
+    let fs: Arc<dyn FileSystem + Send + Sync> = match package_filesystem(&container, &summary.pkg) {
+        Ok(fs) => fs.into(),
+        Err(err) => {
+            tracing::debug!(package = %id, %err, "failed to mount the volumes of package");
+            return Ok(None);
+        }
+    };
+    env.process
+        .dl
+        .lock()
+        .unwrap()
+        .volumes
+        .insert(id, fs.clone());
+    Ok(Some(fs))
+}
+
+/// The parts of the instance of a thread that libraries are linked against
+pub(crate) struct DlTarget {
+    pub main: Instance,
+    pub memory: Memory,
+    pub table: Table,
+    pub local: Arc<Mutex<DlInstances>>,
+}
+
+impl DlTarget {
+    pub fn new(env: &WasiEnv) -> Result<Self, Errno> {
+        let inner = env.try_inner().ok_or(Errno::Fault)?;
+        let table = match inner
+            .instance
+            .exports
+            .get_table("__indirect_function_table")
+        {
+            Ok(table) => table.clone(),
+            Err(_) => {
+                tracing::debug!("the main module does not export its function table");
+                return Err(Errno::Notsup);
+            }
+        };
+        Ok(Self {
+            main: inner.instance.clone(),
+            memory: inner.memory_clone(),
+            table,
+            local: inner.dl.clone(),
+        })
+    }
+}
+
+/// Instantiates a side module against the main module and the libraries
+/// that are already loaded, then runs its relocations and constructors
+fn dl_link(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    path: PathBuf,
+    module: Module,
+    relink: DlRelink,
+    info: DylinkInfo,
+    needed: Vec<Dlhandle>,
+) -> Result<Dlhandle, Errno> {
+    let dl = ctx.data().process.dl.clone();
+    let release_needed = |needed: &[Dlhandle]| {
+        let mut dl = dl.lock().unwrap();
+        for handle in needed {
+            dl.release(*handle);
+        }
+    };
+    let target = DlTarget::new(ctx.data()).inspect_err(|_| release_needed(&needed))?;
+
+    let func_env = ctx.as_ref();
+    let mut store = ctx.as_store_mut();
+
+    let memory_base = dl_alloc_memory(&mut store, &target.main, &target.memory, &info)
+        .inspect_err(|_| release_needed(&needed))?;
+
+    // Everything up to the constructors happens under the lock so that the
+    // libraries are linked into every instance in the same order
+    let (handle, instance) = {
+        let mut dl = dl.lock().unwrap();
+        let mut local = target.local.lock().unwrap();
+        let linked = dl_sync(&mut store, &func_env, &mut dl, &mut local, &target)
+            .and_then(|_| dl_alloc_table(&mut store, &mut dl, &target.table, &info))
+            .and_then(|table_base| {
+                let bindings = dl_bind(&dl, &local, &target.main, &path, &module, &info)?;
+                Ok((table_base, bindings))
+            });
+        let (table_base, bindings) = match linked {
+            Ok(linked) => linked,
+            Err(err) => {
+                for handle in needed {
+                    dl.release(handle);
+                }
+                return Err(err);
+            }
+        };
+
+        // Once the library has a handle it is recorded even if it fails to
+        // link, as it may already have added globals to the store
+        let handle = dl.reserve();
+        let mut library = DlLibrary {
+            path: path.clone(),
+            module,
+            relink,
+            info,
+            memory_base,
+            table_base,
+            bindings,
+            needed,
+            refs: 1,
+        };
+        let linked = dl_instantiate(
+            &mut store,
+            &func_env,
+            &dl,
+            &local,
+            &target,
+            &library,
+            &library.module,
+        );
+        let (instance, got) = match linked {
+            Ok(linked) => linked,
+            Err(err) => {
+                local.instances.insert(handle, None);
+                dl.insert(handle, library);
+                dl.release(handle);
+                return Err(err);
+            }
+        };
+
+        // GOT entries that none of the loaded libraries export may well be
+        // exported by the library itself
+        for (_, name, _) in got.iter() {
+            if !library.bindings.contains_key(name) && instance.exports.get_extern(name).is_some() {
+                library.bindings.insert(name.clone(), handle);
+            }
+        }
+        local.instances.insert(handle, Some(instance.clone()));
+        dl.insert(handle, library);
+
+        if let Err(err) = dl_fill_got(&mut store, &mut dl, &mut local, &target, handle, got) {
+            dl.release(handle);
+            return Err(err);
+        }
+        (handle, instance)
+    };
+
+    // Apply the relocations to the data and then run the constructors
+    for init in [
+        "__wasm_apply_data_relocs",
+        "_initialize",
+        "__wasm_call_ctors",
+    ] {
+        if let Ok(func) = instance.exports.get_function(init) {
+            if let Err(err) = func.call(&mut store, &[]) {
+                tracing::debug!(path = %path.display(), %err, "failed to initialize library");
+                dl.lock().unwrap().release(handle);
+                return Err(Errno::Noexec);
+            }
+            // `_initialize` already calls the constructors
+            if init == "_initialize" {
+                break;
+            }
+        }
+    }
+
+    tracing::debug!(path = %path.display(), handle, memory_base, "loaded library");
+    Ok(handle)
+}
+
+/// Links the libraries of the process that have not been linked into the
+/// instance of the calling thread yet
+pub(crate) fn dl_replay(
+    store: &mut impl AsStoreMut,
+    func_env: &FunctionEnv<WasiEnv>,
+) -> Result<(), Errno> {
+    let dl = func_env.as_ref(store).process.dl.clone();
+    let mut dl = dl.lock().unwrap();
+    if dl.is_empty() {
+        return Ok(());
+    }
+    let target = DlTarget::new(func_env.as_ref(store))?;
+    let mut local = target.local.lock().unwrap();
+    dl_sync(store, func_env, &mut dl, &mut local, &target)
+}
+
+/// Brings an instance up to date with the libraries of the process
+///
+/// The libraries are instantiated at the addresses that they were loaded
+/// at. Their data is already in memory (which is either shared with the
+/// thread that loaded them or copied from the parent process) so they are
+/// instantiated from their relink module, whose data segments are passive
+/// and which is compiled the first time that any instance needs it, and
+/// neither the relocations nor the constructors are run again.
+pub(crate) fn dl_sync(
+    store: &mut impl AsStoreMut,
+    func_env: &FunctionEnv<WasiEnv>,
+    dl: &mut DlState,
+    local: &mut DlInstances,
+    target: &DlTarget,
+) -> Result<(), Errno> {
+    let missing = dl
+        .libraries()
+        .filter(|(handle, _)| !local.instances.contains_key(handle))
+        .map(|(handle, library)| (handle, library.clone()))
+        .collect::<Vec<_>>();
+    let runtime = func_env.as_ref(store).runtime.clone();
+    for (handle, library) in missing {
+        let end = library.table_base as u32 + library.info.table_size;
+        dl_grow_table(store, &target.table, end)?;
+
+        let linked = library
+            .relink
+            .module(runtime.as_ref())
+            .map_err(|err| {
+                tracing::debug!(path = %library.path.display(), %err, "failed to compile library");
+                Errno::Noexec
+            })
+            .and_then(|module| {
+                dl_instantiate(store, func_env, dl, local, target, &library, &module)
+            });
+        match linked {
+            Ok((instance, got)) => {
+                local.instances.insert(handle, Some(instance));
+                if library.refs > 0 {
+                    dl_fill_got(store, dl, local, target, handle, got)?;
+                }
+            }
+            // Libraries that failed to link when they were loaded fail the
+            // same way again
+            Err(_) if library.refs == 0 => {
+                local.instances.insert(handle, None);
+            }
+            Err(err) => {
+                tracing::warn!(path = %library.path.display(), %err, "failed to link library");
+                local.instances.insert(handle, None);
+                return Err(err);
+            }
+        }
+    }
+
+    let slots = dl
+        .func_slots
+        .iter()
+        .filter(|(_, slot)| !local.slots.contains(slot))
+        .map(|((owner, name), slot)| (*owner, name.clone(), *slot))
+        .collect::<Vec<_>>();
+    for (owner, name, slot) in slots {
+        if let Some(DlSymbol {
+            export: Extern::Function(func),
+            ..
+        }) = dl.lookup(local, &target.main, owner, &name)
+        {
+            dl_place_func(store, &target.table, slot, func)?;
+        }
+        local.slots.insert(slot);
+    }
+    Ok(())
+}
+
+/// Works out which module each of the symbols that a library imports comes
+/// from, before anything is added to the store
+fn dl_bind(
+    dl: &DlState,
+    local: &DlInstances,
+    main: &Instance,
+    path: &Path,
+    module: &Module,
+    info: &DylinkInfo,
+) -> Result<HashMap<String, Dlhandle>, Errno> {
+    let mut bindings = HashMap::new();
+    for import in module.imports() {
+        let (namespace, name) = (import.module(), import.name());
+        let got = match namespace {
+            "env" => false,
+            "GOT.mem" | "GOT.func" => true,
+            _ => continue,
+        };
+        if !got
+            && matches!(
+                name,
+                "memory" | "__indirect_function_table" | "__memory_base" | "__table_base"
+            )
+        {
+            continue;
+        }
+        match dl.resolve(local, main, name) {
+            Some(symbol) => {
+                bindings.insert(name.to_string(), symbol.owner);
+            }
+            // GOT entries may be resolved by the library itself
+            None if got => {}
+            None if info
+                .weak_imports
+                .contains(&(namespace.to_string(), name.to_string())) => {}
+            None => {
+                tracing::debug!(path = %path.display(), symbol = name, "undefined symbol");
+                return Err(Errno::Noent);
+            }
+        }
+    }
+    Ok(bindings)
+}
+
+/// An entry of the global offset table of a library: whether it holds a
+/// function, the name of the symbol and the global that holds the address
+type DlGotEntry = (bool, String, Global);
+
+/// Creates the imports of a library from its bindings and instantiates
+/// `module`, which is either the module of the library or its relink module
+fn dl_instantiate(
+    store: &mut impl AsStoreMut,
+    func_env: &FunctionEnv<WasiEnv>,
+    dl: &DlState,
+    local: &DlInstances,
+    target: &DlTarget,
+    library: &DlLibrary,
+    module: &Module,
+) -> Result<(Instance, Vec<DlGotEntry>), Errno> {
+    let (info, path) = (&library.info, &library.path);
+    let (mut imports, _) = import_object_for_all_wasi_versions(module, store, func_env);
+    let mut got = Vec::new();
+    for import in module.imports() {
+        let (namespace, name) = (import.module(), import.name());
+        if imports.exists(namespace, name) {
+            continue;
+        }
+        let weak = info
+            .weak_imports
+            .contains(&(namespace.to_string(), name.to_string()));
+
+        let export: Extern = match (namespace, import.ty()) {
+            ("env", ExternType::Memory(_)) if name == "memory" => target.memory.clone().into(),
+            ("env", ExternType::Table(_)) if name == "__indirect_function_table" => {
+                target.table.clone().into()
+            }
+            ("env", ExternType::Global(ty)) if name == "__memory_base" => {
+                Global::new(store, int_value(ty.ty, library.memory_base)).into()
+            }
+            ("env", ExternType::Global(ty)) if name == "__table_base" => {
+                Global::new(store, int_value(ty.ty, library.table_base)).into()
+            }
+            ("GOT.mem", ExternType::Global(ty)) | ("GOT.func", ExternType::Global(ty)) => {
+                // Filled in once the library is instantiated, as the
+                // library may well export the symbol itself
+                let global = Global::new_mut(store, int_value(ty.ty, 0));
+                got.push((namespace == "GOT.func", name.to_string(), global.clone()));
+                global.into()
+            }
+            ("env", ty) => {
+                let symbol = library
+                    .bindings
+                    .get(name)
+                    .and_then(|owner| dl.lookup(local, &target.main, *owner, name));
+                match symbol {
+                    Some(symbol) => symbol.export,
+                    None if weak => match ty {
+                        ExternType::Function(ty) => {
+                            let name = name.to_string();
+                            Function::new(store, ty, move |_| {
+                                Err(RuntimeError::new(format!(
+                                    "called the unresolved weak symbol {name}"
+                                )))
+                            })
+                            .into()
+                        }
+                        ExternType::Global(ty) => Global::new(store, int_value(ty.ty, 0)).into(),
+                        _ => return Err(Errno::Noexec),
+                    },
+                    None => {
+                        tracing::debug!(path = %path.display(), symbol = name, "undefined symbol");
+                        return Err(Errno::Noent);
+                    }
+                }
+            }
+            _ => {
+                tracing::debug!(path = %path.display(), namespace, name, "unsupported import");
+                return Err(Errno::Noexec);
+            }
+        };
+        imports.define(namespace, name, export);
+    }
+
+    let instance = Instance::new(store, module, &imports).map_err(|err| {
+        tracing::debug!(path = %path.display(), %err, "failed to instantiate library");
+        Errno::Noexec
+    })?;
+    Ok((instance, got))
+}
+
+/// Points the GOT entries of a library at the symbols that they refer to
+fn dl_fill_got(
+    store: &mut impl AsStoreMut,
+    dl: &mut DlState,
+    local: &mut DlInstances,
+    target: &DlTarget,
+    handle: Dlhandle,
+    got: Vec<DlGotEntry>,
+) -> Result<(), Errno> {
+    let library = dl.get(handle).ok_or(Errno::Badf)?;
+    let (path, bindings) = (library.path.clone(), library.bindings.clone());
+    let weak_imports = library.info.weak_imports.clone();
+    for (is_func, name, global) in got {
+        let namespace = if is_func { "GOT.func" } else { "GOT.mem" };
+        let symbol = bindings
+            .get(&name)
+            .and_then(|owner| dl.lookup(local, &target.main, *owner, &name));
+        let value = match symbol {
+            Some(symbol) if is_func => {
+                dl_func_slot(store, dl, local, &target.table, &symbol, &name)?
+            }
+            Some(symbol) => dl_global_addr(store, &symbol)?,
+            None if weak_imports.contains(&(namespace.to_string(), name.clone())) => 0,
+            None => {
+                tracing::debug!(path = %path.display(), symbol = name, "undefined symbol");
+                return Err(Errno::Noent);
+            }
+        };
+        let ty = global.ty(store).ty;
+        global
+            .set(store, int_value(ty, value))
+            .map_err(|_| Errno::Noexec)?;
+    }
+    Ok(())
+}
+
+/// Reserves the memory that holds the data of a library
+fn dl_alloc_memory(
+    store: &mut impl AsStoreMut,
+    main: &Instance,
+    memory: &Memory,
+    info: &DylinkInfo,
+) -> Result<u64, Errno> {
+    if info.memory_size == 0 {
+        return Ok(0);
+    }
+    let align = 1u64.checked_shl(info.memory_align).ok_or(Errno::Noexec)?;
+    let size = info.memory_size as u64 + align;
+
+    let base = match main.exports.get_function("malloc") {
+        Ok(malloc) => {
+            let arg = match malloc.ty(store).params() {
+                [Type::I64] => Value::I64(size as i64),
+                _ => Value::I32(size as i32),
+            };
+            match malloc
+                .call(store, &[arg])
+                .map_err(|_| Errno::Nomem)?
+                .first()
+            {
+                Some(Value::I32(ptr)) => *ptr as u32 as u64,
+                Some(Value::I64(ptr)) => *ptr as u64,
+                _ => 0,
+            }
+        }
+        Err(_) => {
+            let pages = size.div_ceil(WASM_PAGE_SIZE as u64);
+            let pages = u32::try_from(pages).map_err(|_| Errno::Nomem)?;
+            let prev = memory.grow(store, Pages(pages)).map_err(|_| Errno::Nomem)?;
+            prev.0 as u64 * WASM_PAGE_SIZE as u64
+        }
+    };
+    if base == 0 {
+        return Err(Errno::Nomem);
+    }
+    let base = base.next_multiple_of(align);
+
+    // Anything that is not covered by a data segment starts out zeroed
+    memory
+        .view(store)
+        .write(base, &vec![0u8; info.memory_size as usize])
+        .map_err(|_| Errno::Nomem)?;
+    Ok(base)
+}
+
+/// Reserves the table slots that hold the functions of a library
+fn dl_alloc_table(
+    store: &mut impl AsStoreMut,
+    dl: &mut DlState,
+    table: &Table,
+    info: &DylinkInfo,
+) -> Result<u64, Errno> {
+    let align = 1u32.checked_shl(info.table_align).ok_or(Errno::Noexec)?;
+    let size = dl.table_size.max(table.size(store));
+    let base = size.checked_next_multiple_of(align).ok_or(Errno::Nomem)?;
+    let end = base.checked_add(info.table_size).ok_or(Errno::Nomem)?;
+    dl_grow_table(store, table, end)?;
+    dl.table_size = end;
+    Ok(base as u64)
+}
+
+/// Grows the function table of an instance to at least `size` slots
+fn dl_grow_table(store: &mut impl AsStoreMut, table: &Table, size: u32) -> Result<(), Errno> {
+    let current = table.size(store);
+    if current < size {
+        table
+            .grow(store, size - current, Value::FuncRef(None))
+            .map_err(|_| Errno::Nomem)?;
+    }
+    Ok(())
+}
+
+/// Puts a function into a slot of the function table of an instance
+fn dl_place_func(
+    store: &mut impl AsStoreMut,
+    table: &Table,
+    slot: u32,
+    func: Function,
+) -> Result<(), Errno> {
+    dl_grow_table(store, table, slot + 1)?;
+    table
+        .set(store, slot, Value::FuncRef(Some(func)))
+        .map_err(|_| Errno::Nomem)
+}
+
+/// Returns the table slot of a function, handing one out the first time
+/// that it is asked for
+pub(crate) fn dl_func_slot(
+    store: &mut impl AsStoreMut,
+    dl: &mut DlState,
+    local: &mut DlInstances,
+    table: &Table,
+    symbol: &DlSymbol,
+    name: &str,
+) -> Result<u64, Errno> {
+    let Extern::Function(func) = &symbol.export else {
+        return Err(Errno::Inval);
+    };
+    let key = (symbol.owner, name.to_string());
+    let slot = match dl.func_slots.get(&key) {
+        Some(slot) => *slot,
+        None => {
+            let slot = dl.table_size.max(table.size(store));
+            dl.table_size = slot + 1;
+            dl.func_slots.insert(key, slot);
+            slot
+        }
+    };
+    if local.slots.insert(slot) {
+        dl_place_func(store, table, slot, func.clone())?;
+    }
+    Ok(slot as u64)
+}
+
+/// Returns the address of a data symbol, which libraries export relative
+/// to their memory base
+pub(crate) fn dl_global_addr(store: &mut impl AsStoreMut, symbol: &DlSymbol) -> Result<u64, Errno> {
+    let Extern::Global(global) = &symbol.export else {
+        return Err(Errno::Inval);
+    };
+    let value = match global.get(store) {
+        Value::I32(value) => value as u32 as u64,
+        Value::I64(value) => value as u64,
+        _ => return Err(Errno::Inval),
+    };
+    Ok(value + symbol.memory_base)
+}
+
+fn int_value(ty: Type, value: u64) -> Value {
+    match ty {
+        Type::I64 => Value::I64(value as i64),
+        _ => Value::I32(value as i32),
+    }
+}
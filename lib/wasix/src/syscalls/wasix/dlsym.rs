@@ -0,0 +1,76 @@
+use wasmer::Extern;
+
+use super::*;
+use crate::{state::DlState, syscalls::*};
+
+/// ### `dlsym()`
+/// Looks up a symbol in a library that was loaded with `dlopen()`
+///
+/// Functions are returned as their index in `__indirect_function_table`,
+/// which is how function pointers are represented, and data symbols are
+/// returned as their address in memory.
+///
+/// Inputs:
+/// - `Dlhandle handle`
+///     Library to search, the handle of the main module searches the main
+///     module followed by every library in the order that they were loaded
+/// - `const char *symbol`
+///     Name of the symbol
+///
+/// Output:
+/// - `void *addr`
+///     Function pointer or address of the symbol
+#[instrument(level = "trace", skip_all, fields(%handle, symbol = field::Empty, addr = field::Empty), ret)]
+pub fn dlsym<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: Dlhandle,
+    symbol: WasmPtr<u8, M>,
+    symbol_len: M::Offset,
+    ret_addr: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let name = get_input_str!(&memory, symbol, symbol_len);
+    Span::current().record("symbol", name.as_str());
+
+    let dl = env.process.dl.clone();
+    let target = wasi_try!(DlTarget::new(env));
+    let func_env = ctx.as_ref();
+
+    let addr = {
+        let mut dl = dl.lock().unwrap();
+        let mut local = target.local.lock().unwrap();
+        let mut store = ctx.as_store_mut();
+        // Libraries loaded by other threads are linked first
+        wasi_try!(dl_sync(&mut store, &func_env, &mut dl, &mut local, &target));
+
+        let symbol = if handle == DlState::MAIN {
+            dl.resolve(&local, &target.main, &name)
+        } else if dl.get(handle).is_some() {
+            dl.resolve_in(&local, handle, &name)
+        } else {
+            return Errno::Badf;
+        };
+        let symbol = wasi_try!(symbol.ok_or(Errno::Noent));
+
+        match &symbol.export {
+            Extern::Function(_) => wasi_try!(dl_func_slot(
+                &mut store,
+                &mut dl,
+                &mut local,
+                &target.table,
+                &symbol,
+                &name
+            )),
+            Extern::Global(_) => wasi_try!(dl_global_addr(&mut store, &symbol)),
+            _ => return Errno::Inval,
+        }
+    };
+    Span::current().record("addr", addr);
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let addr = wasi_try!(to_offset::<M>(addr as usize));
+    wasi_try_mem!(ret_addr.write(&memory, addr));
+    Errno::Success
+}
@@ -0,0 +1,105 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `fd_fallocate()`
+/// Allocate or deallocate space in a file, similar to Linux's `fallocate()`
+/// Inputs:
+/// - `Fd fd`
+///     The file descriptor to operate on
+/// - `Fallocflags flags`
+///     Controls what happens to the range. With no flags set this behaves
+///     like `fd_allocate()`, while `PUNCH_HOLE | KEEP_SIZE` turns the range
+///     into a hole that reads back as zeroes
+/// - `Filesize offset`
+///     The offset from the start marking the beginning of the range
+/// - `Filesize len`
+///     The length of the range
+#[instrument(level = "trace", skip_all, fields(%fd, ?flags, %offset, %len), ret)]
+pub fn fd_fallocate(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: Fallocflags,
+    offset: Filesize,
+    len: Filesize,
+) -> Result<Errno, WasiError> {
+    wasi_try_ok!(fd_fallocate_internal(&mut ctx, fd, flags, offset, len));
+    let env = ctx.data();
+
+    #[cfg(feature = "journal")]
+    if env.enable_journal {
+        JournalEffector::save_fd_fallocate(&mut ctx, fd, flags, offset, len).map_err(|err| {
+            tracing::error!("failed to save file descriptor fallocate event - {}", err);
+            WasiError::Exit(ExitCode::from(Errno::Fault))
+        })?;
+    }
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn fd_fallocate_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: Fallocflags,
+    offset: Filesize,
+    len: Filesize,
+) -> Result<(), Errno> {
+    if len == 0 {
+        return Err(Errno::Inval);
+    }
+    let end = offset.checked_add(len).ok_or(Errno::Inval)?;
+
+    if !flags.contains(Fallocflags::PUNCH_HOLE) {
+        if flags.contains(Fallocflags::KEEP_SIZE) {
+            // Space is never reserved up front, so there is nothing to do
+            // other than checking the file descriptor
+            let env = ctx.data();
+            let fd_entry = env.state.fs.get_fd(fd)?;
+            if !fd_entry.inner.rights.contains(Rights::FD_ALLOCATE) {
+                return Err(Errno::Access);
+            }
+            return Ok(());
+        }
+        return fd_allocate_internal(ctx, fd, offset, len);
+    }
+
+    // Like Linux, punching a hole is only allowed if the size is kept
+    if !flags.contains(Fallocflags::KEEP_SIZE) {
+        return Err(Errno::Inval);
+    }
+
+    let env = ctx.data();
+    let fd_entry = env.state.fs.get_fd(fd)?;
+    let rights = fd_entry.inner.rights;
+    if !rights.contains(Rights::FD_ALLOCATE) || !rights.contains(Rights::FD_WRITE) {
+        return Err(Errno::Access);
+    }
+
+    let mut guard = fd_entry.inode.write();
+    match guard.deref_mut() {
+        Kind::File { handle, .. } => {
+            if let Some(handle) = handle {
+                let mut handle = handle.write().unwrap();
+                handle
+                    .punch_hole(offset, len)
+                    .map_err(fs_error_into_wasi_err)?;
+            } else {
+                return Err(Errno::Badf);
+            }
+        }
+        Kind::Buffer { buffer } => {
+            let start = (offset as usize).min(buffer.len());
+            let end = (end as usize).min(buffer.len());
+            buffer[start..end].fill(0);
+        }
+        Kind::Socket { .. }
+        | Kind::PipeRx { .. }
+        | Kind::PipeTx { .. }
+        | Kind::DuplexPipe { .. }
+        | Kind::Symlink { .. }
+        | Kind::EventNotifications { .. }
+        | Kind::Epoll { .. } => return Err(Errno::Badf),
+        Kind::Dir { .. } | Kind::Root { .. } => return Err(Errno::Isdir),
+    }
+
+    Ok(())
+}
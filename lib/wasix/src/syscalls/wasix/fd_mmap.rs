@@ -0,0 +1,251 @@
+use super::*;
+use crate::syscalls::*;
+#[cfg(all(unix, feature = "sys"))]
+use crate::{
+    fs::{shm_page_size, SharedMemoryObject, ShmFile, SHM_MAX_SIZE},
+    state::WasiMmapAlias,
+};
+
+/// ### `fd_mmap()`
+/// Maps a range of a file into linear memory, similar to `mmap()` with
+/// `MAP_FIXED`
+///
+/// The pages of a shared memory object (see `shm_open()`) are mapped into
+/// linear memory, which needs `addr` and `offset` to be aligned to the
+/// pages of the host and rounds `len` up to a whole page. Only a shared
+/// linear memory is guaranteed to never move when it grows, so mapping the
+/// object into any other memory fails with `ENOTSUP`. A `SHARED`
+/// mapping of an object that was opened for writing sees the changes made
+/// by every process straight away, any other mapping of it is a private
+/// copy-on-write view.
+///
+/// Other files can not share pages with linear memory, so the range is
+/// copied into memory when it is mapped. Changes made to a `SHARED` mapping
+/// that is writable are copied back into the file by `msync()` and
+/// `munmap()` but never beyond the current end of the file. Mappings that
+/// are still in place when the process exits are not written back.
+///
+/// Inputs:
+/// - `Fd fd`
+///     The file descriptor of the file to map
+/// - `void *addr`
+///     Address in linear memory that the range is mapped at
+/// - `size_t len`
+///     Number of bytes to map, bytes past the end of the file read as zero
+/// - `Mmapprot prot`
+///     Access that is permitted to the mapped memory
+/// - `Mmapflags flags`
+///     Exactly one of `SHARED` or `PRIVATE`
+/// - `Filesize offset`
+///     Offset in the file that the range starts at
+#[allow(clippy::await_holding_lock)]
+#[instrument(level = "trace", skip_all, fields(%fd, addr = field::Empty, len = field::Empty, ?prot, ?flags, %offset), ret)]
+pub fn fd_mmap<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    addr: WasmPtr<u8, M>,
+    len: M::Offset,
+    prot: Mmapprot,
+    flags: Mmapflags,
+    offset: Filesize,
+) -> Result<Errno, WasiError> {
+    let addr: u64 = addr.offset().into();
+    let len: u64 = len.into();
+    Span::current().record("addr", addr);
+    Span::current().record("len", len);
+
+    if len == 0 || flags.contains(Mmapflags::SHARED) == flags.contains(Mmapflags::PRIVATE) {
+        return Ok(Errno::Inval);
+    }
+    if offset.checked_add(len).is_none() {
+        return Ok(Errno::Inval);
+    }
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    match addr.checked_add(len) {
+        Some(end) if end <= memory.data_size() => {}
+        _ => return Ok(Errno::Inval),
+    }
+    if env.state.mmaps.lock().unwrap().overlaps(addr, len) {
+        return Ok(Errno::Inval);
+    }
+
+    let fd_entry = wasi_try_ok!(env.state.fs.get_fd(fd));
+    let rights = fd_entry.inner.rights;
+    let writable = rights.contains(Rights::FD_WRITE);
+    if !rights.contains(Rights::FD_READ)
+        || (flags.contains(Mmapflags::SHARED) && prot.contains(Mmapprot::WRITE) && !writable)
+    {
+        return Ok(Errno::Access);
+    }
+
+    let handle = {
+        let guard = fd_entry.inode.read();
+        match guard.deref() {
+            Kind::File {
+                handle: Some(handle),
+                ..
+            } => handle.clone(),
+            Kind::Dir { .. } | Kind::Root { .. } => return Ok(Errno::Isdir),
+            _ => return Ok(Errno::Badf),
+        }
+    };
+
+    #[cfg(all(unix, feature = "sys"))]
+    if let Some(object) = shm_object(&handle) {
+        return Ok(fd_mmap_shm(
+            &ctx, object, handle, addr, len, prot, flags, offset, writable,
+        ));
+    }
+
+    let data = {
+        let handle = handle.clone();
+        wasi_try_ok!(__asyncify_light(env, None, async move {
+            let mut handle = handle.write().map_err(|_| Errno::Fault)?;
+            let mut data = vec![0u8; len as usize];
+            handle
+                .read_at(&mut data, offset)
+                .await
+                .map_err(map_io_err)?;
+            Ok(data)
+        })?)
+    };
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem_ok!(memory.write(addr, &data));
+
+    env.state.mmaps.lock().unwrap().insert(
+        addr,
+        WasiMmap {
+            len,
+            offset,
+            prot,
+            flags,
+            writable,
+            file: handle,
+            #[cfg(all(unix, feature = "sys"))]
+            alias: None,
+        },
+    );
+
+    Ok(Errno::Success)
+}
+
+/// Returns the shared memory object that a file refers to, if it is one
+#[cfg(all(unix, feature = "sys"))]
+fn shm_object(
+    handle: &Arc<std::sync::RwLock<Box<dyn VirtualFile + Send + Sync + 'static>>>,
+) -> Option<Arc<SharedMemoryObject>> {
+    let handle = handle.read().ok()?;
+    (**handle)
+        .upcast_any_ref()
+        .downcast_ref::<ShmFile>()
+        .map(|file| file.object().clone())
+}
+
+/// Maps the pages of a shared memory object into linear memory
+#[cfg(all(unix, feature = "sys"))]
+#[allow(clippy::too_many_arguments)]
+fn fd_mmap_shm(
+    ctx: &FunctionEnvMut<'_, WasiEnv>,
+    object: Arc<SharedMemoryObject>,
+    file: Arc<std::sync::RwLock<Box<dyn VirtualFile + Send + Sync + 'static>>>,
+    addr: u64,
+    len: u64,
+    prot: Mmapprot,
+    flags: Mmapflags,
+    offset: Filesize,
+    writable: bool,
+) -> Errno {
+    let page_size = shm_page_size();
+    if addr % page_size != 0 || offset % page_size != 0 {
+        return Errno::Inval;
+    }
+    let len = len.next_multiple_of(page_size);
+    if offset.saturating_add(len) > SHM_MAX_SIZE {
+        return Errno::Nxio;
+    }
+
+    let env = ctx.data();
+    // A memory that isn't shared is moved when it grows, which would leave
+    // the pages behind at its old address
+    if !unsafe { env.memory() }.ty(ctx).shared {
+        return Errno::Notsup;
+    }
+    let memory = unsafe { env.memory_view(ctx) };
+    if addr + len > memory.data_size() {
+        return Errno::Inval;
+    }
+    let mut mmaps = env.state.mmaps.lock().unwrap();
+    if mmaps.overlaps(addr, len) {
+        return Errno::Inval;
+    }
+
+    // Linear memory can not refuse writes, so a mapping that must not
+    // change the object gets private copies of the pages that are written
+    let shared = flags.contains(Mmapflags::SHARED) && writable;
+    let base = memory.data_ptr();
+    if let Err(err) = unsafe { object.map_into(base.add(addr as usize), offset, len, shared) } {
+        tracing::warn!(%err, "failed to map a shared memory object");
+        return Errno::Nomem;
+    }
+
+    mmaps.insert(
+        addr,
+        WasiMmap {
+            len,
+            offset,
+            prot,
+            flags,
+            writable,
+            file,
+            alias: Some(WasiMmapAlias {
+                object,
+                base: base as usize,
+                shared,
+            }),
+        },
+    );
+    Errno::Success
+}
+
+/// Maps the shared memory objects of the process into a new linear memory
+///
+/// A forked process gets a copy of the memory of its parent, which needs
+/// the `SHARED` mappings to be mapped again. Private mappings were copied
+/// along with the rest of the memory and are left as they are. Threads
+/// share the memory that the objects were mapped into.
+#[cfg(all(unix, feature = "sys"))]
+pub(crate) fn mmap_replay(
+    store: &impl AsStoreRef,
+    func_env: &FunctionEnv<WasiEnv>,
+) -> Result<(), Errno> {
+    let env = func_env.as_ref(store);
+    let base = unsafe { env.memory_view(store) }.data_ptr();
+    let mut mmaps = env.state.mmaps.lock().unwrap();
+    for (start, region) in mmaps.range_mut(0, u64::MAX) {
+        let Some(alias) = region.alias.as_mut() else {
+            continue;
+        };
+        if alias.base == base as usize {
+            continue;
+        }
+        if !alias.shared {
+            region.alias = None;
+            continue;
+        }
+        unsafe {
+            alias
+                .object
+                .map_into(base.add(*start as usize), region.offset, region.len, true)
+        }
+        .map_err(|err| {
+            tracing::warn!(%err, "failed to map a shared memory object");
+            Errno::Nomem
+        })?;
+        alias.base = base as usize;
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use super::*;
+use crate::{os::pty::fd_pty, syscalls::*};
+
+/// ### `fd_tty_get()`
+/// Retrieves the state of the pseudo-terminal that a file descriptor
+/// refers to, similar to `tcgetattr()` and `TIOCGWINSZ`
+///
+/// Inputs:
+/// - `Fd fd`
+///     Either side of the PTY
+///
+/// Output:
+/// - `Tty state`
+///     The window size and line discipline of the PTY, the `stdin_tty`,
+///     `stdout_tty` and `stderr_tty` fields tell if the standard streams of
+///     the caller are attached to the same PTY
+#[instrument(level = "trace", skip_all, fields(%fd), ret)]
+pub fn fd_tty_get<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    tty_state: WasmPtr<Tty, M>,
+) -> Errno {
+    let env = ctx.data();
+    let pty = wasi_try!(fd_pty(&env.state.fs, fd));
+
+    let attached = |stdio: WasiFd| {
+        fd_pty(&env.state.fs, stdio)
+            .map(|other| Arc::ptr_eq(&pty, &other))
+            .unwrap_or(false)
+    };
+    let state = Tty {
+        stdin_tty: attached(0),
+        stdout_tty: attached(1),
+        stderr_tty: attached(2),
+        ..pty.tty()
+    };
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(tty_state.write(&memory, state));
+    Errno::Success
+}
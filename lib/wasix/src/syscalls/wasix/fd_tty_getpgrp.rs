@@ -0,0 +1,29 @@
+use super::*;
+use crate::{os::pty::fd_pty, syscalls::*};
+
+/// ### `fd_tty_getpgrp()`
+/// Returns the process group that is in the foreground of the
+/// pseudo-terminal that a file descriptor refers to, similar to
+/// `tcgetpgrp()`
+///
+/// Until a process group is moved into the foreground the PTY treats the
+/// process group of the caller as the foreground.
+#[instrument(level = "trace", skip_all, fields(%fd, pgid = field::Empty), ret)]
+pub fn fd_tty_getpgrp<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    ret_pgid: WasmPtr<Pid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let pty = wasi_try!(fd_pty(&env.state.fs, fd));
+
+    let pgid = pty
+        .foreground()
+        .pgid()
+        .unwrap_or_else(|| env.process.pgid());
+    Span::current().record("pgid", pgid.raw());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_pgid.write(&memory, pgid.raw() as Pid));
+    Errno::Success
+}
@@ -0,0 +1,32 @@
+use super::*;
+use crate::{os::pty::fd_pty, syscalls::*};
+
+/// ### `fd_tty_set()`
+/// Updates the state of the pseudo-terminal that a file descriptor refers
+/// to, similar to `tcsetattr()` and `TIOCSWINSZ`
+///
+/// Changing the size of the window sends `SIGWINCH` to the process group
+/// in the foreground of the PTY.
+///
+/// Inputs:
+/// - `Fd fd`
+///     Either side of the PTY
+/// - `Tty state`
+///     The new window size and line discipline, the `stdin_tty`,
+///     `stdout_tty` and `stderr_tty` fields are ignored
+#[instrument(level = "trace", skip_all, fields(%fd), ret)]
+pub fn fd_tty_set<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    tty_state: WasmPtr<Tty, M>,
+) -> Errno {
+    let env = ctx.data();
+    let pty = wasi_try!(fd_pty(&env.state.fs, fd));
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    let state = wasi_try_mem!(tty_state.read(&memory));
+    debug!(echo = %state.echo, line_buffered = %state.line_buffered);
+
+    pty.set_tty(&state);
+    Errno::Success
+}
@@ -0,0 +1,34 @@
+use super::*;
+use crate::{os::pty::fd_pty, syscalls::*};
+
+/// ### `fd_tty_setpgrp()`
+/// Moves a process group into the foreground of the pseudo-terminal that a
+/// file descriptor refers to, similar to `tcsetpgrp()`
+///
+/// The signals generated by the keyboard (`SIGINT`, `SIGTSTP` and
+/// `SIGQUIT`), by resizing the window (`SIGWINCH`) and by closing the
+/// master side (`SIGHUP`) are sent to the process group in the foreground.
+///
+/// ## Parameters
+///
+/// * `fd` - Either side of the PTY
+/// * `pgid` - The process group, which must be in the session of the caller
+#[instrument(level = "trace", skip_all, fields(%fd, %pgid), ret)]
+pub fn fd_tty_setpgrp(ctx: FunctionEnvMut<'_, WasiEnv>, fd: WasiFd, pgid: Pid) -> Errno {
+    let env = ctx.data();
+    let pty = wasi_try!(fd_pty(&env.state.fs, fd));
+
+    let pgid: WasiProcessId = pgid.into();
+    let sid = env.process.sid();
+    if !env
+        .control_plane
+        .get_process_group(pgid)
+        .iter()
+        .any(|process| process.sid() == sid)
+    {
+        return Errno::Perm;
+    }
+
+    pty.foreground().set(pgid, env.control_plane.handle());
+    Errno::Success
+}
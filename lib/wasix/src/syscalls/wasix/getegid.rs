@@ -0,0 +1,23 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `getegid()`
+/// Returns the effective group ID of the calling process
+///
+/// Output:
+/// - `Gid egid`
+///     The effective group ID
+#[instrument(level = "trace", skip_all, fields(egid = field::Empty), ret)]
+pub fn getegid<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_egid: WasmPtr<Gid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let egid = env.state.credentials.lock().unwrap().egid;
+    Span::current().record("egid", egid);
+
+    wasi_try_mem!(ret_egid.write(&memory, egid));
+    Errno::Success
+}
@@ -0,0 +1,23 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `geteuid()`
+/// Returns the effective user ID of the calling process
+///
+/// Output:
+/// - `Uid euid`
+///     The effective user ID
+#[instrument(level = "trace", skip_all, fields(euid = field::Empty), ret)]
+pub fn geteuid<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_euid: WasmPtr<Uid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let euid = env.state.credentials.lock().unwrap().euid;
+    Span::current().record("euid", euid);
+
+    wasi_try_mem!(ret_euid.write(&memory, euid));
+    Errno::Success
+}
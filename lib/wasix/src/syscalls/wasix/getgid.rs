@@ -0,0 +1,20 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `getgid()`
+/// Returns the real group ID of the calling process
+///
+/// Output:
+/// - `Gid gid`
+///     The real group ID
+#[instrument(level = "trace", skip_all, fields(gid = field::Empty), ret)]
+pub fn getgid<M: MemorySize>(ctx: FunctionEnvMut<'_, WasiEnv>, ret_gid: WasmPtr<Gid, M>) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let gid = env.state.credentials.lock().unwrap().gid;
+    Span::current().record("gid", gid);
+
+    wasi_try_mem!(ret_gid.write(&memory, gid));
+    Errno::Success
+}
@@ -0,0 +1,39 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `getgroups()`
+/// Returns the supplementary groups of the calling process
+///
+/// Inputs:
+/// - `Gid *groups`
+///     Buffer that receives the groups
+/// - `size_t groups_len`
+///     Number of groups that fit in the buffer
+///
+/// Output:
+/// - `size_t ngroups`
+///     Number of supplementary groups, `EOVERFLOW` is returned when they
+///     do not fit in the buffer
+#[instrument(level = "trace", skip_all, fields(ngroups = field::Empty), ret)]
+pub fn getgroups<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    groups: WasmPtr<Gid, M>,
+    groups_len: M::Offset,
+    ret_ngroups: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let gids = env.state.credentials.lock().unwrap().groups.clone();
+    Span::current().record("ngroups", gids.len());
+
+    let ngroups = wasi_try!(to_offset::<M>(gids.len()));
+    wasi_try_mem!(ret_ngroups.write(&memory, ngroups));
+    if ngroups > groups_len {
+        return Errno::Overflow;
+    }
+
+    let groups = wasi_try_mem!(groups.slice(&memory, ngroups));
+    wasi_try_mem!(groups.write_slice(&gids));
+    Errno::Success
+}
@@ -0,0 +1,36 @@
+use super::*;
+use crate::{os::task::thread::flush_cpu_time, syscalls::*};
+
+/// ### `getrusage()`
+/// Returns the resources that have been used by the calling process
+/// or by the children of the calling process that have been joined
+///
+/// Inputs:
+/// - `Rusagewho who`
+///     Whether to return the usage of the process or of its children
+///
+/// Output:
+/// - `Rusage usage`
+///     Busy time, CPU time, wall time, peak memory and I/O counters
+#[instrument(level = "trace", skip_all, fields(?who), ret)]
+pub fn getrusage<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    who: Rusagewho,
+    ret_usage: WasmPtr<Rusage, M>,
+) -> Errno {
+    let env = ctx.data();
+    env.sample_memory_size(&ctx);
+    // The CPU time of the calling thread is otherwise only recorded once
+    // it stops running
+    flush_cpu_time();
+
+    let usage = match who {
+        Rusagewho::Self_ => env.process.usage(),
+        Rusagewho::Children => env.process.children_usage(),
+        Rusagewho::Unknown => return Errno::Inval,
+    };
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_usage.write(&memory, usage.into()));
+    Errno::Success
+}
@@ -0,0 +1,20 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `getuid()`
+/// Returns the real user ID of the calling process
+///
+/// Output:
+/// - `Uid uid`
+///     The real user ID
+#[instrument(level = "trace", skip_all, fields(uid = field::Empty), ret)]
+pub fn getuid<M: MemorySize>(ctx: FunctionEnvMut<'_, WasiEnv>, ret_uid: WasmPtr<Uid, M>) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let uid = env.state.credentials.lock().unwrap().uid;
+    Span::current().record("uid", uid);
+
+    wasi_try_mem!(ret_uid.write(&memory, uid));
+    Errno::Success
+}
@@ -1,17 +1,44 @@
 mod callback_signal;
 mod chdir;
+mod dlclose;
+mod dlopen;
+mod dlsym;
 mod epoll_create;
 mod epoll_ctl;
 mod epoll_wait;
 mod fd_dup2;
+mod fd_fallocate;
 mod fd_fdflags_get;
 mod fd_fdflags_set;
+mod fd_mmap;
 mod fd_pipe;
+mod fd_tty_get;
+mod fd_tty_getpgrp;
+mod fd_tty_set;
+mod fd_tty_setpgrp;
 mod futex_wait;
 mod futex_wake;
 mod futex_wake_all;
 mod getcwd;
+mod getegid;
+mod geteuid;
+mod getgid;
+mod getgroups;
+mod getrusage;
+mod getuid;
+mod mprotect;
+mod mq_close;
+mod mq_open;
+mod mq_receive;
+mod mq_send;
+mod mq_unlink;
+mod msync;
+mod munmap;
+mod path_getxattr;
+mod path_listxattr;
 mod path_open2;
+mod path_removexattr;
+mod path_setxattr;
 mod port_addr_add;
 mod port_addr_clear;
 mod port_addr_list;
@@ -29,22 +56,48 @@ mod proc_exec;
 mod proc_exec2;
 mod proc_exec3;
 mod proc_fork;
+mod proc_getpgid;
+mod proc_getsid;
 mod proc_id;
 mod proc_join;
+mod proc_join2;
 mod proc_parent;
+mod proc_setpgid;
+mod proc_setsid;
 mod proc_signal;
+mod proc_signal_group;
 mod proc_signals_get;
 mod proc_signals_sizes_get;
 mod proc_snapshot;
 mod proc_spawn;
 mod proc_spawn2;
+mod pty_index;
+mod pty_open;
 mod resolve;
 mod sched_yield;
+mod sem_close;
+mod sem_getvalue;
+mod sem_open;
+mod sem_post;
+mod sem_trywait;
+mod sem_unlink;
+mod sem_wait;
+mod setegid;
+mod seteuid;
+mod setgid;
+mod setgroups;
+mod setuid;
+mod shm_open;
+mod shm_unlink;
 mod sock_accept;
 mod sock_addr_local;
+mod sock_addr_local_unix;
 mod sock_addr_peer;
+mod sock_addr_peer_unix;
 mod sock_bind;
+mod sock_bind_unix;
 mod sock_connect;
+mod sock_connect_unix;
 mod sock_get_opt_flag;
 mod sock_get_opt_size;
 mod sock_get_opt_time;
@@ -75,22 +128,51 @@ mod thread_signal;
 mod thread_sleep;
 mod thread_spawn;
 mod tty_get;
+mod tty_getpgrp;
 mod tty_set;
+mod tty_setpgrp;
 
 pub use callback_signal::*;
 pub use chdir::*;
+pub use dlclose::*;
+pub use dlopen::*;
+pub use dlsym::*;
 pub use epoll_create::*;
 pub use epoll_ctl::*;
 pub use epoll_wait::*;
 pub use fd_dup2::*;
+pub use fd_fallocate::*;
 pub use fd_fdflags_get::*;
 pub use fd_fdflags_set::*;
+pub use fd_mmap::*;
 pub use fd_pipe::*;
+pub use fd_tty_get::*;
+pub use fd_tty_getpgrp::*;
+pub use fd_tty_set::*;
+pub use fd_tty_setpgrp::*;
 pub use futex_wait::*;
 pub use futex_wake::*;
 pub use futex_wake_all::*;
 pub use getcwd::*;
+pub use getegid::*;
+pub use geteuid::*;
+pub use getgid::*;
+pub use getgroups::*;
+pub use getrusage::*;
+pub use getuid::*;
+pub use mprotect::*;
+pub use mq_close::*;
+pub use mq_open::*;
+pub use mq_receive::*;
+pub use mq_send::*;
+pub use mq_unlink::*;
+pub use msync::*;
+pub use munmap::*;
+pub use path_getxattr::*;
+pub use path_listxattr::*;
 pub use path_open2::*;
+pub use path_removexattr::*;
+pub use path_setxattr::*;
 pub use port_addr_add::*;
 pub use port_addr_clear::*;
 pub use port_addr_list::*;
@@ -108,22 +190,48 @@ pub use proc_exec::*;
 pub use proc_exec2::*;
 pub use proc_exec3::*;
 pub use proc_fork::*;
+pub use proc_getpgid::*;
+pub use proc_getsid::*;
 pub use proc_id::*;
 pub use proc_join::*;
+pub use proc_join2::*;
 pub use proc_parent::*;
+pub use proc_setpgid::*;
+pub use proc_setsid::*;
 pub use proc_signal::*;
+pub use proc_signal_group::*;
 pub use proc_signals_get::*;
 pub use proc_signals_sizes_get::*;
 pub use proc_snapshot::*;
 pub use proc_spawn::*;
 pub use proc_spawn2::*;
+pub use pty_index::*;
+pub use pty_open::*;
 pub use resolve::*;
 pub use sched_yield::*;
+pub use sem_close::*;
+pub use sem_getvalue::*;
+pub use sem_open::*;
+pub use sem_post::*;
+pub use sem_trywait::*;
+pub use sem_unlink::*;
+pub use sem_wait::*;
+pub use setegid::*;
+pub use seteuid::*;
+pub use setgid::*;
+pub use setgroups::*;
+pub use setuid::*;
+pub use shm_open::*;
+pub use shm_unlink::*;
 pub use sock_accept::*;
 pub use sock_addr_local::*;
+pub use sock_addr_local_unix::*;
 pub use sock_addr_peer::*;
+pub use sock_addr_peer_unix::*;
 pub use sock_bind::*;
+pub use sock_bind_unix::*;
 pub use sock_connect::*;
+pub use sock_connect_unix::*;
 pub use sock_get_opt_flag::*;
 pub use sock_get_opt_size::*;
 pub use sock_get_opt_time::*;
@@ -154,6 +262,8 @@ pub use thread_signal::*;
 pub use thread_sleep::*;
 pub use thread_spawn::*;
 pub use tty_get::*;
+pub use tty_getpgrp::*;
 pub use tty_set::*;
+pub use tty_setpgrp::*;
 
 use tracing::{debug_span, field, instrument, trace_span, Span};
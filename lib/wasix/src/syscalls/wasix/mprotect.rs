@@ -0,0 +1,50 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `mprotect()`
+/// Changes the access permitted to file mappings within a range of linear
+/// memory
+///
+/// Linear memory has no page protection so the new protection is only
+/// recorded. It decides whether changes to a `SHARED` mapping are written
+/// back to its file.
+///
+/// Inputs:
+/// - `void *addr`
+///     Start of the range to change
+/// - `size_t len`
+///     Length of the range to change
+/// - `Mmapprot prot`
+///     Access that is permitted to the mapped memory
+#[instrument(level = "trace", skip_all, fields(addr = field::Empty, len = field::Empty, ?prot), ret)]
+pub fn mprotect<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    addr: WasmPtr<u8, M>,
+    len: M::Offset,
+    prot: Mmapprot,
+) -> Errno {
+    let addr: u64 = addr.offset().into();
+    let len: u64 = len.into();
+    Span::current().record("addr", addr);
+    Span::current().record("len", len);
+
+    let mut mmaps = ctx.data().state.mmaps.lock().unwrap();
+    if !mmaps.covers(addr, len) {
+        return Errno::Nomem;
+    }
+
+    // Check every region before changing any of them
+    if prot.contains(Mmapprot::WRITE)
+        && mmaps
+            .regions(addr, len)
+            .iter()
+            .any(|(_, region)| region.flags.contains(Mmapflags::SHARED) && !region.writable)
+    {
+        return Errno::Access;
+    }
+
+    for (_, region) in mmaps.range_mut(addr, len) {
+        region.prot = prot;
+    }
+    Errno::Success
+}
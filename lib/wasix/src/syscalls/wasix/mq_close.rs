@@ -0,0 +1,17 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `mq_close()`
+/// Closes a message queue that was opened by this process
+///
+/// Inputs:
+/// - `Mqhandle handle`
+///     Handle of the queue
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn mq_close(ctx: FunctionEnvMut<'_, WasiEnv>, handle: Mqhandle) -> Errno {
+    let env = ctx.data();
+    match env.state.ipc.lock().unwrap().queues.remove(&handle) {
+        Some(_) => Errno::Success,
+        None => Errno::Badf,
+    }
+}
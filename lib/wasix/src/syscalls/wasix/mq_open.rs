@@ -0,0 +1,56 @@
+use super::*;
+use crate::{
+    os::ipc::{IpcOpenOptions, OpenQueue},
+    syscalls::*,
+};
+
+/// ### `mq_open()`
+/// Opens (or creates) a named message queue that is shared by every process
+///
+/// Inputs:
+/// - `const char *name`
+///     Name of the queue, a slash followed by up to 255 other characters
+/// - `Ipcflags flags`
+///     `CREATE`, `EXCL` and `NONBLOCK` are supported
+/// - `u32 max_messages`
+///     Number of messages that the queue can hold when it is created
+/// - `u32 max_message_size`
+///     Largest message that can be sent when the queue is created
+///
+/// Output:
+/// - `Mqhandle handle`
+///     Handle of the queue, which is inherited by forked processes
+#[instrument(level = "trace", skip_all, fields(name = field::Empty, ?flags, %max_messages, %max_message_size, handle = field::Empty), ret)]
+pub fn mq_open<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+    flags: Ipcflags,
+    max_messages: u32,
+    max_message_size: u32,
+    ret_handle: WasmPtr<Mqhandle, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let name = unsafe { get_input_str!(&memory, name, name_len) };
+    Span::current().record("name", name.as_str());
+
+    let options = IpcOpenOptions {
+        create: flags.contains(Ipcflags::CREATE),
+        exclusive: flags.contains(Ipcflags::EXCL),
+    };
+    let queue = wasi_try!(env.control_plane.ipc().mq_open(
+        &name,
+        options,
+        max_messages as usize,
+        max_message_size as usize
+    ));
+    let handle = env.state.ipc.lock().unwrap().insert_queue(OpenQueue {
+        queue,
+        nonblocking: flags.contains(Ipcflags::NONBLOCK),
+    });
+    Span::current().record("handle", handle);
+
+    wasi_try_mem!(ret_handle.write(&memory, handle));
+    Errno::Success
+}
@@ -0,0 +1,77 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `mq_receive()`
+/// Removes the oldest message with the highest priority from a message
+/// queue, waiting for a message unless the queue was opened with `NONBLOCK`
+///
+/// Inputs:
+/// - `Mqhandle handle`
+///     Handle of the queue
+/// - `u8 *buf`
+///     Buffer that receives the message, it must be at least as large as
+///     the largest message of the queue
+/// - `OptionTimestamp timeout`
+///     Relative time after which the receive fails with `ETIMEDOUT`
+///
+/// Output:
+/// - `size_t len`
+///     Length of the message
+/// - `u32 priority`
+///     Priority of the message
+#[instrument(level = "trace", skip_all, fields(%handle, timeout = field::Empty, len = field::Empty), ret)]
+pub fn mq_receive<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: Mqhandle,
+    buf: WasmPtr<u8, M>,
+    buf_len: M::Offset,
+    timeout: WasmPtr<OptionTimestamp, M>,
+    ret_len: WasmPtr<M::Offset, M>,
+    ret_priority: WasmPtr<u32, M>,
+) -> Result<Errno, WasiError> {
+    wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
+
+    let env = ctx.data();
+    let timeout = {
+        let memory = unsafe { env.memory_view(&ctx) };
+        wasi_try_mem_ok!(timeout.read(&memory))
+    };
+    let timeout = match timeout.tag {
+        OptionTag::Some => Some(Duration::from_nanos(timeout.u)),
+        _ => None,
+    };
+    Span::current().record("timeout", format!("{timeout:?}"));
+
+    let open = wasi_try_ok!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .queues
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+    let buf_len: u64 = buf_len.into();
+    if buf_len < open.queue.max_message_size() as u64 {
+        return Ok(Errno::Msgsize);
+    }
+
+    let (data, priority) = if open.nonblocking {
+        wasi_try_ok!(open.queue.try_receive())
+    } else {
+        wasi_try_ok!(__asyncify(&mut ctx, timeout, async move {
+            open.queue.receive().await
+        })?)
+    };
+    Span::current().record("len", data.len());
+
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let len = wasi_try_ok!(to_offset::<M>(data.len()));
+    wasi_try_mem_ok!(buf
+        .slice(&memory, len)
+        .and_then(|buf| buf.write_slice(&data)));
+    wasi_try_mem_ok!(ret_len.write(&memory, len));
+    wasi_try_mem_ok!(ret_priority.write(&memory, priority));
+    Ok(Errno::Success)
+}
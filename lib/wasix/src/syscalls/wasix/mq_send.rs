@@ -0,0 +1,61 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `mq_send()`
+/// Adds a message to a message queue, waiting for room in the queue
+/// unless it was opened with `NONBLOCK`
+///
+/// Inputs:
+/// - `Mqhandle handle`
+///     Handle of the queue
+/// - `const u8 *msg`
+///     Contents of the message
+/// - `u32 priority`
+///     Messages with a higher priority are received first
+/// - `OptionTimestamp timeout`
+///     Relative time after which the send fails with `ETIMEDOUT`
+#[instrument(level = "trace", skip_all, fields(%handle, msg_len = field::Empty, %priority, timeout = field::Empty), ret)]
+pub fn mq_send<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: Mqhandle,
+    msg: WasmPtr<u8, M>,
+    msg_len: M::Offset,
+    priority: u32,
+    timeout: WasmPtr<OptionTimestamp, M>,
+) -> Result<Errno, WasiError> {
+    wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
+
+    let env = ctx.data();
+    let (data, timeout) = {
+        let memory = unsafe { env.memory_view(&ctx) };
+        let data = wasi_try_mem_ok!(msg.slice(&memory, msg_len));
+        let data = wasi_try_mem_ok!(data.read_to_vec());
+        (data, wasi_try_mem_ok!(timeout.read(&memory)))
+    };
+    let timeout = match timeout.tag {
+        OptionTag::Some => Some(Duration::from_nanos(timeout.u)),
+        _ => None,
+    };
+    Span::current()
+        .record("msg_len", data.len())
+        .record("timeout", format!("{timeout:?}"));
+
+    let open = wasi_try_ok!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .queues
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+    if open.nonblocking {
+        wasi_try_ok!(open.queue.try_send(&data, priority));
+        return Ok(Errno::Success);
+    }
+
+    wasi_try_ok!(__asyncify(&mut ctx, timeout, async move {
+        open.queue.send(&data, priority).await
+    })?);
+    Ok(Errno::Success)
+}
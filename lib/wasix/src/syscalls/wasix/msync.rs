@@ -0,0 +1,118 @@
+use virtual_fs::StaticFile;
+
+use super::*;
+use crate::syscalls::*;
+
+/// ### `msync()`
+/// Writes the changes made to file mappings back to their files
+///
+/// Only regions that are both `SHARED` and writable are written back, and
+/// never beyond the current end of the file. Shared memory objects are
+/// never written back as their pages are mapped rather than copied.
+/// `INVALIDATE` is accepted but has no effect as each mapping of any other
+/// file holds its own copy of it.
+///
+/// Inputs:
+/// - `void *addr`
+///     Start of the range to synchronize
+/// - `size_t len`
+///     Length of the range to synchronize
+/// - `Msyncflags flags`
+///     At most one of `ASYNC` or `SYNC`, optionally with `INVALIDATE`
+#[instrument(level = "trace", skip_all, fields(addr = field::Empty, len = field::Empty, ?flags), ret)]
+pub fn msync<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    addr: WasmPtr<u8, M>,
+    len: M::Offset,
+    flags: Msyncflags,
+) -> Result<Errno, WasiError> {
+    let addr: u64 = addr.offset().into();
+    let len: u64 = len.into();
+    Span::current().record("addr", addr);
+    Span::current().record("len", len);
+
+    if flags.contains(Msyncflags::ASYNC | Msyncflags::SYNC) {
+        return Ok(Errno::Inval);
+    }
+
+    let regions = {
+        let mmaps = ctx.data().state.mmaps.lock().unwrap();
+        if !mmaps.covers(addr, len) {
+            return Ok(Errno::Nomem);
+        }
+        mmaps.regions(addr, len)
+    };
+
+    wasi_try_ok!(mmap_writeback(&ctx, regions)?);
+    Ok(Errno::Success)
+}
+
+/// Copies the contents of the shared writable regions out of linear memory
+/// and back into the files they were mapped from
+#[allow(clippy::await_holding_lock)]
+pub(crate) fn mmap_writeback(
+    ctx: &FunctionEnvMut<'_, WasiEnv>,
+    regions: Vec<(u64, WasiMmap)>,
+) -> WasiResult<()> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(ctx) };
+
+    let mut dirty = Vec::new();
+    for (addr, region) in regions {
+        if !region.is_writeback() {
+            continue;
+        }
+        let mut data = vec![0u8; region.len as usize];
+        if let Err(err) = memory.read(addr, &mut data) {
+            return Ok(Err(mem_error_to_wasi(err)));
+        }
+        dirty.push((region, data));
+    }
+    if dirty.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    __asyncify_light(env, None, async move {
+        for (region, mut data) in dirty {
+            let mut handle = region.file.write().map_err(|_| Errno::Fault)?;
+
+            // Writing back never grows the file
+            let size = handle.size();
+            if region.offset >= size {
+                continue;
+            }
+            data.truncate((size - region.offset).min(region.len) as usize);
+
+            if region.offset == 0 && data.len() as u64 == size {
+                // The whole file is replaced, which file systems such as the
+                // memory file system do by taking the buffer over rather than
+                // copying it, everything else copies it in at the start
+                let accessed = handle.last_accessed();
+                let cursor = handle.stream_position().await.map_err(map_io_err)?;
+                handle.rewind().await.map_err(map_io_err)?;
+                let res = handle.copy_reference(Box::new(StaticFile::new(data))).await;
+                handle
+                    .seek(std::io::SeekFrom::Start(cursor))
+                    .await
+                    .map_err(map_io_err)?;
+                res.map_err(map_io_err)?;
+                handle
+                    .set_times(Some(accessed), Some(now()))
+                    .map_err(fs_error_into_wasi_err)?;
+            } else {
+                handle
+                    .write_all_at(&data, region.offset)
+                    .await
+                    .map_err(map_io_err)?;
+            }
+            handle.flush().await.map_err(map_io_err)?;
+        }
+        Ok(())
+    })
+}
+
+fn now() -> u64 {
+    platform_clock_time_get(Snapshot0Clockid::Realtime, 1_000)
+        .map(|time| time as u64)
+        .unwrap_or_default()
+}
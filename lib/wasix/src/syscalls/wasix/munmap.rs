@@ -0,0 +1,83 @@
+use super::*;
+#[cfg(all(unix, feature = "sys"))]
+use crate::fs::{shm_page_size, shm_unmap};
+use crate::syscalls::*;
+
+/// ### `munmap()`
+/// Removes the file mappings within a range of linear memory, writing the
+/// changes made to shared writable mappings back to their files first
+///
+/// Parts of the range that are not mapped are ignored. The pages of shared
+/// memory objects are replaced with zeros, which needs `addr` to be aligned
+/// to the pages of the host (and `len` is rounded up to a whole page), and
+/// the rest of the memory is left as it is.
+///
+/// Inputs:
+/// - `void *addr`
+///     Start of the range to unmap
+/// - `size_t len`
+///     Length of the range to unmap
+#[instrument(level = "trace", skip_all, fields(addr = field::Empty, len = field::Empty), ret)]
+pub fn munmap<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    addr: WasmPtr<u8, M>,
+    len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let addr: u64 = addr.offset().into();
+    let len: u64 = len.into();
+    Span::current().record("addr", addr);
+    Span::current().record("len", len);
+
+    if len == 0 {
+        return Ok(Errno::Inval);
+    }
+
+    let regions = {
+        let mut mmaps = ctx.data().state.mmaps.lock().unwrap();
+        #[allow(unused_mut)]
+        let mut len = len;
+        #[cfg(all(unix, feature = "sys"))]
+        if mmaps
+            .regions(addr, len)
+            .iter()
+            .any(|(_, region)| region.is_alias())
+        {
+            let page_size = shm_page_size();
+            if addr % page_size != 0 {
+                return Ok(Errno::Inval);
+            }
+            len = len.next_multiple_of(page_size);
+        }
+        mmaps.remove_range(addr, len)
+    };
+
+    #[cfg(all(unix, feature = "sys"))]
+    wasi_try_ok!(mmap_unalias(&ctx, &regions));
+    wasi_try_ok!(mmap_writeback(&ctx, regions)?);
+    Ok(Errno::Success)
+}
+
+/// Gives the pages that shared memory objects were mapped into back to
+/// linear memory
+#[cfg(all(unix, feature = "sys"))]
+fn mmap_unalias(
+    ctx: &FunctionEnvMut<'_, WasiEnv>,
+    regions: &[(u64, WasiMmap)],
+) -> Result<(), Errno> {
+    let base = unsafe { ctx.data().memory_view(ctx) }.data_ptr();
+    for (start, region) in regions {
+        // Pages that were mapped into another memory went away with it and
+        // whatever is at their old address now belongs to someone else
+        if region
+            .alias
+            .as_ref()
+            .is_some_and(|alias| alias.base == base as usize)
+        {
+            unsafe { shm_unmap(base.add(*start as usize), region.len) }.map_err(|err| {
+                tracing::warn!(%err, "failed to unmap a shared memory object");
+                Errno::Nomem
+            })?;
+        }
+    }
+    Ok(())
+}
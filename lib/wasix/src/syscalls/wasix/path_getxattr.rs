@@ -0,0 +1,112 @@
+use std::{ffi::OsStr, path::PathBuf};
+
+use super::*;
+use crate::{os::credentials::OWNER_XATTR, syscalls::*};
+
+/// ### `path_getxattr()`
+/// Reads the value of an extended attribute of a file or directory.
+/// If `value_len` is zero then only the size of the value is returned,
+/// if the buffer is too small then this function will return ERANGE
+/// Inputs:
+/// - `Fd fd`
+///     The directory that `path` is relative to
+/// - `LookupFlags flags`
+///     Flags to control how `path` is understood
+/// - `const char *path`
+///     String containing the file path
+/// - `u32 path_len`
+///     The length of the `path` string
+/// - `const char *name`
+///     String containing the name of the attribute
+/// - `u32 name_len`
+///     The length of the `name` string
+/// - `u8 *value`
+///     Buffer the value of the attribute will be written to
+/// - `u32 value_len`
+///     The length of the `value` buffer
+/// Output:
+/// - `u32 *ret_value_len`
+///     The size of the value of the attribute
+///
+/// A missing attribute is reported as `ENOENT`
+#[instrument(level = "trace", skip_all, fields(%fd, path = field::Empty, name = field::Empty), ret)]
+pub fn path_getxattr<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+    value: WasmPtr<u8, M>,
+    value_len: M::Offset,
+    ret_value_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+
+    let path_string = unsafe { get_input_str!(&memory, path, path_len) };
+    Span::current().record("path", path_string.as_str());
+    let name_string = unsafe { get_input_str!(&memory, name, name_len) };
+    Span::current().record("name", name_string.as_str());
+
+    let (inode, target) = wasi_try!(path_xattr_target(
+        state,
+        inodes,
+        fd,
+        flags,
+        &path_string,
+        Rights::PATH_FILESTAT_GET
+    ));
+    let data = if name_string == OWNER_XATTR {
+        wasi_try!(state.fs_get_owner_xattr(&inode))
+    } else {
+        wasi_try!(state
+            .fs
+            .root_fs
+            .get_xattr(&target, OsStr::new(&name_string))
+            .map_err(fs_error_into_wasi_err))
+    };
+
+    wasi_try_mem!(ret_value_len.write(&memory, wasi_try!(to_offset::<M>(data.len()))));
+    let value_len: u64 = value_len.into();
+    if value_len == 0 {
+        return Errno::Success;
+    }
+    if data.len() as u64 > value_len {
+        return Errno::Range;
+    }
+
+    let value = wasi_try_mem!(value.slice(&memory, wasi_try!(to_offset::<M>(data.len()))));
+    wasi_try_mem!(value.write_slice(&data));
+
+    Errno::Success
+}
+
+/// Resolves `path` (relative to `fd`) into the inode and the path of the file
+/// or directory within the root file system that the extended attribute calls
+/// operate on.
+pub(crate) fn path_xattr_target(
+    state: &WasiState,
+    inodes: &crate::WasiInodes,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: &str,
+    rights: Rights,
+) -> Result<(InodeGuard, PathBuf), Errno> {
+    let root_dir = state.fs.get_fd(fd)?;
+    if !root_dir.inner.rights.contains(rights) {
+        return Err(Errno::Access);
+    }
+
+    let inode =
+        state
+            .fs
+            .get_inode_at_path(inodes, fd, path, flags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0)?;
+    let path = match inode.read().deref() {
+        Kind::File { path, .. } | Kind::Dir { path, .. } => path.clone(),
+        Kind::Root { .. } => PathBuf::from("/"),
+        _ => return Err(Errno::Notsup),
+    };
+    Ok((inode, path))
+}
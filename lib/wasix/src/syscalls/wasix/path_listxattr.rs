@@ -0,0 +1,78 @@
+use super::*;
+use crate::{os::credentials::OWNER_XATTR, syscalls::*};
+
+/// ### `path_listxattr()`
+/// Lists the names of the extended attributes of a file or directory.
+/// The names are written one after the other, each terminated by a NUL byte.
+/// If `buf_len` is zero then only the size of the list is returned,
+/// if the buffer is too small then this function will return ERANGE
+/// Inputs:
+/// - `Fd fd`
+///     The directory that `path` is relative to
+/// - `LookupFlags flags`
+///     Flags to control how `path` is understood
+/// - `const char *path`
+///     String containing the file path
+/// - `u32 path_len`
+///     The length of the `path` string
+/// - `u8 *buf`
+///     Buffer the list of names will be written to
+/// - `u32 buf_len`
+///     The length of the `buf` buffer
+/// Output:
+/// - `u32 *ret_buf_len`
+///     The size of the list of names
+#[instrument(level = "trace", skip_all, fields(%fd, path = field::Empty), ret)]
+pub fn path_listxattr<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    buf: WasmPtr<u8, M>,
+    buf_len: M::Offset,
+    ret_buf_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let env = ctx.data();
+    let (memory, mut state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+
+    let path_string = unsafe { get_input_str!(&memory, path, path_len) };
+    Span::current().record("path", path_string.as_str());
+
+    let (inode, target) = wasi_try!(path_xattr_target(
+        state,
+        inodes,
+        fd,
+        flags,
+        &path_string,
+        Rights::PATH_FILESTAT_GET
+    ));
+    let mut names = wasi_try!(state
+        .fs
+        .root_fs
+        .list_xattr(&target)
+        .map_err(fs_error_into_wasi_err));
+    if state.fs.owner_of(&inode).is_some() {
+        names.push(OWNER_XATTR.into());
+    }
+
+    let mut data = Vec::new();
+    for name in names {
+        data.extend_from_slice(name.to_string_lossy().as_bytes());
+        data.push(0);
+    }
+
+    wasi_try_mem!(ret_buf_len.write(&memory, wasi_try!(to_offset::<M>(data.len()))));
+    let buf_len: u64 = buf_len.into();
+    if buf_len == 0 {
+        return Errno::Success;
+    }
+    if data.len() as u64 > buf_len {
+        return Errno::Range;
+    }
+
+    let buf = wasi_try_mem!(buf.slice(&memory, wasi_try!(to_offset::<M>(data.len()))));
+    wasi_try_mem!(buf.write_slice(&data));
+
+    Errno::Success
+}
@@ -1,5 +1,12 @@
 use super::*;
-use crate::syscalls::*;
+use crate::{
+    fs::PTMX,
+    os::{
+        credentials::{ACCESS_READ, ACCESS_WRITE},
+        pty::PtyMaster,
+    },
+    syscalls::*,
+};
 
 /// ### `path_open()`
 /// Open file located at the given path
@@ -125,13 +132,21 @@ pub(crate) fn path_open_internal(
     let state = env.state.deref();
     let inodes = &state.inodes;
 
-    let path_arg = std::path::PathBuf::from(&path);
-    let maybe_inode = state.fs.get_inode_at_path(
+    let mut maybe_inode = state.fs.get_inode_at_path(
         inodes,
         dirfd,
         path,
         dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0,
     );
+    let ptmx_path = maybe_inode
+        .is_err()
+        .then(|| ptmx_alias(state, dirfd, path))
+        .flatten();
+    if let Some(ptmx_path) = &ptmx_path {
+        maybe_inode = state.fs.get_inode_at_path(inodes, dirfd, ptmx_path, true);
+    }
+    let path = ptmx_path.as_deref().unwrap_or(path);
+    let path_arg = std::path::PathBuf::from(&path);
 
     let working_dir = wasi_try_ok_ok!(state.fs.get_fd(dirfd));
     let working_dir_rights_inheriting = working_dir.inner.rights_inheriting;
@@ -202,6 +217,7 @@ pub(crate) fn path_open_internal(
 
     let orig_path = path;
 
+    let mut ptmx = None;
     let inode = if let Ok(inode) = maybe_inode {
         // Happy path, we found the file we're trying to open
         let processing_inode = inode.clone();
@@ -247,11 +263,28 @@ pub(crate) fn path_open_internal(
                 if minimum_rights.truncate {
                     open_flags |= Fd::TRUNCATE;
                 }
-                // TODO: I strongly suspect that assigning the handle unconditionally
-                // breaks opening the same file multiple times.
-                *handle = Some(Arc::new(std::sync::RwLock::new(wasi_try_ok_ok!(
-                    open_options.open(&path).map_err(fs_error_into_wasi_err)
-                ))));
+
+                let mut access = 0;
+                if minimum_rights.read {
+                    access |= ACCESS_READ;
+                }
+                if minimum_rights.write || minimum_rights.truncate {
+                    access |= ACCESS_WRITE;
+                }
+                wasi_try_ok_ok!(state.fs.check_access(&inode, access));
+
+                let file =
+                    wasi_try_ok_ok!(open_options.open(&path).map_err(fs_error_into_wasi_err));
+
+                // Every open of the PTY multiplexer allocates a new terminal,
+                // which gets an inode of its own
+                if (*file).upcast_any_ref().is::<PtyMaster>() {
+                    ptmx = Some((file, path.clone()));
+                } else {
+                    // TODO: I strongly suspect that assigning the handle unconditionally
+                    // breaks opening the same file multiple times.
+                    *handle = Some(Arc::new(std::sync::RwLock::new(file)));
+                }
 
                 if let Some(handle) = handle {
                     let handle = handle.read().unwrap();
@@ -334,6 +367,8 @@ pub(crate) fn path_open_internal(
                     _ => return Ok(Err(Errno::Inval)),
                 }
             };
+            wasi_try_ok_ok!(state.fs.check_dir_write(&parent_inode));
+
             // once we got the data we need from the parent, we lookup the host file
             // todo: extra check that opening with write access is okay
             let handle = {
@@ -383,6 +418,7 @@ pub(crate) fn path_open_internal(
                     .fs
                     .create_inode(inodes, kind, false, new_entity_name.clone()))
             };
+            state.fs.take_ownership(&new_inode, 0o644);
 
             {
                 let mut guard = parent_inode.write();
@@ -399,6 +435,23 @@ pub(crate) fn path_open_internal(
             return Ok(Err(maybe_inode.unwrap_err()));
         }
     };
+    let inode = match ptmx {
+        Some((file, path)) => state.fs.create_inode_with_stat(
+            inodes,
+            Kind::File {
+                handle: Some(Arc::new(std::sync::RwLock::new(file))),
+                path,
+                fd: None,
+            },
+            false,
+            PTMX.into(),
+            Filestat {
+                st_filetype: Filetype::CharacterDevice,
+                ..Filestat::default()
+            },
+        ),
+        None => inode,
+    };
 
     // TODO: check and reduce these
     // TODO: ensure a mutable fd to root can never be opened
@@ -428,3 +481,22 @@ pub(crate) fn path_open_internal(
 
     Ok(Ok(out_fd))
 }
+
+/// Programs look for the PTY multiplexer at `/dev/ptmx`, which is an alias
+/// of the one in the file system that is mounted at `/dev/pts`
+fn ptmx_alias(state: &WasiState, dirfd: WasiFd, path: &str) -> Option<String> {
+    let dir = path.strip_suffix(PTMX)?;
+    if !dir.is_empty() && !dir.ends_with('/') {
+        return None;
+    }
+    let dir_path = if dir.is_empty() { "." } else { dir };
+    let inode = state
+        .fs
+        .get_inode_at_path(&state.inodes, dirfd, dir_path, true)
+        .ok()?;
+    let guard = inode.read();
+    match guard.deref() {
+        Kind::Dir { path, .. } if path == Path::new("/dev") => Some(format!("{dir}pts/{PTMX}")),
+        _ => None,
+    }
+}
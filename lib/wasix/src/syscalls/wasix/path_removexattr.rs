@@ -0,0 +1,95 @@
+use std::ffi::OsString;
+
+use super::*;
+use crate::{
+    os::credentials::{ACCESS_WRITE, OWNER_XATTR},
+    syscalls::*,
+};
+
+/// ### `path_removexattr()`
+/// Removes an extended attribute from a file or directory
+/// Inputs:
+/// - `Fd fd`
+///     The directory that `path` is relative to
+/// - `LookupFlags flags`
+///     Flags to control how `path` is understood
+/// - `const char *path`
+///     String containing the file path
+/// - `u32 path_len`
+///     The length of the `path` string
+/// - `const char *name`
+///     String containing the name of the attribute
+/// - `u32 name_len`
+///     The length of the `name` string
+///
+/// A missing attribute is reported as `ENOENT`
+#[instrument(level = "trace", skip_all, fields(%fd, path = field::Empty, name = field::Empty), ret)]
+pub fn path_removexattr<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let path_string = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path_string.as_str());
+    let name_string = unsafe { get_input_str_ok!(&memory, name, name_len) };
+    Span::current().record("name", name_string.as_str());
+
+    wasi_try_ok!(path_removexattr_internal(
+        &mut ctx,
+        fd,
+        flags,
+        &path_string,
+        &name_string
+    )?);
+
+    #[cfg(feature = "journal")]
+    if ctx.data().enable_journal {
+        JournalEffector::save_path_removexattr(&mut ctx, fd, flags, path_string, name_string)
+            .map_err(|err| {
+                tracing::error!("failed to save path_removexattr event - {}", err);
+                WasiError::Exit(ExitCode::from(Errno::Fault))
+            })?;
+    }
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn path_removexattr_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: &str,
+    name: &str,
+) -> Result<Result<(), Errno>, WasiError> {
+    let env = ctx.data();
+    let (state, inodes) = env.get_wasi_state_and_inodes();
+
+    let (inode, target) = wasi_try_ok_ok!(path_xattr_target(
+        state,
+        inodes,
+        fd,
+        flags,
+        path,
+        Rights::PATH_FILESTAT_SET_TIMES
+    ));
+
+    if name == OWNER_XATTR {
+        return Ok(state.fs_remove_owner_xattr(&inode));
+    }
+    wasi_try_ok_ok!(state.fs.check_access(&inode, ACCESS_WRITE));
+
+    let fs = state.fs.root_fs.clone();
+    let name = OsString::from(name);
+    __asyncify_light(env, None, async move {
+        fs.remove_xattr(&target, &name)
+            .await
+            .map_err(fs_error_into_wasi_err)
+    })
+}
@@ -0,0 +1,130 @@
+use std::ffi::OsString;
+
+use virtual_fs::XattrMode;
+
+use super::*;
+use crate::{
+    os::credentials::{ACCESS_WRITE, OWNER_XATTR},
+    syscalls::*,
+};
+
+/// ### `path_setxattr()`
+/// Sets the value of an extended attribute of a file or directory
+/// Inputs:
+/// - `Fd fd`
+///     The directory that `path` is relative to
+/// - `LookupFlags flags`
+///     Flags to control how `path` is understood
+/// - `const char *path`
+///     String containing the file path
+/// - `u32 path_len`
+///     The length of the `path` string
+/// - `const char *name`
+///     String containing the name of the attribute
+/// - `u32 name_len`
+///     The length of the `name` string
+/// - `const u8 *value`
+///     The new value of the attribute
+/// - `u32 value_len`
+///     The length of the `value` buffer
+/// - `Xattrflags xflags`
+///     Whether the attribute must (or must not) already exist
+#[instrument(level = "trace", skip_all, fields(%fd, path = field::Empty, name = field::Empty, ?xflags), ret)]
+pub fn path_setxattr<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+    value: WasmPtr<u8, M>,
+    value_len: M::Offset,
+    xflags: Xattrflags,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let path_string = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path_string.as_str());
+    let name_string = unsafe { get_input_str_ok!(&memory, name, name_len) };
+    Span::current().record("name", name_string.as_str());
+
+    let value = wasi_try_mem_ok!(value.slice(&memory, value_len));
+    let value = wasi_try_mem_ok!(value.read_to_vec());
+
+    wasi_try_ok!(path_setxattr_internal(
+        &mut ctx,
+        fd,
+        flags,
+        &path_string,
+        &name_string,
+        &value,
+        xflags
+    )?);
+
+    #[cfg(feature = "journal")]
+    if ctx.data().enable_journal {
+        JournalEffector::save_path_setxattr(
+            &mut ctx,
+            fd,
+            flags,
+            path_string,
+            name_string,
+            value,
+            xflags,
+        )
+        .map_err(|err| {
+            tracing::error!("failed to save path_setxattr event - {}", err);
+            WasiError::Exit(ExitCode::from(Errno::Fault))
+        })?;
+    }
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn path_setxattr_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    flags: LookupFlags,
+    path: &str,
+    name: &str,
+    value: &[u8],
+    xflags: Xattrflags,
+) -> Result<Result<(), Errno>, WasiError> {
+    let env = ctx.data();
+    let (state, inodes) = env.get_wasi_state_and_inodes();
+
+    let mode = match (
+        xflags.contains(Xattrflags::CREATE),
+        xflags.contains(Xattrflags::REPLACE),
+    ) {
+        (false, false) => XattrMode::Upsert,
+        (true, false) => XattrMode::Create,
+        (false, true) => XattrMode::Replace,
+        (true, true) => return Ok(Err(Errno::Inval)),
+    };
+
+    let (inode, target) = wasi_try_ok_ok!(path_xattr_target(
+        state,
+        inodes,
+        fd,
+        flags,
+        path,
+        Rights::PATH_FILESTAT_SET_TIMES
+    ));
+
+    if name == OWNER_XATTR {
+        return Ok(state.fs_set_owner_xattr(&inode, value, mode));
+    }
+    wasi_try_ok_ok!(state.fs.check_access(&inode, ACCESS_WRITE));
+
+    let fs = state.fs.root_fs.clone();
+    let name = OsString::from(name);
+    let value = value.to_vec();
+    __asyncify_light(env, None, async move {
+        fs.set_xattr(&target, &name, &value, mode)
+            .await
+            .map_err(fs_error_into_wasi_err)
+    })
+}
@@ -0,0 +1,28 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_getpgid()`
+/// Returns the process group that a process belongs to
+///
+/// ## Parameters
+///
+/// * `pid` - The process to query, zero refers to the calling process
+#[instrument(level = "trace", skip_all, fields(%pid, pgid = field::Empty), ret)]
+pub fn proc_getpgid<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    pid: Pid,
+    ret_pgid: WasmPtr<Pid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let pgid = if pid == 0 {
+        env.process.pgid()
+    } else {
+        let process = wasi_try!(env.control_plane.get_process(pid.into()).ok_or(Errno::Srch));
+        process.pgid()
+    };
+    Span::current().record("pgid", pgid.raw());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_pgid.write(&memory, pgid.raw() as Pid));
+    Errno::Success
+}
@@ -0,0 +1,28 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_getsid()`
+/// Returns the session that a process belongs to
+///
+/// ## Parameters
+///
+/// * `pid` - The process to query, zero refers to the calling process
+#[instrument(level = "trace", skip_all, fields(%pid, sid = field::Empty), ret)]
+pub fn proc_getsid<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    pid: Pid,
+    ret_sid: WasmPtr<Pid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let sid = if pid == 0 {
+        env.process.sid()
+    } else {
+        let process = wasi_try!(env.control_plane.get_process(pid.into()).ok_or(Errno::Srch));
+        process.sid()
+    };
+    Span::current().record("sid", sid.raw());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_sid.write(&memory, sid.raw() as Pid));
+    Errno::Success
+}
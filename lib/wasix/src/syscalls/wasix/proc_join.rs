@@ -5,12 +5,13 @@ use wasmer::FromToNativeWasmType;
 use wasmer_wasix_types::wasi::{JoinFlags, JoinStatus, JoinStatusType, JoinStatusUnion, OptionPid};
 
 use super::*;
-use crate::{syscalls::*, WasiProcess};
+use crate::{os::task::rusage::ResourceUsage, syscalls::*, WasiProcess};
 
 #[derive(Serialize, Deserialize)]
 enum JoinStatusResult {
     Nothing,
-    ExitNormal(WasiProcessId, ExitCode),
+    ExitNormal(WasiProcessId, ExitCode, ResourceUsage),
+    Stopped(WasiProcessId, Signal),
     Err(Errno),
 }
 
@@ -20,6 +21,8 @@ enum JoinStatusResult {
 /// ## Parameters
 ///
 /// * `pid` - Handle of the child process to wait on
+/// * `flags` - With `WAKE_STOPPED` the join also returns when the child is
+///   stopped by a job control signal
 //#[instrument(level = "trace", skip_all, fields(pid = ctx.data().process.pid().raw()), ret)]
 pub fn proc_join<M: MemorySize + 'static>(
     ctx: FunctionEnvMut<'_, WasiEnv>,
@@ -27,7 +30,7 @@ pub fn proc_join<M: MemorySize + 'static>(
     flags: JoinFlags,
     status_ptr: WasmPtr<JoinStatus, M>,
 ) -> Result<Errno, WasiError> {
-    proc_join_internal(ctx, pid_ptr, flags, status_ptr)
+    proc_join_internal(ctx, pid_ptr, flags, status_ptr, None)
 }
 
 pub(super) fn proc_join_internal<M: MemorySize + 'static>(
@@ -35,6 +38,7 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
     pid_ptr: WasmPtr<OptionPid, M>,
     flags: JoinFlags,
     status_ptr: WasmPtr<JoinStatus, M>,
+    usage_ptr: Option<WasmPtr<Rusage, M>>,
 ) -> Result<Errno, WasiError> {
     wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
 
@@ -52,12 +56,15 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
                     tag: JoinStatusType::Nothing,
                     u: JoinStatusUnion { nothing: 0 },
                 },
-                JoinStatusResult::ExitNormal(pid, exit_code) => {
+                JoinStatusResult::ExitNormal(pid, exit_code, usage) => {
                     let option_pid = OptionPid {
                         tag: OptionTag::Some,
                         pid: pid.raw() as Pid,
                     };
                     pid_ptr.write(&view, option_pid).ok();
+                    if let Some(usage_ptr) = usage_ptr {
+                        wasi_try_mem_ok!(usage_ptr.write(&view, usage.into()));
+                    }
 
                     JoinStatus {
                         tag: JoinStatusType::ExitNormal,
@@ -66,6 +73,18 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
                         },
                     }
                 }
+                JoinStatusResult::Stopped(pid, signal) => {
+                    let option_pid = OptionPid {
+                        tag: OptionTag::Some,
+                        pid: pid.raw() as Pid,
+                    };
+                    pid_ptr.write(&view, option_pid).ok();
+
+                    JoinStatus {
+                        tag: JoinStatusType::Stopped,
+                        u: JoinStatusUnion { stopped: signal },
+                    }
+                }
                 JoinStatusResult::Err(err) => {
                     ret = err;
                     JoinStatus {
@@ -111,6 +130,11 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
             u: JoinStatusUnion { nothing: 0 },
         }
     ));
+    if let Some(usage_ptr) = usage_ptr {
+        wasi_try_mem_ok!(usage_ptr.write(&memory, ResourceUsage::default().into()));
+    }
+
+    let wake_stopped = flags.contains(JoinFlags::WAKE_STOPPED);
 
     // If the ID is maximum then it means wait for any of the children
     let pid = match option_pid {
@@ -120,12 +144,24 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
             // We wait for any process to exit (if it takes too long
             // then we go into a deep sleep)
             let res = __asyncify_with_deep_sleep::<M, _, _>(ctx, async move {
-                let child_exit = process.join_any_child().await;
+                let stopped = process.clone();
+                let child_exit = if wake_stopped {
+                    tokio::select! {
+                        biased;
+                        child_exit = process.join_any_child() => child_exit,
+                        (pid, signal) = stopped.join_any_stopped_child() => {
+                            tracing::trace!(%pid, ?signal, "triggered child stop");
+                            return JoinStatusResult::Stopped(pid, signal);
+                        }
+                    }
+                } else {
+                    process.join_any_child().await
+                };
                 match child_exit {
-                    Ok(Some((pid, exit_code))) => {
+                    Ok(Some((pid, exit_code, usage))) => {
                         tracing::trace!(%pid, %exit_code, "triggered child join");
                         trace!(ret_id = pid.raw(), exit_code = exit_code.raw());
-                        JoinStatusResult::ExitNormal(pid, exit_code)
+                        JoinStatusResult::ExitNormal(pid, exit_code, usage)
                     }
                     Ok(None) => {
                         tracing::trace!("triggered child join (no child)");
@@ -148,19 +184,17 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
     // Otherwise we wait for the specific PID
     let pid: WasiProcessId = pid.into();
 
-    // Waiting for a process that is an explicit child will join it
-    // meaning it will no longer be a sub-process of the main process
-    let mut process = {
-        let mut inner = ctx.data().process.lock();
-        let process = inner
-            .children
-            .iter()
-            .filter(|c| c.pid == pid)
-            .map(Clone::clone)
-            .next();
-        inner.children.retain(|c| c.pid != pid);
-        process
-    };
+    // Joining a process that is an explicit child will reap it meaning it
+    // will no longer be a sub-process of the main process (it stays one
+    // until it has actually finished so polls do not lose its usage)
+    let mut process = ctx
+        .data()
+        .process
+        .lock()
+        .children
+        .iter()
+        .find(|c| c.pid == pid)
+        .cloned();
 
     // Otherwise it could be the case that we are waiting for a process
     // that is not a child of this process but may still be running
@@ -180,18 +214,37 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
 
         if flags.contains(JoinFlags::NON_BLOCKING) {
             if let Some(status) = process.try_join() {
+                let usage = reap_child(&ctx.data().process, &process);
                 let exit_code = status.unwrap_or_else(|_| Errno::Child.into());
-                ret_result(ctx, JoinStatusResult::ExitNormal(pid, exit_code))
+                ret_result(ctx, JoinStatusResult::ExitNormal(pid, exit_code, usage))
+            } else if let Some(signal) = wake_stopped.then(|| process.try_join_stopped()).flatten()
+            {
+                ret_result(ctx, JoinStatusResult::Stopped(pid, signal))
             } else {
                 ret_result(ctx, JoinStatusResult::Nothing)
             }
         } else {
             // Wait for the process to finish
             let process2 = process.clone();
+            let parent = ctx.data().process.clone();
             let res = __asyncify_with_deep_sleep::<M, _, _>(ctx, async move {
-                let exit_code = process.join().await.unwrap_or_else(|_| Errno::Child.into());
+                let join = process.join();
+                let exit_code = if wake_stopped {
+                    tokio::select! {
+                        biased;
+                        exit_code = join => exit_code,
+                        signal = process.join_stopped() => {
+                            tracing::trace!(?signal, "triggered child stop");
+                            return JoinStatusResult::Stopped(pid, signal);
+                        }
+                    }
+                } else {
+                    join.await
+                };
+                let exit_code = exit_code.unwrap_or_else(|_| Errno::Child.into());
+                let usage = reap_child(&parent, &process);
                 tracing::trace!(%exit_code, "triggered child join");
-                JoinStatusResult::ExitNormal(pid, exit_code)
+                JoinStatusResult::ExitNormal(pid, exit_code, usage)
             })?;
             match res {
                 AsyncifyAction::Finish(ctx, result) => ret_result(ctx, result),
@@ -203,3 +256,17 @@ pub(super) fn proc_join_internal<M: MemorySize + 'static>(
         ret_result(ctx, JoinStatusResult::Nothing)
     }
 }
+
+/// Removes a child that has finished from its parent and adds its usage to
+/// the usage of the children of the parent (if it was still a child),
+/// returning the usage of the child
+fn reap_child(parent: &WasiProcess, child: &WasiProcess) -> ResourceUsage {
+    let mut inner = parent.lock();
+    let usage = if inner.children.iter().any(|c| c.pid == child.pid) {
+        parent.usage.add_child(&child.usage)
+    } else {
+        child.usage.total_usage()
+    };
+    inner.children.retain(|c| c.pid != child.pid);
+    usage
+}
@@ -0,0 +1,25 @@
+use wasmer_wasix_types::wasi::{JoinFlags, JoinStatus, OptionPid};
+
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_join2()`
+/// Joins the child process, blocking this one until the other finishes,
+/// and returns the resources that the child used (like `wait4`)
+///
+/// ## Parameters
+///
+/// * `pid` - Handle of the child process to wait on
+/// * `flags` - With `WAKE_STOPPED` the join also returns when the child is
+///   stopped by a job control signal
+/// * `usage` - Receives the resource usage of the child and of the children
+///   that it joined (left zeroed unless the child exited)
+pub fn proc_join2<M: MemorySize + 'static>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    pid_ptr: WasmPtr<OptionPid, M>,
+    flags: JoinFlags,
+    status_ptr: WasmPtr<JoinStatus, M>,
+    usage_ptr: WasmPtr<Rusage, M>,
+) -> Result<Errno, WasiError> {
+    proc_join_internal(ctx, pid_ptr, flags, status_ptr, Some(usage_ptr))
+}
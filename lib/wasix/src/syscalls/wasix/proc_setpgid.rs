@@ -0,0 +1,62 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_setpgid()`
+/// Moves a process into a process group, similar to `setpgid()`
+///
+/// ## Parameters
+///
+/// * `pid` - The process to move, zero refers to the calling process
+/// * `pgid` - The process group to move it into, zero refers to a new
+///   process group led by the process being moved
+///
+/// Only the calling process or one of its children can be moved, and only
+/// into a process group within the same session.
+#[instrument(level = "trace", skip_all, fields(%pid, %pgid), ret)]
+pub fn proc_setpgid(ctx: FunctionEnvMut<'_, WasiEnv>, pid: Pid, pgid: Pid) -> Errno {
+    wasi_try!(proc_setpgid_internal(&ctx, pid, pgid));
+    Errno::Success
+}
+
+pub(crate) fn proc_setpgid_internal(
+    ctx: &FunctionEnvMut<'_, WasiEnv>,
+    pid: Pid,
+    pgid: Pid,
+) -> Result<(), Errno> {
+    let env = ctx.data();
+    let caller = &env.process;
+
+    let target = if pid == 0 || pid == caller.pid().raw() {
+        caller.clone()
+    } else {
+        let inner = caller.lock();
+        inner
+            .children
+            .iter()
+            .find(|child| child.pid().raw() == pid)
+            .cloned()
+            .ok_or(Errno::Srch)?
+    };
+    let pgid: WasiProcessId = if pgid == 0 { target.pid() } else { pgid.into() };
+
+    // Session leaders can not leave their own process group and processes
+    // can not be moved between sessions
+    let sid = caller.sid();
+    if target.sid() != sid || target.sid() == target.pid() {
+        return Err(Errno::Perm);
+    }
+
+    // Unless a new group is being created the group must already exist
+    if pgid != target.pid()
+        && !env
+            .control_plane
+            .get_process_group(pgid)
+            .iter()
+            .any(|process| process.sid() == sid)
+    {
+        return Err(Errno::Perm);
+    }
+
+    target.set_pgid(pgid);
+    Ok(())
+}
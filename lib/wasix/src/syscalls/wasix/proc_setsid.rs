@@ -0,0 +1,33 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_setsid()`
+/// Creates a new session with the calling process as its leader, similar
+/// to `setsid()`
+///
+/// The calling process also becomes the leader of a new process group in
+/// the session. This fails if the calling process already leads a process
+/// group.
+#[instrument(level = "trace", skip_all, fields(sid = field::Empty), ret)]
+pub fn proc_setsid<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_sid: WasmPtr<Pid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let process = &env.process;
+    if process.pgid() == process.pid()
+        || !env
+            .control_plane
+            .get_process_group(process.pid())
+            .is_empty()
+    {
+        return Errno::Perm;
+    }
+
+    let sid = process.set_sid();
+    Span::current().record("sid", sid.raw());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_sid.write(&memory, sid.raw() as Pid));
+    Errno::Success
+}
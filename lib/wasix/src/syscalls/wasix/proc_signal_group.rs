@@ -0,0 +1,32 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `proc_signal_group()`
+/// Sends a signal to every process in a process group
+///
+/// ## Parameters
+///
+/// * `pgid` - The process group to signal, zero refers to the process group
+///   of the calling process
+/// * `sig` - Signal to send to the processes
+#[instrument(level = "trace", skip_all, fields(%pgid, ?sig), ret)]
+pub fn proc_signal_group(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    pgid: Pid,
+    sig: Signal,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let pgid: WasiProcessId = if pgid == 0 {
+        env.process.pgid()
+    } else {
+        pgid.into()
+    };
+    if !env.control_plane.signal_process_group(pgid, sig) {
+        return Ok(Errno::Srch);
+    }
+
+    // The calling process may well be in the group that was signalled
+    wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
+
+    Ok(Errno::Success)
+}
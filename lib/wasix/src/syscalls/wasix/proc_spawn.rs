@@ -116,6 +116,8 @@ pub fn proc_spawn_internal(
         let mut child_state = env.state.fork();
         child_state.args = std::sync::Mutex::new(args);
         child_env.state = Arc::new(child_state);
+        child_env.state.fs.set_proc_self(child_process.pid());
+        child_process.set_state(&child_env.state);
     }
 
     // Take ownership of this child
@@ -252,10 +254,7 @@ pub fn proc_spawn_internal(
     };
 
     // Add the process to the environment state
-    {
-        let mut inner = ctx.data().process.lock();
-        inner.children.push(child_process);
-    }
+    ctx.data().process.add_child(child_process);
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
 
@@ -132,10 +132,7 @@ pub fn proc_spawn2<M: MemorySize>(
         }
     };
 
-    {
-        let mut inner = ctx.data().process.lock();
-        inner.children.push(child_env.process.clone());
-    }
+    ctx.data().process.add_child(child_env.process.clone());
 
     // Setup some properties in the child environment
     let pid = child_env.pid();
@@ -0,0 +1,28 @@
+use super::*;
+use crate::{os::pty::fd_pty, syscalls::*};
+
+/// ### `pty_index()`
+/// Returns the number of the pseudo-terminal that a file descriptor refers
+/// to, the slave side of the PTY is `/dev/pts/<index>` (like `ptsname()`)
+///
+/// Inputs:
+/// - `Fd fd`
+///     Either side of the PTY
+///
+/// Output:
+/// - `u32 index`
+///     Number of the PTY
+#[instrument(level = "trace", skip_all, fields(%fd, index = field::Empty), ret)]
+pub fn pty_index<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    fd: WasiFd,
+    ret_index: WasmPtr<u32, M>,
+) -> Errno {
+    let env = ctx.data();
+    let pty = wasi_try!(fd_pty(&env.state.fs, fd));
+    Span::current().record("index", pty.index());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_index.write(&memory, pty.index()));
+    Errno::Success
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use super::*;
+use crate::syscalls::*;
+
+/// ### `pty_open()`
+/// Allocates a new pseudo-terminal and opens both of its sides, similar
+/// to `openpty()`
+///
+/// Output:
+/// - `Fd master`
+///     The side of the PTY that is held by the terminal emulator
+/// - `Fd slave`
+///     The side of the PTY that becomes the terminal of the programs it runs
+#[instrument(level = "trace", skip_all, fields(master = field::Empty, slave = field::Empty), ret)]
+pub fn pty_open<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_master: WasmPtr<WasiFd, M>,
+    ret_slave: WasmPtr<WasiFd, M>,
+) -> Errno {
+    let env = ctx.data();
+    let (memory, state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+
+    let master = wasi_try!(env.control_plane.ptys().allocate());
+    let slave = wasi_try!(master.pty().open_slave().map_err(fs_error_into_wasi_err));
+    let index = master.pty().index();
+
+    let rights = Rights::FD_READ
+        | Rights::FD_WRITE
+        | Rights::FD_SYNC
+        | Rights::FD_DATASYNC
+        | Rights::POLL_FD_READWRITE
+        | Rights::FD_FDSTAT_SET_FLAGS
+        | Rights::FD_FILESTAT_GET;
+
+    let mut open = |file: Box<dyn VirtualFile + Send + Sync + 'static>, path: PathBuf| {
+        let name = path.to_string_lossy().into_owned();
+        let inode = state.fs.create_inode_with_stat(
+            inodes,
+            Kind::File {
+                handle: Some(Arc::new(std::sync::RwLock::new(file))),
+                path,
+                fd: None,
+            },
+            false,
+            name.into(),
+            Filestat {
+                st_filetype: Filetype::CharacterDevice,
+                ..Filestat::default()
+            },
+        );
+        state.fs.create_fd(
+            rights,
+            rights,
+            Fdflags::empty(),
+            Fdflagsext::empty(),
+            Fd::READ | Fd::WRITE,
+            inode,
+        )
+    };
+    let master_fd = wasi_try!(open(Box::new(master), PathBuf::from("/dev/ptmx")));
+    let slave_fd = wasi_try!(open(
+        Box::new(slave),
+        PathBuf::from(format!("/dev/pts/{index}"))
+    ));
+    Span::current()
+        .record("master", master_fd)
+        .record("slave", slave_fd);
+
+    wasi_try_mem!(ret_master.write(&memory, master_fd));
+    wasi_try_mem!(ret_slave.write(&memory, slave_fd));
+    Errno::Success
+}
@@ -0,0 +1,17 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_close()`
+/// Closes a named semaphore that was opened by this process
+///
+/// Inputs:
+/// - `Semhandle handle`
+///     Handle of the semaphore
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn sem_close(ctx: FunctionEnvMut<'_, WasiEnv>, handle: Semhandle) -> Errno {
+    let env = ctx.data();
+    match env.state.ipc.lock().unwrap().semaphores.remove(&handle) {
+        Some(_) => Errno::Success,
+        None => Errno::Badf,
+    }
+}
@@ -0,0 +1,34 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_getvalue()`
+/// Returns the current value of a named semaphore
+///
+/// Inputs:
+/// - `Semhandle handle`
+///     Handle of the semaphore
+///
+/// Output:
+/// - `u32 value`
+///     Value of the semaphore
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn sem_getvalue<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: Semhandle,
+    ret_value: WasmPtr<u32, M>,
+) -> Errno {
+    let env = ctx.data();
+    let sem = wasi_try!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .semaphores
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_value.write(&memory, sem.value()));
+    Errno::Success
+}
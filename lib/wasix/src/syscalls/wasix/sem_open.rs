@@ -0,0 +1,42 @@
+use super::*;
+use crate::{os::ipc::IpcOpenOptions, syscalls::*};
+
+/// ### `sem_open()`
+/// Opens (or creates) a named semaphore that is shared by every process
+///
+/// Inputs:
+/// - `const char *name`
+///     Name of the semaphore, a slash followed by up to 255 other characters
+/// - `Ipcflags flags`
+///     `CREATE` and `EXCL` are supported
+/// - `u32 value`
+///     Initial value of the semaphore when it is created
+///
+/// Output:
+/// - `Semhandle handle`
+///     Handle of the semaphore, which is inherited by forked processes
+#[instrument(level = "trace", skip_all, fields(name = field::Empty, ?flags, %value, handle = field::Empty), ret)]
+pub fn sem_open<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+    flags: Ipcflags,
+    value: u32,
+    ret_handle: WasmPtr<Semhandle, M>,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let name = unsafe { get_input_str!(&memory, name, name_len) };
+    Span::current().record("name", name.as_str());
+
+    let options = IpcOpenOptions {
+        create: flags.contains(Ipcflags::CREATE),
+        exclusive: flags.contains(Ipcflags::EXCL),
+    };
+    let sem = wasi_try!(env.control_plane.ipc().sem_open(&name, options, value));
+    let handle = env.state.ipc.lock().unwrap().insert_semaphore(sem);
+    Span::current().record("handle", handle);
+
+    wasi_try_mem!(ret_handle.write(&memory, handle));
+    Errno::Success
+}
@@ -0,0 +1,24 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_post()`
+/// Increments a named semaphore, waking one of the threads that wait on it
+///
+/// Inputs:
+/// - `Semhandle handle`
+///     Handle of the semaphore
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn sem_post(ctx: FunctionEnvMut<'_, WasiEnv>, handle: Semhandle) -> Errno {
+    let env = ctx.data();
+    let sem = wasi_try!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .semaphores
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+    wasi_try!(sem.post());
+    Errno::Success
+}
@@ -0,0 +1,25 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_trywait()`
+/// Decrements a named semaphore, failing with `EAGAIN` rather than
+/// waiting when its value is zero
+///
+/// Inputs:
+/// - `Semhandle handle`
+///     Handle of the semaphore
+#[instrument(level = "trace", skip_all, fields(%handle), ret)]
+pub fn sem_trywait(ctx: FunctionEnvMut<'_, WasiEnv>, handle: Semhandle) -> Errno {
+    let env = ctx.data();
+    let sem = wasi_try!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .semaphores
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+    wasi_try!(sem.try_wait());
+    Errno::Success
+}
@@ -0,0 +1,24 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_unlink()`
+/// Removes the name of a semaphore, processes that have it open may
+/// continue to use it
+///
+/// Inputs:
+/// - `const char *name`
+///     Name of the semaphore
+#[instrument(level = "trace", skip_all, fields(name = field::Empty), ret)]
+pub fn sem_unlink<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let name = unsafe { get_input_str!(&memory, name, name_len) };
+    Span::current().record("name", name.as_str());
+
+    wasi_try!(env.control_plane.ipc().sem_unlink(&name));
+    Errno::Success
+}
@@ -0,0 +1,50 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sem_wait()`
+/// Waits until a named semaphore can be decremented
+///
+/// Inputs:
+/// - `Semhandle handle`
+///     Handle of the semaphore
+/// - `OptionTimestamp timeout`
+///     Relative time after which the wait fails with `ETIMEDOUT`
+#[instrument(level = "trace", skip_all, fields(%handle, timeout = field::Empty), ret)]
+pub fn sem_wait<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    handle: Semhandle,
+    timeout: WasmPtr<OptionTimestamp, M>,
+) -> Result<Errno, WasiError> {
+    wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
+
+    let env = ctx.data();
+    let timeout = {
+        let memory = unsafe { env.memory_view(&ctx) };
+        wasi_try_mem_ok!(timeout.read(&memory))
+    };
+    let timeout = match timeout.tag {
+        OptionTag::Some => Some(Duration::from_nanos(timeout.u)),
+        _ => None,
+    };
+    Span::current().record("timeout", format!("{timeout:?}"));
+
+    let sem = wasi_try_ok!(env
+        .state
+        .ipc
+        .lock()
+        .unwrap()
+        .semaphores
+        .get(&handle)
+        .cloned()
+        .ok_or(Errno::Badf));
+    if sem.try_wait().is_ok() {
+        return Ok(Errno::Success);
+    }
+
+    wasi_try_ok!(__asyncify(
+        &mut ctx,
+        timeout,
+        async move { sem.wait().await }
+    )?);
+    Ok(Errno::Success)
+}
@@ -0,0 +1,16 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `setegid()`
+/// Sets the effective group ID of the calling process, which must be its
+/// real or saved group ID unless the process is privileged
+///
+/// Inputs:
+/// - `Gid gid`
+///     The new group ID
+#[instrument(level = "trace", skip_all, fields(%gid), ret)]
+pub fn setegid(ctx: FunctionEnvMut<'_, WasiEnv>, gid: Gid) -> Errno {
+    let env = ctx.data();
+    wasi_try!(env.state.credentials.lock().unwrap().set_egid(gid));
+    Errno::Success
+}
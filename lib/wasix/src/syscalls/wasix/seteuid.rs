@@ -0,0 +1,16 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `seteuid()`
+/// Sets the effective user ID of the calling process, which must be its
+/// real or saved user ID unless the process is privileged
+///
+/// Inputs:
+/// - `Uid uid`
+///     The new user ID
+#[instrument(level = "trace", skip_all, fields(%uid), ret)]
+pub fn seteuid(ctx: FunctionEnvMut<'_, WasiEnv>, uid: Uid) -> Errno {
+    let env = ctx.data();
+    wasi_try!(env.state.credentials.lock().unwrap().set_euid(uid));
+    Errno::Success
+}
@@ -0,0 +1,17 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `setgid()`
+/// Sets the group ID of the calling process, a privileged process changes
+/// its real, effective and saved group IDs while others may only change
+/// their effective group ID to their real or saved group ID
+///
+/// Inputs:
+/// - `Gid gid`
+///     The new group ID
+#[instrument(level = "trace", skip_all, fields(%gid), ret)]
+pub fn setgid(ctx: FunctionEnvMut<'_, WasiEnv>, gid: Gid) -> Errno {
+    let env = ctx.data();
+    wasi_try!(env.state.credentials.lock().unwrap().set_gid(gid));
+    Errno::Success
+}
@@ -0,0 +1,28 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `setgroups()`
+/// Replaces the supplementary groups of the calling process, which only
+/// a privileged process may do
+///
+/// Inputs:
+/// - `const Gid *groups`
+///     The new supplementary groups
+/// - `size_t groups_len`
+///     Number of groups
+#[instrument(level = "trace", skip_all, fields(ngroups = field::Empty), ret)]
+pub fn setgroups<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    groups: WasmPtr<Gid, M>,
+    groups_len: M::Offset,
+) -> Errno {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+
+    let groups = wasi_try_mem!(groups.slice(&memory, groups_len));
+    let groups = wasi_try_mem!(groups.read_to_vec());
+    Span::current().record("ngroups", groups.len());
+
+    wasi_try!(env.state.credentials.lock().unwrap().set_groups(groups));
+    Errno::Success
+}
@@ -0,0 +1,17 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `setuid()`
+/// Sets the user ID of the calling process, a privileged process changes
+/// its real, effective and saved user IDs while others may only change
+/// their effective user ID to their real or saved user ID
+///
+/// Inputs:
+/// - `Uid uid`
+///     The new user ID
+#[instrument(level = "trace", skip_all, fields(%uid), ret)]
+pub fn setuid(ctx: FunctionEnvMut<'_, WasiEnv>, uid: Uid) -> Errno {
+    let env = ctx.data();
+    wasi_try!(env.state.credentials.lock().unwrap().set_uid(uid));
+    Errno::Success
+}
@@ -0,0 +1,89 @@
+use super::*;
+use crate::{
+    os::ipc::{IpcOpenOptions, IpcRegistry},
+    syscalls::*,
+};
+
+/// ### `shm_open()`
+/// Opens (or creates) a named shared memory object, which is visible to
+/// every process under `/dev/shm`
+///
+/// The object is a file that can be resized with `fd_filestat_set_size`
+/// and mapped with `fd_mmap`, every `SHARED` mapping of it refers to the
+/// same memory so changes are seen by the other processes straight away.
+/// Objects are only supported on Unix hosts, elsewhere this returns
+/// `Errno::Notsup`.
+///
+/// Inputs:
+/// - `const char *name`
+///     Name of the object, a slash followed by up to 255 other characters
+/// - `Ipcflags flags`
+///     `CREATE`, `EXCL`, `TRUNC` and `RDONLY` are supported
+///
+/// Output:
+/// - `Fd fd`
+///     File descriptor of the object, it is closed by `proc_exec`
+#[instrument(level = "trace", skip_all, fields(name = field::Empty, ?flags, fd = field::Empty), ret)]
+pub fn shm_open<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    name: WasmPtr<u8, M>,
+    name_len: M::Offset,
+    flags: Ipcflags,
+    ret_fd: WasmPtr<WasiFd, M>,
+) -> Errno {
+    let env = ctx.data();
+    let (memory, state, inodes) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
+    let name = unsafe { get_input_str!(&memory, name, name_len) };
+    Span::current().record("name", name.as_str());
+
+    let path = wasi_try!(IpcRegistry::shm_path(&name));
+    let writable = !flags.contains(Ipcflags::RDONLY);
+    if flags.contains(Ipcflags::TRUNC) && !writable {
+        return Errno::Access;
+    }
+
+    let options = IpcOpenOptions {
+        create: flags.contains(Ipcflags::CREATE),
+        exclusive: flags.contains(Ipcflags::EXCL),
+    };
+    let handle = wasi_try!(env.control_plane.ipc().shm_open(
+        &name,
+        options,
+        writable,
+        flags.contains(Ipcflags::TRUNC)
+    ));
+
+    let mut rights = Rights::FD_READ
+        | Rights::FD_SEEK
+        | Rights::FD_TELL
+        | Rights::FD_FILESTAT_GET
+        | Rights::POLL_FD_READWRITE;
+    if writable {
+        rights |=
+            Rights::FD_WRITE | Rights::FD_FILESTAT_SET_SIZE | Rights::FD_SYNC | Rights::FD_DATASYNC;
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let inode = state.fs.create_inode_with_default_stat(
+        inodes,
+        Kind::File {
+            handle: Some(Arc::new(std::sync::RwLock::new(handle))),
+            path: Path::new("/dev/shm").join(file_name.as_ref()),
+            fd: None,
+        },
+        false,
+        file_name.into_owned().into(),
+    );
+    let fd = wasi_try!(state.fs.create_fd(
+        rights,
+        rights,
+        Fdflags::empty(),
+        Fdflagsext::CLOEXEC,
+        0,
+        inode,
+    ));
+    Span::current().record("fd", fd);
+
+    wasi_try_mem!(ret_fd.write(&memory, fd));
+    Errno::Success
+}
@@ -67,6 +67,13 @@ pub fn sock_accept_v2<M: MemorySize>(
 ) -> Result<Errno, WasiError> {
     wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
 
+    let is_unix = wasi_try_ok!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| { Ok(socket.is_unix()) }
+    ));
+
     let env = ctx.data();
     let (memory, state, _) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
 
@@ -100,12 +107,15 @@ pub fn sock_accept_v2<M: MemorySize>(
     let env = ctx.data();
     let (memory, state, _) = unsafe { env.get_memory_and_wasi_state_and_inodes(&ctx, 0) };
     wasi_try_mem_ok!(ro_fd.write(&memory, fd));
-    wasi_try_ok!(crate::net::write_ip_port(
-        &memory,
-        ro_addr,
-        peer_addr.ip(),
-        peer_addr.port()
-    ));
+    match is_unix {
+        true => wasi_try_ok!(crate::net::write_unix_addr_port(&memory, ro_addr)),
+        false => wasi_try_ok!(crate::net::write_ip_port(
+            &memory,
+            ro_addr,
+            peer_addr.ip(),
+            peer_addr.port()
+        )),
+    }
 
     Ok(Errno::Success)
 }
@@ -121,6 +131,7 @@ pub(crate) fn sock_accept_internal(
     let inodes = &state.inodes;
 
     let tasks = env.tasks().clone();
+    let unix = env.control_plane.unix_sockets().clone();
     let (child, local_addr, peer_addr, fd_flags) = wasi_try_ok_ok!(__sock_asyncify(
         env,
         sock,
@@ -136,20 +147,15 @@ pub(crate) fn sock_accept_internal(
                 .flatten()
                 .unwrap_or(Duration::from_secs(30));
             let local_addr = socket.addr_local()?;
-            socket
+            let (child, peer_addr) = socket
                 .accept(tasks.deref(), nonblocking, Some(timeout))
-                .await
-                .map(|a| (a.0, local_addr, a.1, fd_flags))
+                .await?;
+            let child = socket.accepted(child, peer_addr, &unix);
+            Ok((child, local_addr, peer_addr, fd_flags))
         },
     ));
 
-    let kind = Kind::Socket {
-        socket: InodeSocket::new(InodeSocketKind::TcpStream {
-            socket: child,
-            write_timeout: None,
-            read_timeout: None,
-        }),
-    };
+    let kind = Kind::Socket { socket: child };
     let inode = state
         .fs
         .create_inode_with_default_stat(inodes, kind, false, "socket".into());
@@ -0,0 +1,60 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_addr_local_unix()`
+/// Returns the path that a unix domain socket is bound to.
+///
+/// Note: This is similar to `getsockname` in POSIX using PF_UNIX
+///
+/// The length of the path is always written to `path_len`, unnamed sockets
+/// have an empty path. If the buffer is too small `Errno::Range` is returned.
+///
+/// ## Parameters
+///
+/// * `fd` - Socket that the path is bound to
+/// * `path` - Buffer that receives the path
+/// * `path_len` - Size of the buffer, the length of the path is written back
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_addr_local_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let unix_path = wasi_try!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| socket.unix_path_local()
+    ));
+    let unix_path = unix_path
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Span::current().record("path", unix_path.as_str());
+
+    let memory = unsafe { ctx.data().memory_view(&ctx) };
+    write_unix_path(&memory, &unix_path, path, path_len)
+}
+
+/// Writes the path of a unix socket into a buffer of the guest.
+pub(crate) fn write_unix_path<M: MemorySize>(
+    memory: &MemoryView,
+    unix_path: &str,
+    path: WasmPtr<u8, M>,
+    path_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let max_path_len: u64 = wasi_try_mem!(path_len.read(memory)).into();
+    let unix_path = unix_path.as_bytes();
+    wasi_try_mem!(path_len.write(memory, wasi_try!(to_offset::<M>(unix_path.len()))));
+    if unix_path.len() as u64 > max_path_len {
+        return Errno::Range;
+    }
+    if unix_path.is_empty() {
+        return Errno::Success;
+    }
+
+    let len = wasi_try!(to_offset::<M>(unix_path.len()));
+    let path_slice = wasi_try_mem!(path.slice(memory, len));
+    wasi_try_mem!(path_slice.write_slice(unix_path));
+    Errno::Success
+}
@@ -7,7 +7,8 @@ use crate::syscalls::*;
 /// Note: This is similar to `getpeername` in POSIX
 ///
 /// When successful, the contents of the output buffer consist of an IP address,
-/// either IP4 or IP6.
+/// either IP4 or IP6. Unix sockets only report their address family, the
+/// path is returned by `sock_addr_peer_unix`.
 ///
 /// ## Parameters
 ///
@@ -22,17 +23,23 @@ pub fn sock_addr_peer<M: MemorySize>(
         &mut ctx,
         sock,
         Rights::empty(),
-        |socket, _| socket.addr_peer()
+        |socket, _| match socket.is_unix() {
+            true => Ok(None),
+            false => socket.addr_peer().map(Some),
+        }
     ));
     Span::current().record("addr", format!("{addr:?}"));
 
     let env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
-    wasi_try!(crate::net::write_ip_port(
-        &memory,
-        ro_addr,
-        addr.ip(),
-        addr.port()
-    ));
+    match addr {
+        Some(addr) => wasi_try!(crate::net::write_ip_port(
+            &memory,
+            ro_addr,
+            addr.ip(),
+            addr.port()
+        )),
+        None => wasi_try!(crate::net::write_unix_addr_port(&memory, ro_addr)),
+    }
     Errno::Success
 }
@@ -0,0 +1,38 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_addr_peer_unix()`
+/// Returns the path of the socket that a unix domain socket is connected to.
+///
+/// Note: This is similar to `getpeername` in POSIX using PF_UNIX
+///
+/// The length of the path is always written to `path_len`, peers that are
+/// not bound to a path have an empty path. If the buffer is too small
+/// `Errno::Range` is returned.
+///
+/// ## Parameters
+///
+/// * `fd` - Socket that is connected to the peer
+/// * `path` - Buffer that receives the path
+/// * `path_len` - Size of the buffer, the length of the path is written back
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_addr_peer_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: WasmPtr<M::Offset, M>,
+) -> Errno {
+    let unix_path = wasi_try!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| socket.unix_path_peer()
+    ));
+    let unix_path = unix_path
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Span::current().record("path", unix_path.as_str());
+
+    let memory = unsafe { ctx.data().memory_view(&ctx) };
+    write_unix_path(&memory, &unix_path, path, path_len)
+}
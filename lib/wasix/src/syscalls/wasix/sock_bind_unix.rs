@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use super::*;
+use crate::{fs::VIRTUAL_ROOT_FD, net::socket::WasiSocketStatus, syscalls::*};
+
+/// ### `sock_bind_unix()`
+/// Bind a unix domain socket to a path
+/// Note: This is similar to `bind` in POSIX using PF_UNIX
+///
+/// The path is created in the file system as a socket entry, which other
+/// processes use to connect to this socket. Removing the entry again
+/// (`unlink`) makes the socket unreachable.
+///
+/// ## Parameters
+///
+/// * `fd` - File descriptor of the socket to be bind
+/// * `path` - Path that the socket will be bound to
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_bind_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path.as_str());
+
+    wasi_try_ok!(sock_bind_unix_internal(&mut ctx, sock, &path)?);
+
+    #[cfg(feature = "journal")]
+    if ctx.data().enable_journal {
+        JournalEffector::save_sock_bind_unix(&mut ctx, sock, path).map_err(|err| {
+            tracing::error!("failed to save sock_bind_unix event - {}", err);
+            WasiError::Exit(ExitCode::from(Errno::Fault))
+        })?;
+    }
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn sock_bind_unix_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: &str,
+) -> Result<Result<(), Errno>, WasiError> {
+    let env = ctx.data();
+    let state = env.state();
+    let unix = env.control_plane.unix_sockets().clone();
+
+    let fd_entry = wasi_try_ok_ok!(state.fs.get_fd(sock));
+    if !fd_entry.inner.rights.contains(Rights::SOCK_BIND) {
+        return Ok(Err(Errno::Access));
+    }
+    match fd_entry.inode.read().deref() {
+        Kind::Socket { socket } => {
+            if !matches!(socket.status(), Ok(WasiSocketStatus::Opening)) {
+                return Ok(Err(Errno::Inval));
+            }
+        }
+        _ => return Ok(Err(Errno::Notsock)),
+    }
+
+    let path = wasi_try_ok_ok!(unix_socket_path(state, path));
+    wasi_try_ok_ok!(state
+        .fs
+        .root_fs
+        .create_socket(&path)
+        .map_err(|err| match err {
+            FsError::AlreadyExists => Errno::Addrinuse,
+            err => fs_error_into_wasi_err(err),
+        }));
+
+    let bind_path = path.clone();
+    let res = __sock_upgrade(ctx, sock, Rights::SOCK_BIND, move |socket, _| async move {
+        socket.bind_unix(&unix, bind_path)
+    });
+    if let Err(err) = res {
+        // The socket entry is only useful if the bind worked
+        ctx.data().state().fs.root_fs.remove_file(&path).ok();
+        return Ok(Err(err));
+    }
+
+    Ok(Ok(()))
+}
+
+/// Resolves the path of a unix socket into the path of its entry in the
+/// file system. Relative paths are resolved against the current directory.
+pub(crate) fn unix_socket_path(state: &WasiState, path: &str) -> Result<PathBuf, Errno> {
+    let path = state.fs.relative_path_to_absolute(path.to_string());
+    let (parent_inode, name) = state.fs.get_parent_inode_at_path(
+        &state.inodes,
+        VIRTUAL_ROOT_FD,
+        Path::new(&path),
+        true,
+    )?;
+
+    let guard = parent_inode.read();
+    match guard.deref() {
+        Kind::Dir { path, .. } => Ok(path.join(name)),
+        Kind::Root { .. } => Ok(Path::new("/").join(name)),
+        _ => Err(Errno::Notdir),
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `sock_connect_unix()`
+/// Connect a unix domain socket to the socket bound to a path
+/// Note: This is similar to `connect` in POSIX using PF_UNIX
+///
+/// Stream sockets are connected to the listener of another process (or
+/// this one) while datagram sockets will send all their packets to the
+/// socket bound to the path.
+///
+/// ## Parameters
+///
+/// * `fd` - Socket descriptor
+/// * `path` - Path of the socket to connect to
+#[instrument(level = "trace", skip_all, fields(%sock, path = field::Empty), ret)]
+pub fn sock_connect_unix<M: MemorySize>(
+    mut ctx: FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: WasmPtr<u8, M>,
+    path_len: M::Offset,
+) -> Result<Errno, WasiError> {
+    let env = ctx.data();
+    let memory = unsafe { env.memory_view(&ctx) };
+    let path = unsafe { get_input_str_ok!(&memory, path, path_len) };
+    Span::current().record("path", path.as_str());
+
+    wasi_try_ok!(sock_connect_unix_internal(&mut ctx, sock, &path)?);
+
+    #[cfg(feature = "journal")]
+    if ctx.data().enable_journal {
+        JournalEffector::save_sock_connect_unix(&mut ctx, sock, path).map_err(|err| {
+            tracing::error!("failed to save sock_connect_unix event - {}", err);
+            WasiError::Exit(ExitCode::from(Errno::Fault))
+        })?;
+    }
+
+    Ok(Errno::Success)
+}
+
+pub(crate) fn sock_connect_unix_internal(
+    ctx: &mut FunctionEnvMut<'_, WasiEnv>,
+    sock: WasiFd,
+    path: &str,
+) -> Result<Result<(), Errno>, WasiError> {
+    let env = ctx.data();
+    let state = env.state();
+    let unix = env.control_plane.unix_sockets().clone();
+
+    // Only paths that are still socket entries can be connected to
+    let path = wasi_try_ok_ok!(unix_socket_path(state, path));
+    let metadata = wasi_try_ok_ok!(state
+        .fs
+        .root_fs
+        .metadata(&path)
+        .map_err(fs_error_into_wasi_err));
+    if !metadata.file_type().is_socket() {
+        return Ok(Err(Errno::Connrefused));
+    }
+
+    wasi_try_ok_ok!(__sock_upgrade(
+        ctx,
+        sock,
+        Rights::SOCK_CONNECT,
+        move |mut socket, _| async move { socket.connect_unix(&unix, &path) }
+    ));
+
+    Ok(Ok(()))
+}
@@ -44,12 +44,17 @@ pub(crate) fn sock_listen_internal(
 ) -> Result<Result<(), Errno>, WasiError> {
     let env = ctx.data();
     let net = env.net().clone();
+    let unix = env.control_plane.unix_sockets().clone();
     let tasks = ctx.data().tasks().clone();
     wasi_try_ok_ok!(__sock_upgrade(
         ctx,
         sock,
         Rights::SOCK_LISTEN,
-        |socket, _| async move { socket.listen(tasks.deref(), net.deref(), backlog).await }
+        |socket, _| async move {
+            socket
+                .listen(tasks.deref(), net.deref(), unix.deref(), backlog)
+                .await
+        }
     ));
 
     Ok(Ok(()))
@@ -161,5 +161,6 @@ pub(super) fn sock_recv_internal<M: MemorySize>(
             Ok(total_read)
         }
     ));
+    ctx.data().process.usage.add_socket_read(data);
     Ok(Ok(data))
 }
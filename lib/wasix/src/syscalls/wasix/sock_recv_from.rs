@@ -51,6 +51,13 @@ pub(super) fn sock_recv_from_internal<M: MemorySize>(
 ) -> Result<Errno, WasiError> {
     wasi_try_ok!(WasiEnv::process_signals_and_exit(&mut ctx)?);
 
+    let is_unix = wasi_try_ok!(__sock_actor(
+        &mut ctx,
+        sock,
+        Rights::empty(),
+        |socket, _| { Ok(socket.is_unix()) }
+    ));
+
     let mut env = ctx.data();
     let memory = unsafe { env.memory_view(&ctx) };
     let iovs_arr = wasi_try_mem_ok!(ri_data.slice(&memory, ri_data_len));
@@ -135,8 +142,12 @@ pub(super) fn sock_recv_from_internal<M: MemorySize>(
     Span::current()
         .record("nread", bytes_read)
         .record("peer", format!("{peer:?}"));
+    env.process.usage.add_socket_read(bytes_read);
 
-    wasi_try_ok!(write_ip_port(&memory, ro_addr, peer.ip(), peer.port()));
+    match is_unix {
+        true => wasi_try_ok!(write_unix_addr_port(&memory, ro_addr)),
+        false => wasi_try_ok!(write_ip_port(&memory, ro_addr, peer.ip(), peer.port())),
+    }
 
     let bytes_read: M::Offset = wasi_try_ok!(bytes_read.try_into().map_err(|_| Errno::Overflow));
     wasi_try_mem_ok!(ro_flags.write(&memory, 0));
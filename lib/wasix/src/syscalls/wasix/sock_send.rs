@@ -157,6 +157,7 @@ pub(crate) fn sock_send_internal<M: MemorySize>(
     trace!(
         %bytes_written,
     );
+    ctx.data().process.usage.add_socket_written(bytes_written);
 
     Ok(Ok(bytes_written))
 }
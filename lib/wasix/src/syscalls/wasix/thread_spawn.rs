@@ -5,7 +5,7 @@ use super::*;
 use crate::journal::JournalEffector;
 use crate::{
     capture_store_snapshot,
-    os::task::thread::WasiMemoryLayout,
+    os::task::thread::{CurrentThreadGuard, WasiMemoryLayout},
     runtime::{
         task_manager::{TaskWasm, TaskWasmRunProperties},
         TaintReason,
@@ -185,6 +185,7 @@ fn call_module<M: MemorySize>(
 ) -> Result<Tid, Errno> {
     let env = ctx.data(&store);
     let tasks = env.tasks().clone();
+    let _current = CurrentThreadGuard::enter(env.pid(), env.tid(), &env.process.usage);
 
     // This function calls into the module
     let call_module_internal = move |env: &WasiFunctionEnv, store: &mut Store| {
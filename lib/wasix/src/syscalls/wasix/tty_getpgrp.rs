@@ -0,0 +1,29 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `tty_getpgrp()`
+/// Returns the process group that is in the foreground of the TTY, similar
+/// to `tcgetpgrp()`
+///
+/// Until a process group is moved into the foreground the TTY treats the
+/// process group of the caller as the foreground.
+#[instrument(level = "trace", skip_all, fields(pgid = field::Empty), ret)]
+pub fn tty_getpgrp<M: MemorySize>(
+    ctx: FunctionEnvMut<'_, WasiEnv>,
+    ret_pgid: WasmPtr<Pid, M>,
+) -> Errno {
+    let env = ctx.data();
+    let bridge = if let Some(t) = env.runtime.tty() {
+        t
+    } else {
+        return Errno::Notsup;
+    };
+    let foreground = wasi_try!(bridge.foreground().ok_or(Errno::Notty));
+
+    let pgid = foreground.pgid().unwrap_or_else(|| env.process.pgid());
+    Span::current().record("pgid", pgid.raw());
+
+    let memory = unsafe { env.memory_view(&ctx) };
+    wasi_try_mem!(ret_pgid.write(&memory, pgid.raw() as Pid));
+    Errno::Success
+}
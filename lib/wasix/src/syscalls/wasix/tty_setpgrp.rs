@@ -0,0 +1,37 @@
+use super::*;
+use crate::syscalls::*;
+
+/// ### `tty_setpgrp()`
+/// Moves a process group into the foreground of the TTY, similar to
+/// `tcsetpgrp()`
+///
+/// The signals generated by the keyboard (`SIGINT`, `SIGTSTP` and
+/// `SIGQUIT`) are sent to the process group in the foreground.
+///
+/// ## Parameters
+///
+/// * `pgid` - The process group, which must be in the session of the caller
+#[instrument(level = "trace", skip_all, fields(%pgid), ret)]
+pub fn tty_setpgrp(ctx: FunctionEnvMut<'_, WasiEnv>, pgid: Pid) -> Errno {
+    let env = ctx.data();
+    let bridge = if let Some(t) = env.runtime.tty() {
+        t
+    } else {
+        return Errno::Notsup;
+    };
+    let foreground = wasi_try!(bridge.foreground().ok_or(Errno::Notty));
+
+    let pgid: WasiProcessId = pgid.into();
+    let sid = env.process.sid();
+    if !env
+        .control_plane
+        .get_process_group(pgid)
+        .iter()
+        .any(|process| process.sid() == sid)
+    {
+        return Errno::Perm;
+    }
+
+    foreground.set(pgid, env.control_plane.handle());
+    Errno::Success
+}
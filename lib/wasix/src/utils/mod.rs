@@ -16,9 +16,12 @@ use wasmer_types::ModuleHash;
 
 use std::collections::BTreeSet;
 
+use virtual_fs::FsError;
 use wasmer::Module;
 use wasmer_wasix_types::wasi::Errno;
 
+use crate::fs::fs_error_into_wasi_err;
+
 /// Creates a random xxhash for the module
 pub fn xxhash_random() -> ModuleHash {
     let mut rand = rand::thread_rng();
@@ -45,6 +48,14 @@ pub fn is_wasix_module(module: &Module) -> bool {
 }
 
 pub fn map_io_err(err: std::io::Error) -> Errno {
+    // Errors raised by a virtual file system can carry more detail than the
+    // io::ErrorKind they were converted into (e.g. quota errors)
+    if let Some(fs_error) = err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<FsError>())
+    {
+        return fs_error_into_wasi_err(*fs_error);
+    }
     From::<std::io::Error>::from(err)
 }
 
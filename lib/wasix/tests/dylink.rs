@@ -0,0 +1,343 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use once_cell::sync::OnceCell;
+use tokio::runtime::Handle;
+use virtual_fs::{AsyncWriteExt, FileSystem};
+use wasmer::{Engine, Module, Store};
+use wasmer_config::package::PackageId;
+use wasmer_wasix::{
+    bin_factory::BinaryPackage,
+    runtime::{
+        package_loader::PackageLoader,
+        resolver::{DistributionInfo, PackageInfo, PackageSummary, Resolution, WebcHash},
+        task_manager::tokio::TokioTaskManager,
+    },
+    PluggableRuntime, WasiEnv,
+};
+use webc::Container;
+
+/// Adds two numbers, and exports a number and the address of `add` as
+/// seen through its own GOT entry
+const LIBADD: &str = r#"
+(module
+    (@custom "dylink.0" "\01\04\04\02\00\00")
+    (import "env" "memory" (memory 1 100 shared))
+    (import "env" "__memory_base" (global $memory_base i32))
+    (import "GOT.func" "add" (global $add_ptr (mut i32)))
+    (func $add (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1)))
+    (func (export "add_ptr") (result i32)
+        (global.get $add_ptr))
+    (global (export "answer") i32 (i32.const 0))
+    (data (global.get $memory_base) "\2a\00\00\00")
+)
+"#;
+
+/// Needs `libb.so` and calls into it
+const LIBA: &str = r#"
+(module
+    (@custom "dylink.0" "\02\09\01\07libb.so")
+    (import "env" "b" (func $b (result i32)))
+    (func (export "a") (result i32)
+        (i32.add (call $b) (i32.const 1)))
+)
+"#;
+
+/// Needs `liba.so` back
+const LIBB: &str = r#"
+(module
+    (@custom "dylink.0" "\02\09\01\07liba.so")
+    (func (export "b") (result i32)
+        (i32.const 7))
+)
+"#;
+
+/// Exits with a different code for every check that fails
+const MAIN: &str = r#"
+(module
+    (type $binop (func (param i32 i32) (result i32)))
+    (type $nullary (func (result i32)))
+    (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "dlsym" (func $dlsym (param i32 i32 i32 i32) (result i32)))
+    (import "wasi" "thread-spawn" (func $thread_spawn (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (import "env" "memory" (memory 1 100 shared))
+    (table (export "__indirect_function_table") 1 funcref)
+
+    (data (i32.const 16) "/lib/libadd.so")
+    (data (i32.const 32) "add")
+    (data (i32.const 40) "add_ptr")
+    (data (i32.const 48) "answer")
+    (data (i32.const 64) "liba.so")
+    (data (i32.const 80) "a")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    (func (export "_start")
+        (local $add i32)
+        (call $check
+            (i32.eqz (call $dlopen (i32.const 16) (i32.const 14) (i32.const 0) (i32.const 200)))
+            (i32.const 1))
+        (call $check
+            (i32.eqz (call $dlsym (i32.load (i32.const 200)) (i32.const 32) (i32.const 3) (i32.const 204)))
+            (i32.const 2))
+        (local.set $add (i32.load (i32.const 204)))
+        (call $check
+            (i32.eq (call_indirect (type $binop) (i32.const 2) (i32.const 3) (local.get $add)) (i32.const 5))
+            (i32.const 3))
+
+        ;; The GOT entry of the library points at the same slot
+        (call $check
+            (i32.eqz (call $dlsym (i32.load (i32.const 200)) (i32.const 40) (i32.const 7) (i32.const 208)))
+            (i32.const 4))
+        (call $check
+            (i32.eq (call_indirect (type $nullary) (i32.load (i32.const 208))) (local.get $add))
+            (i32.const 5))
+
+        ;; Data symbols are addresses
+        (call $check
+            (i32.eqz (call $dlsym (i32.load (i32.const 200)) (i32.const 48) (i32.const 6) (i32.const 212)))
+            (i32.const 6))
+        (call $check
+            (i32.eq (i32.load (i32.load (i32.const 212))) (i32.const 42))
+            (i32.const 7))
+
+        ;; `liba.so` is found in /lib and needs `libb.so`, which needs it back
+        (call $check
+            (i32.eqz (call $dlopen (i32.const 64) (i32.const 7) (i32.const 0) (i32.const 216)))
+            (i32.const 8))
+        (call $check
+            (i32.eqz (call $dlsym (i32.load (i32.const 216)) (i32.const 80) (i32.const 1) (i32.const 220)))
+            (i32.const 9))
+        (call $check
+            (i32.eq (call_indirect (type $nullary) (i32.load (i32.const 220))) (i32.const 8))
+            (i32.const 10))
+
+        ;; A thread spawned now calls into the library from its own instance,
+        ;; which leaves the data of the library as it is
+        (i32.store (i32.load (i32.const 212)) (i32.const 43))
+        (i32.store (i32.const 256) (i32.const 0x8000))
+        (i32.store (i32.const 312) (i32.const 0x1000))
+        (call $check
+            (i32.gt_s (call $thread_spawn (i32.const 256)) (i32.const 0))
+            (i32.const 11))
+        (block $done
+            (loop $wait
+                (br_if $done (i32.atomic.load (i32.const 400)))
+                (drop (memory.atomic.wait32 (i32.const 400) (i32.const 0) (i64.const -1)))
+                (br $wait)))
+        (call $check
+            (i32.eq (i32.load (i32.const 404)) (i32.const 42))
+            (i32.const 12))
+        (call $check
+            (i32.eq (i32.load (i32.const 408)) (i32.const 43))
+            (i32.const 13))
+    )
+
+    (func (export "wasi_thread_start") (param i32 i32)
+        (i32.store (i32.const 404)
+            (call_indirect (type $binop) (i32.const 20) (i32.const 22) (i32.load (i32.const 204))))
+        (i32.store (i32.const 408) (i32.load (i32.load (i32.const 212))))
+        (i32.atomic.store (i32.const 400) (i32.const 1))
+        (drop (memory.atomic.notify (i32.const 400) (i32.const 1))))
+)
+"#;
+
+#[tokio::test]
+async fn test_dlopen_and_dlsym() {
+    let fs = virtual_fs::mem_fs::FileSystem::default();
+    fs.create_dir(Path::new("/lib")).unwrap();
+    for (path, wat) in [
+        ("/lib/libadd.so", LIBADD),
+        ("/lib/liba.so", LIBA),
+        ("/lib/libb.so", LIBB),
+    ] {
+        let mut file = fs
+            .new_open_options()
+            .write(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(wat.as_bytes()).await.unwrap();
+    }
+
+    // The libraries are compiled by the runtime, which must use the same
+    // engine as the store
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, MAIN).unwrap();
+    let builder = WasiEnv::builder("dylink")
+        .runtime(Arc::new(runtime))
+        .fs(Box::new(fs));
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+/// Shipped in the volume of a package that the program depends on
+const LIBDEP: &str = r#"
+(module
+    (@custom "dylink.0" "")
+    (func (export "dep") (result i32)
+        (i32.const 7))
+)
+"#;
+
+/// A library of the same name that is on the file system
+const LIBDEP_ON_FS: &str = r#"
+(module
+    (@custom "dylink.0" "")
+    (func (export "dep") (result i32)
+        (i32.const 1))
+)
+"#;
+
+/// Opens `libdep.so` twice and calls into it
+const MAIN_WITH_DEPENDENCY: &str = r#"
+(module
+    (type $nullary (func (result i32)))
+    (import "wasix_32v1" "dlopen" (func $dlopen (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "dlsym" (func $dlsym (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (table (export "__indirect_function_table") 1 funcref)
+
+    (data (i32.const 16) "libdep.so")
+    (data (i32.const 32) "dep")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    (func (export "_start")
+        (call $check
+            (i32.eqz (call $dlopen (i32.const 16) (i32.const 9) (i32.const 0) (i32.const 200)))
+            (i32.const 1))
+        (call $check
+            (i32.eqz (call $dlsym (i32.load (i32.const 200)) (i32.const 32) (i32.const 3) (i32.const 204)))
+            (i32.const 2))
+        (call $check
+            (i32.eq (call_indirect (type $nullary) (i32.load (i32.const 204))) (i32.const 7))
+            (i32.const 3))
+        (call $check
+            (i32.eqz (call $dlopen (i32.const 16) (i32.const 9) (i32.const 0) (i32.const 208)))
+            (i32.const 4))
+        (call $check
+            (i32.eq (i32.load (i32.const 208)) (i32.load (i32.const 200)))
+            (i32.const 5))
+    )
+)
+"#;
+
+/// Hands out a single package and counts how often it is asked for it
+#[derive(Debug)]
+struct CountingLoader {
+    container: Container,
+    loads: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl PackageLoader for CountingLoader {
+    async fn load(&self, _summary: &PackageSummary) -> Result<Container, anyhow::Error> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        Ok(self.container.clone())
+    }
+
+    async fn load_package_tree(
+        &self,
+        _root: &Container,
+        _resolution: &Resolution,
+        _root_is_local_dir: bool,
+    ) -> Result<BinaryPackage, anyhow::Error> {
+        unimplemented!()
+    }
+}
+
+#[tokio::test]
+async fn test_dlopen_finds_libraries_in_dependencies() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let manifest = r#"
+        [package]
+        name = "test/libs"
+        version = "0.1.0"
+        description = "Libraries"
+
+        [fs]
+        "/lib" = "lib"
+    "#;
+    std::fs::write(temp.path().join("wasmer.toml"), manifest).unwrap();
+    std::fs::create_dir(temp.path().join("lib")).unwrap();
+    std::fs::write(temp.path().join("lib").join("libdep.so"), LIBDEP).unwrap();
+    let libs =
+        wasmer_package::package::Package::from_manifest(temp.path().join("wasmer.toml")).unwrap();
+    let libs = Container::from(libs);
+
+    let id = PackageId::new_named("test/libs", "0.1.0".parse().unwrap());
+    let summary = PackageSummary {
+        pkg: PackageInfo::from_manifest(id, libs.manifest(), libs.version()).unwrap(),
+        dist: DistributionInfo {
+            webc: "https://example.com/libs.webc".parse().unwrap(),
+            webc_sha256: WebcHash::from_bytes([0; 32]),
+        },
+    };
+    let app = PackageId::new_named("test/app", "0.1.0".parse().unwrap());
+    let pkg = BinaryPackage {
+        id: app.clone(),
+        package_ids: vec![app],
+        when_cached: None,
+        entrypoint_cmd: None,
+        hash: OnceCell::new(),
+        webc_fs: Arc::new(virtual_fs::mem_fs::FileSystem::default()),
+        commands: Vec::new(),
+        uses: Vec::new(),
+        dependencies: vec![summary],
+        file_system_memory_footprint: 0,
+        additional_host_mapped_directories: Vec::new(),
+    };
+
+    // The library in the package is found before the one on the file system
+    let fs = virtual_fs::mem_fs::FileSystem::default();
+    fs.create_dir(Path::new("/lib")).unwrap();
+    let mut file = fs
+        .new_open_options()
+        .write(true)
+        .create(true)
+        .open("/lib/libdep.so")
+        .unwrap();
+    file.write_all(LIBDEP_ON_FS.as_bytes()).await.unwrap();
+
+    let loader = Arc::new(CountingLoader {
+        container: libs,
+        loads: AtomicUsize::new(0),
+    });
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+    runtime.set_package_loader(loader.clone());
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, MAIN_WITH_DEPENDENCY).unwrap();
+    let mut builder = WasiEnv::builder("dylink")
+        .runtime(Arc::new(runtime))
+        .fs(Box::new(fs));
+    builder.add_webc(pkg);
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(loader.loads.load(Ordering::SeqCst), 1);
+}
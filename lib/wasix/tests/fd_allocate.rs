@@ -0,0 +1,96 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use wasmer::{Engine, Module, Store};
+use wasmer_wasix::{runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnv};
+
+/// Allocates ranges that end before and after the end of a file and exits
+/// with a different code for every check that fails
+const MAIN: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_pread" (func $fd_pread (param i32 i32 i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_allocate" (func $fd_allocate (param i32 i64 i64) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_filestat_get" (func $fd_filestat_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+
+    (data (i32.const 16) "alloc.txt")
+    (data (i32.const 32) "0123456789")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    (func $size (param $fd i32) (result i64)
+        (call $check
+            (i32.eqz (call $fd_filestat_get (local.get $fd) (i32.const 200)))
+            (i32.const 100))
+        (i64.load (i32.const 232)))
+
+    (func (export "_start")
+        (local $fd i32)
+        ;; CREAT in the pre-opened root directory with all rights
+        (call $check
+            (i32.eqz (call $path_open (i32.const 4) (i32.const 0) (i32.const 16) (i32.const 9)
+                (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 100)))
+            (i32.const 1))
+        (local.set $fd (i32.load (i32.const 100)))
+
+        (i32.store (i32.const 104) (i32.const 32))
+        (i32.store (i32.const 108) (i32.const 10))
+        (call $check
+            (i32.eqz (call $fd_write (local.get $fd) (i32.const 104) (i32.const 1) (i32.const 112)))
+            (i32.const 2))
+
+        ;; A range that ends before the end of the file leaves it alone
+        (call $check
+            (i32.eqz (call $fd_allocate (local.get $fd) (i64.const 0) (i64.const 4)))
+            (i32.const 3))
+        (call $check
+            (i64.eq (call $size (local.get $fd)) (i64.const 10))
+            (i32.const 4))
+        (i32.store (i32.const 104) (i32.const 120))
+        (i32.store (i32.const 108) (i32.const 10))
+        (call $check
+            (i32.eqz (call $fd_pread (local.get $fd) (i32.const 104) (i32.const 1) (i64.const 0) (i32.const 112)))
+            (i32.const 5))
+        (call $check
+            (i32.eq (i32.load (i32.const 112)) (i32.const 10))
+            (i32.const 6))
+        (call $check
+            (i64.eq (i64.load (i32.const 120)) (i64.load (i32.const 32)))
+            (i32.const 7))
+
+        ;; A range that ends after the end of the file grows it
+        (call $check
+            (i32.eqz (call $fd_allocate (local.get $fd) (i64.const 8) (i64.const 8)))
+            (i32.const 8))
+        (call $check
+            (i64.eq (call $size (local.get $fd)) (i64.const 16))
+            (i32.const 9))
+    )
+)
+"#;
+
+#[tokio::test]
+async fn test_fd_allocate_never_shrinks() {
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, MAIN).unwrap();
+    let builder = WasiEnv::builder("fd_allocate")
+        .preopen_dir("/")
+        .unwrap()
+        .runtime(Arc::new(runtime));
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,135 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use wasmer::{Engine, Module, Store};
+use wasmer_wasix::{runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnv};
+
+/// Writes through a mapping of a whole file and one of a part of it, and
+/// exits with a different code for every check that fails
+const MAIN: &str = r#"
+(module
+    (import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_pread" (func $fd_pread (param i32 i32 i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_filestat_get" (func $fd_filestat_get (param i32 i32) (result i32)))
+    (import "wasix_32v1" "fd_mmap" (func $fd_mmap (param i32 i32 i32 i32 i32 i64) (result i32)))
+    (import "wasix_32v1" "msync" (func $msync (param i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "munmap" (func $munmap (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 2)
+
+    (data (i32.const 16) "mapped.txt")
+    (data (i32.const 32) "0123456789")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    ;; Reads the whole file into 120..130 without moving the cursor
+    (func $pread_all (param $fd i32)
+        (i32.store (i32.const 104) (i32.const 120))
+        (i32.store (i32.const 108) (i32.const 10))
+        (call $check
+            (i32.eqz (call $fd_pread (local.get $fd) (i32.const 104) (i32.const 1) (i64.const 0) (i32.const 112)))
+            (i32.const 100))
+        (call $check
+            (i32.eq (i32.load (i32.const 112)) (i32.const 10))
+            (i32.const 101)))
+
+    (func (export "_start")
+        (local $fd i32)
+        ;; CREAT in the pre-opened root directory with all rights
+        (call $check
+            (i32.eqz (call $path_open (i32.const 4) (i32.const 0) (i32.const 16) (i32.const 10)
+                (i32.const 1) (i64.const -1) (i64.const -1) (i32.const 0) (i32.const 100)))
+            (i32.const 1))
+        (local.set $fd (i32.load (i32.const 100)))
+        (i32.store (i32.const 104) (i32.const 32))
+        (i32.store (i32.const 108) (i32.const 10))
+        (call $check
+            (i32.eqz (call $fd_write (local.get $fd) (i32.const 104) (i32.const 1) (i32.const 112)))
+            (i32.const 2))
+        (call $check
+            (i32.eqz (call $fd_seek (local.get $fd) (i64.const 3) (i32.const 0) (i32.const 112)))
+            (i32.const 3))
+
+        ;; READ | WRITE and SHARED, over the whole file
+        (call $check
+            (i32.eqz (call $fd_mmap (local.get $fd) (i32.const 0x10000) (i32.const 10)
+                (i32.const 3) (i32.const 1) (i64.const 0)))
+            (i32.const 4))
+        (i32.store8 (i32.const 0x10000) (i32.const 0x41))
+        (i32.store8 (i32.const 0x10009) (i32.const 0x42))
+        ;; SYNC
+        (call $check
+            (i32.eqz (call $msync (i32.const 0x10000) (i32.const 10) (i32.const 2)))
+            (i32.const 5))
+        (call $pread_all (local.get $fd))
+        (call $check
+            (i32.eq (i32.load8_u (i32.const 120)) (i32.const 0x41))
+            (i32.const 6))
+        (call $check
+            (i32.eq (i32.load8_u (i32.const 129)) (i32.const 0x42))
+            (i32.const 7))
+        (call $check
+            (i32.eqz (call $fd_filestat_get (local.get $fd) (i32.const 200)))
+            (i32.const 8))
+        (call $check
+            (i64.eq (i64.load (i32.const 232)) (i64.const 10))
+            (i32.const 9))
+        (call $check
+            (i32.eqz (call $munmap (i32.const 0x10000) (i32.const 10)))
+            (i32.const 10))
+
+        ;; A mapping of the middle of the file only changes that part
+        (call $check
+            (i32.eqz (call $fd_mmap (local.get $fd) (i32.const 0x10000) (i32.const 2)
+                (i32.const 3) (i32.const 1) (i64.const 4)))
+            (i32.const 11))
+        (i32.store16 (i32.const 0x10000) (i32.const 0x4443))
+        (call $check
+            (i32.eqz (call $munmap (i32.const 0x10000) (i32.const 2)))
+            (i32.const 12))
+        (call $pread_all (local.get $fd))
+        (call $check
+            (i64.eq (i64.load (i32.const 120)) (i64.const 0x3736444333323141))
+            (i32.const 13))
+
+        ;; Neither moved the cursor
+        (i32.store (i32.const 104) (i32.const 140))
+        (i32.store (i32.const 108) (i32.const 10))
+        (call $check
+            (i32.eqz (call $fd_read (local.get $fd) (i32.const 104) (i32.const 1) (i32.const 112)))
+            (i32.const 14))
+        (call $check
+            (i32.eq (i32.load (i32.const 112)) (i32.const 7))
+            (i32.const 15))
+        (call $check
+            (i32.eq (i32.load (i32.const 140)) (i32.const 0x36444333))
+            (i32.const 16))
+    )
+)
+"#;
+
+#[tokio::test]
+async fn test_mmap_writes_back_shared_mappings() {
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, MAIN).unwrap();
+    let builder = WasiEnv::builder("mmap")
+        .preopen_dir("/")
+        .unwrap()
+        .runtime(Arc::new(runtime));
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,167 @@
+use std::{path::Path, sync::Arc};
+
+use tokio::runtime::Handle;
+use virtual_fs::{AsyncWriteExt, FileSystem};
+use wasmer::{Engine, Module, Store};
+use wasmer_wasix::{runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnv};
+
+/// A child that writes `message` to its stdout and exits
+fn child(message: &str) -> Vec<u8> {
+    let wat = format!(
+        r#"
+(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 16) "{message}")
+    (func (export "_start")
+        (i32.store (i32.const 0) (i32.const 16))
+        (i32.store (i32.const 4) (i32.const {len}))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))))
+)
+"#,
+        len = message.len()
+    );
+    wasmer::wat2wasm(wat.as_bytes()).unwrap().into_owned()
+}
+
+/// Spawns a child that writes 5 bytes and another that writes 3 bytes, and
+/// exits with a different code for every check that fails
+///
+/// The usage of a child is added to `RUSAGE_CHILDREN` when it is joined,
+/// whether by its pid or as any child, and joining it again adds nothing.
+const MAIN: &str = r#"
+(module
+    (import "wasix_32v1" "proc_spawn2" (func $proc_spawn2
+        (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "proc_join2" (func $proc_join2 (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "getrusage" (func $getrusage (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+
+    (data (i32.const 16) "/bin/five")
+    (data (i32.const 32) "/bin/three")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    ;; Spawns the program at `name`, returning its pid
+    (func $spawn (param $name i32) (param $len i32) (param $code i32) (result i32)
+        (call $check
+            (i32.eqz (call $proc_spawn2
+                (local.get $name) (local.get $len)
+                (i32.const 0) (i32.const 0)
+                (i32.const 0) (i32.const 0)
+                (i32.const 0) (i32.const 0)
+                (i32.const 0) (i32.const 0)
+                (i32.const 0)
+                (i32.const 0) (i32.const 0)
+                (i32.const 100)))
+            (local.get $code))
+        (i32.load (i32.const 100)))
+
+    ;; Joins the child `pid` (or any child if it is -1) into the status at
+    ;; 112 and the usage at 128, returning the errno
+    (func $join (param $pid i32) (result i32)
+        (i32.store8 (i32.const 104) (i32.ne (local.get $pid) (i32.const -1)))
+        (i32.store (i32.const 108) (local.get $pid))
+        (call $proc_join2 (i32.const 104) (i32.const 0) (i32.const 112) (i32.const 128)))
+
+    ;; Returns the bytes written by the joined children
+    (func $children_written (param $code i32) (result i64)
+        (call $check
+            (i32.eqz (call $getrusage (i32.const 1) (i32.const 192)))
+            (local.get $code))
+        (i64.load (i32.const 224)))
+
+    (func (export "_start")
+        (local $five i32)
+        (local $three i32)
+
+        (call $check
+            (i64.eqz (call $children_written (i32.const 1)))
+            (i32.const 2))
+
+        ;; Joining a child by its pid returns its usage and adds it
+        (local.set $five (call $spawn (i32.const 16) (i32.const 9) (i32.const 3)))
+        (call $check (i32.eqz (call $join (local.get $five))) (i32.const 4))
+        (call $check
+            (i32.eq (i32.load8_u (i32.const 112)) (i32.const 1))
+            (i32.const 5))
+        (call $check
+            (i32.eqz (i32.load16_u (i32.const 114)))
+            (i32.const 6))
+        (call $check
+            (i64.eq (i64.load (i32.const 160)) (i64.const 5))
+            (i32.const 7))
+        (call $check
+            (i64.eq (call $children_written (i32.const 8)) (i64.const 5))
+            (i32.const 9))
+
+        ;; The usage of the children is not the usage of the process itself
+        (call $check
+            (i32.eqz (call $getrusage (i32.const 0) (i32.const 192)))
+            (i32.const 10))
+        (call $check
+            (i64.eqz (i64.load (i32.const 224)))
+            (i32.const 11))
+
+        ;; Joining the same child again adds nothing
+        (call $check (i32.eqz (call $join (local.get $five))) (i32.const 12))
+        (call $check
+            (i64.eq (call $children_written (i32.const 13)) (i64.const 5))
+            (i32.const 14))
+
+        ;; Joining any child adds the usage of the one that was joined
+        (local.set $three (call $spawn (i32.const 32) (i32.const 10) (i32.const 15)))
+        (call $check (i32.eqz (call $join (i32.const -1))) (i32.const 16))
+        (call $check
+            (i32.eq (i32.load (i32.const 108)) (local.get $three))
+            (i32.const 17))
+        (call $check
+            (i64.eq (i64.load (i32.const 160)) (i64.const 3))
+            (i32.const 18))
+        (call $check
+            (i64.eq (call $children_written (i32.const 19)) (i64.const 8))
+            (i32.const 20))
+
+        ;; Once there are no children left nothing more is added
+        (call $check
+            (i32.eq (call $join (i32.const -1)) (i32.const 12))
+            (i32.const 21))
+        (call $check
+            (i64.eq (call $children_written (i32.const 22)) (i64.const 8))
+            (i32.const 23))
+    )
+)
+"#;
+
+#[tokio::test]
+async fn test_joined_children_are_counted_once() {
+    let fs = virtual_fs::mem_fs::FileSystem::default();
+    fs.create_dir(Path::new("/bin")).unwrap();
+    for (path, message) in [("/bin/five", "hello"), ("/bin/three", "hey")] {
+        let mut file = fs
+            .new_open_options()
+            .write(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+        file.write_all(&child(message)).await.unwrap();
+    }
+
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, MAIN).unwrap();
+    let builder = WasiEnv::builder("rusage")
+        .runtime(Arc::new(runtime))
+        .fs(Box::new(fs));
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+}
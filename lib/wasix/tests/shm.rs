@@ -0,0 +1,160 @@
+#![cfg(unix)]
+
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use wasmer::{Engine, Module, Store};
+use wasmer_wasix::{runtime::task_manager::tokio::TokioTaskManager, PluggableRuntime, WasiEnv};
+
+/// Maps one shared memory object at two addresses and exits with a
+/// different code for every check that fails
+const MAIN: &str = r#"
+(module
+    (import "wasix_32v1" "shm_open" (func $shm_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "fd_mmap" (func $fd_mmap (param i32 i32 i32 i32 i32 i64) (result i32)))
+    (import "wasix_32v1" "munmap" (func $munmap (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_filestat_set_size" (func $set_size (param i32 i64) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_pread" (func $fd_pread (param i32 i32 i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 4 16 shared)
+
+    (data (i32.const 16) "/region")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    (func (export "_start")
+        (local $fd i32)
+        ;; CREATE | EXCL
+        (call $check
+            (i32.eqz (call $shm_open (i32.const 16) (i32.const 7) (i32.const 3) (i32.const 100)))
+            (i32.const 1))
+        (local.set $fd (i32.load (i32.const 100)))
+        (call $check
+            (i32.eqz (call $set_size (local.get $fd) (i64.const 0x10000)))
+            (i32.const 2))
+
+        ;; READ | WRITE and SHARED, at two addresses
+        (call $check
+            (i32.eqz (call $fd_mmap (local.get $fd) (i32.const 0x10000) (i32.const 0x10000)
+                (i32.const 3) (i32.const 1) (i64.const 0)))
+            (i32.const 3))
+        (call $check
+            (i32.eqz (call $fd_mmap (local.get $fd) (i32.const 0x20000) (i32.const 0x10000)
+                (i32.const 3) (i32.const 1) (i64.const 0)))
+            (i32.const 4))
+
+        ;; A store through one mapping is seen through the other and by reads
+        (i32.store (i32.const 0x10010) (i32.const 42))
+        (call $check
+            (i32.eq (i32.load (i32.const 0x20010)) (i32.const 42))
+            (i32.const 5))
+        (i32.store (i32.const 200) (i32.const 300))
+        (i32.store (i32.const 204) (i32.const 4))
+        (call $check
+            (i32.eqz (call $fd_pread (local.get $fd) (i32.const 200) (i32.const 1) (i64.const 0x10) (i32.const 208)))
+            (i32.const 6))
+        (call $check
+            (i32.eq (i32.load (i32.const 300)) (i32.const 42))
+            (i32.const 7))
+
+        ;; Unmapping gives the pages back to the memory
+        (call $check
+            (i32.eqz (call $munmap (i32.const 0x10000) (i32.const 0x10000)))
+            (i32.const 8))
+        (call $check
+            (i32.eqz (i32.load (i32.const 0x10010)))
+            (i32.const 9))
+        (call $check
+            (i32.eq (i32.load (i32.const 0x20010)) (i32.const 42))
+            (i32.const 10))
+
+        ;; Mappings must be aligned to pages
+        (call $check
+            (i32.ne (call $fd_mmap (local.get $fd) (i32.const 0x10001) (i32.const 0x100)
+                (i32.const 3) (i32.const 1) (i64.const 0))
+                (i32.const 0))
+            (i32.const 11))
+
+        ;; Growing the memory leaves the pages where they were mapped
+        (call $check
+            (i32.ne (memory.grow (i32.const 4)) (i32.const -1))
+            (i32.const 12))
+        (i32.store (i32.const 0x20020) (i32.const 7))
+        (call $check
+            (i32.eqz (call $fd_pread (local.get $fd) (i32.const 200) (i32.const 1) (i64.const 0x20) (i32.const 208)))
+            (i32.const 13))
+        (call $check
+            (i32.eq (i32.load (i32.const 300)) (i32.const 7))
+            (i32.const 14))
+        (call $check
+            (i32.eqz (call $munmap (i32.const 0x20000) (i32.const 0x10000)))
+            (i32.const 15))
+        (call $check
+            (i32.eqz (i32.load (i32.const 0x20020)))
+            (i32.const 16))
+    )
+)
+"#;
+
+/// Tries to map a shared memory object into a memory that moves when it
+/// grows
+const NOT_SHARED: &str = r#"
+(module
+    (import "wasix_32v1" "shm_open" (func $shm_open (param i32 i32 i32 i32) (result i32)))
+    (import "wasix_32v1" "fd_mmap" (func $fd_mmap (param i32 i32 i32 i32 i32 i64) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_filestat_set_size" (func $set_size (param i32 i64) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 4)
+
+    (data (i32.const 16) "/region")
+
+    (func $check (param $ok i32) (param $code i32)
+        (if (i32.eqz (local.get $ok))
+            (then (call $proc_exit (local.get $code)))))
+
+    (func (export "_start")
+        (local $fd i32)
+        (call $check
+            (i32.eqz (call $shm_open (i32.const 16) (i32.const 7) (i32.const 3) (i32.const 100)))
+            (i32.const 1))
+        (local.set $fd (i32.load (i32.const 100)))
+        (call $check
+            (i32.eqz (call $set_size (local.get $fd) (i64.const 0x10000)))
+            (i32.const 2))
+
+        ;; ENOTSUP
+        (call $check
+            (i32.eq (call $fd_mmap (local.get $fd) (i32.const 0x10000) (i32.const 0x10000)
+                (i32.const 3) (i32.const 1) (i64.const 0))
+                (i32.const 58))
+            (i32.const 3))
+    )
+)
+"#;
+
+#[tokio::test]
+async fn test_shm_mappings_share_pages() {
+    run("shm", MAIN).await;
+}
+
+#[tokio::test]
+async fn test_shm_is_not_mapped_into_memories_that_move() {
+    run("shm-not-shared", NOT_SHARED).await;
+}
+
+async fn run(name: &str, wat: &str) {
+    let engine = Engine::default();
+    let mut runtime = PluggableRuntime::new(Arc::new(TokioTaskManager::new(Handle::current())));
+    runtime.set_engine(Some(engine.clone()));
+
+    let mut store = Store::new(engine.clone());
+    let module = Module::new(&engine, wat).unwrap();
+    let builder = WasiEnv::builder(name).runtime(Arc::new(runtime));
+
+    tokio::task::spawn_blocking(move || builder.run_with_store(module, &mut store))
+        .await
+        .unwrap()
+        .unwrap();
+}
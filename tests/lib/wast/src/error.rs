@@ -12,6 +12,31 @@ pub struct DirectiveError {
     pub message: String,
 }
 
+/// The outcome of a directive of a wast script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveOutcome {
+    /// The directive succeeded
+    Passed,
+    /// The directive failed with this message
+    Failed(String),
+    /// The directive was not checked, because it depends on a module that
+    /// failed to instantiate
+    Skipped(String),
+}
+
+/// The result of running a directive
+#[derive(Debug, Clone)]
+pub struct DirectiveReport {
+    /// The kind of directive, like `assert_return`
+    pub kind: &'static str,
+    /// The line where the directive is defined
+    pub line: usize,
+    /// The column where the directive is defined
+    pub col: usize,
+    /// What happened when running the directive
+    pub outcome: DirectiveOutcome,
+}
+
 /// A structure holding the list of all executed directives
 #[derive(Error, Debug)]
 pub struct DirectiveErrors {
@@ -18,7 +18,7 @@ mod spectest;
 mod wasi_wast;
 mod wast;
 
-pub use crate::error::{DirectiveError, DirectiveErrors};
+pub use crate::error::{DirectiveError, DirectiveErrors, DirectiveOutcome, DirectiveReport};
 pub use crate::spectest::spectest_importobject;
 pub use crate::wasi_wast::{WasiFileSystemKind, WasiTest};
 pub use crate::wast::Wast;
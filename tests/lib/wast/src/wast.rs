@@ -1,4 +1,4 @@
-use crate::error::{DirectiveError, DirectiveErrors};
+use crate::error::{DirectiveError, DirectiveErrors, DirectiveOutcome, DirectiveReport};
 use crate::spectest::spectest_importobject;
 use anyhow::{anyhow, bail, Result};
 use std::collections::{HashMap, HashSet};
@@ -327,6 +327,34 @@ impl Wast {
 
     /// Run a wast script from a byte buffer.
     pub fn run_buffer(&mut self, test: &Path, wast: &[u8]) -> Result<()> {
+        let errors: Vec<_> = self
+            .report_buffer(test, wast)?
+            .into_iter()
+            .filter_map(|report| match report.outcome {
+                DirectiveOutcome::Failed(message) => Some(DirectiveError {
+                    line: report.line,
+                    col: report.col,
+                    message,
+                }),
+                _ => None,
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(DirectiveErrors {
+                filename: test.to_str().unwrap().to_string(),
+                errors,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Run a wast script from a byte buffer, and report the outcome of
+    /// each directive.
+    ///
+    /// Failing directives are part of the report, an error is only returned
+    /// if the script can't be parsed.
+    pub fn report_buffer(&mut self, test: &Path, wast: &[u8]) -> Result<Vec<DirectiveReport>> {
         let wast = str::from_utf8(wast)?;
         let filename = test.to_str().unwrap();
         let adjust_wast = |mut err: wast::Error| {
@@ -340,39 +368,38 @@ impl Wast {
         let buf = wast::parser::ParseBuffer::new_with_lexer(lexer).map_err(adjust_wast)?;
         let ast = parser::parse::<WWast>(&buf).map_err(adjust_wast)?;
 
-        let mut errors = Vec::with_capacity(ast.directives.len());
+        let mut reports = Vec::with_capacity(ast.directives.len());
         for directive in ast.directives {
-            let sp = directive.span();
-            if let Err(e) = self.run_directive(test, directive) {
-                let message = format!("{e}");
-                // If depends on an instance that doesn't exist
-                if message.contains("no previous instance found") {
-                    continue;
-                }
-                // We don't compute it, comes from instantiating an instance
-                // that we expected to fail.
-                if self.current.is_none() && self.current_is_allowed_failure {
-                    continue;
-                }
-                let (line, col) = sp.linecol_in(wast);
-                errors.push(DirectiveError {
-                    line: line + 1,
-                    col,
-                    message,
-                });
-                if self.fail_fast {
-                    break;
+            let (line, col) = directive.span().linecol_in(wast);
+            let kind = directive_kind(&directive);
+            let outcome = match self.run_directive(test, directive) {
+                Ok(()) => DirectiveOutcome::Passed,
+                Err(e) => {
+                    let message = format!("{e}");
+                    // If depends on an instance that doesn't exist, or comes
+                    // from instantiating an instance that we expected to fail,
+                    // we don't compute it.
+                    if message.contains("no previous instance found")
+                        || (self.current.is_none() && self.current_is_allowed_failure)
+                    {
+                        DirectiveOutcome::Skipped(message)
+                    } else {
+                        DirectiveOutcome::Failed(message)
+                    }
                 }
+            };
+            let failed = matches!(outcome, DirectiveOutcome::Failed(_));
+            reports.push(DirectiveReport {
+                kind,
+                line: line + 1,
+                col,
+                outcome,
+            });
+            if failed && self.fail_fast {
+                break;
             }
         }
-        if !errors.is_empty() {
-            return Err(DirectiveErrors {
-                filename: filename.to_string(),
-                errors,
-            }
-            .into());
-        }
-        Ok(())
+        Ok(reports)
     }
 
     //fn parse_quote_module(&self, test: &Path, source: &[&[u8]]) -> Result<Vec<u8>> {
@@ -403,6 +430,13 @@ impl Wast {
         let bytes = std::fs::read(path)?;
         self.run_buffer(path, &bytes)
     }
+
+    /// Run a wast script from a file, and report the outcome of each
+    /// directive.
+    pub fn report_file(&mut self, path: &Path) -> Result<Vec<DirectiveReport>> {
+        let bytes = std::fs::read(path)?;
+        self.report_buffer(path, &bytes)
+    }
 }
 
 // This is the implementation specific to the Runtime
@@ -598,6 +632,29 @@ impl Wast {
     }
 }
 
+/// The keyword of a directive, as written in wast scripts
+fn directive_kind(directive: &wast::WastDirective<'_>) -> &'static str {
+    use wast::WastDirective::*;
+
+    match directive {
+        ModuleDefinition(_) => "module definition",
+        Module(_) => "module",
+        ModuleInstance { .. } => "module instance",
+        Register { .. } => "register",
+        Invoke(_) => "invoke",
+        AssertReturn { .. } => "assert_return",
+        AssertTrap { .. } => "assert_trap",
+        AssertExhaustion { .. } => "assert_exhaustion",
+        AssertInvalid { .. } => "assert_invalid",
+        AssertException { .. } => "assert_exception",
+        AssertMalformed { .. } => "assert_malformed",
+        AssertUnlinkable { .. } => "assert_unlinkable",
+        AssertSuspension { .. } => "assert_suspension",
+        Thread(_) => "thread",
+        Wait { .. } => "wait",
+    }
+}
+
 fn extract_lane_as_i8(bytes: u128, lane: usize) -> i8 {
     (bytes >> (lane * 8)) as i8
 }